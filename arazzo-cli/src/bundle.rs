@@ -0,0 +1,81 @@
+//! The `bundle` command
+
+use std::path::Path;
+
+use crate::convert::{convert, write_output};
+use crate::load::{load_description, load_sources, DocumentFormat};
+
+/// Bundles the description in the file into a single document, with the components inlined and
+/// (unless `keep_sources` is set) the OpenAPI source descriptions embedded as `data:` URLs. The
+/// document is written in the given format, or the format of the file if none is given.
+pub fn bundle_command(
+  file: &Path,
+  to: Option<DocumentFormat>,
+  keep_sources: bool,
+  output: Option<&Path>
+) -> anyhow::Result<bool> {
+  let description = load_description(file)?;
+  let mut bundled = description.inline_components()?;
+  if !keep_sources {
+    let resolver = load_sources(&description, file)?;
+    bundled.embed_sources(&resolver);
+  }
+
+  let format = to.unwrap_or_else(|| DocumentFormat::from_path(file));
+  write_output(&convert(&bundled, None, format)?, output)?;
+  Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+
+  use arazzo_models::operations::OpenApiSource;
+  use pretty_assertions::assert_eq;
+
+  use crate::load::{parse_description, DocumentFormat};
+  use super::bundle_command;
+
+  #[test]
+  fn bundles_components_and_sources() {
+    let dir = std::env::temp_dir().join(format!("arazzo-cli-bundle-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("petstore.json"), r#"{ "paths": { "/pets": { "get": { "operationId": "listPets" } } } }"#).unwrap();
+    fs::write(dir.join("arazzo.yaml"), r#"arazzo: 1.0.1
+info:
+  title: Pets
+  version: 1.0.0
+sourceDescriptions:
+  - name: petstore
+    url: petstore.json
+workflows:
+  - workflowId: listPets
+    steps:
+      - stepId: list
+        operationId: listPets
+        parameters:
+          - reference: $components.parameters.limit
+            value: "10"
+components:
+  parameters:
+    limit:
+      name: limit
+      in: query
+      value: 20
+"#).unwrap();
+
+    let output = dir.join("bundled.yaml");
+    let result = bundle_command(&dir.join("arazzo.yaml"), None, false, Some(&output));
+    let contents = fs::read_to_string(&output);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(result.unwrap());
+    let bundled = parse_description(&contents.unwrap(), DocumentFormat::Yaml).unwrap();
+    assert!(bundled.components.is_empty());
+    let step = &bundled.workflows[0].steps[0];
+    assert_eq!(step.parameters[0].first().map(|parameter| parameter.name.as_str()), Some("limit"));
+
+    let source = OpenApiSource::load(&bundled.source_descriptions[0], None).unwrap();
+    assert_eq!(source.find_operation_by_id("listPets").map(|operation| operation.path), Some("/pets".to_string()));
+  }
+}
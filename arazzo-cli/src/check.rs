@@ -0,0 +1,128 @@
+//! The `validate` and `lint` commands
+
+use std::path::Path;
+
+use arazzo_models::lint::lint;
+use arazzo_models::validation::{validate, ValidationIssue};
+use serde_json::json;
+
+use crate::load::load_description;
+use crate::OutputFormat;
+
+/// Validates the description in the file against the specification. Returns true if it is valid.
+pub fn validate_command(file: &Path, output: OutputFormat) -> anyhow::Result<bool> {
+  let description = load_description(file)?;
+  let issues = validate(&description);
+  println!("{}", format_validation(&file.display().to_string(), &issues, output));
+  Ok(issues.is_empty())
+}
+
+/// Lints the description in the file. Returns true if there were no lint issues.
+pub fn lint_command(file: &Path, output: OutputFormat) -> anyhow::Result<bool> {
+  let description = load_description(file)?;
+  let issues = lint(&description);
+  println!("{}", format_lint(&file.display().to_string(), &issues, output));
+  Ok(issues.is_empty())
+}
+
+fn issues_json(issues: &[ValidationIssue]) -> serde_json::Value {
+  issues.iter()
+    .map(|issue| json!({ "path": issue.path, "message": issue.message }))
+    .collect()
+}
+
+fn format_issues(file: &str, issues: &[ValidationIssue]) -> String {
+  let mut lines = issues.iter()
+    .map(|issue| format!("{}: {}", file, issue))
+    .collect::<Vec<_>>();
+  lines.push(if issues.len() == 1 {
+    "1 problem found".to_string()
+  } else {
+    format!("{} problems found", issues.len())
+  });
+  lines.join("\n")
+}
+
+/// Formats the result of validating a file
+pub fn format_validation(file: &str, issues: &[ValidationIssue], output: OutputFormat) -> String {
+  match output {
+    OutputFormat::Text => if issues.is_empty() {
+      format!("{}: valid", file)
+    } else {
+      format_issues(file, issues)
+    },
+    OutputFormat::Json => json!({
+      "file": file,
+      "valid": issues.is_empty(),
+      "issues": issues_json(issues)
+    }).to_string()
+  }
+}
+
+/// Formats the result of linting a file
+pub fn format_lint(file: &str, issues: &[ValidationIssue], output: OutputFormat) -> String {
+  match output {
+    OutputFormat::Text => if issues.is_empty() {
+      format!("{}: no problems found", file)
+    } else {
+      format_issues(file, issues)
+    },
+    OutputFormat::Json => json!({
+      "file": file,
+      "issues": issues_json(issues)
+    }).to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use arazzo_models::validation::ValidationIssue;
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use crate::OutputFormat;
+  use super::*;
+
+  #[test]
+  fn format_validation_as_text() {
+    assert_eq!(format_validation("arazzo.yaml", &[], OutputFormat::Text), "arazzo.yaml: valid");
+
+    let issues = vec![
+      ValidationIssue::new("/info/version", "Version is required [4.6.2.1 Fixed Fields]"),
+      ValidationIssue::new("/workflows", "Workflows list must have at least one entry [4.6.1.1 Fixed Fields]")
+    ];
+    assert_eq!(format_validation("arazzo.yaml", &issues, OutputFormat::Text),
+      "arazzo.yaml: /info/version: Version is required [4.6.2.1 Fixed Fields]\n\
+       arazzo.yaml: /workflows: Workflows list must have at least one entry [4.6.1.1 Fixed Fields]\n\
+       2 problems found");
+  }
+
+  #[test]
+  fn format_validation_as_json() {
+    let issues = vec![ValidationIssue::new("/info/version", "Version is required")];
+    let output = format_validation("arazzo.yaml", &issues, OutputFormat::Json);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&output).unwrap(), json!({
+      "file": "arazzo.yaml",
+      "valid": false,
+      "issues": [
+        { "path": "/info/version", "message": "Version is required" }
+      ]
+    }));
+  }
+
+  #[test]
+  fn format_lint_results() {
+    assert_eq!(format_lint("arazzo.yaml", &[], OutputFormat::Text), "arazzo.yaml: no problems found");
+
+    let issues = vec![ValidationIssue::new("/workflows/0/steps/0", "Step 'one' has no success criteria")];
+    assert_eq!(format_lint("arazzo.yaml", &issues, OutputFormat::Text),
+      "arazzo.yaml: /workflows/0/steps/0: Step 'one' has no success criteria\n1 problem found");
+    let output = format_lint("arazzo.yaml", &issues, OutputFormat::Json);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&output).unwrap(), json!({
+      "file": "arazzo.yaml",
+      "issues": [
+        { "path": "/workflows/0/steps/0", "message": "Step 'one' has no success criteria" }
+      ]
+    }));
+  }
+}
@@ -0,0 +1,200 @@
+//! The `convert` command
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Context;
+use arazzo_models::extensions::{AnyValue, ExtensionFilter};
+use arazzo_models::v1_0::ArazzoDescription;
+use serde_json::Value;
+use yaml_rust2::YamlEmitter;
+
+use crate::load::{parse_description, parse_document, DocumentFormat};
+
+/// Converts the description in the file to the given format, writing it to the output file or
+/// to standard output. Extension values not kept by the filter are removed. The keys are written
+/// in the same order as in the file.
+pub fn convert_command(
+  file: &Path,
+  to: DocumentFormat,
+  filter: Option<&ExtensionFilter>,
+  output: Option<&Path>
+) -> anyhow::Result<bool> {
+  let contents = fs::read_to_string(file)
+    .with_context(|| format!("Failed to read '{}'", file.display()))?;
+  let format = DocumentFormat::from_path(file);
+  let mut description = parse_description(&contents, format)
+    .with_context(|| format!("Failed to load '{}'", file.display()))?;
+  if let Some(filter) = filter {
+    description.filter_extensions(filter);
+  }
+  let source = parse_document(&contents, format)
+    .with_context(|| format!("Failed to load '{}'", file.display()))?;
+
+  write_output(&convert(&description, Some(&source), to)?, output)?;
+  Ok(true)
+}
+
+/// Writes the contents to the output file, or to standard output if there is no file
+pub fn write_output(contents: &str, output: Option<&Path>) -> anyhow::Result<()> {
+  match output {
+    Some(path) => fs::write(path, contents)
+      .with_context(|| format!("Failed to write '{}'", path.display())),
+    None => {
+      print!("{}", contents);
+      Ok(())
+    }
+  }
+}
+
+/// Serialises the description to a document in the given format. If there is a source document
+/// (the document the description was loaded from), the keys of each object are written in the
+/// same order as in the source document, with any keys that are not in the source document after
+/// them. Otherwise, fields are written in the order of the serializers, and the keys of extension
+/// values in the order they were loaded.
+pub fn convert(description: &ArazzoDescription, source: Option<&Value>, to: DocumentFormat) -> anyhow::Result<String> {
+  let mut json = serde_json::to_value(description)?;
+  if let Some(source) = source {
+    order_keys_like(&mut json, source);
+  }
+  match to {
+    DocumentFormat::Json => Ok(serde_json::to_string_pretty(&json)? + "\n"),
+    DocumentFormat::Yaml => {
+      let yaml = AnyValue::try_from(&json)?.to_yaml();
+      let mut buffer = String::new();
+      YamlEmitter::new(&mut buffer).dump(&yaml)?;
+      let contents = buffer.strip_prefix("---")
+        .map(|s| s.strip_prefix(|ch: char| ch == ' ' || ch == '\n').unwrap_or(s))
+        .unwrap_or(buffer.as_str());
+      Ok(format!("{}\n", contents))
+    }
+  }
+}
+
+/// Orders the keys of the objects in the document in the order of the keys of the objects at the
+/// same locations in the source document. Keys that are not in the source document keep their
+/// order, after the keys that are.
+fn order_keys_like(json: &mut Value, source: &Value) {
+  match (json, source) {
+    (Value::Object(map), Value::Object(source_map)) => {
+      let positions = source_map.keys()
+        .enumerate()
+        .map(|(index, key)| (key.as_str(), index))
+        .collect::<HashMap<_, _>>();
+      let mut entries = std::mem::take(map).into_iter().collect::<Vec<_>>();
+      entries.sort_by_key(|(key, _)| positions.get(key.as_str()).copied().unwrap_or(usize::MAX));
+      for (key, mut value) in entries {
+        if let Some(source_value) = source_map.get(&key) {
+          order_keys_like(&mut value, source_value);
+        }
+        map.insert(key, value);
+      }
+    }
+    (Value::Array(array), Value::Array(source_array)) => {
+      for (value, source_value) in array.iter_mut().zip(source_array) {
+        order_keys_like(value, source_value);
+      }
+    }
+    _ => {}
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use arazzo_models::extensions::ExtensionFilter;
+  use pretty_assertions::assert_eq;
+
+  use crate::load::{parse_description, parse_document, DocumentFormat};
+  use super::convert;
+
+  const YAML: &str = r##"arazzo: 1.0.1
+info:
+  title: Pets
+  version: 1.0.0
+  x-team:
+    name: pets
+    channel: "#pets"
+  x-internal: true
+sourceDescriptions:
+  - name: petstore
+    url: https://petstore.example.com/openapi.json
+workflows:
+  - workflowId: listPets
+    steps:
+      - stepId: list
+        operationId: listPets
+        successCriteria:
+          - condition: $statusCode == 200
+"##;
+
+  #[test]
+  fn converts_yaml_to_json() {
+    let description = parse_description(YAML, DocumentFormat::Yaml).unwrap();
+    let source = parse_document(YAML, DocumentFormat::Yaml).unwrap();
+    assert_eq!(convert(&description, Some(&source), DocumentFormat::Json).unwrap(), r##"{
+  "arazzo": "1.0.1",
+  "info": {
+    "title": "Pets",
+    "version": "1.0.0",
+    "x-team": {
+      "name": "pets",
+      "channel": "#pets"
+    },
+    "x-internal": true
+  },
+  "sourceDescriptions": [
+    {
+      "name": "petstore",
+      "url": "https://petstore.example.com/openapi.json"
+    }
+  ],
+  "workflows": [
+    {
+      "workflowId": "listPets",
+      "steps": [
+        {
+          "stepId": "list",
+          "operationId": "listPets",
+          "successCriteria": [
+            {
+              "condition": "$statusCode == 200"
+            }
+          ]
+        }
+      ]
+    }
+  ]
+}
+"##);
+  }
+
+  #[test]
+  fn uses_the_order_of_the_serializers_without_a_source_document() {
+    let description = parse_description(YAML, DocumentFormat::Yaml).unwrap();
+    let json = convert(&description, None, DocumentFormat::Json).unwrap();
+    assert!(json.contains("\"x-internal\": true,\n    \"x-team\""));
+  }
+
+  #[test]
+  fn converts_json_to_yaml_and_back() {
+    let description = parse_description(YAML, DocumentFormat::Yaml).unwrap();
+    let source = parse_document(YAML, DocumentFormat::Yaml).unwrap();
+    let json = convert(&description, Some(&source), DocumentFormat::Json).unwrap();
+    let source = parse_document(&json, DocumentFormat::Json).unwrap();
+    let yaml = convert(&parse_description(&json, DocumentFormat::Json).unwrap(), Some(&source), DocumentFormat::Yaml).unwrap();
+    assert_eq!(parse_description(&yaml, DocumentFormat::Yaml).unwrap(), description);
+    // The emitter quotes strings that contain a colon
+    assert_eq!(yaml, YAML.replace("url: https://petstore.example.com/openapi.json",
+      "url: \"https://petstore.example.com/openapi.json\""));
+  }
+
+  #[test]
+  fn filters_extensions() {
+    let mut description = parse_description(YAML, DocumentFormat::Yaml).unwrap();
+    description.filter_extensions(&ExtensionFilter::Deny(vec!["internal".to_string()]));
+    let json = convert(&description, None, DocumentFormat::Json).unwrap();
+    assert!(json.contains("x-team"));
+    assert!(!json.contains("x-internal"));
+  }
+}
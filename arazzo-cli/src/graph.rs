@@ -0,0 +1,97 @@
+//! The `graph` command
+
+use std::path::Path;
+
+use arazzo_models::render::{dot, mermaid_flowchart_with_options, MermaidOptions};
+use arazzo_models::v1_0::ArazzoDescription;
+use clap::ValueEnum;
+
+use crate::load::load_description;
+
+/// Format to render the graph of the workflows in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+  /// Graphviz DOT graph
+  Dot,
+  /// Mermaid flowchart
+  Mermaid
+}
+
+/// Renders the workflows of the description in the file as a graph
+pub fn graph_command(file: &Path, format: GraphFormat, workflow_id: Option<&str>) -> anyhow::Result<bool> {
+  let description = load_description(file)?;
+  print!("{}", render_graph(&description, format, workflow_id)?);
+  Ok(true)
+}
+
+/// Renders the workflows of the description as a graph. A DOT graph always has all the workflows.
+/// A Mermaid flowchart can only have a single workflow, so if no workflow ID is given, each
+/// workflow is rendered in its own ```` ```mermaid ```` block (i.e. to add to Markdown).
+pub fn render_graph(
+  description: &ArazzoDescription,
+  format: GraphFormat,
+  workflow_id: Option<&str>
+) -> anyhow::Result<String> {
+  match format {
+    GraphFormat::Dot => Ok(dot(description)),
+    GraphFormat::Mermaid => match workflow_id {
+      Some(workflow_id) => {
+        let options = MermaidOptions { fenced: false, .. MermaidOptions::default() };
+        mermaid_flowchart_with_options(description, workflow_id, &options)
+      }
+      None => {
+        let options = MermaidOptions::default();
+        let diagrams = description.workflows.iter()
+          .map(|workflow| mermaid_flowchart_with_options(description, &workflow.workflow_id, &options))
+          .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(diagrams.join("\n"))
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use crate::load::{parse_description, DocumentFormat};
+  use super::*;
+
+  const YAML: &str = r#"arazzo: 1.0.1
+info:
+  title: Pets
+  version: 1.0.0
+sourceDescriptions:
+  - name: petstore
+    url: petstore.json
+workflows:
+  - workflowId: listPets
+    steps:
+      - stepId: list
+        operationId: listPets
+  - workflowId: getPet
+    steps:
+      - stepId: get
+        operationId: getPet
+"#;
+
+  #[test]
+  fn renders_dot_graphs() {
+    let description = parse_description(YAML, DocumentFormat::Yaml).unwrap();
+    assert_eq!(render_graph(&description, GraphFormat::Dot, None).unwrap(), dot(&description));
+  }
+
+  #[test]
+  fn renders_mermaid_flowcharts() {
+    let description = parse_description(YAML, DocumentFormat::Yaml).unwrap();
+
+    let flowchart = render_graph(&description, GraphFormat::Mermaid, Some("getPet")).unwrap();
+    assert!(flowchart.starts_with("flowchart TD\n"));
+
+    let flowcharts = render_graph(&description, GraphFormat::Mermaid, None).unwrap();
+    assert_eq!(flowcharts.matches("```mermaid").count(), 2);
+
+    let result = render_graph(&description, GraphFormat::Mermaid, Some("other"));
+    assert!(result.is_err());
+  }
+}
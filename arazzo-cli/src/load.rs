@@ -0,0 +1,94 @@
+//! Loading of Arazzo descriptions from files
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use arazzo_models::operations::OperationResolver;
+use arazzo_models::v1_0::ArazzoDescription;
+use arazzo_models::yaml::{yaml_load_documents, yaml_to_json};
+use serde_json::Value;
+
+/// Format of an Arazzo document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DocumentFormat {
+  /// JSON document
+  Json,
+  /// YAML document
+  Yaml
+}
+
+impl DocumentFormat {
+  /// Returns the format of the file, based on its extension. Files without a `.json` extension
+  /// are treated as YAML.
+  pub fn from_path(path: &Path) -> Self {
+    match path.extension().and_then(|ext| ext.to_str()) {
+      Some(ext) if ext.eq_ignore_ascii_case("json") => DocumentFormat::Json,
+      _ => DocumentFormat::Yaml
+    }
+  }
+}
+
+/// Loads the Arazzo description from the JSON or YAML file
+pub fn load_description(path: &Path) -> anyhow::Result<ArazzoDescription> {
+  let contents = fs::read_to_string(path)
+    .with_context(|| format!("Failed to read '{}'", path.display()))?;
+  parse_description(&contents, DocumentFormat::from_path(path))
+    .with_context(|| format!("Failed to load '{}'", path.display()))
+}
+
+/// Parses the contents of a JSON or YAML document as a JSON value, keeping the order of the keys
+pub fn parse_document(contents: &str, format: DocumentFormat) -> anyhow::Result<Value> {
+  match format {
+    DocumentFormat::Json => Ok(serde_json::from_str(contents)?),
+    DocumentFormat::Yaml => {
+      let documents = yaml_load_documents(contents)?;
+      let document = documents.first().ok_or_else(|| anyhow!("The document is empty"))?;
+      yaml_to_json(document)
+    }
+  }
+}
+
+/// Loads the OpenAPI source descriptions of the description loaded from the file. Relative file
+/// paths are resolved against the directory of the file.
+pub fn load_sources(description: &ArazzoDescription, path: &Path) -> anyhow::Result<OperationResolver> {
+  OperationResolver::load(description, path.parent())
+}
+
+/// Parses the Arazzo description from the contents of a document
+pub fn parse_description(contents: &str, format: DocumentFormat) -> anyhow::Result<ArazzoDescription> {
+  match format {
+    DocumentFormat::Json => {
+      let json: Value = serde_json::from_str(contents)?;
+      ArazzoDescription::try_from(&json)
+    }
+    DocumentFormat::Yaml => {
+      let documents = yaml_load_documents(contents)?;
+      let document = documents.first().ok_or_else(|| anyhow!("The document is empty"))?;
+      ArazzoDescription::try_from(document)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn document_format_from_path() {
+    assert_eq!(DocumentFormat::from_path(Path::new("arazzo.json")), DocumentFormat::Json);
+    assert_eq!(DocumentFormat::from_path(Path::new("arazzo.JSON")), DocumentFormat::Json);
+    assert_eq!(DocumentFormat::from_path(Path::new("arazzo.yaml")), DocumentFormat::Yaml);
+    assert_eq!(DocumentFormat::from_path(Path::new("arazzo")), DocumentFormat::Yaml);
+  }
+
+  #[test]
+  fn parse_description_fails_for_empty_documents() {
+    let result = parse_description("", DocumentFormat::Yaml);
+    assert_eq!(result.unwrap_err().to_string(), "The document is empty");
+  }
+}
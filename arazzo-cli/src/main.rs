@@ -0,0 +1,170 @@
+//! Command line tool for working with Arazzo descriptions
+//!
+//! The exit code is 0 if the command succeeded, 1 if the command found problems with the
+//! description, and 2 if the command failed (i.e. the file could not be loaded).
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use arazzo_models::extensions::ExtensionFilter;
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::graph::GraphFormat;
+use crate::load::DocumentFormat;
+
+mod bundle;
+mod check;
+mod convert;
+mod graph;
+mod load;
+mod resolve;
+
+/// Command line tool for working with Arazzo descriptions
+#[derive(Debug, Parser)]
+#[command(name = "arazzo", version, about)]
+struct Cli {
+  #[command(subcommand)]
+  command: Command
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+  /// Validates an Arazzo description against the specification
+  Validate {
+    /// JSON or YAML file with the Arazzo description
+    file: PathBuf,
+    /// Format to write the results in
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat
+  },
+  /// Lints an Arazzo description, reporting unused components and missing documentation or
+  /// success criteria
+  Lint {
+    /// JSON or YAML file with the Arazzo description
+    file: PathBuf,
+    /// Format to write the results in
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat
+  },
+  /// Converts an Arazzo description between JSON and YAML. The keys are written in the same
+  /// order as in the source file, so this can also be used to normalise the format of a description.
+  Convert {
+    /// JSON or YAML file with the Arazzo description
+    file: PathBuf,
+    /// Format to convert the description to
+    #[arg(long, value_enum)]
+    to: DocumentFormat,
+    /// File to write the converted description to. Defaults to standard output.
+    #[arg(long)]
+    out: Option<PathBuf>,
+    /// Removes all the extension values
+    #[arg(long, conflicts_with_all = ["keep_extension", "remove_extension"])]
+    remove_extensions: bool,
+    /// Only keeps the extension values with the given key (i.e. `x-internal` or `x-pact-*`). Can
+    /// be repeated.
+    #[arg(long, value_name = "KEY", conflicts_with = "remove_extension")]
+    keep_extension: Vec<String>,
+    /// Removes the extension values with the given key (i.e. `x-internal` or `x-pact-*`). Can be
+    /// repeated.
+    #[arg(long, value_name = "KEY")]
+    remove_extension: Vec<String>
+  },
+  /// Bundles an Arazzo description into a single self-contained document, with the components
+  /// inlined where they are used and the OpenAPI source descriptions embedded as `data:` URLs
+  Bundle {
+    /// JSON or YAML file with the Arazzo description
+    file: PathBuf,
+    /// Format to write the bundled description in. Defaults to the format of the file.
+    #[arg(long, value_enum)]
+    to: Option<DocumentFormat>,
+    /// File to write the bundled description to. Defaults to standard output.
+    #[arg(long)]
+    out: Option<PathBuf>,
+    /// Keeps the URLs of the source descriptions, instead of embedding the documents
+    #[arg(long)]
+    keep_sources: bool
+  },
+  /// Reports the operation that each step references with its `operationId` or `operationPath`,
+  /// resolved from the OpenAPI source descriptions
+  Resolve {
+    /// JSON or YAML file with the Arazzo description
+    file: PathBuf,
+    /// Format to write the results in
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat
+  },
+  /// Renders the workflows of an Arazzo description as a graph
+  Graph {
+    /// JSON or YAML file with the Arazzo description
+    file: PathBuf,
+    /// Format to render the graph in
+    #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+    format: GraphFormat,
+    /// Workflow to render a Mermaid flowchart of. Defaults to a flowchart for every workflow.
+    #[arg(long)]
+    workflow: Option<String>
+  }
+}
+
+impl Command {
+  /// The filter to apply to the extension values when converting, if any
+  fn extension_filter(&self) -> Option<ExtensionFilter> {
+    match self {
+      Command::Convert { remove_extensions, keep_extension, remove_extension, .. } => {
+        if *remove_extensions {
+          Some(ExtensionFilter::RemoveAll)
+        } else if !keep_extension.is_empty() {
+          Some(ExtensionFilter::Allow(keep_extension.clone()))
+        } else if !remove_extension.is_empty() {
+          Some(ExtensionFilter::Deny(remove_extension.clone()))
+        } else {
+          None
+        }
+      }
+      _ => None
+    }
+  }
+}
+
+/// Format to write the results of a command in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+  /// Human-readable text
+  Text,
+  /// JSON document
+  Json
+}
+
+fn main() -> ExitCode {
+  let cli = Cli::parse();
+  let result = match &cli.command {
+    Command::Validate { file, output } => check::validate_command(file, *output),
+    Command::Lint { file, output } => check::lint_command(file, *output),
+    Command::Convert { file, to, out, .. } => convert::convert_command(file, *to,
+      cli.command.extension_filter().as_ref(), out.as_deref()),
+    Command::Bundle { file, to, out, keep_sources } => bundle::bundle_command(file, *to, *keep_sources,
+      out.as_deref()),
+    Command::Resolve { file, output } => resolve::resolve_command(file, *output),
+    Command::Graph { file, format, workflow } => graph::graph_command(file, *format, workflow.as_deref())
+  };
+  match result {
+    Ok(true) => ExitCode::SUCCESS,
+    Ok(false) => ExitCode::from(1),
+    Err(err) => {
+      eprintln!("Error: {:#}", err);
+      ExitCode::from(2)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use clap::CommandFactory;
+
+  use super::Cli;
+
+  #[test]
+  fn verify_cli() {
+    Cli::command().debug_assert();
+  }
+}
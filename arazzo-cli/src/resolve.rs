@@ -0,0 +1,162 @@
+//! The `resolve` command
+
+use std::path::Path;
+
+use arazzo_models::operations::{Operation, OperationResolver};
+use arazzo_models::v1_0::ArazzoDescription;
+use serde_json::json;
+
+use crate::load::{load_description, load_sources};
+use crate::OutputFormat;
+
+/// Operation a step references, and what it was resolved to
+#[derive(Debug)]
+pub struct Resolution {
+  /// ID of the workflow the step belongs to
+  pub workflow_id: String,
+  /// ID of the step
+  pub step_id: String,
+  /// The `operationId` or `operationPath` of the step
+  pub reference: String,
+  /// The resolved operation, or the reason it could not be resolved
+  pub operation: Result<Operation, String>
+}
+
+/// Resolves the operations that the steps of the description in the file reference. Returns true
+/// if all the operations were found.
+pub fn resolve_command(file: &Path, output: OutputFormat) -> anyhow::Result<bool> {
+  let description = load_description(file)?;
+  let resolver = load_sources(&description, file)?;
+  let resolutions = resolve_operations(&description, &resolver);
+  println!("{}", format_resolutions(&resolutions, output));
+  Ok(resolutions.iter().all(|resolution| resolution.operation.is_ok()))
+}
+
+/// Resolves the operation of every step that references one (steps that execute a workflow are
+/// skipped)
+pub fn resolve_operations(description: &ArazzoDescription, resolver: &OperationResolver) -> Vec<Resolution> {
+  description.workflows.iter()
+    .flat_map(|workflow| workflow.steps.iter().map(move |step| (workflow, step)))
+    .filter_map(|(workflow, step)| {
+      let reference = step.operation_id.as_ref().or(step.operation_path.as_ref())?;
+      Some(Resolution {
+        workflow_id: workflow.workflow_id.clone(),
+        step_id: step.step_id.clone(),
+        reference: reference.clone(),
+        operation: resolver.resolve_step(step).map_err(|err| err.to_string())
+      })
+    })
+    .collect()
+}
+
+/// Formats the resolved operations
+pub fn format_resolutions(resolutions: &[Resolution], output: OutputFormat) -> String {
+  match output {
+    OutputFormat::Text => resolutions.iter()
+      .map(|resolution| {
+        let target = match &resolution.operation {
+          Ok(operation) => format!("{} {} ({})", operation.method, operation.path, operation.source_name),
+          Err(err) => format!("not found: {}", err)
+        };
+        format!("{}/{}: {} -> {}", resolution.workflow_id, resolution.step_id, resolution.reference, target)
+      })
+      .collect::<Vec<_>>()
+      .join("\n"),
+    OutputFormat::Json => resolutions.iter()
+      .map(|resolution| {
+        let mut result = json!({
+          "workflowId": resolution.workflow_id,
+          "stepId": resolution.step_id,
+          "reference": resolution.reference
+        });
+        result["operation"] = match &resolution.operation {
+          Ok(operation) => json!({
+            "source": operation.source_name,
+            "operationId": operation.operation_id,
+            "method": operation.method,
+            "path": operation.path,
+            "serverUrl": operation.server_url
+          }),
+          Err(err) => json!({ "error": err })
+        };
+        result
+      })
+      .collect::<serde_json::Value>()
+      .to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use arazzo_models::operations::{OpenApiSource, OperationResolver};
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use crate::load::{parse_description, DocumentFormat};
+  use crate::OutputFormat;
+  use super::*;
+
+  const YAML: &str = r#"arazzo: 1.0.1
+info:
+  title: Pets
+  version: 1.0.0
+sourceDescriptions:
+  - name: petstore
+    url: petstore.json
+workflows:
+  - workflowId: pets
+    steps:
+      - stepId: list
+        operationId: listPets
+      - stepId: get
+        operationPath: '{$sourceDescriptions.petstore.url}#/paths/~1pets~1{petId}/get'
+      - stepId: delete
+        operationId: deletePet
+"#;
+
+  fn resolver() -> OperationResolver {
+    OperationResolver::new(vec![OpenApiSource::new("petstore", "petstore.json", json!({
+      "paths": {
+        "/pets": { "get": { "operationId": "listPets" } },
+        "/pets/{petId}": { "get": { "operationId": "getPet" } }
+      }
+    }))])
+  }
+
+  #[test]
+  fn resolves_operations_as_text() {
+    let description = parse_description(YAML, DocumentFormat::Yaml).unwrap();
+    let resolutions = resolve_operations(&description, &resolver());
+    assert_eq!(format_resolutions(&resolutions, OutputFormat::Text),
+      "pets/list: listPets -> GET /pets (petstore)\n\
+       pets/get: {$sourceDescriptions.petstore.url}#/paths/~1pets~1{petId}/get -> GET /pets/{petId} (petstore)\n\
+       pets/delete: deletePet -> not found: No operation with ID 'deletePet' was found in the source descriptions");
+  }
+
+  #[test]
+  fn resolves_operations_as_json() {
+    let description = parse_description(YAML, DocumentFormat::Yaml).unwrap();
+    let resolutions = resolve_operations(&description, &resolver());
+    let output = format_resolutions(&resolutions[1..], OutputFormat::Json);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&output).unwrap(), json!([
+      {
+        "workflowId": "pets",
+        "stepId": "get",
+        "reference": "{$sourceDescriptions.petstore.url}#/paths/~1pets~1{petId}/get",
+        "operation": {
+          "source": "petstore",
+          "operationId": "getPet",
+          "method": "GET",
+          "path": "/pets/{petId}",
+          "serverUrl": null
+        }
+      },
+      {
+        "workflowId": "pets",
+        "stepId": "delete",
+        "reference": "deletePet",
+        "operation": { "error": "No operation with ID 'deletePet' was found in the source descriptions" }
+      }
+    ]));
+  }
+}
@@ -0,0 +1,98 @@
+//! Compares loading the owned models with loading the borrowed models from the same JSON
+//! document. Before running the benchmarks, the number of allocations (and bytes allocated) each
+//! one needs to load the document is printed.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use arazzo_models::borrowed;
+use arazzo_models::v1_0::ArazzoDescription;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use serde_json::{json, Value};
+
+/// Allocator that counts the allocations made through it
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+    unsafe { System.alloc(layout) }
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    unsafe { System.dealloc(ptr, layout) }
+  }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the number of allocations and bytes allocated by the function
+fn count_allocations<T>(f: impl FnOnce() -> T) -> (usize, usize) {
+  let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+  let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+  let result = f();
+  let counts = (ALLOCATIONS.load(Ordering::Relaxed) - allocations, ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes);
+  drop(result);
+  counts
+}
+
+/// Generates a description with the given number of workflows, each with ten steps
+fn document(workflows: usize) -> Value {
+  let workflows = (0..workflows).map(|index| json!({
+    "workflowId": format!("workflow-{}", index),
+    "summary": "Adds a pet to the store, and then finds it",
+    "inputs": { "type": "object", "properties": { "name": { "type": "string" } } },
+    "x-owner": { "team": "pets", "channel": "#pets" },
+    "steps": (0..10).map(|step| json!({
+      "stepId": format!("step-{}", step),
+      "description": "Calls an operation of the pet store",
+      "operationId": "$sourceDescriptions.petstore.addPet",
+      "parameters": [
+        { "name": "store", "in": "header", "value": "$inputs.store" },
+        { "reference": "$components.parameters.page", "value": "2" }
+      ],
+      "requestBody": { "contentType": "application/json", "payload": { "name": "$inputs.name", "tags": ["dog"] } },
+      "successCriteria": [ { "condition": "$statusCode == 200" }, { "context": "$response.body", "condition": "$.id", "type": "jsonpath" } ],
+      "onFailure": [ { "name": "retry", "type": "retry", "retryAfter": 1, "retryLimit": 3 } ],
+      "outputs": { "id": "$response.body#/id" },
+      "x-timeout": "10s"
+    })).collect::<Vec<_>>()
+  })).collect::<Vec<_>>();
+  json!({
+    "arazzo": "1.0.1",
+    "info": { "title": "Pet store", "version": "1.0.0" },
+    "sourceDescriptions": [ { "name": "petstore", "url": "petstore.json" } ],
+    "workflows": workflows,
+    "components": {
+      "parameters": { "page": { "name": "page", "in": "query", "value": 1 } }
+    }
+  })
+}
+
+fn load(c: &mut Criterion) {
+  let mut group = c.benchmark_group("load");
+  for workflows in [1, 10, 100] {
+    let json = document(workflows);
+
+    let (owned, owned_bytes) = count_allocations(|| ArazzoDescription::try_from(&json).unwrap());
+    let (borrowed, borrowed_bytes) = count_allocations(|| borrowed::ArazzoDescription::try_from(&json).unwrap());
+    println!("{} workflows: owned models {} allocations ({} bytes), borrowed models {} allocations ({} bytes)",
+      workflows, owned, owned_bytes, borrowed, borrowed_bytes);
+
+    group.bench_with_input(BenchmarkId::new("owned", workflows), &json, |b, json| {
+      b.iter(|| ArazzoDescription::try_from(json).unwrap())
+    });
+    group.bench_with_input(BenchmarkId::new("borrowed", workflows), &json, |b, json| {
+      b.iter(|| borrowed::ArazzoDescription::try_from(json).unwrap())
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, load);
+criterion_main!(benches);
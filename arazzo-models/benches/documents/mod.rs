@@ -0,0 +1,82 @@
+//! Representative documents for the benchmarks. Each benchmark is run against all of them.
+//!
+//! * `small`: A single workflow with a few steps, like a hand written description.
+//! * `medium`: 50 workflows with 10 steps each, like a generated workflow suite.
+//! * `extensions`: 10 workflows with 10 steps each, where every object has 20 extension values
+//!   (some of them nested), which is the pathological case for extracting extension values.
+
+use serde_json::{json, Map, Value};
+
+/// Returns the name and JSON value of each of the documents
+pub fn documents() -> Vec<(&'static str, Value)> {
+  vec![
+    ("small", generate(1, 3, 0)),
+    ("medium", generate(50, 10, 1)),
+    ("extensions", generate(10, 10, 20))
+  ]
+}
+
+/// Generates the extension values for an object
+fn extensions(count: usize) -> Map<String, Value> {
+  (0..count).map(|index| {
+    let value = match index % 4 {
+      0 => json!(format!("value-{}", index)),
+      1 => json!(index),
+      2 => json!([ "pets", "smoke", { "priority": index } ]),
+      _ => json!({ "team": "pets", "owners": [ "alice", "bob" ], "details": { "level": index, "enabled": true } })
+    };
+    (format!("x-ext-{}", index), value)
+  }).collect()
+}
+
+/// Adds the extension values to the object
+fn with_extensions(mut value: Value, count: usize) -> Value {
+  if let Some(map) = value.as_object_mut() {
+    map.extend(extensions(count));
+  }
+  value
+}
+
+/// Generates a description with the number of workflows and steps in each workflow. Every object
+/// gets the given number of extension values.
+pub fn generate(workflows: usize, steps: usize, extensions: usize) -> Value {
+  let workflows = (0..workflows).map(|index| with_extensions(json!({
+    "workflowId": format!("workflow-{}", index),
+    "summary": "Adds a pet to the store, and then finds it",
+    "inputs": { "type": "object", "properties": { "name": { "type": "string" } } },
+    "steps": (0..steps).map(|step| with_extensions(json!({
+      "stepId": format!("step-{}", step),
+      "description": "Calls an operation of the pet store",
+      "operationId": "$sourceDescriptions.petstore.addPet",
+      "parameters": [
+        with_extensions(json!({ "name": "store", "in": "header", "value": "$inputs.store" }), extensions),
+        { "name": "limit", "in": "query", "value": 10 },
+        { "reference": "$components.parameters.page", "value": "2" }
+      ],
+      "requestBody": with_extensions(json!({
+        "contentType": "application/json",
+        "payload": { "name": "$inputs.name", "tags": ["dog"] }
+      }), extensions),
+      "successCriteria": [
+        with_extensions(json!({ "condition": "$statusCode == 200" }), extensions),
+        { "context": "$response.body", "condition": "$.id", "type": "jsonpath" }
+      ],
+      "onFailure": [
+        with_extensions(json!({ "name": "retry", "type": "retry", "retryAfter": 1, "retryLimit": 3 }), extensions)
+      ],
+      "outputs": { "id": "$response.body#/id" }
+    }), extensions)).collect::<Vec<_>>(),
+    "outputs": { "id": format!("$steps.step-{}.outputs.id", steps.saturating_sub(1)) }
+  }), extensions)).collect::<Vec<_>>();
+
+  with_extensions(json!({
+    "arazzo": "1.0.1",
+    "info": with_extensions(json!({ "title": "Pet store", "version": "1.0.0" }), extensions),
+    "sourceDescriptions": [ { "name": "petstore", "url": "petstore.json", "type": "openapi" } ],
+    "workflows": workflows,
+    "components": {
+      "inputs": { "pet": { "type": "object", "properties": { "name": { "type": "string" } } } },
+      "parameters": { "page": { "name": "page", "in": "query", "value": 1 } }
+    }
+  }), extensions)
+}
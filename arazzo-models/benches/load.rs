@@ -0,0 +1,54 @@
+//! Loads the models from the benchmark documents. `load` measures loading the models from values
+//! that have already been parsed, and `parse` measures parsing the document text and then loading
+//! the models from it (`yaml_stream` parses the YAML text with the streaming loader).
+
+use arazzo_models::v1_0::ArazzoDescription;
+use arazzo_models::yaml::yaml_load_documents;
+use arazzo_models::yaml_stream::yaml_stream_load;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde_json::Value;
+use yaml_rust2::YamlLoader;
+
+mod documents;
+
+fn load(c: &mut Criterion) {
+  let mut group = c.benchmark_group("load");
+  for (name, json) in documents::documents() {
+    let yaml = YamlLoader::load_from_str(serde_yaml::to_string(&json).unwrap().as_str()).unwrap();
+
+    group.bench_with_input(BenchmarkId::new("json", name), &json, |b, json| {
+      b.iter(|| ArazzoDescription::try_from(json).unwrap())
+    });
+    group.bench_with_input(BenchmarkId::new("yaml", name), &yaml[0], |b, yaml| {
+      b.iter(|| ArazzoDescription::try_from(yaml).unwrap())
+    });
+  }
+  group.finish();
+}
+
+fn parse(c: &mut Criterion) {
+  let mut group = c.benchmark_group("parse");
+  for (name, json) in documents::documents() {
+    let json_text = serde_json::to_string(&json).unwrap();
+    let yaml_text = serde_yaml::to_string(&json).unwrap();
+
+    group.throughput(Throughput::Bytes(json_text.len() as u64));
+    group.bench_with_input(BenchmarkId::new("json", name), &json_text, |b, text| {
+      b.iter(|| {
+        let json: Value = serde_json::from_str(text).unwrap();
+        ArazzoDescription::try_from(&json).unwrap()
+      })
+    });
+    group.throughput(Throughput::Bytes(yaml_text.len() as u64));
+    group.bench_with_input(BenchmarkId::new("yaml", name), &yaml_text, |b, text| {
+      b.iter(|| ArazzoDescription::try_from(&yaml_load_documents(text).unwrap()[0]).unwrap())
+    });
+    group.bench_with_input(BenchmarkId::new("yaml_stream", name), &yaml_text, |b, text| {
+      b.iter(|| yaml_stream_load(text).unwrap())
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, load, parse);
+criterion_main!(benches);
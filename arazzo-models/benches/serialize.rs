@@ -0,0 +1,23 @@
+//! Writes the descriptions loaded from the benchmark documents to JSON and YAML
+
+use arazzo_models::v1_0::ArazzoDescription;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+mod documents;
+
+fn serialize(c: &mut Criterion) {
+  let mut group = c.benchmark_group("serialize");
+  for (name, json) in documents::documents() {
+    let description = ArazzoDescription::try_from(&json).unwrap();
+    group.bench_with_input(BenchmarkId::new("json", name), &description, |b, description| {
+      b.iter(|| serde_json::to_string(description).unwrap())
+    });
+    group.bench_with_input(BenchmarkId::new("yaml", name), &description, |b, description| {
+      b.iter(|| serde_yaml::to_string(description).unwrap())
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, serialize);
+criterion_main!(benches);
@@ -0,0 +1,21 @@
+//! Validates the descriptions loaded from the benchmark documents
+
+use arazzo_models::v1_0::ArazzoDescription;
+use arazzo_models::validation::validate;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+mod documents;
+
+fn validation(c: &mut Criterion) {
+  let mut group = c.benchmark_group("validate");
+  for (name, json) in documents::documents() {
+    let description = ArazzoDescription::try_from(&json).unwrap();
+    group.bench_with_input(BenchmarkId::from_parameter(name), &description, |b, description| {
+      b.iter(|| validate(description))
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, validation);
+criterion_main!(benches);
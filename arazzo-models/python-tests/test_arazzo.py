@@ -0,0 +1,81 @@
+import json
+
+import pytest
+
+import arazzo
+
+DESCRIPTION = {
+    "arazzo": "1.0.1",
+    "info": {"title": "Pet Store", "version": "1.0.0", "x-owner": "team-a"},
+    "sourceDescriptions": [{"name": "petstore", "url": "petstore.yaml", "type": "openapi"}],
+    "workflows": [
+        {
+            "workflowId": "adopt",
+            "inputs": {"type": "object", "properties": {"petId": {"type": "string"}}},
+            "steps": [
+                {
+                    "stepId": "find",
+                    "operationId": "getPet",
+                    "parameters": [{"name": "petId", "in": "path", "value": "$inputs.petId"}],
+                    "successCriteria": [{"condition": "$statusCode == 200"}],
+                    "outputs": {"id": "$response.body#/id"},
+                }
+            ],
+        }
+    ],
+}
+
+
+def test_loads_descriptions_from_json_and_yaml():
+    description = arazzo.loads(json.dumps(DESCRIPTION))
+    assert description.info.title == "Pet Store"
+    assert description.info.extensions == {"x-owner": "team-a"}
+    assert [source.type for source in description.source_descriptions] == ["openapi"]
+
+    yaml = arazzo.loads(
+        "arazzo: 1.0.1\n"
+        "info:\n  title: From YAML\n  version: 1.0.0\n"
+        "sourceDescriptions:\n  - name: petstore\n    url: petstore.yaml\n"
+        "workflows:\n  - workflowId: adopt\n    steps:\n      - stepId: find\n        operationId: getPet\n"
+    )
+    assert yaml.info.title == "From YAML"
+
+
+def test_mirrors_the_workflows_and_steps():
+    description = arazzo.from_dict(DESCRIPTION)
+    workflow = description.workflow("adopt")
+    assert workflow.workflow_id == "adopt"
+    assert workflow.inputs["properties"]["petId"] == {"type": "string"}
+
+    step = workflow.step("find")
+    assert step.operation_id == "getPet"
+    assert step.parameters == [{"name": "petId", "in": "path", "value": "$inputs.petId"}]
+    assert [criterion.type for criterion in step.success_criteria] == ["simple"]
+    assert step.outputs == {"id": "$response.body#/id"}
+    assert step.request_body is None
+    assert step.on_success == []
+    assert workflow.success_actions == []
+    assert description.workflow("missing") is None
+
+
+def test_converts_descriptions_to_dictionaries():
+    description = arazzo.from_dict(DESCRIPTION)
+    assert arazzo.from_dict(description.to_dict()).to_dict() == description.to_dict()
+    assert json.loads(description.to_json())["info"]["title"] == "Pet Store"
+
+
+def test_validates_descriptions():
+    description = arazzo.from_dict(DESCRIPTION)
+    assert description.validate() == []
+
+    invalid = dict(DESCRIPTION, arazzo="2.0.0")
+    issues = arazzo.validate(arazzo.from_dict(invalid))
+    assert issues
+    assert all(issue.path and issue.message for issue in issues)
+
+
+def test_raises_an_error_for_invalid_documents():
+    with pytest.raises(arazzo.ArazzoError):
+        arazzo.loads("[]")
+    with pytest.raises(TypeError):
+        arazzo.from_dict({"info": object()})
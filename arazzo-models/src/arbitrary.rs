@@ -0,0 +1,602 @@
+//! Implementations of the proptest `Arbitrary` trait for the models (requires the `proptest`
+//! feature), so that crates using the models can property test round-tripping and their own
+//! transformations without having to write generators for the models.
+//!
+//! The generated objects are valid according to the specification (i.e. they pass the checks in
+//! the `validation` module). Generated descriptions have no dangling references: steps and actions
+//! only refer to source descriptions, workflows, steps and components that exist in the
+//! description, and workflows only refer to workflows that come after them, so there are no
+//! cycles.
+//!
+//! Values are generated in the form the JSON loader creates them (non-negative integers are
+//! `AnyValue::UInteger`, and there are no binary or big number values), so the generated models
+//! are equal to the result of writing them to JSON and loading them again.
+//!
+//! ```rust
+//! # use arazzo_models::v1_0::ArazzoDescription;
+//! use proptest::prelude::*;
+//! use proptest::strategy::ValueTree;
+//! use proptest::test_runner::TestRunner;
+//!
+//! let mut runner = TestRunner::default();
+//! let description = any::<ArazzoDescription>().new_tree(&mut runner).unwrap().current();
+//! assert!(description.validate().is_ok());
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use proptest::collection::{btree_map, btree_set, hash_map, vec};
+use proptest::option;
+use proptest::prelude::*;
+use proptest::sample::{select, subsequence};
+use proptest::strategy::Union;
+use serde_json::{json, Map, Value};
+
+use crate::either::Either;
+use crate::extensions::AnyValue;
+use crate::payloads::JsonPayload;
+use crate::v1_0::*;
+
+/// Names of the objects that generated objects can refer to
+#[derive(Debug, Clone, Default)]
+struct Names {
+  source_descriptions: Vec<String>,
+  workflows: Vec<String>,
+  steps: Vec<String>,
+  inputs: Vec<String>,
+  parameters: Vec<String>,
+  /// Component parameters without a location, which steps that target a workflow can refer to
+  input_parameters: Vec<String>,
+  success_actions: Vec<String>,
+  failure_actions: Vec<String>
+}
+
+/// Generates a name or ID that is valid for all the named objects and keys
+fn identifier() -> impl Strategy<Value = String> {
+  "[a-zA-Z][a-zA-Z0-9_-]{0,11}"
+}
+
+/// Generates a human-readable text value, like a title or description
+fn text() -> impl Strategy<Value = String> {
+  "[a-zA-Z][a-zA-Z0-9 .,_-]{0,31}"
+}
+
+fn any_value() -> impl Strategy<Value = AnyValue> {
+  let leaf = prop_oneof![
+    Just(AnyValue::Null),
+    any::<bool>().prop_map(AnyValue::Boolean),
+    (i64::MIN..0).prop_map(AnyValue::Integer),
+    any::<u64>().prop_map(AnyValue::UInteger),
+    (-1.0e6..1.0e6f64).prop_map(AnyValue::Float),
+    text().prop_map(AnyValue::String)
+  ];
+  leaf.prop_recursive(3, 16, 4, |inner| prop_oneof![
+    vec(inner.clone(), 0..4).prop_map(AnyValue::Array),
+    vec((identifier(), inner), 0..4).prop_map(|entries| AnyValue::Object(entries.into_iter().collect()))
+  ])
+}
+
+fn extensions() -> impl Strategy<Value = HashMap<String, AnyValue>> {
+  hash_map(identifier(), any_value(), 0..3)
+}
+
+/// Generates a value for a parameter or payload replacement, which is either a literal value or a
+/// runtime expression
+fn any_or_expression() -> impl Strategy<Value = Either<AnyValue, String>> {
+  prop_oneof![
+    any_value().prop_map(Either::First),
+    identifier().prop_map(|name| Either::Second(format!("$inputs.{}", name)))
+  ]
+}
+
+/// Generates a JSON Schema for the inputs of a workflow
+fn input_schema() -> impl Strategy<Value = Value> {
+  btree_set(identifier(), 1..4).prop_map(|names| {
+    let properties = names.iter()
+      .map(|name| (name.clone(), json!({ "type": "string" })))
+      .collect::<Map<_, _>>();
+    json!({ "type": "object", "properties": properties })
+  })
+}
+
+fn reusable_object(prefix: &'static str, names: &[String], with_value: bool) -> BoxedStrategy<ReusableObject> {
+  let value = if with_value { option::of(text()).boxed() } else { Just(None).boxed() };
+  (select(names.to_vec()), value)
+    .prop_map(move |(name, value)| ReusableObject { reference: format!("{}{}", prefix, name), value })
+    .boxed()
+}
+
+impl Arbitrary for AnyValue {
+  type Parameters = ();
+  type Strategy = BoxedStrategy<Self>;
+
+  fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+    any_value().boxed()
+  }
+}
+
+impl Arbitrary for Info {
+  type Parameters = ();
+  type Strategy = BoxedStrategy<Self>;
+
+  fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+    (text(), option::of(text()), option::of(text()), "[0-9]\\.[0-9]{1,2}\\.[0-9]{1,2}", extensions())
+      .prop_map(|(title, summary, description, version, extensions)| Info {
+        title,
+        summary,
+        description,
+        version,
+        extensions
+      })
+      .boxed()
+  }
+}
+
+fn source_description(name: String) -> impl Strategy<Value = SourceDescription> {
+  (option::of(Just("openapi".to_string())), extensions())
+    .prop_map(move |(r#type, extensions)| SourceDescription {
+      url: format!("https://api.example.com/{}/openapi.yaml", name),
+      name: name.clone(),
+      r#type,
+      extensions
+    })
+}
+
+impl Arbitrary for SourceDescription {
+  type Parameters = ();
+  type Strategy = BoxedStrategy<Self>;
+
+  fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+    identifier().prop_flat_map(source_description).boxed()
+  }
+}
+
+impl Arbitrary for CriterionExpressionType {
+  type Parameters = ();
+  type Strategy = BoxedStrategy<Self>;
+
+  fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+    let expression_type = prop_oneof![
+      Just(("jsonpath", "draft-goessner-dispatch-jsonpath-00")),
+      select(vec![("xpath", "xpath-30"), ("xpath", "xpath-20"), ("xpath", "xpath-10")])
+    ];
+    (expression_type, extensions())
+      .prop_map(|((r#type, version), extensions)| CriterionExpressionType {
+        r#type: r#type.to_string(),
+        version: version.to_string(),
+        extensions
+      })
+      .boxed()
+  }
+}
+
+impl Arbitrary for Criterion {
+  type Parameters = ();
+  type Strategy = BoxedStrategy<Self>;
+
+  fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+    let simple = (
+      select(vec!["$statusCode == 200", "$statusCode < 400", "$response.body#/id != null"]),
+      option::of(Just(Either::First("simple".to_string())))
+    ).prop_map(|(condition, r#type)| (None, condition.to_string(), r#type));
+    let regex = Just((
+      Some("$response.header.Content-Type".to_string()),
+      "^application/json".to_string(),
+      Some(Either::First("regex".to_string()))
+    ));
+    let jsonpath = prop_oneof![
+      Just(Either::First("jsonpath".to_string())),
+      any::<CriterionExpressionType>()
+        .prop_filter("JSONPath expression type", |expression_type| expression_type.r#type == "jsonpath")
+        .prop_map(Either::Second)
+    ].prop_map(|r#type| (Some("$response.body".to_string()), "$[?(@.id != null)]".to_string(), Some(r#type)));
+
+    (prop_oneof![simple, regex, jsonpath], extensions())
+      .prop_map(|((context, condition, r#type), extensions)| Criterion {
+        context,
+        condition,
+        r#type,
+        extensions
+      })
+      .boxed()
+  }
+}
+
+/// Locations the generated parameters can have
+#[derive(Debug, Clone, Copy)]
+enum ParameterLocation {
+  /// Parameters of steps that target an operation must have a location
+  Required,
+  /// Parameters of workflows and components may have a location
+  Optional,
+  /// Parameters of steps that target a workflow map to the workflow inputs, so can not have one
+  Forbidden
+}
+
+fn parameter(location: ParameterLocation) -> impl Strategy<Value = ParameterObject> {
+  let locations = select(vec!["path", "query", "header", "cookie"]).prop_map(|location| location.to_string());
+  let location = match location {
+    ParameterLocation::Required => locations.prop_map(Some).boxed(),
+    ParameterLocation::Optional => option::of(locations).boxed(),
+    ParameterLocation::Forbidden => Just(None).boxed()
+  };
+  (identifier(), location, any_or_expression(), extensions())
+    .prop_map(|(name, r#in, value, extensions)| ParameterObject { name, r#in, value, extensions })
+}
+
+impl Arbitrary for ParameterObject {
+  type Parameters = ();
+  type Strategy = BoxedStrategy<Self>;
+
+  fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+    parameter(ParameterLocation::Required).boxed()
+  }
+}
+
+fn parameters(names: &Names, location: ParameterLocation) -> BoxedStrategy<Vec<Either<ParameterObject, ReusableObject>>> {
+  let mut options = vec![parameter(location).prop_map(Either::First).boxed()];
+  let components = match location {
+    ParameterLocation::Forbidden => &names.input_parameters,
+    _ => &names.parameters
+  };
+  if !components.is_empty() {
+    options.push(reusable_object("$components.parameters.", components, true).prop_map(Either::Second).boxed());
+  }
+  vec(Union::new(options), 0..3).boxed()
+}
+
+/// Type, workflow ID and step ID of an action
+type ActionTarget = (&'static str, Option<String>, Option<String>);
+
+/// Generates the type, workflow ID and step ID of a `goto` action
+fn goto_targets(names: &Names) -> Vec<BoxedStrategy<ActionTarget>> {
+  let mut options = vec![];
+  if !names.steps.is_empty() {
+    options.push(select(names.steps.clone()).prop_map(|step_id| ("goto", None, Some(step_id))).boxed());
+  }
+  if !names.workflows.is_empty() {
+    options.push(select(names.workflows.clone()).prop_map(|workflow_id| ("goto", Some(workflow_id), None)).boxed());
+  }
+  options
+}
+
+fn success_action(names: &Names) -> BoxedStrategy<SuccessObject> {
+  let mut targets = vec![Just(("end", None, None)).boxed()];
+  targets.extend(goto_targets(names));
+  (identifier(), Union::new(targets), vec(any::<Criterion>(), 0..2), extensions())
+    .prop_map(|(name, (r#type, workflow_id, step_id), criteria, extensions)| SuccessObject {
+      name,
+      r#type: r#type.to_string(),
+      workflow_id,
+      step_id,
+      criteria,
+      extensions
+    })
+    .boxed()
+}
+
+impl Arbitrary for SuccessObject {
+  type Parameters = ();
+  type Strategy = BoxedStrategy<Self>;
+
+  fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+    identifier()
+      .prop_flat_map(|step_id| success_action(&Names { steps: vec![step_id], .. Names::default() }))
+      .boxed()
+  }
+}
+
+fn success_actions(names: &Names) -> BoxedStrategy<Vec<Either<SuccessObject, ReusableObject>>> {
+  let mut options = vec![success_action(names).prop_map(Either::First).boxed()];
+  if !names.success_actions.is_empty() {
+    options.push(reusable_object("$components.successActions.", &names.success_actions, false).prop_map(Either::Second).boxed());
+  }
+  vec(Union::new(options), 0..3).boxed()
+}
+
+fn failure_action(names: &Names) -> BoxedStrategy<FailureObject> {
+  let mut targets = vec![
+    Just(("end", None, None)).boxed(),
+    Just(("retry", None, None)).boxed()
+  ];
+  targets.extend(goto_targets(names));
+  (identifier(), Union::new(targets), option::of(0.0..10.0f64), option::of(0..5i64),
+    vec(any::<Criterion>(), 0..2), extensions())
+    .prop_map(|(name, (r#type, workflow_id, step_id), retry_after, retry_limit, criteria, extensions)| {
+      let retry = r#type == "retry";
+      FailureObject {
+        name,
+        r#type: r#type.to_string(),
+        workflow_id,
+        step_id,
+        retry_after: retry_after.filter(|_| retry),
+        retry_limit: retry_limit.filter(|_| retry),
+        criteria,
+        extensions
+      }
+    })
+    .boxed()
+}
+
+impl Arbitrary for FailureObject {
+  type Parameters = ();
+  type Strategy = BoxedStrategy<Self>;
+
+  fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+    identifier()
+      .prop_flat_map(|step_id| failure_action(&Names { steps: vec![step_id], .. Names::default() }))
+      .boxed()
+  }
+}
+
+fn failure_actions(names: &Names) -> BoxedStrategy<Vec<Either<FailureObject, ReusableObject>>> {
+  let mut options = vec![failure_action(names).prop_map(Either::First).boxed()];
+  if !names.failure_actions.is_empty() {
+    options.push(reusable_object("$components.failureActions.", &names.failure_actions, false).prop_map(Either::Second).boxed());
+  }
+  vec(Union::new(options), 0..3).boxed()
+}
+
+impl Arbitrary for ReusableObject {
+  type Parameters = ();
+  type Strategy = BoxedStrategy<Self>;
+
+  fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+    identifier()
+      .prop_flat_map(|name| reusable_object("$components.parameters.", &[name], true))
+      .boxed()
+  }
+}
+
+fn payload_replacement(fields: Vec<String>) -> impl Strategy<Value = PayloadReplacement> {
+  (select(fields), any_or_expression(), extensions())
+    .prop_map(|(field, value, extensions)| PayloadReplacement {
+      target: format!("/{}", field),
+      value,
+      extensions
+    })
+}
+
+impl Arbitrary for PayloadReplacement {
+  type Parameters = ();
+  type Strategy = BoxedStrategy<Self>;
+
+  fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+    identifier().prop_flat_map(|field| payload_replacement(vec![field])).boxed()
+  }
+}
+
+impl Arbitrary for RequestBody {
+  type Parameters = ();
+  type Strategy = BoxedStrategy<Self>;
+
+  /// Generates a JSON request body, with replacements that target the fields of the payload
+  fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+    (btree_map(identifier(), text(), 1..4), extensions())
+      .prop_flat_map(|(fields, extensions)| {
+        let payload = fields.iter()
+          .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+          .collect::<Map<_, _>>();
+        vec(payload_replacement(fields.keys().cloned().collect()), 0..3)
+          .prop_map(move |replacements| RequestBody {
+            content_type: Some("application/json".to_string()),
+            payload: Some(Arc::new(JsonPayload(Value::Object(payload.clone())))),
+            replacements,
+            extensions: extensions.clone()
+          })
+      })
+      .boxed()
+  }
+}
+
+/// Generates the operation ID, operation path or workflow ID that a step targets
+fn step_target(names: &Names) -> BoxedStrategy<(Option<String>, Option<String>, Option<String>)> {
+  let mut options = vec![identifier().prop_map(|operation_id| (Some(operation_id), None, None)).boxed()];
+  if !names.source_descriptions.is_empty() {
+    options.push((select(names.source_descriptions.clone()), identifier())
+      .prop_map(|(source, operation_id)| (Some(format!("$sourceDescriptions.{}.{}", source, operation_id)), None, None))
+      .boxed());
+    options.push((select(names.source_descriptions.clone()), identifier())
+      .prop_map(|(source, path)| (None, Some(format!("{{$sourceDescriptions.{}.url}}#/paths/~1{}/get", source, path)), None))
+      .boxed());
+  }
+  if !names.workflows.is_empty() {
+    options.push(select(names.workflows.clone()).prop_map(|workflow_id| (None, None, Some(workflow_id))).boxed());
+  }
+  Union::new(options).boxed()
+}
+
+fn step(step_id: String, names: Names) -> impl Strategy<Value = Step> {
+  let target = step_target(&names);
+  target.prop_flat_map(move |(operation_id, operation_path, workflow_id)| {
+    let targets_workflow = workflow_id.is_some();
+    let request_body = if targets_workflow { Just(None).boxed() } else { option::of(any::<RequestBody>()).boxed() };
+    let outputs = btree_map(identifier(),
+      select(vec!["$statusCode", "$response.body#/id", "$response.header.Location"]).prop_map(|output| output.to_string()),
+      0..3);
+    let step_id = step_id.clone();
+    (
+      option::of(text()),
+      parameters(&names, if targets_workflow { ParameterLocation::Forbidden } else { ParameterLocation::Required }),
+      request_body,
+      vec(any::<Criterion>(), 0..3),
+      success_actions(&names),
+      failure_actions(&names),
+      outputs,
+      extensions()
+    ).prop_map(move |(description, parameters, request_body, success_criteria, on_success, on_failure, outputs, extensions)| Step {
+      step_id: step_id.clone(),
+      operation_id: operation_id.clone(),
+      operation_path: operation_path.clone(),
+      workflow_id: workflow_id.clone(),
+      description,
+      parameters,
+      request_body,
+      success_criteria,
+      on_success,
+      on_failure,
+      outputs,
+      extensions
+    })
+  })
+}
+
+impl Arbitrary for Step {
+  type Parameters = ();
+  type Strategy = BoxedStrategy<Self>;
+
+  fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+    identifier().prop_flat_map(|step_id| step(step_id, Names::default())).boxed()
+  }
+}
+
+/// Generates a workflow. The steps of the workflow are added to the names the steps and actions can
+/// refer to.
+fn workflow(workflow_id: String, names: Names) -> impl Strategy<Value = Workflow> {
+  btree_set(identifier(), 1..4).prop_flat_map(move |step_ids| {
+    let names = Names { steps: step_ids.iter().cloned().collect(), .. names.clone() };
+    let steps = step_ids.iter()
+      .map(|step_id| step(step_id.clone(), names.clone()))
+      .collect::<Vec<_>>();
+    let mut inputs = vec![Just(Value::Null).boxed(), input_schema().boxed()];
+    if !names.inputs.is_empty() {
+      inputs.push(select(names.inputs.clone())
+        .prop_map(|name| json!({ "$ref": format!("#/components/inputs/{}", name) }))
+        .boxed());
+    }
+    let outputs = btree_map(identifier(), identifier().prop_map(|name| format!("$inputs.{}", name)), 0..3);
+    let workflow_id = workflow_id.clone();
+    (
+      option::of(text()),
+      option::of(text()),
+      Union::new(inputs),
+      subsequence(names.workflows.clone(), 0..=names.workflows.len()),
+      steps,
+      success_actions(&names),
+      failure_actions(&names),
+      outputs,
+      parameters(&names, ParameterLocation::Optional),
+      extensions()
+    ).prop_map(move |(summary, description, inputs, depends_on, steps, success_actions, failure_actions,
+      outputs, parameters, extensions)| Workflow {
+      workflow_id: workflow_id.clone(),
+      summary,
+      description,
+      inputs,
+      depends_on,
+      steps,
+      success_actions,
+      failure_actions,
+      outputs,
+      parameters,
+      extensions
+    })
+  })
+}
+
+impl Arbitrary for Workflow {
+  type Parameters = ();
+  type Strategy = BoxedStrategy<Self>;
+
+  fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+    identifier().prop_flat_map(|workflow_id| workflow(workflow_id, Names::default())).boxed()
+  }
+}
+
+impl Arbitrary for Components {
+  type Parameters = ();
+  type Strategy = BoxedStrategy<Self>;
+
+  /// Generates components that do not refer to any other objects, so the actions are either `end`
+  /// or `retry` actions
+  fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+    let names = Names::default();
+    (
+      hash_map(identifier(), input_schema(), 0..3),
+      hash_map(identifier(), parameter(ParameterLocation::Optional), 0..3),
+      hash_map(identifier(), success_action(&names), 0..3),
+      hash_map(identifier(), failure_action(&names), 0..3),
+      extensions()
+    ).prop_map(|(inputs, parameters, success_actions, failure_actions, extensions)| Components {
+      inputs,
+      parameters,
+      success_actions,
+      failure_actions,
+      extensions
+    }).boxed()
+  }
+}
+
+impl Arbitrary for ArazzoDescription {
+  type Parameters = ();
+  type Strategy = BoxedStrategy<Self>;
+
+  fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+    (btree_set(identifier(), 1..3), any::<Components>(), btree_set(identifier(), 1..4))
+      .prop_flat_map(|(source_names, components, workflow_ids)| {
+        let workflow_ids = workflow_ids.into_iter().collect::<Vec<_>>();
+        let names = Names {
+          source_descriptions: source_names.iter().cloned().collect(),
+          inputs: components.inputs.keys().cloned().collect(),
+          parameters: components.parameters.keys().cloned().collect(),
+          input_parameters: components.parameters.iter()
+            .filter(|(_, parameter)| parameter.r#in.is_none())
+            .map(|(name, _)| name.clone())
+            .collect(),
+          success_actions: components.success_actions.keys().cloned().collect(),
+          failure_actions: components.failure_actions.keys().cloned().collect(),
+          .. Names::default()
+        };
+        let source_descriptions = source_names.into_iter()
+          .map(source_description)
+          .collect::<Vec<_>>();
+        // Workflows can only refer to the workflows after them, so there are no cycles
+        let workflows = workflow_ids.iter().enumerate()
+          .map(|(index, workflow_id)| {
+            let names = Names { workflows: workflow_ids[index + 1..].to_vec(), .. names.clone() };
+            workflow(workflow_id.clone(), names)
+          })
+          .collect::<Vec<_>>();
+        (
+          select(vec!["1.0.0", "1.0.1"]),
+          any::<Info>(),
+          source_descriptions,
+          workflows,
+          Just(components),
+          extensions()
+        )
+      })
+      .prop_map(|(arazzo, info, source_descriptions, workflows, components, extensions)| ArazzoDescription {
+        arazzo: arazzo.to_string(),
+        info,
+        source_descriptions,
+        workflows,
+        components,
+        extensions
+      })
+      .boxed()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use proptest::prelude::*;
+
+  use crate::v1_0::ArazzoDescription;
+  use crate::validation::validate;
+
+  proptest! {
+    #[test]
+    fn generated_descriptions_are_valid(description in any::<ArazzoDescription>()) {
+      prop_assert_eq!(validate(&description), vec![]);
+    }
+
+    #[test]
+    fn generated_descriptions_round_trip_through_json(description in any::<ArazzoDescription>()) {
+      let json = serde_json::to_value(&description).unwrap();
+      let loaded = ArazzoDescription::try_from(&json).unwrap();
+      prop_assert_eq!(loaded, description);
+    }
+  }
+}
@@ -0,0 +1,342 @@
+//! Credentials for the requests of steps. An [`AuthProvider`] is set on the
+//! [`Executor`](crate::executor::Executor) for a source description or a step, and adds the
+//! credentials to each request just before it is sent, so secrets never need to be embedded in
+//! (or templated into) the Arazzo documents.
+//!
+//! Providers are included for API keys ([`ApiKeyAuth`]), bearer tokens ([`BearerTokenAuth`]) and
+//! the OAuth2 client credentials flow ([`OAuth2ClientCredentials`]). Secrets can be read from
+//! environment variables with the `from_env` functions.
+
+use std::env;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+
+use crate::base64;
+use crate::execution_context::StepRequest;
+use crate::executor::percent_encode;
+use crate::http_config::HttpClientConfig;
+use crate::operations::Operation;
+use crate::v1_0::Step;
+
+/// Adds credentials to the requests of steps
+pub trait AuthProvider {
+  /// Adds the credentials to the request for the step (i.e. by setting a header). The operation
+  /// is the one the step references.
+  fn authenticate(&self, step: &Step, operation: &Operation, request: &mut StepRequest) -> anyhow::Result<()>;
+}
+
+impl <F> AuthProvider for F
+  where F: Fn(&Step, &Operation, &mut StepRequest) -> anyhow::Result<()> {
+  fn authenticate(&self, step: &Step, operation: &Operation, request: &mut StepRequest) -> anyhow::Result<()> {
+    self(step, operation, request)
+  }
+}
+
+fn env_secret(name: &str) -> anyhow::Result<String> {
+  env::var(name).with_context(|| format!("Failed to read the secret from environment variable '{}'", name))
+}
+
+/// Sets the header, replacing any existing values (header names are not case-sensitive)
+fn set_header(request: &mut StepRequest, name: &str, value: String) {
+  request.headers.retain(|(header, _)| !header.eq_ignore_ascii_case(name));
+  request.headers.push((name.to_string(), value));
+}
+
+/// Where an API key is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiKeyLocation {
+  /// In a request header
+  Header,
+  /// In a query parameter
+  Query
+}
+
+/// Sends an API key in a header or query parameter
+#[derive(Clone, PartialEq)]
+pub struct ApiKeyAuth {
+  /// Name of the header or query parameter
+  pub name: String,
+  /// Where the API key is sent
+  pub location: ApiKeyLocation,
+  /// The API key
+  pub key: String
+}
+
+impl ApiKeyAuth {
+  /// Creates a provider that sends the API key in the header
+  pub fn header<N: Into<String>, K: Into<String>>(name: N, key: K) -> Self {
+    ApiKeyAuth { name: name.into(), location: ApiKeyLocation::Header, key: key.into() }
+  }
+
+  /// Creates a provider that sends the API key in the query parameter
+  pub fn query<N: Into<String>, K: Into<String>>(name: N, key: K) -> Self {
+    ApiKeyAuth { name: name.into(), location: ApiKeyLocation::Query, key: key.into() }
+  }
+
+  /// Creates a provider with the API key from the environment variable
+  pub fn from_env<N: Into<String>>(name: N, location: ApiKeyLocation, variable: &str) -> anyhow::Result<Self> {
+    Ok(ApiKeyAuth { name: name.into(), location, key: env_secret(variable)? })
+  }
+}
+
+impl std::fmt::Debug for ApiKeyAuth {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("ApiKeyAuth")
+      .field("name", &self.name)
+      .field("location", &self.location)
+      .finish_non_exhaustive()
+  }
+}
+
+impl AuthProvider for ApiKeyAuth {
+  fn authenticate(&self, _step: &Step, _operation: &Operation, request: &mut StepRequest) -> anyhow::Result<()> {
+    match self.location {
+      ApiKeyLocation::Header => set_header(request, &self.name, self.key.clone()),
+      ApiKeyLocation::Query => {
+        let separator = if request.url.contains('?') { '&' } else { '?' };
+        request.url = format!("{}{}{}={}", request.url, separator, percent_encode(&self.name), percent_encode(&self.key));
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Sends a bearer token in the `Authorization` header
+#[derive(Clone, PartialEq)]
+pub struct BearerTokenAuth {
+  /// The token
+  pub token: String
+}
+
+impl BearerTokenAuth {
+  /// Creates a provider that sends the token
+  pub fn new<T: Into<String>>(token: T) -> Self {
+    BearerTokenAuth { token: token.into() }
+  }
+
+  /// Creates a provider with the token from the environment variable
+  pub fn from_env(variable: &str) -> anyhow::Result<Self> {
+    Ok(BearerTokenAuth { token: env_secret(variable)? })
+  }
+}
+
+impl std::fmt::Debug for BearerTokenAuth {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("BearerTokenAuth").finish_non_exhaustive()
+  }
+}
+
+impl AuthProvider for BearerTokenAuth {
+  fn authenticate(&self, _step: &Step, _operation: &Operation, request: &mut StepRequest) -> anyhow::Result<()> {
+    set_header(request, "Authorization", format!("Bearer {}", self.token));
+    Ok(())
+  }
+}
+
+/// Access token fetched from the token endpoint, and when it needs to be refreshed
+#[derive(Debug, Clone)]
+struct CachedToken {
+  token: String,
+  refresh_at: Option<Instant>
+}
+
+/// Fetches an access token with the OAuth2 client credentials flow, and sends it as a bearer
+/// token. The token is cached, and fetched again when it is about to expire (within the refresh
+/// margin of the `expires_in` returned by the token endpoint). As the client credentials are sent to
+/// the token endpoint, only `https:` token URLs are accepted.
+pub struct OAuth2ClientCredentials {
+  /// URL of the token endpoint
+  pub token_url: String,
+  /// Client ID
+  pub client_id: String,
+  /// Client secret
+  pub client_secret: String,
+  /// Scopes to request
+  pub scopes: Vec<String>,
+  /// How long before the token expires that it is fetched again
+  pub refresh_margin: Duration,
+  /// Timeout for the request to the token endpoint
+  pub timeout: Duration,
+  /// Configuration of the HTTP client for the request to the token endpoint (i.e. a proxy)
+  pub http: HttpClientConfig,
+  token: Mutex<Option<CachedToken>>
+}
+
+impl OAuth2ClientCredentials {
+  /// Creates a provider for the token endpoint and client
+  pub fn new<U: Into<String>, I: Into<String>, S: Into<String>>(token_url: U, client_id: I, client_secret: S) -> Self {
+    OAuth2ClientCredentials {
+      token_url: token_url.into(),
+      client_id: client_id.into(),
+      client_secret: client_secret.into(),
+      scopes: vec![],
+      refresh_margin: Duration::from_secs(30),
+      timeout: Duration::from_secs(30),
+      http: HttpClientConfig::default(),
+      token: Mutex::new(None)
+    }
+  }
+
+  /// Creates a provider with the client ID and secret from the environment variables
+  pub fn from_env<U: Into<String>>(token_url: U, client_id_variable: &str, client_secret_variable: &str) -> anyhow::Result<Self> {
+    Ok(OAuth2ClientCredentials::new(token_url, env_secret(client_id_variable)?, env_secret(client_secret_variable)?))
+  }
+
+  /// Sets the scopes to request
+  pub fn with_scopes<S: Into<String>>(mut self, scopes: impl IntoIterator<Item = S>) -> Self {
+    self.scopes = scopes.into_iter().map(|scope| scope.into()).collect();
+    self
+  }
+
+  /// Returns the cached access token, fetching a new one if there is none or it is about to expire
+  pub fn access_token(&self) -> anyhow::Result<String> {
+    let mut cached = self.lock();
+    if let Some(token) = cached.as_ref()
+      && token.refresh_at.is_none_or(|refresh_at| Instant::now() < refresh_at) {
+      return Ok(token.token.clone());
+    }
+    let token = self.fetch_token()?;
+    let value = token.token.clone();
+    *cached = Some(token);
+    Ok(value)
+  }
+
+  /// Discards the cached access token, so a new one is fetched for the next request
+  pub fn clear_token(&self) {
+    *self.lock() = None;
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Option<CachedToken>> {
+    self.token.lock().unwrap_or_else(|err| err.into_inner())
+  }
+
+  fn fetch_token(&self) -> anyhow::Result<CachedToken> {
+    let token_url = reqwest::Url::parse(&self.token_url)
+      .map_err(|err| anyhow!("The token URL '{}' is not valid: {}", self.token_url, err))?;
+    if token_url.scheme() != "https" {
+      return Err(anyhow!("The token URL '{}' must be a https: URL, as the client credentials are sent to it",
+        self.token_url));
+    }
+    let mut body = "grant_type=client_credentials".to_string();
+    if !self.scopes.is_empty() {
+      body.push_str("&scope=");
+      body.push_str(&percent_encode(&self.scopes.join(" ")));
+    }
+    let credentials = format!("{}:{}", percent_encode(&self.client_id), percent_encode(&self.client_secret));
+    let headers = [
+      ("Authorization".to_string(), format!("Basic {}", base64::encode(credentials.as_bytes()))),
+      ("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string()),
+      ("Accept".to_string(), "application/json".to_string())
+    ];
+    let requested_at = Instant::now();
+    let response = self.http.client()
+      .and_then(|client| crate::http_config::send(&client, "POST", &self.token_url, &headers, Some(body.as_bytes()),
+        self.timeout, self.http.max_response_size()))
+      .with_context(|| format!("Failed to fetch an access token from '{}'", self.token_url))?;
+    if !(200..300).contains(&response.status) {
+      return Err(anyhow!("Failed to fetch an access token from '{}': the token endpoint returned status {}",
+        self.token_url, response.status));
+    }
+    let json: Value = serde_json::from_slice(&response.body)
+      .with_context(|| format!("The response from token endpoint '{}' is not valid JSON", self.token_url))?;
+    let token = json.get("access_token")
+      .and_then(|token| token.as_str())
+      .ok_or_else(|| anyhow!("The response from token endpoint '{}' does not have an 'access_token'", self.token_url))?;
+    let refresh_at = json.get("expires_in")
+      .and_then(|expires_in| expires_in.as_u64())
+      .map(|expires_in| requested_at + Duration::from_secs(expires_in).saturating_sub(self.refresh_margin));
+    Ok(CachedToken { token: token.to_string(), refresh_at })
+  }
+}
+
+impl std::fmt::Debug for OAuth2ClientCredentials {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("OAuth2ClientCredentials")
+      .field("token_url", &self.token_url)
+      .field("client_id", &self.client_id)
+      .field("scopes", &self.scopes)
+      .finish_non_exhaustive()
+  }
+}
+
+impl AuthProvider for OAuth2ClientCredentials {
+  fn authenticate(&self, _step: &Step, _operation: &Operation, request: &mut StepRequest) -> anyhow::Result<()> {
+    set_header(request, "Authorization", format!("Bearer {}", self.access_token()?));
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use crate::auth::{ApiKeyAuth, AuthProvider, BearerTokenAuth, OAuth2ClientCredentials};
+  use crate::execution_context::StepRequest;
+  use crate::http_config::{HttpClientConfig, TlsConfig};
+  use crate::http_config::tests::serve_tls_once;
+  use crate::operations::Operation;
+  use crate::v1_0::Step;
+
+  fn request() -> StepRequest {
+    StepRequest {
+      method: "GET".to_string(),
+      url: "http://localhost/pets?limit=1".to_string(),
+      headers: vec![("authorization".to_string(), "Basic abc".to_string())],
+      body: None
+    }
+  }
+
+  fn operation() -> Operation {
+    Operation {
+      source_name: "petstore".to_string(),
+      operation_id: Some("findPets".to_string()),
+      method: "GET".to_string(),
+      path: "/pets".to_string(),
+      server_url: None
+    }
+  }
+
+  #[test]
+  fn adds_api_keys_and_bearer_tokens() {
+    let mut request = request();
+    ApiKeyAuth::header("X-API-Key", "secret").authenticate(&Step::default(), &operation(), &mut request).unwrap();
+    ApiKeyAuth::query("api key", "a&b").authenticate(&Step::default(), &operation(), &mut request).unwrap();
+    BearerTokenAuth::new("token").authenticate(&Step::default(), &operation(), &mut request).unwrap();
+    expect!(request.url).to(be_equal_to("http://localhost/pets?limit=1&api%20key=a%26b"));
+    expect!(request.headers).to(be_equal_to(vec![
+      ("X-API-Key".to_string(), "secret".to_string()),
+      ("Authorization".to_string(), "Bearer token".to_string())
+    ]));
+    expect!(format!("{:?}", BearerTokenAuth::new("token")).contains("token\"")).to(be_false());
+  }
+
+  #[test]
+  fn fetches_and_caches_client_credentials_tokens() {
+    let (url, ca_bundle) = serve_tls_once("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 43\r\n\r\n{\"access_token\":\"abc123\",\"expires_in\":3600}");
+    let mut provider = OAuth2ClientCredentials::new(url, "client", "secret").with_scopes(["pets:read"]);
+    provider.http = HttpClientConfig {
+      tls: TlsConfig { ca_bundle: Some(ca_bundle.clone()), .. TlsConfig::default() },
+      .. HttpClientConfig::default()
+    };
+    let mut request = request();
+    provider.authenticate(&Step::default(), &operation(), &mut request).unwrap();
+    expect!(request.headers.clone()).to(be_equal_to(vec![("Authorization".to_string(), "Bearer abc123".to_string())]));
+
+    // The server only responds once, so the second token must come from the cache
+    expect!(provider.access_token()).to(be_ok().value("abc123"));
+    provider.clear_token();
+    expect!(provider.access_token()).to(be_err());
+    std::fs::remove_file(ca_bundle).unwrap();
+  }
+
+  #[test]
+  fn only_sends_client_credentials_over_tls() {
+    let provider = OAuth2ClientCredentials::new("http://localhost:8080/token", "client", "secret");
+    expect!(provider.access_token().unwrap_err().to_string()).to(be_equal_to(
+      "The token URL 'http://localhost:8080/token' must be a https: URL, as the client credentials are sent to it"));
+    expect!(OAuth2ClientCredentials::new("not a url", "client", "secret").access_token()).to(be_err());
+  }
+}
@@ -0,0 +1,98 @@
+//! Minimal Base64 encoding and decoding (RFC 4648, standard alphabet with padding)
+
+use anyhow::anyhow;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes the bytes as a Base64 string
+pub fn encode(bytes: &[u8]) -> String {
+  let mut result = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+  for chunk in bytes.chunks(3) {
+    let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+    let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+    for i in 0..4 {
+      if i <= chunk.len() {
+        result.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3F) as usize] as char);
+      } else {
+        result.push('=');
+      }
+    }
+  }
+
+  result
+}
+
+fn decode_char(ch: u8) -> Option<u32> {
+  match ch {
+    b'A'..=b'Z' => Some((ch - b'A') as u32),
+    b'a'..=b'z' => Some((ch - b'a') as u32 + 26),
+    b'0'..=b'9' => Some((ch - b'0') as u32 + 52),
+    b'+' => Some(62),
+    b'/' => Some(63),
+    _ => None
+  }
+}
+
+/// Decodes a Base64 string. Any whitespace (i.e. from line wrapping) is ignored, and the padding
+/// is optional.
+pub fn decode(value: &str) -> anyhow::Result<Vec<u8>> {
+  let chars = value.bytes()
+    .filter(|ch| !ch.is_ascii_whitespace())
+    .collect::<Vec<_>>();
+  let data = match chars.iter().position(|ch| *ch == b'=') {
+    Some(index) => {
+      if chars[index..].iter().any(|ch| *ch != b'=') || chars.len() % 4 != 0 || chars.len() - index > 2 {
+        return Err(anyhow!("Invalid Base64 padding"));
+      }
+      &chars[..index]
+    }
+    None => chars.as_slice()
+  };
+  if data.len() % 4 == 1 {
+    return Err(anyhow!("Invalid Base64 length"));
+  }
+
+  let mut result = Vec::with_capacity(data.len() * 3 / 4);
+  for chunk in data.chunks(4) {
+    let mut n = 0_u32;
+    for (i, ch) in chunk.iter().enumerate() {
+      let value = decode_char(*ch)
+        .ok_or_else(|| anyhow!("Invalid Base64 character '{}'", *ch as char))?;
+      n |= value << (18 - 6 * i);
+    }
+    let bytes = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+    result.extend_from_slice(&bytes[..chunk.len() - 1]);
+  }
+
+  Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use super::{decode, encode};
+
+  #[test]
+  fn base64_encode() {
+    expect!(encode(b"")).to(be_equal_to(""));
+    expect!(encode(b"f")).to(be_equal_to("Zg=="));
+    expect!(encode(b"fo")).to(be_equal_to("Zm8="));
+    expect!(encode(b"foo")).to(be_equal_to("Zm9v"));
+    expect!(encode(b"foobar")).to(be_equal_to("Zm9vYmFy"));
+    expect!(encode(&[0xFF, 0xFE, 0x00])).to(be_equal_to("//4A"));
+  }
+
+  #[test]
+  fn base64_decode() {
+    expect!(decode("").unwrap()).to(be_equal_to(b"".to_vec()));
+    expect!(decode("Zg==").unwrap()).to(be_equal_to(b"f".to_vec()));
+    expect!(decode("Zm8").unwrap()).to(be_equal_to(b"fo".to_vec()));
+    expect!(decode("Zm9v\n  YmFy\n").unwrap()).to(be_equal_to(b"foobar".to_vec()));
+    expect!(decode("//4A").unwrap()).to(be_equal_to(vec![0xFF, 0xFE, 0x00]));
+    expect!(decode("Zm9v!")).to(be_err());
+    expect!(decode("Z")).to(be_err());
+    expect!(decode("Zg=a")).to(be_err());
+  }
+}
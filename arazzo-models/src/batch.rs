@@ -0,0 +1,150 @@
+//! Loading of many Arazzo documents in parallel (requires the `parallel` feature). This is for
+//! registries and monorepos that need to load (and validate) hundreds of workflow files at a time.
+//! The documents are read and parsed on the rayon thread pool.
+//!
+//! ```rust,no_run
+//! use arazzo_models::batch::try_load_all;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let descriptions = try_load_all(&["pets.arazzo.yaml", "orders.arazzo.json"])?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use rayon::prelude::*;
+use serde_json::Value;
+
+use crate::v1_0::ArazzoDescription;
+
+/// Loads an Arazzo description from a JSON or YAML file. Files with a `.json` extension, or
+/// where the document starts with a `{`, are loaded as JSON. All other files are loaded as YAML.
+pub fn load_file<P: AsRef<Path>>(path: P) -> anyhow::Result<ArazzoDescription> {
+  let path = path.as_ref();
+  let contents = fs::read_to_string(path)
+    .with_context(|| format!("Failed to read '{}'", path.display()))?;
+  parse_document(path, &contents)
+    .with_context(|| format!("Failed to load '{}'", path.display()))
+}
+
+fn parse_document(path: &Path, contents: &str) -> anyhow::Result<ArazzoDescription> {
+  let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) ||
+    contents.trim_start().starts_with('{');
+  if is_json {
+    let json: Value = serde_json::from_str(contents)?;
+    ArazzoDescription::try_from(&json)
+  } else {
+    parse_yaml(contents)
+  }
+}
+
+#[cfg(feature = "yaml")]
+fn parse_yaml(contents: &str) -> anyhow::Result<ArazzoDescription> {
+  let documents = crate::yaml::yaml_load_documents(contents)?;
+  let document = documents.first().ok_or_else(|| anyhow!("The document is empty"))?;
+  ArazzoDescription::try_from(document)
+}
+
+#[cfg(not(feature = "yaml"))]
+fn parse_yaml(_contents: &str) -> anyhow::Result<ArazzoDescription> {
+  Err(anyhow!("The document is not a JSON document, and the yaml feature is not enabled"))
+}
+
+/// Loads all the files in parallel. The results are returned in the same order as the paths, and
+/// any errors include the path of the file that failed to load.
+pub fn load_all<P: AsRef<Path> + Sync>(paths: &[P]) -> Vec<anyhow::Result<ArazzoDescription>> {
+  paths.par_iter()
+    .map(load_file)
+    .collect()
+}
+
+/// Loads all the files in parallel, returning the descriptions in the same order as the paths. If
+/// any files fail to load, a single error is returned listing all the failures (not just the
+/// first one).
+pub fn try_load_all<P: AsRef<Path> + Sync>(paths: &[P]) -> anyhow::Result<Vec<ArazzoDescription>> {
+  aggregate_errors(load_all(paths))
+}
+
+/// Returns the descriptions if all the results are Ok, otherwise an error with the messages of all
+/// the errors.
+pub fn aggregate_errors(results: Vec<anyhow::Result<ArazzoDescription>>) -> anyhow::Result<Vec<ArazzoDescription>> {
+  let total = results.len();
+  let (descriptions, errors): (Vec<_>, Vec<_>) = results.into_iter().partition(Result::is_ok);
+  if errors.is_empty() {
+    Ok(descriptions.into_iter().flatten().collect())
+  } else {
+    let mut message = format!("Failed to load {} of {} documents:", errors.len(), total);
+    for err in errors.into_iter().filter_map(Result::err) {
+      let _ = write!(message, "\n  - {:#}", err);
+    }
+    Err(anyhow!(message))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+  use std::path::PathBuf;
+
+  use expectest::prelude::*;
+
+  use crate::batch::{load_all, try_load_all};
+
+  const JSON: &str = r#"{
+    "arazzo": "1.0.1",
+    "info": { "title": "Pets", "version": "1.0.0" },
+    "sourceDescriptions": [ { "name": "petstore", "url": "petstore.json" } ],
+    "workflows": [ { "workflowId": "listPets", "steps": [ { "stepId": "list", "operationId": "listPets" } ] } ]
+  }"#;
+
+  const YAML: &str = "arazzo: 1.0.1
+info:
+  title: Orders
+  version: 1.0.0
+sourceDescriptions:
+  - name: orders
+    url: orders.json
+workflows:
+  - workflowId: listOrders
+    steps:
+      - stepId: list
+        operationId: listOrders
+";
+
+  fn write_file(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("arazzo-batch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, contents).unwrap();
+    path
+  }
+
+  #[test]
+  fn loads_all_the_files_in_order() {
+    let paths = vec![write_file("pets.json", JSON), write_file("orders.yaml", YAML), write_file("pets.txt", JSON)];
+    let descriptions = try_load_all(&paths).unwrap();
+    let titles = descriptions.iter().map(|description| description.info.title.as_str()).collect::<Vec<_>>();
+    expect!(titles).to(be_equal_to(vec!["Pets", "Orders", "Pets"]));
+  }
+
+  #[test]
+  fn reports_all_the_errors() {
+    let invalid = write_file("invalid.yaml", "arazzo: 1.0.1\n");
+    let missing = invalid.with_file_name("missing.json");
+    let paths = vec![write_file("valid.json", JSON), invalid.clone(), missing.clone()];
+
+    let results = load_all(&paths);
+    expect!(results.len()).to(be_equal_to(3));
+    expect!(results[0].as_ref()).to(be_ok());
+    expect!(results[1].as_ref()).to(be_err());
+
+    let message = try_load_all(&paths).unwrap_err().to_string();
+    expect!(message.starts_with("Failed to load 2 of 3 documents:\n")).to(be_true());
+    expect!(message.contains(&format!("Failed to load '{}': Info Object is required", invalid.display()))).to(be_true());
+    expect!(message.contains(&format!("Failed to read '{}'", missing.display()))).to(be_true());
+  }
+}
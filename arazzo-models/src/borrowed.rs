@@ -0,0 +1,966 @@
+//! Borrowed variant of the Arazzo models, loaded from a JSON document without copying the strings
+//! out of it. This is for services that need to inspect large numbers of documents, where the
+//! allocations of the owned models (`v1_0`) add up.
+//!
+//! The models have the same structure and field names as the owned models, with the following
+//! differences:
+//! * Strings are `Cow<'a, str>` values borrowed from the document. Only values that are converted
+//!   to strings (i.e. a number used as a version) are owned.
+//! * Extension values, parameter values, workflow inputs, component inputs and request body
+//!   payloads are references to the JSON values in the document.
+//! * Extension keys, component names and output names are borrowed from the document.
+//!
+//! The documents are checked in the same way the owned models are loaded, and `into_owned`
+//! converts the models to the owned models when needed.
+//!
+//! ```
+//! use arazzo_models::borrowed::ArazzoDescription;
+//! use serde_json::json;
+//!
+//! let json = json!({
+//!   "arazzo": "1.0.1",
+//!   "info": { "title": "Pets", "version": "1.0.0" },
+//!   "sourceDescriptions": [ { "name": "petstore", "url": "petstore.json" } ],
+//!   "workflows": [ { "workflowId": "listPets", "steps": [ { "stepId": "list", "operationId": "listPets" } ] } ]
+//! });
+//! let description = ArazzoDescription::try_from(&json).unwrap();
+//! assert_eq!(description.workflows[0].workflow_id, "listPets");
+//!
+//! let owned = description.into_owned().unwrap();
+//! assert_eq!(owned.workflows[0].steps[0].step_id, "list");
+//! ```
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::anyhow;
+use serde_json::{Map, Value};
+
+use crate::either::Either;
+use crate::extensions::AnyValue;
+use crate::json::{
+  json_object_lookup_integer,
+  json_object_lookup_number,
+  json_type_name,
+  json_value_payload,
+  json_workflows_error
+};
+use crate::payloads::with_schema_hint;
+use crate::v1_0;
+
+/// Extension values, keyed without the `x-` prefix
+pub type Extensions<'a> = HashMap<&'a str, &'a Value>;
+
+/// 4.6.1 Arazzo Description (borrowed)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArazzoDescription<'a> {
+  /// Version number of the Arazzo Specification.
+  pub arazzo: Cow<'a, str>,
+  /// Provides metadata about the workflows contain within the Arazzo Description.
+  pub info: Info<'a>,
+  /// List of source descriptions.
+  pub source_descriptions: Vec<SourceDescription<'a>>,
+  /// List of workflows.
+  pub workflows: Vec<Workflow<'a>>,
+  /// Element to hold various schemas for the Arazzo Description.
+  pub components: Components<'a>,
+  /// Extension values, keyed without the `x-` prefix
+  pub extensions: Extensions<'a>
+}
+
+/// 4.6.2 Info Object (borrowed)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Info<'a> {
+  /// A human readable title of the Arazzo Description.
+  pub title: Cow<'a, str>,
+  /// A short summary of the Arazzo Description.
+  pub summary: Option<Cow<'a, str>>,
+  /// A description of the purpose of the workflows defined.
+  pub description: Option<Cow<'a, str>>,
+  /// The version identifier of the Arazzo document.
+  pub version: Cow<'a, str>,
+  /// Extension values, keyed without the `x-` prefix
+  pub extensions: Extensions<'a>
+}
+
+/// 4.6.3 Source Description Object (borrowed)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceDescription<'a> {
+  /// Unique name for the source description.
+  pub name: Cow<'a, str>,
+  /// URL to a source description to be used by a workflow.
+  pub url: Cow<'a, str>,
+  /// The type of source description.
+  pub r#type: Option<Cow<'a, str>>,
+  /// Extension values, keyed without the `x-` prefix
+  pub extensions: Extensions<'a>
+}
+
+/// 4.6.4 Workflow Object (borrowed)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workflow<'a> {
+  /// Unique string to represent the workflow.
+  pub workflow_id: Cow<'a, str>,
+  /// Summary of the purpose or objective of the workflow.
+  pub summary: Option<Cow<'a, str>>,
+  /// Description of the workflow.
+  pub description: Option<Cow<'a, str>>,
+  /// JSON Schema object representing the input parameters used by this workflow.
+  pub inputs: Option<&'a Value>,
+  /// List of workflows that must be completed before this workflow can be processed.
+  pub depends_on: Vec<Cow<'a, str>>,
+  /// Ordered list of steps.
+  pub steps: Vec<Step<'a>>,
+  /// List of success actions that are applicable for all steps described under this workflow.
+  pub success_actions: Vec<Either<SuccessObject<'a>, ReusableObject<'a>>>,
+  /// List of failure actions that are applicable for all steps described under this workflow.
+  pub failure_actions: Vec<Either<FailureObject<'a>, ReusableObject<'a>>>,
+  /// Map between a friendly name and a dynamic output value.
+  pub outputs: BTreeMap<&'a str, &'a str>,
+  /// List of parameters that are applicable for all steps described under this workflow.
+  pub parameters: Vec<Either<ParameterObject<'a>, ReusableObject<'a>>>,
+  /// Extension values, keyed without the `x-` prefix
+  pub extensions: Extensions<'a>
+}
+
+/// 4.6.5 Step Object (borrowed)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step<'a> {
+  /// Unique string to represent the step.
+  pub step_id: Cow<'a, str>,
+  /// The name of an existing, resolvable operation, as defined with a unique operationId and
+  /// existing within one of the sourceDescriptions.
+  pub operation_id: Option<Cow<'a, str>>,
+  /// A reference to a Source Description Object combined with a JSON Pointer to reference an operation.
+  pub operation_path: Option<Cow<'a, str>>,
+  /// The workflowId referencing an existing workflow within the Arazzo Description.
+  pub workflow_id: Option<Cow<'a, str>>,
+  /// A description of the step.
+  pub description: Option<Cow<'a, str>>,
+  /// List of parameters that must be passed to an operation or workflow as referenced by
+  /// operationId, operationPath, or workflowId.
+  pub parameters: Vec<Either<ParameterObject<'a>, ReusableObject<'a>>>,
+  /// The request body to pass to an operation as referenced by operationId or operationPath.
+  pub request_body: Option<RequestBody<'a>>,
+  /// List of assertions to determine the success of the step.
+  pub success_criteria: Vec<Criterion<'a>>,
+  /// Array of success action objects that specify what to do upon step success.
+  pub on_success: Vec<Either<SuccessObject<'a>, ReusableObject<'a>>>,
+  /// Array of failure action objects that specify what to do upon step failure.
+  pub on_failure: Vec<Either<FailureObject<'a>, ReusableObject<'a>>>,
+  /// Map between a friendly name and a dynamic output value.
+  pub outputs: BTreeMap<&'a str, &'a str>,
+  /// Extension values, keyed without the `x-` prefix
+  pub extensions: Extensions<'a>
+}
+
+/// 4.6.6 Parameter Object (borrowed)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterObject<'a> {
+  /// The name of the parameter.
+  pub name: Cow<'a, str>,
+  /// The location of the parameter.
+  pub r#in: Option<Cow<'a, str>>,
+  /// Value to pass in the parameter, or a runtime expression.
+  pub value: Either<&'a Value, &'a str>,
+  /// Extension values, keyed without the `x-` prefix
+  pub extensions: Extensions<'a>
+}
+
+/// 4.6.7 Success Action Object (borrowed)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuccessObject<'a> {
+  /// The name of the success action.
+  pub name: Cow<'a, str>,
+  /// The type of action to take.
+  pub r#type: Cow<'a, str>,
+  /// The workflowId referencing an existing workflow to transfer to upon success of the step.
+  pub workflow_id: Option<Cow<'a, str>>,
+  /// The stepId to transfer to upon success of the step.
+  pub step_id: Option<Cow<'a, str>>,
+  /// List of assertions to determine if this action shall be executed.
+  pub criteria: Vec<Criterion<'a>>,
+  /// Extension values, keyed without the `x-` prefix
+  pub extensions: Extensions<'a>
+}
+
+/// 4.6.8 Failure Action Object (borrowed)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailureObject<'a> {
+  /// The name of the failure action.
+  pub name: Cow<'a, str>,
+  /// The type of action to take.
+  pub r#type: Cow<'a, str>,
+  /// The workflowId referencing an existing workflow to transfer to upon failure of the step.
+  pub workflow_id: Option<Cow<'a, str>>,
+  /// The stepId to transfer to upon failure of the step.
+  pub step_id: Option<Cow<'a, str>>,
+  /// A non-negative decimal indicating the seconds to delay after the step failure before another
+  /// attempt shall be made.
+  pub retry_after: Option<f64>,
+  /// A non-negative integer indicating how many attempts to retry the step may be attempted.
+  pub retry_limit: Option<i64>,
+  /// List of assertions to determine if this action shall be executed.
+  pub criteria: Vec<Criterion<'a>>,
+  /// Extension values, keyed without the `x-` prefix
+  pub extensions: Extensions<'a>
+}
+
+/// 4.6.9 Components Object (borrowed)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Components<'a> {
+  /// Object to hold reusable JSON Schema objects to be referenced from workflow inputs.
+  pub inputs: HashMap<&'a str, &'a Value>,
+  /// Object to hold reusable Parameter Objects
+  pub parameters: HashMap<&'a str, ParameterObject<'a>>,
+  /// Object to hold reusable Success Actions Objects.
+  pub success_actions: HashMap<&'a str, SuccessObject<'a>>,
+  /// Object to hold reusable Failure Actions Objects.
+  pub failure_actions: HashMap<&'a str, FailureObject<'a>>,
+  /// Extension values, keyed without the `x-` prefix
+  pub extensions: Extensions<'a>
+}
+
+/// 4.6.10 Reusable Object (borrowed)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReusableObject<'a> {
+  /// Runtime Expression used to reference the desired object.
+  pub reference: Cow<'a, str>,
+  /// Sets a value of the referenced parameter.
+  pub value: Option<Cow<'a, str>>
+}
+
+/// 4.6.11 Criterion Object (borrowed)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Criterion<'a> {
+  /// Runtime Expression used to set the context for the condition to be applied on.
+  pub context: Option<Cow<'a, str>>,
+  /// The condition to apply.
+  pub condition: Cow<'a, str>,
+  /// The type of condition to be applied.
+  pub r#type: Option<Either<Cow<'a, str>, CriterionExpressionType<'a>>>,
+  /// Extension values, keyed without the `x-` prefix
+  pub extensions: Extensions<'a>
+}
+
+/// 4.6.12 Criterion Expression Type Object (borrowed)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CriterionExpressionType<'a> {
+  /// The type of condition to be applied.
+  pub r#type: Cow<'a, str>,
+  /// A short hand string representing the version of the expression type being used.
+  pub version: Cow<'a, str>,
+  /// Extension values, keyed without the `x-` prefix
+  pub extensions: Extensions<'a>
+}
+
+/// 4.6.13 Request Body Object (borrowed)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestBody<'a> {
+  /// The Content-Type for the request content.
+  pub content_type: Option<Cow<'a, str>>,
+  /// A value representing the request body payload.
+  pub payload: Option<&'a Value>,
+  /// A list of locations and values to set within a payload.
+  pub replacements: Vec<PayloadReplacement<'a>>,
+  /// Extension values, keyed without the `x-` prefix
+  pub extensions: Extensions<'a>
+}
+
+/// 4.6.14 Payload Replacement Object (borrowed)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PayloadReplacement<'a> {
+  /// A JSON Pointer or XPath Expression which MUST be resolved against the request body.
+  pub target: Cow<'a, str>,
+  /// The value set within the target location, or a runtime expression.
+  pub value: Either<&'a Value, &'a str>,
+  /// Extension values, keyed without the `x-` prefix
+  pub extensions: Extensions<'a>
+}
+
+impl<'a> TryFrom<&'a Value> for ArazzoDescription<'a> {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+    let map = object(value)?;
+    let arazzo = require_string(map, "arazzo")
+      .map_err(|_| anyhow!("Arazzo version number is required [4.6.1.1 Fixed Fields]"))?;
+    let info = map.get("info")
+      .ok_or_else(|| anyhow!("Info Object is required [4.6.1.1 Fixed Fields]"))
+      .and_then(Info::try_from)?;
+
+    let source_descriptions = match map.get("sourceDescriptions").and_then(|value| value.as_array()) {
+      Some(array) if array.is_empty() => return Err(anyhow!("Source Description list must have at least one entry [4.6.1.1 Fixed Fields]")),
+      Some(array) => array.iter().map(SourceDescription::try_from).collect::<anyhow::Result<Vec<_>>>()?,
+      None => return Err(anyhow!("Source Description Object is required [4.6.1.1 Fixed Fields]"))
+    };
+    let workflows = match map.get("workflows") {
+      Some(Value::Array(array)) if array.is_empty() => return Err(anyhow!("Workflows list must have at least one entry [4.6.1.1 Fixed Fields]")),
+      Some(Value::Array(array)) => array.iter().map(Workflow::try_from).collect::<anyhow::Result<Vec<_>>>()?,
+      workflows => return Err(json_workflows_error(workflows))
+    };
+    let components = map.get("components")
+      .map(Components::try_from)
+      .transpose()?
+      .unwrap_or_default();
+
+    Ok(ArazzoDescription {
+      arazzo,
+      info,
+      source_descriptions,
+      workflows,
+      components,
+      extensions: extensions(map)
+    })
+  }
+}
+
+impl<'a> TryFrom<&'a Value> for Info<'a> {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+    let map = object(value)?;
+    Ok(Info {
+      title: require_string(map, "title")?,
+      summary: lookup_string(map, "summary"),
+      description: lookup_string(map, "description"),
+      version: require_string(map, "version")?,
+      extensions: extensions(map)
+    })
+  }
+}
+
+impl<'a> TryFrom<&'a Value> for SourceDescription<'a> {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+    let map = object(value)?;
+    Ok(SourceDescription {
+      name: require_string(map, "name")?,
+      url: require_string(map, "url")?,
+      r#type: lookup_string(map, "type"),
+      extensions: extensions(map)
+    })
+  }
+}
+
+impl<'a> TryFrom<&'a Value> for Workflow<'a> {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+    let map = object(value)?;
+    let workflow_id = require_string(map, "workflowId")?;
+    let steps = match map.get("steps").and_then(|value| value.as_array()) {
+      Some(array) if !array.is_empty() => array.iter().map(Step::try_from).collect::<anyhow::Result<Vec<_>>>()?,
+      _ => return Err(anyhow!("At lest one Step is required [4.6.4.1 Fixed Fields]"))
+    };
+    Ok(Workflow {
+      workflow_id,
+      summary: lookup_string(map, "summary"),
+      description: lookup_string(map, "description"),
+      inputs: map.get("inputs"),
+      depends_on: map.get("dependsOn")
+        .and_then(|value| value.as_array())
+        .map(|array| array.iter().filter_map(scalar_string).collect())
+        .unwrap_or_default(),
+      steps,
+      success_actions: load_reusable_list(map, "successActions", SuccessObject::try_from)?,
+      failure_actions: load_reusable_list(map, "failureActions", FailureObject::try_from)?,
+      outputs: outputs(map),
+      parameters: load_reusable_list(map, "parameters", ParameterObject::try_from)?,
+      extensions: extensions(map)
+    })
+  }
+}
+
+impl<'a> TryFrom<&'a Value> for Step<'a> {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+    let map = object(value)?;
+    Ok(Step {
+      step_id: require_string(map, "stepId")?,
+      operation_id: lookup_string(map, "operationId"),
+      operation_path: lookup_string(map, "operationPath"),
+      workflow_id: lookup_string(map, "workflowId"),
+      description: lookup_string(map, "description"),
+      parameters: load_reusable_list(map, "parameters", ParameterObject::try_from)?,
+      request_body: map.get("requestBody").map(RequestBody::try_from).transpose()?,
+      on_success: load_reusable_list(map, "onSuccess", SuccessObject::try_from)?,
+      success_criteria: load_list(map, "successCriteria", Criterion::try_from)?,
+      on_failure: load_reusable_list(map, "onFailure", FailureObject::try_from)?,
+      outputs: outputs(map),
+      extensions: extensions(map)
+    })
+  }
+}
+
+impl<'a> TryFrom<&'a Value> for ParameterObject<'a> {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+    let map = object(value)?;
+    Ok(ParameterObject {
+      name: require_string(map, "name")?,
+      r#in: lookup_string(map, "in"),
+      value: any_or_expression(map, "value")?,
+      extensions: extensions(map)
+    })
+  }
+}
+
+impl<'a> TryFrom<&'a Value> for SuccessObject<'a> {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+    let map = object(value)?;
+    Ok(SuccessObject {
+      name: require_string(map, "name")?,
+      r#type: require_string(map, "type")?,
+      workflow_id: lookup_string(map, "workflowId"),
+      step_id: lookup_string(map, "stepId"),
+      criteria: load_list(map, "criteria", Criterion::try_from)?,
+      extensions: extensions(map)
+    })
+  }
+}
+
+impl<'a> TryFrom<&'a Value> for FailureObject<'a> {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+    let map = object(value)?;
+    Ok(FailureObject {
+      name: require_string(map, "name")?,
+      r#type: require_string(map, "type")?,
+      workflow_id: lookup_string(map, "workflowId"),
+      step_id: lookup_string(map, "stepId"),
+      retry_after: json_object_lookup_number(map, "retryAfter"),
+      retry_limit: json_object_lookup_integer(map, "retryLimit"),
+      criteria: load_list(map, "criteria", Criterion::try_from)?,
+      extensions: extensions(map)
+    })
+  }
+}
+
+impl<'a> TryFrom<&'a Value> for Components<'a> {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+    let Some(map) = value.as_object() else {
+      return Ok(Components::default());
+    };
+    Ok(Components {
+      inputs: load_map(map, "inputs", Ok)?,
+      parameters: load_map(map, "parameters", ParameterObject::try_from)?,
+      success_actions: load_map(map, "successActions", SuccessObject::try_from)?,
+      failure_actions: load_map(map, "failureActions", FailureObject::try_from)?,
+      extensions: extensions(map)
+    })
+  }
+}
+
+impl<'a> TryFrom<&'a Value> for ReusableObject<'a> {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+    let map = object(value)?;
+    Ok(ReusableObject {
+      reference: require_string(map, "reference")
+        .map_err(|_| anyhow!("Reference is required [4.6.10.1 Fixed Fields]"))?,
+      value: lookup_string(map, "value")
+    })
+  }
+}
+
+impl<'a> TryFrom<&'a Value> for Criterion<'a> {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+    let map = object(value)?;
+    let r#type = map.get("type")
+      .map(|value| match value.as_str() {
+        Some(s) => Ok(Either::First(Cow::Borrowed(s))),
+        None => CriterionExpressionType::try_from(value).map(Either::Second)
+      })
+      .transpose()?;
+    Ok(Criterion {
+      context: lookup_string(map, "context"),
+      condition: require_string(map, "condition")?,
+      r#type,
+      extensions: extensions(map)
+    })
+  }
+}
+
+impl<'a> TryFrom<&'a Value> for CriterionExpressionType<'a> {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+    let map = object(value)?;
+    Ok(CriterionExpressionType {
+      r#type: require_string(map, "type")?,
+      version: require_string(map, "version")?,
+      extensions: extensions(map)
+    })
+  }
+}
+
+impl<'a> TryFrom<&'a Value> for RequestBody<'a> {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+    let map = object(value)?;
+    Ok(RequestBody {
+      content_type: lookup_string(map, "contentType"),
+      payload: map.get("payload"),
+      replacements: load_list(map, "replacements", PayloadReplacement::try_from)?,
+      extensions: extensions(map)
+    })
+  }
+}
+
+impl<'a> TryFrom<&'a Value> for PayloadReplacement<'a> {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+    let map = object(value)?;
+    Ok(PayloadReplacement {
+      target: require_string(map, "target")?,
+      value: any_or_expression(map, "value")?,
+      extensions: extensions(map)
+    })
+  }
+}
+
+fn object(value: &Value) -> anyhow::Result<&Map<String, Value>> {
+  value.as_object().ok_or_else(|| anyhow!("JSON value must be an Object, got {:?}", value))
+}
+
+/// Returns the string value, or the string form of a number or boolean value
+fn scalar_string(value: &Value) -> Option<Cow<'_, str>> {
+  match value {
+    Value::Bool(b) => Some(Cow::Owned(b.to_string())),
+    Value::Number(n) => Some(Cow::Owned(n.to_string())),
+    Value::String(s) => Some(Cow::Borrowed(s.as_str())),
+    _ => None
+  }
+}
+
+fn lookup_string<'a>(map: &'a Map<String, Value>, key: &str) -> Option<Cow<'a, str>> {
+  map.get(key).and_then(scalar_string)
+}
+
+fn require_string<'a>(map: &'a Map<String, Value>, key: &str) -> anyhow::Result<Cow<'a, str>> {
+  match map.get(key) {
+    Some(Value::String(s)) => Ok(Cow::Borrowed(s.as_str())),
+    Some(value) => Err(anyhow!("Value for key '{}' in Object was not a string, was {}", key, json_type_name(value))),
+    None => Err(anyhow!("Did not find key '{}' in Object", key))
+  }
+}
+
+fn any_or_expression<'a>(map: &'a Map<String, Value>, key: &str) -> anyhow::Result<Either<&'a Value, &'a str>> {
+  match map.get(key) {
+    Some(Value::String(s)) if s.starts_with('$') => Ok(Either::Second(s.as_str())),
+    Some(value) => Ok(Either::First(value)),
+    None => Err(anyhow!("Parameter value is required [4.6.6.1 Fixed Fields]"))
+  }
+}
+
+fn extensions(map: &Map<String, Value>) -> Extensions<'_> {
+  map.iter()
+    .filter_map(|(key, value)| key.strip_prefix("x-").map(|key| (key, value)))
+    .collect()
+}
+
+fn outputs(map: &Map<String, Value>) -> BTreeMap<&str, &str> {
+  map.get("outputs")
+    .and_then(|value| value.as_object())
+    .map(|outputs| outputs.iter()
+      .filter_map(|(key, value)| value.as_str().map(|value| (key.as_str(), value)))
+      .collect())
+    .unwrap_or_default()
+}
+
+fn load_list<'a, T>(
+  map: &'a Map<String, Value>,
+  key: &str,
+  load: impl Fn(&'a Value) -> anyhow::Result<T>
+) -> anyhow::Result<Vec<T>> {
+  map.get(key)
+    .and_then(|value| value.as_array())
+    .map(|array| array.iter().map(load).collect())
+    .unwrap_or_else(|| Ok(vec![]))
+}
+
+/// Loads a list of objects or Reusable Objects (objects with a `reference` key). Values that are
+/// not objects are ignored.
+fn load_reusable_list<'a, T>(
+  map: &'a Map<String, Value>,
+  key: &str,
+  load: impl Fn(&'a Value) -> anyhow::Result<T>
+) -> anyhow::Result<Vec<Either<T, ReusableObject<'a>>>>
+  where T: std::fmt::Debug + Clone + PartialEq {
+  let Some(array) = map.get(key).and_then(|value| value.as_array()) else {
+    return Ok(vec![]);
+  };
+  let mut list = Vec::with_capacity(array.len());
+  for item in array {
+    if let Some(object) = item.as_object() {
+      if object.contains_key("reference") {
+        list.push(Either::Second(ReusableObject::try_from(item)?));
+      } else {
+        list.push(Either::First(load(item)?));
+      }
+    }
+  }
+  Ok(list)
+}
+
+fn load_map<'a, T>(
+  map: &'a Map<String, Value>,
+  key: &str,
+  load: impl Fn(&'a Value) -> anyhow::Result<T>
+) -> anyhow::Result<HashMap<&'a str, T>> {
+  map.get(key)
+    .and_then(|value| value.as_object())
+    .map(|object| object.iter()
+      .map(|(key, value)| load(value).map(|value| (key.as_str(), value)))
+      .collect())
+    .unwrap_or_else(|| Ok(HashMap::new()))
+}
+
+fn owned_extensions(extensions: &Extensions<'_>) -> anyhow::Result<HashMap<String, AnyValue>> {
+  extensions.iter()
+    .map(|(key, value)| AnyValue::try_from(*value).map(|value| (key.to_string(), value)))
+    .collect()
+}
+
+fn owned_any_or_expression(value: &Either<&Value, &str>) -> anyhow::Result<Either<AnyValue, String>> {
+  match value {
+    Either::First(value) => AnyValue::try_from(*value).map(Either::First),
+    Either::Second(expression) => Ok(Either::Second(expression.to_string()))
+  }
+}
+
+fn owned_string(value: &Option<Cow<'_, str>>) -> Option<String> {
+  value.as_ref().map(|value| value.to_string())
+}
+
+fn owned_reusable<T, O>(
+  list: Vec<Either<T, ReusableObject<'_>>>,
+  into_owned: impl Fn(T) -> anyhow::Result<O>
+) -> anyhow::Result<Vec<Either<O, v1_0::ReusableObject>>>
+  where T: std::fmt::Debug + Clone + PartialEq,
+        O: std::fmt::Debug + Clone + PartialEq {
+  list.into_iter()
+    .map(|item| match item {
+      Either::First(item) => into_owned(item).map(Either::First),
+      Either::Second(reusable) => Ok(Either::Second(reusable.into_owned()))
+    })
+    .collect()
+}
+
+fn owned_criteria(criteria: Vec<Criterion<'_>>) -> anyhow::Result<Vec<v1_0::Criterion>> {
+  criteria.into_iter().map(Criterion::into_owned).collect()
+}
+
+impl ArazzoDescription<'_> {
+  /// Converts this description to the owned models
+  pub fn into_owned(self) -> anyhow::Result<v1_0::ArazzoDescription> {
+    Ok(v1_0::ArazzoDescription {
+      arazzo: self.arazzo.into_owned(),
+      info: self.info.into_owned()?,
+      source_descriptions: self.source_descriptions.into_iter()
+        .map(SourceDescription::into_owned)
+        .collect::<anyhow::Result<_>>()?,
+      workflows: self.workflows.into_iter()
+        .map(Workflow::into_owned)
+        .collect::<anyhow::Result<_>>()?,
+      components: self.components.into_owned()?,
+      extensions: owned_extensions(&self.extensions)?
+    })
+  }
+}
+
+impl Info<'_> {
+  /// Converts this Info Object to the owned model
+  pub fn into_owned(self) -> anyhow::Result<v1_0::Info> {
+    Ok(v1_0::Info {
+      title: self.title.into_owned(),
+      summary: owned_string(&self.summary),
+      description: owned_string(&self.description),
+      version: self.version.into_owned(),
+      extensions: owned_extensions(&self.extensions)?
+    })
+  }
+}
+
+impl SourceDescription<'_> {
+  /// Converts this Source Description Object to the owned model
+  pub fn into_owned(self) -> anyhow::Result<v1_0::SourceDescription> {
+    Ok(v1_0::SourceDescription {
+      name: self.name.into_owned(),
+      url: self.url.into_owned(),
+      r#type: owned_string(&self.r#type),
+      extensions: owned_extensions(&self.extensions)?
+    })
+  }
+}
+
+impl Workflow<'_> {
+  /// Converts this Workflow Object to the owned model
+  pub fn into_owned(self) -> anyhow::Result<v1_0::Workflow> {
+    Ok(v1_0::Workflow {
+      workflow_id: self.workflow_id.into_owned(),
+      summary: owned_string(&self.summary),
+      description: owned_string(&self.description),
+      inputs: self.inputs.cloned().unwrap_or_default(),
+      depends_on: self.depends_on.into_iter().map(Cow::into_owned).collect(),
+      steps: self.steps.into_iter().map(Step::into_owned).collect::<anyhow::Result<_>>()?,
+      success_actions: owned_reusable(self.success_actions, SuccessObject::into_owned)?,
+      failure_actions: owned_reusable(self.failure_actions, FailureObject::into_owned)?,
+      outputs: self.outputs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+      parameters: owned_reusable(self.parameters, ParameterObject::into_owned)?,
+      extensions: owned_extensions(&self.extensions)?
+    })
+  }
+}
+
+impl Step<'_> {
+  /// Converts this Step Object to the owned model
+  pub fn into_owned(self) -> anyhow::Result<v1_0::Step> {
+    Ok(v1_0::Step {
+      step_id: self.step_id.into_owned(),
+      operation_id: owned_string(&self.operation_id),
+      operation_path: owned_string(&self.operation_path),
+      workflow_id: owned_string(&self.workflow_id),
+      description: owned_string(&self.description),
+      parameters: owned_reusable(self.parameters, ParameterObject::into_owned)?,
+      request_body: self.request_body.map(RequestBody::into_owned).transpose()?,
+      success_criteria: owned_criteria(self.success_criteria)?,
+      on_success: owned_reusable(self.on_success, SuccessObject::into_owned)?,
+      on_failure: owned_reusable(self.on_failure, FailureObject::into_owned)?,
+      outputs: self.outputs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+      extensions: owned_extensions(&self.extensions)?
+    })
+  }
+}
+
+impl ParameterObject<'_> {
+  /// Converts this Parameter Object to the owned model
+  pub fn into_owned(self) -> anyhow::Result<v1_0::ParameterObject> {
+    Ok(v1_0::ParameterObject {
+      name: self.name.into_owned(),
+      r#in: owned_string(&self.r#in),
+      value: owned_any_or_expression(&self.value)?,
+      extensions: owned_extensions(&self.extensions)?
+    })
+  }
+}
+
+impl SuccessObject<'_> {
+  /// Converts this Success Action Object to the owned model
+  pub fn into_owned(self) -> anyhow::Result<v1_0::SuccessObject> {
+    Ok(v1_0::SuccessObject {
+      name: self.name.into_owned(),
+      r#type: self.r#type.into_owned(),
+      workflow_id: owned_string(&self.workflow_id),
+      step_id: owned_string(&self.step_id),
+      criteria: owned_criteria(self.criteria)?,
+      extensions: owned_extensions(&self.extensions)?
+    })
+  }
+}
+
+impl FailureObject<'_> {
+  /// Converts this Failure Action Object to the owned model
+  pub fn into_owned(self) -> anyhow::Result<v1_0::FailureObject> {
+    Ok(v1_0::FailureObject {
+      name: self.name.into_owned(),
+      r#type: self.r#type.into_owned(),
+      workflow_id: owned_string(&self.workflow_id),
+      step_id: owned_string(&self.step_id),
+      retry_after: self.retry_after,
+      retry_limit: self.retry_limit,
+      criteria: owned_criteria(self.criteria)?,
+      extensions: owned_extensions(&self.extensions)?
+    })
+  }
+}
+
+impl Components<'_> {
+  /// Converts this Components Object to the owned model
+  pub fn into_owned(self) -> anyhow::Result<v1_0::Components> {
+    Ok(v1_0::Components {
+      inputs: self.inputs.iter().map(|(key, value)| (key.to_string(), (*value).clone())).collect(),
+      parameters: self.parameters.into_iter()
+        .map(|(key, value)| value.into_owned().map(|value| (key.to_string(), value)))
+        .collect::<anyhow::Result<_>>()?,
+      success_actions: self.success_actions.into_iter()
+        .map(|(key, value)| value.into_owned().map(|value| (key.to_string(), value)))
+        .collect::<anyhow::Result<_>>()?,
+      failure_actions: self.failure_actions.into_iter()
+        .map(|(key, value)| value.into_owned().map(|value| (key.to_string(), value)))
+        .collect::<anyhow::Result<_>>()?,
+      extensions: owned_extensions(&self.extensions)?
+    })
+  }
+}
+
+impl ReusableObject<'_> {
+  /// Converts this Reusable Object to the owned model
+  pub fn into_owned(self) -> v1_0::ReusableObject {
+    v1_0::ReusableObject {
+      reference: self.reference.into_owned(),
+      value: owned_string(&self.value)
+    }
+  }
+}
+
+impl Criterion<'_> {
+  /// Converts this Criterion Object to the owned model
+  pub fn into_owned(self) -> anyhow::Result<v1_0::Criterion> {
+    Ok(v1_0::Criterion {
+      context: owned_string(&self.context),
+      condition: self.condition.into_owned(),
+      r#type: match self.r#type {
+        Some(Either::First(r#type)) => Some(Either::First(r#type.into_owned())),
+        Some(Either::Second(r#type)) => Some(Either::Second(r#type.into_owned()?)),
+        None => None
+      },
+      extensions: owned_extensions(&self.extensions)?
+    })
+  }
+}
+
+impl CriterionExpressionType<'_> {
+  /// Converts this Criterion Expression Type Object to the owned model
+  pub fn into_owned(self) -> anyhow::Result<v1_0::CriterionExpressionType> {
+    Ok(v1_0::CriterionExpressionType {
+      r#type: self.r#type.into_owned(),
+      version: self.version.into_owned(),
+      extensions: owned_extensions(&self.extensions)?
+    })
+  }
+}
+
+impl RequestBody<'_> {
+  /// Converts this Request Body Object to the owned model. The payload is created in the same way
+  /// as the JSON loader does, based on the content type.
+  pub fn into_owned(self) -> anyhow::Result<v1_0::RequestBody> {
+    let content_type = owned_string(&self.content_type);
+    let extensions = owned_extensions(&self.extensions)?;
+    let payload = self.payload
+      .map(|payload| json_value_payload(payload, content_type.as_deref()))
+      .transpose()?
+      .map(|payload| with_schema_hint(payload, content_type.as_deref(), &extensions));
+    Ok(v1_0::RequestBody {
+      content_type,
+      payload,
+      replacements: self.replacements.into_iter()
+        .map(PayloadReplacement::into_owned)
+        .collect::<anyhow::Result<_>>()?,
+      extensions
+    })
+  }
+}
+
+impl PayloadReplacement<'_> {
+  /// Converts this Payload Replacement Object to the owned model
+  pub fn into_owned(self) -> anyhow::Result<v1_0::PayloadReplacement> {
+    Ok(v1_0::PayloadReplacement {
+      target: self.target.into_owned(),
+      value: owned_any_or_expression(&self.value)?,
+      extensions: owned_extensions(&self.extensions)?
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::borrow::Cow;
+
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::borrowed::ArazzoDescription;
+  use crate::either::Either;
+  use crate::v1_0;
+
+  fn document() -> serde_json::Value {
+    json!({
+      "arazzo": "1.0.1",
+      "info": { "title": "Pets", "version": "1.0.0", "x-owner": { "team": "pets" } },
+      "sourceDescriptions": [ { "name": "petstore", "url": "petstore.json" } ],
+      "workflows": [
+        {
+          "workflowId": "addPet",
+          "inputs": { "type": "object" },
+          "steps": [
+            {
+              "stepId": "add",
+              "operationId": "addPet",
+              "parameters": [
+                { "name": "store", "in": "header", "value": "$inputs.store" },
+                { "reference": "$components.parameters.page", "value": 2 }
+              ],
+              "requestBody": { "contentType": "application/json", "payload": { "name": "Rex" } },
+              "successCriteria": [ { "condition": "$statusCode == 201" } ],
+              "onFailure": [ { "name": "retry", "type": "retry", "retryAfter": 1, "retryLimit": 3 } ],
+              "outputs": { "id": "$response.body#/id" }
+            }
+          ]
+        }
+      ],
+      "components": {
+        "parameters": { "page": { "name": "page", "in": "query", "value": 1 } }
+      }
+    })
+  }
+
+  #[test]
+  fn borrows_strings_from_the_document() {
+    let json = document();
+    let description = ArazzoDescription::try_from(&json).unwrap();
+
+    expect!(matches!(description.info.title, Cow::Borrowed("Pets"))).to(be_true());
+    expect!(description.info.extensions.get("owner").copied()).to(be_some().value(&json["info"]["x-owner"]));
+
+    let step = &description.workflows[0].steps[0];
+    expect!(matches!(step.step_id, Cow::Borrowed("add"))).to(be_true());
+    expect!(step.parameters[0].first().map(|parameter| &parameter.value)).to(be_some().value(&Either::Second("$inputs.store")));
+    let value = step.parameters[1].second().and_then(|reusable| reusable.value.as_ref());
+    expect!(matches!(value, Some(Cow::Owned(value)) if value == "2")).to(be_true());
+    expect!(step.outputs.get("id")).to(be_some().value(&"$response.body#/id"));
+    expect!(step.request_body.as_ref().and_then(|body| body.payload)).to(be_some().value(&json!({ "name": "Rex" })));
+  }
+
+  #[test]
+  fn converts_to_the_owned_models() {
+    let json = document();
+    let description = ArazzoDescription::try_from(&json).unwrap();
+    let owned = v1_0::ArazzoDescription::try_from(&json).unwrap();
+    expect!(description.into_owned().unwrap()).to(be_equal_to(owned));
+  }
+
+  #[test]
+  fn fails_in_the_same_way_as_the_owned_models() {
+    let documents = [
+      json!("test"),
+      json!({}),
+      json!({ "arazzo": "1.0.1", "info": { "title": "Pets", "version": "1" } }),
+      json!({ "arazzo": "1.0.1", "info": { "title": "Pets", "version": "1" }, "sourceDescriptions": [] }),
+      json!({
+        "arazzo": "1.0.1",
+        "info": { "title": "Pets", "version": "1" },
+        "sourceDescriptions": [ { "name": "petstore", "url": "petstore.json" } ],
+        "workflows": [ { "workflowId": "test", "steps": [] } ]
+      })
+    ];
+    for json in &documents {
+      let error = ArazzoDescription::try_from(json).unwrap_err().to_string();
+      expect!(error).to(be_equal_to(v1_0::ArazzoDescription::try_from(json).unwrap_err().to_string()));
+    }
+  }
+}
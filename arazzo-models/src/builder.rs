@@ -0,0 +1,674 @@
+//! Fluent builders for constructing Arazzo descriptions in code.
+//!
+//! ```
+//! use arazzo_models::builder::ArazzoBuilder;
+//!
+//! let description = ArazzoBuilder::new()
+//!   .info("Pet Store", "1.0.0")
+//!   .source("petStore", "https://petstore.swagger.io/v2/swagger.json")
+//!   .workflow("findPet", |workflow| workflow
+//!     .step("find", |step| step
+//!       .operation_id("$sourceDescriptions.petStore.findPetsByStatus")
+//!       .parameter("status", "query", "available")
+//!       .success_criterion("$statusCode == 200")
+//!       .output("pets", "$response.body")))
+//!   .build()
+//!   .unwrap();
+//! assert_eq!(description.workflows[0].steps[0].step_id, "find");
+//! ```
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::either::Either;
+use crate::extensions::{AnyValue, Extensible};
+use crate::payloads::JsonPayload;
+use crate::v1_0::{
+  ArazzoDescription,
+  Criterion,
+  FailureObject,
+  Info,
+  ParameterObject,
+  RequestBody,
+  ReusableObject,
+  SourceDescription,
+  Step,
+  SuccessObject,
+  Workflow
+};
+
+/// Marker for a required part of an [`ArazzoBuilder`] that has not been set yet
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Missing;
+
+/// Marker for a required part of an [`ArazzoBuilder`] that has been set
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Provided;
+
+/// Builder for a whole Arazzo description. The required parts are the Info Object
+/// ([`info`](ArazzoBuilder::info)), at least one source description ([`source`](ArazzoBuilder::source))
+/// and at least one workflow ([`workflow`](ArazzoBuilder::workflow)). The type parameters track
+/// which of them have been set (as [`Missing`] or [`Provided`]), so [`build`](ArazzoBuilder::build)
+/// can only be called once all of them have been. The description is validated when it is built.
+///
+/// ```compile_fail,E0599
+/// use arazzo_models::builder::ArazzoBuilder;
+///
+/// // No source description has been added
+/// let description = ArazzoBuilder::new()
+///   .info("Pet Store", "1.0.0")
+///   .workflow("findPet", |workflow| workflow)
+///   .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ArazzoBuilder<I = Missing, S = Missing, W = Missing> {
+  description: ArazzoDescription,
+  state: PhantomData<(I, S, W)>
+}
+
+impl Default for ArazzoBuilder {
+  fn default() -> Self {
+    ArazzoBuilder {
+      description: ArazzoDescription::default(),
+      state: PhantomData
+    }
+  }
+}
+
+impl ArazzoBuilder {
+  /// Creates a new builder for the latest version of the specification
+  pub fn new() -> Self {
+    ArazzoBuilder::default()
+  }
+}
+
+impl <I, S, W> ArazzoBuilder<I, S, W> {
+  fn with_state<I2, S2, W2>(self) -> ArazzoBuilder<I2, S2, W2> {
+    ArazzoBuilder {
+      description: self.description,
+      state: PhantomData
+    }
+  }
+
+  /// Sets the required title and version of the description
+  pub fn info<T: Into<String>, V: Into<String>>(mut self, title: T, version: V) -> ArazzoBuilder<Provided, S, W> {
+    self.description.info.title = title.into();
+    self.description.info.version = version.into();
+    self.with_state()
+  }
+
+  /// Replaces the whole Info Object
+  pub fn info_object(mut self, info: Info) -> ArazzoBuilder<Provided, S, W> {
+    self.description.info = info;
+    self.with_state()
+  }
+
+  /// Sets the summary of the description
+  pub fn summary<D: Into<String>>(mut self, summary: D) -> Self {
+    self.description.info.summary = Some(summary.into());
+    self
+  }
+
+  /// Sets the description of the description
+  pub fn description<D: Into<String>>(mut self, description: D) -> Self {
+    self.description.info.description = Some(description.into());
+    self
+  }
+
+  /// Adds an OpenAPI source description
+  pub fn source<N: Into<String>, U: Into<String>>(mut self, name: N, url: U) -> ArazzoBuilder<I, Provided, W> {
+    self.description.source_descriptions.push(SourceDescription {
+      name: name.into(),
+      url: url.into(),
+      r#type: Some("openapi".to_string()),
+      extensions: Default::default()
+    });
+    self.with_state()
+  }
+
+  /// Adds a source description
+  pub fn source_description(mut self, source: SourceDescription) -> ArazzoBuilder<I, Provided, W> {
+    self.description.source_descriptions.push(source);
+    self.with_state()
+  }
+
+  /// Adds a workflow with the given ID, using the callback to configure it
+  pub fn workflow<N, F>(mut self, workflow_id: N, callback: F) -> ArazzoBuilder<I, S, Provided>
+    where N: Into<String>,
+          F: FnOnce(WorkflowBuilder) -> WorkflowBuilder {
+    self.description.workflows.push(callback(WorkflowBuilder::new(workflow_id)).build());
+    self.with_state()
+  }
+
+  /// Adds an already constructed workflow
+  pub fn add_workflow(mut self, workflow: Workflow) -> ArazzoBuilder<I, S, Provided> {
+    self.description.workflows.push(workflow);
+    self.with_state()
+  }
+
+  /// Adds a reusable JSON Schema for workflow inputs
+  pub fn component_input<N: Into<String>>(mut self, name: N, schema: Value) -> Self {
+    self.description.components.inputs.insert(name.into(), schema);
+    self
+  }
+
+  /// Adds a reusable parameter
+  pub fn component_parameter<N: Into<String>>(mut self, name: N, parameter: ParameterObject) -> Self {
+    self.description.components.parameters.insert(name.into(), parameter);
+    self
+  }
+
+  /// Adds a reusable success action
+  pub fn component_success_action<N: Into<String>>(mut self, name: N, action: SuccessObject) -> Self {
+    self.description.components.success_actions.insert(name.into(), action);
+    self
+  }
+
+  /// Adds a reusable failure action
+  pub fn component_failure_action<N: Into<String>>(mut self, name: N, action: FailureObject) -> Self {
+    self.description.components.failure_actions.insert(name.into(), action);
+    self
+  }
+
+  /// Adds an extension value. The `x-` prefix is optional.
+  pub fn extension<K: AsRef<str>, V: Into<AnyValue>>(mut self, key: K, value: V) -> Self {
+    self.description.set_extension(key, value);
+    self
+  }
+
+  /// Returns the description without validating it. This can be called before the required parts
+  /// have been set, i.e. to create incomplete descriptions for tests.
+  pub fn build_unchecked(self) -> ArazzoDescription {
+    self.description
+  }
+}
+
+impl ArazzoBuilder<Provided, Provided, Provided> {
+  /// Validates and returns the description
+  pub fn build(self) -> anyhow::Result<ArazzoDescription> {
+    self.description.validate()?;
+    Ok(self.description)
+  }
+}
+
+fn parameter_value(value: AnyValue) -> Either<AnyValue, String> {
+  match value {
+    AnyValue::String(s) if s.starts_with('$') => Either::Second(s),
+    _ => Either::First(value)
+  }
+}
+
+/// Builder for a Workflow Object
+#[derive(Debug, Clone)]
+pub struct WorkflowBuilder {
+  workflow: Workflow
+}
+
+impl WorkflowBuilder {
+  /// Creates a builder for a workflow with the given ID
+  pub fn new<S: Into<String>>(workflow_id: S) -> Self {
+    WorkflowBuilder {
+      workflow: Workflow {
+        workflow_id: workflow_id.into(),
+        .. Workflow::default()
+      }
+    }
+  }
+
+  /// Sets the summary of the workflow
+  pub fn summary<S: Into<String>>(mut self, summary: S) -> Self {
+    self.workflow.summary = Some(summary.into());
+    self
+  }
+
+  /// Sets the description of the workflow
+  pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+    self.workflow.description = Some(description.into());
+    self
+  }
+
+  /// Sets the JSON Schema for the inputs of the workflow
+  pub fn inputs(mut self, schema: Value) -> Self {
+    self.workflow.inputs = schema;
+    self
+  }
+
+  /// Adds a workflow that must be completed before this one
+  pub fn depends_on<S: Into<String>>(mut self, workflow_id: S) -> Self {
+    self.workflow.depends_on.push(workflow_id.into());
+    self
+  }
+
+  /// Adds a step with the given ID, using the callback to configure it
+  pub fn step<S, F>(mut self, step_id: S, callback: F) -> Self
+    where S: Into<String>,
+          F: FnOnce(StepBuilder) -> StepBuilder {
+    self.workflow.steps.push(callback(StepBuilder::new(step_id)).build());
+    self
+  }
+
+  /// Adds an already constructed step
+  pub fn add_step(mut self, step: Step) -> Self {
+    self.workflow.steps.push(step);
+    self
+  }
+
+  /// Adds a parameter that applies to all the steps. Values that start with `$` are treated as
+  /// runtime expressions.
+  pub fn parameter<N, L, V>(mut self, name: N, location: L, value: V) -> Self
+    where N: Into<String>,
+          L: Into<String>,
+          V: Into<AnyValue> {
+    self.workflow.parameters.push(Either::First(ParameterObject {
+      name: name.into(),
+      r#in: Some(location.into()),
+      value: parameter_value(value.into()),
+      extensions: Default::default()
+    }));
+    self
+  }
+
+  /// Adds a reference to a reusable parameter from the components
+  pub fn reusable_parameter<S: Into<String>>(mut self, name: S) -> Self {
+    self.workflow.parameters.push(Either::Second(ReusableObject {
+      reference: format!("$components.parameters.{}", name.into()),
+      value: None
+    }));
+    self
+  }
+
+  /// Adds a success action that applies to all the steps
+  pub fn success_action(mut self, action: SuccessObject) -> Self {
+    self.workflow.success_actions.push(Either::First(action));
+    self
+  }
+
+  /// Adds a failure action that applies to all the steps
+  pub fn failure_action(mut self, action: FailureObject) -> Self {
+    self.workflow.failure_actions.push(Either::First(action));
+    self
+  }
+
+  /// Adds a reference to a reusable success action from the components that applies to all the steps
+  pub fn reusable_success_action<S: Into<String>>(mut self, name: S) -> Self {
+    self.workflow.success_actions.push(Either::Second(ReusableObject {
+      reference: format!("$components.successActions.{}", name.into()),
+      value: None
+    }));
+    self
+  }
+
+  /// Adds a reference to a reusable failure action from the components that applies to all the steps
+  pub fn reusable_failure_action<S: Into<String>>(mut self, name: S) -> Self {
+    self.workflow.failure_actions.push(Either::Second(ReusableObject {
+      reference: format!("$components.failureActions.{}", name.into()),
+      value: None
+    }));
+    self
+  }
+
+  /// Adds an output of the workflow
+  pub fn output<N: Into<String>, E: Into<String>>(mut self, name: N, expression: E) -> Self {
+    self.workflow.outputs.insert(name.into(), expression.into());
+    self
+  }
+
+  /// Adds an extension value. The `x-` prefix is optional.
+  pub fn extension<K: AsRef<str>, V: Into<AnyValue>>(mut self, key: K, value: V) -> Self {
+    self.workflow.set_extension(key, value);
+    self
+  }
+
+  /// Returns the workflow
+  pub fn build(self) -> Workflow {
+    self.workflow
+  }
+}
+
+/// Builder for a Step Object
+#[derive(Debug, Clone)]
+pub struct StepBuilder {
+  step: Step
+}
+
+impl StepBuilder {
+  /// Creates a builder for a step with the given ID
+  pub fn new<S: Into<String>>(step_id: S) -> Self {
+    StepBuilder {
+      step: Step {
+        step_id: step_id.into(),
+        .. Step::default()
+      }
+    }
+  }
+
+  /// Sets the operation the step invokes by its operation ID
+  pub fn operation_id<S: Into<String>>(mut self, operation_id: S) -> Self {
+    self.step.operation_id = Some(operation_id.into());
+    self
+  }
+
+  /// Sets the operation the step invokes by a reference to the operation
+  pub fn operation_path<S: Into<String>>(mut self, operation_path: S) -> Self {
+    self.step.operation_path = Some(operation_path.into());
+    self
+  }
+
+  /// Sets the workflow the step invokes
+  pub fn workflow_id<S: Into<String>>(mut self, workflow_id: S) -> Self {
+    self.step.workflow_id = Some(workflow_id.into());
+    self
+  }
+
+  /// Sets the description of the step
+  pub fn description<S: Into<String>>(mut self, description: S) -> Self {
+    self.step.description = Some(description.into());
+    self
+  }
+
+  /// Adds a parameter to pass to the operation. Values that start with `$` are treated as runtime
+  /// expressions.
+  pub fn parameter<N, L, V>(mut self, name: N, location: L, value: V) -> Self
+    where N: Into<String>,
+          L: Into<String>,
+          V: Into<AnyValue> {
+    self.step.parameters.push(Either::First(ParameterObject {
+      name: name.into(),
+      r#in: Some(location.into()),
+      value: parameter_value(value.into()),
+      extensions: Default::default()
+    }));
+    self
+  }
+
+  /// Adds an input to pass to a workflow. Values that start with `$` are treated as runtime
+  /// expressions.
+  pub fn input<N: Into<String>, V: Into<AnyValue>>(mut self, name: N, value: V) -> Self {
+    self.step.parameters.push(Either::First(ParameterObject {
+      name: name.into(),
+      r#in: None,
+      value: parameter_value(value.into()),
+      extensions: Default::default()
+    }));
+    self
+  }
+
+  /// Adds a reference to a reusable parameter from the components
+  pub fn reusable_parameter<S: Into<String>>(mut self, name: S) -> Self {
+    self.step.parameters.push(Either::Second(ReusableObject {
+      reference: format!("$components.parameters.{}", name.into()),
+      value: None
+    }));
+    self
+  }
+
+  /// Adds a reference to a reusable parameter from the components, with a value that replaces the
+  /// value of the referenced parameter
+  pub fn reusable_parameter_with_value<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+    self.step.parameters.push(Either::Second(ReusableObject {
+      reference: format!("$components.parameters.{}", name.into()),
+      value: Some(value.into())
+    }));
+    self
+  }
+
+  /// Sets the request body to pass to the operation
+  pub fn request_body(mut self, body: RequestBody) -> Self {
+    self.step.request_body = Some(body);
+    self
+  }
+
+  /// Sets the request body to pass to the operation to a JSON payload
+  pub fn json_body(mut self, payload: Value) -> Self {
+    self.step.request_body = Some(RequestBody {
+      content_type: None,
+      payload: Some(Arc::new(JsonPayload(payload))),
+      replacements: vec![],
+      extensions: Default::default()
+    });
+    self
+  }
+
+  /// Adds a simple condition that must be true for the step to be successful
+  pub fn success_criterion<S: Into<String>>(mut self, condition: S) -> Self {
+    self.step.success_criteria.push(Criterion {
+      condition: condition.into(),
+      .. Criterion::default()
+    });
+    self
+  }
+
+  /// Adds a condition of the given type (i.e. `regex` or `jsonpath`), applied to the value of the
+  /// context expression, that must be true for the step to be successful
+  pub fn typed_success_criterion<T, C, S>(mut self, criterion_type: T, context: C, condition: S) -> Self
+    where T: Into<String>,
+          C: Into<String>,
+          S: Into<String> {
+    self.step.success_criteria.push(Criterion {
+      context: Some(context.into()),
+      condition: condition.into(),
+      r#type: Some(Either::First(criterion_type.into())),
+      extensions: Default::default()
+    });
+    self
+  }
+
+  /// Adds a criterion that must be met for the step to be successful
+  pub fn add_success_criterion(mut self, criterion: Criterion) -> Self {
+    self.step.success_criteria.push(criterion);
+    self
+  }
+
+  /// Adds an action to take when the step succeeds
+  pub fn on_success(mut self, action: SuccessObject) -> Self {
+    self.step.on_success.push(Either::First(action));
+    self
+  }
+
+  /// Adds a reference to a reusable success action from the components
+  pub fn reusable_on_success<S: Into<String>>(mut self, name: S) -> Self {
+    self.step.on_success.push(Either::Second(ReusableObject {
+      reference: format!("$components.successActions.{}", name.into()),
+      value: None
+    }));
+    self
+  }
+
+  /// Adds an action to take when the step fails
+  pub fn on_failure(mut self, action: FailureObject) -> Self {
+    self.step.on_failure.push(Either::First(action));
+    self
+  }
+
+  /// Adds a reference to a reusable failure action from the components
+  pub fn reusable_on_failure<S: Into<String>>(mut self, name: S) -> Self {
+    self.step.on_failure.push(Either::Second(ReusableObject {
+      reference: format!("$components.failureActions.{}", name.into()),
+      value: None
+    }));
+    self
+  }
+
+  /// Adds an output of the step
+  pub fn output<N: Into<String>, E: Into<String>>(mut self, name: N, expression: E) -> Self {
+    self.step.outputs.insert(name.into(), expression.into());
+    self
+  }
+
+  /// Adds an extension value. The `x-` prefix is optional.
+  pub fn extension<K: AsRef<str>, V: Into<AnyValue>>(mut self, key: K, value: V) -> Self {
+    self.step.set_extension(key, value);
+    self
+  }
+
+  /// Returns the step
+  pub fn build(self) -> Step {
+    self.step
+  }
+}
+
+/// Constructs an [`ArazzoDescription`] using a compact DSL, intended for test fixtures. Each
+/// statement is a call to a method of the [`ArazzoBuilder`], [`WorkflowBuilder`] or [`StepBuilder`],
+/// either as `method(args...);`, `method: value;` or `method(args...) { ... }` for methods that take
+/// a callback to configure a nested workflow or step. The description is not validated.
+///
+/// ```
+/// use arazzo_models::arazzo;
+///
+/// let description = arazzo! {
+///   info("Pet Store", "1.0.0");
+///   source("petStore", "https://petstore.swagger.io/v2/swagger.json");
+///   workflow("findPet") {
+///     summary: "Find available pets";
+///     step("find") {
+///       operation_id: "$sourceDescriptions.petStore.findPetsByStatus";
+///       parameter("status", "query", "available");
+///       success_criterion: "$statusCode == 200";
+///       output("pets", "$response.body");
+///     }
+///     output("pets", "$steps.find.outputs.pets");
+///   }
+/// };
+/// assert_eq!(description.workflows[0].steps[0].step_id, "find");
+/// ```
+#[macro_export]
+macro_rules! arazzo {
+  ($($body:tt)*) => {
+    $crate::__arazzo_calls!($crate::builder::ArazzoBuilder::new(); $($body)*).build_unchecked()
+  };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __arazzo_calls {
+  ($builder:expr;) => { $builder };
+  ($builder:expr; $method:ident ( $($args:expr),+ ) { $($inner:tt)* } $($rest:tt)*) => {
+    $crate::__arazzo_calls!($builder.$method($($args),+, |b| $crate::__arazzo_calls!(b; $($inner)*)); $($rest)*)
+  };
+  ($builder:expr; $method:ident ( $($args:expr),* ) ; $($rest:tt)*) => {
+    $crate::__arazzo_calls!($builder.$method($($args),*); $($rest)*)
+  };
+  ($builder:expr; $method:ident : $value:expr ; $($rest:tt)*) => {
+    $crate::__arazzo_calls!($builder.$method($value); $($rest)*)
+  };
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use serde_json::json;
+
+  use crate::builder::ArazzoBuilder;
+  use crate::either::Either;
+  use crate::extensions::AnyValue;
+  use crate::payloads::JsonPayload;
+  use crate::v1_0::Criterion;
+
+  #[test]
+  fn builds_a_description() {
+    let description = ArazzoBuilder::new()
+      .info("Test", "1.0.0")
+      .source("api", "http://api")
+      .extension("x-owner", "team")
+      .workflow("login", |wf| wf
+        .parameter("Authorization", "header", "$inputs.token")
+        .step("one", |step| step
+          .operation_id("$sourceDescriptions.api.login")
+          .parameter("page", "query", 1_i64)
+          .output("token", "$response.body#/token"))
+        .output("token", "$steps.one.outputs.token"))
+      .build()
+      .unwrap();
+
+    expect!(description.info.title.as_str()).to(be_equal_to("Test"));
+    expect!(description.extensions.get("owner")).to(be_some().value(&AnyValue::String("team".to_string())));
+    let workflow = &description.workflows[0];
+    expect!(workflow.parameters[0].first().unwrap().value.clone()).to(be_equal_to(Either::Second("$inputs.token".to_string())));
+    expect!(workflow.steps[0].parameters[0].first().unwrap().value.clone()).to(be_equal_to(Either::First(AnyValue::Integer(1))));
+  }
+
+  #[test]
+  fn builds_steps_with_typed_criteria_json_bodies_and_reusable_objects() {
+    let description = ArazzoBuilder::new()
+      .workflow("login", |wf| wf
+        .reusable_success_action("done")
+        .reusable_failure_action("retry")
+        .step("one", |step| step
+          .reusable_parameter_with_value("page", "2")
+          .typed_success_criterion("regex", "$response.header.Content-Type", "^application/json")
+          .json_body(json!({ "user": "$inputs.user" }))))
+      .build_unchecked();
+
+    let workflow = &description.workflows[0];
+    expect!(workflow.success_actions[0].second().map(|action| action.reference.as_str()))
+      .to(be_some().value("$components.successActions.done"));
+    expect!(workflow.failure_actions[0].second().map(|action| action.reference.as_str()))
+      .to(be_some().value("$components.failureActions.retry"));
+    let step = &workflow.steps[0];
+    expect!(step.parameters[0].second().unwrap().value.as_deref()).to(be_some().value("2"));
+    expect!(step.success_criteria[0].clone()).to(be_equal_to(Criterion {
+      context: Some("$response.header.Content-Type".to_string()),
+      condition: "^application/json".to_string(),
+      r#type: Some(Either::First("regex".to_string())),
+      extensions: Default::default()
+    }));
+    let body = step.request_body.as_ref().unwrap();
+    expect!(body.payload_as::<JsonPayload>().map(|payload| payload.0.clone())).to(be_some().value(json!({ "user": "$inputs.user" })));
+  }
+
+  #[test]
+  fn arazzo_macro() {
+    let description = crate::arazzo! {
+      info("Test", "1.0.0");
+      source("api", "http://api");
+      workflow("login") {
+        depends_on: "other";
+        step("one") {
+          operation_id: "$sourceDescriptions.api.login";
+          parameter("page", "query", 1_i64);
+        }
+        step("two") {
+          workflow_id: "other";
+          input("token", "$steps.one.outputs.token");
+        }
+      }
+    };
+
+    let expected = ArazzoBuilder::new()
+      .info("Test", "1.0.0")
+      .source("api", "http://api")
+      .workflow("login", |wf| wf
+        .depends_on("other")
+        .step("one", |step| step
+          .operation_id("$sourceDescriptions.api.login")
+          .parameter("page", "query", 1_i64))
+        .step("two", |step| step
+          .workflow_id("other")
+          .input("token", "$steps.one.outputs.token")))
+      .build_unchecked();
+    expect!(description).to(be_equal_to(expected));
+  }
+
+  #[test]
+  fn build_validates_the_description() {
+    let result = ArazzoBuilder::new()
+      .info("Test", "1.0.0")
+      .source("api", "http://api")
+      .workflow("login", |wf| wf.step("one", |step| step.workflow_id("missing")))
+      .build();
+    expect!(result.unwrap_err().to_string()).to(be_equal_to(
+      "Arazzo description is not valid: /workflows/0/steps/0/workflowId: Workflow 'missing' does not exist"));
+  }
+
+  #[test]
+  fn build_unchecked_does_not_require_the_required_parts() {
+    let description = ArazzoBuilder::new()
+      .workflow("login", |wf| wf.step("one", |step| step.workflow_id("missing")))
+      .build_unchecked();
+    expect!(description.source_descriptions.is_empty()).to(be_true());
+    expect!(description.validate()).to(be_err());
+  }
+}
@@ -0,0 +1,297 @@
+//! Support for bundling an Arazzo description into a single self-contained document. References
+//! to components are replaced with the referenced objects, and (with the `execute` feature) the
+//! OpenAPI source descriptions can be embedded in the description as `data:` URLs.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Debug;
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::either::Either;
+use crate::extensions::AnyValue;
+use crate::v1_0::{ArazzoDescription, ParameterObject, ReusableObject};
+
+/// Maximum depth of nested component input references that are inlined
+const MAX_INPUT_DEPTH: usize = 32;
+
+impl ArazzoDescription {
+  /// Returns a copy of this description with all the references to components replaced with the
+  /// referenced objects, and the inlined components removed. The `value` of a reusable parameter
+  /// reference overrides the value of the component parameter.
+  ///
+  /// Component inputs that refer to themselves (i.e. recursive schemas) can not be inlined, so
+  /// they are kept along with any references to them. Returns an error if a reference is to a
+  /// component that does not exist.
+  pub fn inline_components(&self) -> anyhow::Result<ArazzoDescription> {
+    let mut description = self.clone();
+    let components = &self.components;
+    let mut kept_inputs = BTreeSet::new();
+
+    for workflow in &mut description.workflows {
+      inline_input_refs(&mut workflow.inputs, &components.inputs, &mut vec![], &mut kept_inputs)?;
+      for parameter in &mut workflow.parameters {
+        inline_parameter(parameter, &components.parameters)?;
+      }
+      for action in &mut workflow.success_actions {
+        inline_action(action, "$components.successActions.", &components.success_actions)?;
+      }
+      for action in &mut workflow.failure_actions {
+        inline_action(action, "$components.failureActions.", &components.failure_actions)?;
+      }
+      for step in &mut workflow.steps {
+        for parameter in &mut step.parameters {
+          inline_parameter(parameter, &components.parameters)?;
+        }
+        for action in &mut step.on_success {
+          inline_action(action, "$components.successActions.", &components.success_actions)?;
+        }
+        for action in &mut step.on_failure {
+          inline_action(action, "$components.failureActions.", &components.failure_actions)?;
+        }
+      }
+    }
+
+    // Recursive inputs keep their references, so anything they refer to must also be kept
+    let mut pending = kept_inputs.iter().cloned().collect::<Vec<_>>();
+    while let Some(name) = pending.pop() {
+      if let Some(schema) = components.inputs.get(&name) {
+        for reference in input_refs(schema) {
+          if kept_inputs.insert(reference.clone()) {
+            pending.push(reference);
+          }
+        }
+      }
+    }
+
+    description.components.inputs.retain(|name, _| kept_inputs.contains(name));
+    description.components.parameters.clear();
+    description.components.success_actions.clear();
+    description.components.failure_actions.clear();
+    Ok(description)
+  }
+}
+
+fn inline_parameter(
+  parameter: &mut Either<ParameterObject, ReusableObject>,
+  parameters: &HashMap<String, ParameterObject>
+) -> anyhow::Result<()> {
+  if let Either::Second(reusable) = parameter {
+    let name = reusable.reference.strip_prefix("$components.parameters.")
+      .ok_or_else(|| anyhow!("'{}' is not a reference to a component parameter", reusable.reference))?;
+    let mut inlined = parameters.get(name)
+      .ok_or_else(|| anyhow!("No component parameter with name '{}' was found", name))?
+      .clone();
+    if let Some(value) = &reusable.value {
+      inlined.value = if value.starts_with('$') {
+        Either::Second(value.clone())
+      } else {
+        Either::First(AnyValue::String(value.clone()))
+      };
+    }
+    *parameter = Either::First(inlined);
+  }
+  Ok(())
+}
+
+fn inline_action<A: Debug + Clone + PartialEq>(
+  action: &mut Either<A, ReusableObject>,
+  prefix: &str,
+  actions: &HashMap<String, A>
+) -> anyhow::Result<()> {
+  if let Either::Second(reusable) = action {
+    let name = reusable.reference.strip_prefix(prefix)
+      .ok_or_else(|| anyhow!("'{}' is not a reference to a component action", reusable.reference))?;
+    let inlined = actions.get(name)
+      .ok_or_else(|| anyhow!("No component action with name '{}' was found", name))?
+      .clone();
+    *action = Either::First(inlined);
+  }
+  Ok(())
+}
+
+/// Replaces the JSON Schema references to component inputs (`#/components/inputs/<name>`) with
+/// the referenced schemas. References that are recursive are left as is, and the names of the
+/// inputs they refer to are added to the kept set.
+fn inline_input_refs(
+  json: &mut Value,
+  inputs: &HashMap<String, Value>,
+  stack: &mut Vec<String>,
+  kept: &mut BTreeSet<String>
+) -> anyhow::Result<()> {
+  match json {
+    Value::Object(map) => {
+      let name = map.get("$ref")
+        .and_then(|reference| reference.as_str())
+        .and_then(|reference| reference.strip_prefix("#/components/inputs/"))
+        .map(|name| name.to_string());
+      if let Some(name) = name {
+        if stack.contains(&name) || stack.len() >= MAX_INPUT_DEPTH {
+          kept.insert(name);
+          return Ok(());
+        }
+        let mut schema = inputs.get(&name)
+          .ok_or_else(|| anyhow!("No component input with name '{}' was found", name))?
+          .clone();
+        stack.push(name);
+        inline_input_refs(&mut schema, inputs, stack, kept)?;
+        stack.pop();
+
+        // Any keywords next to the reference are kept with the inlined schema
+        map.remove("$ref");
+        if map.is_empty() {
+          *json = schema;
+        } else if let Value::Object(schema) = schema {
+          for (key, value) in schema {
+            map.entry(key).or_insert(value);
+          }
+        }
+      } else {
+        for value in map.values_mut() {
+          inline_input_refs(value, inputs, stack, kept)?;
+        }
+      }
+    }
+    Value::Array(array) => for item in array {
+      inline_input_refs(item, inputs, stack, kept)?;
+    },
+    _ => {}
+  }
+  Ok(())
+}
+
+/// Returns the names of all the component inputs the schema refers to
+fn input_refs(json: &Value) -> Vec<String> {
+  match json {
+    Value::Object(map) => map.iter()
+      .flat_map(|(key, value)| match (key.as_str(), value) {
+        ("$ref", Value::String(reference)) => reference.strip_prefix("#/components/inputs/")
+          .map(|name| vec![name.to_string()])
+          .unwrap_or_default(),
+        _ => input_refs(value)
+      })
+      .collect(),
+    Value::Array(array) => array.iter().flat_map(input_refs).collect(),
+    _ => vec![]
+  }
+}
+
+#[cfg(feature = "execute")]
+impl ArazzoDescription {
+  /// Embeds the OpenAPI documents loaded by the resolver in the description, replacing the URLs
+  /// of the source descriptions with `data:` URLs containing the documents as JSON. Source
+  /// descriptions the resolver does not have a document for are left as is. Returns the number of
+  /// source descriptions that were embedded.
+  pub fn embed_sources(&mut self, resolver: &crate::operations::OperationResolver) -> usize {
+    let mut embedded = 0;
+    for source in &mut self.source_descriptions {
+      if let Some(document) = resolver.sources.iter().find(|document| document.name == source.name) {
+        source.url = document.to_data_url();
+        embedded += 1;
+      }
+    }
+    embedded
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::arazzo;
+  use crate::either::Either;
+  use crate::extensions::AnyValue;
+  use crate::v1_0::*;
+
+  fn done() -> SuccessObject {
+    SuccessObject {
+      name: "done".to_string(),
+      r#type: "end".to_string(),
+      workflow_id: None,
+      step_id: None,
+      criteria: vec![],
+      extensions: Default::default()
+    }
+  }
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      info("Test", "1.0.0");
+      source("api", "http://api");
+      workflow("test") {
+        inputs: json!({ "$ref": "#/components/inputs/user" });
+        reusable_success_action: "done";
+        step("one") {
+          operation_id: "getOne";
+          reusable_parameter_with_value("page", "2");
+        }
+      }
+      component_input("user", json!({
+        "type": "object",
+        "properties": { "address": { "$ref": "#/components/inputs/address" } }
+      }));
+      component_input("address", json!({ "type": "string" }));
+      component_parameter("page", ParameterObject {
+        name: "page".to_string(),
+        r#in: Some("query".to_string()),
+        value: Either::First(AnyValue::UInteger(1)),
+        .. ParameterObject::default()
+      });
+      component_success_action("done", done());
+    }
+  }
+
+  #[test]
+  fn inline_components() {
+    let bundled = description().inline_components().unwrap();
+
+    let workflow = &bundled.workflows[0];
+    expect!(&workflow.inputs).to(be_equal_to(&json!({
+      "type": "object",
+      "properties": { "address": { "type": "string" } }
+    })));
+    expect!(&workflow.success_actions).to(be_equal_to(&vec![
+      Either::First(done())
+    ]));
+    expect!(&workflow.steps[0].parameters).to(be_equal_to(&vec![
+      Either::First(ParameterObject {
+        name: "page".to_string(),
+        r#in: Some("query".to_string()),
+        value: Either::First(AnyValue::String("2".to_string())),
+        .. ParameterObject::default()
+      })
+    ]));
+    expect!(bundled.components.is_empty()).to(be_true());
+  }
+
+  #[test]
+  fn inline_components_keeps_recursive_inputs() {
+    let mut description = description();
+    description.components.inputs.insert("address".to_string(), json!({
+      "type": "object",
+      "properties": { "next": { "$ref": "#/components/inputs/address" } }
+    }));
+
+    let bundled = description.inline_components().unwrap();
+    expect!(&bundled.workflows[0].inputs).to(be_equal_to(&json!({
+      "type": "object",
+      "properties": {
+        "address": {
+          "type": "object",
+          "properties": { "next": { "$ref": "#/components/inputs/address" } }
+        }
+      }
+    })));
+    let mut kept = bundled.components.inputs.keys().collect::<Vec<_>>();
+    kept.sort();
+    expect!(kept).to(be_equal_to(vec!["address"]));
+  }
+
+  #[test]
+  fn inline_components_fails_for_missing_components() {
+    let mut description = description();
+    description.components.parameters.clear();
+    expect!(description.inline_components()).to(be_err());
+  }
+}
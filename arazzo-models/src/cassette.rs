@@ -0,0 +1,382 @@
+//! Record and replay of step requests (like VCR), so workflow runs can be reproduced offline and
+//! in CI without the APIs. The [`RecordingExecutor`] wraps another step executor and writes every
+//! request and response to a cassette file, and the [`ReplayExecutor`] serves the responses back
+//! from the cassette.
+//!
+//! Cassettes are JSON files with the interactions in the order they were recorded:
+//!
+//! ```json
+//! {
+//!   "interactions": [
+//!     {
+//!       "stepId": "findPets",
+//!       "request": { "method": "GET", "url": "http://localhost/pets", "headers": [] },
+//!       "response": { "status": 200, "headers": [["Content-Type", "application/json"]], "body": "[]" }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Bodies that are not valid UTF-8 are written in base64, with `"bodyEncoding": "base64"`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use bytes::Bytes;
+use serde_json::{json, Map, Value};
+
+use crate::base64;
+use crate::execution_context::{ExecutionContext, StepRequest, StepResponse};
+use crate::executor::StepExecutor;
+use crate::operations::Operation;
+use crate::v1_0::Step;
+
+/// Request and response recorded for a step
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interaction {
+  /// ID of the step
+  pub step_id: String,
+  /// Request that was sent
+  pub request: StepRequest,
+  /// Response that was received
+  pub response: StepResponse
+}
+
+/// Recorded interactions
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Cassette {
+  /// Interactions, in the order they were recorded
+  pub interactions: Vec<Interaction>
+}
+
+impl Cassette {
+  /// Loads a cassette from a file
+  pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+    let path = path.as_ref();
+    let contents = fs::read(path)
+      .with_context(|| format!("Failed to load cassette from '{}'", path.display()))?;
+    let json = serde_json::from_slice(&contents)
+      .with_context(|| format!("Failed to load cassette from '{}'", path.display()))?;
+    Cassette::from_json(&json)
+  }
+
+  /// Writes the cassette to a file
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    let contents = serde_json::to_string_pretty(&self.to_json())?;
+    fs::write(path, contents)
+      .with_context(|| format!("Failed to write cassette to '{}'", path.display()))
+  }
+
+  /// Loads a cassette from JSON
+  pub fn from_json(json: &Value) -> anyhow::Result<Self> {
+    let interactions = json.get("interactions")
+      .and_then(|interactions| interactions.as_array())
+      .ok_or_else(|| anyhow!("A cassette must have an 'interactions' array"))?
+      .iter()
+      .enumerate()
+      .map(|(index, interaction)| interaction_from_json(interaction)
+        .with_context(|| format!("Interaction {} of the cassette is not valid", index)))
+      .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Cassette { interactions })
+  }
+
+  /// Converts the cassette to JSON
+  pub fn to_json(&self) -> Value {
+    json!({
+      "interactions": self.interactions.iter()
+        .map(|interaction| {
+          let mut request = Map::new();
+          request.insert("method".to_string(), json!(interaction.request.method));
+          request.insert("url".to_string(), json!(interaction.request.url));
+          request.insert("headers".to_string(), headers_to_json(&interaction.request.headers));
+          if let Some(body) = &interaction.request.body {
+            body_to_json(&mut request, body);
+          }
+          let mut response = Map::new();
+          response.insert("status".to_string(), json!(interaction.response.status));
+          response.insert("headers".to_string(), headers_to_json(&interaction.response.headers));
+          if !interaction.response.body.is_empty() {
+            body_to_json(&mut response, &interaction.response.body);
+          }
+          json!({ "stepId": interaction.step_id, "request": request, "response": response })
+        })
+        .collect::<Vec<_>>()
+    })
+  }
+}
+
+fn headers_to_json(headers: &[(String, String)]) -> Value {
+  Value::Array(headers.iter().map(|(name, value)| json!([name, value])).collect())
+}
+
+fn body_to_json(map: &mut Map<String, Value>, body: &Bytes) {
+  match std::str::from_utf8(body) {
+    Ok(text) => {
+      map.insert("body".to_string(), json!(text));
+    }
+    Err(_) => {
+      map.insert("body".to_string(), json!(base64::encode(body)));
+      map.insert("bodyEncoding".to_string(), json!("base64"));
+    }
+  }
+}
+
+fn interaction_from_json(json: &Value) -> anyhow::Result<Interaction> {
+  let step_id = string_field(json, "stepId")?;
+  let request = json.get("request").ok_or_else(|| anyhow!("'request' is required"))?;
+  let response = json.get("response").ok_or_else(|| anyhow!("'response' is required"))?;
+  let status = response.get("status")
+    .and_then(|status| status.as_u64())
+    .and_then(|status| u16::try_from(status).ok())
+    .ok_or_else(|| anyhow!("'status' must be a HTTP status code"))?;
+  Ok(Interaction {
+    step_id,
+    request: StepRequest {
+      method: string_field(request, "method")?,
+      url: string_field(request, "url")?,
+      headers: headers_from_json(request)?,
+      body: body_from_json(request)?
+    },
+    response: StepResponse {
+      status,
+      headers: headers_from_json(response)?,
+      body: body_from_json(response)?.unwrap_or_default()
+    }
+  })
+}
+
+fn string_field(json: &Value, name: &str) -> anyhow::Result<String> {
+  json.get(name)
+    .and_then(|value| value.as_str())
+    .map(|value| value.to_string())
+    .ok_or_else(|| anyhow!("'{}' must be a string", name))
+}
+
+fn headers_from_json(json: &Value) -> anyhow::Result<Vec<(String, String)>> {
+  match json.get("headers") {
+    Some(Value::Array(headers)) => headers.iter()
+      .map(|header| match header.as_array().map(|header| header.as_slice()) {
+        Some([Value::String(name), Value::String(value)]) => Ok((name.clone(), value.clone())),
+        _ => Err(anyhow!("Headers must be [name, value] pairs"))
+      })
+      .collect(),
+    None => Ok(vec![]),
+    Some(_) => Err(anyhow!("'headers' must be an array"))
+  }
+}
+
+fn body_from_json(json: &Value) -> anyhow::Result<Option<Bytes>> {
+  match json.get("body") {
+    Some(Value::String(body)) => if json.get("bodyEncoding").and_then(|encoding| encoding.as_str()) == Some("base64") {
+      Ok(Some(Bytes::from(base64::decode(body)?)))
+    } else {
+      Ok(Some(Bytes::from(body.clone())))
+    },
+    None => Ok(None),
+    Some(_) => Err(anyhow!("'body' must be a string"))
+  }
+}
+
+/// Step executor that sends the requests with another step executor, and records the requests
+/// and responses to a cassette file. The file is written after each interaction, so it is kept
+/// even if the run is aborted.
+#[derive(Debug)]
+pub struct RecordingExecutor<E> {
+  inner: E,
+  path: PathBuf,
+  cassette: Mutex<Cassette>
+}
+
+impl <E: StepExecutor> RecordingExecutor<E> {
+  /// Creates an executor that records to the cassette file (any existing file is replaced)
+  pub fn new<P: Into<PathBuf>>(inner: E, path: P) -> Self {
+    RecordingExecutor { inner, path: path.into(), cassette: Mutex::new(Cassette::default()) }
+  }
+
+  /// Returns the interactions recorded so far
+  pub fn cassette(&self) -> Cassette {
+    self.lock().clone()
+  }
+
+  fn lock(&self) -> MutexGuard<'_, Cassette> {
+    self.cassette.lock().unwrap_or_else(|err| err.into_inner())
+  }
+
+  fn record(&self, step: &Step, request: &StepRequest, response: StepResponse) -> anyhow::Result<StepResponse> {
+    let mut cassette = self.lock();
+    cassette.interactions.push(Interaction {
+      step_id: step.step_id.clone(),
+      request: request.clone(),
+      response: response.clone()
+    });
+    cassette.save(&self.path)?;
+    Ok(response)
+  }
+}
+
+impl <E: StepExecutor> StepExecutor for RecordingExecutor<E> {
+  fn execute(
+    &self,
+    step: &Step,
+    operation: &Operation,
+    request: &StepRequest,
+    context: &ExecutionContext
+  ) -> anyhow::Result<StepResponse> {
+    let response = self.inner.execute(step, operation, request, context)?;
+    self.record(step, request, response)
+  }
+
+  fn execute_with_timeout(
+    &self,
+    step: &Step,
+    operation: &Operation,
+    request: &StepRequest,
+    context: &ExecutionContext,
+    timeout: Duration
+  ) -> anyhow::Result<StepResponse> {
+    let response = self.inner.execute_with_timeout(step, operation, request, context, timeout)?;
+    self.record(step, request, response)
+  }
+}
+
+/// Step executor that returns the responses from a cassette. Requests are matched to the
+/// recorded interactions by the step ID, method and URL (and the body, if `match_body` is set).
+/// Matching interactions are used in the order they were recorded, and the last one is repeated
+/// once they have all been used.
+#[derive(Debug)]
+pub struct ReplayExecutor {
+  cassette: Cassette,
+  match_body: bool,
+  used: Mutex<Vec<bool>>
+}
+
+impl ReplayExecutor {
+  /// Creates an executor that replays the cassette
+  pub fn new(cassette: Cassette) -> Self {
+    let used = Mutex::new(vec![false; cassette.interactions.len()]);
+    ReplayExecutor { cassette, match_body: false, used }
+  }
+
+  /// Creates an executor that replays the cassette file
+  pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+    Ok(ReplayExecutor::new(Cassette::load(path)?))
+  }
+
+  /// Sets if the request bodies must also match the recorded requests
+  pub fn match_body(mut self, match_body: bool) -> Self {
+    self.match_body = match_body;
+    self
+  }
+
+  fn matches(&self, interaction: &Interaction, step: &Step, request: &StepRequest) -> bool {
+    interaction.step_id == step.step_id &&
+      interaction.request.method.eq_ignore_ascii_case(&request.method) &&
+      interaction.request.url == request.url &&
+      (!self.match_body || interaction.request.body.as_ref().filter(|body| !body.is_empty()) ==
+        request.body.as_ref().filter(|body| !body.is_empty()))
+  }
+}
+
+impl StepExecutor for ReplayExecutor {
+  fn execute(
+    &self,
+    step: &Step,
+    _operation: &Operation,
+    request: &StepRequest,
+    _context: &ExecutionContext
+  ) -> anyhow::Result<StepResponse> {
+    let mut used = self.used.lock().unwrap_or_else(|err| err.into_inner());
+    let matching = self.cassette.interactions.iter()
+      .enumerate()
+      .filter(|(_, interaction)| self.matches(interaction, step, request))
+      .map(|(index, _)| index)
+      .collect::<Vec<_>>();
+    let index = matching.iter().find(|index| !used[**index]).or(matching.last())
+      .ok_or_else(|| anyhow!("No interaction was recorded for step '{}' ({} {})", step.step_id,
+        request.method, request.url))?;
+    used[*index] = true;
+    Ok(self.cassette.interactions[*index].response.clone())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::arazzo;
+  use crate::cassette::{Cassette, RecordingExecutor, ReplayExecutor};
+  use crate::execution_context::StepResponse;
+  use crate::executor::{ExecutionStatus, Executor, ExecutorOptions};
+  use crate::extensions::AnyValue;
+  use crate::mock_executor::MockExecutor;
+  use crate::operations::{OpenApiSource, OperationResolver};
+  use crate::v1_0::ArazzoDescription;
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      workflow("pets") {
+        step("find") { operation_id: "findPets"; }
+        step("image") { operation_id: "getImage"; }
+        output("name", "$steps.find.outputs.name");
+      }
+    }
+  }
+
+  fn operations() -> OperationResolver {
+    OperationResolver::new(vec![OpenApiSource::new("petstore", "petstore.json", json!({
+      "servers": [{ "url": "http://localhost" }],
+      "paths": {
+        "/pets": { "get": { "operationId": "findPets" } },
+        "/image": { "get": { "operationId": "getImage" } }
+      }
+    }))])
+  }
+
+  #[test]
+  fn records_and_replays_the_interactions() {
+    let mut description = description();
+    description.workflows[0].steps[0].outputs.insert("name".to_string(), "$response.body#/0/name".to_string());
+    let dir = std::env::temp_dir().join(format!("arazzo-cassette-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("pets.json");
+
+    let mock = MockExecutor::new();
+    mock.respond_to_step("find", StepResponse::new(200).with_json(&json!([{ "name": "Tom" }])))
+      .respond_to_step("image", StepResponse::new(200).with_body(vec![0xFF_u8, 0xD8, 0x00]));
+    let executor = Executor::with_operations(&description, operations(), ExecutorOptions::default())
+      .with_step_executor(RecordingExecutor::new(mock, &path));
+    let recorded = executor.execute("pets", AnyValue::Null).unwrap();
+    expect!(recorded.status).to(be_equal_to(ExecutionStatus::Success));
+
+    let cassette = Cassette::load(&path).unwrap();
+    expect!(cassette.interactions.len()).to(be_equal_to(2));
+    expect!(cassette.interactions[1].response.body.to_vec()).to(be_equal_to(vec![0xFF_u8, 0xD8, 0x00]));
+    expect!(cassette.to_json()["interactions"][1]["response"]["bodyEncoding"].clone()).to(be_equal_to(json!("base64")));
+
+    let executor = executor.with_step_executor(ReplayExecutor::load(&path).unwrap());
+    let replayed = executor.execute("pets", AnyValue::Null).unwrap();
+    expect!(replayed.status).to(be_equal_to(ExecutionStatus::Success));
+    expect!(replayed.outputs).to(be_equal_to(recorded.outputs));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let mut cassette = cassette;
+    cassette.interactions.remove(1);
+    let executor = executor.with_step_executor(ReplayExecutor::new(cassette));
+    let result = executor.execute("pets", AnyValue::Null).unwrap();
+    expect!(result.step("image").unwrap().error.as_deref())
+      .to(be_some().value("No interaction was recorded for step 'image' (GET http://localhost/image)"));
+  }
+
+  #[test]
+  fn returns_an_error_for_invalid_cassettes() {
+    expect!(Cassette::from_json(&json!({})).unwrap_err().to_string())
+      .to(be_equal_to("A cassette must have an 'interactions' array"));
+    let error = Cassette::from_json(&json!({ "interactions": [{ "stepId": "find", "request": {}, "response": {} }] })).unwrap_err();
+    expect!(format!("{:#}", error)).to(be_equal_to("Interaction 0 of the cassette is not valid: 'status' must be a HTTP status code"));
+  }
+}
@@ -0,0 +1,238 @@
+//! Checkpoints of workflow executions, so a long-running workflow that was interrupted (i.e. by
+//! an infrastructure failure) can be resumed from the step it stopped at, instead of restarting
+//! from the first step.
+//!
+//! When [`ExecutorOptions::checkpoint_file`](crate::executor::ExecutorOptions::checkpoint_file)
+//! is set, the executor writes the state of the workflow to the file before each step is executed.
+//! The checkpoint can then be loaded and passed to
+//! [`Executor::resume`](crate::executor::Executor::resume). Checkpoints are JSON files:
+//!
+//! ```json
+//! {
+//!   "workflowId": "adopt-pet",
+//!   "inputs": { "petId": 1 },
+//!   "nextStep": "place-order",
+//!   "attempt": 2,
+//!   "completedSteps": ["find-pet"],
+//!   "stepOutputs": { "find-pet": { "id": 1 } },
+//!   "workflowOutputs": {}
+//! }
+//! ```
+//!
+//! Only the outputs of the steps are kept, not the requests and responses, so expressions that
+//! reference the request or response of an earlier step can not be resolved after resuming.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use serde_json::{json, Map, Value};
+
+use crate::execution_context::ExecutionContext;
+use crate::extensions::AnyValue;
+
+/// State of a workflow execution, which can be used to resume the workflow
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Checkpoint {
+  /// ID of the workflow that was being executed
+  pub workflow_id: String,
+  /// Inputs of the workflow
+  pub inputs: AnyValue,
+  /// Step that is executed next. This is `None` if all the steps have completed.
+  pub next_step: Option<String>,
+  /// Attempt number of the next step (greater than 1 if a retry is pending)
+  pub attempt: usize,
+  /// IDs of the step executions that have completed, in the order they were executed
+  pub completed_steps: Vec<String>,
+  /// Captured outputs of the steps, keyed by step ID
+  pub step_outputs: BTreeMap<String, BTreeMap<String, AnyValue>>,
+  /// Outputs of other workflows (from `goto` actions), keyed by workflow ID
+  pub workflow_outputs: BTreeMap<String, BTreeMap<String, AnyValue>>
+}
+
+impl Checkpoint {
+  /// Creates a checkpoint from the state of the execution context
+  pub fn new(
+    workflow_id: &str,
+    context: &ExecutionContext,
+    next_step: Option<&str>,
+    attempt: usize,
+    completed_steps: Vec<String>
+  ) -> Self {
+    Checkpoint {
+      workflow_id: workflow_id.to_string(),
+      inputs: context.inputs().clone(),
+      next_step: next_step.map(|step| step.to_string()),
+      attempt,
+      completed_steps,
+      step_outputs: sorted(&context.expressions.steps),
+      workflow_outputs: sorted(&context.expressions.workflows)
+    }
+  }
+
+  /// Creates an execution context with the inputs and outputs of the checkpoint
+  pub fn context(&self) -> ExecutionContext {
+    let mut context = ExecutionContext::new(self.inputs.clone());
+    context.expressions.steps = unsorted(&self.step_outputs);
+    context.expressions.workflows = unsorted(&self.workflow_outputs);
+    context
+  }
+
+  /// Loads a checkpoint from a file
+  pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+    let path = path.as_ref();
+    let contents = fs::read(path)
+      .with_context(|| format!("Failed to load checkpoint from '{}'", path.display()))?;
+    let json = serde_json::from_slice(&contents)
+      .with_context(|| format!("Failed to load checkpoint from '{}'", path.display()))?;
+    Checkpoint::from_json(&json)
+  }
+
+  /// Writes the checkpoint to a file. The checkpoint is written to a temporary file first, so
+  /// an interruption while writing does not leave a partial checkpoint.
+  pub fn save<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    let contents = serde_json::to_string_pretty(&self.to_json())?;
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, contents)
+      .and_then(|_| fs::rename(&temp_path, path))
+      .with_context(|| format!("Failed to write checkpoint to '{}'", path.display()))
+  }
+
+  /// Loads a checkpoint from JSON
+  pub fn from_json(json: &Value) -> anyhow::Result<Self> {
+    let workflow_id = json.get("workflowId")
+      .and_then(|id| id.as_str())
+      .ok_or_else(|| anyhow!("A checkpoint must have a 'workflowId' string"))?;
+    let next_step = match json.get("nextStep") {
+      None | Some(Value::Null) => None,
+      Some(Value::String(step)) => Some(step.clone()),
+      Some(_) => return Err(anyhow!("The 'nextStep' of a checkpoint must be a string"))
+    };
+    let attempt = match json.get("attempt") {
+      None => 1,
+      Some(attempt) => attempt.as_u64()
+        .filter(|attempt| *attempt > 0)
+        .ok_or_else(|| anyhow!("The 'attempt' of a checkpoint must be a positive integer"))? as usize
+    };
+    let completed_steps = match json.get("completedSteps") {
+      None => vec![],
+      Some(Value::Array(steps)) => steps.iter()
+        .map(|step| step.as_str().map(|step| step.to_string()))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| anyhow!("The 'completedSteps' of a checkpoint must be strings"))?,
+      Some(_) => return Err(anyhow!("The 'completedSteps' of a checkpoint must be an array"))
+    };
+    Ok(Checkpoint {
+      workflow_id: workflow_id.to_string(),
+      inputs: match json.get("inputs") {
+        Some(inputs) => AnyValue::try_from(inputs)?,
+        None => AnyValue::Null
+      },
+      next_step,
+      attempt,
+      completed_steps,
+      step_outputs: outputs_from_json(json, "stepOutputs")?,
+      workflow_outputs: outputs_from_json(json, "workflowOutputs")?
+    })
+  }
+
+  /// Converts the checkpoint to JSON
+  pub fn to_json(&self) -> Value {
+    json!({
+      "workflowId": self.workflow_id,
+      "inputs": self.inputs.to_json(),
+      "nextStep": self.next_step,
+      "attempt": self.attempt,
+      "completedSteps": self.completed_steps,
+      "stepOutputs": outputs_to_json(&self.step_outputs),
+      "workflowOutputs": outputs_to_json(&self.workflow_outputs)
+    })
+  }
+}
+
+fn sorted(outputs: &HashMap<String, HashMap<String, AnyValue>>) -> BTreeMap<String, BTreeMap<String, AnyValue>> {
+  outputs.iter()
+    .map(|(key, values)| (key.clone(), values.iter().map(|(k, v)| (k.clone(), v.clone())).collect()))
+    .collect()
+}
+
+fn unsorted(outputs: &BTreeMap<String, BTreeMap<String, AnyValue>>) -> HashMap<String, HashMap<String, AnyValue>> {
+  outputs.iter()
+    .map(|(key, values)| (key.clone(), values.iter().map(|(k, v)| (k.clone(), v.clone())).collect()))
+    .collect()
+}
+
+fn outputs_to_json(outputs: &BTreeMap<String, BTreeMap<String, AnyValue>>) -> Value {
+  Value::Object(outputs.iter()
+    .map(|(key, values)| (key.clone(), Value::Object(values.iter()
+      .map(|(name, value)| (name.clone(), value.to_json()))
+      .collect::<Map<_, _>>())))
+    .collect())
+}
+
+fn outputs_from_json(json: &Value, field: &str) -> anyhow::Result<BTreeMap<String, BTreeMap<String, AnyValue>>> {
+  match json.get(field) {
+    None | Some(Value::Null) => Ok(BTreeMap::new()),
+    Some(Value::Object(outputs)) => outputs.iter()
+      .map(|(key, values)| {
+        let values = values.as_object()
+          .ok_or_else(|| anyhow!("The '{}' of a checkpoint must be objects of outputs", field))?
+          .iter()
+          .map(|(name, value)| Ok((name.clone(), AnyValue::try_from(value)?)))
+          .collect::<anyhow::Result<BTreeMap<_, _>>>()?;
+        Ok((key.clone(), values))
+      })
+      .collect(),
+    Some(_) => Err(anyhow!("The '{}' of a checkpoint must be an object", field))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use indexmap::indexmap;
+  use maplit::btreemap;
+  use serde_json::json;
+
+  use crate::checkpoint::Checkpoint;
+  use crate::extensions::AnyValue;
+
+  #[test]
+  fn round_trips_through_json() {
+    let checkpoint = Checkpoint {
+      workflow_id: "adopt-pet".to_string(),
+      inputs: AnyValue::Object(indexmap!{ "petId".to_string() => AnyValue::UInteger(1) }),
+      next_step: Some("place-order".to_string()),
+      attempt: 2,
+      completed_steps: vec!["find-pet".to_string()],
+      step_outputs: btreemap!{
+        "find-pet".to_string() => btreemap!{ "name".to_string() => AnyValue::String("Rex".to_string()) }
+      },
+      workflow_outputs: Default::default()
+    };
+    let json = checkpoint.to_json();
+    expect!(json.clone()).to(be_equal_to(json!({
+      "workflowId": "adopt-pet",
+      "inputs": { "petId": 1 },
+      "nextStep": "place-order",
+      "attempt": 2,
+      "completedSteps": ["find-pet"],
+      "stepOutputs": { "find-pet": { "name": "Rex" } },
+      "workflowOutputs": {}
+    })));
+    expect!(Checkpoint::from_json(&json).unwrap()).to(be_equal_to(checkpoint));
+
+    let context = Checkpoint::from_json(&json).unwrap().context();
+    expect!(context.step_outputs("find-pet").and_then(|outputs| outputs.get("name")).cloned())
+      .to(be_some().value(AnyValue::String("Rex".to_string())));
+  }
+
+  #[test]
+  fn rejects_invalid_checkpoints() {
+    expect!(Checkpoint::from_json(&json!({}))).to(be_err());
+    expect!(Checkpoint::from_json(&json!({ "workflowId": "pets", "attempt": 0 }))).to(be_err());
+    expect!(Checkpoint::from_json(&json!({ "workflowId": "pets", "stepOutputs": { "a": 1 } }))).to(be_err());
+  }
+}
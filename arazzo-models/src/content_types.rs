@@ -0,0 +1,196 @@
+//! Helpers for working with content types: inferring the content type of a payload when it is not
+//! declared, and matching content types against a set of acceptable types.
+
+use crate::payload_registry::content_type_matches;
+use crate::payloads::{
+  BytesPayload,
+  EmptyPayload,
+  ExternalPayload,
+  FormPayload,
+  GraphQlPayload,
+  JsonPayload,
+  Payload,
+  PayloadLocation,
+  ProtobufPayload,
+  StringPayload
+};
+#[cfg(feature = "xml")] use crate::payloads::XmlPayload;
+#[cfg(feature = "yaml")] use crate::payloads::YamlPayload;
+use crate::v1_0::RequestBody;
+
+/// Content types for common file extensions, used for external payloads
+const FILE_EXTENSIONS: [(&str, &str); 12] = [
+  ("json", "application/json"),
+  ("xml", "application/xml"),
+  ("yaml", "application/yaml"),
+  ("yml", "application/yaml"),
+  ("txt", "text/plain"),
+  ("csv", "text/csv"),
+  ("html", "text/html"),
+  ("pdf", "application/pdf"),
+  ("png", "image/png"),
+  ("jpg", "image/jpeg"),
+  ("jpeg", "image/jpeg"),
+  ("gif", "image/gif")
+];
+
+/// Infers the content type of the payload from its type. JSON and GraphQL payloads are
+/// `application/json`, XML payloads are `application/xml`, YAML payloads are `application/yaml`,
+/// form payloads are `application/x-www-form-urlencoded`, protobuf payloads are
+/// `application/x-protobuf` and binary payloads are `application/octet-stream`. String payloads are inspected: JSON objects and arrays are
+/// `application/json`, XML documents are `application/xml`, and anything else is `text/plain`.
+/// External payloads are inferred from the file extension. Returns `None` for empty payloads, or if the content type can not be inferred.
+pub fn infer_content_type(payload: &(dyn Payload + Send + Sync)) -> Option<&'static str> {
+  if payload.downcast_ref::<EmptyPayload>().is_some() {
+    None
+  } else if payload.downcast_ref::<JsonPayload>().is_some() || payload.downcast_ref::<GraphQlPayload>().is_some() {
+    Some("application/json")
+  } else if payload.downcast_ref::<FormPayload>().is_some() {
+    Some("application/x-www-form-urlencoded")
+  } else if payload.downcast_ref::<BytesPayload>().is_some() {
+    Some("application/octet-stream")
+  } else if payload.downcast_ref::<ProtobufPayload>().is_some() {
+    Some("application/x-protobuf")
+  } else if let Some(string_payload) = payload.downcast_ref::<StringPayload>() {
+    Some(infer_text_content_type(&string_payload.0))
+  } else if let Some(external_payload) = payload.downcast_ref::<ExternalPayload>() {
+    let path = match &external_payload.location {
+      PayloadLocation::File(path) => path.to_string_lossy().to_string(),
+      PayloadLocation::Url(url) => url.split(['?', '#']).next().unwrap_or_default().to_string()
+    };
+    let extension = path.rsplit_once('.')
+      .map(|(_, extension)| extension.to_lowercase())
+      .filter(|extension| !extension.contains('/'))?;
+    FILE_EXTENSIONS.iter()
+      .find(|(ext, _)| *ext == extension)
+      .map(|(_, content_type)| *content_type)
+  } else {
+    infer_structured_content_type(payload)
+  }
+}
+
+#[allow(unused_variables)]
+fn infer_structured_content_type(payload: &(dyn Payload + Send + Sync)) -> Option<&'static str> {
+  #[cfg(feature = "xml")]
+  if payload.downcast_ref::<XmlPayload>().is_some() {
+    return Some("application/xml");
+  }
+  #[cfg(feature = "yaml")]
+  if payload.downcast_ref::<YamlPayload>().is_some() {
+    return Some("application/yaml");
+  }
+  None
+}
+
+fn infer_text_content_type(text: &str) -> &'static str {
+  let text = text.trim_start();
+  if (text.starts_with('{') || text.starts_with('[')) &&
+    serde_json::from_str::<serde_json::Value>(text).is_ok() {
+    "application/json"
+  } else if text.starts_with("<?xml") || (text.starts_with('<') && !text.to_lowercase().starts_with("<!doctype html") &&
+    !text.to_lowercase().starts_with("<html")) {
+    "application/xml"
+  } else {
+    "text/plain"
+  }
+}
+
+impl RequestBody {
+  /// Returns the declared content type of the request body, otherwise the content type inferred
+  /// from the payload (see [`infer_content_type`]).
+  pub fn effective_content_type(&self) -> Option<String> {
+    self.content_type.clone()
+      .or_else(|| self.payload.as_ref()
+        .and_then(|payload| infer_content_type(payload.as_ref()))
+        .map(|content_type| content_type.to_string()))
+  }
+}
+
+/// Splits a content type into the media type (lowercased) and the parameters (with lowercased
+/// names). Quotes around parameter values are removed.
+pub fn parse_content_type(content_type: &str) -> (String, Vec<(String, String)>) {
+  let mut parts = content_type.split(';');
+  let media_type = parts.next().unwrap_or_default().trim().to_lowercase();
+  let parameters = parts
+    .filter_map(|parameter| parameter.split_once('='))
+    .map(|(name, value)| (name.trim().to_lowercase(), value.trim().trim_matches('"').to_string()))
+    .collect();
+  (media_type, parameters)
+}
+
+/// If the content type matches the acceptable type. The acceptable type can use wildcards (`*/*`,
+/// `application/*` or `*+json`), and any parameters it has (apart from `q`) must also be present
+/// on the content type with the same value (the `charset` parameter is compared ignoring case).
+/// Parameters on the content type that are not in the acceptable type are ignored, so
+/// `application/json;charset=utf-8` matches `application/json`.
+pub fn media_type_matches(acceptable: &str, content_type: &str) -> bool {
+  let (mut pattern, pattern_parameters) = parse_content_type(acceptable);
+  if pattern.starts_with("*+") {
+    pattern.insert_str(0, "*/");
+  }
+  let (media_type, parameters) = parse_content_type(content_type);
+  content_type_matches(&pattern, &media_type) && pattern_parameters.iter()
+    .filter(|(name, _)| name != "q")
+    .all(|(name, value)| parameters.iter().any(|(n, v)| {
+      n == name && if name == "charset" { v.eq_ignore_ascii_case(value) } else { v == value }
+    }))
+}
+
+/// Returns the first acceptable type that the content type matches (see [`media_type_matches`]).
+pub fn negotiate_content_type<'a>(content_type: &str, acceptable: &[&'a str]) -> Option<&'a str> {
+  acceptable.iter()
+    .find(|acceptable| media_type_matches(acceptable, content_type))
+    .copied()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::content_types::*;
+  use crate::payloads::*;
+  use crate::v1_0::RequestBody;
+
+  #[test]
+  fn infers_content_types() {
+    expect!(infer_content_type(&JsonPayload(json!({ "a": 1 })))).to(be_some().value("application/json"));
+    expect!(infer_content_type(&StringPayload("{\"a\": 1}".to_string()))).to(be_some().value("application/json"));
+    expect!(infer_content_type(&StringPayload("<pet><id>1</id></pet>".to_string()))).to(be_some().value("application/xml"));
+    expect!(infer_content_type(&StringPayload("<html><body/></html>".to_string()))).to(be_some().value("text/plain"));
+    expect!(infer_content_type(&StringPayload("{$inputs.pet}".to_string()))).to(be_some().value("text/plain"));
+    expect!(infer_content_type(&BytesPayload(bytes::Bytes::from_static(b"\0")))).to(be_some().value("application/octet-stream"));
+    expect!(infer_content_type(&ExternalPayload::file("fixtures/pet.JSON"))).to(be_some().value("application/json"));
+    expect!(infer_content_type(&ExternalPayload::url("http://localhost/pet.png?v=1"))).to(be_some().value("image/png"));
+    expect!(infer_content_type(&ExternalPayload::url("http://localhost/pet"))).to(be_none());
+    expect!(infer_content_type(&EmptyPayload)).to(be_none());
+
+    let body = RequestBody {
+      content_type: None,
+      payload: Some(Arc::new(JsonPayload(json!([1, 2])))),
+      replacements: vec![],
+      extensions: Default::default()
+    };
+    expect!(body.effective_content_type()).to(be_some().value("application/json"));
+    let body = RequestBody { content_type: Some("application/vnd.pets+json".to_string()), .. body };
+    expect!(body.effective_content_type()).to(be_some().value("application/vnd.pets+json"));
+  }
+
+  #[test]
+  fn matches_media_types() {
+    expect!(media_type_matches("application/json", "application/json;charset=utf-8")).to(be_true());
+    expect!(media_type_matches("application/json;charset=UTF-8", "application/json; charset=\"utf-8\"")).to(be_true());
+    expect!(media_type_matches("application/json;charset=utf-8", "application/json")).to(be_false());
+    expect!(media_type_matches("application/json;q=0.5", "Application/JSON")).to(be_true());
+    expect!(media_type_matches("*+json", "application/vnd.pets+json")).to(be_true());
+    expect!(media_type_matches("application/*", "application/xml")).to(be_true());
+    expect!(media_type_matches("text/*", "application/xml")).to(be_false());
+
+    let acceptable = ["application/xml", "*/*+json", "*/*"];
+    expect!(negotiate_content_type("application/hal+json", &acceptable)).to(be_some().value("*/*+json"));
+    expect!(negotiate_content_type("text/plain", &acceptable)).to(be_some().value("*/*"));
+    expect!(negotiate_content_type("text/plain", &acceptable[..2])).to(be_none());
+  }
+}
@@ -0,0 +1,135 @@
+//! Context that holds the values of runtime expressions (workflow inputs, step outputs, etc.),
+//! used to resolve the expressions when rendering requests.
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+
+use crate::expressions::ExpressionResolver;
+use crate::extensions::AnyValue;
+
+/// Values that runtime expressions are resolved against. Expressions are resolved as follows:
+/// * `$inputs.<path>` - from the workflow inputs
+/// * `$steps.<stepId>.outputs.<name>` - from the outputs of a step
+/// * `$outputs.<name>` - from the outputs of the current workflow
+/// * `$workflows.<workflowId>.outputs.<name>` - from the outputs of another workflow
+/// * any other expression (i.e. `$url` or `$response.header.Location`) by the whole expression
+///   from the values.
+///
+/// Paths after the name can use dot-separated fields and indices (i.e. `$inputs.pet.tags[0]`), and
+/// a JSON Pointer fragment (i.e. `$steps.one.outputs.body#/pets/0`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExpressionContext {
+  /// Workflow inputs
+  pub inputs: AnyValue,
+  /// Outputs of the steps, keyed by step ID
+  pub steps: HashMap<String, HashMap<String, AnyValue>>,
+  /// Outputs of the current workflow
+  pub outputs: HashMap<String, AnyValue>,
+  /// Outputs of other workflows, keyed by workflow ID
+  pub workflows: HashMap<String, HashMap<String, AnyValue>>,
+  /// Values of any other expressions, keyed by the whole expression
+  pub values: HashMap<String, AnyValue>
+}
+
+impl ExpressionContext {
+  /// Creates a context with the workflow inputs
+  pub fn new(inputs: AnyValue) -> Self {
+    ExpressionContext {
+      inputs,
+      .. ExpressionContext::default()
+    }
+  }
+
+  /// Sets the value of an output of a step
+  pub fn set_step_output<S: Into<String>, N: Into<String>>(&mut self, step_id: S, name: N, value: AnyValue) {
+    self.steps.entry(step_id.into()).or_default().insert(name.into(), value);
+  }
+
+  /// Sets the value of any other expression
+  pub fn set_value<S: Into<String>>(&mut self, expression: S, value: AnyValue) {
+    self.values.insert(expression.into(), value);
+  }
+
+  fn lookup(&self, expression: &str) -> Option<&AnyValue> {
+    if let Some(value) = self.values.get(expression) {
+      return Some(value);
+    }
+
+    let (expression, pointer) = match expression.split_once('#') {
+      Some((expression, pointer)) => (expression, Some(pointer)),
+      None => (expression, None)
+    };
+    let value = if expression == "$inputs" {
+      Some(&self.inputs)
+    } else if let Some(path) = expression.strip_prefix("$inputs.") {
+      self.inputs.get_path(path)
+    } else if let Some(rest) = expression.strip_prefix("$steps.") {
+      let (step_id, rest) = rest.split_once(".outputs.")?;
+      self.steps.get(step_id).and_then(|outputs| lookup_output(outputs, rest))
+    } else if let Some(rest) = expression.strip_prefix("$outputs.") {
+      lookup_output(&self.outputs, rest)
+    } else if let Some(rest) = expression.strip_prefix("$workflows.") {
+      let (workflow_id, rest) = rest.split_once(".outputs.")?;
+      self.workflows.get(workflow_id).and_then(|outputs| lookup_output(outputs, rest))
+    } else {
+      self.values.get(expression)
+    }?;
+
+    match pointer {
+      Some(pointer) => value.pointer(pointer),
+      None => Some(value)
+    }
+  }
+}
+
+fn lookup_output<'a>(outputs: &'a HashMap<String, AnyValue>, path: &str) -> Option<&'a AnyValue> {
+  let end = path.find(['.', '[']).unwrap_or(path.len());
+  let value = outputs.get(&path[..end])?;
+  value.get_path(&path[end..])
+}
+
+impl ExpressionResolver for ExpressionContext {
+  fn resolve(&self, expression: &str) -> anyhow::Result<AnyValue> {
+    self.lookup(expression.trim())
+      .cloned()
+      .ok_or_else(|| anyhow!("Runtime expression '{}' could not be resolved", expression))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use indexmap::indexmap;
+
+  use crate::context::ExpressionContext;
+  use crate::expressions::ExpressionResolver;
+  use crate::extensions::AnyValue;
+
+  #[test]
+  fn resolves_expressions() {
+    let mut context = ExpressionContext::new(AnyValue::Object(indexmap!{
+      "pet".to_string() => AnyValue::Object(indexmap!{
+        "id".to_string() => AnyValue::Integer(10),
+        "tags".to_string() => AnyValue::Array(vec![AnyValue::String("dog".to_string())])
+      })
+    }));
+    context.set_step_output("login", "token", AnyValue::String("abc".to_string()));
+    context.set_step_output("find", "body", AnyValue::Object(indexmap!{
+      "pets".to_string() => AnyValue::Array(vec![AnyValue::Integer(1), AnyValue::Integer(2)])
+    }));
+    context.outputs.insert("count".to_string(), AnyValue::Integer(2));
+    context.set_value("$statusCode", AnyValue::Integer(200));
+
+    expect!(context.resolve("$inputs.pet.id").unwrap()).to(be_equal_to(AnyValue::Integer(10)));
+    expect!(context.resolve("$inputs.pet.tags[0]").unwrap()).to(be_equal_to(AnyValue::String("dog".to_string())));
+    expect!(context.resolve("$inputs#/pet/id").unwrap()).to(be_equal_to(AnyValue::Integer(10)));
+    expect!(context.resolve(" $steps.login.outputs.token ").unwrap()).to(be_equal_to(AnyValue::String("abc".to_string())));
+    expect!(context.resolve("$steps.find.outputs.body#/pets/1").unwrap()).to(be_equal_to(AnyValue::Integer(2)));
+    expect!(context.resolve("$steps.find.outputs.body.pets[0]").unwrap()).to(be_equal_to(AnyValue::Integer(1)));
+    expect!(context.resolve("$outputs.count").unwrap()).to(be_equal_to(AnyValue::Integer(2)));
+    expect!(context.resolve("$statusCode").unwrap()).to(be_equal_to(AnyValue::Integer(200)));
+    expect!(context.resolve("$steps.other.outputs.token").unwrap_err().to_string())
+      .to(be_equal_to("Runtime expression '$steps.other.outputs.token' could not be resolved"));
+  }
+}
@@ -0,0 +1,427 @@
+//! Evaluation of Criterion Objects (see [4.6.12 Criterion Object](https://spec.openapis.org/arazzo/v1.0.1.html#criterion-object)).
+//!
+//! Simple conditions support literals (numbers, `true`, `false`, `null` and single-quoted strings),
+//! runtime expressions, the comparison operators (`<`, `<=`, `>`, `>=`, `==` and `!=`), the
+//! logical operators (`!`, `&&` and `||`) and grouping with parentheses. String comparisons ignore
+//! case. A runtime expression on its own (i.e. `$response.body#/available`) is true if it resolves
+//! to a value other than `false` or `null`.
+//!
+//! The `regex` type matches the condition against the context value (using the syntax of the
+//! `regex` crate, which matches in linear time, so conditions from untrusted documents can not
+//! hang the process), the `jsonpath` type is true if the JSONPath
+//! query selects any values from the context (see the `jsonpath` module) and the `xpath` type
+//! (which requires the `xml` feature) is true if the XPath expression selects any nodes from the
+//! context.
+
+use anyhow::anyhow;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::either::Either;
+use crate::expressions::ExpressionResolver;
+use crate::extensions::AnyValue;
+use crate::jsonpath;
+use crate::v1_0::Criterion;
+
+/// Returns the type of the criterion (`simple`, `regex`, `jsonpath` or `xpath`). The type
+/// defaults to `simple` if it is not specified.
+pub fn criterion_type(criterion: &Criterion) -> &str {
+  match &criterion.r#type {
+    Some(Either::First(criterion_type)) => criterion_type.as_str(),
+    Some(Either::Second(expression_type)) => expression_type.r#type.as_str(),
+    None => "simple"
+  }
+}
+
+/// Evaluates the criterion, using the resolver to resolve any runtime expressions. Returns an error
+/// if the condition is invalid, or the runtime expressions can not be resolved.
+pub fn evaluate_criterion(criterion: &Criterion, resolver: &dyn ExpressionResolver) -> anyhow::Result<bool> {
+  let criterion_type = criterion_type(criterion);
+  if criterion_type == "simple" {
+    return evaluate_condition(&criterion.condition, resolver);
+  }
+
+  let context = criterion.context.as_ref()
+    .ok_or_else(|| anyhow!("A context is required for '{}' criteria [4.6.12.1 Fixed Fields]", criterion_type))?;
+  let value = resolver.resolve(context)?;
+  match criterion_type {
+    "regex" => {
+      let regex = Regex::new(&criterion.condition)?;
+      Ok(regex.is_match(&value_text(&value)))
+    }
+    "jsonpath" => {
+      let json = match &value {
+        AnyValue::String(s) => serde_json::from_str(s).unwrap_or_else(|_| Value::String(s.clone())),
+        _ => value.to_json()
+      };
+      Ok(!jsonpath::query(&json, &criterion.condition)?.is_empty())
+    }
+    "xpath" => evaluate_xpath(&criterion.condition, &value),
+    _ => Err(anyhow!("'{}' is not a supported criterion type [4.6.12.1 Fixed Fields]", criterion_type))
+  }
+}
+
+/// Evaluates all the criteria, returning true if they all pass
+pub fn evaluate_criteria(criteria: &[Criterion], resolver: &dyn ExpressionResolver) -> anyhow::Result<bool> {
+  for criterion in criteria {
+    if !evaluate_criterion(criterion, resolver)? {
+      return Ok(false);
+    }
+  }
+  Ok(true)
+}
+
+#[cfg(feature = "xml")]
+fn evaluate_xpath(xpath: &str, value: &AnyValue) -> anyhow::Result<bool> {
+  let document = crate::xml::parse_xml(&value_text(value))?;
+  document.matches(xpath)
+}
+
+#[cfg(not(feature = "xml"))]
+fn evaluate_xpath(_xpath: &str, _value: &AnyValue) -> anyhow::Result<bool> {
+  Err(anyhow!("'xpath' criteria require the xml feature"))
+}
+
+fn value_text(value: &AnyValue) -> String {
+  value.to_text().unwrap_or_else(|| value.to_json().to_string())
+}
+
+/// Evaluates a simple condition (i.e. `$statusCode == 200 && $response.body#/available`), using
+/// the resolver to resolve any runtime expressions
+pub fn evaluate_condition(condition: &str, resolver: &dyn ExpressionResolver) -> anyhow::Result<bool> {
+  let tokens = tokenize(condition)?;
+  let mut parser = ConditionParser { tokens: &tokens, pos: 0, resolver, condition };
+  let value = parser.parse_or()?;
+  if parser.pos < tokens.len() {
+    return Err(anyhow!("Invalid condition '{}': unexpected '{}'", condition, tokens[parser.pos]));
+  }
+  Ok(is_truthy(&value))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+  Literal(AnyValue),
+  Expression(String),
+  Operator(&'static str)
+}
+
+impl std::fmt::Display for Token {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Token::Literal(value) => write!(f, "{}", value_text(value)),
+      Token::Expression(expression) => write!(f, "{}", expression),
+      Token::Operator(op) => write!(f, "{}", op)
+    }
+  }
+}
+
+const OPERATORS: [&str; 12] = ["&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "(", ")", "="];
+
+fn tokenize(condition: &str) -> anyhow::Result<Vec<Token>> {
+  let chars = condition.chars().collect::<Vec<_>>();
+  let mut tokens = vec![];
+  let mut pos = 0;
+  while pos < chars.len() {
+    let ch = chars[pos];
+    if ch.is_whitespace() {
+      pos += 1;
+    } else if ch == '$' {
+      let start = pos;
+      while pos < chars.len() && !chars[pos].is_whitespace() && !"()=!<>&|".contains(chars[pos]) {
+        pos += 1;
+      }
+      tokens.push(Token::Expression(chars[start..pos].iter().collect()));
+    } else if ch == '\'' || ch == '"' {
+      let mut value = String::new();
+      pos += 1;
+      loop {
+        match chars.get(pos) {
+          // A doubled quote is an escaped quote
+          Some(c) if *c == ch && chars.get(pos + 1) == Some(&ch) => {
+            value.push(ch);
+            pos += 2;
+          }
+          Some(c) if *c == ch => break,
+          Some(c) => {
+            value.push(*c);
+            pos += 1;
+          }
+          None => return Err(anyhow!("Invalid condition '{}': unterminated string", condition))
+        }
+      }
+      pos += 1;
+      tokens.push(Token::Literal(AnyValue::String(value)));
+    } else if ch.is_ascii_digit() || (ch == '-' && chars.get(pos + 1).is_some_and(|c| c.is_ascii_digit())) {
+      let start = pos;
+      pos += 1;
+      while pos < chars.len() && (chars[pos].is_ascii_digit() || chars[pos] == '.') {
+        pos += 1;
+      }
+      let number = chars[start..pos].iter().collect::<String>();
+      let value = match number.parse::<i64>() {
+        Ok(i) => AnyValue::Integer(i),
+        Err(_) => number.parse::<f64>()
+          .map(AnyValue::Float)
+          .map_err(|_| anyhow!("Invalid condition '{}': invalid number '{}'", condition, number))?
+      };
+      tokens.push(Token::Literal(value));
+    } else if ch.is_ascii_alphabetic() {
+      let start = pos;
+      while pos < chars.len() && chars[pos].is_ascii_alphanumeric() {
+        pos += 1;
+      }
+      let word = chars[start..pos].iter().collect::<String>();
+      let value = match word.as_str() {
+        "true" => AnyValue::Boolean(true),
+        "false" => AnyValue::Boolean(false),
+        "null" => AnyValue::Null,
+        _ => return Err(anyhow!("Invalid condition '{}': unexpected '{}'", condition, word))
+      };
+      tokens.push(Token::Literal(value));
+    } else {
+      let rest = chars[pos..].iter().collect::<String>();
+      let op = OPERATORS.iter()
+        .find(|op| rest.starts_with(*op))
+        .ok_or_else(|| anyhow!("Invalid condition '{}': unexpected '{}'", condition, ch))?;
+      if *op == "=" {
+        return Err(anyhow!("Invalid condition '{}': use '==' to compare values", condition));
+      }
+      tokens.push(Token::Operator(op));
+      pos += op.len();
+    }
+  }
+  Ok(tokens)
+}
+
+struct ConditionParser<'a> {
+  tokens: &'a [Token],
+  pos: usize,
+  resolver: &'a dyn ExpressionResolver,
+  condition: &'a str
+}
+
+impl ConditionParser<'_> {
+  fn consume(&mut self, op: &str) -> bool {
+    if matches!(self.tokens.get(self.pos), Some(Token::Operator(o)) if *o == op) {
+      self.pos += 1;
+      true
+    } else {
+      false
+    }
+  }
+
+  fn parse_or(&mut self) -> anyhow::Result<AnyValue> {
+    let mut value = self.parse_and()?;
+    while self.consume("||") {
+      let right = self.parse_and()?;
+      value = AnyValue::Boolean(is_truthy(&value) || is_truthy(&right));
+    }
+    Ok(value)
+  }
+
+  fn parse_and(&mut self) -> anyhow::Result<AnyValue> {
+    let mut value = self.parse_comparison()?;
+    while self.consume("&&") {
+      let right = self.parse_comparison()?;
+      value = AnyValue::Boolean(is_truthy(&value) && is_truthy(&right));
+    }
+    Ok(value)
+  }
+
+  fn parse_comparison(&mut self) -> anyhow::Result<AnyValue> {
+    let left = self.parse_unary()?;
+    for op in ["==", "!=", "<=", ">=", "<", ">"] {
+      if self.consume(op) {
+        let right = self.parse_unary()?;
+        return Ok(AnyValue::Boolean(compare_values(&left, op, &right)));
+      }
+    }
+    Ok(left)
+  }
+
+  fn parse_unary(&mut self) -> anyhow::Result<AnyValue> {
+    if self.consume("!") {
+      let value = self.parse_unary()?;
+      return Ok(AnyValue::Boolean(!is_truthy(&value)));
+    }
+    if self.consume("(") {
+      let value = self.parse_or()?;
+      if !self.consume(")") {
+        return Err(anyhow!("Invalid condition '{}': missing ')'", self.condition));
+      }
+      return Ok(value);
+    }
+    match self.tokens.get(self.pos) {
+      Some(Token::Literal(value)) => {
+        self.pos += 1;
+        Ok(value.clone())
+      }
+      Some(Token::Expression(expression)) => {
+        self.pos += 1;
+        self.resolver.resolve(expression)
+      }
+      Some(token) => Err(anyhow!("Invalid condition '{}': unexpected '{}'", self.condition, token)),
+      None => Err(anyhow!("Invalid condition '{}': unexpected end of condition", self.condition))
+    }
+  }
+}
+
+fn is_truthy(value: &AnyValue) -> bool {
+  !matches!(value, AnyValue::Null | AnyValue::Boolean(false))
+}
+
+fn number_value(value: &AnyValue) -> Option<f64> {
+  match value {
+    AnyValue::String(s) => s.trim().parse::<f64>().ok(),
+    AnyValue::BigNumber(n) => n.parse::<f64>().ok(),
+    _ => value.as_f64()
+  }
+}
+
+fn compare_values(left: &AnyValue, op: &str, right: &AnyValue) -> bool {
+  use std::cmp::Ordering;
+
+  let ordering = match (left, right) {
+    (AnyValue::String(a), AnyValue::String(b)) => Some(a.to_lowercase().cmp(&b.to_lowercase())),
+    (AnyValue::Null, AnyValue::Null) => Some(Ordering::Equal),
+    (AnyValue::Null, _) | (_, AnyValue::Null) => None,
+    (AnyValue::Boolean(a), AnyValue::Boolean(b)) => Some(a.cmp(b)),
+    (AnyValue::Boolean(a), AnyValue::String(b)) | (AnyValue::String(b), AnyValue::Boolean(a)) =>
+      if b.eq_ignore_ascii_case(&a.to_string()) { Some(Ordering::Equal) } else { None },
+    _ => match (number_value(left), number_value(right)) {
+      (Some(a), Some(b)) => a.partial_cmp(&b),
+      _ => if left == right { Some(Ordering::Equal) } else { None }
+    }
+  };
+  match op {
+    "==" => ordering == Some(Ordering::Equal),
+    "!=" => ordering != Some(Ordering::Equal),
+    "<" => ordering == Some(Ordering::Less),
+    "<=" => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+    ">" => ordering == Some(Ordering::Greater),
+    ">=" => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+    _ => false
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use expectest::prelude::*;
+  use maplit::hashmap;
+
+  use crate::criteria::{evaluate_condition, evaluate_criterion};
+  use crate::either::Either;
+  use crate::extensions::AnyValue;
+  use crate::v1_0::{Criterion, CriterionExpressionType};
+
+  fn values() -> HashMap<String, AnyValue> {
+    hashmap!{
+      "$statusCode".to_string() => AnyValue::Integer(200),
+      "$response.header.Content-Type".to_string() => AnyValue::String("application/json".to_string()),
+      "$response.body#/available".to_string() => AnyValue::Boolean(true),
+      "$response.body#/name".to_string() => AnyValue::String("Fido".to_string()),
+      "$response.body".to_string() => AnyValue::String("{\"pets\": [{\"id\": 1}], \"name\": \"Fido\"}".to_string()),
+      "$inputs.limit".to_string() => AnyValue::String("10".to_string())
+    }
+  }
+
+  #[test]
+  fn evaluates_simple_conditions() {
+    let values = values();
+    expect!(evaluate_condition("$statusCode == 200", &values).unwrap()).to(be_true());
+    expect!(evaluate_condition("$statusCode==201", &values).unwrap()).to(be_false());
+    expect!(evaluate_condition("$statusCode >= 200 && $statusCode < 300", &values).unwrap()).to(be_true());
+    expect!(evaluate_condition("$statusCode == 404 || $response.body#/available", &values).unwrap()).to(be_true());
+    expect!(evaluate_condition("!($statusCode == 200)", &values).unwrap()).to(be_false());
+    expect!(evaluate_condition("$response.body#/name == 'fido'", &values).unwrap()).to(be_true());
+    expect!(evaluate_condition("$response.body#/name != 'Tom'", &values).unwrap()).to(be_true());
+    expect!(evaluate_condition("$inputs.limit > 5", &values).unwrap()).to(be_true());
+    expect!(evaluate_condition("'it''s' == \"IT'S\"", &values).unwrap()).to(be_true());
+    expect!(evaluate_condition("$response.body#/available == true", &values).unwrap()).to(be_true());
+    expect!(evaluate_condition("null == null", &values).unwrap()).to(be_true());
+
+    expect!(evaluate_condition("$statusCode = 200", &values)).to(be_err());
+    expect!(evaluate_condition("($statusCode == 200", &values)).to(be_err());
+    expect!(evaluate_condition("$statusCode == ", &values)).to(be_err());
+    expect!(evaluate_condition("$statusCode 200", &values)).to(be_err());
+    expect!(evaluate_condition("$response.header.Location == 'a'", &values).unwrap_err().to_string())
+      .to(be_equal_to("Runtime expression '$response.header.Location' could not be resolved"));
+  }
+
+  #[test]
+  fn evaluates_regex_and_jsonpath_criteria() {
+    let values = values();
+    let criterion = Criterion {
+      context: Some("$statusCode".to_string()),
+      condition: "^2\\d\\d$".to_string(),
+      r#type: Some(Either::First("regex".to_string())),
+      extensions: Default::default()
+    };
+    expect!(evaluate_criterion(&criterion, &values).unwrap()).to(be_true());
+
+    let criterion = Criterion { condition: "(2\\d".to_string(), .. criterion };
+    expect!(evaluate_criterion(&criterion, &values)).to(be_err());
+
+    let criterion = Criterion {
+      context: Some("$response.body".to_string()),
+      condition: "$[?count(@.pets) > 0]".to_string(),
+      r#type: Some(Either::Second(CriterionExpressionType {
+        r#type: "jsonpath".to_string(),
+        version: "draft-goessner-dispatch-jsonpath-00".to_string(),
+        extensions: Default::default()
+      })),
+      extensions: Default::default()
+    };
+    expect!(evaluate_criterion(&criterion, &values).unwrap()).to(be_true());
+
+    let criterion = Criterion { condition: "$.pets[?@.id == 2]".to_string(), .. criterion };
+    expect!(evaluate_criterion(&criterion, &values).unwrap()).to(be_false());
+
+    let criterion = Criterion { context: None, .. criterion };
+    expect!(evaluate_criterion(&criterion, &values)).to(be_err());
+  }
+
+  #[test]
+  fn regex_criteria_match_large_values_in_linear_time() {
+    let body = format!("{}end", "x".repeat(100_000));
+    let values = hashmap!{
+      "$response.body".to_string() => AnyValue::String(body),
+      "$response.header.X-Value".to_string() => AnyValue::String("a".repeat(30))
+    };
+    let criterion = Criterion {
+      context: Some("$response.body".to_string()),
+      condition: ".*end".to_string(),
+      r#type: Some(Either::First("regex".to_string())),
+      extensions: Default::default()
+    };
+    expect!(evaluate_criterion(&criterion, &values).unwrap()).to(be_true());
+
+    let criterion = Criterion {
+      context: Some("$response.header.X-Value".to_string()),
+      condition: "^(a+)+b$".to_string(),
+      .. criterion
+    };
+    expect!(evaluate_criterion(&criterion, &values).unwrap()).to(be_false());
+  }
+
+  #[test]
+  #[cfg(feature = "xml")]
+  fn evaluates_xpath_criteria() {
+    let values = hashmap!{
+      "$response.body".to_string() => AnyValue::String("<pets><pet id='1'>Fido</pet></pets>".to_string())
+    };
+    let criterion = Criterion {
+      context: Some("$response.body".to_string()),
+      condition: "/pets/pet[@id='1']".to_string(),
+      r#type: Some(Either::First("xpath".to_string())),
+      extensions: Default::default()
+    };
+    expect!(evaluate_criterion(&criterion, &values).unwrap()).to(be_true());
+    let criterion = Criterion { condition: "/pets/pet[@id='2']".to_string(), .. criterion };
+    expect!(evaluate_criterion(&criterion, &values).unwrap()).to(be_false());
+    let criterion = Criterion { condition: "count(//pet) = 1".to_string(), .. criterion };
+    expect!(evaluate_criterion(&criterion, &values).unwrap()).to(be_true());
+  }
+}
@@ -0,0 +1,533 @@
+//! Generates shell scripts of curl commands from workflows, for debugging workflows and sharing
+//! the steps to reproduce a problem without the Rust tooling. Each step is a curl command that
+//! saves the response to a temporary directory, followed by the checks of the success criteria of
+//! the step and the outputs of the step, which are extracted from the response with
+//! [jq](https://jqlang.org). The scripts need curl 7.87 or later (for `--url-query`) and jq.
+//!
+//! Runtime expressions are converted to shell variables, in upper snake case: inputs are the name
+//! of the input (i.e. `$inputs.petId` is `PET_ID`), step outputs are the step ID and output name
+//! (i.e. `$steps.find.outputs.id` is `FIND_ID`) and the base URL of each source description is
+//! `<SOURCE NAME>_BASE_URL`. The inputs and base URLs are set as environment variables before
+//! running the script, and inputs with a default in the inputs schema are optional.
+//!
+//! The operations of the steps are taken from their operation paths. With the `execute` feature,
+//! the operations can also be resolved from the OpenAPI source descriptions (see
+//! [`curl_script_with_operations`]). Success criteria that can not be checked are added as
+//! comments.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use anyhow::anyhow;
+
+use crate::docs::{input_properties, step_parameters};
+use crate::either::Either;
+use crate::extensions::AnyValue;
+#[cfg(feature = "execute")] use crate::operations::OperationResolver;
+use crate::payloads::FormPayload;
+use crate::step_summary::{step_operation, StepOperation};
+use crate::v1_0::{ArazzoDescription, Criterion, ParameterObject, RequestBody, Step, Workflow};
+
+/// Shell function used to run the checks of the success criteria
+const CHECK_FUNCTION: &str = r#"check() {
+  description="$1"
+  shift
+  if ! "$@" > /dev/null 2>&1; then
+    echo "Check failed: $description" >&2
+    exit 1
+  fi
+}"#;
+
+/// Comparison operators of simple conditions, with the `test` and jq operators
+const OPERATORS: [(&str, &str, &str); 6] = [
+  (">=", "-ge", ">="),
+  ("<=", "-le", "<="),
+  ("==", "-eq", "=="),
+  ("!=", "-ne", "!="),
+  (">", "-gt", ">"),
+  ("<", "-lt", "<")
+];
+
+/// Generates a shell script of curl commands for the workflow, with the operations of the steps
+/// from their operation paths. Steps with an operation ID are added as comments, as the operation
+/// can not be resolved.
+pub fn curl_script(description: &ArazzoDescription, workflow_id: &str) -> anyhow::Result<String> {
+  render(description, workflow_id, &|step| step_operation(description, step))
+}
+
+/// Generates a shell script of curl commands for the workflow, resolving the operations of the
+/// steps from the OpenAPI source descriptions
+#[cfg(feature = "execute")]
+pub fn curl_script_with_operations(
+  description: &ArazzoDescription,
+  workflow_id: &str,
+  resolver: &OperationResolver
+) -> anyhow::Result<String> {
+  render(description, workflow_id, &|step| {
+    resolver.resolve_step(step).ok()
+      .map(|operation| StepOperation {
+        source_name: operation.source_name,
+        method: operation.method,
+        path: operation.path
+      })
+      .or_else(|| step_operation(description, step))
+  })
+}
+
+/// Environment variables used by the script, with their defaults
+#[derive(Debug, Default)]
+struct Variables {
+  names: Vec<String>,
+  defaults: HashMap<String, String>
+}
+
+impl Variables {
+  fn add(&mut self, name: &str) {
+    if !self.names.iter().any(|existing| existing == name) {
+      self.names.push(name.to_string());
+    }
+  }
+}
+
+fn render(
+  description: &ArazzoDescription,
+  workflow_id: &str,
+  resolve: &dyn Fn(&Step) -> Option<StepOperation>
+) -> anyhow::Result<String> {
+  let workflow = description.workflows.iter()
+    .find(|workflow| workflow.workflow_id == workflow_id)
+    .ok_or_else(|| anyhow!("No workflow with ID '{}' was found", workflow_id))?;
+
+  let mut variables = Variables::default();
+  for property in input_properties(description, workflow) {
+    if let Some(default) = property.default {
+      variables.defaults.insert(shell_name(&property.path), default);
+    }
+  }
+  let mut steps = String::new();
+  let mut stack = vec![workflow.workflow_id.clone()];
+  write_workflow(&mut steps, description, workflow, resolve, &mut variables, &mut stack)?;
+
+  let mut script = String::from("#!/bin/sh\n");
+  let _ = writeln!(script, "# {} - {}", description.info.title, workflow.workflow_id);
+  if let Some(summary) = &workflow.summary {
+    let _ = writeln!(script, "# {}", summary);
+  }
+  script.push_str("#\n# Generated from an Arazzo description. Requires curl 7.87 or later and jq.\n");
+  if !variables.names.is_empty() {
+    let _ = writeln!(script, "# Set these environment variables before running the script: {}", variables.names.join(", "));
+  }
+  script.push_str("set -eu\n\n");
+  for name in &variables.names {
+    match variables.defaults.get(name) {
+      Some(default) => { let _ = writeln!(script, ": \"${{{}:={}}}\"", name, escape(default)); }
+      None => { let _ = writeln!(script, ": \"${{{}:?is not set}}\"", name); }
+    }
+  }
+  if !variables.names.is_empty() {
+    script.push('\n');
+  }
+  script.push_str("WORKDIR=$(mktemp -d)\ntrap 'rm -rf \"$WORKDIR\"' EXIT\n\n");
+  script.push_str(CHECK_FUNCTION);
+  script.push_str("\n\n");
+  script.push_str(steps.trim_end());
+  script.push('\n');
+  Ok(script)
+}
+
+fn write_workflow(
+  output: &mut String,
+  description: &ArazzoDescription,
+  workflow: &Workflow,
+  resolve: &dyn Fn(&Step) -> Option<StepOperation>,
+  variables: &mut Variables,
+  stack: &mut Vec<String>
+) -> anyhow::Result<()> {
+  for step in &workflow.steps {
+    let parameters = step_parameters(description, workflow, step)?;
+    if let Some(workflow_id) = &step.workflow_id {
+      let _ = writeln!(output, "# Step '{}': workflow '{}'", step.step_id, workflow_id);
+      match description.workflows.iter().find(|workflow| workflow.workflow_id == *workflow_id) {
+        Some(target) if !stack.contains(&target.workflow_id) => {
+          for (parameter, value) in &parameters {
+            let _ = writeln!(output, "{}=\"{}\"", shell_name(&parameter.name), template(value, variables));
+          }
+          output.push('\n');
+          stack.push(target.workflow_id.clone());
+          write_workflow(output, description, target, resolve, variables, stack)?;
+          stack.pop();
+        }
+        Some(_) => { let _ = writeln!(output, "# The workflow calls itself, and is not inlined again\n"); }
+        None => { let _ = writeln!(output, "# The workflow is not in this description, and is not inlined\n"); }
+      }
+      continue;
+    }
+
+    let Some(operation) = resolve(step) else {
+      let target = step.operation_id.as_ref().or(step.operation_path.as_ref()).cloned().unwrap_or_default();
+      let _ = writeln!(output, "# Step '{}' is skipped, as the operation '{}' could not be resolved\n", step.step_id, target);
+      continue;
+    };
+    write_step(output, step, &operation, &parameters, variables);
+  }
+  Ok(())
+}
+
+fn write_step(
+  output: &mut String,
+  step: &Step,
+  operation: &StepOperation,
+  parameters: &[(ParameterObject, String)],
+  variables: &mut Variables
+) {
+  let _ = writeln!(output, "# Step '{}'", step.step_id);
+  if let Some(description) = &step.description {
+    for line in description.lines() {
+      let _ = writeln!(output, "# {}", line);
+    }
+  }
+
+  let prefix = shell_name(&step.step_id);
+  let file = format!("\"$WORKDIR/{}", step.step_id.chars().map(|ch| if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' { ch } else { '_' }).collect::<String>());
+  let base_url = format!("{}_BASE_URL", shell_name(&operation.source_name));
+  variables.add(&base_url);
+
+  let mut path = escape(&operation.path);
+  let mut options = vec![];
+  for (parameter, value) in parameters {
+    let value = template(value, variables);
+    match parameter.r#in.as_deref() {
+      Some("path") => path = path.replace(&format!("{{{}}}", parameter.name), &value),
+      Some("query") => options.push(format!("--url-query \"{}={}\"", escape(&parameter.name), value)),
+      Some("header") => options.push(format!("-H \"{}: {}\"", escape(&parameter.name), value)),
+      Some("cookie") => options.push(format!("-b \"{}={}\"", escape(&parameter.name), value)),
+      _ => {}
+    }
+  }
+  if let Some(body) = &step.request_body {
+    body_options(body, variables, &mut options);
+  }
+
+  let _ = write!(output, "{}_STATUS=$(curl -sS -o {}.body\" -D {}.headers\" -w '%{{http_code}}' \\\n  -X {} \"${{{}}}{}\"",
+    prefix, file, file, operation.method.to_uppercase(), base_url, path);
+  for option in options {
+    let _ = write!(output, " \\\n  {}", option);
+  }
+  output.push_str(")\n");
+
+  for criterion in &step.success_criteria {
+    match criterion_check(criterion, &prefix, &file) {
+      Some(checks) => {
+        for check in checks {
+          let _ = writeln!(output, "check {} {}", single_quote(&criterion.condition), check);
+        }
+      }
+      None => { let _ = writeln!(output, "# Not checked: {}", criterion.condition); }
+    }
+  }
+  for (name, expression) in &step.outputs {
+    let variable = format!("{}_{}", prefix, shell_name(name));
+    match output_command(expression, &prefix, &file) {
+      Some(command) => { let _ = writeln!(output, "{}={}", variable, command); }
+      None => { let _ = writeln!(output, "# Output '{}' is not extracted: {}", name, expression); }
+    }
+  }
+  output.push('\n');
+}
+
+fn body_options(body: &RequestBody, variables: &mut Variables, options: &mut Vec<String>) {
+  if let Some(content_type) = body.effective_content_type() {
+    options.push(format!("-H \"Content-Type: {}\"", escape(&content_type)));
+  }
+  if let Some(form) = body.payload_as::<FormPayload>() {
+    for (name, value) in &form.0 {
+      options.push(format!("--data-urlencode \"{}={}\"", escape(name), template(&value.to_text().unwrap_or_default(), variables)));
+    }
+    return;
+  }
+  let Some(payload) = &body.payload else {
+    return;
+  };
+  let text = match payload.as_json() {
+    Some(mut json) => {
+      for replacement in &body.replacements {
+        let value = match &replacement.value {
+          Either::First(AnyValue::String(value)) => serde_json::Value::String(value.clone()),
+          Either::First(value) => value.to_json(),
+          Either::Second(expression) => serde_json::Value::String(format!("{{{}}}", expression))
+        };
+        if let Some(target) = json.pointer_mut(&replacement.target) {
+          *target = value;
+        }
+      }
+      json.to_string()
+    }
+    None => payload.as_string()
+  };
+  options.push(format!("--data-binary \"{}\"", template(&text, variables)));
+}
+
+/// Commands to check the criterion, or `None` if it can not be checked
+fn criterion_check(criterion: &Criterion, prefix: &str, file: &str) -> Option<Vec<String>> {
+  let criterion_type = match &criterion.r#type {
+    Some(Either::First(criterion_type)) => criterion_type.as_str(),
+    Some(Either::Second(expression_type)) => expression_type.r#type.as_str(),
+    None => "simple"
+  };
+  match (criterion_type, criterion.context.as_deref()) {
+    ("simple", None) if !criterion.condition.contains("||") => criterion.condition.split("&&")
+      .map(|part| simple_check(part.trim(), prefix, file))
+      .collect(),
+    ("regex", Some("$response.body")) => Some(vec![format!("grep -Eq {} {}.body\"", single_quote(&criterion.condition), file)]),
+    _ => None
+  }
+}
+
+fn simple_check(condition: &str, prefix: &str, file: &str) -> Option<String> {
+  let operator = condition.char_indices()
+    .find_map(|(index, _)| OPERATORS.iter().find(|(operator, _, _)| condition[index..].starts_with(operator)).map(|operator| (index, operator)));
+  let Some((index, (operator, test_operator, jq_operator))) = operator else {
+    let pointer = condition.strip_prefix("$response.body#")?;
+    return Some(format!("jq -e {} {}.body\"", single_quote(&format!("{} != null", jq_path(pointer))), file));
+  };
+  let expression = condition[..index].trim();
+  let literal = condition[index + operator.len()..].trim();
+  if expression == "$statusCode" {
+    literal.parse::<u16>().ok()
+      .map(|status| format!("test \"${}_STATUS\" {} {}", prefix, test_operator, status))
+  } else {
+    let pointer = expression.strip_prefix("$response.body#")?;
+    let literal = match literal.strip_prefix('\'').and_then(|literal| literal.strip_suffix('\'')) {
+      Some(text) => serde_json::Value::String(text.to_string()).to_string(),
+      None => literal.to_string()
+    };
+    Some(format!("jq -e {} {}.body\"", single_quote(&format!("{} {} {}", jq_path(pointer), jq_operator, literal)), file))
+  }
+}
+
+/// Command substitution to extract the output from the response
+fn output_command(expression: &str, prefix: &str, file: &str) -> Option<String> {
+  if expression == "$statusCode" {
+    Some(format!("\"${}_STATUS\"", prefix))
+  } else if expression == "$response.body" {
+    Some(format!("$(cat {}.body\")", file))
+  } else if let Some(pointer) = expression.strip_prefix("$response.body#") {
+    Some(format!("$(jq -r {} {}.body\")", single_quote(&jq_path(pointer)), file))
+  } else {
+    expression.strip_prefix("$response.header.").map(|header| {
+      format!("$(grep -i {} {}.headers\" | head -n 1 | cut -d: -f2- | sed 's/^ *//' | tr -d '\\r')",
+        single_quote(&format!("^{}:", header)), file)
+    })
+  }
+}
+
+/// Converts a JSON Pointer to a jq path
+fn jq_path(pointer: &str) -> String {
+  let mut path = String::new();
+  for segment in pointer.split('/').skip(1) {
+    let segment = segment.replace("~1", "/").replace("~0", "~");
+    if segment.parse::<usize>().is_ok() {
+      let _ = write!(path, "[{}]", segment);
+    } else if !segment.is_empty() && segment.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+      && !segment.starts_with(|ch: char| ch.is_ascii_digit()) {
+      let _ = write!(path, ".{}", segment);
+    } else {
+      let _ = write!(path, ".[{}]", serde_json::Value::String(segment));
+    }
+  }
+  if path.is_empty() || path.starts_with('[') { format!(".{}", path) } else { path }
+}
+
+/// Converts the runtime expression, or the expressions embedded in the text, to shell variables,
+/// escaping the rest of the text for a double quoted string
+fn template(text: &str, variables: &mut Variables) -> String {
+  if text.trim().starts_with('$') {
+    return format!("${{{}}}", expression_variable(text.trim(), variables));
+  }
+  let mut result = String::new();
+  let mut rest = text;
+  while let Some(start) = rest.find("{$") {
+    let Some(end) = rest[start..].find('}') else {
+      break;
+    };
+    result.push_str(&escape(&rest[..start]));
+    let _ = write!(result, "${{{}}}", expression_variable(&rest[start + 1..start + end], variables));
+    rest = &rest[start + end + 1..];
+  }
+  result.push_str(&escape(rest));
+  result
+}
+
+/// Name of the shell variable for a runtime expression. Inputs (and expressions that are not step
+/// outputs) are added to the environment variables of the script.
+fn expression_variable(expression: &str, variables: &mut Variables) -> String {
+  if let Some((step, output)) = expression.strip_prefix("$steps.").and_then(|rest| rest.split_once(".outputs.")) {
+    format!("{}_{}", shell_name(step), shell_name(output))
+  } else {
+    let name = shell_name(expression.strip_prefix("$inputs.").unwrap_or(expression.trim_start_matches('$')));
+    variables.add(&name);
+    name
+  }
+}
+
+/// Converts the name to upper snake case (i.e. `petId` is `PET_ID`)
+fn shell_name(name: &str) -> String {
+  let mut result = String::new();
+  let mut previous_lower = false;
+  for ch in name.chars() {
+    if ch.is_ascii_alphanumeric() {
+      if ch.is_ascii_uppercase() && previous_lower {
+        result.push('_');
+      }
+      result.push(ch.to_ascii_uppercase());
+      previous_lower = ch.is_ascii_lowercase() || ch.is_ascii_digit();
+    } else {
+      if !result.ends_with('_') {
+        result.push('_');
+      }
+      previous_lower = false;
+    }
+  }
+  if result.starts_with(|ch: char| ch.is_ascii_digit()) {
+    result.insert(0, '_');
+  }
+  result
+}
+
+/// Escapes the text for a double quoted shell string
+fn escape(text: &str) -> String {
+  let mut result = String::new();
+  for ch in text.chars() {
+    if matches!(ch, '"' | '\\' | '$' | '`') {
+      result.push('\\');
+    }
+    result.push(ch);
+  }
+  result
+}
+
+/// Quotes the text as a single quoted shell string
+fn single_quote(text: &str) -> String {
+  format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeMap;
+  use std::sync::Arc;
+
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::curl::{curl_script, jq_path, shell_name};
+  use crate::either::Either;
+  use crate::extensions::AnyValue;
+  use crate::payloads::JsonPayload;
+  use crate::v1_0::{ArazzoDescription, Criterion, Info, ParameterObject, RequestBody, Step, Workflow};
+
+  #[test]
+  fn generates_a_script_of_curl_commands() {
+    let description = ArazzoDescription {
+      info: Info { title: "Pet Store".to_string(), .. Info::default() },
+      workflows: vec![Workflow {
+        workflow_id: "adopt".to_string(),
+        inputs: json!({ "type": "object", "properties": { "petId": { "type": "string", "default": "1" } } }),
+        steps: vec![
+          Step {
+            step_id: "find".to_string(),
+            operation_path: Some("{$sourceDescriptions.petstore.url}#/paths/~1pets~1{petId}/get".to_string()),
+            parameters: vec![
+              Either::First(ParameterObject {
+                name: "petId".to_string(),
+                r#in: Some("path".to_string()),
+                value: Either::Second("$inputs.petId".to_string()),
+                .. ParameterObject::default()
+              }),
+              Either::First(ParameterObject {
+                name: "Authorization".to_string(),
+                r#in: Some("header".to_string()),
+                value: Either::First(AnyValue::String("Bearer {$inputs.token}".to_string())),
+                .. ParameterObject::default()
+              })
+            ],
+            success_criteria: vec![
+              Criterion { condition: "$statusCode == 200 && $response.body#/status == 'available'".to_string(), .. Criterion::default() },
+              Criterion { condition: "$.tags[?(@ == 'cute')]".to_string(), context: Some("$response.body".to_string()),
+                r#type: Some(Either::First("jsonpath".to_string())), .. Criterion::default() }
+            ],
+            outputs: BTreeMap::from([
+              ("id".to_string(), "$response.body#/id".to_string()),
+              ("location".to_string(), "$response.header.Location".to_string())
+            ]),
+            .. Step::default()
+          },
+          Step {
+            step_id: "adopt".to_string(),
+            operation_path: Some("{$sourceDescriptions.petstore.url}#/paths/~1adoptions/post".to_string()),
+            request_body: Some(RequestBody {
+              content_type: None,
+              payload: Some(Arc::new(JsonPayload(json!({ "pet": "{$steps.find.outputs.id}" })))),
+              replacements: vec![],
+              extensions: Default::default()
+            }),
+            .. Step::default()
+          }
+        ],
+        .. Workflow::default()
+      }],
+      .. ArazzoDescription::default()
+    };
+
+    expect!(curl_script(&description, "adopt").unwrap()).to(be_equal_to(
+r#"#!/bin/sh
+# Pet Store - adopt
+#
+# Generated from an Arazzo description. Requires curl 7.87 or later and jq.
+# Set these environment variables before running the script: PETSTORE_BASE_URL, PET_ID, TOKEN
+set -eu
+
+: "${PETSTORE_BASE_URL:?is not set}"
+: "${PET_ID:=1}"
+: "${TOKEN:?is not set}"
+
+WORKDIR=$(mktemp -d)
+trap 'rm -rf "$WORKDIR"' EXIT
+
+check() {
+  description="$1"
+  shift
+  if ! "$@" > /dev/null 2>&1; then
+    echo "Check failed: $description" >&2
+    exit 1
+  fi
+}
+
+# Step 'find'
+FIND_STATUS=$(curl -sS -o "$WORKDIR/find.body" -D "$WORKDIR/find.headers" -w '%{http_code}' \
+  -X GET "${PETSTORE_BASE_URL}/pets/${PET_ID}" \
+  -H "Authorization: Bearer ${TOKEN}")
+check '$statusCode == 200 && $response.body#/status == '\''available'\''' test "$FIND_STATUS" -eq 200
+check '$statusCode == 200 && $response.body#/status == '\''available'\''' jq -e '.status == "available"' "$WORKDIR/find.body"
+# Not checked: $.tags[?(@ == 'cute')]
+FIND_ID=$(jq -r '.id' "$WORKDIR/find.body")
+FIND_LOCATION=$(grep -i '^Location:' "$WORKDIR/find.headers" | head -n 1 | cut -d: -f2- | sed 's/^ *//' | tr -d '\r')
+
+# Step 'adopt'
+ADOPT_STATUS=$(curl -sS -o "$WORKDIR/adopt.body" -D "$WORKDIR/adopt.headers" -w '%{http_code}' \
+  -X POST "${PETSTORE_BASE_URL}/adoptions" \
+  -H "Content-Type: application/json" \
+  --data-binary "{\"pet\":\"${FIND_ID}\"}")
+"#.to_string()));
+    expect!(curl_script(&description, "missing")).to(be_err());
+  }
+
+  #[test]
+  fn converts_names_and_pointers() {
+    expect!(shell_name("petId")).to(be_equal_to("PET_ID"));
+    expect!(shell_name("get-owner")).to(be_equal_to("GET_OWNER"));
+    expect!(shell_name("2fa")).to(be_equal_to("_2FA"));
+    expect!(jq_path("")).to(be_equal_to("."));
+    expect!(jq_path("/items/0/the id")).to(be_equal_to(".items[0].[\"the id\"]"));
+    expect!(jq_path("/0")).to(be_equal_to(".[0]"));
+  }
+}
@@ -0,0 +1,223 @@
+//! Datasets for data-driven workflow runs. The [`Executor`](crate::executor::Executor) runs the
+//! workflow once for each row of the dataset (see
+//! [`Executor::execute_dataset`](crate::executor::Executor::execute_dataset)), with the columns
+//! of the row as the inputs.
+//!
+//! Datasets are CSV files with a header row, or NDJSON files with an object on each line. Column
+//! names with dots set nested inputs (i.e. `user.name`), and CSV values are converted to the types
+//! in the inputs schema of the workflow.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use serde_json::{Map, Value};
+
+use crate::executor::WorkflowResult;
+use crate::report::ExecutionReport;
+
+/// Rows of input values
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dataset {
+  /// Rows, each an object of column values
+  pub rows: Vec<Map<String, Value>>
+}
+
+impl Dataset {
+  /// Loads a dataset from a CSV (`.csv`) or NDJSON (`.ndjson` or `.jsonl`) file
+  pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path)
+      .with_context(|| format!("Failed to load dataset from '{}'", path.display()))?;
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default().to_ascii_lowercase();
+    let dataset = match extension.as_str() {
+      "csv" => Dataset::from_csv(&contents),
+      "ndjson" | "jsonl" => Dataset::from_ndjson(&contents),
+      _ => Err(anyhow!("The dataset must be a .csv, .ndjson or .jsonl file"))
+    };
+    dataset.with_context(|| format!("Failed to load dataset from '{}'", path.display()))
+  }
+
+  /// Parses a CSV dataset. The first row has the column names. Fields can be quoted with `"`
+  /// (with `""` for a quote in a quoted field), and empty fields are left out of the row.
+  pub fn from_csv(csv: &str) -> anyhow::Result<Self> {
+    let mut records = parse_csv(csv)?.into_iter();
+    let header = records.next().ok_or_else(|| anyhow!("The CSV dataset has no header row"))?;
+    let rows = records
+      .enumerate()
+      .map(|(index, record)| {
+        if record.len() > header.len() {
+          return Err(anyhow!("Row {} has {} fields, but there are only {} columns", index + 1, record.len(), header.len()));
+        }
+        Ok(header.iter().zip(record)
+          .filter(|(_, value)| !value.is_empty())
+          .map(|(column, value)| (column.clone(), Value::String(value)))
+          .collect())
+      })
+      .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Dataset { rows })
+  }
+
+  /// Parses a NDJSON dataset, with a JSON object on each line. Blank lines are ignored.
+  pub fn from_ndjson(ndjson: &str) -> anyhow::Result<Self> {
+    let rows = ndjson.lines()
+      .enumerate()
+      .filter(|(_, line)| !line.trim().is_empty())
+      .map(|(index, line)| match serde_json::from_str(line) {
+        Ok(Value::Object(row)) => Ok(row),
+        Ok(_) => Err(anyhow!("Line {} is not a JSON object", index + 1)),
+        Err(err) => Err(anyhow!("Line {} is not valid JSON: {}", index + 1, err))
+      })
+      .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Dataset { rows })
+  }
+
+  /// Returns the inputs for the row. Columns are renamed with the mapping (columns that are not
+  /// mapped keep their name), and names with dots set nested values. Returns an error if the row
+  /// does not exist, or if columns are mapped to the same input (i.e. `user` and `user.name`).
+  pub fn row_inputs(&self, index: usize, mapping: &BTreeMap<String, String>) -> anyhow::Result<Value> {
+    let row = self.rows.get(index)
+      .ok_or_else(|| anyhow!("Row {} does not exist, the dataset has {} rows", index + 1, self.rows.len()))?;
+    let mut inputs = Map::new();
+    for (column, value) in row {
+      let name = mapping.get(column).unwrap_or(column);
+      let conflict = || anyhow!("Column '{}' is mapped to input '{}', which is already set by another column", column, name);
+      let mut target = &mut inputs;
+      let mut parts = name.split('.').peekable();
+      while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+          if target.insert(part.to_string(), value.clone()).is_some() {
+            return Err(conflict());
+          }
+        } else {
+          target = target.entry(part.to_string())
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or_else(conflict)?;
+        }
+      }
+    }
+    Ok(Value::Object(inputs))
+  }
+}
+
+fn parse_csv(csv: &str) -> anyhow::Result<Vec<Vec<String>>> {
+  let mut records = vec![];
+  let mut record = vec![];
+  let mut field = String::new();
+  let mut quoted = false;
+  let mut chars = csv.chars().peekable();
+  while let Some(ch) = chars.next() {
+    match ch {
+      '"' if quoted && chars.peek() == Some(&'"') => {
+        field.push('"');
+        chars.next();
+      }
+      '"' if quoted => quoted = false,
+      '"' if field.is_empty() => quoted = true,
+      ',' if !quoted => record.push(std::mem::take(&mut field)),
+      '\r' if !quoted => {}
+      '\n' if !quoted => {
+        record.push(std::mem::take(&mut field));
+        if record.len() > 1 || !record[0].is_empty() {
+          records.push(std::mem::take(&mut record));
+        } else {
+          record.clear();
+        }
+      }
+      _ => field.push(ch)
+    }
+  }
+  if quoted {
+    return Err(anyhow!("The CSV dataset has an unterminated quoted field"));
+  }
+  if !field.is_empty() || !record.is_empty() {
+    record.push(field);
+    records.push(record);
+  }
+  Ok(records)
+}
+
+/// Options for data-driven runs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DatasetOptions {
+  /// Stop after the first row that does not complete successfully (otherwise all the rows are run)
+  pub fail_fast: bool,
+  /// Input names for the columns of the dataset, keyed by the column name
+  pub column_mapping: BTreeMap<String, String>
+}
+
+/// Outcome of running the workflow for a row of the dataset
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowResult {
+  /// Index of the row (starting at 0)
+  pub row: usize,
+  /// Inputs the workflow was run with
+  pub inputs: Value,
+  /// Result of the workflow
+  pub result: WorkflowResult
+}
+
+/// Outcome of a data-driven run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DatasetResult {
+  /// Results of the rows that were run, in order
+  pub rows: Vec<RowResult>,
+  /// Number of rows that were not run (because of `fail_fast`)
+  pub skipped: usize
+}
+
+impl DatasetResult {
+  /// If the workflow completed successfully for all the rows
+  pub fn is_success(&self) -> bool {
+    self.skipped == 0 && self.rows.iter().all(|row| row.result.is_success())
+  }
+
+  /// Returns a report with a workflow for each row (the workflow ID has the row number appended,
+  /// i.e. `login [row 2]`, with rows numbered from 1)
+  pub fn report(&self) -> ExecutionReport {
+    let results = self.rows.iter()
+      .map(|row| WorkflowResult {
+        workflow_id: format!("{} [row {}]", row.result.workflow_id, row.row + 1),
+        .. row.result.clone()
+      })
+      .collect::<Vec<_>>();
+    ExecutionReport::new(&results)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::btreemap;
+  use serde_json::json;
+
+  use crate::dataset::Dataset;
+
+  #[test]
+  fn parses_csv_datasets() {
+    let dataset = Dataset::from_csv("name,user.id,notes\r\nfred,1,\"says \"\"hi\"\", twice\"\n\nwilma,,\"two\nlines\"\n").unwrap();
+    expect!(dataset.rows.len()).to(be_equal_to(2));
+    expect!(dataset.row_inputs(0, &Default::default())).to(be_ok().value(json!({
+      "name": "fred", "user": { "id": "1" }, "notes": "says \"hi\", twice"
+    })));
+    expect!(dataset.row_inputs(1, &btreemap!{ "name".to_string() => "user.name".to_string() })).to(be_ok().value(json!({
+      "user": { "name": "wilma" }, "notes": "two\nlines"
+    })));
+    expect!(dataset.row_inputs(2, &Default::default()).unwrap_err().to_string())
+      .to(be_equal_to("Row 3 does not exist, the dataset has 2 rows"));
+    expect!(dataset.row_inputs(0, &btreemap!{ "name".to_string() => "user".to_string() }).unwrap_err().to_string())
+      .to(be_equal_to("Column 'user.id' is mapped to input 'user.id', which is already set by another column"));
+    expect!(Dataset::from_csv("a\n1,2")).to(be_err());
+    expect!(Dataset::from_csv("a\n\"1")).to(be_err());
+  }
+
+  #[test]
+  fn parses_ndjson_datasets() {
+    let dataset = Dataset::from_ndjson("{\"id\": 1}\n\n{\"id\": 2, \"tags\": [\"a\"]}\n").unwrap();
+    expect!(dataset.rows.len()).to(be_equal_to(2));
+    expect!(dataset.row_inputs(1, &Default::default())).to(be_ok().value(json!({ "id": 2, "tags": ["a"] })));
+    expect!(Dataset::from_ndjson("[1]").unwrap_err().to_string()).to(be_equal_to("Line 1 is not a JSON object"));
+  }
+}
@@ -0,0 +1,480 @@
+//! Generates Markdown documentation for an Arazzo description. The document has an overview from
+//! the Info Object and the source descriptions, followed by a section for each workflow with
+//! tables of its inputs (from the JSON Schema), steps (with the operation, parameters and success
+//! criteria of each step) and outputs.
+
+use std::fmt::Write;
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::either::Either;
+use crate::extensions::AnyValue;
+use crate::inputs::{resolve_schema, workflow_schema};
+use crate::render::{mermaid_flowchart_with_options, MermaidOptions};
+use crate::v1_0::{ArazzoDescription, Criterion, ParameterObject, ReusableObject, Step, Workflow};
+
+/// Options for generating the Markdown documentation
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkdownOptions {
+  /// Level of the title heading (the other headings are nested under it)
+  pub heading_level: usize,
+  /// If a list of the workflows (linked to their sections) is added after the overview
+  pub table_of_contents: bool,
+  /// If a Mermaid flowchart of each workflow is added to its section
+  pub flowcharts: bool
+}
+
+impl Default for MarkdownOptions {
+  fn default() -> Self {
+    MarkdownOptions {
+      heading_level: 1,
+      table_of_contents: true,
+      flowcharts: false
+    }
+  }
+}
+
+/// Generates Markdown documentation for the Arazzo description, with the default options
+pub fn markdown(description: &ArazzoDescription) -> String {
+  markdown_with_options(description, &MarkdownOptions::default())
+}
+
+/// Generates Markdown documentation for the Arazzo description
+pub fn markdown_with_options(description: &ArazzoDescription, options: &MarkdownOptions) -> String {
+  let level = options.heading_level.clamp(1, 4);
+  let mut doc = String::new();
+
+  let _ = writeln!(doc, "{} {}\n", heading(level), description.info.title);
+  paragraph(&mut doc, description.info.summary.as_deref());
+  paragraph(&mut doc, description.info.description.as_deref());
+  let _ = writeln!(doc, "**Version:** {}\n", description.info.version);
+
+  if !description.source_descriptions.is_empty() {
+    let _ = writeln!(doc, "{} Source Descriptions\n", heading(level + 1));
+    let rows = description.source_descriptions.iter()
+      .map(|source| vec![
+        code(&source.name),
+        source.r#type.clone().unwrap_or_default(),
+        source.url.clone()
+      ])
+      .collect::<Vec<_>>();
+    table(&mut doc, &["Name", "Type", "URL"], &rows);
+  }
+
+  if options.table_of_contents && !description.workflows.is_empty() {
+    let _ = writeln!(doc, "{} Workflows\n", heading(level + 1));
+    for workflow in &description.workflows {
+      let _ = write!(doc, "- [{}](#{})", workflow.workflow_id, anchor(&workflow.workflow_id));
+      if let Some(summary) = &workflow.summary {
+        let _ = write!(doc, ": {}", summary);
+      }
+      doc.push('\n');
+    }
+    doc.push('\n');
+  }
+
+  for workflow in &description.workflows {
+    workflow_section(&mut doc, description, workflow, level + 1, options);
+  }
+
+  let trimmed = doc.trim_end().len();
+  doc.truncate(trimmed);
+  doc.push('\n');
+  doc
+}
+
+fn workflow_section(
+  doc: &mut String,
+  description: &ArazzoDescription,
+  workflow: &Workflow,
+  level: usize,
+  options: &MarkdownOptions
+) {
+  let _ = writeln!(doc, "{} {}\n", heading(level), workflow.workflow_id);
+  paragraph(doc, workflow.summary.as_deref());
+  paragraph(doc, workflow.description.as_deref());
+  if !workflow.depends_on.is_empty() {
+    let depends_on = workflow.depends_on.iter().map(|id| code(id)).collect::<Vec<_>>();
+    let _ = writeln!(doc, "**Depends on:** {}\n", depends_on.join(", "));
+  }
+
+  if options.flowcharts {
+    let mermaid = MermaidOptions { fenced: true, .. MermaidOptions::default() };
+    if let Ok(flowchart) = mermaid_flowchart_with_options(description, &workflow.workflow_id, &mermaid) {
+      let _ = writeln!(doc, "{}", flowchart);
+    }
+  }
+
+  let inputs = input_properties(description, workflow).into_iter()
+    .map(input_row)
+    .collect::<Vec<_>>();
+  if !inputs.is_empty() {
+    let _ = writeln!(doc, "{} Inputs\n", heading(level + 1));
+    table(doc, &["Name", "Type", "Required", "Description"], &inputs);
+  }
+
+  if !workflow.steps.is_empty() {
+    let _ = writeln!(doc, "{} Steps\n", heading(level + 1));
+    let rows = workflow.steps.iter()
+      .map(step_row)
+      .collect::<Vec<_>>();
+    table(doc, &["Step", "Operation", "Description", "Parameters", "Success Criteria"], &rows);
+  }
+
+  if !workflow.outputs.is_empty() {
+    let _ = writeln!(doc, "{} Outputs\n", heading(level + 1));
+    let rows = workflow.outputs.iter()
+      .map(|(name, expression)| vec![code(name), code(expression)])
+      .collect::<Vec<_>>();
+    table(doc, &["Name", "Value"], &rows);
+  }
+}
+
+/// Property of the inputs schema of a workflow
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct InputProperty {
+  /// Name of the property, with dots for nested properties (i.e. `user.name`)
+  pub(crate) path: String,
+  /// Type of the property (see [`schema_type`])
+  pub(crate) schema_type: String,
+  /// If the property is required (and so are all the properties it is nested in)
+  pub(crate) required: bool,
+  /// Description from the schema
+  pub(crate) description: Option<String>,
+  /// Allowed values (`enum`)
+  pub(crate) values: Vec<String>,
+  /// Default value
+  pub(crate) default: Option<String>
+}
+
+/// Returns the properties of the inputs schema of the workflow, with the properties of nested
+/// objects after the object
+pub(crate) fn input_properties(description: &ArazzoDescription, workflow: &Workflow) -> Vec<InputProperty> {
+  let mut properties = vec![];
+  if let Ok(Some(root)) = workflow_schema(description, &workflow.workflow_id) {
+    let schema = resolve_schema(&root, &root);
+    collect_properties(&root, schema, "", true, &mut properties);
+  }
+  properties
+}
+
+fn collect_properties(root: &Value, schema: &Value, prefix: &str, required: bool, result: &mut Vec<InputProperty>) {
+  let Some(properties) = schema.get("properties").and_then(|properties| properties.as_object()) else {
+    return;
+  };
+  let required_properties = schema.get("required")
+    .and_then(|required| required.as_array())
+    .map(|required| required.iter().filter_map(|name| name.as_str()).collect::<Vec<_>>())
+    .unwrap_or_default();
+  for (name, property) in properties {
+    let property = resolve_schema(root, property);
+    let path = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+    let is_required = required && required_properties.contains(&name.as_str());
+    result.push(InputProperty {
+      path: path.clone(),
+      schema_type: schema_type(property),
+      required: is_required,
+      description: property.get("description").and_then(|description| description.as_str()).map(|d| d.to_string()),
+      values: property.get("enum").and_then(|values| values.as_array())
+        .map(|values| values.iter().map(json_text).collect())
+        .unwrap_or_default(),
+      default: property.get("default").map(json_text)
+    });
+    collect_properties(root, property, &path, is_required, result);
+  }
+}
+
+fn input_row(property: InputProperty) -> Vec<String> {
+  let mut description = property.description.into_iter().collect::<Vec<_>>();
+  if !property.values.is_empty() {
+    let values = property.values.iter().map(|value| code(value)).collect::<Vec<_>>();
+    description.push(format!("One of: {}", values.join(", ")));
+  }
+  if let Some(default) = &property.default {
+    description.push(format!("Default: {}", code(default)));
+  }
+  vec![
+    code(&property.path),
+    property.schema_type,
+    if property.required { "yes".to_string() } else { "no".to_string() },
+    description.join(" ")
+  ]
+}
+
+/// Type of the schema, with the format (i.e. `string (date-time)`), or the item type of arrays
+/// (i.e. `array of string`)
+fn schema_type(schema: &Value) -> String {
+  let schema_type = match schema.get("type") {
+    Some(Value::String(schema_type)) => schema_type.clone(),
+    Some(Value::Array(types)) => types.iter().filter_map(|t| t.as_str()).collect::<Vec<_>>().join(" | "),
+    _ if schema.get("enum").is_some() => "enum".to_string(),
+    _ => return String::new()
+  };
+  if let Some(format) = schema.get("format").and_then(|format| format.as_str()) {
+    format!("{} ({})", schema_type, format)
+  } else if schema_type == "array" {
+    match schema.get("items").map(schema_type_of_items) {
+      Some(items) if !items.is_empty() => format!("array of {}", items),
+      _ => schema_type
+    }
+  } else {
+    schema_type
+  }
+}
+
+fn schema_type_of_items(items: &Value) -> String {
+  items.get("type").and_then(|t| t.as_str()).unwrap_or_default().to_string()
+}
+
+fn step_row(step: &Step) -> Vec<String> {
+  let operation = match (&step.operation_id, &step.operation_path, &step.workflow_id) {
+    (Some(operation_id), _, _) => code(operation_id),
+    (None, Some(operation_path), _) => code(operation_path),
+    (None, None, Some(workflow_id)) => format!("workflow {}", code(workflow_id)),
+    _ => String::new()
+  };
+  let parameters = step.parameters.iter()
+    .map(|parameter| match parameter {
+      Either::First(parameter) => parameter_text(parameter),
+      Either::Second(reusable) => reusable_text(reusable)
+    })
+    .collect::<Vec<_>>();
+  let criteria = step.success_criteria.iter().map(criterion_text).collect::<Vec<_>>();
+  vec![
+    code(&step.step_id),
+    operation,
+    step.description.clone().unwrap_or_default(),
+    parameters.join("<br/>"),
+    criteria.join("<br/>")
+  ]
+}
+
+fn parameter_text(parameter: &ParameterObject) -> String {
+  let value = parameter_value(parameter);
+  match &parameter.r#in {
+    Some(location) => format!("{} ({}): {}", code(&parameter.name), location, code(&value)),
+    None => format!("{}: {}", code(&parameter.name), code(&value))
+  }
+}
+
+/// Value of the parameter as text (strings and expressions are not quoted)
+pub(crate) fn parameter_value(parameter: &ParameterObject) -> String {
+  match &parameter.value {
+    Either::First(AnyValue::String(value)) => value.clone(),
+    Either::First(value) => value.to_json().to_string(),
+    Either::Second(expression) => expression.clone()
+  }
+}
+
+/// Parameters of the step, with the workflow parameters. Step parameters override workflow
+/// parameters with the same name and location.
+pub(crate) fn step_parameters(
+  description: &ArazzoDescription,
+  workflow: &Workflow,
+  step: &Step
+) -> anyhow::Result<Vec<(ParameterObject, String)>> {
+  let mut parameters: Vec<(ParameterObject, String)> = vec![];
+  for parameter in workflow.parameters.iter().chain(step.parameters.iter()) {
+    let (parameter, value) = match parameter {
+      Either::First(parameter) => (parameter.clone(), None),
+      Either::Second(reusable) => {
+        let name = reusable.reference.strip_prefix("$components.parameters.")
+          .ok_or_else(|| anyhow!("'{}' is not a reference to a component parameter", reusable.reference))?;
+        let parameter = description.components.parameters.get(name)
+          .ok_or_else(|| anyhow!("No component parameter with name '{}' was found", name))?;
+        (parameter.clone(), reusable.value.clone())
+      }
+    };
+    let value = value.unwrap_or_else(|| parameter_value(&parameter));
+    parameters.retain(|(p, _)| p.name != parameter.name || p.r#in != parameter.r#in);
+    parameters.push((parameter, value));
+  }
+  Ok(parameters)
+}
+
+fn reusable_text(reusable: &ReusableObject) -> String {
+  match &reusable.value {
+    Some(value) => format!("{}: {}", code(&reusable.reference), code(value)),
+    None => code(&reusable.reference)
+  }
+}
+
+fn criterion_text(criterion: &Criterion) -> String {
+  let mut text = code(&criterion.condition);
+  if let Some(context) = &criterion.context {
+    let _ = write!(text, " on {}", code(context));
+  }
+  match &criterion.r#type {
+    Some(Either::First(criterion_type)) if criterion_type != "simple" => {
+      let _ = write!(text, " ({})", criterion_type);
+    }
+    Some(Either::Second(expression_type)) => {
+      let _ = write!(text, " ({} {})", expression_type.r#type, expression_type.version);
+    }
+    _ => {}
+  }
+  text
+}
+
+fn json_text(value: &Value) -> String {
+  match value {
+    Value::String(value) => value.clone(),
+    value => value.to_string()
+  }
+}
+
+fn heading(level: usize) -> String {
+  "#".repeat(level)
+}
+
+fn paragraph(doc: &mut String, text: Option<&str>) {
+  if let Some(text) = text.map(|text| text.trim()).filter(|text| !text.is_empty()) {
+    let _ = writeln!(doc, "{}\n", text);
+  }
+}
+
+/// Inline code span, using a longer backtick fence if the text has backticks
+fn code(text: &str) -> String {
+  if text.contains('`') {
+    format!("`` {} ``", text)
+  } else {
+    format!("`{}`", text)
+  }
+}
+
+fn table(doc: &mut String, headers: &[&str], rows: &[Vec<String>]) {
+  let _ = writeln!(doc, "| {} |", headers.join(" | "));
+  let _ = writeln!(doc, "|{}", " --- |".repeat(headers.len()));
+  for row in rows {
+    let cells = row.iter().map(|cell| table_cell(cell)).collect::<Vec<_>>();
+    let _ = writeln!(doc, "| {} |", cells.join(" | "));
+  }
+  doc.push('\n');
+}
+
+/// Escapes pipes and newlines, which would break the table
+fn table_cell(text: &str) -> String {
+  text.replace('|', "\\|").replace("\r\n", "<br/>").replace('\n', "<br/>")
+}
+
+/// Anchor of a heading, as generated by GitHub (lowercase, with spaces replaced with `-` and other
+/// punctuation removed)
+fn anchor(heading: &str) -> String {
+  heading.trim().to_lowercase().chars()
+    .filter_map(|ch| match ch {
+      ' ' => Some('-'),
+      ch if ch.is_alphanumeric() || ch == '-' || ch == '_' => Some(ch),
+      _ => None
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::arazzo;
+  use crate::docs::{anchor, markdown, markdown_with_options, MarkdownOptions};
+  use crate::v1_0::ArazzoDescription;
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      info("Pet adoption", "1.0.0");
+      summary: "Adopting pets from the store";
+      source("petstore", "https://petstore.example.com/openapi.json");
+      workflow("adopt-pet") {
+        summary: "Adopt a pet";
+        inputs: json!({
+          "type": "object",
+          "required": ["owner"],
+          "properties": {
+            "status": { "type": "string", "enum": ["available", "pending"], "default": "available" },
+            "owner": {
+              "type": "object",
+              "required": ["name"],
+              "properties": {
+                "name": { "type": "string", "description": "Name of the\nnew owner" },
+                "born": { "type": "string", "format": "date" }
+              }
+            },
+            "tags": { "type": "array", "items": { "type": "string" } }
+          }
+        });
+        step("find") {
+          operation_id: "findPetsByStatus";
+          description: "Finds a pet | any pet";
+          parameter("status", "query", "$inputs.status");
+          input("limit", 1_i64);
+          reusable_parameter: "apiKey";
+          success_criterion: "$statusCode == 200";
+          typed_success_criterion("jsonpath", "$response.body", "$[?@.id]");
+          output("id", "$response.body#/0/id");
+        }
+        step("adopt") { workflow_id: "$sourceDescriptions.petstore.adopt"; }
+        output("pet_id", "$steps.find.outputs.id");
+      }
+    }
+  }
+
+  #[test]
+  fn generates_markdown_documentation() {
+    expect!(markdown(&description())).to(be_equal_to(r#"# Pet adoption
+
+Adopting pets from the store
+
+**Version:** 1.0.0
+
+## Source Descriptions
+
+| Name | Type | URL |
+| --- | --- | --- |
+| `petstore` | openapi | https://petstore.example.com/openapi.json |
+
+## Workflows
+
+- [adopt-pet](#adopt-pet): Adopt a pet
+
+## adopt-pet
+
+Adopt a pet
+
+### Inputs
+
+| Name | Type | Required | Description |
+| --- | --- | --- | --- |
+| `owner` | object | yes |  |
+| `owner.born` | string (date) | no |  |
+| `owner.name` | string | yes | Name of the<br/>new owner |
+| `status` | string | no | One of: `available`, `pending` Default: `available` |
+| `tags` | array of string | no |  |
+
+### Steps
+
+| Step | Operation | Description | Parameters | Success Criteria |
+| --- | --- | --- | --- | --- |
+| `find` | `findPetsByStatus` | Finds a pet \| any pet | `status` (query): `$inputs.status`<br/>`limit`: `1`<br/>`$components.parameters.apiKey` | `$statusCode == 200`<br/>`$[?@.id]` on `$response.body` (jsonpath) |
+| `adopt` | workflow `$sourceDescriptions.petstore.adopt` |  |  |  |
+
+### Outputs
+
+| Name | Value |
+| --- | --- |
+| `pet_id` | `$steps.find.outputs.id` |
+"#.to_string()));
+  }
+
+  #[test]
+  fn generates_markdown_with_options() {
+    let options = MarkdownOptions { heading_level: 2, table_of_contents: false, flowcharts: true };
+    let doc = markdown_with_options(&description(), &options);
+    expect!(doc.starts_with("## Pet adoption\n")).to(be_true());
+    expect!(doc.contains("### adopt-pet\n")).to(be_true());
+    expect!(doc.contains("#### Steps\n")).to(be_true());
+    expect!(doc.contains("### Workflows")).to(be_false());
+    expect!(doc.contains("```mermaid\nflowchart TD\n")).to(be_true());
+
+    expect!(anchor("Get a Pet (v2)")).to(be_equal_to("get-a-pet-v2"));
+  }
+}
@@ -0,0 +1,243 @@
+//! Results of a dry-run of an execution plan (see [`Executor::dry_run`](crate::executor::Executor::dry_run)),
+//! which report the requests that would be sent for the steps without performing any network calls.
+
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+
+use crate::execution_context::{ExecutionContext, StepRequest};
+use crate::expressions::ExpressionResolver;
+use crate::extensions::AnyValue;
+use crate::operations::Operation;
+
+/// Request that would be sent for a step
+#[derive(Debug, Clone, PartialEq)]
+pub struct DryRunStep {
+  /// Step ID
+  pub step_id: String,
+  /// Operation the step references (for operation steps)
+  pub operation: Option<Operation>,
+  /// Request that would be sent (for operation steps)
+  pub request: Option<StepRequest>,
+  /// Dry-run of the workflow the step references (for workflow steps)
+  pub workflow: Option<Box<DryRunWorkflow>>,
+  /// Expressions that could not be resolved before executing the workflow, and were replaced
+  /// with placeholders (`<expression>`)
+  pub placeholders: Vec<String>,
+  /// Error if the request could not be rendered
+  pub error: Option<String>
+}
+
+impl DryRunStep {
+  pub(crate) fn new(step_id: &str) -> Self {
+    DryRunStep {
+      step_id: step_id.to_string(),
+      operation: None,
+      request: None,
+      workflow: None,
+      placeholders: vec![],
+      error: None
+    }
+  }
+}
+
+/// Dry-run of a workflow
+#[derive(Debug, Clone, PartialEq)]
+pub struct DryRunWorkflow {
+  /// Workflow ID
+  pub workflow_id: String,
+  /// Steps of the workflow, in order
+  pub steps: Vec<DryRunStep>
+}
+
+impl DryRunWorkflow {
+  /// Returns the step with the ID
+  pub fn step(&self, step_id: &str) -> Option<&DryRunStep> {
+    self.steps.iter().find(|step| step.step_id == step_id)
+  }
+
+  /// If any of the steps (including the steps of nested workflows) have an error
+  pub fn has_errors(&self) -> bool {
+    self.steps.iter().any(|step| step.error.is_some() ||
+      step.workflow.as_ref().map(|workflow| workflow.has_errors()).unwrap_or_default())
+  }
+
+  fn fmt_indented(&self, f: &mut Formatter<'_>, indent: &str) -> std::fmt::Result {
+    for step in &self.steps {
+      writeln!(f, "{}- {}", indent, step.step_id)?;
+      if let Some(request) = &step.request {
+        writeln!(f, "{}  {} {}", indent, request.method, request.url)?;
+        for (name, value) in &request.headers {
+          writeln!(f, "{}  {}: {}", indent, name, value)?;
+        }
+        if let Some(body) = &request.body {
+          writeln!(f, "{}  {}", indent, String::from_utf8_lossy(body))?;
+        }
+      }
+      if let Some(workflow) = &step.workflow {
+        writeln!(f, "{}  workflow {}", indent, workflow.workflow_id)?;
+        workflow.fmt_indented(f, &format!("{}    ", indent))?;
+      }
+      if !step.placeholders.is_empty() {
+        writeln!(f, "{}  placeholders: {}", indent, step.placeholders.join(", "))?;
+      }
+      if let Some(error) = &step.error {
+        writeln!(f, "{}  error: {}", indent, error)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Report of the requests that would be sent for the workflows of an execution plan
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DryRunReport {
+  /// Workflows, in the order of the plan
+  pub workflows: Vec<DryRunWorkflow>
+}
+
+impl DryRunReport {
+  /// Returns the workflow with the ID
+  pub fn workflow(&self, workflow_id: &str) -> Option<&DryRunWorkflow> {
+    self.workflows.iter().find(|workflow| workflow.workflow_id == workflow_id)
+  }
+
+  /// If any of the requests could not be rendered
+  pub fn has_errors(&self) -> bool {
+    self.workflows.iter().any(|workflow| workflow.has_errors())
+  }
+}
+
+impl Display for DryRunReport {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    for workflow in &self.workflows {
+      writeln!(f, "workflow {}", workflow.workflow_id)?;
+      workflow.fmt_indented(f, "  ")?;
+    }
+    Ok(())
+  }
+}
+
+/// Resolves expressions from the execution context, replacing any that can not be resolved (as
+/// they depend on requests being sent) with a placeholder
+pub(crate) struct PlaceholderResolver {
+  context: ExecutionContext,
+  placeholders: RefCell<Vec<String>>
+}
+
+impl PlaceholderResolver {
+  pub(crate) fn new(context: ExecutionContext) -> Self {
+    PlaceholderResolver { context, placeholders: RefCell::new(vec![]) }
+  }
+
+  /// Returns the expressions replaced with placeholders since the last call
+  pub(crate) fn take_placeholders(&self) -> Vec<String> {
+    self.placeholders.take()
+  }
+}
+
+impl ExpressionResolver for PlaceholderResolver {
+  fn resolve(&self, expression: &str) -> anyhow::Result<AnyValue> {
+    match self.context.resolve(expression) {
+      Ok(value) => Ok(value),
+      Err(_) => {
+        let mut placeholders = self.placeholders.borrow_mut();
+        if !placeholders.iter().any(|placeholder| placeholder == expression) {
+          placeholders.push(expression.to_string());
+        }
+        Ok(AnyValue::String(format!("<{}>", expression)))
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use expectest::prelude::*;
+  use indexmap::indexmap;
+  use serde_json::json;
+
+  use crate::either::Either;
+  use crate::executor::{Executor, ExecutorOptions};
+  use crate::extensions::AnyValue;
+  use crate::mock_executor::MockExecutor;
+  use crate::operations::{OpenApiSource, OperationResolver};
+  use crate::payloads::JsonPayload;
+  use crate::plan::plan;
+  use crate::v1_0::{ArazzoDescription, ParameterObject, RequestBody, ReusableObject, Step, Workflow};
+
+  fn parameter(name: &str, location: &str, value: &str) -> Either<ParameterObject, ReusableObject> {
+    Either::First(ParameterObject {
+      name: name.to_string(),
+      r#in: Some(location.to_string()),
+      value: Either::Second(value.to_string()),
+      extensions: Default::default()
+    })
+  }
+
+  #[test]
+  fn renders_the_requests_without_sending_them() {
+    let description = ArazzoDescription {
+      workflows: vec![Workflow {
+        workflow_id: "adopt".to_string(),
+        steps: vec![
+          Step {
+            step_id: "login".to_string(),
+            operation_id: Some("login".to_string()),
+            request_body: Some(RequestBody {
+              content_type: Some("application/json".to_string()),
+              payload: Some(Arc::new(JsonPayload(json!({ "user": "{$inputs.user}" })))),
+              replacements: vec![],
+              extensions: Default::default()
+            }),
+            .. Step::default()
+          },
+          Step {
+            step_id: "adopt".to_string(),
+            operation_id: Some("adoptPet".to_string()),
+            parameters: vec![
+              parameter("petId", "path", "$inputs.petId"),
+              parameter("Authorization", "header", "$steps.login.outputs.token")
+            ],
+            .. Step::default()
+          },
+          Step {
+            step_id: "missing".to_string(),
+            operation_id: Some("deletePet".to_string()),
+            .. Step::default()
+          }
+        ],
+        .. Workflow::default()
+      }],
+      .. ArazzoDescription::default()
+    };
+    let operations = OperationResolver::new(vec![OpenApiSource::new("petstore", "petstore.json", json!({
+      "servers": [{ "url": "http://localhost" }],
+      "paths": {
+        "/login": { "post": { "operationId": "login" } },
+        "/pets/{petId}/adopt": { "post": { "operationId": "adoptPet" } }
+      }
+    }))]);
+    let mock = MockExecutor::new();
+    let executor = Executor::with_operations(&description, operations, ExecutorOptions::default())
+      .with_step_executor(mock.clone());
+    let inputs = AnyValue::Object(indexmap!{
+      "user".to_string() => AnyValue::String("tom".to_string()),
+      "petId".to_string() => AnyValue::Integer(12)
+    });
+
+    let report = executor.dry_run(&plan(&description, &[]).unwrap(), inputs).unwrap();
+    mock.assert_steps_invoked(&[]);
+    let workflow = report.workflow("adopt").unwrap();
+    let adopt = workflow.step("adopt").unwrap();
+    expect!(adopt.placeholders.clone()).to(be_equal_to(vec!["$steps.login.outputs.token".to_string()]));
+    expect!(report.has_errors()).to(be_true());
+    expect!(report.to_string()).to(be_equal_to(
+      "workflow adopt\n  \
+       - login\n    POST http://localhost/login\n    Content-Type: application/json\n    {\"user\":\"tom\"}\n  \
+       - adopt\n    POST http://localhost/pets/12/adopt\n    Authorization: <$steps.login.outputs.token>\n    \
+       placeholders: $steps.login.outputs.token\n  \
+       - missing\n    error: No operation with ID 'deletePet' was found in the source descriptions\n"));
+  }
+}
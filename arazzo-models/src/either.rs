@@ -48,4 +48,82 @@ impl <A, B> Either<A, B>
       Either::Second(b) => Some(b)
     }
   }
+
+  /// Returns the value if it is an A, consuming the Either
+  pub fn into_first(self) -> Option<A> {
+    match self {
+      Either::First(a) => Some(a),
+      Either::Second(_) => None
+    }
+  }
+
+  /// Returns the value if it is a B, consuming the Either
+  pub fn into_second(self) -> Option<B> {
+    match self {
+      Either::First(_) => None,
+      Either::Second(b) => Some(b)
+    }
+  }
+
+  /// Converts from `&Either<A, B>` to `Either<&A, &B>`
+  pub fn as_ref(&self) -> Either<&A, &B> {
+    match self {
+      Either::First(a) => Either::First(a),
+      Either::Second(b) => Either::Second(b)
+    }
+  }
+
+  /// Maps an A value with the function, leaving a B value untouched
+  pub fn map_first<C, F>(self, f: F) -> Either<C, B>
+    where C: Debug + Clone + PartialEq,
+          F: FnOnce(A) -> C {
+    match self {
+      Either::First(a) => Either::First(f(a)),
+      Either::Second(b) => Either::Second(b)
+    }
+  }
+
+  /// Maps a B value with the function, leaving an A value untouched
+  pub fn map_second<C, F>(self, f: F) -> Either<A, C>
+    where C: Debug + Clone + PartialEq,
+          F: FnOnce(B) -> C {
+    match self {
+      Either::First(a) => Either::First(a),
+      Either::Second(b) => Either::Second(f(b))
+    }
+  }
+
+  /// Converts both possible values to the same type
+  pub fn either<C, F, G>(self, f: F, g: G) -> C
+    where F: FnOnce(A) -> C,
+          G: FnOnce(B) -> C {
+    match self {
+      Either::First(a) => f(a),
+      Either::Second(b) => g(b)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use crate::either::Either;
+
+  #[test]
+  fn either_accessors() {
+    let first: Either<i64, String> = Either::First(10);
+    let second: Either<i64, String> = Either::Second("ten".to_string());
+
+    expect!(first.as_ref()).to(be_equal_to(Either::First(&10)));
+    expect!(second.as_ref()).to(be_equal_to(Either::Second(&"ten".to_string())));
+    expect!(first.clone().into_first()).to(be_some().value(10));
+    expect!(first.clone().into_second()).to(be_none());
+    expect!(second.clone().into_second()).to(be_some().value("ten".to_string()));
+    expect!(first.clone().map_first(|v| v * 2)).to(be_equal_to(Either::First(20)));
+    expect!(second.clone().map_first(|v| v * 2)).to(be_equal_to(Either::Second("ten".to_string())));
+    expect!(second.clone().map_second(|v| v.len())).to(be_equal_to(Either::Second(3)));
+    expect!(first.either(|v| v.to_string(), |v| v)).to(be_equal_to("10".to_string()));
+    expect!(second.either(|v| v.to_string(), |v| v)).to(be_equal_to("ten".to_string()));
+  }
 }
@@ -0,0 +1,185 @@
+//! Environments (i.e. dev, staging and prod) that map the source descriptions, or the servers of
+//! the OpenAPI documents, to the base URLs of a deployment. The environment is selected when the
+//! [`Executor`](crate::executor::Executor) is created, so the same Arazzo document can be run
+//! against different deployments without editing it.
+//!
+//! Environments can be loaded from a JSON (or YAML, with the `yaml` feature) file:
+//!
+//! ```json
+//! {
+//!   "default": "dev",
+//!   "environments": {
+//!     "dev": {
+//!       "sources": { "petstore": "http://localhost:8080/v1" }
+//!     },
+//!     "staging": {
+//!       "servers": { "https://api.example.com/v1": "http://staging.internal/v1" }
+//!     }
+//!   }
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+
+use crate::operations::parse_document;
+
+/// Base URLs for a deployment
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Environment {
+  /// Name of the environment
+  pub name: String,
+  /// Base URLs keyed by the source description name. These take precedence over the server URLs.
+  pub source_urls: BTreeMap<String, String>,
+  /// Base URLs keyed by the server URL from the OpenAPI documents
+  pub server_urls: BTreeMap<String, String>
+}
+
+impl Environment {
+  /// Creates an empty environment
+  pub fn new<S: Into<String>>(name: S) -> Self {
+    Environment { name: name.into(), .. Environment::default() }
+  }
+
+  /// Sets the base URL for the source description
+  pub fn with_source_url<N: Into<String>, U: Into<String>>(mut self, source_name: N, url: U) -> Self {
+    self.source_urls.insert(source_name.into(), url.into());
+    self
+  }
+
+  /// Sets the base URL to use in place of the server URL of the OpenAPI documents
+  pub fn with_server_url<S: Into<String>, U: Into<String>>(mut self, server_url: S, url: U) -> Self {
+    self.server_urls.insert(server_url.into(), url.into());
+    self
+  }
+
+  /// Returns the base URL for the operations of the source description with the server URL (from
+  /// the OpenAPI document). Returns `None` if the environment does not override the URL.
+  pub fn base_url(&self, source_name: &str, server_url: Option<&str>) -> Option<&str> {
+    self.source_urls.get(source_name)
+      .or_else(|| {
+        let server_url = server_url?.trim_end_matches('/');
+        self.server_urls.iter()
+          .find(|(url, _)| url.trim_end_matches('/') == server_url)
+          .map(|(_, base_url)| base_url)
+      })
+      .map(|url| url.as_str())
+  }
+
+  fn from_json(name: &str, json: &Value) -> anyhow::Result<Self> {
+    Ok(Environment {
+      name: name.to_string(),
+      source_urls: urls_from_json(json, "sources")
+        .with_context(|| format!("Environment '{}' is not valid", name))?,
+      server_urls: urls_from_json(json, "servers")
+        .with_context(|| format!("Environment '{}' is not valid", name))?
+    })
+  }
+}
+
+fn urls_from_json(json: &Value, field: &str) -> anyhow::Result<BTreeMap<String, String>> {
+  match json.get(field) {
+    None | Some(Value::Null) => Ok(BTreeMap::new()),
+    Some(Value::Object(urls)) => urls.iter()
+      .map(|(key, url)| url.as_str()
+        .map(|url| (key.clone(), url.to_string()))
+        .ok_or_else(|| anyhow!("The URL for '{}' in '{}' must be a string", key, field)))
+      .collect(),
+    Some(_) => Err(anyhow!("'{}' must be an object of URLs", field))
+  }
+}
+
+/// Set of environments that one can be selected from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Environments {
+  /// Environments keyed by name
+  pub environments: BTreeMap<String, Environment>,
+  /// Name of the environment to use when none is selected
+  pub default: Option<String>
+}
+
+impl Environments {
+  /// Loads the environments from a JSON (or YAML) file
+  pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+    let path = path.as_ref();
+    let contents = fs::read(path)
+      .with_context(|| format!("Failed to load environments from '{}'", path.display()))?;
+    let json = parse_document(&contents)
+      .with_context(|| format!("Failed to load environments from '{}'", path.display()))?;
+    Environments::from_json(&json)
+  }
+
+  /// Loads the environments from JSON
+  pub fn from_json(json: &Value) -> anyhow::Result<Self> {
+    let environments = json.get("environments")
+      .and_then(|environments| environments.as_object())
+      .ok_or_else(|| anyhow!("The environments file must have an 'environments' object"))?
+      .iter()
+      .map(|(name, environment)| Ok((name.clone(), Environment::from_json(name, environment)?)))
+      .collect::<anyhow::Result<BTreeMap<_, _>>>()?;
+    let default = match json.get("default") {
+      None | Some(Value::Null) => None,
+      Some(Value::String(name)) if environments.contains_key(name) => Some(name.clone()),
+      Some(Value::String(name)) => return Err(anyhow!("The default environment '{}' is not defined", name)),
+      Some(_) => return Err(anyhow!("'default' must be the name of an environment"))
+    };
+    Ok(Environments { environments, default })
+  }
+
+  /// Adds the environment
+  pub fn add(&mut self, environment: Environment) {
+    self.environments.insert(environment.name.clone(), environment);
+  }
+
+  /// Selects the environment with the name, or the default environment if no name is given.
+  /// Returns `None` if no name is given and there is no default.
+  pub fn select(&self, name: Option<&str>) -> anyhow::Result<Option<&Environment>> {
+    match name.or(self.default.as_deref()) {
+      Some(name) => self.environments.get(name)
+        .map(Some)
+        .ok_or_else(|| {
+          let names = self.environments.keys().map(|name| name.as_str()).collect::<Vec<_>>();
+          anyhow!("There is no environment named '{}' (the environments are: {})", name, names.join(", "))
+        }),
+      None => Ok(None)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::environments::{Environment, Environments};
+
+  #[test]
+  fn selects_environments() {
+    let environments = Environments::from_json(&json!({
+      "default": "dev",
+      "environments": {
+        "dev": { "sources": { "petstore": "http://localhost:8080/v1" } },
+        "staging": { "servers": { "https://api.example.com/v1/": "http://staging.internal/v1" } }
+      }
+    })).unwrap();
+
+    let dev = environments.select(None).unwrap().unwrap();
+    expect!(dev.base_url("petstore", Some("https://api.example.com/v1"))).to(be_some().value("http://localhost:8080/v1"));
+    expect!(dev.base_url("other", Some("https://api.example.com/v1"))).to(be_none());
+
+    let staging = environments.select(Some("staging")).unwrap().unwrap();
+    expect!(staging.base_url("petstore", Some("https://api.example.com/v1"))).to(be_some().value("http://staging.internal/v1"));
+    expect!(staging.base_url("petstore", None)).to(be_none());
+
+    expect!(environments.select(Some("prod")).unwrap_err().to_string())
+      .to(be_equal_to("There is no environment named 'prod' (the environments are: dev, staging)"));
+    expect!(Environments::default().select(None).unwrap()).to(be_none());
+    expect!(Environments::from_json(&json!({ "default": "prod", "environments": {} }))).to(be_err());
+    expect!(Environments::from_json(&json!({ "environments": { "dev": { "sources": { "a": 1 } } } }))).to(be_err());
+    expect!(Environment::new("local").with_source_url("petstore", "http://localhost").source_urls.len()).to(be_equal_to(1));
+  }
+}
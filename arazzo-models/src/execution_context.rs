@@ -0,0 +1,545 @@
+//! Context for executing a workflow. The [`ExecutionContext`] holds the workflow inputs, the
+//! requests and responses captured for each step, and the evaluated step and workflow outputs, and
+//! resolves runtime expressions against them. It does not depend on the executor, so it can also be
+//! used on its own (i.e. to extract the outputs of a workflow from recorded traffic with
+//! [`extract_outputs`]).
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::anyhow;
+use bytes::Bytes;
+
+use crate::context::ExpressionContext;
+use crate::expressions::ExpressionResolver;
+use crate::extensions::AnyValue;
+use crate::v1_0::Workflow;
+
+/// Request that was sent for a step
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepRequest {
+  /// HTTP method
+  pub method: String,
+  /// Full URL, including the query string
+  pub url: String,
+  /// Request headers
+  pub headers: Vec<(String, String)>,
+  /// Request body
+  pub body: Option<Bytes>
+}
+
+/// Response that was received for a step
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepResponse {
+  /// HTTP status code
+  pub status: u16,
+  /// Response headers
+  pub headers: Vec<(String, String)>,
+  /// Response body
+  pub body: Bytes
+}
+
+impl StepResponse {
+  /// Creates a response with the status code, and no headers or body
+  pub fn new(status: u16) -> Self {
+    StepResponse { status, headers: vec![], body: Bytes::new() }
+  }
+
+  /// Adds a header to the response
+  pub fn with_header<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+    self.headers.push((name.into(), value.into()));
+    self
+  }
+
+  /// Sets the body of the response
+  pub fn with_body<B: Into<Bytes>>(mut self, body: B) -> Self {
+    self.body = body.into();
+    self
+  }
+
+  /// Sets the body of the response to the JSON document, and the content type to `application/json`
+  pub fn with_json(self, json: &serde_json::Value) -> Self {
+    self.with_header("Content-Type", "application/json")
+      .with_body(json.to_string())
+  }
+}
+
+/// Request and response captured for a step
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StepExchange {
+  /// Request that was sent
+  pub request: Option<StepRequest>,
+  /// Response that was received
+  pub response: Option<StepResponse>,
+  /// Values of the path parameters (as these can not be recovered from the URL)
+  pub path_parameters: BTreeMap<String, AnyValue>
+}
+
+/// Values that runtime expressions are resolved against while executing a workflow. The request
+/// and response expressions (`$url`, `$method`, `$statusCode`, `$request.*` and `$response.*`)
+/// are resolved from the exchange of the current step, and all other expressions (`$inputs`,
+/// `$steps`, `$outputs` and `$workflows`, or any expressions not in the exchange) are resolved as
+/// for an [`ExpressionContext`].
+///
+/// Header names are matched ignoring case, and request and response bodies are parsed as JSON if
+/// they are valid JSON (otherwise they are strings). A JSON Pointer fragment can be used to select
+/// a value from a body (i.e. `$response.body#/pets/0/id`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionContext {
+  /// Workflow inputs, and the outputs of the steps and workflows
+  pub expressions: ExpressionContext,
+  /// Requests and responses captured for the steps, keyed by step ID (only the last exchange for
+  /// each step is kept)
+  pub exchanges: HashMap<String, StepExchange>,
+  /// ID of the step that the request and response expressions are resolved for
+  pub current_step: Option<String>
+}
+
+impl ExecutionContext {
+  /// Creates a context with the workflow inputs
+  pub fn new(inputs: AnyValue) -> Self {
+    ExecutionContext {
+      expressions: ExpressionContext::new(inputs),
+      .. ExecutionContext::default()
+    }
+  }
+
+  /// Workflow inputs
+  pub fn inputs(&self) -> &AnyValue {
+    &self.expressions.inputs
+  }
+
+  /// Records the request and response for the step, and makes it the current step
+  pub fn record_exchange<S: Into<String>>(&mut self, step_id: S, exchange: StepExchange) {
+    let step_id = step_id.into();
+    self.exchanges.insert(step_id.clone(), exchange);
+    self.current_step = Some(step_id);
+  }
+
+  /// Records the request for the step (clearing any previous response), and makes it the current step
+  pub fn record_request<S: Into<String>>(
+    &mut self,
+    step_id: S,
+    request: StepRequest,
+    path_parameters: BTreeMap<String, AnyValue>
+  ) {
+    self.record_exchange(step_id, StepExchange { request: Some(request), response: None, path_parameters });
+  }
+
+  /// Records the response for the step, and makes it the current step
+  pub fn record_response<S: Into<String>>(&mut self, step_id: S, response: StepResponse) {
+    let step_id = step_id.into();
+    self.exchanges.entry(step_id.clone()).or_default().response = Some(response);
+    self.current_step = Some(step_id);
+  }
+
+  /// Returns the exchange of the current step
+  pub fn current_exchange(&self) -> Option<&StepExchange> {
+    self.current_step.as_ref().and_then(|step_id| self.exchanges.get(step_id))
+  }
+
+  /// Sets the value of an output of a step
+  pub fn set_step_output<S: Into<String>, N: Into<String>>(&mut self, step_id: S, name: N, value: AnyValue) {
+    self.expressions.set_step_output(step_id, name, value);
+  }
+
+  /// Returns the outputs of the step
+  pub fn step_outputs(&self, step_id: &str) -> Option<&HashMap<String, AnyValue>> {
+    self.expressions.steps.get(step_id)
+  }
+
+  /// Evaluates the output expressions of the step against its recorded exchange, storing and
+  /// returning the values. Returns an error if any expression can not be resolved.
+  pub fn capture_outputs(
+    &mut self,
+    step_id: &str,
+    outputs: &BTreeMap<String, String>
+  ) -> anyhow::Result<BTreeMap<String, AnyValue>> {
+    self.current_step = Some(step_id.to_string());
+    let values = evaluate_outputs(self, outputs)?;
+    for (name, value) in &values {
+      self.set_step_output(step_id, name.as_str(), value.clone());
+    }
+    Ok(values)
+  }
+
+  /// Evaluates the output expressions of the workflow, storing and returning the values. Returns
+  /// an error if any expression can not be resolved.
+  pub fn capture_workflow_outputs(
+    &mut self,
+    outputs: &BTreeMap<String, String>
+  ) -> anyhow::Result<BTreeMap<String, AnyValue>> {
+    let values = evaluate_outputs(self, outputs)?;
+    self.expressions.outputs.extend(values.clone());
+    Ok(values)
+  }
+
+  fn lookup_exchange(&self, expression: &str) -> Option<AnyValue> {
+    self.current_exchange().and_then(|exchange| exchange.resolve(expression))
+  }
+
+  /// Returns the requests and responses captured for the steps
+  pub fn captures(&self) -> CaptureStore {
+    CaptureStore {
+      exchanges: self.exchanges.iter()
+        .map(|(step_id, exchange)| (step_id.clone(), exchange.clone()))
+        .collect()
+    }
+  }
+}
+
+/// Requests and responses captured for the steps of a workflow, keyed by step ID (only the last
+/// exchange of each step is kept). This is what the request and response expressions of the steps
+/// were resolved against, so tools can inspect the values after a run. With the `serialize`
+/// feature, the store can be written as JSON.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CaptureStore {
+  /// Exchanges keyed by step ID
+  pub exchanges: BTreeMap<String, StepExchange>
+}
+
+impl CaptureStore {
+  /// Returns the exchange of the step
+  pub fn exchange(&self, step_id: &str) -> Option<&StepExchange> {
+    self.exchanges.get(step_id)
+  }
+
+  /// Resolves a request or response expression (i.e. `$statusCode` or `$response.body#/id`)
+  /// against the exchange of the step
+  pub fn resolve(&self, step_id: &str, expression: &str) -> anyhow::Result<AnyValue> {
+    let exchange = self.exchange(step_id)
+      .ok_or_else(|| anyhow!("No request or response was captured for step '{}'", step_id))?;
+    exchange.resolve(expression.trim())
+      .ok_or_else(|| anyhow!("Runtime expression '{}' could not be resolved for step '{}'", expression, step_id))
+  }
+
+  /// Returns the status code of the response of the step
+  pub fn status_code(&self, step_id: &str) -> Option<u16> {
+    self.exchange(step_id)?.response.as_ref().map(|response| response.status)
+  }
+
+  /// Returns the value of a request header of the step (matched ignoring case)
+  pub fn request_header(&self, step_id: &str, name: &str) -> Option<&str> {
+    let request = self.exchange(step_id)?.request.as_ref()?;
+    find_header(&request.headers, name)
+  }
+
+  /// Returns the value of a response header of the step (matched ignoring case)
+  pub fn response_header(&self, step_id: &str, name: &str) -> Option<&str> {
+    let response = self.exchange(step_id)?.response.as_ref()?;
+    find_header(&response.headers, name)
+  }
+
+  /// Returns the request body of the step (parsed if it is JSON)
+  pub fn request_body(&self, step_id: &str) -> Option<AnyValue> {
+    self.exchange(step_id)?.request.as_ref()?.body.as_ref().map(body_value)
+  }
+
+  /// Returns the response body of the step (parsed if it is JSON)
+  pub fn response_body(&self, step_id: &str) -> Option<AnyValue> {
+    self.exchange(step_id)?.response.as_ref().map(|response| body_value(&response.body))
+  }
+}
+
+impl StepExchange {
+  /// Resolves a request or response expression (`$url`, `$method`, `$statusCode`, `$request.*`
+  /// or `$response.*`, with an optional JSON Pointer fragment) against the exchange. Returns
+  /// `None` if the expression is not a request or response expression, or there is no value.
+  pub fn resolve(&self, expression: &str) -> Option<AnyValue> {
+    let (source, pointer) = match expression.split_once('#') {
+      Some((source, pointer)) => (source, Some(pointer)),
+      None => (expression, None)
+    };
+    let request = self.request.as_ref();
+    let response = self.response.as_ref();
+    let value = match source {
+      "$url" => request.map(|request| AnyValue::String(request.url.clone())),
+      "$method" => request.map(|request| AnyValue::String(request.method.clone())),
+      "$statusCode" => response.map(|response| AnyValue::Integer(response.status as i64)),
+      "$request.body" => request.and_then(|request| request.body.as_ref()).map(body_value),
+      "$response.body" => response.map(|response| body_value(&response.body)),
+      _ => if let Some(name) = source.strip_prefix("$request.header.") {
+        request.and_then(|request| header_value(&request.headers, name))
+      } else if let Some(name) = source.strip_prefix("$response.header.") {
+        response.and_then(|response| header_value(&response.headers, name))
+      } else if let Some(name) = source.strip_prefix("$request.query.") {
+        request.and_then(|request| query_value(&request.url, name))
+      } else if let Some(name) = source.strip_prefix("$request.path.") {
+        self.path_parameters.get(name).cloned()
+      } else {
+        None
+      }
+    };
+    match (value, pointer) {
+      (Some(value), Some(pointer)) => value.pointer(pointer).cloned(),
+      (value, _) => value
+    }
+  }
+}
+
+/// Outputs of a workflow and its steps
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorkflowOutputs {
+  /// Outputs of the steps, keyed by step ID
+  pub steps: BTreeMap<String, BTreeMap<String, AnyValue>>,
+  /// Outputs of the workflow
+  pub workflow: BTreeMap<String, AnyValue>
+}
+
+/// Evaluates the output expressions of the steps of the workflow against the exchanges recorded
+/// in the context, and then the output expressions of the workflow. Steps without a recorded
+/// exchange keep any outputs already in the context (i.e. steps that ran another workflow), and
+/// are otherwise left out. The context is not modified.
+///
+/// Returns an error if any expression can not be resolved.
+pub fn extract_outputs(workflow: &Workflow, context: &ExecutionContext) -> anyhow::Result<WorkflowOutputs> {
+  let mut context = context.clone();
+  let mut result = WorkflowOutputs::default();
+  for step in &workflow.steps {
+    if context.exchanges.contains_key(&step.step_id) {
+      let outputs = context.capture_outputs(&step.step_id, &step.outputs)
+        .map_err(|err| anyhow!("Failed to extract the outputs of step '{}': {}", step.step_id, err))?;
+      result.steps.insert(step.step_id.clone(), outputs);
+    } else if let Some(outputs) = context.step_outputs(&step.step_id) {
+      result.steps.insert(step.step_id.clone(), outputs.iter()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect());
+    }
+  }
+  result.workflow = context.capture_workflow_outputs(&workflow.outputs)
+    .map_err(|err| anyhow!("Failed to extract the outputs of workflow '{}': {}", workflow.workflow_id, err))?;
+  Ok(result)
+}
+
+fn evaluate_outputs(
+  resolver: &dyn ExpressionResolver,
+  outputs: &BTreeMap<String, String>
+) -> anyhow::Result<BTreeMap<String, AnyValue>> {
+  outputs.iter()
+    .map(|(name, expression)| resolver.resolve(expression)
+      .map(|value| (name.clone(), value))
+      .map_err(|err| anyhow!("Failed to evaluate output '{}': {}", name, err)))
+    .collect()
+}
+
+impl ExpressionResolver for ExecutionContext {
+  fn resolve(&self, expression: &str) -> anyhow::Result<AnyValue> {
+    let expression = expression.trim();
+    match self.lookup_exchange(expression) {
+      Some(value) => Ok(value),
+      None => self.expressions.resolve(expression)
+    }
+  }
+}
+
+fn header_value(headers: &[(String, String)], name: &str) -> Option<AnyValue> {
+  find_header(headers, name).map(|value| AnyValue::String(value.to_string()))
+}
+
+fn find_header<'h>(headers: &'h [(String, String)], name: &str) -> Option<&'h str> {
+  headers.iter()
+    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+    .map(|(_, value)| value.as_str())
+}
+
+fn query_value(url: &str, name: &str) -> Option<AnyValue> {
+  let (_, query) = url.split_once('?')?;
+  query.split('#').next().unwrap_or_default()
+    .split('&')
+    .filter_map(|pair| {
+      let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+      (percent_decode(key) == name).then(|| AnyValue::String(percent_decode(value)))
+    })
+    .next()
+}
+
+fn percent_decode(value: &str) -> String {
+  let bytes = value.as_bytes();
+  let mut result = Vec::with_capacity(bytes.len());
+  let mut index = 0;
+  while index < bytes.len() {
+    let escaped = value.get(index + 1..index + 3)
+      .filter(|_| bytes[index] == b'%')
+      .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+    if let Some(byte) = escaped {
+      result.push(byte);
+      index += 3;
+    } else {
+      result.push(if bytes[index] == b'+' { b' ' } else { bytes[index] });
+      index += 1;
+    }
+  }
+  String::from_utf8_lossy(&result).to_string()
+}
+
+/// Value of a request or response body for runtime expressions. JSON bodies are parsed, otherwise
+/// the body is a string.
+pub(crate) fn body_value(body: &Bytes) -> AnyValue {
+  serde_json::from_slice::<serde_json::Value>(body).ok()
+    .and_then(|json| AnyValue::try_from(json).ok())
+    .unwrap_or_else(|| AnyValue::String(String::from_utf8_lossy(body).to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+  use bytes::Bytes;
+  use expectest::prelude::*;
+  use indexmap::indexmap;
+  use maplit::btreemap;
+  use serde_json::json;
+
+  use crate::execution_context::{extract_outputs, ExecutionContext, StepExchange, StepRequest, StepResponse};
+  use crate::expressions::ExpressionResolver;
+  use crate::extensions::AnyValue;
+  use crate::v1_0::{Step, Workflow};
+
+  #[test]
+  fn resolves_request_and_response_expressions() {
+    let mut context = ExecutionContext::new(AnyValue::Object(indexmap!{
+      "id".to_string() => AnyValue::Integer(1)
+    }));
+    context.record_exchange("find", StepExchange {
+      request: Some(StepRequest {
+        method: "GET".to_string(),
+        url: "http://localhost/pets/1?name=Fido%20Dog&limit=10".to_string(),
+        headers: vec![("Accept".to_string(), "application/json".to_string())],
+        body: None
+      }),
+      response: Some(StepResponse::new(200).with_json(&json!({ "pets": [{ "id": 1 }] }))),
+      path_parameters: btreemap!{ "petId".to_string() => AnyValue::Integer(1) }
+    });
+
+    expect!(context.resolve("$url").unwrap()).to(be_equal_to(AnyValue::String("http://localhost/pets/1?name=Fido%20Dog&limit=10".to_string())));
+    expect!(context.resolve("$method").unwrap()).to(be_equal_to(AnyValue::String("GET".to_string())));
+    expect!(context.resolve(" $statusCode ").unwrap()).to(be_equal_to(AnyValue::Integer(200)));
+    expect!(context.resolve("$request.header.accept").unwrap()).to(be_equal_to(AnyValue::String("application/json".to_string())));
+    expect!(context.resolve("$request.query.name").unwrap()).to(be_equal_to(AnyValue::String("Fido Dog".to_string())));
+    expect!(context.resolve("$request.path.petId").unwrap()).to(be_equal_to(AnyValue::Integer(1)));
+    expect!(context.resolve("$response.header.CONTENT-TYPE").unwrap()).to(be_equal_to(AnyValue::String("application/json".to_string())));
+    expect!(context.resolve("$response.body#/pets/0/id").unwrap()).to(be_equal_to(AnyValue::UInteger(1)));
+    expect!(context.resolve("$inputs.id").unwrap()).to(be_equal_to(AnyValue::Integer(1)));
+    expect!(context.resolve("$request.body").unwrap_err().to_string())
+      .to(be_equal_to("Runtime expression '$request.body' could not be resolved"));
+    expect!(context.resolve("$response.header.Location")).to(be_err());
+  }
+
+  #[test]
+  fn captures_outputs_from_recorded_traffic() {
+    let mut context = ExecutionContext::new(AnyValue::Null);
+    context.record_request("login", StepRequest {
+      method: "POST".to_string(),
+      url: "http://localhost/login".to_string(),
+      headers: vec![],
+      body: Some(Bytes::from_static(b"user=fido"))
+    }, Default::default());
+    context.record_response("login", StepResponse::new(200).with_header("X-Token", "abc"));
+    context.record_exchange("find", StepExchange {
+      response: Some(StepResponse::new(200).with_body("[1, 2]")),
+      .. StepExchange::default()
+    });
+
+    let outputs = context.capture_outputs("login", &btreemap!{
+      "token".to_string() => "$response.header.x-token".to_string(),
+      "body".to_string() => "$request.body".to_string()
+    }).unwrap();
+    expect!(outputs).to(be_equal_to(btreemap!{
+      "token".to_string() => AnyValue::String("abc".to_string()),
+      "body".to_string() => AnyValue::String("user=fido".to_string())
+    }));
+    expect!(context.resolve("$steps.login.outputs.token").unwrap()).to(be_equal_to(AnyValue::String("abc".to_string())));
+
+    let outputs = context.capture_outputs("find", &btreemap!{ "second".to_string() => "$response.body#/1".to_string() }).unwrap();
+    expect!(outputs.get("second")).to(be_some().value(&AnyValue::UInteger(2)));
+
+    let outputs = context.capture_workflow_outputs(&btreemap!{ "token".to_string() => "$steps.login.outputs.token".to_string() }).unwrap();
+    expect!(outputs.len()).to(be_equal_to(1));
+    expect!(context.resolve("$outputs.token").unwrap()).to(be_equal_to(AnyValue::String("abc".to_string())));
+    expect!(context.capture_outputs("find", &btreemap!{ "missing".to_string() => "$response.header.Location".to_string() })
+      .unwrap_err().to_string())
+      .to(be_equal_to("Failed to evaluate output 'missing': Runtime expression '$response.header.Location' could not be resolved"));
+  }
+
+  #[test]
+  fn extracts_workflow_outputs_from_recorded_traffic() {
+    let workflow = Workflow {
+      workflow_id: "login".to_string(),
+      steps: vec![
+        Step {
+          step_id: "login".to_string(),
+          outputs: btreemap!{ "token".to_string() => "$response.body#/token".to_string() },
+          .. Step::default()
+        },
+        Step {
+          step_id: "profile".to_string(),
+          outputs: btreemap!{ "name".to_string() => "$response.body#/name".to_string() },
+          .. Step::default()
+        },
+        Step { step_id: "skipped".to_string(), .. Step::default() }
+      ],
+      outputs: btreemap!{
+        "token".to_string() => "$steps.login.outputs.token".to_string(),
+        "name".to_string() => "$steps.profile.outputs.name".to_string()
+      },
+      .. Workflow::default()
+    };
+    let mut context = ExecutionContext::new(AnyValue::Null);
+    context.record_response("login", StepResponse::new(200).with_json(&json!({ "token": "abc" })));
+    context.set_step_output("profile", "name", AnyValue::String("Fido".to_string()));
+
+    let outputs = extract_outputs(&workflow, &context).unwrap();
+    expect!(outputs.workflow).to(be_equal_to(btreemap!{
+      "token".to_string() => AnyValue::String("abc".to_string()),
+      "name".to_string() => AnyValue::String("Fido".to_string())
+    }));
+    expect!(outputs.steps.len()).to(be_equal_to(2));
+    expect!(context.step_outputs("login")).to(be_none());
+
+    context.record_response("profile", StepResponse::new(404));
+    expect!(extract_outputs(&workflow, &context).unwrap_err().to_string())
+      .to(be_equal_to("Failed to extract the outputs of step 'profile': Failed to evaluate output 'name': Runtime expression '$response.body#/name' could not be resolved"));
+  }
+
+  #[test]
+  fn exposes_the_captured_exchanges() {
+    let mut context = ExecutionContext::new(AnyValue::Null);
+    context.record_request("find", StepRequest {
+      method: "GET".to_string(),
+      url: "http://localhost/pets/1".to_string(),
+      headers: vec![("Accept".to_string(), "application/json".to_string())],
+      body: None
+    }, btreemap!{ "petId".to_string() => AnyValue::Integer(1) });
+    context.record_response("find", StepResponse::new(200)
+      .with_header("X-Rate-Remaining", "9")
+      .with_json(&json!({ "name": "Fido" })));
+
+    let captures = context.captures();
+    expect!(captures.status_code("find")).to(be_some().value(200));
+    expect!(captures.request_header("find", "accept")).to(be_some().value("application/json"));
+    expect!(captures.response_header("find", "x-rate-remaining")).to(be_some().value("9"));
+    expect!(captures.request_body("find")).to(be_none());
+    expect!(captures.response_body("find")).to(be_some().value(AnyValue::Object(indexmap!{
+      "name".to_string() => AnyValue::String("Fido".to_string())
+    })));
+    expect!(captures.resolve("find", "$request.path.petId").unwrap()).to(be_equal_to(AnyValue::Integer(1)));
+    expect!(captures.resolve("find", "$inputs.id").unwrap_err().to_string())
+      .to(be_equal_to("Runtime expression '$inputs.id' could not be resolved for step 'find'"));
+    expect!(captures.resolve("other", "$statusCode").unwrap_err().to_string())
+      .to(be_equal_to("No request or response was captured for step 'other'"));
+
+    #[cfg(feature = "serialize")]
+    expect!(serde_json::to_value(&captures).unwrap()).to(be_equal_to(json!({
+      "find": {
+        "request": {
+          "method": "GET",
+          "url": "http://localhost/pets/1",
+          "headers": { "Accept": "application/json" }
+        },
+        "response": {
+          "statusCode": 200,
+          "headers": { "X-Rate-Remaining": "9", "Content-Type": "application/json" },
+          "body": { "name": "Fido" }
+        },
+        "pathParameters": { "petId": 1 }
+      }
+    })));
+  }
+}
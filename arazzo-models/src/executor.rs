@@ -0,0 +1,2256 @@
+//! Reference runner for Arazzo workflows. The [`Executor`] runs a workflow end-to-end: for each
+//! step the operation is resolved from the source descriptions (see the
+//! [operations module](crate::operations)), the request is rendered from the parameters and request
+//! body and sent, the success criteria are evaluated (see the [criteria module](crate::criteria)),
+//! the outputs are captured, and then the success or failure actions are followed.
+//!
+//! By default, requests are sent with a blocking [reqwest](https://docs.rs/reqwest) client using
+//! rustls (see [`HttpStepExecutor`]). The transport can be replaced by setting a [`StepExecutor`].
+//!
+//! Credentials are added to the requests by the [`AuthProvider`]s set for the source
+//! descriptions or steps (see the [auth module](crate::auth)).
+//!
+//! `end` actions stop the workflow (with the outcome of the step), and `goto` actions continue at
+//! another step of the workflow, or transfer control to another workflow, which gets the same
+//! inputs and whose outputs are then available to the current workflow. Cycles are stopped by the
+//! limits in the [`ExecutorOptions`].
+//!
+//! `retry` failure actions re-execute the step (after running any referenced step or workflow)
+//! until the success criteria pass or the `retryLimit` is reached (one retry if no limit is set).
+//! The executor is synchronous, so the `retryAfter` delay blocks the current thread by default.
+//! A [`RetryTimer`] can be set to wait on another timer, or to skip the delays in tests. As the
+//! executor does not run on an async runtime, a timer that waits on one must use a handle to a
+//! runtime that is driven by other threads. The delay can be increased with a backoff strategy,
+//! and requests can be limited to a rate for each source description or host (see the
+//! [rate_limit module](crate::rate_limit)).
+//!
+//! Each request is aborted if it takes longer than the step timeout, and a workflow stops once it
+//! has run for longer than the workflow timeout. Timeouts are set with the `x-timeout` extension on
+//! steps and workflows (see [`Timeout`]), and can be overridden in the [`ExecutorOptions`]. Steps
+//! that time out have the [`ExecutionStatus::Timeout`] status, and their failure actions are
+//! followed (so they can be retried).
+//!
+//! If a checkpoint file is set in the [`ExecutorOptions`], the state of the workflow is written
+//! before each step, so an interrupted execution can be resumed with [`Executor::resume`] (see
+//! the [checkpoint module](crate::checkpoint)).
+//!
+//! The workflows of an [`ExecutionPlan`] can be executed concurrently with
+//! [`Executor::execute_plan`] (see the `max_concurrency` option), where workflows that do not
+//! depend on each other are run on a pool of threads. Concurrency is limited to workflows: the
+//! steps of a workflow are always executed one at a time, in order. Arazzo steps have no dependency
+//! declarations, the next step is chosen by the actions of the previous one, and a step can depend
+//! on the side effects of the earlier steps (i.e. creating a resource before fetching it) without
+//! referencing their outputs, so there is no way to tell which steps are safe to run concurrently.
+//!
+//! Workflows can also be run once for each row of a CSV or NDJSON dataset with
+//! [`Executor::execute_dataset`] (see the [dataset module](crate::dataset)), or simulated with
+//! stubbed step outputs and no requests with [`Executor::simulate`] (see the
+//! [simulation module](crate::simulation)).
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+
+use crate::auth::AuthProvider;
+use crate::checkpoint::Checkpoint;
+use crate::criteria::evaluate_criteria;
+use crate::dataset::{Dataset, DatasetOptions, DatasetResult, RowResult};
+use crate::extension_registry::{typed_extension, TypedExtension};
+use crate::dry_run::{DryRunReport, DryRunStep, DryRunWorkflow, PlaceholderResolver};
+use crate::execution_context::{CaptureStore, ExecutionContext, StepRequest, StepResponse};
+use crate::either::Either;
+use crate::environments::Environment;
+use crate::expressions::ExpressionResolver;
+use crate::extensions::AnyValue;
+use crate::http_config::HttpClientConfig;
+use crate::inputs::{coerce_inputs, validate_workflow_inputs};
+use crate::listener::ExecutionListener;
+use crate::operations::{Operation, OperationResolver};
+use crate::plan::ExecutionPlan;
+use crate::rate_limit::{url_host, BackoffStrategy, RateLimit, RateLimiter};
+use crate::simulation::{Simulation, SimulationReport, SimulationStubs};
+use crate::templates::{render_template, TemplateOptions};
+use crate::v1_0::{
+  ArazzoDescription,
+  Criterion,
+  FailureObject,
+  ParameterObject,
+  ReusableObject,
+  Step,
+  Workflow
+};
+
+/// Options for the executor
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutorOptions {
+  /// Directory that relative source description file paths are resolved against
+  pub base_dir: Option<PathBuf>,
+  /// Server URLs to use for the source descriptions (keyed by the source description name),
+  /// overriding the servers defined in the OpenAPI documents
+  pub server_urls: BTreeMap<String, String>,
+  /// Environment that provides the base URLs for the source descriptions and servers (see the
+  /// [environments module](crate::environments)). The `server_urls` take precedence over the
+  /// environment.
+  pub environment: Option<Environment>,
+  /// Timeout for each request, for steps that do not have an `x-timeout` extension
+  pub timeout: Duration,
+  /// Configuration of the HTTP client, used to fetch the source descriptions and by the
+  /// [`HttpStepExecutor`] (proxies, TLS settings and response size limits)
+  pub http: HttpClientConfig,
+  /// Timeouts for the requests of steps, keyed by the step ID. These override the `x-timeout`
+  /// extension of the steps (in all workflows).
+  pub step_timeouts: BTreeMap<String, Duration>,
+  /// Timeouts for workflows, keyed by the workflow ID. These override the `x-timeout` extension of
+  /// the workflows.
+  pub workflow_timeouts: BTreeMap<String, Duration>,
+  /// Maximum number of step executions for a workflow, to stop `goto` and `retry` actions from
+  /// looping forever
+  pub max_step_executions: usize,
+  /// Maximum number of times a single step can be executed in a workflow (including retries),
+  /// to detect cycles of `goto` actions
+  pub max_step_visits: usize,
+  /// Maximum depth of nested workflows (from workflow steps and `goto` actions that reference a
+  /// workflow), to detect workflows that reference each other
+  pub max_workflow_depth: usize,
+  /// Maximum number of workflows of an [`ExecutionPlan`] that are executed at the same time. With
+  /// a value greater than 1, workflows that do not depend on each other are executed concurrently
+  /// (on a pool of threads of this size). The steps of a workflow are always executed in order.
+  pub max_concurrency: usize,
+  /// File that a [`Checkpoint`] of the workflow is written to before each step is executed (and
+  /// once all the steps have completed). Checkpoints are only written for workflows executed with
+  /// [`Executor::execute`] or [`Executor::resume`], not for nested workflows or execution plans.
+  pub checkpoint_file: Option<PathBuf>,
+  /// Rate limits for the requests to the operations of source descriptions, keyed by the source
+  /// description name
+  pub source_rate_limits: BTreeMap<String, RateLimit>,
+  /// Rate limits for the requests to hosts, keyed by the host (with the port if it is not the
+  /// default port, i.e. `localhost:8080`). Requests must be within both the source and host limits.
+  pub host_rate_limits: BTreeMap<String, RateLimit>,
+  /// Backoff strategy for the delays before steps are retried
+  pub backoff: BackoffStrategy
+}
+
+impl Default for ExecutorOptions {
+  fn default() -> Self {
+    ExecutorOptions {
+      base_dir: None,
+      server_urls: Default::default(),
+      environment: None,
+      timeout: Duration::from_secs(30),
+      http: HttpClientConfig::default(),
+      step_timeouts: Default::default(),
+      workflow_timeouts: Default::default(),
+      max_step_executions: 1000,
+      max_step_visits: 100,
+      max_workflow_depth: 32,
+      max_concurrency: 1,
+      checkpoint_file: None,
+      source_rate_limits: Default::default(),
+      host_rate_limits: Default::default(),
+      backoff: BackoffStrategy::None
+    }
+  }
+}
+
+/// Outcome of executing a workflow or step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionStatus {
+  /// All the success criteria passed
+  Success,
+  /// One of the success criteria did not pass
+  Failure,
+  /// The workflow or step could not be executed (i.e. the operation could not be resolved, or the
+  /// request could not be sent)
+  Error,
+  /// The request of the step, or the workflow, did not complete within its timeout
+  Timeout
+}
+
+/// Value of the `x-timeout` extension of steps and workflows. Timeouts are either a number of
+/// milliseconds, or a string with a `ms`, `s` or `m` unit (i.e. `"500ms"` or `"1.5s"`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeout(pub Duration);
+
+impl TypedExtension for Timeout {
+  const KEY: &'static str = "timeout";
+
+  fn from_value(value: &AnyValue) -> anyhow::Result<Self> {
+    let millis = match value {
+      AnyValue::String(s) => {
+        let s = s.trim();
+        let (number, scale) = if let Some(number) = s.strip_suffix("ms") {
+          (number, 1.0)
+        } else if let Some(number) = s.strip_suffix('s') {
+          (number, 1000.0)
+        } else if let Some(number) = s.strip_suffix('m') {
+          (number, 60000.0)
+        } else {
+          (s, 1.0)
+        };
+        number.trim().parse::<f64>()
+          .map(|number| number * scale)
+          .map_err(|_| anyhow!("'{}' is not a valid timeout (it must be a number of milliseconds, or have a ms, s or m unit)", s))?
+      }
+      _ => value.as_f64().ok_or_else(|| anyhow!("A timeout must be a number of milliseconds or a string"))?
+    };
+    if !millis.is_finite() || millis < 0.0 {
+      return Err(anyhow!("A timeout can not be negative"));
+    }
+    Ok(Timeout(Duration::from_secs_f64(millis / 1000.0)))
+  }
+
+  fn to_value(&self) -> AnyValue {
+    AnyValue::UInteger(self.0.as_millis() as u64)
+  }
+
+  fn validate(&self) -> anyhow::Result<()> {
+    if self.0.is_zero() {
+      Err(anyhow!("A timeout must be greater than zero"))
+    } else {
+      Ok(())
+    }
+  }
+}
+
+/// Result of evaluating a criterion
+#[derive(Debug, Clone, PartialEq)]
+pub struct CriterionResult {
+  /// Condition of the criterion
+  pub condition: String,
+  /// If the criterion passed
+  pub passed: bool,
+  /// Error if the criterion could not be evaluated
+  pub error: Option<String>
+}
+
+/// Result of executing a step
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepResult {
+  /// Step ID
+  pub step_id: String,
+  /// Outcome of the step
+  pub status: ExecutionStatus,
+  /// Request that was sent (for operation steps)
+  pub request: Option<StepRequest>,
+  /// Response that was received (for operation steps)
+  pub response: Option<StepResponse>,
+  /// Results of the success criteria
+  pub criteria: Vec<CriterionResult>,
+  /// Outputs of the step
+  pub outputs: BTreeMap<String, AnyValue>,
+  /// Result of the workflow that was executed (for workflow steps)
+  pub workflow: Option<Box<WorkflowResult>>,
+  /// Name of the success or failure action that was followed
+  pub action: Option<String>,
+  /// Attempt number (starting at 1, and incremented each time the step is retried)
+  pub attempt: usize,
+  /// Time taken to execute the step
+  pub duration: Duration,
+  /// Error if the step could not be executed
+  pub error: Option<String>
+}
+
+impl StepResult {
+  fn new(step_id: &str) -> Self {
+    StepResult {
+      step_id: step_id.to_string(),
+      status: ExecutionStatus::Success,
+      request: None,
+      response: None,
+      criteria: vec![],
+      outputs: Default::default(),
+      workflow: None,
+      action: None,
+      attempt: 1,
+      duration: Duration::ZERO,
+      error: None
+    }
+  }
+
+  fn fail(&mut self, status: ExecutionStatus, error: anyhow::Error) {
+    self.status = status;
+    self.error = Some(error.to_string());
+  }
+}
+
+/// Result of executing a workflow
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkflowResult {
+  /// Workflow ID
+  pub workflow_id: String,
+  /// Outcome of the workflow
+  pub status: ExecutionStatus,
+  /// Results of the steps, in the order they were executed
+  pub steps: Vec<StepResult>,
+  /// Outputs of the workflow
+  pub outputs: BTreeMap<String, AnyValue>,
+  /// Time taken to execute the workflow
+  pub duration: Duration,
+  /// Error if the workflow could not be executed
+  pub error: Option<String>,
+  /// Requests and responses captured for the steps (what the request and response expressions
+  /// were resolved against)
+  pub captures: CaptureStore
+}
+
+impl WorkflowResult {
+  /// If the workflow completed successfully
+  pub fn is_success(&self) -> bool {
+    self.status == ExecutionStatus::Success
+  }
+
+  /// Returns the result of the last execution of the step
+  pub fn step(&self, step_id: &str) -> Option<&StepResult> {
+    self.steps.iter().rev().find(|step| step.step_id == step_id)
+  }
+}
+
+/// Action to take after a step has been executed
+enum NextAction {
+  Continue,
+  End,
+  GotoStep(String),
+  GotoWorkflow(String),
+  Retry {
+    delay: Option<Duration>,
+    limit: usize,
+    step_id: Option<String>,
+    workflow_id: Option<String>
+  }
+}
+
+impl NextAction {
+  fn action_type(&self) -> &'static str {
+    match self {
+      NextAction::Continue => "continue",
+      NextAction::End => "end",
+      NextAction::GotoStep(_) | NextAction::GotoWorkflow(_) => "goto",
+      NextAction::Retry { .. } => "retry"
+    }
+  }
+}
+
+/// Where the steps of a workflow start (the first step, or the step of a checkpoint)
+struct StartPoint<'p> {
+  index: usize,
+  attempt: usize,
+  completed_steps: Vec<String>,
+  checkpoint: Option<&'p Path>
+}
+
+impl Default for StartPoint<'_> {
+  fn default() -> Self {
+    StartPoint { index: 0, attempt: 1, completed_steps: vec![], checkpoint: None }
+  }
+}
+
+/// Waits for the delay before a step is retried, or a request can be sent within the rate limits.
+/// The wait is synchronous, as the executor is; async timers are not supported.
+pub trait RetryTimer {
+  /// Waits for the delay to pass
+  fn wait(&self, delay: Duration);
+}
+
+impl <F: Fn(Duration)> RetryTimer for F {
+  fn wait(&self, delay: Duration) {
+    self(delay)
+  }
+}
+
+/// Retry timer that blocks the current thread for the delay
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SleepTimer;
+
+impl RetryTimer for SleepTimer {
+  fn wait(&self, delay: Duration) {
+    std::thread::sleep(delay)
+  }
+}
+
+/// Executes the requests for the steps of a workflow. The [`Executor`] takes care of rendering
+/// the requests, evaluating the criteria and following the actions, so implementations only need
+/// to send the request and return the response. This allows the HTTP transport to be replaced
+/// (i.e. with a custom client, another protocol or a test double).
+pub trait StepExecutor {
+  /// Executes the request for the step, returning the response. The operation is the one the step
+  /// references, and the context has the request recorded as the exchange of the current step.
+  fn execute(
+    &self,
+    step: &Step,
+    operation: &Operation,
+    request: &StepRequest,
+    context: &ExecutionContext
+  ) -> anyhow::Result<StepResponse>;
+
+  /// Executes the request for the step, aborting it if it takes longer than the timeout. The
+  /// default implementation calls [`execute`](StepExecutor::execute) (the [`Executor`] still
+  /// records a timeout if the response is returned after the timeout).
+  fn execute_with_timeout(
+    &self,
+    step: &Step,
+    operation: &Operation,
+    request: &StepRequest,
+    context: &ExecutionContext,
+    _timeout: Duration
+  ) -> anyhow::Result<StepResponse> {
+    self.execute(step, operation, request, context)
+  }
+}
+
+impl <F> StepExecutor for F
+  where F: Fn(&Step, &Operation, &StepRequest, &ExecutionContext) -> anyhow::Result<StepResponse> {
+  fn execute(
+    &self,
+    step: &Step,
+    operation: &Operation,
+    request: &StepRequest,
+    context: &ExecutionContext
+  ) -> anyhow::Result<StepResponse> {
+    self(step, operation, request, context)
+  }
+}
+
+/// Step executor that sends the requests with a blocking reqwest client (`http:` and `https:`
+/// URLs are supported). The client is built from the configuration for the first request, and then
+/// reused.
+#[derive(Debug, Clone)]
+pub struct HttpStepExecutor {
+  timeout: Duration,
+  config: HttpClientConfig,
+  client: OnceLock<reqwest::blocking::Client>
+}
+
+impl HttpStepExecutor {
+  /// Creates a step executor with the timeout for each request (when no timeout is given for the
+  /// step), and the configuration of the HTTP client
+  pub fn new(timeout: Duration, config: HttpClientConfig) -> Self {
+    HttpStepExecutor { timeout, config, client: OnceLock::new() }
+  }
+
+  /// Timeout for each request (when no timeout is given for the step)
+  pub fn timeout(&self) -> Duration {
+    self.timeout
+  }
+
+  /// Configuration of the HTTP client
+  pub fn config(&self) -> &HttpClientConfig {
+    &self.config
+  }
+
+  fn client(&self) -> anyhow::Result<&reqwest::blocking::Client> {
+    if let Some(client) = self.client.get() {
+      return Ok(client);
+    }
+    let client = self.config.client()?;
+    Ok(self.client.get_or_init(|| client))
+  }
+}
+
+impl StepExecutor for HttpStepExecutor {
+  fn execute(
+    &self,
+    step: &Step,
+    operation: &Operation,
+    request: &StepRequest,
+    context: &ExecutionContext
+  ) -> anyhow::Result<StepResponse> {
+    self.execute_with_timeout(step, operation, request, context, self.timeout)
+  }
+
+  fn execute_with_timeout(
+    &self,
+    _step: &Step,
+    _operation: &Operation,
+    request: &StepRequest,
+    _context: &ExecutionContext,
+    timeout: Duration
+  ) -> anyhow::Result<StepResponse> {
+    let response = crate::http_config::send(self.client()?, &request.method, &request.url, &request.headers,
+      request.body.as_deref(), timeout, self.config.max_response_size())?;
+    Ok(StepResponse { status: response.status, headers: response.headers, body: response.body })
+  }
+}
+
+/// Executes the workflows of an Arazzo description
+#[derive(Clone)]
+pub struct Executor<'a> {
+  description: &'a ArazzoDescription,
+  operations: OperationResolver,
+  options: ExecutorOptions,
+  step_executor: Arc<dyn StepExecutor + Send + Sync + 'a>,
+  retry_timer: Arc<dyn RetryTimer + Send + Sync + 'a>,
+  rate_limiter: Arc<RateLimiter>,
+  simulation: Option<Arc<Simulation>>,
+  listeners: Vec<Arc<dyn ExecutionListener + Send + Sync + 'a>>,
+  source_auth: HashMap<String, Arc<dyn AuthProvider + Send + Sync + 'a>>,
+  step_auth: HashMap<String, Arc<dyn AuthProvider + Send + Sync + 'a>>
+}
+
+impl <'a> Executor<'a> {
+  /// Creates an executor for the Arazzo description, loading the OpenAPI source descriptions.
+  /// Returns an error if the HTTP client configuration, rate limits or backoff strategy are not
+  /// valid.
+  pub fn new(description: &'a ArazzoDescription, options: ExecutorOptions) -> anyhow::Result<Self> {
+    options.http.validate()?;
+    for limit in options.source_rate_limits.values().chain(options.host_rate_limits.values()) {
+      limit.validate()?;
+    }
+    options.backoff.validate()?;
+    let operations = OperationResolver::load_with_config(description, options.base_dir.as_deref(), &options.http)?;
+    Ok(Executor::with_operations(description, operations, options))
+  }
+
+  /// Creates an executor for the Arazzo description with already loaded OpenAPI descriptions
+  pub fn with_operations(
+    description: &'a ArazzoDescription,
+    operations: OperationResolver,
+    options: ExecutorOptions
+  ) -> Self {
+    let step_executor = Arc::new(HttpStepExecutor::new(options.timeout, options.http.clone()));
+    Executor {
+      description,
+      operations,
+      options,
+      step_executor,
+      retry_timer: Arc::new(SleepTimer),
+      rate_limiter: Arc::default(),
+      simulation: None,
+      listeners: vec![],
+      source_auth: HashMap::new(),
+      step_auth: HashMap::new()
+    }
+  }
+
+  /// Sets the step executor that executes the requests for the steps (the default sends them with
+  /// [`HttpStepExecutor`])
+  pub fn with_step_executor<E: StepExecutor + Send + Sync + 'a>(self, step_executor: E) -> Self {
+    Executor { step_executor: Arc::new(step_executor), .. self }
+  }
+
+  /// Sets the timer that waits for the `retryAfter` delay of `retry` failure actions (the default
+  /// is [`SleepTimer`])
+  pub fn with_retry_timer<T: RetryTimer + Send + Sync + 'a>(self, retry_timer: T) -> Self {
+    Executor { retry_timer: Arc::new(retry_timer), .. self }
+  }
+
+  /// Adds a listener that is notified of the progress of the execution. Listeners are notified
+  /// in the order they were added.
+  pub fn with_listener<L: ExecutionListener + Send + Sync + 'a>(mut self, listener: L) -> Self {
+    self.listeners.push(Arc::new(listener));
+    self
+  }
+
+  /// Sets the credentials for the requests of the operations of the source description (keyed by
+  /// the source description name)
+  pub fn with_source_auth<P: AuthProvider + Send + Sync + 'a>(mut self, source_name: &str, provider: P) -> Self {
+    self.source_auth.insert(source_name.to_string(), Arc::new(provider));
+    self
+  }
+
+  /// Sets the credentials for the requests of the steps with the ID (in all workflows). These take
+  /// precedence over the credentials set for the source description.
+  pub fn with_step_auth<P: AuthProvider + Send + Sync + 'a>(mut self, step_id: &str, provider: P) -> Self {
+    self.step_auth.insert(step_id.to_string(), Arc::new(provider));
+    self
+  }
+
+  /// Executes the workflow with the inputs. Returns an error if there is no workflow with the ID,
+  /// otherwise the outcome of the workflow is returned in the result.
+  pub fn execute(&self, workflow_id: &str, inputs: AnyValue) -> anyhow::Result<WorkflowResult> {
+    let workflow = self.workflow(workflow_id)?;
+    let start = StartPoint { checkpoint: self.options.checkpoint_file.as_deref(), .. StartPoint::default() };
+    Ok(self.run_workflow_from(workflow, ExecutionContext::new(inputs), 0, start))
+  }
+
+  /// Resumes the execution of a workflow from a checkpoint. Execution continues at the step the
+  /// checkpoint was written for (with the same attempt number), with the inputs and step outputs
+  /// of the checkpoint. The result only includes the steps executed after resuming.
+  pub fn resume(&self, checkpoint: &Checkpoint) -> anyhow::Result<WorkflowResult> {
+    let workflow = self.workflow(&checkpoint.workflow_id)?;
+    let index = match &checkpoint.next_step {
+      Some(step_id) => workflow.steps.iter()
+        .position(|step| &step.step_id == step_id)
+        .ok_or_else(|| anyhow!("No step with ID '{}' was found in workflow '{}'", step_id, workflow.workflow_id))?,
+      None => workflow.steps.len()
+    };
+    let start = StartPoint {
+      index,
+      attempt: checkpoint.attempt.max(1),
+      completed_steps: checkpoint.completed_steps.clone(),
+      checkpoint: self.options.checkpoint_file.as_deref()
+    };
+    Ok(self.run_workflow_from(workflow, checkpoint.context(), 0, start))
+  }
+
+  /// Executes the workflows of the plan in order, with the same inputs. The outputs of each
+  /// workflow are available to the workflows that depend on it (as `$workflows.<workflowId>.outputs`).
+  ///
+  /// If the `max_concurrency` option is greater than 1, the plan is executed in stages, where each
+  /// stage has the workflows whose dependencies are all in the earlier stages, and the workflows of
+  /// a stage are executed concurrently by a pool of `max_concurrency` threads. The results are
+  /// returned in the order of the plan, regardless of when the workflows complete.
+  ///
+  /// Only workflows are executed concurrently. The steps of each workflow are always executed one
+  /// at a time and in order, as the actions of each step decide which step is executed next. The
+  /// executor is synchronous, so the workflows are executed on threads rather than an async runtime.
+  ///
+  /// Execution stops after the first workflow (or stage) that does not complete successfully, so
+  /// the results are returned for the workflows that were executed.
+  pub fn execute_plan(&self, plan: &ExecutionPlan, inputs: AnyValue) -> anyhow::Result<Vec<WorkflowResult>> {
+    let workflows = plan.workflows.iter()
+      .map(|planned| self.workflow(&planned.workflow_id))
+      .collect::<anyhow::Result<Vec<_>>>()?;
+    let stages = if self.options.max_concurrency > 1 {
+      plan_stages(plan)
+    } else {
+      (0..plan.workflows.len()).map(|index| vec![index]).collect()
+    };
+
+    let mut results = vec![];
+    let mut workflow_outputs = HashMap::new();
+    for stage in stages {
+      let run = |index: usize| {
+        let mut context = ExecutionContext::new(inputs.clone());
+        context.expressions.workflows = workflow_outputs.clone();
+        (index, self.run_workflow(workflows[index], context, 0))
+      };
+      let workers = self.options.max_concurrency.clamp(1, stage.len().max(1));
+      let mut stage_results = if workers == 1 {
+        stage.iter().map(|index| run(*index)).collect::<Vec<_>>()
+      } else {
+        // Each worker takes the next workflow of the stage once it has finished its current one,
+        // so a slow workflow only holds up its own worker
+        let next = AtomicUsize::new(0);
+        thread::scope(|scope| {
+          let handles = (0..workers)
+            .map(|_| scope.spawn(|| {
+              let mut worker_results = vec![];
+              while let Some(index) = stage.get(next.fetch_add(1, Ordering::SeqCst)) {
+                worker_results.push(run(*index));
+              }
+              worker_results
+            }))
+            .collect::<Vec<_>>();
+          handles.into_iter()
+            .flat_map(|handle| match handle.join() {
+              Ok(results) => results,
+              Err(panic) => std::panic::resume_unwind(panic)
+            })
+            .collect::<Vec<_>>()
+        })
+      };
+
+      stage_results.sort_by_key(|(index, _)| *index);
+      let success = stage_results.iter().all(|(_, result)| result.is_success());
+      for (_, result) in stage_results {
+        workflow_outputs.insert(result.workflow_id.clone(), result.outputs.clone().into_iter().collect());
+        results.push(result);
+      }
+      if !success {
+        break;
+      }
+    }
+    Ok(results)
+  }
+
+  /// Executes the workflow once for each row of the dataset, with the columns of the row as the
+  /// inputs (see the [dataset module](crate::dataset)). The inputs are converted to the types in
+  /// the inputs schema of the workflow and validated, and rows with inputs that are not valid (or
+  /// columns that can not be mapped to the inputs) have an error result.
+  ///
+  /// Rows are executed in order. If `fail_fast` is set, execution stops after the first row that
+  /// does not complete successfully, and the remaining rows are counted as skipped.
+  pub fn execute_dataset(&self, workflow_id: &str, dataset: &Dataset, options: &DatasetOptions) -> anyhow::Result<DatasetResult> {
+    let workflow = self.workflow(workflow_id)?;
+    let mut result = DatasetResult::default();
+    for row in 0..dataset.rows.len() {
+      let inputs = dataset.row_inputs(row, &options.column_mapping);
+      let converted = inputs.as_ref()
+        .map_err(|err| anyhow!("{:#}", err))
+        .and_then(|inputs| self.dataset_inputs(workflow_id, inputs));
+      let workflow_result = match converted {
+        Ok(inputs) => self.execute(workflow_id, inputs)?,
+        Err(err) => WorkflowResult {
+          workflow_id: workflow.workflow_id.clone(),
+          status: ExecutionStatus::Error,
+          steps: vec![],
+          outputs: BTreeMap::new(),
+          duration: Duration::ZERO,
+          error: Some(format!("{:#}", err)),
+          captures: CaptureStore::default()
+        }
+      };
+      let success = workflow_result.is_success();
+      result.rows.push(RowResult { row, inputs: inputs.unwrap_or_default(), result: workflow_result });
+      if options.fail_fast && !success {
+        result.skipped = dataset.rows.len() - row - 1;
+        break;
+      }
+    }
+    Ok(result)
+  }
+
+  fn dataset_inputs(&self, workflow_id: &str, inputs: &serde_json::Value) -> anyhow::Result<AnyValue> {
+    let inputs = coerce_inputs(self.description, workflow_id, inputs)?;
+    validate_workflow_inputs(self.description, workflow_id, &inputs)?;
+    AnyValue::try_from(&inputs)
+  }
+
+  /// Simulates the workflow with the stubbed step outputs, without sending any requests. The
+  /// success criteria and actions are evaluated as for a real run, so the report shows the path
+  /// that would be taken through the workflow (see the [simulation module](crate::simulation)).
+  /// Retry delays and rate limits are skipped, and no checkpoints are written.
+  pub fn simulate(&self, workflow_id: &str, inputs: AnyValue, stubs: &SimulationStubs) -> anyhow::Result<SimulationReport> {
+    let workflow = self.workflow(workflow_id)?;
+    let executor = Executor {
+      simulation: Some(Arc::new(Simulation::new(stubs.clone()))),
+      retry_timer: Arc::new(|_: Duration| {}),
+      rate_limiter: Arc::default(),
+      .. self.clone()
+    };
+    let result = executor.run_workflow(workflow, ExecutionContext::new(inputs), 0);
+    Ok(SimulationReport::new(result, &self.description.workflows, stubs))
+  }
+
+  /// Walks the workflows of the plan without sending any requests, resolving the operations and
+  /// rendering the requests that would be sent. Values that are only known once requests have
+  /// been sent (like step outputs and response values) are replaced with placeholders. The steps
+  /// are listed in order, without following any success or failure actions.
+  pub fn dry_run(&self, plan: &ExecutionPlan, inputs: AnyValue) -> anyhow::Result<DryRunReport> {
+    let workflows = plan.workflows.iter()
+      .map(|planned| Ok(self.dry_run_workflow(self.workflow(&planned.workflow_id)?, inputs.clone(), 0)))
+      .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(DryRunReport { workflows })
+  }
+
+  fn dry_run_workflow(&self, workflow: &Workflow, inputs: AnyValue, depth: usize) -> DryRunWorkflow {
+    let resolver = PlaceholderResolver::new(ExecutionContext::new(inputs));
+    let steps = workflow.steps.iter()
+      .map(|step| {
+        let mut result = DryRunStep::new(&step.step_id);
+        let parameters = self.parameters(workflow, step, &resolver);
+        match (parameters, &step.workflow_id) {
+          (Ok(parameters), Some(workflow_id)) => match self.workflow(workflow_id) {
+            Ok(_) if depth >= self.options.max_workflow_depth => result.error = Some(format!(
+              "Workflow '{}' exceeded the maximum depth of {} nested workflows", workflow_id, self.options.max_workflow_depth)),
+            Ok(other) => {
+              let inputs = AnyValue::Object(parameters.into_iter()
+                .map(|(parameter, value)| (parameter.name, value))
+                .collect());
+              result.workflow = Some(Box::new(self.dry_run_workflow(other, inputs, depth + 1)));
+            }
+            Err(err) => result.error = Some(err.to_string())
+          },
+          (Ok(parameters), None) => match self.build_step_request(step, parameters, &resolver) {
+            Ok((operation, request, _)) => {
+              result.operation = Some(operation);
+              result.request = Some(request);
+            }
+            Err(err) => result.error = Some(err.to_string())
+          },
+          (Err(err), _) => result.error = Some(err.to_string())
+        }
+        result.placeholders = resolver.take_placeholders();
+        result
+      })
+      .collect();
+    DryRunWorkflow { workflow_id: workflow.workflow_id.clone(), steps }
+  }
+
+  /// Waits until the request is within the rate limits of its source description and host
+  fn wait_for_rate_limits(&self, operation: &Operation, request: &StepRequest) {
+    let now = Instant::now();
+    let source_wait = self.options.source_rate_limits.get(&operation.source_name)
+      .map(|limit| self.rate_limiter.acquire(&format!("source:{}", operation.source_name), limit, now));
+    let host_wait = url_host(&request.url)
+      .and_then(|host| self.host_rate_limit(&host).map(|limit| (host, limit)))
+      .map(|(host, limit)| self.rate_limiter.acquire(&format!("host:{}", host), limit, now));
+    let wait = source_wait.into_iter().chain(host_wait).max().unwrap_or_default();
+    if !wait.is_zero() {
+      self.retry_timer.wait(wait);
+    }
+  }
+
+  fn host_rate_limit(&self, host: &str) -> Option<&RateLimit> {
+    self.options.host_rate_limits.iter()
+      .find(|(key, _)| key.eq_ignore_ascii_case(host))
+      .or_else(|| {
+        let (name, _) = host.rsplit_once(':')?;
+        self.options.host_rate_limits.iter().find(|(key, _)| key.eq_ignore_ascii_case(name))
+      })
+      .map(|(_, limit)| limit)
+  }
+
+  fn workflow(&self, workflow_id: &str) -> anyhow::Result<&'a Workflow> {
+    self.description.workflows.iter()
+      .find(|workflow| workflow.workflow_id == workflow_id)
+      .ok_or_else(|| anyhow!("No workflow with ID '{}' was found", workflow_id))
+  }
+
+  fn run_workflow(&self, workflow: &Workflow, context: ExecutionContext, depth: usize) -> WorkflowResult {
+    self.run_workflow_from(workflow, context, depth, StartPoint::default())
+  }
+
+  fn run_workflow_from(
+    &self,
+    workflow: &Workflow,
+    context: ExecutionContext,
+    depth: usize,
+    start: StartPoint
+  ) -> WorkflowResult {
+    self.notify(|listener| listener.workflow_started(workflow, context.inputs()));
+    let started = Instant::now();
+    let mut context = context;
+    let mut result = self.run_workflow_steps(workflow, &mut context, depth, start);
+    result.duration = started.elapsed();
+    result.captures = context.captures();
+    self.notify(|listener| listener.workflow_finished(&result));
+    result
+  }
+
+  fn notify<F: Fn(&dyn ExecutionListener)>(&self, event: F) {
+    for listener in &self.listeners {
+      event(listener.as_ref());
+    }
+  }
+
+  fn run_workflow_steps(
+    &self,
+    workflow: &Workflow,
+    context: &mut ExecutionContext,
+    depth: usize,
+    start: StartPoint
+  ) -> WorkflowResult {
+    let mut result = WorkflowResult {
+      workflow_id: workflow.workflow_id.clone(),
+      status: ExecutionStatus::Success,
+      steps: vec![],
+      outputs: Default::default(),
+      duration: Duration::ZERO,
+      error: None,
+      captures: CaptureStore::default()
+    };
+    if depth > self.options.max_workflow_depth {
+      result.status = ExecutionStatus::Error;
+      result.error = Some(format!("Workflow '{}' exceeded the maximum depth of {} nested workflows",
+        workflow.workflow_id, self.options.max_workflow_depth));
+      return result;
+    }
+
+    let deadline = match self.workflow_timeout(workflow) {
+      Ok(timeout) => timeout.map(|timeout| (Instant::now() + timeout, timeout)),
+      Err(err) => {
+        result.status = ExecutionStatus::Error;
+        result.error = Some(err.to_string());
+        return result;
+      }
+    };
+
+    let mut index = start.index;
+    let mut attempt = start.attempt;
+    let mut completed_steps = start.completed_steps;
+    let mut visits = HashMap::new();
+    while index < workflow.steps.len() {
+      if result.steps.len() >= self.options.max_step_executions {
+        result.status = ExecutionStatus::Error;
+        result.error = Some(format!("Workflow '{}' exceeded the maximum of {} step executions",
+          workflow.workflow_id, self.options.max_step_executions));
+        return result;
+      }
+
+      if let Some((deadline, timeout)) = deadline && Instant::now() >= deadline {
+        result.status = ExecutionStatus::Timeout;
+        result.error = Some(format!("Workflow '{}' timed out after {:?}", workflow.workflow_id, timeout));
+        return result;
+      }
+
+      let step = &workflow.steps[index];
+      let visit_count = visits.entry(step.step_id.as_str()).or_insert(0);
+      *visit_count += 1;
+      if *visit_count > self.options.max_step_visits {
+        result.status = ExecutionStatus::Error;
+        result.error = Some(format!("Step '{}' was executed more than {} times in workflow '{}' (the actions may form a cycle)",
+          step.step_id, self.options.max_step_visits, workflow.workflow_id));
+        return result;
+      }
+
+      if let Some(path) = start.checkpoint {
+        let checkpoint = Checkpoint::new(&workflow.workflow_id, context, Some(&step.step_id), attempt, completed_steps.clone());
+        if let Err(err) = checkpoint.save(path) {
+          result.status = ExecutionStatus::Error;
+          result.error = Some(format!("{:#}", err));
+          return result;
+        }
+      }
+
+      let (mut step_result, scope) = self.run_step(workflow, step, context, depth, attempt, deadline);
+      let action = match self.next_action(workflow, step, &step_result, scope.as_ref().unwrap_or(context)) {
+        Ok((name, action)) => {
+          if let Some(name) = &name {
+            self.notify(|listener| listener.action_taken(step, name, action.action_type()));
+          }
+          step_result.action = name;
+          action
+        }
+        Err(err) => {
+          step_result.fail(ExecutionStatus::Error, err);
+          NextAction::End
+        }
+      };
+      let status = step_result.status;
+      self.notify(|listener| listener.step_finished(step, &step_result));
+      completed_steps.push(step.step_id.clone());
+      result.steps.push(step_result);
+
+      match action {
+        NextAction::Continue if status == ExecutionStatus::Success => {
+          index += 1;
+          attempt = 1;
+        }
+        NextAction::Continue | NextAction::End => {
+          if status != ExecutionStatus::Success {
+            result.status = status;
+            result.error = Some(format!("Step '{}' failed", step.step_id));
+            return result;
+          }
+          break;
+        }
+        NextAction::GotoStep(step_id) => match workflow.steps.iter().position(|step| step.step_id == step_id) {
+          Some(position) => {
+            index = position;
+            attempt = 1;
+          }
+          None => {
+            result.status = ExecutionStatus::Error;
+            result.error = Some(format!("No step with ID '{}' was found in workflow '{}'", step_id, workflow.workflow_id));
+            return result;
+          }
+        },
+        NextAction::GotoWorkflow(workflow_id) => {
+          let other = match self.workflow(&workflow_id) {
+            Ok(other) => self.transfer_to_workflow(other, context, depth),
+            Err(err) => {
+              result.status = ExecutionStatus::Error;
+              result.error = Some(err.to_string());
+              return result;
+            }
+          };
+          let status = other.status;
+          let error = other.error.clone();
+          if let Some(step_result) = result.steps.last_mut() {
+            step_result.workflow = Some(Box::new(other));
+          }
+          if status != ExecutionStatus::Success {
+            result.status = status;
+            result.error = error;
+            return result;
+          }
+          break;
+        }
+        NextAction::Retry { delay, limit, step_id, workflow_id } => {
+          if attempt > limit {
+            result.status = status;
+            result.error = Some(format!("Step '{}' failed after {} attempt(s)", step.step_id, attempt));
+            return result;
+          }
+          let response = result.steps.last().and_then(|step_result| step_result.response.as_ref());
+          let delay = self.options.backoff.delay(attempt, delay, response);
+          self.notify(|listener| listener.retry_scheduled(step, attempt + 1, delay));
+          if let Some(delay) = delay {
+            self.retry_timer.wait(delay);
+          }
+          if let Some(step_id) = step_id {
+            match workflow.steps.iter().find(|step| step.step_id == step_id) {
+              Some(other) => {
+                let (other_result, _) = self.run_step(workflow, other, context, depth, 1, deadline);
+                self.notify(|listener| listener.step_finished(other, &other_result));
+                completed_steps.push(other.step_id.clone());
+                result.steps.push(other_result);
+              }
+              None => {
+                result.status = ExecutionStatus::Error;
+                result.error = Some(format!("No step with ID '{}' was found in workflow '{}'", step_id, workflow.workflow_id));
+                return result;
+              }
+            }
+          } else if let Some(workflow_id) = workflow_id {
+            match self.workflow(&workflow_id) {
+              Ok(other) => {
+                let other = self.transfer_to_workflow(other, context, depth);
+                if let Some(step_result) = result.steps.last_mut() {
+                  step_result.workflow = Some(Box::new(other));
+                }
+              }
+              Err(err) => {
+                result.status = ExecutionStatus::Error;
+                result.error = Some(err.to_string());
+                return result;
+              }
+            }
+          }
+          attempt += 1;
+        }
+      }
+    }
+
+    if let Some(path) = start.checkpoint {
+      let checkpoint = Checkpoint::new(&workflow.workflow_id, context, None, 1, completed_steps);
+      if let Err(err) = checkpoint.save(path) {
+        result.status = ExecutionStatus::Error;
+        result.error = Some(format!("{:#}", err));
+        return result;
+      }
+    }
+
+    match context.capture_workflow_outputs(&workflow.outputs) {
+      Ok(outputs) => result.outputs = outputs,
+      Err(err) => {
+        result.status = ExecutionStatus::Error;
+        result.error = Some(err.to_string());
+      }
+    }
+    result
+  }
+
+  /// Runs the workflow a `goto` or `retry` action references. The workflow receives the inputs
+  /// and the outputs of any workflows already executed from the current workflow, and its outputs
+  /// are then available to the current workflow (as `$workflows.<workflowId>.outputs`).
+  fn transfer_to_workflow(&self, workflow: &Workflow, context: &mut ExecutionContext, depth: usize) -> WorkflowResult {
+    let mut other_context = ExecutionContext::new(context.inputs().clone());
+    other_context.expressions.workflows = context.expressions.workflows.clone();
+    let result = self.run_workflow(workflow, other_context, depth + 1);
+    context.expressions.workflows.insert(workflow.workflow_id.clone(),
+      result.outputs.clone().into_iter().collect());
+    result
+  }
+
+  fn run_step(
+    &self,
+    workflow: &Workflow,
+    step: &Step,
+    context: &mut ExecutionContext,
+    depth: usize,
+    attempt: usize,
+    deadline: Option<(Instant, Duration)>
+  ) -> (StepResult, Option<ExecutionContext>) {
+    self.notify(|listener| listener.step_started(step, attempt));
+    let started = Instant::now();
+    let (mut result, scope) = self.execute_step(workflow, step, context, depth, deadline);
+    result.attempt = attempt;
+    result.duration = started.elapsed();
+    (result, scope)
+  }
+
+  /// Executes the step, returning the result. For workflow steps, the context the criteria and
+  /// outputs were evaluated with (where `$outputs` are the outputs of the workflow) is also returned.
+  fn execute_step(
+    &self,
+    workflow: &Workflow,
+    step: &Step,
+    context: &mut ExecutionContext,
+    depth: usize,
+    deadline: Option<(Instant, Duration)>
+  ) -> (StepResult, Option<ExecutionContext>) {
+    let mut result = StepResult::new(&step.step_id);
+    context.current_step = None;
+    let stub = self.simulation.as_ref().and_then(|simulation| simulation.next_stub(&step.step_id));
+    let parameters = match self.parameters(workflow, step, context) {
+      Ok(parameters) => parameters,
+      Err(err) => {
+        result.fail(ExecutionStatus::Error, err);
+        return (result, None);
+      }
+    };
+
+    let mut scope = if let Some(workflow_id) = &step.workflow_id {
+      let workflow = match self.workflow(workflow_id) {
+        Ok(workflow) => workflow,
+        Err(err) => {
+          result.fail(ExecutionStatus::Error, err);
+          return (result, None);
+        }
+      };
+      let inputs = AnyValue::Object(parameters.into_iter()
+        .map(|(parameter, value)| (parameter.name, value))
+        .collect());
+      let mut sub_context = ExecutionContext::new(inputs);
+      sub_context.expressions.workflows = context.expressions.workflows.clone();
+      let workflow_result = self.run_workflow(workflow, sub_context, depth + 1);
+      let outputs = workflow_result.outputs.clone().into_iter().collect::<HashMap<_, _>>();
+      context.expressions.workflows.insert(workflow_id.clone(), outputs.clone());
+      if !workflow_result.is_success() {
+        result.status = workflow_result.status;
+        result.error = workflow_result.error.clone();
+      }
+      result.workflow = Some(Box::new(workflow_result));
+
+      let mut scope = context.clone();
+      scope.expressions.outputs = outputs;
+      Some(scope)
+    } else {
+      match self.build_step_request(step, parameters, context) {
+        Ok((operation, mut request, path_parameters)) => {
+          // Simulations do not send requests, so no credentials are fetched for them
+          if self.simulation.is_none() && let Err(err) = self.authenticate(step, &operation, &mut request) {
+            result.fail(ExecutionStatus::Error, err);
+            return (result, None);
+          }
+          let timeout = match self.step_timeout(step) {
+            Ok(timeout) => match deadline {
+              Some((deadline, _)) => timeout.min(deadline.saturating_duration_since(Instant::now())),
+              None => timeout
+            },
+            Err(err) => {
+              result.fail(ExecutionStatus::Error, err);
+              return (result, None);
+            }
+          };
+          if self.simulation.is_none() {
+            self.wait_for_rate_limits(&operation, &request);
+          }
+          context.record_request(step.step_id.as_str(), request.clone(), path_parameters);
+          self.notify(|listener| listener.request_sent(step, &request));
+          let started = Instant::now();
+          let response = match &self.simulation {
+            Some(_) => Ok(stub.map(|stub| stub.response.clone()).unwrap_or_else(|| StepResponse::new(200))),
+            None => self.step_executor.execute_with_timeout(step, &operation, &request, context, timeout)
+          };
+          result.request = Some(request);
+          if started.elapsed() >= timeout {
+            result.fail(ExecutionStatus::Timeout, anyhow!("Step '{}' timed out after {:?}", step.step_id, timeout));
+            return (result, None);
+          }
+          match response {
+            Ok(response) => {
+              self.notify(|listener| listener.response_received(step, &response));
+              context.record_response(step.step_id.as_str(), response.clone());
+              result.response = Some(response);
+            }
+            Err(err) => {
+              result.fail(ExecutionStatus::Error, err);
+              return (result, None);
+            }
+          }
+        }
+        Err(err) => {
+          result.fail(ExecutionStatus::Error, err);
+          return (result, None);
+        }
+      }
+      None
+    };
+
+    if result.status == ExecutionStatus::Success {
+      let resolver: &dyn ExpressionResolver = scope.as_ref().unwrap_or(context);
+      result.criteria = step.success_criteria.iter()
+        .map(|criterion| {
+          let criterion_result = evaluate_criterion(criterion, resolver);
+          self.notify(|listener| listener.criterion_evaluated(step, &criterion_result));
+          criterion_result
+        })
+        .collect();
+      if result.criteria.iter().any(|criterion| !criterion.passed) {
+        result.status = ExecutionStatus::Failure;
+      }
+    }
+
+    // Outputs are only captured from successful steps. Stubbed outputs replace the expressions.
+    if result.status == ExecutionStatus::Success {
+      let expressions = step.outputs.iter()
+        .filter(|(name, _)| !stub.is_some_and(|stub| stub.outputs.contains_key(*name)))
+        .map(|(name, expression)| (name.clone(), expression.clone()))
+        .collect();
+      let outputs = match &mut scope {
+        Some(scope) => scope.capture_outputs(&step.step_id, &expressions),
+        None => context.capture_outputs(&step.step_id, &expressions)
+      };
+      match outputs {
+        Ok(mut outputs) => {
+          if let Some(stub) = stub {
+            outputs.extend(stub.outputs.clone());
+          }
+          for (name, value) in &outputs {
+            context.set_step_output(step.step_id.as_str(), name.as_str(), value.clone());
+            if let Some(scope) = &mut scope {
+              scope.set_step_output(step.step_id.as_str(), name.as_str(), value.clone());
+            }
+          }
+          result.outputs = outputs;
+        }
+        Err(err) => result.fail(ExecutionStatus::Error, err)
+      }
+    }
+    (result, scope)
+  }
+
+  /// Adds the credentials for the step or source description to the request
+  fn authenticate(&self, step: &Step, operation: &Operation, request: &mut StepRequest) -> anyhow::Result<()> {
+    let provider = self.step_auth.get(&step.step_id)
+      .or_else(|| self.source_auth.get(&operation.source_name));
+    match provider {
+      Some(provider) => provider.authenticate(step, operation, request)
+        .map_err(|err| anyhow!("Failed to add the credentials for step '{}': {:#}", step.step_id, err)),
+      None => Ok(())
+    }
+  }
+
+  /// Returns the timeout for the request of the step
+  fn step_timeout(&self, step: &Step) -> anyhow::Result<Duration> {
+    if let Some(timeout) = self.options.step_timeouts.get(&step.step_id) {
+      return Ok(*timeout);
+    }
+    match typed_extension::<Timeout>(&step.extensions) {
+      Some(timeout) => timeout
+        .and_then(|timeout| timeout.validate().map(|_| timeout.0))
+        .map_err(|err| anyhow!("The x-timeout extension of step '{}' is not valid: {}", step.step_id, err)),
+      None => Ok(self.options.timeout)
+    }
+  }
+
+  /// Returns the timeout for the workflow, if it has one
+  fn workflow_timeout(&self, workflow: &Workflow) -> anyhow::Result<Option<Duration>> {
+    if let Some(timeout) = self.options.workflow_timeouts.get(&workflow.workflow_id) {
+      return Ok(Some(*timeout));
+    }
+    typed_extension::<Timeout>(&workflow.extensions)
+      .map(|timeout| timeout
+        .and_then(|timeout| timeout.validate().map(|_| timeout.0))
+        .map_err(|err| anyhow!("The x-timeout extension of workflow '{}' is not valid: {}", workflow.workflow_id, err)))
+      .transpose()
+  }
+
+  /// Returns the parameters for the step (including the workflow parameters) with their values
+  pub(crate) fn parameters(
+    &self,
+    workflow: &Workflow,
+    step: &Step,
+    context: &dyn ExpressionResolver
+  ) -> anyhow::Result<Vec<(ParameterObject, AnyValue)>> {
+    let mut parameters: Vec<(ParameterObject, AnyValue)> = vec![];
+    for parameter in workflow.parameters.iter().chain(step.parameters.iter()) {
+      let (parameter, value) = match parameter {
+        Either::First(parameter) => (parameter.clone(), None),
+        Either::Second(reusable) => {
+          let name = reusable.reference.strip_prefix("$components.parameters.")
+            .ok_or_else(|| anyhow!("'{}' is not a reference to a component parameter", reusable.reference))?;
+          let parameter = self.description.components.parameters.get(name)
+            .ok_or_else(|| anyhow!("No component parameter with name '{}' was found", name))?;
+          (parameter.clone(), reusable.value.clone())
+        }
+      };
+      let value = match value {
+        Some(value) if value.trim().starts_with('$') => context.resolve(&value)?,
+        Some(value) => AnyValue::String(value),
+        None => parameter_value(&parameter, context)?
+      };
+      // Step parameters override workflow parameters with the same name and location
+      parameters.retain(|(p, _)| p.name != parameter.name || p.r#in != parameter.r#in);
+      parameters.push((parameter, value));
+    }
+    Ok(parameters)
+  }
+
+  /// Builds the request for the step from the operation, parameters and request body
+  pub(crate) fn build_step_request(
+    &self,
+    step: &Step,
+    parameters: Vec<(ParameterObject, AnyValue)>,
+    context: &dyn ExpressionResolver
+  ) -> anyhow::Result<(Operation, StepRequest, BTreeMap<String, AnyValue>)> {
+    let operation = self.operations.resolve_step(step)?;
+    let server_url = self.options.server_urls.get(&operation.source_name)
+      .cloned()
+      .or_else(|| self.options.environment.as_ref()
+        .and_then(|environment| environment.base_url(&operation.source_name, operation.server_url.as_deref()))
+        .map(|url| url.to_string()))
+      .or(operation.server_url.clone())
+      .ok_or_else(|| anyhow!("There is no server URL for source description '{}'", operation.source_name))?;
+
+    let mut path = operation.path.clone();
+    let mut path_parameters = BTreeMap::new();
+    let mut query = vec![];
+    let mut headers = vec![];
+    let mut cookies = vec![];
+    for (parameter, value) in &parameters {
+      let text = value_text(value);
+      match parameter.r#in.as_deref() {
+        Some("path") => {
+          path = path.replace(&format!("{{{}}}", parameter.name), &percent_encode(&text));
+          path_parameters.insert(parameter.name.clone(), value.clone());
+        }
+        Some("query") => query.push(format!("{}={}", percent_encode(&parameter.name), percent_encode(&text))),
+        Some("header") => headers.push((parameter.name.clone(), text)),
+        Some("cookie") => cookies.push(format!("{}={}", parameter.name, text)),
+        _ => return Err(anyhow!("Parameter '{}' of step '{}' must have a location ('in') [4.6.6.1 Fixed Fields]",
+          parameter.name, step.step_id))
+      }
+    }
+    if !cookies.is_empty() {
+      headers.push(("Cookie".to_string(), cookies.join("; ")));
+    }
+
+    let body = match &step.request_body {
+      Some(request_body) => {
+        let rendered = request_body.render(context)?;
+        if let Some(content_type) = rendered.content_type &&
+          !headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("content-type")) {
+          headers.push(("Content-Type".to_string(), content_type));
+        }
+        Some(rendered.bytes)
+      }
+      None => None
+    };
+
+    let mut url = format!("{}{}", server_url.trim_end_matches('/'), path);
+    if !query.is_empty() {
+      url.push(if url.contains('?') { '&' } else { '?' });
+      url.push_str(&query.join("&"));
+    }
+    let request = StepRequest { method: operation.method.clone(), url, headers, body };
+    Ok((operation, request, path_parameters))
+  }
+
+  /// Returns the action to take after the step, and the name of the action that was selected
+  fn next_action(
+    &self,
+    workflow: &Workflow,
+    step: &Step,
+    result: &StepResult,
+    context: &ExecutionContext
+  ) -> anyhow::Result<(Option<String>, NextAction)> {
+    if result.status == ExecutionStatus::Success {
+      let actions = merge_actions(&step.on_success, &workflow.success_actions, |reusable| {
+        self.component_action(reusable, "$components.successActions.", &self.description.components.success_actions)
+      }, |action| action.name.clone())?;
+      for action in actions {
+        if action_applies(&action.criteria, context) {
+          let next = match action.r#type.as_str() {
+            "end" => NextAction::End,
+            "goto" => goto_action(action.step_id.as_ref(), action.workflow_id.as_ref())?,
+            _ => return Err(anyhow!("'{}' is not a valid success action type [4.6.7.1 Fixed Fields]", action.r#type))
+          };
+          return Ok((Some(action.name.clone()), next));
+        }
+      }
+    } else {
+      let actions = merge_actions(&step.on_failure, &workflow.failure_actions, |reusable| {
+        self.component_action(reusable, "$components.failureActions.", &self.description.components.failure_actions)
+      }, |action| action.name.clone())?;
+      for action in actions {
+        if action_applies(&action.criteria, context) {
+          let next = match action.r#type.as_str() {
+            "end" => NextAction::End,
+            "retry" => retry_action(&action)?,
+            "goto" => goto_action(action.step_id.as_ref(), action.workflow_id.as_ref())?,
+            _ => return Err(anyhow!("'{}' is not a valid failure action type [4.6.8.1 Fixed Fields]", action.r#type))
+          };
+          return Ok((Some(action.name.clone()), next));
+        }
+      }
+    }
+    Ok((None, NextAction::Continue))
+  }
+
+  fn component_action<T: Clone>(
+    &self,
+    reusable: &ReusableObject,
+    prefix: &str,
+    components: &HashMap<String, T>
+  ) -> anyhow::Result<T> {
+    let name = reusable.reference.strip_prefix(prefix)
+      .ok_or_else(|| anyhow!("'{}' is not a reference to a component action", reusable.reference))?;
+    components.get(name)
+      .cloned()
+      .ok_or_else(|| anyhow!("No component action with name '{}' was found", name))
+  }
+}
+
+/// Resolves the step actions, followed by any workflow actions that the step does not override
+/// (with an action with the same name)
+fn merge_actions<T, F, N>(
+  step_actions: &[Either<T, ReusableObject>],
+  workflow_actions: &[Either<T, ReusableObject>],
+  resolve: F,
+  name: N
+) -> anyhow::Result<Vec<T>>
+  where T: Debug + Clone + PartialEq,
+        F: Fn(&ReusableObject) -> anyhow::Result<T>,
+        N: Fn(&T) -> String
+{
+  let resolve_all = |actions: &[Either<T, ReusableObject>]| actions.iter()
+    .map(|action| match action {
+      Either::First(action) => Ok(action.clone()),
+      Either::Second(reusable) => resolve(reusable)
+    })
+    .collect::<anyhow::Result<Vec<_>>>();
+  let mut actions = resolve_all(step_actions)?;
+  let names = actions.iter().map(&name).collect::<Vec<_>>();
+  actions.extend(resolve_all(workflow_actions)?.into_iter().filter(|action| !names.contains(&name(action))));
+  Ok(actions)
+}
+
+fn goto_action(step_id: Option<&String>, workflow_id: Option<&String>) -> anyhow::Result<NextAction> {
+  match (step_id, workflow_id) {
+    (Some(step_id), _) => Ok(NextAction::GotoStep(step_id.clone())),
+    (None, Some(workflow_id)) => Ok(NextAction::GotoWorkflow(workflow_id.clone())),
+    (None, None) => Err(anyhow!("A goto action requires a stepId or workflowId"))
+  }
+}
+
+/// Groups the workflows of the plan (by index) into stages, where each workflow is in the stage
+/// after the last of its dependencies
+fn plan_stages(plan: &ExecutionPlan) -> Vec<Vec<usize>> {
+  let mut levels: HashMap<&str, usize> = HashMap::new();
+  let mut stages: Vec<Vec<usize>> = vec![];
+  for (index, planned) in plan.workflows.iter().enumerate() {
+    let level = planned.depends_on.iter()
+      .filter_map(|workflow_id| levels.get(workflow_id.as_str()))
+      .map(|level| level + 1)
+      .max()
+      .unwrap_or_default();
+    levels.insert(planned.workflow_id.as_str(), level);
+    if stages.len() <= level {
+      stages.resize_with(level + 1, Vec::new);
+    }
+    stages[level].push(index);
+  }
+  stages
+}
+
+fn retry_action(action: &FailureObject) -> anyhow::Result<NextAction> {
+  let delay = match action.retry_after {
+    Some(after) if after.is_finite() && after >= 0.0 => Some(Duration::from_secs_f64(after)),
+    Some(after) => return Err(anyhow!("retryAfter must be a non-negative number, got {} [4.6.8.1 Fixed Fields]", after)),
+    None => None
+  };
+  let limit = match action.retry_limit {
+    Some(limit) => usize::try_from(limit)
+      .map_err(|_| anyhow!("retryLimit must be a non-negative integer, got {} [4.6.8.1 Fixed Fields]", limit))?,
+    None => 1
+  };
+  Ok(NextAction::Retry { delay, limit, step_id: action.step_id.clone(), workflow_id: action.workflow_id.clone() })
+}
+
+/// Action criteria that can not be evaluated mean the action does not apply
+fn action_applies(criteria: &[Criterion], resolver: &dyn ExpressionResolver) -> bool {
+  evaluate_criteria(criteria, resolver).unwrap_or(false)
+}
+
+fn evaluate_criterion(criterion: &Criterion, resolver: &dyn ExpressionResolver) -> CriterionResult {
+  let (passed, error) = match crate::criteria::evaluate_criterion(criterion, resolver) {
+    Ok(passed) => (passed, None),
+    Err(err) => (false, Some(err.to_string()))
+  };
+  CriterionResult { condition: criterion.condition.clone(), passed, error }
+}
+
+fn parameter_value(parameter: &ParameterObject, context: &dyn ExpressionResolver) -> anyhow::Result<AnyValue> {
+  match &parameter.value {
+    Either::First(AnyValue::String(value)) =>
+      render_template(value, context, &TemplateOptions::default()).map(AnyValue::String),
+    Either::First(value) => Ok(value.clone()),
+    Either::Second(expression) => context.resolve(expression)
+  }
+}
+
+fn value_text(value: &AnyValue) -> String {
+  value.to_text().unwrap_or_else(|| value.to_json().to_string())
+}
+
+pub(crate) fn percent_encode(value: &str) -> String {
+  let mut result = String::with_capacity(value.len());
+  for byte in value.bytes() {
+    match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => result.push(byte as char),
+      _ => result.push_str(&format!("%{:02X}", byte))
+    }
+  }
+  result
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{Arc, Mutex};
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  use std::time::Duration;
+
+  use bytes::Bytes;
+  use expectest::prelude::*;
+  use indexmap::indexmap;
+  use maplit::btreemap;
+  use serde_json::json;
+
+  use crate::arazzo;
+  use crate::auth::{ApiKeyAuth, BearerTokenAuth};
+  use crate::checkpoint::Checkpoint;
+  use crate::dataset::{Dataset, DatasetOptions};
+  use crate::environments::Environment;
+  use crate::either::Either;
+  use crate::execution_context::{ExecutionContext, StepRequest, StepResponse};
+  use crate::executor::{ExecutionStatus, Executor, ExecutorOptions, Timeout};
+  use crate::extension_registry::TypedExtension;
+  use crate::expressions::ExpressionResolver;
+  use crate::extensions::AnyValue;
+  use crate::http_config::tests::serve_once;
+  use crate::mock_executor::MockExecutor;
+  use crate::operations::{OpenApiSource, Operation, OperationResolver};
+  use crate::plan::plan;
+  use crate::rate_limit::{BackoffStrategy, RateLimit};
+  use crate::simulation::{SimulationStubs, StepStub};
+  use crate::v1_0::{ArazzoDescription, Criterion, FailureObject, ParameterObject, Step, SuccessObject, Workflow};
+
+  fn operations(url: &str) -> OperationResolver {
+    OperationResolver::new(vec![OpenApiSource::new("petstore", "petstore.json", json!({
+      "openapi": "3.1.0",
+      "servers": [{ "url": url }],
+      "paths": {
+        "/pets/{petId}": { "get": { "operationId": "getPet" } }
+      }
+    }))])
+  }
+
+  fn criterion(condition: &str) -> Criterion {
+    Criterion { context: None, condition: condition.to_string(), r#type: None, extensions: Default::default() }
+  }
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      workflow("get-pet") {
+        step("find") {
+          operation_id: "getPet";
+          parameter("petId", "path", "$inputs.id");
+          parameter("X-Request", "header", "pet-{$inputs.id}");
+          success_criterion: "$statusCode == 200";
+          output("name", "$response.body#/name");
+          output("type", "$response.header.content-type");
+        }
+        output("petName", "$steps.find.outputs.name");
+      }
+    }
+  }
+
+  #[test]
+  fn executes_a_workflow() {
+    let url = serve_once("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 15\r\n\r\n{\"name\":\"Fido\"}");
+    let description = description();
+    let executor = Executor::with_operations(&description, operations(&url), ExecutorOptions::default());
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(10) });
+    let result = executor.execute("get-pet", inputs).unwrap();
+
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Success));
+    expect!(result.outputs.clone()).to(be_equal_to(btreemap!{
+      "petName".to_string() => AnyValue::String("Fido".to_string())
+    }));
+    let step = result.step("find").unwrap();
+    let request = step.request.as_ref().unwrap();
+    expect!(request.method.as_str()).to(be_equal_to("GET"));
+    expect!(request.url.clone()).to(be_equal_to(format!("{}/pets/10", url)));
+    expect!(request.headers.clone()).to(be_equal_to(vec![("X-Request".to_string(), "pet-10".to_string())]));
+    expect!(step.criteria[0].passed).to(be_true());
+    expect!(step.outputs.get("type")).to(be_some().value(&AnyValue::String("application/json".to_string())));
+    expect!(result.captures.status_code("find")).to(be_some().value(200));
+    expect!(result.captures.resolve("find", "$request.header.X-Request").unwrap()).to(be_equal_to(AnyValue::String("pet-10".to_string())));
+  }
+
+  #[test]
+  fn executes_the_steps_with_a_step_executor() {
+    let description = description();
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), ExecutorOptions::default())
+      .with_step_executor(|step: &Step, operation: &Operation, request: &StepRequest, context: &ExecutionContext| {
+        expect!(step.step_id.as_str()).to(be_equal_to("find"));
+        expect!(operation.operation_id.as_deref()).to(be_some().value("getPet"));
+        expect!(context.resolve("$url").unwrap()).to(be_equal_to(AnyValue::String(request.url.clone())));
+        Ok(StepResponse {
+          status: 200,
+          headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+          body: Bytes::from_static(b"{\"name\":\"Tom\"}")
+        })
+      });
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(2) });
+    let result = executor.execute("get-pet", inputs).unwrap();
+
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Success));
+    expect!(result.step("find").unwrap().request.as_ref().unwrap().url.as_str()).to(be_equal_to("http://pets.local/pets/2"));
+    expect!(result.outputs.get("petName")).to(be_some().value(&AnyValue::String("Tom".to_string())));
+
+    let executor = executor.with_step_executor(|_: &Step, _: &Operation, _: &StepRequest, _: &ExecutionContext| {
+      Err(anyhow::anyhow!("Connection refused"))
+    });
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(2) });
+    let result = executor.execute("get-pet", inputs).unwrap();
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Error));
+    let step = result.step("find").unwrap();
+    expect!(step.error.as_deref()).to(be_some().value("Connection refused"));
+    expect!(step.request.is_some()).to(be_true());
+  }
+
+  #[test]
+  fn executes_a_workflow_for_each_row_of_a_dataset() {
+    let mut description = description();
+    description.workflows[0].inputs = json!({
+      "type": "object",
+      "properties": { "id": { "type": "integer" } },
+      "required": ["id"]
+    });
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), ExecutorOptions::default())
+      .with_step_executor(|_: &Step, _: &Operation, request: &StepRequest, _: &ExecutionContext| {
+        Ok(StepResponse {
+          status: if request.url.ends_with("/pets/3") { 404 } else { 200 },
+          headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+          body: Bytes::from_static(b"{\"name\":\"Tom\"}")
+        })
+      });
+    let dataset = Dataset::from_csv("petId\n1\nx\n3\n4\n").unwrap();
+    let options = DatasetOptions {
+      column_mapping: btreemap!{ "petId".to_string() => "id".to_string() },
+      .. DatasetOptions::default()
+    };
+
+    let result = executor.execute_dataset("get-pet", &dataset, &options).unwrap();
+    let statuses = result.rows.iter().map(|row| row.result.status).collect::<Vec<_>>();
+    expect!(statuses).to(be_equal_to(vec![ExecutionStatus::Success, ExecutionStatus::Error,
+      ExecutionStatus::Failure, ExecutionStatus::Success]));
+    expect!(result.rows[1].result.error.as_deref())
+      .to(be_some().value("Input 'id' is not valid: expected an integer, but got 'x'"));
+    expect!(result.rows[3].inputs.clone()).to(be_equal_to(json!({ "id": "4" })));
+    expect!(result.is_success()).to(be_false());
+    let report = result.report();
+    expect!(report.workflows.iter().map(|workflow| workflow.workflow_id.as_str()).collect::<Vec<_>>())
+      .to(be_equal_to(vec!["get-pet [row 1]", "get-pet [row 2]", "get-pet [row 3]", "get-pet [row 4]"]));
+
+    let options = DatasetOptions { fail_fast: true, .. options };
+    let result = executor.execute_dataset("get-pet", &dataset, &options).unwrap();
+    expect!(result.rows.len()).to(be_equal_to(2));
+    expect!(result.skipped).to(be_equal_to(2));
+  }
+
+  #[test]
+  fn rows_with_columns_that_can_not_be_mapped_have_an_error_result() {
+    let description = description();
+    let mock = MockExecutor::new();
+    mock.respond_to_operation("getPet", StepResponse::new(200).with_json(&json!({ "name": "Tom" })));
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), ExecutorOptions::default())
+      .with_step_executor(mock.clone());
+    let dataset = Dataset::from_csv("petId,id\n1,2\n").unwrap();
+    let options = DatasetOptions {
+      column_mapping: btreemap!{ "petId".to_string() => "id".to_string() },
+      .. DatasetOptions::default()
+    };
+
+    let result = executor.execute_dataset("get-pet", &dataset, &options).unwrap();
+    expect!(result.rows[0].result.status).to(be_equal_to(ExecutionStatus::Error));
+    expect!(result.rows[0].result.error.as_deref())
+      .to(be_some().value("Column 'petId' is mapped to input 'id', which is already set by another column"));
+    expect!(mock.invocations().is_empty()).to(be_true());
+  }
+
+  #[test]
+  fn fails_the_workflow_when_the_criteria_do_not_pass() {
+    let url = serve_once("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+    let description = description();
+    let executor = Executor::with_operations(&description, operations(&url), ExecutorOptions::default());
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(10) });
+    let result = executor.execute("get-pet", inputs).unwrap();
+
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Failure));
+    expect!(result.error.clone()).to(be_some().value("Step 'find' failed"));
+    expect!(result.outputs.is_empty()).to(be_true());
+    expect!(executor.execute("other", AnyValue::Null)).to(be_err());
+  }
+
+  #[test]
+  fn follows_failure_actions() {
+    let url = serve_once("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+    let mut description = description();
+    let workflow = &mut description.workflows[0];
+    workflow.outputs.clear();
+    workflow.steps[0].on_failure.push(Either::First(FailureObject {
+      name: "missing".to_string(),
+      r#type: "goto".to_string(),
+      workflow_id: None,
+      step_id: Some("report".to_string()),
+      retry_after: None,
+      retry_limit: None,
+      criteria: vec![criterion("$statusCode == 404")],
+      extensions: Default::default()
+    }));
+    workflow.steps.push(Step {
+      step_id: "report".to_string(),
+      workflow_id: Some("not-found".to_string()),
+      .. Step::default()
+    });
+    description.workflows.push(Workflow {
+      workflow_id: "not-found".to_string(),
+      .. Workflow::default()
+    });
+
+    let executor = Executor::with_operations(&description, operations(&url), ExecutorOptions::default());
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(10) });
+    let result = executor.execute("get-pet", inputs).unwrap();
+
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Success));
+    expect!(result.steps.iter().map(|step| step.step_id.as_str()).collect::<Vec<_>>()).to(be_equal_to(vec!["find", "report"]));
+    expect!(result.steps[0].status).to(be_equal_to(ExecutionStatus::Failure));
+    expect!(result.steps[0].action.clone()).to(be_some().value("missing"));
+    expect!(result.steps[1].workflow.as_ref().map(|workflow| workflow.workflow_id.as_str())).to(be_some().value("not-found"));
+  }
+
+  #[test]
+  fn retries_failed_steps() {
+    let mut description = description();
+    let workflow = &mut description.workflows[0];
+    workflow.steps[0].on_failure.push(Either::First(FailureObject {
+      name: "unavailable".to_string(),
+      r#type: "retry".to_string(),
+      workflow_id: None,
+      step_id: None,
+      retry_after: Some(0.5),
+      retry_limit: Some(3),
+      criteria: vec![criterion("$statusCode == 503")],
+      extensions: Default::default()
+    }));
+
+    let mock = MockExecutor::new();
+    mock.respond_to_step("find", StepResponse::new(503))
+      .respond_to_step("find", StepResponse::new(503))
+      .respond_to_step("find", StepResponse::new(200).with_json(&json!({ "name": "Tom" })));
+    let delays = Arc::new(Mutex::new(vec![]));
+    let waited = delays.clone();
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), ExecutorOptions::default())
+      .with_step_executor(mock.clone())
+      .with_retry_timer(move |delay: Duration| waited.lock().unwrap().push(delay));
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(2) });
+    let result = executor.execute("get-pet", inputs.clone()).unwrap();
+
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Success));
+    expect!(result.steps.iter().map(|step| (step.attempt, step.status)).collect::<Vec<_>>()).to(be_equal_to(vec![
+      (1, ExecutionStatus::Failure), (2, ExecutionStatus::Failure), (3, ExecutionStatus::Success)
+    ]));
+    expect!(result.steps[0].action.clone()).to(be_some().value("unavailable"));
+    expect!(delays.lock().unwrap().clone()).to(be_equal_to(vec![Duration::from_millis(500); 2]));
+    expect!(result.outputs.get("petName")).to(be_some().value(&AnyValue::String("Tom".to_string())));
+
+    let mock = MockExecutor::new();
+    mock.respond_to_step("find", StepResponse::new(503));
+    let executor = executor.with_step_executor(mock.clone());
+    let result = executor.execute("get-pet", inputs.clone()).unwrap();
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Failure));
+    expect!(result.error.as_deref()).to(be_some().value("Step 'find' failed after 4 attempt(s)"));
+    expect!(mock.invocation_count("find")).to(be_equal_to(4));
+
+    let mock = MockExecutor::new();
+    mock.respond_to_step("find", StepResponse::new(404));
+    let executor = executor.with_step_executor(mock.clone());
+    let result = executor.execute("get-pet", inputs).unwrap();
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Failure));
+    expect!(mock.invocation_count("find")).to(be_equal_to(1));
+  }
+
+  #[test]
+  fn applies_rate_limits_and_backoff() {
+    let mut description = description();
+    description.workflows[0].steps[0].on_failure.push(Either::First(FailureObject {
+      name: "unavailable".to_string(),
+      r#type: "retry".to_string(),
+      workflow_id: None,
+      step_id: None,
+      retry_after: Some(0.5),
+      retry_limit: Some(3),
+      criteria: vec![],
+      extensions: Default::default()
+    }));
+    let mock = MockExecutor::new();
+    mock.respond_to_step("find", StepResponse::new(503))
+      .respond_to_step("find", StepResponse::new(200).with_json(&json!({ "name": "Tom" })));
+    let delays = Arc::new(Mutex::new(vec![]));
+    let waited = delays.clone();
+    let options = ExecutorOptions {
+      source_rate_limits: btreemap!{ "petstore".to_string() => RateLimit::new(1, Duration::from_secs(10)) },
+      backoff: BackoffStrategy::Fixed(Duration::from_secs(1)),
+      .. ExecutorOptions::default()
+    };
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), options)
+      .with_step_executor(mock.clone())
+      .with_retry_timer(move |delay: Duration| waited.lock().unwrap().push(delay));
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(2) });
+    let result = executor.execute("get-pet", inputs).unwrap();
+
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Success));
+    let delays = delays.lock().unwrap().clone();
+    expect!(delays.len()).to(be_equal_to(2));
+    expect!(delays[0]).to(be_equal_to(Duration::from_secs(1)));
+    expect!(delays[1] > Duration::from_secs(9) && delays[1] <= Duration::from_secs(10)).to(be_true());
+
+    let options = ExecutorOptions {
+      host_rate_limits: btreemap!{ "pets.local".to_string() => RateLimit::per_second(0) },
+      .. ExecutorOptions::default()
+    };
+    expect!(Executor::new(&description, options).is_err()).to(be_true());
+  }
+
+  #[test]
+  fn simulates_workflows_with_stubbed_outputs() {
+    let mut description = description();
+    let workflow = &mut description.workflows[0];
+    let mut adopt = workflow.steps[0].clone();
+    adopt.step_id = "adopt".to_string();
+    adopt.outputs.clear();
+    let mut notify = adopt.clone();
+    notify.step_id = "notify".to_string();
+    workflow.steps[0].on_success.push(Either::First(SuccessObject {
+      name: "dog".to_string(),
+      r#type: "goto".to_string(),
+      workflow_id: None,
+      step_id: Some("notify".to_string()),
+      criteria: vec![criterion("$steps.find.outputs.name == 'Rex'")],
+      extensions: Default::default()
+    }));
+    workflow.steps[0].on_failure.push(Either::First(FailureObject {
+      name: "unavailable".to_string(),
+      r#type: "retry".to_string(),
+      workflow_id: None,
+      step_id: None,
+      retry_after: Some(60.0),
+      retry_limit: Some(1),
+      criteria: vec![criterion("$statusCode == 503")],
+      extensions: Default::default()
+    }));
+    workflow.steps.push(adopt);
+    workflow.steps.push(notify);
+    let mock = MockExecutor::new();
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), ExecutorOptions::default())
+      .with_step_executor(mock.clone());
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(2) });
+
+    let stubs = SimulationStubs::new()
+      .stub("find", StepStub::new().with_status(503))
+      .stub("find", StepStub::new()
+        .with_output("name", AnyValue::String("Rex".to_string()))
+        .with_output("type", AnyValue::String("dog".to_string())));
+    let report = executor.simulate("get-pet", inputs.clone(), &stubs).unwrap();
+    expect!(report.status).to(be_equal_to(ExecutionStatus::Success));
+    expect!(report.step_ids()).to(be_equal_to(vec!["find", "find", "notify"]));
+    expect!(report.actions()).to(be_equal_to(vec!["unavailable", "dog"]));
+    expect!(report.unreached_steps.clone()).to(be_equal_to(vec!["adopt".to_string()]));
+    expect!(report.path[2].stubbed).to(be_false());
+    expect!(report.outputs.get("petName")).to(be_some().value(&AnyValue::String("Rex".to_string())));
+    expect!(mock.invocations().is_empty()).to(be_true());
+
+    let stubs = SimulationStubs::new().stub("find", StepStub::new()
+      .with_response(StepResponse::new(200).with_header("Content-Type", "application/json").with_json(&json!({ "name": "Tom" }))));
+    let report = executor.simulate("get-pet", inputs, &stubs).unwrap();
+    expect!(report.step_ids()).to(be_equal_to(vec!["find", "adopt", "notify"]));
+    expect!(report.outputs.get("petName")).to(be_some().value(&AnyValue::String("Tom".to_string())));
+  }
+
+  #[test]
+  fn does_not_authenticate_simulated_steps() {
+    let description = description();
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), ExecutorOptions::default())
+      .with_step_executor(MockExecutor::new())
+      .with_source_auth("petstore", |_: &Step, _: &Operation, _: &mut StepRequest| -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("Credentials must not be fetched for simulations"))
+      });
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(2) });
+    let stubs = SimulationStubs::new().stub("find", StepStub::new()
+      .with_output("name", AnyValue::String("Rex".to_string()))
+      .with_output("type", AnyValue::String("dog".to_string())));
+
+    let report = executor.simulate("get-pet", inputs.clone(), &stubs).unwrap();
+    expect!(report.status).to(be_equal_to(ExecutionStatus::Success));
+    let request = report.result.step("find").unwrap().request.clone().unwrap();
+    expect!(request.headers.iter().any(|(name, _)| name == "Authorization")).to(be_false());
+
+    let result = executor.execute("get-pet", inputs).unwrap();
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Error));
+  }
+
+  #[test]
+  fn adds_credentials_to_the_requests() {
+    let mut description = description();
+    let mut sibling = description.workflows[0].steps[0].clone();
+    sibling.step_id = "sibling".to_string();
+    description.workflows[0].steps.push(sibling);
+    let mock = MockExecutor::new();
+    mock.respond_to_operation("getPet", StepResponse::new(200).with_json(&json!({ "name": "Tom" })));
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), ExecutorOptions::default())
+      .with_step_executor(mock.clone())
+      .with_source_auth("petstore", BearerTokenAuth::new("abc123"))
+      .with_step_auth("sibling", ApiKeyAuth::header("X-API-Key", "key"));
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(2) });
+    let result = executor.execute("get-pet", inputs.clone()).unwrap();
+
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Success));
+    let invocations = mock.invocations();
+    expect!(invocations[0].request.headers.clone()).to(be_equal_to(vec![
+      ("X-Request".to_string(), "pet-2".to_string()),
+      ("Authorization".to_string(), "Bearer abc123".to_string())
+    ]));
+    expect!(invocations[1].request.headers.clone()).to(be_equal_to(vec![
+      ("X-Request".to_string(), "pet-2".to_string()),
+      ("X-API-Key".to_string(), "key".to_string())
+    ]));
+
+    let executor = executor.with_step_auth("find", |_: &Step, _: &Operation, _: &mut StepRequest| {
+      Err(anyhow::anyhow!("The token has been revoked"))
+    });
+    let result = executor.execute("get-pet", inputs).unwrap();
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Error));
+    expect!(result.steps[0].error.as_deref()).to(be_some().value("Failed to add the credentials for step 'find': The token has been revoked"));
+  }
+
+  #[test]
+  fn uses_the_base_urls_of_the_environment() {
+    let description = description();
+    let mock = MockExecutor::new();
+    mock.respond_to_step("find", StepResponse::new(200).with_json(&json!({ "name": "Tom" })));
+    let options = ExecutorOptions {
+      environment: Some(Environment::new("staging").with_server_url("http://pets.local/", "http://staging.internal/v2")),
+      .. ExecutorOptions::default()
+    };
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), options)
+      .with_step_executor(mock.clone());
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(2) });
+    executor.execute("get-pet", inputs.clone()).unwrap();
+    expect!(mock.invocations()[0].request.url.as_str()).to(be_equal_to("http://staging.internal/v2/pets/2"));
+
+    let mock = MockExecutor::new();
+    mock.respond_to_step("find", StepResponse::new(200).with_json(&json!({ "name": "Tom" })));
+    let options = ExecutorOptions {
+      environment: Some(Environment::new("dev").with_source_url("petstore", "http://localhost:8080")),
+      .. ExecutorOptions::default()
+    };
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), options)
+      .with_step_executor(mock.clone());
+    executor.execute("get-pet", inputs).unwrap();
+    expect!(mock.invocations()[0].request.url.as_str()).to(be_equal_to("http://localhost:8080/pets/2"));
+  }
+
+  #[test]
+  fn times_out_slow_steps() {
+    let mut description = description();
+    let workflow = &mut description.workflows[0];
+    workflow.steps[0].extensions.insert("timeout".to_string(), AnyValue::String("20ms".to_string()));
+    workflow.steps[0].on_failure.push(Either::First(FailureObject {
+      name: "again".to_string(),
+      r#type: "retry".to_string(),
+      workflow_id: None,
+      step_id: None,
+      retry_after: None,
+      retry_limit: Some(1),
+      criteria: vec![],
+      extensions: Default::default()
+    }));
+    let calls = Arc::new(Mutex::new(0));
+    let counter = calls.clone();
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), ExecutorOptions::default())
+      .with_step_executor(move |_: &Step, _: &Operation, _: &StepRequest, _: &ExecutionContext| {
+        let mut calls = counter.lock().unwrap();
+        *calls += 1;
+        if *calls == 1 {
+          std::thread::sleep(Duration::from_millis(50));
+        }
+        Ok(StepResponse::new(200).with_json(&json!({ "name": "Tom" })))
+      });
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(2) });
+    let result = executor.execute("get-pet", inputs.clone()).unwrap();
+
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Success));
+    expect!(result.steps.iter().map(|step| step.status).collect::<Vec<_>>()).to(be_equal_to(vec![
+      ExecutionStatus::Timeout, ExecutionStatus::Success
+    ]));
+    expect!(result.steps[0].error.as_deref()).to(be_some().value("Step 'find' timed out after 20ms"));
+    expect!(result.steps[0].action.as_deref()).to(be_some().value("again"));
+
+    let options = ExecutorOptions {
+      workflow_timeouts: btreemap!{ "get-pet".to_string() => Duration::from_millis(10) },
+      .. ExecutorOptions::default()
+    };
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), options)
+      .with_step_executor(|_: &Step, _: &Operation, _: &StepRequest, _: &ExecutionContext| {
+        std::thread::sleep(Duration::from_millis(30));
+        Ok(StepResponse::new(500))
+      });
+    let result = executor.execute("get-pet", inputs).unwrap();
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Timeout));
+    expect!(result.steps.len()).to(be_equal_to(1));
+    expect!(result.steps[0].error.clone().unwrap_or_default().starts_with("Step 'find' timed out")).to(be_true());
+    expect!(result.error.as_deref()).to(be_some().value("Workflow 'get-pet' timed out after 10ms"));
+  }
+
+  #[test]
+  fn parses_timeout_extensions() {
+    expect!(Timeout::from_value(&AnyValue::UInteger(250))).to(be_ok().value(Timeout(Duration::from_millis(250))));
+    expect!(Timeout::from_value(&AnyValue::String("250ms".to_string()))).to(be_ok().value(Timeout(Duration::from_millis(250))));
+    expect!(Timeout::from_value(&AnyValue::String("1.5s".to_string()))).to(be_ok().value(Timeout(Duration::from_millis(1500))));
+    expect!(Timeout::from_value(&AnyValue::String("2m".to_string()))).to(be_ok().value(Timeout(Duration::from_secs(120))));
+    expect!(Timeout::from_value(&AnyValue::String("soon".to_string()))).to(be_err());
+    expect!(Timeout::from_value(&AnyValue::Integer(-1))).to(be_err());
+    expect!(Timeout::from_value(&AnyValue::Boolean(true))).to(be_err());
+  }
+
+  #[test]
+  fn resumes_a_workflow_from_a_checkpoint() {
+    let mut description = description();
+    description.workflows[0].steps.push(Step {
+      step_id: "sibling".to_string(),
+      operation_id: Some("getPet".to_string()),
+      parameters: vec![Either::First(ParameterObject {
+        name: "petId".to_string(),
+        r#in: Some("path".to_string()),
+        value: Either::Second("$steps.find.outputs.name".to_string()),
+        extensions: Default::default()
+      })],
+      success_criteria: vec![criterion("$statusCode == 200")],
+      .. Step::default()
+    });
+    let dir = std::env::temp_dir().join(format!("arazzo-checkpoint-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("get-pet.json");
+    let options = ExecutorOptions { checkpoint_file: Some(path.clone()), .. ExecutorOptions::default() };
+
+    let mock = MockExecutor::new();
+    mock.respond_to_step("find", StepResponse::new(200).with_json(&json!({ "name": "Tom" })))
+      .respond_to_step("sibling", StepResponse::new(502));
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), options)
+      .with_step_executor(mock);
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(2) });
+    let result = executor.execute("get-pet", inputs).unwrap();
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Failure));
+
+    let checkpoint = Checkpoint::load(&path).unwrap();
+    expect!(checkpoint.next_step.as_deref()).to(be_some().value("sibling"));
+    expect!(checkpoint.completed_steps.clone()).to(be_equal_to(vec!["find".to_string()]));
+
+    let mock = MockExecutor::new();
+    mock.respond_to_step("sibling", StepResponse::new(200));
+    let executor = executor.with_step_executor(mock.clone());
+    let result = executor.resume(&checkpoint).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Success));
+    expect!(result.steps.iter().map(|step| step.step_id.as_str()).collect::<Vec<_>>()).to(be_equal_to(vec!["sibling"]));
+    expect!(mock.invocation_count("find")).to(be_equal_to(0));
+    expect!(result.steps[0].request.as_ref().unwrap().url.as_str()).to(be_equal_to("http://pets.local/pets/Tom"));
+    expect!(result.outputs.get("petName")).to(be_some().value(&AnyValue::String("Tom".to_string())));
+  }
+
+  #[test]
+  fn follows_goto_actions_to_other_workflows() {
+    let mut description = description();
+    let workflow = &mut description.workflows[0];
+    workflow.steps[0].on_success.push(Either::First(SuccessObject {
+      name: "owner".to_string(),
+      r#type: "goto".to_string(),
+      workflow_id: Some("get-owner".to_string()),
+      step_id: None,
+      criteria: vec![],
+      extensions: Default::default()
+    }));
+    workflow.outputs.insert("owner".to_string(), "$workflows.get-owner.outputs.id".to_string());
+    description.workflows.push(Workflow {
+      workflow_id: "get-owner".to_string(),
+      outputs: btreemap!{ "id".to_string() => "$inputs.id".to_string() },
+      .. Workflow::default()
+    });
+
+    let mock = MockExecutor::new();
+    mock.respond_to_step("find", StepResponse::new(200).with_json(&json!({ "name": "Tom" })));
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), ExecutorOptions::default())
+      .with_step_executor(mock.clone());
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(2) });
+    let result = executor.execute("get-pet", inputs.clone()).unwrap();
+
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Success));
+    expect!(result.steps[0].workflow.as_ref().map(|workflow| workflow.workflow_id.as_str())).to(be_some().value("get-owner"));
+    expect!(result.outputs.get("owner")).to(be_some().value(&AnyValue::Integer(2)));
+    drop(executor);
+
+    description.workflows[1].steps.push(Step {
+      step_id: "back".to_string(),
+      workflow_id: Some("get-pet".to_string()),
+      parameters: vec![Either::First(ParameterObject {
+        name: "id".to_string(),
+        r#in: None,
+        value: Either::Second("$inputs.id".to_string()),
+        extensions: Default::default()
+      })],
+      .. Step::default()
+    });
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), ExecutorOptions {
+      max_workflow_depth: 4,
+      .. ExecutorOptions::default()
+    }).with_step_executor(mock.clone());
+    let result = executor.execute("get-pet", inputs).unwrap();
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Error));
+    let mut nested = &result;
+    while let Some(workflow) = nested.steps.last().and_then(|step| step.workflow.as_deref()) {
+      nested = workflow;
+    }
+    expect!(nested.error.as_deref()).to(be_some().value("Workflow 'get-owner' exceeded the maximum depth of 4 nested workflows"));
+  }
+
+  #[test]
+  fn stops_goto_actions_that_form_a_cycle() {
+    let mut description = description();
+    let workflow = &mut description.workflows[0];
+    workflow.steps[0].on_success.push(Either::First(SuccessObject {
+      name: "again".to_string(),
+      r#type: "goto".to_string(),
+      workflow_id: None,
+      step_id: Some("find".to_string()),
+      criteria: vec![],
+      extensions: Default::default()
+    }));
+
+    let mock = MockExecutor::new();
+    mock.respond_to_step("find", StepResponse::new(200).with_json(&json!({ "name": "Tom" })));
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), ExecutorOptions {
+      max_step_visits: 5,
+      .. ExecutorOptions::default()
+    }).with_step_executor(mock.clone());
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(2) });
+    let result = executor.execute("get-pet", inputs).unwrap();
+
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Error));
+    expect!(result.error.as_deref()).to(be_some().value(
+      "Step 'find' was executed more than 5 times in workflow 'get-pet' (the actions may form a cycle)"));
+    expect!(mock.invocation_count("find")).to(be_equal_to(5));
+  }
+
+  #[test]
+  fn executes_the_workflows_of_a_plan() {
+    let mut description = description();
+    description.workflows[0].depends_on.push("login".to_string());
+    description.workflows[0].steps[0].parameters.push(Either::First(ParameterObject {
+      name: "Authorization".to_string(),
+      r#in: Some("header".to_string()),
+      value: Either::Second("$workflows.login.outputs.token".to_string()),
+      extensions: Default::default()
+    }));
+    description.workflows.push(Workflow {
+      workflow_id: "login".to_string(),
+      outputs: btreemap!{ "token".to_string() => "$inputs.token".to_string() },
+      .. Workflow::default()
+    });
+
+    let mock = MockExecutor::new();
+    mock.respond_to_step("find", StepResponse::new(200).with_json(&json!({ "name": "Tom" })));
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), ExecutorOptions::default())
+      .with_step_executor(mock.clone());
+    let execution_plan = plan(&description, &["get-pet"]).unwrap();
+    let inputs = AnyValue::Object(indexmap!{
+      "id".to_string() => AnyValue::Integer(2),
+      "token".to_string() => AnyValue::String("secret".to_string())
+    });
+    let results = executor.execute_plan(&execution_plan, inputs).unwrap();
+
+    expect!(results.iter().map(|result| (result.workflow_id.as_str(), result.status)).collect::<Vec<_>>())
+      .to(be_equal_to(vec![("login", ExecutionStatus::Success), ("get-pet", ExecutionStatus::Success)]));
+    expect!(mock.invocations()[0].request.headers.contains(&("Authorization".to_string(), "secret".to_string()))).to(be_true());
+  }
+
+  #[test]
+  fn executes_independent_workflows_concurrently() {
+    let mut description = description();
+    let mut other = description.workflows[0].clone();
+    other.workflow_id = "get-other-pet".to_string();
+    description.workflows.push(other);
+    description.workflows.push(Workflow {
+      workflow_id: "summary".to_string(),
+      depends_on: vec!["get-pet".to_string(), "get-other-pet".to_string()],
+      outputs: btreemap!{ "name".to_string() => "$workflows.get-other-pet.outputs.petName".to_string() },
+      .. Workflow::default()
+    });
+
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let max_in_flight = Arc::new(AtomicUsize::new(0));
+    let (current, max) = (in_flight.clone(), max_in_flight.clone());
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), ExecutorOptions {
+      max_concurrency: 4,
+      .. ExecutorOptions::default()
+    }).with_step_executor(move |_: &Step, _: &Operation, _: &StepRequest, _: &ExecutionContext| {
+      max.fetch_max(current.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+      std::thread::sleep(Duration::from_millis(50));
+      current.fetch_sub(1, Ordering::SeqCst);
+      Ok(StepResponse::new(200).with_json(&json!({ "name": "Tom" })))
+    });
+    let execution_plan = plan(&description, &["summary"]).unwrap();
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(2) });
+    let results = executor.execute_plan(&execution_plan, inputs).unwrap();
+
+    expect!(results.iter().map(|result| (result.workflow_id.as_str(), result.status)).collect::<Vec<_>>())
+      .to(be_equal_to(vec![
+        ("get-pet", ExecutionStatus::Success),
+        ("get-other-pet", ExecutionStatus::Success),
+        ("summary", ExecutionStatus::Success)
+      ]));
+    expect!(max_in_flight.load(Ordering::SeqCst)).to(be_equal_to(2));
+    expect!(results[2].outputs.get("name")).to(be_some().value(&AnyValue::String("Tom".to_string())));
+  }
+
+  #[test]
+  fn executes_the_steps_of_a_workflow_in_order() {
+    let mut description = description();
+    let mut step = description.workflows[0].steps[0].clone();
+    step.step_id = "find-again".to_string();
+    description.workflows[0].steps.push(step);
+
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let max_in_flight = Arc::new(AtomicUsize::new(0));
+    let (current, max) = (in_flight.clone(), max_in_flight.clone());
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), ExecutorOptions {
+      max_concurrency: 4,
+      .. ExecutorOptions::default()
+    }).with_step_executor(move |_: &Step, _: &Operation, _: &StepRequest, _: &ExecutionContext| {
+      max.fetch_max(current.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+      std::thread::sleep(Duration::from_millis(20));
+      current.fetch_sub(1, Ordering::SeqCst);
+      Ok(StepResponse::new(200).with_json(&json!({ "name": "Tom" })))
+    });
+    let execution_plan = plan(&description, &["get-pet"]).unwrap();
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(2) });
+    let results = executor.execute_plan(&execution_plan, inputs).unwrap();
+
+    expect!(results[0].steps.iter().map(|step| step.step_id.as_str()).collect::<Vec<_>>())
+      .to(be_equal_to(vec!["find", "find-again"]));
+    expect!(max_in_flight.load(Ordering::SeqCst)).to(be_equal_to(1));
+  }
+
+  #[test]
+  fn limits_the_number_of_workflows_executed_concurrently() {
+    let mut description = description();
+    for index in 1..5 {
+      let mut other = description.workflows[0].clone();
+      other.workflow_id = format!("get-pet-{}", index);
+      description.workflows.push(other);
+    }
+
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let max_in_flight = Arc::new(AtomicUsize::new(0));
+    let (current, max) = (in_flight.clone(), max_in_flight.clone());
+    let executor = Executor::with_operations(&description, operations("http://pets.local"), ExecutorOptions {
+      max_concurrency: 2,
+      .. ExecutorOptions::default()
+    }).with_step_executor(move |_: &Step, _: &Operation, _: &StepRequest, _: &ExecutionContext| {
+      max.fetch_max(current.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+      std::thread::sleep(Duration::from_millis(20));
+      current.fetch_sub(1, Ordering::SeqCst);
+      Ok(StepResponse::new(200).with_json(&json!({ "name": "Tom" })))
+    });
+    let execution_plan = plan(&description, &["get-pet-4", "get-pet-2", "get-pet-3", "get-pet-1", "get-pet"]).unwrap();
+    let inputs = AnyValue::Object(indexmap!{ "id".to_string() => AnyValue::Integer(2) });
+    let results = executor.execute_plan(&execution_plan, inputs).unwrap();
+
+    expect!(results.iter().map(|result| result.workflow_id.as_str()).collect::<Vec<_>>())
+      .to(be_equal_to(execution_plan.workflows.iter().map(|planned| planned.workflow_id.as_str()).collect::<Vec<_>>()));
+    expect!(results.iter().all(|result| result.is_success())).to(be_true());
+    expect!(max_in_flight.load(Ordering::SeqCst)).to(be_equal_to(2));
+  }
+}
@@ -0,0 +1,691 @@
+//! Support for Runtime Expressions (<https://spec.openapis.org/arazzo/v1.0.1.html#runtime-expressions>).
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::either::Either;
+use crate::extensions::AnyValue;
+use crate::payloads::{string_payload, FormPayload, GraphQlPayload, JsonPayload, Payload, StringPayload};
+#[cfg(feature = "xml")] use crate::payloads::XmlPayload;
+#[cfg(feature = "yaml")] use crate::payloads::YamlPayload;
+#[cfg(feature = "yaml")] use yaml_rust2::Yaml;
+use crate::v1_0::{
+  Components,
+  Criterion,
+  FailureObject,
+  ParameterObject,
+  RequestBody,
+  ReusableObject,
+  Step,
+  SuccessObject,
+  Workflow
+};
+
+/// The type of object that a runtime expression refers to by name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ReferenceKind {
+  /// Source description (`$sourceDescriptions.<name>`)
+  SourceDescription,
+  /// Workflow (`$workflows.<workflowId>`)
+  Workflow,
+  /// Step in the current workflow (`$steps.<stepId>`)
+  Step,
+  /// Reusable input schema (`$components.inputs.<name>`)
+  ComponentInput,
+  /// Reusable parameter (`$components.parameters.<name>`)
+  ComponentParameter,
+  /// Reusable success action (`$components.successActions.<name>`)
+  ComponentSuccessAction,
+  /// Reusable failure action (`$components.failureActions.<name>`)
+  ComponentFailureAction
+}
+
+impl ReferenceKind {
+  /// The expression prefix used to refer to this type of object
+  pub fn prefix(&self) -> &'static str {
+    match self {
+      ReferenceKind::SourceDescription => "$sourceDescriptions.",
+      ReferenceKind::Workflow => "$workflows.",
+      ReferenceKind::Step => "$steps.",
+      ReferenceKind::ComponentInput => "$components.inputs.",
+      ReferenceKind::ComponentParameter => "$components.parameters.",
+      ReferenceKind::ComponentSuccessAction => "$components.successActions.",
+      ReferenceKind::ComponentFailureAction => "$components.failureActions."
+    }
+  }
+
+  /// All the reference kinds
+  pub fn all() -> [ReferenceKind; 7] {
+    [
+      ReferenceKind::SourceDescription,
+      ReferenceKind::Workflow,
+      ReferenceKind::Step,
+      ReferenceKind::ComponentInput,
+      ReferenceKind::ComponentParameter,
+      ReferenceKind::ComponentSuccessAction,
+      ReferenceKind::ComponentFailureAction
+    ]
+  }
+}
+
+impl Display for ReferenceKind {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ReferenceKind::SourceDescription => write!(f, "Source Description"),
+      ReferenceKind::Workflow => write!(f, "Workflow"),
+      ReferenceKind::Step => write!(f, "Step"),
+      ReferenceKind::ComponentInput => write!(f, "Component Input"),
+      ReferenceKind::ComponentParameter => write!(f, "Component Parameter"),
+      ReferenceKind::ComponentSuccessAction => write!(f, "Component Success Action"),
+      ReferenceKind::ComponentFailureAction => write!(f, "Component Failure Action")
+    }
+  }
+}
+
+/// Reference to a named object found in a runtime expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpressionReference {
+  /// Type of object referred to
+  pub kind: ReferenceKind,
+  /// Name or ID of the object
+  pub name: String,
+  /// Byte range of the name in the text that was scanned
+  pub span: Range<usize>
+}
+
+fn is_name_char(ch: char) -> bool {
+  ch.is_ascii_alphanumeric() || ch == '_' || ch == '-'
+}
+
+/// Finds all the references to named objects (steps, workflows, source descriptions and components)
+/// in the text. This will find references in plain expressions (i.e. `$steps.one.outputs.a`) as
+/// well as expressions embedded in strings (i.e. `{$steps.one.outputs.a}`).
+pub fn expression_references(text: &str) -> Vec<ExpressionReference> {
+  let mut references = vec![];
+
+  for (index, _) in text.match_indices('$') {
+    let rest = &text[index..];
+    for kind in ReferenceKind::all() {
+      if let Some(remainder) = rest.strip_prefix(kind.prefix()) {
+        let len = remainder.find(|ch| !is_name_char(ch)).unwrap_or(remainder.len());
+        if len > 0 {
+          let start = index + kind.prefix().len();
+          references.push(ExpressionReference {
+            kind,
+            name: remainder[..len].to_string(),
+            span: start..start + len
+          });
+        }
+        break;
+      }
+    }
+  }
+
+  references
+}
+
+/// Rewrites all the references to named objects in the text. The callback is invoked for each
+/// reference, and if it returns a new name, the name will be replaced.
+pub fn rewrite_expression_references<F>(text: &str, callback: F) -> String
+  where F: Fn(&ExpressionReference) -> Option<String> {
+  let mut result = String::with_capacity(text.len());
+  let mut position = 0;
+
+  for reference in expression_references(text) {
+    if let Some(name) = callback(&reference) {
+      result.push_str(&text[position..reference.span.start]);
+      result.push_str(&name);
+      position = reference.span.end;
+    }
+  }
+
+  result.push_str(&text[position..]);
+  result
+}
+
+/// Invokes the callback for every string value in the workflow that can contain a runtime
+/// expression, along with a JSON Pointer to its location relative to the workflow.
+pub fn for_each_expression<F>(workflow: &Workflow, callback: &mut F)
+  where F: FnMut(&str, &str) {
+  for (index, depends_on) in workflow.depends_on.iter().enumerate() {
+    callback(&format!("/dependsOn/{}", index), depends_on);
+  }
+  visit_parameters(&workflow.parameters, "/parameters", callback);
+  visit_success_actions(&workflow.success_actions, "/successActions", callback);
+  visit_failure_actions(&workflow.failure_actions, "/failureActions", callback);
+  for (key, value) in &workflow.outputs {
+    callback(&format!("/outputs/{}", key), value);
+  }
+  for (index, step) in workflow.steps.iter().enumerate() {
+    visit_step(step, &format!("/steps/{}", index), callback);
+  }
+}
+
+/// Invokes the callback for every string value in the components that can contain a runtime
+/// expression, along with a JSON Pointer to its location relative to the components.
+pub fn for_each_component_expression<F>(components: &Components, callback: &mut F)
+  where F: FnMut(&str, &str) {
+  for (key, parameter) in &components.parameters {
+    visit_parameter(parameter, &format!("/parameters/{}", key), callback);
+  }
+  for (key, action) in &components.success_actions {
+    visit_success_action(action, &format!("/successActions/{}", key), callback);
+  }
+  for (key, action) in &components.failure_actions {
+    visit_failure_action(action, &format!("/failureActions/{}", key), callback);
+  }
+}
+
+fn visit_step<F: FnMut(&str, &str)>(step: &Step, path: &str, callback: &mut F) {
+  if let Some(operation_id) = &step.operation_id {
+    callback(&format!("{}/operationId", path), operation_id);
+  }
+  if let Some(operation_path) = &step.operation_path {
+    callback(&format!("{}/operationPath", path), operation_path);
+  }
+  if let Some(workflow_id) = &step.workflow_id {
+    callback(&format!("{}/workflowId", path), workflow_id);
+  }
+  visit_parameters(&step.parameters, &format!("{}/parameters", path), callback);
+  if let Some(body) = &step.request_body {
+    visit_request_body(body, &format!("{}/requestBody", path), callback);
+  }
+  visit_criteria(&step.success_criteria, &format!("{}/successCriteria", path), callback);
+  visit_success_actions(&step.on_success, &format!("{}/onSuccess", path), callback);
+  visit_failure_actions(&step.on_failure, &format!("{}/onFailure", path), callback);
+  for (key, value) in &step.outputs {
+    callback(&format!("{}/outputs/{}", path, key), value);
+  }
+}
+
+fn visit_parameters<F: FnMut(&str, &str)>(
+  parameters: &[Either<ParameterObject, ReusableObject>],
+  path: &str,
+  callback: &mut F
+) {
+  for (index, parameter) in parameters.iter().enumerate() {
+    let path = format!("{}/{}", path, index);
+    match parameter {
+      Either::First(parameter) => visit_parameter(parameter, &path, callback),
+      Either::Second(reusable) => visit_reusable(reusable, &path, callback)
+    }
+  }
+}
+
+fn visit_parameter<F: FnMut(&str, &str)>(parameter: &ParameterObject, path: &str, callback: &mut F) {
+  if let Either::Second(expression) = &parameter.value {
+    callback(&format!("{}/value", path), expression);
+  }
+}
+
+fn visit_reusable<F: FnMut(&str, &str)>(reusable: &ReusableObject, path: &str, callback: &mut F) {
+  callback(&format!("{}/reference", path), &reusable.reference);
+  if let Some(value) = &reusable.value {
+    callback(&format!("{}/value", path), value);
+  }
+}
+
+fn visit_success_actions<F: FnMut(&str, &str)>(
+  actions: &[Either<SuccessObject, ReusableObject>],
+  path: &str,
+  callback: &mut F
+) {
+  for (index, action) in actions.iter().enumerate() {
+    let path = format!("{}/{}", path, index);
+    match action {
+      Either::First(action) => visit_success_action(action, &path, callback),
+      Either::Second(reusable) => visit_reusable(reusable, &path, callback)
+    }
+  }
+}
+
+fn visit_success_action<F: FnMut(&str, &str)>(action: &SuccessObject, path: &str, callback: &mut F) {
+  if let Some(workflow_id) = &action.workflow_id {
+    callback(&format!("{}/workflowId", path), workflow_id);
+  }
+  visit_criteria(&action.criteria, &format!("{}/criteria", path), callback);
+}
+
+fn visit_failure_actions<F: FnMut(&str, &str)>(
+  actions: &[Either<FailureObject, ReusableObject>],
+  path: &str,
+  callback: &mut F
+) {
+  for (index, action) in actions.iter().enumerate() {
+    let path = format!("{}/{}", path, index);
+    match action {
+      Either::First(action) => visit_failure_action(action, &path, callback),
+      Either::Second(reusable) => visit_reusable(reusable, &path, callback)
+    }
+  }
+}
+
+fn visit_failure_action<F: FnMut(&str, &str)>(action: &FailureObject, path: &str, callback: &mut F) {
+  if let Some(workflow_id) = &action.workflow_id {
+    callback(&format!("{}/workflowId", path), workflow_id);
+  }
+  visit_criteria(&action.criteria, &format!("{}/criteria", path), callback);
+}
+
+fn visit_criteria<F: FnMut(&str, &str)>(criteria: &[Criterion], path: &str, callback: &mut F) {
+  for (index, criterion) in criteria.iter().enumerate() {
+    if let Some(context) = &criterion.context {
+      callback(&format!("{}/{}/context", path, index), context);
+    }
+    callback(&format!("{}/{}/condition", path, index), &criterion.condition);
+  }
+}
+
+fn visit_request_body<F: FnMut(&str, &str)>(body: &RequestBody, path: &str, callback: &mut F) {
+  if let Some(payload) = &body.payload {
+    if let Some(string_payload) = payload.downcast_ref::<StringPayload>() {
+      callback(&format!("{}/payload", path), &string_payload.0);
+    } else if let Some(xml_payload) = xml_payload(payload.as_ref()) {
+      callback(&format!("{}/payload", path), &xml_payload);
+    } else if let Some(json_payload) = payload.downcast_ref::<JsonPayload>() {
+      visit_json(&json_payload.0, &format!("{}/payload", path), callback);
+    } else if let Some(graphql_payload) = payload.downcast_ref::<GraphQlPayload>() {
+      callback(&format!("{}/payload/query", path), &graphql_payload.query);
+      for (key, value) in &graphql_payload.variables {
+        visit_json(value, &format!("{}/payload/variables/{}", path, key.replace('~', "~0").replace('/', "~1")), callback);
+      }
+    } else if let Some(form_payload) = payload.downcast_ref::<FormPayload>() {
+      for (key, value) in &form_payload.0 {
+        if let AnyValue::String(s) = value {
+          callback(&format!("{}/payload/{}", path, key.replace('~', "~0").replace('/', "~1")), s);
+        }
+      }
+    } else {
+      visit_yaml_payload(payload.as_ref(), &format!("{}/payload", path), callback);
+    }
+  }
+  for (index, replacement) in body.replacements.iter().enumerate() {
+    if let Either::Second(expression) = &replacement.value {
+      callback(&format!("{}/replacements/{}/value", path, index), expression);
+    }
+  }
+}
+
+#[cfg(feature = "xml")]
+fn xml_payload(payload: &(dyn Payload + Send + Sync)) -> Option<String> {
+  payload.downcast_ref::<XmlPayload>().map(|xml| xml.as_string())
+}
+
+#[cfg(not(feature = "xml"))]
+fn xml_payload(_payload: &(dyn Payload + Send + Sync)) -> Option<String> {
+  None
+}
+
+#[cfg(feature = "yaml")]
+fn visit_yaml_payload<F: FnMut(&str, &str)>(payload: &(dyn Payload + Send + Sync), path: &str, callback: &mut F) {
+  if let Some(yaml_payload) = payload.downcast_ref::<YamlPayload>() {
+    visit_yaml(&yaml_payload.0, path, callback);
+  }
+}
+
+#[cfg(not(feature = "yaml"))]
+fn visit_yaml_payload<F: FnMut(&str, &str)>(_payload: &(dyn Payload + Send + Sync), _path: &str, _callback: &mut F) {}
+
+#[cfg(feature = "yaml")]
+fn visit_yaml<F: FnMut(&str, &str)>(yaml: &Yaml, path: &str, callback: &mut F) {
+  match yaml {
+    Yaml::String(s) => callback(path, s),
+    Yaml::Array(array) => for (index, item) in array.iter().enumerate() {
+      visit_yaml(item, &format!("{}/{}", path, index), callback);
+    },
+    Yaml::Hash(hash) => for (key, item) in hash {
+      let key = yaml_key(key);
+      visit_yaml(item, &format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1")), callback);
+    },
+    _ => {}
+  }
+}
+
+#[cfg(feature = "yaml")]
+fn yaml_key(key: &Yaml) -> String {
+  match key {
+    Yaml::String(s) | Yaml::Real(s) => s.clone(),
+    Yaml::Integer(i) => i.to_string(),
+    Yaml::Boolean(b) => b.to_string(),
+    _ => String::new()
+  }
+}
+
+fn visit_json<F: FnMut(&str, &str)>(json: &Value, path: &str, callback: &mut F) {
+  match json {
+    Value::String(s) => callback(path, s),
+    Value::Array(array) => for (index, item) in array.iter().enumerate() {
+      visit_json(item, &format!("{}/{}", path, index), callback);
+    },
+    Value::Object(map) => for (key, item) in map {
+      visit_json(item, &format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1")), callback);
+    },
+    _ => {}
+  }
+}
+
+/// Invokes the callback for every string value in the workflow that can contain a runtime
+/// expression, allowing the value to be modified.
+pub fn for_each_expression_mut<F>(workflow: &mut Workflow, callback: &mut F)
+  where F: FnMut(&mut String) {
+  for depends_on in &mut workflow.depends_on {
+    callback(depends_on);
+  }
+  visit_parameters_mut(&mut workflow.parameters, callback);
+  visit_success_actions_mut(&mut workflow.success_actions, callback);
+  visit_failure_actions_mut(&mut workflow.failure_actions, callback);
+  for value in workflow.outputs.values_mut() {
+    callback(value);
+  }
+  for step in &mut workflow.steps {
+    visit_step_mut(step, callback);
+  }
+}
+
+/// Invokes the callback for every string value in the components that can contain a runtime
+/// expression, allowing the value to be modified.
+pub fn for_each_component_expression_mut<F>(components: &mut Components, callback: &mut F)
+  where F: FnMut(&mut String) {
+  for parameter in components.parameters.values_mut() {
+    visit_parameter_mut(parameter, callback);
+  }
+  for action in components.success_actions.values_mut() {
+    visit_success_action_mut(action, callback);
+  }
+  for action in components.failure_actions.values_mut() {
+    visit_failure_action_mut(action, callback);
+  }
+}
+
+fn visit_step_mut<F: FnMut(&mut String)>(step: &mut Step, callback: &mut F) {
+  if let Some(operation_id) = &mut step.operation_id {
+    callback(operation_id);
+  }
+  if let Some(operation_path) = &mut step.operation_path {
+    callback(operation_path);
+  }
+  if let Some(workflow_id) = &mut step.workflow_id {
+    callback(workflow_id);
+  }
+  visit_parameters_mut(&mut step.parameters, callback);
+  if let Some(body) = &mut step.request_body {
+    visit_request_body_mut(body, callback);
+  }
+  visit_criteria_mut(&mut step.success_criteria, callback);
+  visit_success_actions_mut(&mut step.on_success, callback);
+  visit_failure_actions_mut(&mut step.on_failure, callback);
+  for value in step.outputs.values_mut() {
+    callback(value);
+  }
+}
+
+fn visit_parameters_mut<F: FnMut(&mut String)>(
+  parameters: &mut [Either<ParameterObject, ReusableObject>],
+  callback: &mut F
+) {
+  for parameter in parameters {
+    match parameter {
+      Either::First(parameter) => visit_parameter_mut(parameter, callback),
+      Either::Second(reusable) => visit_reusable_mut(reusable, callback)
+    }
+  }
+}
+
+fn visit_parameter_mut<F: FnMut(&mut String)>(parameter: &mut ParameterObject, callback: &mut F) {
+  if let Either::Second(expression) = &mut parameter.value {
+    callback(expression);
+  }
+}
+
+fn visit_reusable_mut<F: FnMut(&mut String)>(reusable: &mut ReusableObject, callback: &mut F) {
+  callback(&mut reusable.reference);
+  if let Some(value) = &mut reusable.value {
+    callback(value);
+  }
+}
+
+fn visit_success_actions_mut<F: FnMut(&mut String)>(
+  actions: &mut [Either<SuccessObject, ReusableObject>],
+  callback: &mut F
+) {
+  for action in actions {
+    match action {
+      Either::First(action) => visit_success_action_mut(action, callback),
+      Either::Second(reusable) => visit_reusable_mut(reusable, callback)
+    }
+  }
+}
+
+fn visit_success_action_mut<F: FnMut(&mut String)>(action: &mut SuccessObject, callback: &mut F) {
+  if let Some(workflow_id) = &mut action.workflow_id {
+    callback(workflow_id);
+  }
+  visit_criteria_mut(&mut action.criteria, callback);
+}
+
+fn visit_failure_actions_mut<F: FnMut(&mut String)>(
+  actions: &mut [Either<FailureObject, ReusableObject>],
+  callback: &mut F
+) {
+  for action in actions {
+    match action {
+      Either::First(action) => visit_failure_action_mut(action, callback),
+      Either::Second(reusable) => visit_reusable_mut(reusable, callback)
+    }
+  }
+}
+
+fn visit_failure_action_mut<F: FnMut(&mut String)>(action: &mut FailureObject, callback: &mut F) {
+  if let Some(workflow_id) = &mut action.workflow_id {
+    callback(workflow_id);
+  }
+  visit_criteria_mut(&mut action.criteria, callback);
+}
+
+fn visit_criteria_mut<F: FnMut(&mut String)>(criteria: &mut [Criterion], callback: &mut F) {
+  for criterion in criteria {
+    if let Some(context) = &mut criterion.context {
+      callback(context);
+    }
+    callback(&mut criterion.condition);
+  }
+}
+
+fn visit_request_body_mut<F: FnMut(&mut String)>(body: &mut RequestBody, callback: &mut F) {
+  if let Some(payload) = &body.payload {
+    let updated: Option<Arc<dyn Payload + Send + Sync>> = if let Some(string_payload) = payload.downcast_ref::<StringPayload>() {
+      let mut value = string_payload.0.clone();
+      callback(&mut value);
+      Some(Arc::new(StringPayload(value)))
+    } else if let Some(mut value) = xml_payload(payload.as_ref()) {
+      callback(&mut value);
+      Some(string_payload(&value, body.content_type.as_deref()))
+    } else if let Some(json_payload) = payload.downcast_ref::<JsonPayload>() {
+      let mut value = json_payload.0.clone();
+      visit_json_mut(&mut value, callback);
+      Some(Arc::new(JsonPayload(value)))
+    } else if let Some(graphql_payload) = payload.downcast_ref::<GraphQlPayload>() {
+      let mut value = graphql_payload.clone();
+      callback(&mut value.query);
+      for variable in value.variables.values_mut() {
+        visit_json_mut(variable, callback);
+      }
+      Some(Arc::new(value))
+    } else if let Some(form_payload) = payload.downcast_ref::<FormPayload>() {
+      let mut value = form_payload.clone();
+      for field in value.0.values_mut() {
+        if let AnyValue::String(s) = field {
+          callback(s);
+        }
+      }
+      Some(Arc::new(value))
+    } else {
+      visit_yaml_payload_mut(payload.as_ref(), callback)
+    };
+    if updated.is_some() {
+      body.payload = updated;
+    }
+  }
+  for replacement in &mut body.replacements {
+    if let Either::Second(expression) = &mut replacement.value {
+      callback(expression);
+    }
+  }
+}
+
+#[cfg(feature = "yaml")]
+fn visit_yaml_payload_mut<F: FnMut(&mut String)>(
+  payload: &(dyn Payload + Send + Sync),
+  callback: &mut F
+) -> Option<Arc<dyn Payload + Send + Sync>> {
+  payload.downcast_ref::<YamlPayload>().map(|yaml_payload| {
+    let mut value = yaml_payload.0.clone();
+    visit_yaml_mut(&mut value, callback);
+    let payload: Arc<dyn Payload + Send + Sync> = Arc::new(YamlPayload(value));
+    payload
+  })
+}
+
+#[cfg(not(feature = "yaml"))]
+fn visit_yaml_payload_mut<F: FnMut(&mut String)>(
+  _payload: &(dyn Payload + Send + Sync),
+  _callback: &mut F
+) -> Option<Arc<dyn Payload + Send + Sync>> {
+  None
+}
+
+#[cfg(feature = "yaml")]
+fn visit_yaml_mut<F: FnMut(&mut String)>(yaml: &mut Yaml, callback: &mut F) {
+  match yaml {
+    Yaml::String(s) => callback(s),
+    Yaml::Array(array) => for item in array {
+      visit_yaml_mut(item, callback);
+    },
+    Yaml::Hash(hash) => for item in hash.values_mut() {
+      visit_yaml_mut(item, callback);
+    },
+    _ => {}
+  }
+}
+
+fn visit_json_mut<F: FnMut(&mut String)>(json: &mut Value, callback: &mut F) {
+  match json {
+    Value::String(s) => callback(s),
+    Value::Array(array) => for item in array {
+      visit_json_mut(item, callback);
+    },
+    Value::Object(map) => for item in map.values_mut() {
+      visit_json_mut(item, callback);
+    },
+    _ => {}
+  }
+}
+
+/// Resolves runtime expressions to values (i.e. from the inputs and the outputs of previous steps)
+pub trait ExpressionResolver {
+  /// Resolves the runtime expression to a value. Returns an error if the expression can not be
+  /// resolved.
+  fn resolve(&self, expression: &str) -> anyhow::Result<AnyValue>;
+}
+
+impl <F> ExpressionResolver for F where F: Fn(&str) -> anyhow::Result<AnyValue> {
+  fn resolve(&self, expression: &str) -> anyhow::Result<AnyValue> {
+    self(expression)
+  }
+}
+
+/// Resolves expressions by looking up the value of the whole expression (i.e. `$inputs.pet_id`)
+impl ExpressionResolver for HashMap<String, AnyValue> {
+  fn resolve(&self, expression: &str) -> anyhow::Result<AnyValue> {
+    self.get(expression.trim())
+      .cloned()
+      .ok_or_else(|| anyhow!("Runtime expression '{}' could not be resolved", expression))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::btreemap;
+
+  use crate::either::Either;
+  use crate::expressions::{
+    expression_references,
+    for_each_expression,
+    for_each_expression_mut,
+    rewrite_expression_references,
+    ExpressionReference,
+    ReferenceKind
+  };
+  use crate::v1_0::{ParameterObject, Step, Workflow};
+
+  #[test]
+  fn expression_references_test() {
+    expect!(expression_references("")).to(be_equal_to(vec![]));
+    expect!(expression_references("$statusCode == 200")).to(be_equal_to(vec![]));
+    expect!(expression_references("$steps.loginStep.outputs.token")).to(be_equal_to(vec![
+      ExpressionReference { kind: ReferenceKind::Step, name: "loginStep".to_string(), span: 7..16 }
+    ]));
+    expect!(expression_references("{$sourceDescriptions.petStore.url}#/paths/~1pet/get")).to(be_equal_to(vec![
+      ExpressionReference { kind: ReferenceKind::SourceDescription, name: "petStore".to_string(), span: 21..29 }
+    ]));
+    expect!(expression_references("$components.parameters.page")).to(be_equal_to(vec![
+      ExpressionReference { kind: ReferenceKind::ComponentParameter, name: "page".to_string(), span: 23..27 }
+    ]));
+    expect!(expression_references("{\"a\": \"{$steps.a.outputs.b}\", \"b\": \"{$workflows.wf-1.outputs.c}\"}"))
+      .to(be_equal_to(vec![
+        ExpressionReference { kind: ReferenceKind::Step, name: "a".to_string(), span: 15..16 },
+        ExpressionReference { kind: ReferenceKind::Workflow, name: "wf-1".to_string(), span: 48..52 }
+      ]));
+  }
+
+  #[test]
+  fn rewrite_expression_references_test() {
+    let result = rewrite_expression_references("$steps.a.outputs.a == $steps.b.outputs.b", |reference| {
+      if reference.name == "a" { Some("c".to_string()) } else { None }
+    });
+    expect!(result).to(be_equal_to("$steps.c.outputs.a == $steps.b.outputs.b"));
+  }
+
+  #[test]
+  fn visits_all_the_expressions_in_a_workflow() {
+    let mut workflow = Workflow {
+      workflow_id: "wf".to_string(),
+      depends_on: vec!["other".to_string()],
+      steps: vec![
+        Step {
+          step_id: "one".to_string(),
+          operation_id: Some("$sourceDescriptions.api.getPets".to_string()),
+          parameters: vec![
+            Either::First(ParameterObject {
+              name: "id".to_string(),
+              value: Either::Second("$inputs.id".to_string()),
+              .. ParameterObject::default()
+            })
+          ],
+          .. Step::default()
+        }
+      ],
+      outputs: btreemap!{ "pets".to_string() => "$steps.one.outputs.pets".to_string() },
+      .. Workflow::default()
+    };
+
+    let mut visited = vec![];
+    for_each_expression(&workflow, &mut |path, value| visited.push((path.to_string(), value.to_string())));
+    expect!(visited).to(be_equal_to(vec![
+      ("/dependsOn/0".to_string(), "other".to_string()),
+      ("/outputs/pets".to_string(), "$steps.one.outputs.pets".to_string()),
+      ("/steps/0/operationId".to_string(), "$sourceDescriptions.api.getPets".to_string()),
+      ("/steps/0/parameters/0/value".to_string(), "$inputs.id".to_string())
+    ]));
+
+    for_each_expression_mut(&mut workflow, &mut |value| *value = value.to_uppercase());
+    expect!(workflow.depends_on).to(be_equal_to(vec!["OTHER".to_string()]));
+    expect!(workflow.steps[0].operation_id.clone()).to(be_some().value("$SOURCEDESCRIPTIONS.API.GETPETS"));
+  }
+}
@@ -0,0 +1,386 @@
+//! Registry of handlers for vendor extensions (like `x-timeout`). Handlers convert extension
+//! values into typed values, which are used to validate and normalise the values when a
+//! description is loaded or validated. JSON Schemas can also be registered to check the structure
+//! of extension values (requires the `json` feature).
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+
+use anyhow::anyhow;
+#[cfg(feature = "json")] use serde_json::Value;
+#[cfg(feature = "yaml")] use yaml_rust2::Yaml;
+
+use crate::extensions::{extension_name, for_each_extensions, for_each_extensions_mut, AnyValue};
+#[cfg(feature = "json")] use crate::json_schema::validate_json_schema;
+use crate::v1_0::ArazzoDescription;
+use crate::validation::ValidationIssue;
+
+/// Extension value that can be converted to and from a typed value
+pub trait TypedExtension: Sized {
+  /// Key of the extension, without the `x-` prefix
+  const KEY: &'static str;
+
+  /// Converts the extension value into the typed value
+  fn from_value(value: &AnyValue) -> anyhow::Result<Self>;
+
+  /// Converts the typed value back into an extension value
+  fn to_value(&self) -> AnyValue;
+
+  /// Validates the typed value. The default implementation accepts all values.
+  fn validate(&self) -> anyhow::Result<()> {
+    Ok(())
+  }
+}
+
+/// Handler for the values of an extension
+pub trait ExtensionHandler {
+  /// Key of the extension this handler is for, without the `x-` prefix
+  fn key(&self) -> &str;
+
+  /// Validates the extension value
+  fn validate(&self, value: &AnyValue) -> anyhow::Result<()>;
+
+  /// Returns the normalised form of the extension value. Returns an error if the value is not valid.
+  fn normalise(&self, value: &AnyValue) -> anyhow::Result<AnyValue>;
+}
+
+/// Handler for a [`TypedExtension`]. The value is normalised by converting it to the typed value
+/// and back again.
+struct TypedExtensionHandler<T>(PhantomData<fn() -> T>);
+
+impl <T: TypedExtension> ExtensionHandler for TypedExtensionHandler<T> {
+  fn key(&self) -> &str {
+    T::KEY
+  }
+
+  fn validate(&self, value: &AnyValue) -> anyhow::Result<()> {
+    T::from_value(value)?.validate()
+  }
+
+  fn normalise(&self, value: &AnyValue) -> anyhow::Result<AnyValue> {
+    let typed = T::from_value(value)?;
+    typed.validate()?;
+    Ok(typed.to_value())
+  }
+}
+
+/// Returns the typed value of an extension from the extension values of an object. Returns `None`
+/// if the object does not have the extension, or an error if the value could not be converted.
+pub fn typed_extension<T: TypedExtension>(extensions: &HashMap<String, AnyValue>) -> Option<anyhow::Result<T>> {
+  extensions.get(T::KEY).map(T::from_value)
+}
+
+/// Registry of extension handlers and schemas, keyed by the extension key
+#[derive(Default)]
+pub struct ExtensionRegistry {
+  handlers: HashMap<String, Box<dyn ExtensionHandler + Send + Sync>>,
+  #[cfg(feature = "json")]
+  schemas: HashMap<String, Value>
+}
+
+impl ExtensionRegistry {
+  /// Creates an empty registry
+  pub fn new() -> Self {
+    ExtensionRegistry::default()
+  }
+
+  /// Registers a typed extension. This will replace any existing handler for the extension key.
+  pub fn register<T: TypedExtension + 'static>(&mut self) {
+    self.register_handler(TypedExtensionHandler::<T>(PhantomData));
+  }
+
+  /// Registers a handler. This will replace any existing handler for the extension key.
+  pub fn register_handler<H: ExtensionHandler + Send + Sync + 'static>(&mut self, handler: H) {
+    let key = extension_name(handler.key()).to_string();
+    self.handlers.insert(key, Box::new(handler));
+  }
+
+  /// Registers a JSON Schema that all values of the extension must match. This will replace any
+  /// existing schema for the extension key. Schemas are checked before any handler for the key is
+  /// invoked.
+  #[cfg(feature = "json")]
+  pub fn register_schema(&mut self, key: &str, schema: Value) {
+    self.schemas.insert(extension_name(key).to_string(), schema);
+  }
+
+  /// Returns the JSON Schema registered for the extension key (with or without the `x-` prefix)
+  #[cfg(feature = "json")]
+  pub fn schema(&self, key: &str) -> Option<&Value> {
+    self.schemas.get(extension_name(key))
+  }
+
+  /// Returns the handler registered for the extension key (with or without the `x-` prefix)
+  pub fn handler(&self, key: &str) -> Option<&(dyn ExtensionHandler + Send + Sync)> {
+    self.handlers.get(extension_name(key)).map(|handler| handler.as_ref())
+  }
+
+  /// If a handler or schema has been registered for the extension key (with or without the `x-` prefix)
+  pub fn is_registered(&self, key: &str) -> bool {
+    #[cfg(feature = "json")]
+    if self.schema(key).is_some() {
+      return true;
+    }
+    self.handler(key).is_some()
+  }
+
+  /// Checks the extension value against the registered schema, returning the violations as
+  /// JSON Pointer and message pairs (relative to the extension value)
+  #[cfg(feature = "json")]
+  fn schema_violations(&self, key: &str, value: &AnyValue) -> Vec<(String, String)> {
+    match self.schemas.get(key) {
+      Some(schema) => validate_json_schema(schema, &value.to_json())
+        .into_iter()
+        .map(|violation| (violation.path, violation.message))
+        .collect(),
+      None => vec![]
+    }
+  }
+
+  #[cfg(not(feature = "json"))]
+  fn schema_violations(&self, _key: &str, _value: &AnyValue) -> Vec<(String, String)> {
+    vec![]
+  }
+
+  /// Validates all the extension values in the description that have a registered handler
+  pub fn validate(&self, description: &ArazzoDescription) -> Vec<ValidationIssue> {
+    let mut issues = vec![];
+
+    for_each_extensions(description, &mut |path, extensions| {
+      let mut keys = extensions.keys().collect::<Vec<_>>();
+      keys.sort();
+      for key in keys {
+        let violations = self.schema_violations(key, &extensions[key]);
+        if !violations.is_empty() {
+          for (pointer, message) in violations {
+            issues.push(ValidationIssue::new(format!("{}/x-{}{}", path, key, pointer),
+              format!("Extension value does not match the schema: {}", message)));
+          }
+        } else if let Some(handler) = self.handlers.get(key) &&
+          let Err(err) = handler.validate(&extensions[key]) {
+          issues.push(ValidationIssue::new(format!("{}/x-{}", path, key),
+            format!("Extension value is not valid: {}", err)));
+        }
+      }
+    });
+
+    issues
+  }
+
+  /// Normalises all the extension values in the description that have a registered handler.
+  /// Returns an error if any of the values are not valid, in which case the description is not
+  /// modified.
+  pub fn apply(&self, description: &mut ArazzoDescription) -> anyhow::Result<()> {
+    let mut updates = vec![];
+    let mut errors = vec![];
+
+    for_each_extensions(description, &mut |path, extensions| {
+      let mut keys = extensions.keys().collect::<Vec<_>>();
+      keys.sort();
+      for key in keys {
+        let violations = self.schema_violations(key, &extensions[key]);
+        if !violations.is_empty() {
+          for (pointer, message) in violations {
+            errors.push(format!("{}/x-{}{}: {}", path, key, pointer, message));
+          }
+        } else if let Some(handler) = self.handlers.get(key) {
+          match handler.normalise(&extensions[key]) {
+            Ok(value) => updates.push((path.to_string(), key.clone(), value)),
+            Err(err) => errors.push(format!("{}/x-{}: {}", path, key, err))
+          }
+        }
+      }
+    });
+
+    if !errors.is_empty() {
+      return Err(anyhow!("Extension values are not valid: {}", errors.join(", ")));
+    }
+
+    let mut updates = updates.into_iter().peekable();
+    for_each_extensions_mut(description, &mut |path, extensions| {
+      while let Some((_, key, value)) = updates.next_if(|(update_path, _, _)| update_path == path) {
+        extensions.insert(key, value);
+      }
+    });
+
+    Ok(())
+  }
+
+  /// Loads a description from a JSON document, and then normalises the extension values with
+  /// the registered handlers.
+  #[cfg(feature = "json")]
+  pub fn load_json(&self, json: &Value) -> anyhow::Result<ArazzoDescription> {
+    let mut description = ArazzoDescription::try_from(json)?;
+    self.apply(&mut description)?;
+    Ok(description)
+  }
+
+  /// Loads a description from a YAML document, and then normalises the extension values with
+  /// the registered handlers.
+  #[cfg(feature = "yaml")]
+  pub fn load_yaml(&self, yaml: &Yaml) -> anyhow::Result<ArazzoDescription> {
+    let mut description = ArazzoDescription::try_from(yaml)?;
+    self.apply(&mut description)?;
+    Ok(description)
+  }
+}
+
+impl Debug for ExtensionRegistry {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let mut keys = self.handlers.keys().collect::<Vec<_>>();
+    keys.sort();
+    let mut debug = f.debug_struct("ExtensionRegistry");
+    debug.field("handlers", &keys);
+    #[cfg(feature = "json")]
+    {
+      let mut schemas = self.schemas.keys().collect::<Vec<_>>();
+      schemas.sort();
+      debug.field("schemas", &schemas);
+    }
+    debug.finish()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+
+  use crate::extension_registry::{typed_extension, ExtensionRegistry, TypedExtension};
+  use crate::extensions::AnyValue;
+  use crate::v1_0::{ArazzoDescription, Info, Step, Workflow};
+  use crate::validation::ValidationIssue;
+
+  #[derive(Debug, PartialEq)]
+  struct Timeout(u64);
+
+  impl TypedExtension for Timeout {
+    const KEY: &'static str = "timeout";
+
+    fn from_value(value: &AnyValue) -> anyhow::Result<Self> {
+      match value {
+        AnyValue::String(s) => s.trim_end_matches("ms").parse().map(Timeout).map_err(|err| anyhow::anyhow!(err)),
+        _ => value.as_u64().map(Timeout).ok_or_else(|| anyhow::anyhow!("Timeout must be a number"))
+      }
+    }
+
+    fn to_value(&self) -> AnyValue {
+      AnyValue::UInteger(self.0)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+      if self.0 == 0 {
+        Err(anyhow::anyhow!("Timeout must be greater than zero"))
+      } else {
+        Ok(())
+      }
+    }
+  }
+
+  fn description(timeout: AnyValue) -> ArazzoDescription {
+    ArazzoDescription {
+      info: Info {
+        extensions: hashmap!{ "timeout".to_string() => AnyValue::Integer(100) },
+        .. Info::default()
+      },
+      workflows: vec![
+        Workflow {
+          workflow_id: "test".to_string(),
+          steps: vec![
+            Step {
+              step_id: "one".to_string(),
+              extensions: hashmap!{
+                "timeout".to_string() => timeout,
+                "other".to_string() => AnyValue::Boolean(true)
+              },
+              .. Step::default()
+            }
+          ],
+          .. Workflow::default()
+        }
+      ],
+      .. ArazzoDescription::default()
+    }
+  }
+
+  #[test]
+  fn registry_normalises_extension_values() {
+    let mut registry = ExtensionRegistry::new();
+    registry.register::<Timeout>();
+    expect!(registry.is_registered("x-timeout")).to(be_true());
+    expect!(registry.is_registered("other")).to(be_false());
+
+    let mut description = description(AnyValue::String("250ms".to_string()));
+    registry.apply(&mut description).unwrap();
+
+    let step = &description.workflows[0].steps[0];
+    expect!(step.extensions["timeout"].clone()).to(be_equal_to(AnyValue::UInteger(250)));
+    expect!(step.extensions["other"].clone()).to(be_equal_to(AnyValue::Boolean(true)));
+    expect!(description.info.extensions["timeout"].clone()).to(be_equal_to(AnyValue::UInteger(100)));
+    expect!(typed_extension::<Timeout>(&step.extensions).unwrap().unwrap()).to(be_equal_to(Timeout(250)));
+    expect!(typed_extension::<Timeout>(&description.extensions).is_none()).to(be_true());
+  }
+
+  #[test]
+  fn registry_validates_extension_values() {
+    let mut registry = ExtensionRegistry::new();
+    registry.register::<Timeout>();
+
+    let mut description = description(AnyValue::Integer(0));
+    expect!(registry.validate(&description)).to(be_equal_to(vec![
+      ValidationIssue::new("/workflows/0/steps/0/x-timeout", "Extension value is not valid: Timeout must be greater than zero")
+    ]));
+    expect!(registry.apply(&mut description).unwrap_err().to_string()).to(be_equal_to(
+      "Extension values are not valid: /workflows/0/steps/0/x-timeout: Timeout must be greater than zero"));
+    expect!(description.info.extensions["timeout"].clone()).to(be_equal_to(AnyValue::Integer(100)));
+
+    expect!(ExtensionRegistry::new().validate(&description)).to(be_equal_to(vec![]));
+  }
+
+  #[test]
+  #[cfg(feature = "json")]
+  fn registry_checks_extension_values_against_the_schemas() {
+    let mut registry = ExtensionRegistry::new();
+    registry.register_schema("x-owner", serde_json::json!({
+      "type": "object",
+      "required": ["team"],
+      "properties": { "team": { "type": "string" }, "slack": { "type": "string" } }
+    }));
+    expect!(registry.is_registered("owner")).to(be_true());
+
+    let mut description = description(AnyValue::Integer(10));
+    description.info.extensions.insert("owner".to_string(), AnyValue::from(hashmap!{
+      "team".to_string() => AnyValue::from("a")
+    }));
+    description.workflows[0].extensions.insert("owner".to_string(), AnyValue::from(hashmap!{
+      "slack".to_string() => AnyValue::from(1)
+    }));
+    expect!(registry.validate(&description)).to(be_equal_to(vec![
+      ValidationIssue::new("/workflows/0/x-owner",
+        "Extension value does not match the schema: Required property 'team' is missing"),
+      ValidationIssue::new("/workflows/0/x-owner/slack",
+        "Extension value does not match the schema: Expected a value of type string, got Number")
+    ]));
+    expect!(registry.apply(&mut description).unwrap_err().to_string()).to(be_equal_to(
+      "Extension values are not valid: /workflows/0/x-owner: Required property 'team' is missing, \
+      /workflows/0/x-owner/slack: Expected a value of type string, got Number"));
+  }
+
+  #[test]
+  #[cfg(feature = "json")]
+  fn load_json_applies_the_registered_handlers() {
+    let mut registry = ExtensionRegistry::new();
+    registry.register::<Timeout>();
+
+    let json = serde_json::json!({
+      "arazzo": "1.0.1",
+      "info": { "title": "test", "version": "1.0.0" },
+      "sourceDescriptions": [ { "name": "api", "url": "openapi.yaml" } ],
+      "workflows": [
+        { "workflowId": "test", "steps": [ { "stepId": "one", "operationId": "op", "x-timeout": "10ms" } ] }
+      ]
+    });
+    let description = registry.load_json(&json).unwrap();
+    expect!(description.workflows[0].steps[0].extensions["timeout"].clone()).to(be_equal_to(AnyValue::UInteger(10)));
+  }
+}
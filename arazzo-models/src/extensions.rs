@@ -1,13 +1,35 @@
 //! Structs and Traits for dealing with extensions (<https://spec.openapis.org/arazzo/v1.0.1.html#specification-extensions>).
 
 use std::collections::HashMap;
+use std::ops::Index;
+
+use bytes::Bytes;
+use indexmap::IndexMap;
 
 #[cfg(feature = "yaml")] use anyhow::anyhow;
-#[cfg(feature = "yaml")] use maplit::hashmap;
-#[cfg(feature = "json")] use serde_json::{Map, Value};
+#[cfg(feature = "json")] use serde_json::{Map, Number, Value};
 #[cfg(feature = "yaml")] use yaml_rust2::Yaml;
 #[cfg(feature = "yaml")] use yaml_rust2::yaml::Hash;
 
+use crate::base64;
+use crate::either::Either;
+#[cfg(any(feature = "json", feature = "yaml"))] use crate::fields::ObjectFields;
+use crate::v1_0::{
+  ArazzoDescription,
+  Components,
+  Criterion,
+  CriterionExpressionType,
+  FailureObject,
+  Info,
+  ParameterObject,
+  PayloadReplacement,
+  RequestBody,
+  ReusableObject,
+  SourceDescription,
+  Step,
+  SuccessObject,
+  Workflow
+};
 #[cfg(feature = "yaml")] use crate::yaml::yaml_type_name;
 
 /// Enum to store a value of additional data
@@ -29,14 +51,186 @@ pub enum AnyValue {
   /// 64-bit floating point number
   Float(f64),
 
+  /// Number that can not be stored as an Integer, UInteger or Float without losing precision (i.e.
+  /// integers larger than 64 bits or decimals with more than 15 significant digits), stored as it
+  /// was written in the source document. The loaders only create these values when the
+  /// `arbitrary_precision` feature is enabled.
+  BigNumber(String),
+
   /// String
   String(String),
 
   /// An array of values
   Array(Vec<AnyValue>),
 
-  /// An Object, which is stored as a Map with String keys
-  Object(HashMap<String, AnyValue>)
+  /// Binary data. This is written as a Base64 encoded string, and loaded from YAML `!!binary` values.
+  Binary(Bytes),
+
+  /// An Object, which is stored as a Map with String keys. The order the keys were added (or
+  /// authored in the document) is preserved.
+  Object(IndexMap<String, AnyValue>)
+}
+
+static NULL: AnyValue = AnyValue::Null;
+
+impl AnyValue {
+  /// If the value is Null
+  pub fn is_null(&self) -> bool {
+    matches!(self, AnyValue::Null)
+  }
+
+  /// Returns the string value, if the value is a String
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      AnyValue::String(s) => Some(s.as_str()),
+      _ => None
+    }
+  }
+
+  /// Returns the value as a signed integer, if the value is an integer that fits
+  pub fn as_i64(&self) -> Option<i64> {
+    match self {
+      AnyValue::Integer(i) => Some(*i),
+      AnyValue::UInteger(u) => i64::try_from(*u).ok(),
+      _ => None
+    }
+  }
+
+  /// Returns the value as an unsigned integer, if the value is a non-negative integer
+  pub fn as_u64(&self) -> Option<u64> {
+    match self {
+      AnyValue::Integer(i) => u64::try_from(*i).ok(),
+      AnyValue::UInteger(u) => Some(*u),
+      _ => None
+    }
+  }
+
+  /// Returns the value as a floating point number, if the value is a number
+  pub fn as_f64(&self) -> Option<f64> {
+    match self {
+      AnyValue::Integer(i) => Some(*i as f64),
+      AnyValue::UInteger(u) => Some(*u as f64),
+      AnyValue::BigNumber(n) => n.parse().ok(),
+      AnyValue::Float(f) => Some(*f),
+      _ => None
+    }
+  }
+
+  /// Returns the boolean value, if the value is a Boolean
+  pub fn as_bool(&self) -> Option<bool> {
+    match self {
+      AnyValue::Boolean(b) => Some(*b),
+      _ => None
+    }
+  }
+
+  /// Returns the binary data, if the value is Binary
+  pub fn as_bytes(&self) -> Option<&Bytes> {
+    match self {
+      AnyValue::Binary(b) => Some(b),
+      _ => None
+    }
+  }
+
+  /// Returns the value as text, if the value is not an Array or Object. Null values are returned
+  /// as an empty string, and binary data is Base64 encoded.
+  pub fn to_text(&self) -> Option<String> {
+    match self {
+      AnyValue::Null => Some(String::new()),
+      AnyValue::String(s) | AnyValue::BigNumber(s) => Some(s.clone()),
+      AnyValue::Boolean(b) => Some(b.to_string()),
+      AnyValue::Integer(i) => Some(i.to_string()),
+      AnyValue::UInteger(u) => Some(u.to_string()),
+      AnyValue::Float(f) => Some(f.to_string()),
+      AnyValue::Binary(bytes) => Some(base64::encode(bytes)),
+      AnyValue::Array(_) | AnyValue::Object(_) => None
+    }
+  }
+
+  /// Returns the values, if the value is an Array
+  pub fn as_array(&self) -> Option<&Vec<AnyValue>> {
+    match self {
+      AnyValue::Array(a) => Some(a),
+      _ => None
+    }
+  }
+
+  /// Returns the entries, if the value is an Object
+  pub fn as_object(&self) -> Option<&IndexMap<String, AnyValue>> {
+    match self {
+      AnyValue::Object(o) => Some(o),
+      _ => None
+    }
+  }
+
+  /// Returns the value of the field, if this value is an Object with the field
+  pub fn get(&self, key: &str) -> Option<&AnyValue> {
+    self.as_object().and_then(|o| o.get(key))
+  }
+
+  /// Looks up a nested value using a JSON Pointer (i.e. `/a/b/2/c`)
+  pub fn pointer(&self, pointer: &str) -> Option<&AnyValue> {
+    if pointer.is_empty() {
+      return Some(self);
+    }
+
+    let tokens = pointer.strip_prefix('/')?;
+    tokens.split('/')
+      .map(|token| token.replace("~1", "/").replace("~0", "~"))
+      .try_fold(self, |value, token| value.lookup_token(&token))
+  }
+
+  /// Looks up a nested value using a path with dot-separated fields and indices in square
+  /// brackets (i.e. `a.b[2].c`)
+  pub fn get_path(&self, path: &str) -> Option<&AnyValue> {
+    let mut value = self;
+
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+      let (field, mut indices) = match segment.find('[') {
+        Some(index) => segment.split_at(index),
+        None => (segment, "")
+      };
+      if !field.is_empty() {
+        value = value.get(field)?;
+      }
+      while let Some(rest) = indices.strip_prefix('[') {
+        let end = rest.find(']')?;
+        value = value.lookup_token(&rest[..end])?;
+        indices = &rest[end + 1..];
+      }
+      if !indices.is_empty() {
+        return None;
+      }
+    }
+
+    Some(value)
+  }
+
+  fn lookup_token(&self, token: &str) -> Option<&AnyValue> {
+    match self {
+      AnyValue::Object(o) => o.get(token),
+      AnyValue::Array(a) => token.parse::<usize>().ok().and_then(|i| a.get(i)),
+      _ => None
+    }
+  }
+}
+
+impl Index<&str> for AnyValue {
+  type Output = AnyValue;
+
+  /// Returns the value of the field, or Null if this is not an Object or does not have the field
+  fn index(&self, key: &str) -> &Self::Output {
+    self.get(key).unwrap_or(&NULL)
+  }
+}
+
+impl Index<usize> for AnyValue {
+  type Output = AnyValue;
+
+  /// Returns the item from the Array, or Null if this is not an Array or the index is out of bounds
+  fn index(&self, index: usize) -> &Self::Output {
+    self.as_array().and_then(|a| a.get(index)).unwrap_or(&NULL)
+  }
 }
 
 impl From<&str> for AnyValue {
@@ -51,6 +245,18 @@ impl From<&String> for AnyValue {
   }
 }
 
+impl From<String> for AnyValue {
+  fn from(value: String) -> Self {
+    AnyValue::String(value)
+  }
+}
+
+impl From<i32> for AnyValue {
+  fn from(value: i32) -> Self {
+    AnyValue::Integer(value as i64)
+  }
+}
+
 impl From<i64> for AnyValue {
   fn from(value: i64) -> Self {
     AnyValue::Integer(value)
@@ -63,6 +269,12 @@ impl From<u64> for AnyValue {
   }
 }
 
+impl From<u32> for AnyValue {
+  fn from(value: u32) -> Self {
+    AnyValue::UInteger(value as u64)
+  }
+}
+
 impl From<f64> for AnyValue {
   fn from(value: f64) -> Self {
     AnyValue::Float(value)
@@ -75,15 +287,46 @@ impl From<bool> for AnyValue {
   }
 }
 
+impl From<Bytes> for AnyValue {
+  fn from(value: Bytes) -> Self {
+    AnyValue::Binary(value)
+  }
+}
+
+impl <T: Into<AnyValue>> From<Vec<T>> for AnyValue {
+  fn from(value: Vec<T>) -> Self {
+    AnyValue::Array(value.into_iter().map(|v| v.into()).collect())
+  }
+}
+
+impl <T: Into<AnyValue>> From<IndexMap<String, T>> for AnyValue {
+  fn from(value: IndexMap<String, T>) -> Self {
+    AnyValue::Object(value.into_iter().map(|(k, v)| (k, v.into())).collect())
+  }
+}
+
+impl <T: Into<AnyValue>> From<HashMap<String, T>> for AnyValue {
+  /// As a HashMap has no order, the keys will be sorted
+  fn from(value: HashMap<String, T>) -> Self {
+    let mut entries = value.into_iter().collect::<Vec<_>>();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    AnyValue::Object(entries.into_iter().map(|(k, v)| (k, v.into())).collect())
+  }
+}
+
+impl <T: Into<AnyValue>> From<Option<T>> for AnyValue {
+  fn from(value: Option<T>) -> Self {
+    value.map(|v| v.into()).unwrap_or_default()
+  }
+}
+
 #[cfg(feature = "yaml")]
 impl TryFrom<&Yaml> for AnyValue {
   type Error = anyhow::Error;
 
   fn try_from(value: &Yaml) -> Result<Self, Self::Error> {
     match value {
-      Yaml::Real(f) => f.parse::<f64>()
-        .map(|f| AnyValue::Float(f))
-        .map_err(|err| anyhow!(err)),
+      Yaml::Real(f) => yaml_parse_number(f),
       Yaml::Integer(i) => Ok(AnyValue::Integer(*i)),
       Yaml::String(s) => Ok(AnyValue::String(s.clone())),
       Yaml::Boolean(b) => Ok(AnyValue::Boolean(*b)),
@@ -96,8 +339,13 @@ impl TryFrom<&Yaml> for AnyValue {
 
         Ok(AnyValue::Array(array))
       }
+      Yaml::Hash(h) if h.len() == 1 && h.contains_key(&Yaml::String(YAML_BINARY_KEY.to_string())) => {
+        let data = h[&Yaml::String(YAML_BINARY_KEY.to_string())].as_str()
+          .ok_or_else(|| anyhow!("Binary value must be a Base64 encoded string"))?;
+        Ok(AnyValue::Binary(Bytes::from(base64::decode(data)?)))
+      }
       Yaml::Hash(h) => {
-        let mut map = hashmap!{};
+        let mut map = IndexMap::new();
 
         for (k, value) in h {
           let key = k.as_str()
@@ -115,18 +363,120 @@ impl TryFrom<&Yaml> for AnyValue {
   }
 }
 
-/// Extracts all the extension values from the Hash, stripping the `x-` suffix off.
+/// Key used to mark a YAML `!!binary` value when loading YAML documents with
+/// [`yaml_load_documents`](crate::yaml::yaml_load_documents), as the yaml-rust2 models can not
+/// store tags. The value is loaded as a Hash with this as the only key.
 #[cfg(feature = "yaml")]
-pub fn yaml_extract_extensions(hash: &Hash) -> anyhow::Result<HashMap<String, AnyValue>> {
-  let mut extensions = hashmap!{};
+pub const YAML_BINARY_KEY: &str = "!!binary";
 
-  for (k, v) in hash {
-    if let Some(key) = k.as_str() && let Some(suffix) = key.strip_prefix("x-") {
-      extensions.insert(suffix.to_string(), v.try_into()?);
+#[cfg(feature = "yaml")]
+fn yaml_parse_number(value: &str) -> anyhow::Result<AnyValue> {
+  match value {
+    ".nan" | ".NaN" | ".NAN" => Ok(AnyValue::Float(f64::NAN)),
+    ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" => Ok(AnyValue::Float(f64::INFINITY)),
+    "-.inf" | "-.Inf" | "-.INF" => Ok(AnyValue::Float(f64::NEG_INFINITY)),
+    _ => if let Ok(u) = value.parse::<u64>() {
+      Ok(AnyValue::UInteger(u))
+    } else {
+      #[cfg(feature = "arbitrary_precision")]
+      if is_big_number(value) {
+        return Ok(AnyValue::BigNumber(value.to_string()));
+      }
+      value.parse::<f64>().map(AnyValue::Float).map_err(|err| anyhow!(err))
+    }
+  }
+}
+
+/// If the number (as written in a document) can not be stored in 64 bits without losing precision
+#[cfg(all(feature = "arbitrary_precision", any(feature = "json", feature = "yaml")))]
+fn is_big_number(value: &str) -> bool {
+  let mantissa = value.split(['e', 'E']).next().unwrap_or_default();
+  if mantissa == value && !mantissa.contains('.') {
+    // Integers are only passed in here if they do not fit into an i64 or u64
+    return true;
+  }
+  // The number fits if the shortest representation of the f64 has the same digits
+  match value.parse::<f64>() {
+    Ok(f) if f.is_finite() => {
+      let shortest = format!("{:e}", f);
+      let shortest_mantissa = shortest.split('e').next().unwrap_or_default();
+      significant_digits(mantissa) != significant_digits(shortest_mantissa)
+    }
+    _ => true
+  }
+}
+
+/// Digits of the mantissa of a number, without any leading or trailing zeros
+#[cfg(all(feature = "arbitrary_precision", any(feature = "json", feature = "yaml")))]
+fn significant_digits(mantissa: &str) -> String {
+  let digits = mantissa.chars().filter(|ch| ch.is_ascii_digit()).collect::<String>();
+  digits.trim_start_matches('0').trim_end_matches('0').to_string()
+}
+
+#[cfg(feature = "yaml")]
+impl TryFrom<Yaml> for AnyValue {
+  type Error = anyhow::Error;
+
+  fn try_from(value: Yaml) -> Result<Self, Self::Error> {
+    AnyValue::try_from(&value)
+  }
+}
+
+#[cfg(feature = "yaml")]
+impl From<&AnyValue> for Yaml {
+  fn from(value: &AnyValue) -> Self {
+    match value {
+      AnyValue::Null => Yaml::Null,
+      AnyValue::Boolean(b) => Yaml::Boolean(*b),
+      AnyValue::Integer(i) => Yaml::Integer(*i),
+      AnyValue::UInteger(u) => match i64::try_from(*u) {
+        Ok(i) => Yaml::Integer(i),
+        Err(_) => Yaml::Real(u.to_string())
+      },
+      AnyValue::BigNumber(n) => Yaml::Real(n.clone()),
+      AnyValue::Float(f) => if f.is_nan() {
+        Yaml::Real(".nan".to_string())
+      } else if f.is_infinite() {
+        Yaml::Real(if *f > 0.0 { ".inf" } else { "-.inf" }.to_string())
+      } else {
+        Yaml::Real(format!("{:?}", f))
+      },
+      AnyValue::String(s) => Yaml::String(s.clone()),
+      AnyValue::Binary(b) => Yaml::String(base64::encode(b)),
+      AnyValue::Array(a) => Yaml::Array(a.iter().map(Yaml::from).collect()),
+      AnyValue::Object(o) => {
+        let mut hash = Hash::new();
+        for (k, v) in o {
+          hash.insert(Yaml::String(k.clone()), Yaml::from(v));
+        }
+        Yaml::Hash(hash)
+      }
     }
   }
+}
+
+#[cfg(feature = "yaml")]
+impl From<AnyValue> for Yaml {
+  fn from(value: AnyValue) -> Self {
+    Yaml::from(&value)
+  }
+}
+
+#[cfg(feature = "yaml")]
+impl AnyValue {
+  /// Converts this value to a YAML value. Unsigned integers that are too large for a YAML integer
+  /// are written as real numbers.
+  pub fn to_yaml(&self) -> Yaml {
+    self.into()
+  }
+}
 
-  Ok(extensions)
+/// Extracts all the extension values from the Hash, stripping the `x-` prefix off. Extension keys
+/// are always stored without the prefix, and the serializers add it back.
+#[cfg(feature = "yaml")]
+pub fn yaml_extract_extensions(hash: &Hash) -> anyhow::Result<HashMap<String, AnyValue>> {
+  ObjectFields::<Yaml, 0>::classify(&[], hash.iter().filter_map(|(k, v)| k.as_str().map(|k| (k, v))))
+    .extensions()
 }
 
 #[cfg(feature = "json")]
@@ -143,6 +493,10 @@ impl TryFrom<&Value> for AnyValue {
         } else if let Some(int) = n.as_i64() {
           Ok(AnyValue::Integer(int))
         } else {
+          #[cfg(feature = "arbitrary_precision")]
+          if is_big_number(n.as_str()) {
+            return Ok(AnyValue::BigNumber(n.to_string()));
+          }
           Ok(AnyValue::Float(n.as_f64().unwrap_or_default()))
         }
       }
@@ -157,7 +511,7 @@ impl TryFrom<&Value> for AnyValue {
         Ok(AnyValue::Array(array))
       }
       Value::Object(o) => {
-        let mut map = hashmap!{};
+        let mut map = IndexMap::new();
 
         for (k, value) in o {
           map.insert(k.clone(), value.try_into()?);
@@ -169,28 +523,501 @@ impl TryFrom<&Value> for AnyValue {
   }
 }
 
-/// Extracts all the extension values from the Object, stripping the `x-` suffix off.
+#[cfg(feature = "json")]
+impl TryFrom<Value> for AnyValue {
+  type Error = anyhow::Error;
+
+  fn try_from(value: Value) -> Result<Self, Self::Error> {
+    AnyValue::try_from(&value)
+  }
+}
+
+#[cfg(feature = "json")]
+impl From<&AnyValue> for Value {
+  fn from(value: &AnyValue) -> Self {
+    match value {
+      AnyValue::Null => Value::Null,
+      AnyValue::Boolean(b) => Value::Bool(*b),
+      AnyValue::Integer(i) => Value::from(*i),
+      AnyValue::UInteger(u) => Value::from(*u),
+      AnyValue::Float(f) => Value::from(*f),
+      AnyValue::BigNumber(n) => n.parse::<Number>().map(Value::Number).unwrap_or(Value::Null),
+      AnyValue::String(s) => Value::String(s.clone()),
+      AnyValue::Binary(b) => Value::String(base64::encode(b)),
+      AnyValue::Array(a) => Value::Array(a.iter().map(Value::from).collect()),
+      AnyValue::Object(o) => Value::Object(o.iter()
+        .map(|(k, v)| (k.clone(), Value::from(v)))
+        .collect())
+    }
+  }
+}
+
+#[cfg(feature = "json")]
+impl From<AnyValue> for Value {
+  fn from(value: AnyValue) -> Self {
+    Value::from(&value)
+  }
+}
+
+#[cfg(feature = "json")]
+impl AnyValue {
+  /// Converts this value to a JSON value. Note that JSON can not represent NaN or infinite
+  /// floating point values, and these will be converted to a JSON null.
+  pub fn to_json(&self) -> Value {
+    self.into()
+  }
+}
+
+/// Extracts all the extension values from the Object, stripping the `x-` prefix off. Extension keys
+/// are always stored without the prefix, and the serializers add it back.
 #[cfg(feature = "json")]
 pub fn json_extract_extensions(map: &Map<String, Value>) -> anyhow::Result<HashMap<String, AnyValue>> {
-  let mut extensions = hashmap!{};
+  ObjectFields::<Value, 0>::classify(&[], map.iter().map(|(k, v)| (k.as_str(), v)))
+    .extensions()
+}
+
+/// Returns the extension key without the `x-` prefix, which is how extension values are stored
+pub fn extension_name(key: &str) -> &str {
+  key.strip_prefix("x-").unwrap_or(key)
+}
+
+/// Trait for models that can have extension values. Keys can be given with or without the `x-`
+/// prefix, and are always stored without it.
+pub trait Extensible {
+  /// Extension values, keyed without the `x-` prefix
+  fn extensions(&self) -> &HashMap<String, AnyValue>;
+
+  /// Mutable reference to the extension values, keyed without the `x-` prefix
+  fn extensions_mut(&mut self) -> &mut HashMap<String, AnyValue>;
+
+  /// Returns the extension value for the key
+  fn extension(&self, key: &str) -> Option<&AnyValue> {
+    self.extensions().get(extension_name(key))
+  }
+
+  /// Sets the extension value for the key, returning any previous value
+  fn set_extension<K: AsRef<str>, V: Into<AnyValue>>(&mut self, key: K, value: V) -> Option<AnyValue> {
+    self.extensions_mut().insert(extension_name(key.as_ref()).to_string(), value.into())
+  }
+
+  /// Removes the extension value for the key, returning it if it was set
+  fn remove_extension(&mut self, key: &str) -> Option<AnyValue> {
+    self.extensions_mut().remove(extension_name(key))
+  }
+
+  /// Builder style function that sets the extension value and returns the model
+  fn with_extension<K: AsRef<str>, V: Into<AnyValue>>(mut self, key: K, value: V) -> Self where Self: Sized {
+    self.set_extension(key, value);
+    self
+  }
+}
+
+macro_rules! impl_extensible {
+  ($($model:ty),*) => {
+    $(
+      impl Extensible for $model {
+        fn extensions(&self) -> &HashMap<String, AnyValue> {
+          &self.extensions
+        }
+
+        fn extensions_mut(&mut self) -> &mut HashMap<String, AnyValue> {
+          &mut self.extensions
+        }
+      }
+    )*
+  };
+}
 
-  for (k, v) in map {
-    if let Some(suffix) = k.strip_prefix("x-") {
-      extensions.insert(suffix.to_string(), v.try_into()?);
+impl_extensible!(
+  ArazzoDescription,
+  Info,
+  SourceDescription,
+  Workflow,
+  Step,
+  ParameterObject,
+  SuccessObject,
+  FailureObject,
+  Components,
+  Criterion,
+  CriterionExpressionType,
+  RequestBody,
+  PayloadReplacement
+);
+
+/// Selects which extension values to keep when filtering the extensions of a description. Keys
+/// can be given with or without the `x-` prefix, and can end with a `*` to match all the keys
+/// starting with the given text (i.e. `pact-*`).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ExtensionFilter {
+  /// Remove all extension values
+  #[default]
+  RemoveAll,
+  /// Only keep the extension values with a key in the list
+  Allow(Vec<String>),
+  /// Remove the extension values with a key in the list
+  Deny(Vec<String>)
+}
+
+impl ExtensionFilter {
+  /// If the extension value with the given key should be kept
+  pub fn keeps(&self, key: &str) -> bool {
+    let key = extension_name(key);
+    let matches = |keys: &Vec<String>| keys.iter().any(|pattern| {
+      let pattern = extension_name(pattern);
+      match pattern.strip_suffix('*') {
+        Some(prefix) => key.starts_with(prefix),
+        None => key == pattern
+      }
+    });
+    match self {
+      ExtensionFilter::RemoveAll => false,
+      ExtensionFilter::Allow(keys) => matches(keys),
+      ExtensionFilter::Deny(keys) => !matches(keys)
+    }
+  }
+}
+
+impl ArazzoDescription {
+  /// Removes the extension values from all the objects in the description that are not kept by
+  /// the filter. Returns the number of values removed.
+  pub fn filter_extensions(&mut self, filter: &ExtensionFilter) -> usize {
+    let mut removed = 0;
+    for_each_extensions_mut(self, &mut |_, extensions| {
+      let count = extensions.len();
+      extensions.retain(|key, _| filter.keeps(key));
+      removed += count - extensions.len();
+    });
+    removed
+  }
+
+  /// Returns a copy of the description with the extension values removed that are not kept by
+  /// the filter (i.e. to publish a document without the internal extensions).
+  pub fn without_extensions(&self, filter: &ExtensionFilter) -> ArazzoDescription {
+    let mut description = self.clone();
+    description.filter_extensions(filter);
+    description
+  }
+}
+
+/// Invokes the callback with the extension values of every object in the description that can
+/// have extensions, along with the JSON Pointer to the object. Components are visited in the
+/// order of their names.
+pub fn for_each_extensions<F>(description: &ArazzoDescription, callback: &mut F)
+  where F: FnMut(&str, &HashMap<String, AnyValue>) {
+  callback("", &description.extensions);
+  callback("/info", &description.info.extensions);
+  for (index, source) in description.source_descriptions.iter().enumerate() {
+    callback(&format!("/sourceDescriptions/{}", index), &source.extensions);
+  }
+  for (index, workflow) in description.workflows.iter().enumerate() {
+    let path = format!("/workflows/{}", index);
+    callback(&path, &workflow.extensions);
+    visit_parameters(&workflow.parameters, &format!("{}/parameters", path), callback);
+    visit_success_actions(&workflow.success_actions, &format!("{}/successActions", path), callback);
+    visit_failure_actions(&workflow.failure_actions, &format!("{}/failureActions", path), callback);
+    for (index, step) in workflow.steps.iter().enumerate() {
+      let path = format!("{}/steps/{}", path, index);
+      callback(&path, &step.extensions);
+      visit_parameters(&step.parameters, &format!("{}/parameters", path), callback);
+      if let Some(body) = &step.request_body {
+        callback(&format!("{}/requestBody", path), &body.extensions);
+        for (index, replacement) in body.replacements.iter().enumerate() {
+          callback(&format!("{}/requestBody/replacements/{}", path, index), &replacement.extensions);
+        }
+      }
+      visit_criteria(&step.success_criteria, &format!("{}/successCriteria", path), callback);
+      visit_success_actions(&step.on_success, &format!("{}/onSuccess", path), callback);
+      visit_failure_actions(&step.on_failure, &format!("{}/onFailure", path), callback);
     }
   }
 
-  Ok(extensions)
+  let components = &description.components;
+  callback("/components", &components.extensions);
+  for name in sorted_keys(&components.parameters) {
+    callback(&format!("/components/parameters/{}", name), &components.parameters[name].extensions);
+  }
+  for name in sorted_keys(&components.success_actions) {
+    let path = format!("/components/successActions/{}", name);
+    visit_success_action(&components.success_actions[name], &path, callback);
+  }
+  for name in sorted_keys(&components.failure_actions) {
+    let path = format!("/components/failureActions/{}", name);
+    visit_failure_action(&components.failure_actions[name], &path, callback);
+  }
+}
+
+fn sorted_keys<V>(map: &HashMap<String, V>) -> Vec<&String> {
+  let mut keys = map.keys().collect::<Vec<_>>();
+  keys.sort();
+  keys
+}
+
+fn visit_parameters<F: FnMut(&str, &HashMap<String, AnyValue>)>(
+  parameters: &[Either<ParameterObject, ReusableObject>],
+  path: &str,
+  callback: &mut F
+) {
+  for (index, parameter) in parameters.iter().enumerate() {
+    if let Either::First(parameter) = parameter {
+      callback(&format!("{}/{}", path, index), &parameter.extensions);
+    }
+  }
+}
+
+fn visit_success_actions<F: FnMut(&str, &HashMap<String, AnyValue>)>(
+  actions: &[Either<SuccessObject, ReusableObject>],
+  path: &str,
+  callback: &mut F
+) {
+  for (index, action) in actions.iter().enumerate() {
+    if let Either::First(action) = action {
+      visit_success_action(action, &format!("{}/{}", path, index), callback);
+    }
+  }
+}
+
+fn visit_success_action<F: FnMut(&str, &HashMap<String, AnyValue>)>(
+  action: &SuccessObject,
+  path: &str,
+  callback: &mut F
+) {
+  callback(path, &action.extensions);
+  visit_criteria(&action.criteria, &format!("{}/criteria", path), callback);
+}
+
+fn visit_failure_actions<F: FnMut(&str, &HashMap<String, AnyValue>)>(
+  actions: &[Either<FailureObject, ReusableObject>],
+  path: &str,
+  callback: &mut F
+) {
+  for (index, action) in actions.iter().enumerate() {
+    if let Either::First(action) = action {
+      visit_failure_action(action, &format!("{}/{}", path, index), callback);
+    }
+  }
+}
+
+fn visit_failure_action<F: FnMut(&str, &HashMap<String, AnyValue>)>(
+  action: &FailureObject,
+  path: &str,
+  callback: &mut F
+) {
+  callback(path, &action.extensions);
+  visit_criteria(&action.criteria, &format!("{}/criteria", path), callback);
+}
+
+fn visit_criteria<F: FnMut(&str, &HashMap<String, AnyValue>)>(criteria: &[Criterion], path: &str, callback: &mut F) {
+  for (index, criterion) in criteria.iter().enumerate() {
+    let path = format!("{}/{}", path, index);
+    callback(&path, &criterion.extensions);
+    if let Some(Either::Second(expression_type)) = &criterion.r#type {
+      callback(&format!("{}/type", path), &expression_type.extensions);
+    }
+  }
+}
+
+/// Invokes the callback with the extension values of every object in the description that can
+/// have extensions, along with the JSON Pointer to the object, allowing the values to be modified.
+/// Objects are visited in the same order as [`for_each_extensions`].
+pub fn for_each_extensions_mut<F>(description: &mut ArazzoDescription, callback: &mut F)
+  where F: FnMut(&str, &mut HashMap<String, AnyValue>) {
+  callback("", &mut description.extensions);
+  callback("/info", &mut description.info.extensions);
+  for (index, source) in description.source_descriptions.iter_mut().enumerate() {
+    callback(&format!("/sourceDescriptions/{}", index), &mut source.extensions);
+  }
+  for (index, workflow) in description.workflows.iter_mut().enumerate() {
+    let path = format!("/workflows/{}", index);
+    callback(&path, &mut workflow.extensions);
+    visit_parameters_mut(&mut workflow.parameters, &format!("{}/parameters", path), callback);
+    visit_success_actions_mut(&mut workflow.success_actions, &format!("{}/successActions", path), callback);
+    visit_failure_actions_mut(&mut workflow.failure_actions, &format!("{}/failureActions", path), callback);
+    for (index, step) in workflow.steps.iter_mut().enumerate() {
+      let path = format!("{}/steps/{}", path, index);
+      callback(&path, &mut step.extensions);
+      visit_parameters_mut(&mut step.parameters, &format!("{}/parameters", path), callback);
+      if let Some(body) = &mut step.request_body {
+        callback(&format!("{}/requestBody", path), &mut body.extensions);
+        for (index, replacement) in body.replacements.iter_mut().enumerate() {
+          callback(&format!("{}/requestBody/replacements/{}", path, index), &mut replacement.extensions);
+        }
+      }
+      visit_criteria_mut(&mut step.success_criteria, &format!("{}/successCriteria", path), callback);
+      visit_success_actions_mut(&mut step.on_success, &format!("{}/onSuccess", path), callback);
+      visit_failure_actions_mut(&mut step.on_failure, &format!("{}/onFailure", path), callback);
+    }
+  }
+
+  let components = &mut description.components;
+  callback("/components", &mut components.extensions);
+  let mut parameters = components.parameters.iter_mut().collect::<Vec<_>>();
+  parameters.sort_by_key(|(name, _)| name.as_str());
+  for (name, parameter) in parameters {
+    callback(&format!("/components/parameters/{}", name), &mut parameter.extensions);
+  }
+  let mut success_actions = components.success_actions.iter_mut().collect::<Vec<_>>();
+  success_actions.sort_by_key(|(name, _)| name.as_str());
+  for (name, action) in success_actions {
+    visit_success_action_mut(action, &format!("/components/successActions/{}", name), callback);
+  }
+  let mut failure_actions = components.failure_actions.iter_mut().collect::<Vec<_>>();
+  failure_actions.sort_by_key(|(name, _)| name.as_str());
+  for (name, action) in failure_actions {
+    visit_failure_action_mut(action, &format!("/components/failureActions/{}", name), callback);
+  }
+}
+
+fn visit_parameters_mut<F: FnMut(&str, &mut HashMap<String, AnyValue>)>(
+  parameters: &mut [Either<ParameterObject, ReusableObject>],
+  path: &str,
+  callback: &mut F
+) {
+  for (index, parameter) in parameters.iter_mut().enumerate() {
+    if let Either::First(parameter) = parameter {
+      callback(&format!("{}/{}", path, index), &mut parameter.extensions);
+    }
+  }
+}
+
+fn visit_success_actions_mut<F: FnMut(&str, &mut HashMap<String, AnyValue>)>(
+  actions: &mut [Either<SuccessObject, ReusableObject>],
+  path: &str,
+  callback: &mut F
+) {
+  for (index, action) in actions.iter_mut().enumerate() {
+    if let Either::First(action) = action {
+      visit_success_action_mut(action, &format!("{}/{}", path, index), callback);
+    }
+  }
+}
+
+fn visit_success_action_mut<F: FnMut(&str, &mut HashMap<String, AnyValue>)>(
+  action: &mut SuccessObject,
+  path: &str,
+  callback: &mut F
+) {
+  callback(path, &mut action.extensions);
+  visit_criteria_mut(&mut action.criteria, &format!("{}/criteria", path), callback);
+}
+
+fn visit_failure_actions_mut<F: FnMut(&str, &mut HashMap<String, AnyValue>)>(
+  actions: &mut [Either<FailureObject, ReusableObject>],
+  path: &str,
+  callback: &mut F
+) {
+  for (index, action) in actions.iter_mut().enumerate() {
+    if let Either::First(action) = action {
+      visit_failure_action_mut(action, &format!("{}/{}", path, index), callback);
+    }
+  }
+}
+
+fn visit_failure_action_mut<F: FnMut(&str, &mut HashMap<String, AnyValue>)>(
+  action: &mut FailureObject,
+  path: &str,
+  callback: &mut F
+) {
+  callback(path, &mut action.extensions);
+  visit_criteria_mut(&mut action.criteria, &format!("{}/criteria", path), callback);
+}
+
+fn visit_criteria_mut<F: FnMut(&str, &mut HashMap<String, AnyValue>)>(criteria: &mut [Criterion], path: &str, callback: &mut F) {
+  for (index, criterion) in criteria.iter_mut().enumerate() {
+    let path = format!("{}/{}", path, index);
+    callback(&path, &mut criterion.extensions);
+    if let Some(Either::Second(expression_type)) = &mut criterion.r#type {
+      callback(&format!("{}/type", path), &mut expression_type.extensions);
+    }
+  }
 }
 
 #[cfg(test)]
 mod tests {
+  use std::collections::BTreeSet;
+
+  use bytes::Bytes;
   use expectest::prelude::*;
-  use maplit::hashmap;
+  use indexmap::indexmap;
+  use maplit::{btreeset, hashmap};
   #[cfg(feature = "yaml")] use yaml_rust2::Yaml;
   #[cfg(feature = "yaml")] use yaml_rust2::yaml::Hash;
 
-  use crate::extensions::AnyValue;
+  use crate::extensions::{AnyValue, ExtensionFilter, Extensible};
+  use crate::v1_0::{ArazzoDescription, Info, Step, Workflow};
+
+  #[test]
+  fn any_value_from_rust_values() {
+    expect!(AnyValue::from("test")).to(be_equal_to(AnyValue::String("test".to_string())));
+    expect!(AnyValue::from("test".to_string())).to(be_equal_to(AnyValue::String("test".to_string())));
+    let value: AnyValue = 100.into();
+    expect!(value).to(be_equal_to(AnyValue::Integer(100)));
+    expect!(AnyValue::from(100_u32)).to(be_equal_to(AnyValue::UInteger(100)));
+    expect!(AnyValue::from(1.5)).to(be_equal_to(AnyValue::Float(1.5)));
+    expect!(AnyValue::from(true)).to(be_equal_to(AnyValue::Boolean(true)));
+    expect!(AnyValue::from(vec!["a", "b"])).to(be_equal_to(AnyValue::Array(vec![
+      AnyValue::String("a".to_string()),
+      AnyValue::String("b".to_string())
+    ])));
+    expect!(AnyValue::from(vec![AnyValue::Null])).to(be_equal_to(AnyValue::Array(vec![AnyValue::Null])));
+    expect!(AnyValue::from(hashmap!{ "a".to_string() => 1_i64 })).to(be_equal_to(AnyValue::Object(indexmap!{
+      "a".to_string() => AnyValue::Integer(1)
+    })));
+    expect!(AnyValue::from(None::<bool>)).to(be_equal_to(AnyValue::Null));
+    expect!(AnyValue::from(Some(false))).to(be_equal_to(AnyValue::Boolean(false)));
+  }
+
+  #[test]
+  fn any_value_accessors() {
+    let value = AnyValue::Object(indexmap!{
+      "a".to_string() => AnyValue::Object(indexmap!{
+        "b".to_string() => AnyValue::Array(vec![
+          AnyValue::Integer(1),
+          AnyValue::UInteger(2),
+          AnyValue::Object(indexmap!{ "c/d".to_string() => AnyValue::Boolean(true) })
+        ])
+      }),
+      "e".to_string() => AnyValue::String("text".to_string())
+    });
+
+    expect!(value["e"].as_str()).to(be_some().value("text"));
+    expect!(value["a"]["b"][0].as_i64()).to(be_some().value(1));
+    expect!(value["a"]["b"][1].as_u64()).to(be_some().value(2));
+    expect!(value["a"]["b"][1].as_f64()).to(be_some().value(2.0));
+    expect!(value["a"]["b"][10].is_null()).to(be_true());
+    expect!(value["x"]["y"].is_null()).to(be_true());
+    expect!(value["e"].as_bool()).to(be_none());
+    expect!(value["a"]["b"].as_array().map(|a| a.len())).to(be_some().value(3));
+    expect!(value["a"].as_object().map(|o| o.len())).to(be_some().value(1));
+
+    expect!(value.pointer("/a/b/2/c~1d")).to(be_some().value(&AnyValue::Boolean(true)));
+    expect!(value.pointer("")).to(be_some().value(&value));
+    expect!(value.pointer("/a/x")).to(be_none());
+    expect!(value.get_path("a.b[2].c/d")).to(be_some().value(&AnyValue::Boolean(true)));
+    expect!(value.get_path("a.b[0]")).to(be_some().value(&AnyValue::Integer(1)));
+    expect!(value.get_path("a.b[5]")).to(be_none());
+    expect!(value.get_path("a.b[0")).to(be_none());
+  }
+
+  #[test]
+  #[cfg(feature = "json")]
+  fn any_value_json_round_trip() {
+    let json = serde_json::json!({
+      "null": null,
+      "bool": true,
+      "int": -10,
+      "uint": 18446744073709551615_u64,
+      "float": 1.5,
+      "string": "text",
+      "array": [1, "two", [3]],
+      "object": { "a": { "b": false } }
+    });
+    let value = AnyValue::try_from(json.clone()).unwrap();
+    expect!(value["int"].clone()).to(be_equal_to(AnyValue::Integer(-10)));
+    expect!(value["uint"].clone()).to(be_equal_to(AnyValue::UInteger(u64::MAX)));
+    expect!(value.to_json()).to(be_equal_to(json.clone()));
+    expect!(serde_json::Value::from(value)).to(be_equal_to(json));
+    expect!(AnyValue::Float(f64::NAN).to_json()).to(be_equal_to(serde_json::Value::Null));
+  }
 
   #[test]
   #[cfg(feature = "yaml")]
@@ -207,6 +1034,141 @@ mod tests {
       .to(be_ok().value(AnyValue::Float(1234.56)));
   }
 
+  #[test]
+  #[cfg(feature = "yaml")]
+  fn any_value_yaml_round_trip() {
+    let value = AnyValue::Object(indexmap!{
+      "b".to_string() => AnyValue::Array(vec![
+        AnyValue::Null,
+        AnyValue::Boolean(true),
+        AnyValue::Integer(-1),
+        AnyValue::Integer(2),
+        AnyValue::Float(1.0),
+        AnyValue::String("text".to_string())
+      ]),
+      "a".to_string() => AnyValue::Float(f64::INFINITY)
+    });
+
+    let yaml = value.to_yaml();
+    let keys = yaml.as_hash().unwrap().keys().map(|k| k.as_str().unwrap()).collect::<Vec<_>>();
+    expect!(keys).to(be_equal_to(vec!["b", "a"]));
+    expect!(yaml["b"][4].clone()).to(be_equal_to(Yaml::Real("1.0".to_string())));
+    expect!(AnyValue::try_from(yaml)).to(be_ok().value(value));
+
+    expect!(Yaml::from(AnyValue::UInteger(u64::MAX))).to(be_equal_to(Yaml::Real("18446744073709551615".to_string())));
+    expect!(AnyValue::try_from(Yaml::Real("18446744073709551615".to_string()))).to(be_ok().value(AnyValue::UInteger(u64::MAX)));
+    let nan = AnyValue::try_from(Yaml::from(AnyValue::Float(f64::NAN))).unwrap();
+    expect!(nan.as_f64().unwrap().is_nan()).to(be_true());
+  }
+
+  #[test]
+  fn extension_setters_normalise_the_keys() {
+    let mut step = Step::default()
+      .with_extension("x-timeout", 100)
+      .with_extension("retries", 2);
+    expect!(step.extensions.keys().cloned().collect::<BTreeSet<_>>())
+      .to(be_equal_to(btreeset!{ "retries".to_string(), "timeout".to_string() }));
+    expect!(step.extension("timeout")).to(be_some().value(&AnyValue::Integer(100)));
+    expect!(step.extension("x-retries")).to(be_some().value(&AnyValue::Integer(2)));
+
+    expect!(step.set_extension("timeout", "1s")).to(be_some().value(AnyValue::Integer(100)));
+    expect!(step.remove_extension("x-timeout")).to(be_some().value(AnyValue::from("1s")));
+    expect!(step.remove_extension("timeout")).to(be_none());
+    expect!(step.extensions().len()).to(be_equal_to(1));
+  }
+
+  #[test]
+  fn filter_extensions_from_a_description() {
+    let description = ArazzoDescription {
+      info: Info::default().with_extension("pact-id", 1).with_extension("internal", true),
+      workflows: vec![
+        Workflow {
+          steps: vec![ Step::default().with_extension("x-pact-timeout", 10).with_extension("owner", "a") ],
+          .. Workflow::default()
+        }.with_extension("owner", "b")
+      ],
+      .. ArazzoDescription::default()
+    }.with_extension("x-internal", "c");
+
+    let filtered = description.without_extensions(&ExtensionFilter::Allow(vec!["x-pact-*".to_string()]));
+    expect!(filtered.info.extensions.keys().cloned().collect::<Vec<_>>()).to(be_equal_to(vec!["pact-id".to_string()]));
+    expect!(filtered.workflows[0].steps[0].extension("pact-timeout")).to(be_some());
+    expect!(filtered.workflows[0].steps[0].extensions.len()).to(be_equal_to(1));
+    expect!(filtered.workflows[0].extensions.is_empty()).to(be_true());
+    expect!(filtered.extensions.is_empty()).to(be_true());
+
+    let mut filtered = description.clone();
+    expect!(filtered.filter_extensions(&ExtensionFilter::Deny(vec!["internal".to_string(), "owner".to_string()]))).to(be_equal_to(4));
+    expect!(filtered.info.extensions.len()).to(be_equal_to(1));
+    expect!(filtered.workflows[0].steps[0].extensions.len()).to(be_equal_to(1));
+
+    let mut filtered = description.clone();
+    expect!(filtered.filter_extensions(&ExtensionFilter::RemoveAll)).to(be_equal_to(6));
+    expect!(description.extensions.len()).to(be_equal_to(1));
+  }
+
+  #[test]
+  #[cfg(all(feature = "json", feature = "yaml"))]
+  fn binary_values_are_base64_encoded() {
+    let value = AnyValue::from(Bytes::from_static(b"foobar"));
+    expect!(value.as_bytes()).to(be_some().value(&Bytes::from_static(b"foobar")));
+    expect!(value.to_json()).to(be_equal_to(serde_json::Value::String("Zm9vYmFy".to_string())));
+    expect!(value.to_yaml()).to(be_equal_to(Yaml::String("Zm9vYmFy".to_string())));
+  }
+
+  #[test]
+  #[cfg(all(feature = "json", feature = "serialize", feature = "arbitrary_precision"))]
+  fn big_numbers_keep_their_precision_with_json() {
+    let json = r#"{"big":123456789012345678901234567890,"float":1.5,"long":-178696.12125897125,"precise":0.10000000000000000555}"#;
+    let value = AnyValue::try_from(serde_json::from_str::<serde_json::Value>(json).unwrap()).unwrap();
+    expect!(value["big"].clone()).to(be_equal_to(AnyValue::BigNumber("123456789012345678901234567890".to_string())));
+    expect!(value["precise"].clone()).to(be_equal_to(AnyValue::BigNumber("0.10000000000000000555".to_string())));
+    expect!(value["float"].clone()).to(be_equal_to(AnyValue::Float(1.5)));
+    expect!(value["long"].clone()).to(be_equal_to(AnyValue::Float(-178696.12125897125)));
+    expect!(value["precise"].as_f64()).to(be_some().value(0.1));
+    expect!(serde_json::to_string(&value).unwrap()).to(be_equal_to(json));
+    expect!(serde_json::to_string(&value.to_json()).unwrap()).to(be_equal_to(json));
+  }
+
+  #[test]
+  #[cfg(all(feature = "yaml", feature = "arbitrary_precision"))]
+  fn big_numbers_keep_their_precision_with_yaml() {
+    let yaml = yaml_rust2::YamlLoader::load_from_str("a: 123456789012345678901234567890\nb: 0.10000000000000000555\nc: 1e400\n").unwrap();
+    let value = AnyValue::try_from(&yaml[0]).unwrap();
+    expect!(value["a"].clone()).to(be_equal_to(AnyValue::BigNumber("123456789012345678901234567890".to_string())));
+    expect!(value["b"].clone()).to(be_equal_to(AnyValue::BigNumber("0.10000000000000000555".to_string())));
+    expect!(value["c"].clone()).to(be_equal_to(AnyValue::BigNumber("1e400".to_string())));
+    expect!(value.to_yaml()).to(be_equal_to(yaml[0].clone()));
+  }
+
+  #[test]
+  #[cfg(feature = "yaml")]
+  fn load_binary_values_from_yaml() {
+    let yaml = crate::yaml::yaml_load_documents("a: !!binary |\n  Zm9v\n  YmFy\nb: !!binary Zm9v\nc: Zm9v\n").unwrap();
+    let value = AnyValue::try_from(&yaml[0]).unwrap();
+    expect!(value["a"].clone()).to(be_equal_to(AnyValue::Binary(Bytes::from_static(b"foobar"))));
+    expect!(value["b"].clone()).to(be_equal_to(AnyValue::Binary(Bytes::from_static(b"foo"))));
+    expect!(value["c"].clone()).to(be_equal_to(AnyValue::String("Zm9v".to_string())));
+
+    let yaml = crate::yaml::yaml_load_documents("a: !!binary Zm9v!").unwrap();
+    expect!(AnyValue::try_from(&yaml[0])).to(be_err());
+    expect!(crate::yaml::yaml_load_documents("a: [b")).to(be_err());
+  }
+
+  #[test]
+  #[cfg(feature = "yaml")]
+  fn object_values_preserve_the_key_order() {
+    let mut hash = Hash::new();
+    hash.insert(Yaml::String("z".to_string()), Yaml::Integer(1));
+    hash.insert(Yaml::String("a".to_string()), Yaml::Integer(2));
+    hash.insert(Yaml::String("m".to_string()), Yaml::Integer(3));
+
+    let value = AnyValue::try_from(&Yaml::Hash(hash.clone())).unwrap();
+    let keys = value.as_object().unwrap().keys().cloned().collect::<Vec<_>>();
+    expect!(keys).to(be_equal_to(vec!["z".to_string(), "a".to_string(), "m".to_string()]));
+    expect!(value.to_yaml()).to(be_equal_to(Yaml::Hash(hash)));
+  }
+
   #[test]
   #[cfg(feature = "yaml")]
   fn create_extension_value_from_array() {
@@ -232,7 +1194,7 @@ mod tests {
   fn create_extension_value_from_object() {
     let hash = Hash::new();
     expect!(AnyValue::try_from(&Yaml::Hash(hash)))
-      .to(be_ok().value(AnyValue::Object(hashmap!{})));
+      .to(be_ok().value(AnyValue::Object(indexmap!{})));
 
     let mut hash = Hash::new();
     hash.insert(Yaml::String("a".to_string()), Yaml::Null);
@@ -245,7 +1207,7 @@ mod tests {
     hash.insert(Yaml::String("c".to_string()), array);
 
     expect!(AnyValue::try_from(&Yaml::Hash(hash)))
-      .to(be_ok().value(AnyValue::Object(hashmap!{
+      .to(be_ok().value(AnyValue::Object(indexmap!{
         "a".to_string() => AnyValue::Null,
         "b".to_string() => AnyValue::Float(123.4),
         "c".to_string() => AnyValue::Array(vec![
@@ -0,0 +1,54 @@
+//! Single-pass classification of the keys of JSON Objects and YAML Hashes. The loaders use this for
+//! the objects that occur many times in a document (workflows, steps, parameters, actions and
+//! criteria), so the values of the fixed fields and the extension values are found with one pass
+//! over the object, instead of a lookup for each field followed by another pass for the extensions.
+
+use std::collections::HashMap;
+
+use crate::extensions::AnyValue;
+
+/// Values of the fixed fields and the extensions of an object
+pub(crate) struct ObjectFields<'a, V, const N: usize> {
+  keys: &'static [&'static str; N],
+  values: [Option<&'a V>; N],
+  extensions: Vec<(&'a str, &'a V)>
+}
+
+impl<'a, V, const N: usize> ObjectFields<'a, V, N> {
+  /// Classifies the entries of an object. Entries with an `x-` prefix are extension values,
+  /// entries with one of the keys are fixed fields, and all other entries are ignored.
+  pub(crate) fn classify<I>(keys: &'static [&'static str; N], entries: I) -> Self
+    where I: IntoIterator<Item = (&'a str, &'a V)> {
+    let mut values = [None; N];
+    let mut extensions = Vec::new();
+
+    for (key, value) in entries {
+      if let Some(name) = key.strip_prefix("x-") {
+        extensions.push((name, value));
+      } else if let Some(index) = keys.iter().position(|k| *k == key) {
+        values[index] = Some(value);
+      }
+    }
+
+    ObjectFields { keys, values, extensions }
+  }
+
+  /// Returns the value of the fixed field
+  pub(crate) fn get(&self, key: &str) -> Option<&'a V> {
+    self.keys.iter()
+      .position(|k| *k == key)
+      .and_then(|index| self.values[index])
+  }
+
+  /// Converts the extension values, keyed without the `x-` prefix
+  pub(crate) fn extensions(&self) -> anyhow::Result<HashMap<String, AnyValue>>
+    where &'a V: TryInto<AnyValue, Error = anyhow::Error> {
+    let mut extensions = HashMap::with_capacity(self.extensions.len());
+
+    for (key, value) in &self.extensions {
+      extensions.insert(key.to_string(), (*value).try_into()?);
+    }
+
+    Ok(extensions)
+  }
+}
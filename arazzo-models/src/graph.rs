@@ -0,0 +1,529 @@
+//! Graph of the workflows and steps of an Arazzo description, for visualisers to draw without
+//! having to derive the graph from the document. The graph can be converted to a JSON document
+//! with [`WorkflowGraph::to_json`], which has this structure (version 1):
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "metadata": { "title": "...", "version": "...", "arazzo": "1.0.1", "workflows": 2, "steps": 5 },
+//!   "nodes": [
+//!     { "id": "workflow:adopt", "type": "workflow", "label": "adopt", "workflowId": "adopt", "summary": "...", "external": false },
+//!     { "id": "step:adopt/find", "type": "step", "label": "find", "workflowId": "adopt", "stepId": "find", "index": 0, "operationId": "findPets" }
+//!   ],
+//!   "edges": [
+//!     { "id": "e0", "type": "next", "source": "workflow:adopt", "target": "step:adopt/find" }
+//!   ]
+//! }
+//! ```
+//!
+//! Node IDs are `workflow:<workflowId>` and `step:<workflowId>/<stepId>`. Workflows of other
+//! source descriptions are `workflow:<sourceName>.<workflowId>` with `external` set. Step nodes
+//! have one of `operationId`, `operationPath` or `targetWorkflowId`. Fields without a value are
+//! left out.
+//!
+//! The edge types are:
+//! * `next`: from a workflow to its first step, and from each step to the following step
+//! * `dependsOn`: from a workflow to a workflow it depends on
+//! * `workflowCall`: from a step to the workflow it executes
+//! * `goto`, `retry` and `end`: success and failure actions (`action` has the action name, and
+//!   `outcome` is `success` or `failure`). `end` actions go to the workflow node.
+//! * `data`: from the step (or workflow) whose outputs are referenced by a runtime expression to
+//!   the step (or workflow) with the expression. `output` has the output name (if there is one),
+//!   `expression` the referencing text, and `location` a JSON Pointer to it (relative to the
+//!   workflow).
+//! * `input`: from a workflow to a step (or the workflow outputs) that references one of its
+//!   inputs, with `input` set to the input name
+
+use serde_json::{json, Map, Value};
+
+use crate::expressions::{expression_references, for_each_expression, ReferenceKind};
+use crate::render::transitions;
+use crate::v1_0::{ArazzoDescription, Step, Workflow};
+
+/// Version of the JSON structure of the graph
+pub const GRAPH_VERSION: u32 = 1;
+
+/// Type of graph node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeType {
+  /// Workflow
+  Workflow,
+  /// Step of a workflow
+  Step
+}
+
+/// Node of the graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphNode {
+  /// ID of the node (`workflow:<workflowId>` or `step:<workflowId>/<stepId>`)
+  pub id: String,
+  /// Type of node
+  pub node_type: NodeType,
+  /// Label to display
+  pub label: String,
+  /// Workflow ID (of the workflow, or the workflow the step is in)
+  pub workflow_id: String,
+  /// Step ID (for steps)
+  pub step_id: Option<String>,
+  /// Position of the step in the workflow (for steps)
+  pub index: Option<usize>,
+  /// Summary of the workflow, or description of the step
+  pub summary: Option<String>,
+  /// Operation ID (for operation steps)
+  pub operation_id: Option<String>,
+  /// Operation path (for operation steps)
+  pub operation_path: Option<String>,
+  /// Workflow the step executes (for workflow steps)
+  pub target_workflow_id: Option<String>,
+  /// If the workflow is from another source description
+  pub external: bool
+}
+
+/// Type of graph edge (see the [module documentation](self))
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeType {
+  /// Execution order of the steps
+  Next,
+  /// Dependency on another workflow
+  DependsOn,
+  /// Step that executes a workflow
+  WorkflowCall,
+  /// `goto` action
+  Goto,
+  /// `retry` action
+  Retry,
+  /// `end` action
+  End,
+  /// Reference to the outputs of a step or workflow
+  Data,
+  /// Reference to an input of the workflow
+  Input
+}
+
+impl EdgeType {
+  /// Name of the edge type in the JSON document
+  pub fn name(&self) -> &'static str {
+    match self {
+      EdgeType::Next => "next",
+      EdgeType::DependsOn => "dependsOn",
+      EdgeType::WorkflowCall => "workflowCall",
+      EdgeType::Goto => "goto",
+      EdgeType::Retry => "retry",
+      EdgeType::End => "end",
+      EdgeType::Data => "data",
+      EdgeType::Input => "input"
+    }
+  }
+}
+
+/// Edge of the graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphEdge {
+  /// ID of the edge (`e<index>`)
+  pub id: String,
+  /// Type of edge
+  pub edge_type: EdgeType,
+  /// ID of the source node
+  pub source: String,
+  /// ID of the target node
+  pub target: String,
+  /// Name of the action (for action edges)
+  pub action: Option<String>,
+  /// `success` or `failure` (for action edges)
+  pub outcome: Option<String>,
+  /// Name of the output (for data edges)
+  pub output: Option<String>,
+  /// Name of the input (for input edges)
+  pub input: Option<String>,
+  /// Text with the runtime expression (for data and input edges)
+  pub expression: Option<String>,
+  /// JSON Pointer to the expression, relative to the workflow (for data and input edges)
+  pub location: Option<String>
+}
+
+impl GraphEdge {
+  fn new(edge_type: EdgeType, source: String, target: String) -> Self {
+    GraphEdge {
+      id: String::new(),
+      edge_type,
+      source,
+      target,
+      action: None,
+      outcome: None,
+      output: None,
+      input: None,
+      expression: None,
+      location: None
+    }
+  }
+}
+
+/// Graph of the workflows and steps of an Arazzo description
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorkflowGraph {
+  /// Title of the description
+  pub title: String,
+  /// Version of the description
+  pub version: String,
+  /// Arazzo specification version of the description
+  pub arazzo: String,
+  /// Workflow and step nodes
+  pub nodes: Vec<GraphNode>,
+  /// Edges between the nodes
+  pub edges: Vec<GraphEdge>
+}
+
+impl WorkflowGraph {
+  /// Builds the graph of the Arazzo description
+  pub fn build(description: &ArazzoDescription) -> Self {
+    let mut graph = WorkflowGraph {
+      title: description.info.title.clone(),
+      version: description.info.version.clone(),
+      arazzo: description.arazzo.clone(),
+      nodes: vec![],
+      edges: vec![]
+    };
+    for workflow in &description.workflows {
+      graph.add_workflow(description, workflow);
+    }
+    for (index, edge) in graph.edges.iter_mut().enumerate() {
+      edge.id = format!("e{}", index);
+    }
+    graph
+  }
+
+  /// Returns the node with the ID
+  pub fn node(&self, id: &str) -> Option<&GraphNode> {
+    self.nodes.iter().find(|node| node.id == id)
+  }
+
+  /// Returns the edges of the type
+  pub fn edges_of_type(&self, edge_type: EdgeType) -> Vec<&GraphEdge> {
+    self.edges.iter().filter(|edge| edge.edge_type == edge_type).collect()
+  }
+
+  fn add_workflow(&mut self, description: &ArazzoDescription, workflow: &Workflow) {
+    let workflow_node = workflow_node_id(&workflow.workflow_id);
+    self.nodes.push(GraphNode {
+      id: workflow_node.clone(),
+      node_type: NodeType::Workflow,
+      label: workflow.workflow_id.clone(),
+      workflow_id: workflow.workflow_id.clone(),
+      step_id: None,
+      index: None,
+      summary: workflow.summary.clone(),
+      operation_id: None,
+      operation_path: None,
+      target_workflow_id: None,
+      external: false
+    });
+
+    let mut previous = workflow_node.clone();
+    for (index, step) in workflow.steps.iter().enumerate() {
+      let node = step_node_id(&workflow.workflow_id, &step.step_id);
+      self.nodes.push(GraphNode {
+        id: node.clone(),
+        node_type: NodeType::Step,
+        label: step.step_id.clone(),
+        workflow_id: workflow.workflow_id.clone(),
+        step_id: Some(step.step_id.clone()),
+        index: Some(index),
+        summary: step.description.clone(),
+        operation_id: step.operation_id.clone(),
+        operation_path: step.operation_path.clone(),
+        target_workflow_id: step.workflow_id.clone(),
+        external: false
+      });
+      self.edges.push(GraphEdge::new(EdgeType::Next, previous, node.clone()));
+      previous = node;
+    }
+
+    for dependency in &workflow.depends_on {
+      let target = self.workflow_reference(dependency);
+      self.edges.push(GraphEdge::new(EdgeType::DependsOn, workflow_node.clone(), target));
+    }
+    for step in &workflow.steps {
+      self.add_step_edges(description, workflow, step);
+    }
+    self.add_expression_edges(workflow);
+  }
+
+  fn add_step_edges(&mut self, description: &ArazzoDescription, workflow: &Workflow, step: &Step) {
+    let node = step_node_id(&workflow.workflow_id, &step.step_id);
+    if let Some(workflow_id) = &step.workflow_id {
+      let target = self.workflow_reference(workflow_id);
+      self.edges.push(GraphEdge::new(EdgeType::WorkflowCall, node.clone(), target));
+    }
+    for transition in transitions(description, workflow, step) {
+      let (edge_type, target) = match (transition.action_type.as_str(), &transition.step_id, &transition.workflow_id) {
+        ("goto", Some(step_id), _) => (EdgeType::Goto, step_node_id(&workflow.workflow_id, step_id)),
+        ("goto", None, Some(workflow_id)) => (EdgeType::Goto, self.workflow_reference(workflow_id)),
+        ("retry", Some(step_id), _) => (EdgeType::Retry, step_node_id(&workflow.workflow_id, step_id)),
+        ("retry", None, Some(workflow_id)) => (EdgeType::Retry, self.workflow_reference(workflow_id)),
+        ("retry", None, None) => (EdgeType::Retry, node.clone()),
+        ("end", _, _) => (EdgeType::End, workflow_node_id(&workflow.workflow_id)),
+        _ => continue
+      };
+      let mut edge = GraphEdge::new(edge_type, node.clone(), target);
+      edge.action = Some(transition.name.clone());
+      edge.outcome = Some(if transition.failure { "failure" } else { "success" }.to_string());
+      self.edges.push(edge);
+    }
+  }
+
+  /// Adds the data and input edges for the runtime expressions of the workflow
+  fn add_expression_edges(&mut self, workflow: &Workflow) {
+    let workflow_node = workflow_node_id(&workflow.workflow_id);
+    let mut edges = vec![];
+    for_each_expression(workflow, &mut |location: &str, text: &str| {
+      if location.starts_with("/dependsOn/") {
+        return;
+      }
+      // Expressions in a step belong to the step, and all others to the workflow
+      let owner = location.strip_prefix("/steps/")
+        .and_then(|rest| rest.split('/').next())
+        .and_then(|index| index.parse::<usize>().ok())
+        .and_then(|index| workflow.steps.get(index))
+        .map(|step| step_node_id(&workflow.workflow_id, &step.step_id))
+        .unwrap_or_else(|| workflow_node.clone());
+
+      for reference in expression_references(text) {
+        let source = match reference.kind {
+          ReferenceKind::Step => step_node_id(&workflow.workflow_id, &reference.name),
+          ReferenceKind::Workflow => workflow_node_id(&reference.name),
+          _ => continue
+        };
+        if source == owner {
+          continue;
+        }
+        let mut edge = GraphEdge::new(EdgeType::Data, source, owner.clone());
+        edge.output = text[reference.span.end..].strip_prefix(".outputs.").map(name_prefix);
+        edge.expression = Some(text.to_string());
+        edge.location = Some(location.to_string());
+        edges.push(edge);
+      }
+      for (index, _) in text.match_indices("$inputs.") {
+        let input = name_prefix(&text[index + "$inputs.".len()..]);
+        if !input.is_empty() && owner != workflow_node {
+          let mut edge = GraphEdge::new(EdgeType::Input, workflow_node.clone(), owner.clone());
+          edge.input = Some(input);
+          edge.expression = Some(text.to_string());
+          edge.location = Some(location.to_string());
+          edges.push(edge);
+        }
+      }
+    });
+    self.edges.extend(edges);
+  }
+
+  /// Node ID of a workflow reference (a workflow ID, or a `$sourceDescriptions.<name>.<workflowId>`
+  /// expression), adding an external workflow node for references to other source descriptions
+  fn workflow_reference(&mut self, reference: &str) -> String {
+    match reference.strip_prefix("$sourceDescriptions.") {
+      Some(name) => {
+        let id = workflow_node_id(name);
+        if self.node(&id).is_none() {
+          self.nodes.push(GraphNode {
+            id: id.clone(),
+            node_type: NodeType::Workflow,
+            label: name.to_string(),
+            workflow_id: name.split_once('.').map(|(_, workflow_id)| workflow_id).unwrap_or(name).to_string(),
+            step_id: None,
+            index: None,
+            summary: None,
+            operation_id: None,
+            operation_path: None,
+            target_workflow_id: None,
+            external: true
+          });
+        }
+        id
+      }
+      None => workflow_node_id(reference)
+    }
+  }
+
+  /// Converts the graph to the JSON structure described in the [module documentation](self)
+  pub fn to_json(&self) -> Value {
+    let steps = self.nodes.iter().filter(|node| node.node_type == NodeType::Step).count();
+    let workflows = self.nodes.iter().filter(|node| node.node_type == NodeType::Workflow && !node.external).count();
+    json!({
+      "version": GRAPH_VERSION,
+      "metadata": {
+        "title": self.title,
+        "version": self.version,
+        "arazzo": self.arazzo,
+        "workflows": workflows,
+        "steps": steps
+      },
+      "nodes": self.nodes.iter().map(node_json).collect::<Vec<_>>(),
+      "edges": self.edges.iter().map(edge_json).collect::<Vec<_>>()
+    })
+  }
+}
+
+fn node_json(node: &GraphNode) -> Value {
+  let mut json = Map::new();
+  json.insert("id".to_string(), json!(node.id));
+  json.insert("type".to_string(), json!(match node.node_type {
+    NodeType::Workflow => "workflow",
+    NodeType::Step => "step"
+  }));
+  json.insert("label".to_string(), json!(node.label));
+  json.insert("workflowId".to_string(), json!(node.workflow_id));
+  insert_optional(&mut json, "stepId", &node.step_id);
+  if let Some(index) = node.index {
+    json.insert("index".to_string(), json!(index));
+  }
+  insert_optional(&mut json, "summary", &node.summary);
+  insert_optional(&mut json, "operationId", &node.operation_id);
+  insert_optional(&mut json, "operationPath", &node.operation_path);
+  insert_optional(&mut json, "targetWorkflowId", &node.target_workflow_id);
+  if node.node_type == NodeType::Workflow {
+    json.insert("external".to_string(), json!(node.external));
+  }
+  Value::Object(json)
+}
+
+fn edge_json(edge: &GraphEdge) -> Value {
+  let mut json = Map::new();
+  json.insert("id".to_string(), json!(edge.id));
+  json.insert("type".to_string(), json!(edge.edge_type.name()));
+  json.insert("source".to_string(), json!(edge.source));
+  json.insert("target".to_string(), json!(edge.target));
+  insert_optional(&mut json, "action", &edge.action);
+  insert_optional(&mut json, "outcome", &edge.outcome);
+  insert_optional(&mut json, "output", &edge.output);
+  insert_optional(&mut json, "input", &edge.input);
+  insert_optional(&mut json, "expression", &edge.expression);
+  insert_optional(&mut json, "location", &edge.location);
+  Value::Object(json)
+}
+
+fn insert_optional(json: &mut Map<String, Value>, key: &str, value: &Option<String>) {
+  if let Some(value) = value {
+    json.insert(key.to_string(), json!(value));
+  }
+}
+
+/// Name at the start of the text (up to the first character that is not valid in a name)
+fn name_prefix(text: &str) -> String {
+  text.chars().take_while(|ch| ch.is_ascii_alphanumeric() || *ch == '_' || *ch == '-').collect()
+}
+
+fn workflow_node_id(workflow_id: &str) -> String {
+  format!("workflow:{}", workflow_id)
+}
+
+fn step_node_id(workflow_id: &str, step_id: &str) -> String {
+  format!("step:{}/{}", workflow_id, step_id)
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::btreemap;
+  use serde_json::json;
+
+  use crate::either::Either;
+  use crate::graph::{EdgeType, WorkflowGraph};
+  use crate::v1_0::{ArazzoDescription, Info, ParameterObject, Step, SuccessObject, Workflow};
+
+  #[test]
+  fn builds_the_graph_of_the_workflows() {
+    let description = ArazzoDescription {
+      arazzo: "1.0.1".to_string(),
+      info: Info {
+        title: "Pets".to_string(),
+        summary: None,
+        description: None,
+        version: "1.0".to_string(),
+        extensions: Default::default()
+      },
+      workflows: vec![Workflow {
+        workflow_id: "adopt".to_string(),
+        depends_on: vec!["$sourceDescriptions.users.login".to_string()],
+        steps: vec![
+          Step {
+            step_id: "find".to_string(),
+            operation_id: Some("findPets".to_string()),
+            parameters: vec![Either::First(ParameterObject {
+              name: "status".to_string(),
+              r#in: Some("query".to_string()),
+              value: Either::Second("$inputs.status".to_string()),
+              extensions: Default::default()
+            })],
+            outputs: btreemap!{ "id".to_string() => "$response.body#/0/id".to_string() },
+            .. Step::default()
+          },
+          Step {
+            step_id: "adopt".to_string(),
+            operation_path: Some("{$sourceDescriptions.pets.url}#/paths/~1adopt/post".to_string()),
+            parameters: vec![Either::First(ParameterObject {
+              name: "id".to_string(),
+              r#in: Some("query".to_string()),
+              value: Either::Second("$steps.find.outputs.id".to_string()),
+              extensions: Default::default()
+            })],
+            on_success: vec![Either::First(SuccessObject {
+              name: "done".to_string(),
+              r#type: "end".to_string(),
+              workflow_id: None,
+              step_id: None,
+              criteria: vec![],
+              extensions: Default::default()
+            })],
+            .. Step::default()
+          }
+        ],
+        outputs: btreemap!{ "pet".to_string() => "$steps.find.outputs.id".to_string() },
+        .. Workflow::default()
+      }],
+      .. ArazzoDescription::default()
+    };
+
+    let graph = WorkflowGraph::build(&description);
+    expect!(graph.to_json()).to(be_equal_to(json!({
+      "version": 1,
+      "metadata": { "title": "Pets", "version": "1.0", "arazzo": "1.0.1", "workflows": 1, "steps": 2 },
+      "nodes": [
+        { "id": "workflow:adopt", "type": "workflow", "label": "adopt", "workflowId": "adopt", "external": false },
+        {
+          "id": "step:adopt/find", "type": "step", "label": "find", "workflowId": "adopt", "stepId": "find",
+          "index": 0, "operationId": "findPets"
+        },
+        {
+          "id": "step:adopt/adopt", "type": "step", "label": "adopt", "workflowId": "adopt", "stepId": "adopt",
+          "index": 1, "operationPath": "{$sourceDescriptions.pets.url}#/paths/~1adopt/post"
+        },
+        {
+          "id": "workflow:users.login", "type": "workflow", "label": "users.login", "workflowId": "login",
+          "external": true
+        }
+      ],
+      "edges": [
+        { "id": "e0", "type": "next", "source": "workflow:adopt", "target": "step:adopt/find" },
+        { "id": "e1", "type": "next", "source": "step:adopt/find", "target": "step:adopt/adopt" },
+        { "id": "e2", "type": "dependsOn", "source": "workflow:adopt", "target": "workflow:users.login" },
+        {
+          "id": "e3", "type": "end", "source": "step:adopt/adopt", "target": "workflow:adopt",
+          "action": "done", "outcome": "success"
+        },
+        {
+          "id": "e4", "type": "data", "source": "step:adopt/find", "target": "workflow:adopt", "output": "id",
+          "expression": "$steps.find.outputs.id", "location": "/outputs/pet"
+        },
+        {
+          "id": "e5", "type": "input", "source": "workflow:adopt", "target": "step:adopt/find", "input": "status",
+          "expression": "$inputs.status", "location": "/steps/0/parameters/0/value"
+        },
+        {
+          "id": "e6", "type": "data", "source": "step:adopt/find", "target": "step:adopt/adopt", "output": "id",
+          "expression": "$steps.find.outputs.id", "location": "/steps/1/parameters/0/value"
+        }
+      ]
+    })));
+    expect!(graph.edges_of_type(EdgeType::Data).len()).to(be_equal_to(2));
+    expect!(graph.node("step:adopt/find").and_then(|node| node.index)).to(be_some().value(0));
+  }
+}
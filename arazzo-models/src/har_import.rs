@@ -0,0 +1,397 @@
+//! Generates a skeleton workflow from a [HAR](https://w3c.github.io/web-performance/specs/HAR/Overview.html)
+//! capture of a browser or proxy session, to be cleaned up by the author of the workflow.
+//!
+//! Each request of the capture is a step of the workflow, in the order they were made. Requests
+//! for static resources (images, fonts, stylesheets and scripts) are skipped. The operations of the
+//! steps are operation paths in a source description for each origin of the requests, with the
+//! origin as the URL of the source description (which needs to be changed to the URL of the
+//! OpenAPI document of the API). The content types of the request bodies are taken from the
+//! captured requests, and each step has a success criterion for the captured status code.
+//!
+//! Values in the JSON responses that are used in later requests (found by searching the text of
+//! the path segments, query parameters, headers and bodies of the later requests) are outputs of
+//! the step, and the later requests use the outputs instead of the captured values. Short values
+//! are not matched, to avoid replacing values that only happen to be the same.
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use indexmap::IndexMap;
+use serde_json::Value;
+
+use crate::either::Either;
+use crate::extensions::AnyValue;
+use crate::payloads::{FormPayload, JsonPayload, Payload, StringPayload};
+use crate::postman_import::{sanitise_id, unique_id};
+use crate::v1_0::{
+  ArazzoDescription,
+  Criterion,
+  Info,
+  ParameterObject,
+  RequestBody,
+  ReusableObject,
+  SourceDescription,
+  Step,
+  Workflow
+};
+
+/// Minimum length of response values that are matched in later requests
+const MIN_VALUE_LENGTH: usize = 4;
+
+/// Headers that are set by the browser or HTTP client, and are not added to the steps
+const IGNORED_HEADERS: [&str; 12] = [
+  "accept-encoding",
+  "cache-control",
+  "connection",
+  "content-length",
+  "cookie",
+  "host",
+  "origin",
+  "pragma",
+  "referer",
+  "te",
+  "upgrade-insecure-requests",
+  "user-agent"
+];
+
+/// Imports the requests of the HAR capture as a workflow with the given ID
+pub fn import_har(har: &Value, workflow_id: &str) -> anyhow::Result<ArazzoDescription> {
+  let entries = har.pointer("/log/entries").and_then(|entries| entries.as_array())
+    .ok_or_else(|| anyhow!("The HAR capture has no 'log.entries' array"))?;
+
+  let mut sources: IndexMap<String, String> = IndexMap::new();
+  let mut steps: Vec<Step> = vec![];
+  let mut captured: Vec<CapturedValue> = vec![];
+  for (index, entry) in entries.iter().enumerate() {
+    let request = entry.get("request")
+      .ok_or_else(|| anyhow!("Entry {} of the HAR capture has no 'request'", index))?;
+    if is_static_resource(entry) {
+      continue;
+    }
+    let method = request.get("method").and_then(|method| method.as_str()).unwrap_or("GET").to_uppercase();
+    let url = request.get("url").and_then(|url| url.as_str())
+      .ok_or_else(|| anyhow!("Entry {} of the HAR capture has no request URL", index))?;
+    let (origin, path) = split_url(url);
+
+    let source = match sources.iter().find(|(_, existing)| *existing == origin) {
+      Some((name, _)) => name.clone(),
+      None => {
+        let domain = origin.split_once("://").map(|(_, domain)| domain).unwrap_or(origin);
+        let name = unique_id(&sanitise_id(domain), |name| sources.contains_key(name));
+        sources.insert(name.clone(), origin.to_string());
+        name
+      }
+    };
+
+    let last_segment = path.split('/').rfind(|segment| !segment.is_empty()).unwrap_or("root");
+    let step_id = unique_id(&sanitise_id(&format!("{}-{}", method.to_lowercase(), last_segment)),
+      |id| steps.iter().any(|step| step.step_id == id));
+
+    let mut parameters = vec![];
+    let mut template = String::new();
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+      template.push('/');
+      match find_value(&captured, segment) {
+        Some(value) if value.value == segment => {
+          let (name, expression) = use_value(&mut steps, value);
+          template.push_str(&format!("{{{}}}", name));
+          parameters.push(parameter(&name, "path", expression));
+        }
+        _ => template.push_str(segment)
+      }
+    }
+    for param in har_pairs(request.get("queryString")) {
+      let value = replace_values(&param.1, &captured, &mut steps);
+      parameters.push(parameter(&param.0, "query", value));
+    }
+    for header in har_pairs(request.get("headers")) {
+      let name = header.0.to_lowercase();
+      if name.starts_with(':') || name.starts_with("sec-") || name == "content-type" || IGNORED_HEADERS.contains(&name.as_str()) {
+        continue;
+      }
+      let value = replace_values(&header.1, &captured, &mut steps);
+      parameters.push(parameter(&header.0, "header", value));
+    }
+
+    let request_body = request.get("postData")
+      .map(|post_data| self::request_body(post_data, &captured, &mut steps));
+
+    let status = entry.pointer("/response/status").and_then(|status| status.as_u64()).filter(|status| *status > 0);
+    steps.push(Step {
+      step_id: step_id.clone(),
+      operation_path: Some(format!("{{$sourceDescriptions.{}.url}}#/paths/{}/{}", source,
+        template.replace('~', "~0").replace('/', "~1"), method.to_lowercase())),
+      parameters,
+      request_body,
+      success_criteria: status
+        .map(|status| vec![Criterion { condition: format!("$statusCode == {}", status), .. Criterion::default() }])
+        .unwrap_or_default(),
+      .. Step::default()
+    });
+
+    if let Some(body) = response_json(entry) {
+      let step_index = steps.len() - 1;
+      collect_values(&body, "", step_index, &mut captured);
+    }
+  }
+
+  Ok(ArazzoDescription {
+    info: Info {
+      title: format!("Workflow recorded by {}", har.pointer("/log/creator/name").and_then(|name| name.as_str()).unwrap_or("HAR capture")),
+      version: "1.0.0".to_string(),
+      .. Info::default()
+    },
+    source_descriptions: sources.into_iter()
+      .map(|(name, url)| SourceDescription { name, url, r#type: Some("openapi".to_string()), extensions: Default::default() })
+      .collect(),
+    workflows: vec![Workflow {
+      workflow_id: workflow_id.to_string(),
+      steps,
+      .. Workflow::default()
+    }],
+    .. ArazzoDescription::default()
+  })
+}
+
+/// Value from a response that may be used in later requests
+#[derive(Debug, Clone)]
+struct CapturedValue {
+  /// Text of the value
+  value: String,
+  /// Index of the step with the response
+  step: usize,
+  /// JSON Pointer to the value in the response body
+  pointer: String
+}
+
+fn collect_values(value: &Value, pointer: &str, step: usize, captured: &mut Vec<CapturedValue>) {
+  let text = match value {
+    Value::Object(map) => {
+      for (key, value) in map {
+        collect_values(value, &format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1")), step, captured);
+      }
+      return;
+    }
+    Value::Array(values) => {
+      for (index, value) in values.iter().enumerate() {
+        collect_values(value, &format!("{}/{}", pointer, index), step, captured);
+      }
+      return;
+    }
+    Value::String(text) => text.clone(),
+    Value::Number(number) => number.to_string(),
+    _ => return
+  };
+  if text.len() >= MIN_VALUE_LENGTH {
+    captured.push(CapturedValue { value: text, step, pointer: pointer.to_string() });
+  }
+}
+
+/// Finds the most recently captured value in the text, preferring longer values
+fn find_value<'a>(captured: &'a [CapturedValue], text: &str) -> Option<&'a CapturedValue> {
+  captured.iter().rev()
+    .filter(|value| text.contains(&value.value))
+    .max_by_key(|value| value.value.len())
+}
+
+/// Adds the output for the captured value to the step that captured it, returning the name of
+/// the output and the expression for it
+fn use_value(steps: &mut [Step], value: &CapturedValue) -> (String, String) {
+  let step = &mut steps[value.step];
+  let response = format!("$response.body#{}", value.pointer);
+  let name = match step.outputs.iter().find(|(_, existing)| **existing == response) {
+    Some((name, _)) => name.clone(),
+    None => {
+      let last = value.pointer.rsplit('/').find(|segment| segment.parse::<usize>().is_err()).unwrap_or("value");
+      let name = unique_id(&sanitise_id(last), |name| step.outputs.contains_key(name));
+      step.outputs.insert(name.clone(), response);
+      name
+    }
+  };
+  let expression = format!("$steps.{}.outputs.{}", step.step_id, name);
+  (name, expression)
+}
+
+/// Replaces the captured values in the text with the outputs of the steps. If the text is a
+/// captured value, the expression is returned, otherwise the expressions are embedded in the text.
+fn replace_values(text: &str, captured: &[CapturedValue], steps: &mut [Step]) -> String {
+  let mut result = text.to_string();
+  let mut whole = None;
+  let mut replaced: Vec<String> = vec![];
+  while let Some(value) = find_value(captured, &result).filter(|value| !replaced.contains(&value.value)) {
+    let (_, expression) = use_value(steps, value);
+    if result == value.value {
+      whole = Some(expression);
+      break;
+    }
+    result = result.replace(&value.value, &format!("{{{}}}", expression));
+    replaced.push(value.value.clone());
+  }
+  whole.unwrap_or(result)
+}
+
+fn request_body(post_data: &Value, captured: &[CapturedValue], steps: &mut [Step]) -> RequestBody {
+  let content_type = post_data.get("mimeType").and_then(|mime_type| mime_type.as_str())
+    .filter(|mime_type| !mime_type.is_empty())
+    .map(|mime_type| mime_type.to_string());
+  let text = post_data.get("text").and_then(|text| text.as_str()).unwrap_or_default();
+  let is_json = content_type.as_deref().map(|content_type| content_type.contains("json")).unwrap_or(false);
+  let is_form = content_type.as_deref().map(|content_type| content_type.starts_with("application/x-www-form-urlencoded")).unwrap_or(false);
+  let params = har_pairs(post_data.get("params"));
+
+  let payload: Arc<dyn Payload + Send + Sync> = if is_form && !params.is_empty() {
+    Arc::new(FormPayload(params.iter()
+      .map(|(name, value)| (name.clone(), AnyValue::String(replace_values(value, captured, steps))))
+      .collect()))
+  } else {
+    let text = replace_values(text, captured, steps);
+    match serde_json::from_str::<Value>(&text) {
+      Ok(json) if is_json => Arc::new(JsonPayload(json)),
+      _ => Arc::new(StringPayload(text))
+    }
+  };
+  RequestBody {
+    content_type,
+    payload: Some(payload),
+    replacements: vec![],
+    extensions: Default::default()
+  }
+}
+
+fn parameter(name: &str, location: &str, value: String) -> Either<ParameterObject, ReusableObject> {
+  Either::First(ParameterObject {
+    name: name.to_string(),
+    r#in: Some(location.to_string()),
+    value: if value.starts_with('$') { Either::Second(value) } else { Either::First(AnyValue::String(value)) },
+    extensions: Default::default()
+  })
+}
+
+/// Name and value pairs (headers, query parameters and form parameters) of a HAR request
+fn har_pairs(pairs: Option<&Value>) -> Vec<(String, String)> {
+  pairs.and_then(|pairs| pairs.as_array()).into_iter().flatten()
+    .filter_map(|pair| Some((pair.get("name")?.as_str()?.to_string(),
+      pair.get("value").and_then(|value| value.as_str()).unwrap_or_default().to_string())))
+    .collect()
+}
+
+/// Splits the URL into the origin and the path, without the query string and fragment
+pub(crate) fn split_url(url: &str) -> (&str, &str) {
+  let url = url.split(['?', '#']).next().unwrap_or(url);
+  let path_start = url.find("://").map(|index| index + 3).unwrap_or(0);
+  match url[path_start..].find('/') {
+    Some(index) => url.split_at(path_start + index),
+    None => (url, "")
+  }
+}
+
+fn response_json(entry: &Value) -> Option<Value> {
+  let content = entry.pointer("/response/content")?;
+  let mime_type = content.get("mimeType").and_then(|mime_type| mime_type.as_str()).unwrap_or_default();
+  if !mime_type.contains("json") {
+    return None;
+  }
+  let text = content.get("text")?.as_str()?;
+  if content.get("encoding").and_then(|encoding| encoding.as_str()) == Some("base64") {
+    let bytes = crate::base64::decode(text).ok()?;
+    serde_json::from_slice(&bytes).ok()
+  } else {
+    serde_json::from_str(text).ok()
+  }
+}
+
+fn is_static_resource(entry: &Value) -> bool {
+  let mime_type = entry.pointer("/response/content/mimeType").and_then(|mime_type| mime_type.as_str()).unwrap_or_default();
+  mime_type.starts_with("image/") || mime_type.starts_with("font/") || mime_type.starts_with("text/css")
+    || mime_type.contains("javascript")
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::either::Either;
+  use crate::extensions::AnyValue;
+  use crate::har_import::import_har;
+  use crate::payloads::JsonPayload;
+
+  #[test]
+  fn imports_the_requests_as_steps() {
+    let har = json!({
+      "log": {
+        "creator": { "name": "Firefox" },
+        "entries": [
+          {
+            "request": {
+              "method": "POST",
+              "url": "https://auth.example.com/tokens",
+              "headers": [{ "name": "Content-Type", "value": "application/json" }, { "name": "User-Agent", "value": "Firefox" }],
+              "postData": { "mimeType": "application/json", "text": "{\"user\": \"jane\"}" }
+            },
+            "response": {
+              "status": 201,
+              "content": { "mimeType": "application/json", "text": "{\"token\": \"abc123xyz\", \"ttl\": 60}" }
+            }
+          },
+          {
+            "request": { "method": "GET", "url": "https://shop.example.com/logo.png" },
+            "response": { "status": 200, "content": { "mimeType": "image/png" } }
+          },
+          {
+            "request": {
+              "method": "GET",
+              "url": "https://shop.example.com/orders?limit=10",
+              "headers": [{ "name": "Authorization", "value": "Bearer abc123xyz" }],
+              "queryString": [{ "name": "limit", "value": "10" }]
+            },
+            "response": {
+              "status": 200,
+              "content": { "mimeType": "application/json", "text": "{\"orders\": [{\"id\": \"order-42\"}]}" }
+            }
+          },
+          {
+            "request": {
+              "method": "PUT",
+              "url": "https://shop.example.com/orders/order-42",
+              "postData": { "mimeType": "application/json", "text": "{\"id\": \"order-42\", \"paid\": true}" }
+            },
+            "response": { "status": 204, "content": {} }
+          }
+        ]
+      }
+    });
+    let description = import_har(&har, "checkout").unwrap();
+    expect!(description.info.title.clone()).to(be_equal_to("Workflow recorded by Firefox"));
+    let sources = description.source_descriptions.iter().map(|source| (source.name.as_str(), source.url.as_str())).collect::<Vec<_>>();
+    expect!(sources).to(be_equal_to(vec![("auth-example-com", "https://auth.example.com"), ("shop-example-com", "https://shop.example.com")]));
+
+    let workflow = &description.workflows[0];
+    expect!(workflow.workflow_id.clone()).to(be_equal_to("checkout"));
+    let ids = workflow.steps.iter().map(|step| step.step_id.as_str()).collect::<Vec<_>>();
+    expect!(ids).to(be_equal_to(vec!["post-tokens", "get-orders", "put-order-42"]));
+
+    let token = &workflow.steps[0];
+    expect!(token.success_criteria[0].condition.clone()).to(be_equal_to("$statusCode == 201"));
+    expect!(token.outputs.get("token").cloned()).to(be_some().value("$response.body#/token"));
+    expect!(token.parameters.is_empty()).to(be_true());
+    expect!(token.request_body.as_ref().unwrap().content_type.clone()).to(be_some().value("application/json"));
+
+    let orders = &workflow.steps[1];
+    let Either::First(authorization) = &orders.parameters[1] else { panic!("Expected a parameter") };
+    expect!(authorization.value.clone()).to(be_equal_to(Either::First(AnyValue::String("Bearer {$steps.post-tokens.outputs.token}".to_string()))));
+    expect!(orders.outputs.get("id").cloned()).to(be_some().value("$response.body#/orders/0/id"));
+
+    let update = &workflow.steps[2];
+    expect!(update.operation_path.clone()).to(be_some().value("{$sourceDescriptions.shop-example-com.url}#/paths/~1orders~1{id}/put"));
+    let Either::First(id) = &update.parameters[0] else { panic!("Expected a parameter") };
+    expect!(id.value.clone()).to(be_equal_to(Either::Second("$steps.get-orders.outputs.id".to_string())));
+    let body = update.request_body.as_ref().unwrap().payload_as::<JsonPayload>().unwrap();
+    expect!(body.0.clone()).to(be_equal_to(json!({ "id": "{$steps.get-orders.outputs.id}", "paid": true })));
+  }
+
+  #[test]
+  fn returns_an_error_if_there_are_no_entries() {
+    expect!(import_har(&json!({ "log": {} }), "recorded")).to(be_err());
+  }
+}
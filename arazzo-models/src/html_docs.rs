@@ -0,0 +1,466 @@
+//! Renders an Arazzo description as a self-contained HTML documentation page (no external styles
+//! or scripts), for publishing to a documentation portal. It has the same content as the
+//! [Markdown documentation](crate::docs), with the steps of each workflow in collapsible sections,
+//! syntax highlighted request body payloads, and links to the workflows and steps that are
+//! referenced by `workflowId`, `stepId` and `dependsOn`.
+//!
+//! The page is rendered with a template, which has `{{title}}`, `{{style}}` and `{{content}}`
+//! placeholders (see [`TEMPLATE`] for the default one).
+
+use std::fmt::Write;
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::docs::{input_properties, parameter_value};
+use crate::either::Either;
+use crate::render::transitions;
+use crate::v1_0::{ArazzoDescription, Criterion, RequestBody, Step, Workflow};
+
+/// Default template of the page
+pub const TEMPLATE: &str = "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{{title}}</title>
+<style>{{style}}</style>
+</head>
+<body>
+{{content}}</body>
+</html>
+";
+
+/// Default styles of the page
+pub const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222;max-width:70em}\
+section{margin-top:2em}\
+table{border-collapse:collapse;margin:.5em 0}td,th{padding:.2em .8em;text-align:left;border-bottom:1px solid #eee}\
+details{margin:.3em 0;border:1px solid #ddd;padding:.3em .6em}summary{cursor:pointer}\
+pre{background:#f6f8fa;padding:.5em;overflow:auto}\
+.key{color:#0550ae}.string{color:#0a3069}.number{color:#953800}.literal{color:#8250df}\
+.success{color:#1a7f37}.failure{color:#cf222e}";
+
+/// Options for rendering the HTML documentation
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlDocsOptions {
+  /// Title of the page (defaults to the title of the Info Object)
+  pub title: Option<String>,
+  /// Template of the page, with `{{title}}`, `{{style}}` and `{{content}}` placeholders
+  pub template: String,
+  /// Styles added to the `{{style}}` placeholder
+  pub style: String
+}
+
+impl Default for HtmlDocsOptions {
+  fn default() -> Self {
+    HtmlDocsOptions {
+      title: None,
+      template: TEMPLATE.to_string(),
+      style: STYLE.to_string()
+    }
+  }
+}
+
+/// Renders the HTML documentation for the Arazzo description, with the default options
+pub fn html_docs(description: &ArazzoDescription) -> String {
+  render_html_docs(description, &HtmlDocsOptions::default())
+    .expect("the default template has a content placeholder")
+}
+
+/// Renders the HTML documentation for the Arazzo description. Returns an error if the template
+/// does not have a `{{content}}` placeholder.
+pub fn render_html_docs(description: &ArazzoDescription, options: &HtmlDocsOptions) -> anyhow::Result<String> {
+  if !options.template.contains("{{content}}") {
+    return Err(anyhow!("The HTML template must have a {{{{content}}}} placeholder"));
+  }
+
+  let title = options.title.as_ref().unwrap_or(&description.info.title);
+  let mut html = String::new();
+  let _ = writeln!(html, "<h1>{}</h1>", escape(&description.info.title));
+  paragraph(&mut html, description.info.summary.as_deref());
+  paragraph(&mut html, description.info.description.as_deref());
+  let _ = writeln!(html, "<p><strong>Version:</strong> {}</p>", escape(&description.info.version));
+
+  if !description.source_descriptions.is_empty() {
+    html.push_str("<h2>Source Descriptions</h2>\n");
+    let rows = description.source_descriptions.iter()
+      .map(|source| vec![
+        code(&source.name),
+        escape(source.r#type.as_deref().unwrap_or_default()),
+        format!("<a href=\"{}\">{}</a>", escape(&source.url), escape(&source.url))
+      ])
+      .collect::<Vec<_>>();
+    table(&mut html, &["Name", "Type", "URL"], &rows);
+  }
+
+  if !description.workflows.is_empty() {
+    html.push_str("<nav>\n<h2>Workflows</h2>\n<ul>\n");
+    for workflow in &description.workflows {
+      let _ = write!(html, "<li>{}", workflow_link(description, &workflow.workflow_id));
+      if let Some(summary) = &workflow.summary {
+        let _ = write!(html, ": {}", escape(summary));
+      }
+      html.push_str("</li>\n");
+    }
+    html.push_str("</ul>\n</nav>\n");
+  }
+
+  for workflow in &description.workflows {
+    workflow_section(&mut html, description, workflow);
+  }
+
+  Ok(options.template
+    .replace("{{title}}", &escape(title))
+    .replace("{{style}}", &options.style)
+    .replace("{{content}}", &html))
+}
+
+fn workflow_section(html: &mut String, description: &ArazzoDescription, workflow: &Workflow) {
+  let _ = writeln!(html, "<section id=\"{}\">", escape(&workflow_anchor(&workflow.workflow_id)));
+  let _ = writeln!(html, "<h2>{}</h2>", escape(&workflow.workflow_id));
+  paragraph(html, workflow.summary.as_deref());
+  paragraph(html, workflow.description.as_deref());
+  if !workflow.depends_on.is_empty() {
+    let depends_on = workflow.depends_on.iter()
+      .map(|workflow_id| workflow_link(description, workflow_id))
+      .collect::<Vec<_>>();
+    let _ = writeln!(html, "<p><strong>Depends on:</strong> {}</p>", depends_on.join(", "));
+  }
+
+  let inputs = input_properties(description, workflow);
+  if !inputs.is_empty() {
+    html.push_str("<h3>Inputs</h3>\n");
+    let rows = inputs.iter()
+      .map(|property| {
+        let mut details = property.description.iter().map(|description| escape(description)).collect::<Vec<_>>();
+        if !property.values.is_empty() {
+          let values = property.values.iter().map(|value| code(value)).collect::<Vec<_>>();
+          details.push(format!("One of: {}", values.join(", ")));
+        }
+        if let Some(default) = &property.default {
+          details.push(format!("Default: {}", code(default)));
+        }
+        vec![
+          code(&property.path),
+          escape(&property.schema_type),
+          if property.required { "yes".to_string() } else { "no".to_string() },
+          details.join(" ")
+        ]
+      })
+      .collect::<Vec<_>>();
+    table(html, &["Name", "Type", "Required", "Description"], &rows);
+  }
+
+  if !workflow.steps.is_empty() {
+    html.push_str("<h3>Steps</h3>\n");
+    for step in &workflow.steps {
+      step_details(html, description, workflow, step);
+    }
+  }
+
+  if !workflow.outputs.is_empty() {
+    html.push_str("<h3>Outputs</h3>\n");
+    let rows = workflow.outputs.iter()
+      .map(|(name, expression)| vec![code(name), code(expression)])
+      .collect::<Vec<_>>();
+    table(html, &["Name", "Value"], &rows);
+  }
+  html.push_str("</section>\n");
+}
+
+fn step_details(html: &mut String, description: &ArazzoDescription, workflow: &Workflow, step: &Step) {
+  let _ = writeln!(html, "<details id=\"{}\">", escape(&step_anchor(&workflow.workflow_id, &step.step_id)));
+  let operation = match (&step.operation_id, &step.operation_path, &step.workflow_id) {
+    (Some(operation_id), _, _) => format!(" {}", code(operation_id)),
+    (None, Some(operation_path), _) => format!(" {}", code(operation_path)),
+    (None, None, Some(workflow_id)) => format!(" workflow {}", workflow_link(description, workflow_id)),
+    _ => String::new()
+  };
+  let _ = writeln!(html, "<summary><strong>{}</strong>{}</summary>", escape(&step.step_id), operation);
+  paragraph(html, step.description.as_deref());
+
+  if !step.parameters.is_empty() {
+    html.push_str("<h4>Parameters</h4>\n");
+    let rows = step.parameters.iter()
+      .map(|parameter| match parameter {
+        Either::First(parameter) => vec![
+          code(&parameter.name),
+          escape(parameter.r#in.as_deref().unwrap_or_default()),
+          code(&parameter_value(parameter))
+        ],
+        Either::Second(reusable) => vec![
+          code(&reusable.reference),
+          String::new(),
+          reusable.value.as_ref().map(|value| code(value)).unwrap_or_default()
+        ]
+      })
+      .collect::<Vec<_>>();
+    table(html, &["Name", "In", "Value"], &rows);
+  }
+
+  if let Some(request_body) = &step.request_body {
+    request_body_details(html, request_body);
+  }
+
+  if !step.success_criteria.is_empty() {
+    html.push_str("<h4>Success Criteria</h4>\n<ul>\n");
+    for criterion in &step.success_criteria {
+      let _ = writeln!(html, "<li>{}</li>", criterion_html(criterion));
+    }
+    html.push_str("</ul>\n");
+  }
+
+  let actions = transitions(description, workflow, step);
+  if !actions.is_empty() {
+    html.push_str("<h4>Actions</h4>\n<ul>\n");
+    for action in actions {
+      let class = if action.failure { "failure" } else { "success" };
+      let _ = write!(html, "<li><span class=\"{}\">{}</span> {}", class, escape(&action.name), escape(&action.action_type));
+      if let Some(step_id) = &action.step_id {
+        let _ = write!(html, " to <a href=\"#{}\">{}</a>", escape(&step_anchor(&workflow.workflow_id, step_id)),
+          escape(step_id));
+      } else if let Some(workflow_id) = &action.workflow_id {
+        let _ = write!(html, " to {}", workflow_link(description, workflow_id));
+      }
+      if action.action_type == "retry" {
+        let _ = write!(html, " ({})", escape(&action.retry_annotation()));
+      }
+      html.push_str("</li>\n");
+    }
+    html.push_str("</ul>\n");
+  }
+
+  if !step.outputs.is_empty() {
+    html.push_str("<h4>Outputs</h4>\n");
+    let rows = step.outputs.iter()
+      .map(|(name, expression)| vec![code(name), code(expression)])
+      .collect::<Vec<_>>();
+    table(html, &["Name", "Value"], &rows);
+  }
+  html.push_str("</details>\n");
+}
+
+fn request_body_details(html: &mut String, request_body: &RequestBody) {
+  html.push_str("<h4>Request Body</h4>\n");
+  if let Some(content_type) = request_body.effective_content_type() {
+    let _ = writeln!(html, "<p>Content type: {}</p>", code(&content_type));
+  }
+  if let Some(payload) = &request_body.payload {
+    let _ = writeln!(html, "<pre><code>{}</code></pre>", highlight_payload(&payload.as_string()));
+  }
+  if !request_body.replacements.is_empty() {
+    let rows = request_body.replacements.iter()
+      .map(|replacement| {
+        let value = match &replacement.value {
+          Either::First(value) => value.to_json().to_string(),
+          Either::Second(expression) => expression.clone()
+        };
+        vec![code(&replacement.target), code(&value)]
+      })
+      .collect::<Vec<_>>();
+    table(html, &["Target", "Value"], &rows);
+  }
+}
+
+/// Highlights JSON payloads (pretty printed). Other payloads are escaped.
+fn highlight_payload(payload: &str) -> String {
+  match serde_json::from_str::<Value>(payload) {
+    Ok(json) => highlight_json(&serde_json::to_string_pretty(&json).unwrap_or_else(|_| payload.to_string())),
+    Err(_) => escape(payload)
+  }
+}
+
+/// Wraps the keys, strings, numbers and literals (`true`, `false` and `null`) of the JSON in spans
+fn highlight_json(json: &str) -> String {
+  let chars = json.chars().collect::<Vec<_>>();
+  let mut html = String::with_capacity(json.len() * 2);
+  let mut index = 0;
+  while index < chars.len() {
+    let ch = chars[index];
+    if ch == '"' {
+      let start = index;
+      index += 1;
+      while index < chars.len() && chars[index] != '"' {
+        if chars[index] == '\\' {
+          index += 1;
+        }
+        index += 1;
+      }
+      index = (index + 1).min(chars.len());
+      let token = chars[start..index].iter().collect::<String>();
+      let is_key = chars[index..].iter().find(|ch| !ch.is_whitespace()) == Some(&':');
+      let class = if is_key { "key" } else { "string" };
+      let _ = write!(html, "<span class=\"{}\">{}</span>", class, escape(&token));
+    } else if ch == '-' || ch.is_ascii_digit() || ch.is_ascii_alphabetic() {
+      let start = index;
+      while index < chars.len() && (chars[index].is_ascii_alphanumeric() || "+-.".contains(chars[index])) {
+        index += 1;
+      }
+      let token = chars[start..index].iter().collect::<String>();
+      let class = if ch.is_ascii_alphabetic() { "literal" } else { "number" };
+      let _ = write!(html, "<span class=\"{}\">{}</span>", class, escape(&token));
+    } else {
+      html.push_str(&escape(&ch.to_string()));
+      index += 1;
+    }
+  }
+  html
+}
+
+fn criterion_html(criterion: &Criterion) -> String {
+  let mut html = code(&criterion.condition);
+  if let Some(context) = &criterion.context {
+    let _ = write!(html, " on {}", code(context));
+  }
+  match &criterion.r#type {
+    Some(Either::First(criterion_type)) if criterion_type != "simple" => {
+      let _ = write!(html, " ({})", escape(criterion_type));
+    }
+    Some(Either::Second(expression_type)) => {
+      let _ = write!(html, " ({} {})", escape(&expression_type.r#type), escape(&expression_type.version));
+    }
+    _ => {}
+  }
+  html
+}
+
+/// Link to the section of the workflow, if it is in the description. Workflows of other source
+/// descriptions are not linked.
+fn workflow_link(description: &ArazzoDescription, workflow_id: &str) -> String {
+  if description.workflows.iter().any(|workflow| workflow.workflow_id == workflow_id) {
+    format!("<a href=\"#{}\">{}</a>", escape(&workflow_anchor(workflow_id)), escape(workflow_id))
+  } else {
+    code(workflow_id)
+  }
+}
+
+fn workflow_anchor(workflow_id: &str) -> String {
+  format!("workflow-{}", workflow_id)
+}
+
+fn step_anchor(workflow_id: &str, step_id: &str) -> String {
+  format!("step-{}-{}", workflow_id, step_id)
+}
+
+fn paragraph(html: &mut String, text: Option<&str>) {
+  if let Some(text) = text.map(|text| text.trim()).filter(|text| !text.is_empty()) {
+    let _ = writeln!(html, "<p>{}</p>", escape(text));
+  }
+}
+
+fn code(text: &str) -> String {
+  format!("<code>{}</code>", escape(text))
+}
+
+fn table(html: &mut String, headers: &[&str], rows: &[Vec<String>]) {
+  html.push_str("<table>\n<tr>");
+  for header in headers {
+    let _ = write!(html, "<th>{}</th>", header);
+  }
+  html.push_str("</tr>\n");
+  for row in rows {
+    html.push_str("<tr>");
+    for cell in row {
+      let _ = write!(html, "<td>{}</td>", cell);
+    }
+    html.push_str("</tr>\n");
+  }
+  html.push_str("</table>\n");
+}
+
+/// Escapes the special HTML characters of the text
+pub(crate) fn escape(text: &str) -> String {
+  let mut result = String::with_capacity(text.len());
+  for ch in text.chars() {
+    match ch {
+      '<' => result.push_str("&lt;"),
+      '>' => result.push_str("&gt;"),
+      '&' => result.push_str("&amp;"),
+      '"' => result.push_str("&quot;"),
+      '\'' => result.push_str("&#39;"),
+      _ => result.push(ch)
+    }
+  }
+  result
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::arazzo;
+  use crate::html_docs::{highlight_json, html_docs, render_html_docs, HtmlDocsOptions};
+  use crate::payloads::JsonPayload;
+  use crate::v1_0::{ArazzoDescription, FailureObject, RequestBody};
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      info("Pets <v2>", "2.0.0");
+      workflow("adopt") {
+        depends_on: "login";
+        step("create") {
+          operation_id: "createPet";
+          request_body: RequestBody {
+            content_type: Some("application/json".to_string()),
+            payload: Some(Arc::new(JsonPayload(json!({ "name": "Rex", "age": 3 })))),
+            replacements: vec![],
+            extensions: Default::default()
+          };
+          on_failure: FailureObject {
+            name: "again".to_string(),
+            r#type: "retry".to_string(),
+            workflow_id: None,
+            step_id: Some("create".to_string()),
+            retry_after: None,
+            retry_limit: Some(2),
+            criteria: vec![],
+            extensions: Default::default()
+          };
+        }
+        step("external") { workflow_id: "$sourceDescriptions.users.get"; }
+      }
+      workflow("login") {}
+    }
+  }
+
+  #[test]
+  fn renders_html_documentation() {
+    let html = html_docs(&description());
+    expect!(html.contains("<title>Pets &lt;v2&gt;</title>")).to(be_true());
+    expect!(html.contains("<section id=\"workflow-adopt\">")).to(be_true());
+    expect!(html.contains("<p><strong>Depends on:</strong> <a href=\"#workflow-login\">login</a></p>")).to(be_true());
+    expect!(html.contains("<details id=\"step-adopt-create\">\n<summary><strong>create</strong> <code>createPet</code></summary>"))
+      .to(be_true());
+    expect!(html.contains("<span class=\"key\">&quot;name&quot;</span>: <span class=\"string\">&quot;Rex&quot;</span>"))
+      .to(be_true());
+    expect!(html.contains("<li><span class=\"failure\">again</span> retry to <a href=\"#step-adopt-create\">create</a> (retry, up to 2 times)</li>"))
+      .to(be_true());
+    expect!(html.contains("<summary><strong>external</strong> workflow <code>$sourceDescriptions.users.get</code></summary>"))
+      .to(be_true());
+  }
+
+  #[test]
+  fn renders_html_documentation_with_a_template() {
+    let options = HtmlDocsOptions {
+      title: Some("Portal".to_string()),
+      template: "<main title=\"{{title}}\">{{content}}</main>".to_string(),
+      .. HtmlDocsOptions::default()
+    };
+    let html = render_html_docs(&description(), &options).unwrap();
+    expect!(html.starts_with("<main title=\"Portal\"><h1>Pets &lt;v2&gt;</h1>")).to(be_true());
+
+    let options = HtmlDocsOptions { template: "<main></main>".to_string(), .. HtmlDocsOptions::default() };
+    expect!(render_html_docs(&description(), &options).unwrap_err().to_string())
+      .to(be_equal_to("The HTML template must have a {{content}} placeholder"));
+  }
+
+  #[test]
+  fn highlights_json() {
+    expect!(highlight_json("{\"a\\\"b\": [-1.5e3, true, null, \"x<y\"]}")).to(be_equal_to(
+      "{<span class=\"key\">&quot;a\\&quot;b&quot;</span>: [<span class=\"number\">-1.5e3</span>, \
+      <span class=\"literal\">true</span>, <span class=\"literal\">null</span>, <span class=\"string\">&quot;x&lt;y&quot;</span>]}"
+    ));
+  }
+}
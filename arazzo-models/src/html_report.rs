@@ -0,0 +1,311 @@
+//! Renders [execution reports](crate::report::ExecutionReport) as a self-contained HTML page (no
+//! external styles or scripts), with a timeline of the steps of each workflow, the requests and
+//! responses (with any secrets redacted), and the criteria and retries of each step.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use bytes::Bytes;
+use serde_json::Value;
+
+use crate::executor::ExecutionStatus;
+use crate::html_docs::escape;
+use crate::report::{ExecutionReport, StepReport, WorkflowReport};
+
+/// Value that redacted secrets are replaced with
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Options for rendering the HTML report
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlOptions {
+  /// Title of the page
+  pub title: String,
+  /// Headers whose values are redacted (matched ignoring case)
+  pub redact_headers: Vec<String>,
+  /// Words that mark a JSON body field or query parameter as a secret. A field is redacted if its
+  /// name contains one of the words (ignoring case, `-` and `_`).
+  pub redact_fields: Vec<String>
+}
+
+impl Default for HtmlOptions {
+  fn default() -> Self {
+    HtmlOptions {
+      title: "Arazzo execution report".to_string(),
+      redact_headers: ["authorization", "proxy-authorization", "cookie", "set-cookie", "x-api-key"]
+        .iter().map(|header| header.to_string()).collect(),
+      redact_fields: ["password", "secret", "token", "apikey", "credential"]
+        .iter().map(|field| field.to_string()).collect()
+    }
+  }
+}
+
+impl HtmlOptions {
+  fn is_secret_header(&self, name: &str) -> bool {
+    self.redact_headers.iter().any(|header| header.eq_ignore_ascii_case(name)) || self.is_secret_field(name)
+  }
+
+  fn is_secret_field(&self, name: &str) -> bool {
+    let name = name.to_lowercase().replace(['-', '_'], "");
+    self.redact_fields.iter().any(|field| name.contains(&field.to_lowercase()))
+  }
+}
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+h2{margin-top:2em}\
+.success{color:#1a7f37}.failure,.timeout{color:#cf222e}.error{color:#9a6700}\
+.timeline{position:relative;height:1.4em;background:#f3f3f3;margin:.5em 0}\
+.bar{position:absolute;top:0;height:100%;min-width:2px;opacity:.8}\
+.bar.success{background:#1a7f37}.bar.failure,.bar.timeout{background:#cf222e}.bar.error{background:#d4a72c}\
+details{margin:.3em 0;border:1px solid #ddd;padding:.3em .6em}\
+pre{background:#f6f8fa;padding:.5em;overflow:auto}\
+table{border-collapse:collapse}td,th{padding:.2em .8em;text-align:left}";
+
+/// Renders the report as a self-contained HTML page
+pub fn render_html(report: &ExecutionReport, options: &HtmlOptions) -> String {
+  let mut html = String::new();
+  let _ = writeln!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>",
+    escape(&options.title), STYLE);
+  let _ = writeln!(html, "<h1>{}</h1>", escape(&options.title));
+  let summary = &report.summary;
+  let _ = writeln!(html, "<p>{} in {}: {} workflow(s), {} passed, {} failed, {} error(s), {} step execution(s)</p>",
+    status_label(report.status), millis(report.duration), summary.workflows, summary.passed, summary.failed,
+    summary.errors, summary.steps);
+  for workflow in &report.workflows {
+    render_workflow(&mut html, workflow, options, 2);
+  }
+  html.push_str("</body>\n</html>\n");
+  html
+}
+
+fn render_workflow(html: &mut String, workflow: &WorkflowReport, options: &HtmlOptions, level: usize) {
+  let _ = writeln!(html, "<h{level}>Workflow {} &mdash; {} ({})</h{level}>", escape(&workflow.workflow_id),
+    status_label(workflow.status), millis(workflow.duration), level = level.min(6));
+  if let Some(error) = &workflow.error {
+    let _ = writeln!(html, "<p class=\"{}\">{}</p>", status_class(workflow.status), escape(error));
+  }
+  render_timeline(html, workflow);
+  for step in &workflow.steps {
+    render_step(html, step, options, level);
+  }
+  if !workflow.outputs.is_empty() {
+    html.push_str("<table>\n<tr><th>Output</th><th>Value</th></tr>\n");
+    for (name, value) in &workflow.outputs {
+      let _ = writeln!(html, "<tr><td>{}</td><td>{}</td></tr>", escape(name), escape(&value.to_json().to_string()));
+    }
+    html.push_str("</table>\n");
+  }
+}
+
+/// Renders a bar for each step execution, positioned by when it started relative to the workflow
+fn render_timeline(html: &mut String, workflow: &WorkflowReport) {
+  let total = workflow.steps.iter().map(|step| step.duration).sum::<Duration>()
+    .max(workflow.duration)
+    .as_secs_f64();
+  html.push_str("<div class=\"timeline\">");
+  let mut offset = Duration::ZERO;
+  for step in &workflow.steps {
+    let (left, width) = if total > 0.0 {
+      (offset.as_secs_f64() / total * 100.0, step.duration.as_secs_f64() / total * 100.0)
+    } else {
+      (0.0, 0.0)
+    };
+    let _ = write!(html, "<div class=\"bar {}\" style=\"left:{:.2}%;width:{:.2}%\" title=\"{} (attempt {}) {}\"></div>",
+      status_class(step.status), left, width, escape(&step.step_id), step.attempt, millis(step.duration));
+    offset += step.duration;
+  }
+  html.push_str("</div>\n");
+}
+
+fn render_step(html: &mut String, step: &StepReport, options: &HtmlOptions, level: usize) {
+  let open = if step.status == ExecutionStatus::Success { "" } else { " open" };
+  let _ = writeln!(html, "<details{}>\n<summary><span class=\"{}\">{}</span> {}{} ({})</summary>", open,
+    status_class(step.status), status_label(step.status), escape(&step.step_id),
+    if step.attempt > 1 { format!(" &mdash; attempt {}", step.attempt) } else { String::new() },
+    millis(step.duration));
+  if let Some(error) = &step.error {
+    let _ = writeln!(html, "<p class=\"error\">{}</p>", escape(error));
+  }
+  if let Some(action) = &step.action {
+    let _ = writeln!(html, "<p>Action: {}</p>", escape(action));
+  }
+
+  if let Some(request) = &step.request {
+    let mut text = format!("{} {}\n", request.method, redact_url(&request.url, options));
+    for (name, value) in &request.headers {
+      let _ = writeln!(text, "{}: {}", name, redact_header(name, value, options));
+    }
+    if let Some(body) = &request.body {
+      let _ = write!(text, "\n{}", redact_body(body, options));
+    }
+    let _ = writeln!(html, "<h4>Request</h4>\n<pre>{}</pre>", escape(&text));
+  }
+  if let Some(response) = &step.response {
+    let mut text = format!("{}\n", response.status);
+    for (name, value) in &response.headers {
+      let _ = writeln!(text, "{}: {}", name, redact_header(name, value, options));
+    }
+    if !response.body.is_empty() {
+      let _ = write!(text, "\n{}", redact_body(&response.body, options));
+    }
+    let _ = writeln!(html, "<h4>Response</h4>\n<pre>{}</pre>", escape(&text));
+  }
+
+  if !step.criteria.is_empty() {
+    html.push_str("<table>\n<tr><th>Criterion</th><th>Result</th></tr>\n");
+    for criterion in &step.criteria {
+      let result = match (&criterion.error, criterion.passed) {
+        (Some(error), _) => format!("<span class=\"error\">error: {}</span>", escape(error)),
+        (None, true) => "<span class=\"success\">passed</span>".to_string(),
+        (None, false) => "<span class=\"failure\">failed</span>".to_string()
+      };
+      let _ = writeln!(html, "<tr><td><code>{}</code></td><td>{}</td></tr>", escape(&criterion.condition), result);
+    }
+    html.push_str("</table>\n");
+  }
+  if let Some(workflow) = &step.workflow {
+    render_workflow(html, workflow, options, level + 1);
+  }
+  html.push_str("</details>\n");
+}
+
+fn redact_header(name: &str, value: &str, options: &HtmlOptions) -> String {
+  if options.is_secret_header(name) {
+    REDACTED.to_string()
+  } else {
+    value.to_string()
+  }
+}
+
+fn redact_url(url: &str, options: &HtmlOptions) -> String {
+  match url.split_once('?') {
+    Some((base, query)) => {
+      let query = query.split('&')
+        .map(|pair| match pair.split_once('=') {
+          Some((name, _)) if options.is_secret_field(name) => format!("{}={}", name, REDACTED),
+          _ => pair.to_string()
+        })
+        .collect::<Vec<_>>();
+      format!("{}?{}", base, query.join("&"))
+    }
+    None => url.to_string()
+  }
+}
+
+/// Redacts the secret fields of JSON bodies. Other bodies are written as text.
+fn redact_body(body: &Bytes, options: &HtmlOptions) -> String {
+  match serde_json::from_slice::<Value>(body) {
+    Ok(mut json) => {
+      redact_json(&mut json, options);
+      serde_json::to_string_pretty(&json).unwrap_or_default()
+    }
+    Err(_) => String::from_utf8_lossy(body).to_string()
+  }
+}
+
+fn redact_json(json: &mut Value, options: &HtmlOptions) {
+  match json {
+    Value::Object(map) => for (key, value) in map.iter_mut() {
+      if options.is_secret_field(key) && !value.is_object() && !value.is_array() {
+        *value = Value::String(REDACTED.to_string());
+      } else {
+        redact_json(value, options);
+      }
+    },
+    Value::Array(array) => for value in array {
+      redact_json(value, options);
+    },
+    _ => {}
+  }
+}
+
+fn status_class(status: ExecutionStatus) -> &'static str {
+  match status {
+    ExecutionStatus::Success => "success",
+    ExecutionStatus::Failure => "failure",
+    ExecutionStatus::Error => "error",
+    ExecutionStatus::Timeout => "timeout"
+  }
+}
+
+fn status_label(status: ExecutionStatus) -> String {
+  format!("<span class=\"{}\">{:?}</span>", status_class(status), status)
+}
+
+fn millis(duration: Duration) -> String {
+  format!("{} ms", duration.as_millis())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use bytes::Bytes;
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::execution_context::{StepRequest, StepResponse};
+  use crate::executor::{CriterionResult, ExecutionStatus, StepResult, WorkflowResult};
+  use crate::html_report::{render_html, HtmlOptions};
+  use crate::report::ExecutionReport;
+
+  fn report() -> ExecutionReport {
+    let step = StepResult {
+      step_id: "login".to_string(),
+      status: ExecutionStatus::Failure,
+      request: Some(StepRequest {
+        method: "POST".to_string(),
+        url: "http://localhost/login?user=tom&api_key=abc123".to_string(),
+        headers: vec![
+          ("Authorization".to_string(), "Bearer abc123".to_string()),
+          ("Content-Type".to_string(), "application/json".to_string())
+        ],
+        body: Some(Bytes::from(json!({ "user": "tom", "password": "hunter2" }).to_string()))
+      }),
+      response: Some(StepResponse::new(401).with_body("<b>Unauthorised</b>")),
+      criteria: vec![CriterionResult { condition: "$statusCode == 200".to_string(), passed: false, error: None }],
+      outputs: Default::default(),
+      workflow: None,
+      action: Some("again".to_string()),
+      attempt: 1,
+      duration: Duration::from_millis(30),
+      error: None
+    };
+    ExecutionReport::new(&[WorkflowResult {
+      workflow_id: "login".to_string(),
+      status: ExecutionStatus::Success,
+      steps: vec![
+        step.clone(),
+        StepResult {
+          status: ExecutionStatus::Success,
+          response: Some(StepResponse::new(200).with_json(&json!({ "access_token": "xyz", "expires": 60 }))),
+          criteria: vec![CriterionResult { condition: "$statusCode == 200".to_string(), passed: true, error: None }],
+          action: None,
+          attempt: 2,
+          duration: Duration::from_millis(10),
+          .. step
+        }
+      ],
+      outputs: Default::default(),
+      duration: Duration::from_millis(40),
+      error: None,
+      captures: Default::default()
+    }])
+  }
+
+  #[test]
+  fn renders_the_report_with_secrets_redacted() {
+    let html = render_html(&report(), &HtmlOptions::default());
+    expect!(html.starts_with("<!DOCTYPE html>")).to(be_true());
+    expect!(html.contains("abc123")).to(be_false());
+    expect!(html.contains("hunter2")).to(be_false());
+    expect!(html.contains("xyz")).to(be_false());
+    expect!(html.contains("POST http://localhost/login?user=tom&amp;api_key=[REDACTED]")).to(be_true());
+    expect!(html.contains("Authorization: [REDACTED]")).to(be_true());
+    expect!(html.contains("&quot;expires&quot;: 60")).to(be_true());
+    expect!(html.contains("&lt;b&gt;Unauthorised&lt;/b&gt;")).to(be_true());
+    expect!(html.contains("login &mdash; attempt 2")).to(be_true());
+    expect!(html.contains("style=\"left:0.00%;width:75.00%\"")).to(be_true());
+    expect!(html.contains("style=\"left:75.00%;width:25.00%\"")).to(be_true());
+    expect!(html.contains("<span class=\"failure\">failed</span>")).to(be_true());
+  }
+}
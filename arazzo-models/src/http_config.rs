@@ -0,0 +1,588 @@
+//! Configuration of the HTTP client, for fetching source descriptions and executing steps against
+//! internal environments (proxies, CA bundles, client certificates, TLS versions and response size
+//! limits).
+//!
+//! With the `http` feature (enabled by the `execute` feature), requests are sent with a blocking
+//! [reqwest](https://docs.rs/reqwest) client using rustls, so both `http:` and `https:` URLs are
+//! supported. The client is built from the configuration, and the size of each response body is
+//! limited (see [`HttpClientConfig::max_response_size`]).
+
+use std::env;
+#[cfg(feature = "http")] use std::fs;
+#[cfg(feature = "http")] use std::io::Read;
+use std::path::PathBuf;
+#[cfg(feature = "http")] use std::time::Duration;
+
+use anyhow::anyhow;
+#[cfg(feature = "http")] use bytes::Bytes;
+
+/// HTTP proxy that requests are sent through
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProxyConfig {
+  /// URL of the proxy (i.e. `http://proxy.internal:3128`)
+  pub url: String,
+  /// User name to authenticate with the proxy (with basic authentication)
+  pub username: Option<String>,
+  /// Password to authenticate with the proxy
+  pub password: Option<String>,
+  /// Hosts that are not accessed through the proxy. Entries match the host exactly, or any
+  /// sub-domain if they start with a `.` (i.e. `.internal`). `*` matches all hosts.
+  pub no_proxy: Vec<String>
+}
+
+impl ProxyConfig {
+  /// Creates the configuration for the proxy URL
+  pub fn new<S: Into<String>>(url: S) -> Self {
+    ProxyConfig { url: url.into(), .. ProxyConfig::default() }
+  }
+
+  /// Returns the proxy configured with the `HTTP_PROXY` and `NO_PROXY` environment variables (or
+  /// their lowercase forms), if there is one
+  pub fn from_env() -> Option<Self> {
+    let url = env_var("HTTP_PROXY").filter(|url| !url.is_empty())?;
+    let no_proxy = env_var("NO_PROXY")
+      .map(|hosts| hosts.split(',')
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .collect())
+      .unwrap_or_default();
+    Some(ProxyConfig { url, no_proxy, .. ProxyConfig::default() })
+  }
+
+  /// Sets the credentials to authenticate with the proxy
+  pub fn with_credentials<U: Into<String>, P: Into<String>>(mut self, username: U, password: P) -> Self {
+    self.username = Some(username.into());
+    self.password = Some(password.into());
+    self
+  }
+
+  /// If requests to the host are sent through the proxy
+  pub fn applies_to(&self, host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    !self.no_proxy.iter().any(|entry| {
+      let entry = entry.to_ascii_lowercase();
+      entry == "*" || entry == host || (entry.starts_with('.') && host.ends_with(&entry)) ||
+        host.strip_suffix(&entry).is_some_and(|prefix| prefix.ends_with('.'))
+    })
+  }
+}
+
+fn env_var(name: &str) -> Option<String> {
+  env::var(name).or_else(|_| env::var(name.to_ascii_lowercase())).ok()
+}
+
+/// TLS protocol version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TlsVersion {
+  /// TLS 1.2
+  Tls1_2,
+  /// TLS 1.3
+  Tls1_3
+}
+
+#[cfg(feature = "http")]
+impl From<TlsVersion> for reqwest::tls::Version {
+  fn from(version: TlsVersion) -> Self {
+    match version {
+      TlsVersion::Tls1_2 => reqwest::tls::Version::TLS_1_2,
+      TlsVersion::Tls1_3 => reqwest::tls::Version::TLS_1_3
+    }
+  }
+}
+
+/// TLS settings for `https:` URLs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TlsConfig {
+  /// PEM file with the CA certificates to trust (in addition to the system certificates)
+  pub ca_bundle: Option<PathBuf>,
+  /// PEM file with the client certificate (for mutual TLS)
+  pub client_certificate: Option<PathBuf>,
+  /// PEM file with the private key of the client certificate
+  pub client_key: Option<PathBuf>,
+  /// Minimum TLS version to accept
+  pub min_version: Option<TlsVersion>,
+  /// Maximum TLS version to accept
+  pub max_version: Option<TlsVersion>
+}
+
+impl TlsConfig {
+  /// Checks that the files exist, the client certificate and key are both set, and the version
+  /// range is not empty
+  pub fn validate(&self) -> anyhow::Result<()> {
+    let files = [
+      ("CA bundle", &self.ca_bundle),
+      ("client certificate", &self.client_certificate),
+      ("client key", &self.client_key)
+    ];
+    for (name, path) in files {
+      if let Some(path) = path && !path.is_file() {
+        return Err(anyhow!("The {} '{}' does not exist", name, path.display()));
+      }
+    }
+    if self.client_certificate.is_some() != self.client_key.is_some() {
+      return Err(anyhow!("Both the client certificate and client key must be set for mutual TLS"));
+    }
+    if let (Some(min), Some(max)) = (self.min_version, self.max_version) && min > max {
+      return Err(anyhow!("The minimum TLS version ({:?}) is greater than the maximum version ({:?})", min, max));
+    }
+    Ok(())
+  }
+
+  /// Applies the settings to the client builder, loading the CA bundle and client certificate
+  #[cfg(feature = "http")]
+  fn apply(&self, mut builder: reqwest::blocking::ClientBuilder) -> anyhow::Result<reqwest::blocking::ClientBuilder> {
+    if let Some(path) = &self.ca_bundle {
+      let certificates = fs::read(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|pem| reqwest::Certificate::from_pem_bundle(&pem).map_err(anyhow::Error::from))
+        .map_err(|err| anyhow!("Failed to load the CA bundle '{}': {}", path.display(), err))?;
+      if certificates.is_empty() {
+        return Err(anyhow!("The CA bundle '{}' does not contain any PEM certificates", path.display()));
+      }
+      for certificate in certificates {
+        builder = builder.add_root_certificate(certificate);
+      }
+    }
+    if let (Some(certificate), Some(key)) = (&self.client_certificate, &self.client_key) {
+      let mut pem = fs::read(certificate)
+        .map_err(|err| anyhow!("Failed to load the client certificate '{}': {}", certificate.display(), err))?;
+      pem.push(b'\n');
+      pem.extend(fs::read(key)
+        .map_err(|err| anyhow!("Failed to load the client key '{}': {}", key.display(), err))?);
+      let identity = reqwest::Identity::from_pem(&pem)
+        .map_err(|err| anyhow!("The client certificate '{}' or key '{}' is not valid: {}",
+          certificate.display(), key.display(), err))?;
+      builder = builder.identity(identity);
+    }
+    if let Some(version) = self.min_version {
+      builder = builder.min_tls_version(version.into());
+    }
+    if let Some(version) = self.max_version {
+      builder = builder.max_tls_version(version.into());
+    }
+    Ok(builder)
+  }
+}
+
+/// Default maximum size of a response body (64 MiB)
+pub const DEFAULT_MAX_RESPONSE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Configuration of the HTTP client
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HttpClientConfig {
+  /// Proxy to send requests through
+  pub proxy: Option<ProxyConfig>,
+  /// Maximum size of a response body. Responses with larger bodies fail. Defaults to
+  /// [`DEFAULT_MAX_RESPONSE_SIZE`].
+  pub max_response_size: Option<usize>,
+  /// TLS settings
+  pub tls: TlsConfig
+}
+
+impl HttpClientConfig {
+  /// Returns the configuration with the proxy from the environment variables (see
+  /// [`ProxyConfig::from_env`])
+  pub fn from_env() -> Self {
+    HttpClientConfig { proxy: ProxyConfig::from_env(), .. HttpClientConfig::default() }
+  }
+
+  /// Validates the configuration
+  pub fn validate(&self) -> anyhow::Result<()> {
+    let Some(proxy) = &self.proxy else {
+      return self.tls.validate();
+    };
+    if !proxy.url.starts_with("http://") && !proxy.url.starts_with("https://") {
+      return Err(anyhow!("The proxy URL is not valid: '{}' is not a HTTP URL", proxy.url));
+    }
+    #[cfg(feature = "http")]
+    reqwest::Url::parse(&proxy.url)
+      .map_err(|err| anyhow!("The proxy URL is not valid: {}", err))?;
+    self.tls.validate()
+  }
+
+  /// Builds a blocking HTTP client with the configuration. Proxies from the environment are only
+  /// used if they are set in the configuration (see [`HttpClientConfig::from_env`]).
+  #[cfg(feature = "http")]
+  pub(crate) fn client(&self) -> anyhow::Result<reqwest::blocking::Client> {
+    self.validate()?;
+    let mut builder = self.tls.apply(reqwest::blocking::Client::builder().no_proxy())?;
+    if let Some(proxy) = &self.proxy {
+      let config = proxy.clone();
+      let proxy_url = reqwest::Url::parse(&proxy.url)?;
+      let mut custom = reqwest::Proxy::custom(move |url| {
+        url.host_str()
+          .filter(|host| config.applies_to(host.trim_start_matches('[').trim_end_matches(']')))
+          .map(|_| proxy_url.clone())
+      });
+      if let Some(username) = &proxy.username {
+        custom = custom.basic_auth(username, proxy.password.as_deref().unwrap_or_default());
+      }
+      builder = builder.proxy(custom);
+    }
+    builder.build().map_err(|err| anyhow!("Failed to create the HTTP client: {}", err))
+  }
+
+  /// Maximum size of a response body
+  pub fn max_response_size(&self) -> usize {
+    self.max_response_size.unwrap_or(DEFAULT_MAX_RESPONSE_SIZE)
+  }
+
+  /// Returns the proxy to use for requests to the host, if there is one
+  pub fn proxy_for(&self, host: &str) -> Option<&ProxyConfig> {
+    self.proxy.as_ref().filter(|proxy| proxy.applies_to(host))
+  }
+}
+
+/// HTTP response
+#[cfg(feature = "http")]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct HttpResponse {
+  pub status: u16,
+  pub headers: Vec<(String, String)>,
+  pub body: Bytes
+}
+
+/// Sends the request with the client, returning the response. The request fails if it does not
+/// complete within the timeout, or if the response body is larger than the maximum size.
+#[cfg(feature = "http")]
+pub(crate) fn send(
+  client: &reqwest::blocking::Client,
+  method: &str,
+  url: &str,
+  headers: &[(String, String)],
+  body: Option<&[u8]>,
+  timeout: Duration,
+  max_size: usize
+) -> anyhow::Result<HttpResponse> {
+  let method = reqwest::Method::from_bytes(method.as_bytes())
+    .map_err(|_| anyhow!("'{}' is not a valid HTTP method", method.escape_debug()))?;
+  let mut request = client.request(method, url).timeout(timeout);
+  for (name, value) in headers {
+    let name = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+      .map_err(|_| anyhow!("'{}' is not a valid HTTP header name", name.escape_debug()))?;
+    let value = reqwest::header::HeaderValue::from_str(value)
+      .map_err(|_| anyhow!("The value of the '{}' header is not a valid HTTP header value", name))?;
+    request = request.header(name, value);
+  }
+  if let Some(body) = body {
+    request = request.body(body.to_vec());
+  }
+
+  let mut response = request.send()
+    .map_err(|err| anyhow!("Request to '{}' failed: {}", url, error_message(&err)))?;
+  let status = response.status().as_u16();
+  let headers = response.headers().iter()
+    .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).to_string()))
+    .collect();
+  if response.content_length().is_some_and(|length| length > max_size as u64) {
+    return Err(body_size_error(url, max_size));
+  }
+  let mut body = vec![];
+  response.by_ref().take(max_size as u64 + 1).read_to_end(&mut body)
+    .map_err(|err| anyhow!("Failed to read the response from '{}': {}", url, err))?;
+  if body.len() > max_size {
+    return Err(body_size_error(url, max_size));
+  }
+  Ok(HttpResponse { status, headers, body: Bytes::from(body) })
+}
+
+#[cfg(feature = "http")]
+fn body_size_error(url: &str, max_size: usize) -> anyhow::Error {
+  anyhow!("The HTTP response body from '{}' is larger than the maximum size of {} bytes", url, max_size)
+}
+
+/// Message of a request error, including the errors it was caused by
+#[cfg(feature = "http")]
+fn error_message(err: &reqwest::Error) -> String {
+  let mut message = err.to_string();
+  let mut source = std::error::Error::source(err);
+  while let Some(err) = source {
+    message.push_str(&format!(": {}", err));
+    source = err.source();
+  }
+  message
+}
+
+/// Fetches the resource at the URL with a GET request and the client configuration, returning the
+/// response body. Non-2xx responses are returned as errors.
+#[cfg(feature = "http")]
+pub(crate) fn get(url: &str, config: &HttpClientConfig) -> anyhow::Result<Bytes> {
+  let headers = [("Accept".to_string(), "*/*".to_string())];
+  let response = send(&config.client()?, "GET", url, &headers, None, Duration::from_secs(30),
+    config.max_response_size())?;
+  if (200..300).contains(&response.status) {
+    Ok(response.body)
+  } else {
+    Err(anyhow!("Request to '{}' failed with status {}", url, response.status))
+  }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  #[cfg(feature = "http")] use std::io::{Read, Write};
+  #[cfg(feature = "http")] use std::net::TcpListener;
+  use std::path::PathBuf;
+  #[cfg(feature = "http")] use std::sync::{Arc, mpsc};
+  #[cfg(feature = "http")] use std::thread;
+  #[cfg(feature = "http")] use std::time::{Duration, Instant};
+
+  use expectest::prelude::*;
+
+  #[cfg(feature = "http")] use crate::http_config::{get, send};
+  use crate::http_config::{HttpClientConfig, ProxyConfig, TlsConfig, TlsVersion};
+
+  /// Reads the head of the request from the stream
+  #[cfg(feature = "http")]
+  fn read_request<R: Read>(stream: &mut R) -> String {
+    let mut request = vec![];
+    let mut buffer = [0; 1024];
+    while let Ok(read) = stream.read(&mut buffer) {
+      request.extend_from_slice(&buffer[..read]);
+      if read == 0 || request.windows(4).any(|window| window == b"\r\n\r\n") {
+        break;
+      }
+    }
+    String::from_utf8_lossy(&request).to_string()
+  }
+
+  /// Starts a server on a random port that returns the response to a single request
+  #[cfg(feature = "http")]
+  pub(crate) fn serve_once(response: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+      if let Ok((mut stream, _)) = listener.accept() {
+        read_request(&mut stream);
+        let _ = stream.write_all(response.as_bytes());
+      }
+    });
+    format!("http://{}", address)
+  }
+
+  /// Starts a TLS server with a self-signed certificate for `localhost` on a random port that
+  /// returns the response to a single request. Returns the URL of the server, and the path to a CA
+  /// bundle with the certificate.
+  #[cfg(feature = "http")]
+  pub(crate) fn serve_tls_once(response: &'static str) -> (String, PathBuf) {
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let key = rustls::pki_types::PrivateKeyDer::Pkcs8(certified.signing_key.serialize_der().into());
+    let config = rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+      .with_safe_default_protocol_versions().unwrap()
+      .with_no_client_auth()
+      .with_single_cert(vec![certified.cert.der().clone()], key).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let ca_bundle = std::env::temp_dir().join(format!("arazzo-ca-{}-{}.pem", std::process::id(), address.port()));
+    std::fs::write(&ca_bundle, certified.cert.pem()).unwrap();
+    thread::spawn(move || {
+      if let Ok((stream, _)) = listener.accept() {
+        let connection = rustls::ServerConnection::new(Arc::new(config)).unwrap();
+        let mut stream = rustls::StreamOwned::new(connection, stream);
+        read_request(&mut stream);
+        let _ = stream.write_all(response.as_bytes());
+        stream.conn.send_close_notify();
+        let _ = stream.flush();
+      }
+    });
+    (format!("https://localhost:{}", address.port()), ca_bundle)
+  }
+
+  #[test]
+  fn proxy_applies_to_hosts_not_excluded() {
+    let proxy = ProxyConfig {
+      no_proxy: vec!["localhost".to_string(), ".internal".to_string(), "example.com".to_string()],
+      .. ProxyConfig::new("http://proxy:3128")
+    };
+    expect!(proxy.applies_to("api.test")).to(be_true());
+    expect!(proxy.applies_to("LOCALHOST")).to(be_false());
+    expect!(proxy.applies_to("pets.internal")).to(be_false());
+    expect!(proxy.applies_to("api.example.com")).to(be_false());
+    expect!(proxy.applies_to("notexample.com")).to(be_true());
+
+    let config = HttpClientConfig { proxy: Some(proxy), .. HttpClientConfig::default() };
+    expect!(config.proxy_for("api.test")).to(be_some());
+    expect!(config.proxy_for("localhost")).to(be_none());
+    expect!(config.validate()).to(be_ok());
+  }
+
+  #[test]
+  fn validates_the_tls_settings() {
+    expect!(TlsConfig::default().validate()).to(be_ok());
+    expect!(TlsConfig { ca_bundle: Some(PathBuf::from("/does/not/exist.pem")), .. TlsConfig::default() }.validate())
+      .to(be_err());
+    expect!(TlsConfig { client_certificate: Some(PathBuf::from("Cargo.toml")), .. TlsConfig::default() }.validate())
+      .to(be_err());
+    expect!(TlsConfig {
+      min_version: Some(TlsVersion::Tls1_3),
+      max_version: Some(TlsVersion::Tls1_2),
+      .. TlsConfig::default()
+    }.validate()).to(be_err());
+    expect!(HttpClientConfig {
+      tls: TlsConfig { ca_bundle: Some(PathBuf::from("/does/not/exist.pem")), .. TlsConfig::default() },
+      .. HttpClientConfig::default()
+    }.validate()).to(be_err());
+  }
+
+  #[test]
+  #[cfg(feature = "http")]
+  fn applies_the_tls_settings_to_the_client() {
+    let config = HttpClientConfig {
+      tls: TlsConfig { min_version: Some(TlsVersion::Tls1_3), .. TlsConfig::default() },
+      .. HttpClientConfig::default()
+    };
+    expect!(config.client()).to(be_ok());
+
+    let config = HttpClientConfig {
+      tls: TlsConfig { ca_bundle: Some(PathBuf::from("Cargo.toml")), .. TlsConfig::default() },
+      .. HttpClientConfig::default()
+    };
+    expect!(config.client().unwrap_err().to_string()).to(be_equal_to(
+      "The CA bundle 'Cargo.toml' does not contain any PEM certificates"));
+
+    let config = HttpClientConfig {
+      tls: TlsConfig {
+        client_certificate: Some(PathBuf::from("Cargo.toml")),
+        client_key: Some(PathBuf::from("Cargo.toml")),
+        .. TlsConfig::default()
+      },
+      .. HttpClientConfig::default()
+    };
+    expect!(config.client()).to(be_err());
+  }
+
+  #[test]
+  #[cfg(feature = "http")]
+  fn sends_requests_over_tls() {
+    let (url, ca_bundle) = serve_tls_once("HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello");
+    let config = HttpClientConfig {
+      tls: TlsConfig { ca_bundle: Some(ca_bundle.clone()), .. TlsConfig::default() },
+      .. HttpClientConfig::default()
+    };
+    expect!(get(&url, &config).unwrap()).to(be_equal_to(bytes::Bytes::from_static(b"hello")));
+
+    // The self-signed certificate is not trusted without the CA bundle
+    let (url, other_ca_bundle) = serve_tls_once("HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello");
+    expect!(get(&url, &HttpClientConfig::default())).to(be_err());
+    std::fs::remove_file(ca_bundle).unwrap();
+    std::fs::remove_file(other_ca_bundle).unwrap();
+  }
+
+  #[test]
+  fn validates_the_proxy_url() {
+    expect!(HttpClientConfig { proxy: Some(ProxyConfig::new("socks5://proxy")), .. HttpClientConfig::default() }.validate())
+      .to(be_err());
+  }
+
+  #[test]
+  #[cfg(feature = "http")]
+  fn get_request() {
+    let config = HttpClientConfig::default();
+    let url = serve_once("HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello");
+    expect!(get(&url, &config).unwrap()).to(be_equal_to(bytes::Bytes::from_static(b"hello")));
+
+    let url = serve_once("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n");
+    expect!(get(&url, &config).unwrap()).to(be_equal_to(bytes::Bytes::from_static(b"abcde")));
+
+    let url = serve_once("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+    expect!(get(&url, &config).unwrap_err().to_string()).to(be_equal_to(format!("Request to '{}' failed with status 404", url)));
+
+    expect!(get("file:///tmp", &config)).to(be_err());
+  }
+
+  #[test]
+  #[cfg(feature = "http")]
+  fn send_request() {
+    let url = serve_once("HTTP/1.1 201 Created\r\nLocation: /pets/1\r\nContent-Length: 2\r\n\r\n{}");
+    let headers = [("Content-Type".to_string(), "application/json".to_string())];
+    let config = HttpClientConfig::default();
+    let response = send(&config.client().unwrap(), "POST", &format!("{}/pets", url), &headers, Some(b"{\"id\":1}"),
+      Duration::from_secs(5), config.max_response_size()).unwrap();
+    expect!(response.status).to(be_equal_to(201));
+    expect!(response.headers).to(be_equal_to(vec![
+      ("location".to_string(), "/pets/1".to_string()),
+      ("content-length".to_string(), "2".to_string())
+    ]));
+    expect!(response.body).to(be_equal_to(bytes::Bytes::from_static(b"{}")));
+  }
+
+  #[test]
+  #[cfg(feature = "http")]
+  fn rejects_headers_with_line_breaks() {
+    let url = serve_once("HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+    let client = HttpClientConfig::default().client().unwrap();
+    let headers = [("X-Token".to_string(), "abc\r\nX-Injected: true".to_string())];
+    let result = send(&client, "GET", &url, &headers, None, Duration::from_secs(5), 1024);
+    expect!(result.unwrap_err().to_string())
+      .to(be_equal_to("The value of the 'x-token' header is not a valid HTTP header value"));
+
+    let headers = [("X-Token\r\nX-Injected".to_string(), "abc".to_string())];
+    expect!(send(&client, "GET", &url, &headers, None, Duration::from_secs(5), 1024)).to(be_err());
+  }
+
+  #[test]
+  #[cfg(feature = "http")]
+  fn limits_the_size_of_the_response_body() {
+    let config = HttpClientConfig { max_response_size: Some(4), .. HttpClientConfig::default() };
+    let url = serve_once("HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello");
+    expect!(get(&url, &config).unwrap_err().to_string()).to(be_equal_to(
+      format!("The HTTP response body from '{}' is larger than the maximum size of 4 bytes", url)));
+
+    let url = serve_once("HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n3\r\ndef\r\n0\r\n\r\n");
+    expect!(get(&url, &config)).to(be_err());
+
+    let url = serve_once("HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nabcd");
+    expect!(get(&url, &config).unwrap()).to(be_equal_to(bytes::Bytes::from_static(b"abcd")));
+  }
+
+  #[test]
+  #[cfg(feature = "http")]
+  fn the_timeout_applies_to_the_whole_request() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+      if let Ok((mut stream, _)) = listener.accept() {
+        let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n");
+        // Sends a chunk every 100ms, so no single read times out
+        for _ in 0..50 {
+          thread::sleep(Duration::from_millis(100));
+          if stream.write_all(b"1\r\na\r\n").is_err() {
+            break;
+          }
+        }
+      }
+    });
+
+    let start = Instant::now();
+    let client = HttpClientConfig::default().client().unwrap();
+    let result = send(&client, "GET", &format!("http://{}", address), &[], None, Duration::from_millis(500), 1024);
+    expect!(result).to(be_err());
+    expect!(start.elapsed() < Duration::from_secs(2)).to(be_true());
+  }
+
+  #[test]
+  #[cfg(feature = "http")]
+  fn sends_requests_through_the_proxy() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+      if let Ok((mut stream, _)) = listener.accept() {
+        let request = read_request(&mut stream);
+        let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+        let _ = sender.send(request);
+      }
+    });
+    let config = HttpClientConfig {
+      proxy: Some(ProxyConfig::new(format!("http://{}", address)).with_credentials("user", "pass")),
+      .. HttpClientConfig::default()
+    };
+
+    expect!(get("http://pets.internal:8080/pets?limit=1", &config).unwrap())
+      .to(be_equal_to(bytes::Bytes::from_static(b"ok")));
+    let request = receiver.recv().unwrap().to_ascii_lowercase();
+    expect!(request.starts_with("get http://pets.internal:8080/pets?limit=1 http/1.1\r\n")).to(be_true());
+    expect!(request.contains("host: pets.internal:8080\r\n")).to(be_true());
+    expect!(request.contains("proxy-authorization: basic dxnlcjpwyxnz\r\n")).to(be_true());
+  }
+}
@@ -0,0 +1,440 @@
+//! Exports workflows as [Hurl](https://hurl.dev) files, so they can be run with the Hurl CLI
+//! without the executor. Each step is a request in the file, with the success criteria of the
+//! step as asserts and the outputs of the step as captures. Steps that execute another workflow
+//! of the description have the requests of that workflow inlined.
+//!
+//! Runtime expressions are converted to Hurl variables: inputs are the name of the input (i.e.
+//! `$inputs.petId` is `{{petId}}`), step outputs are the step ID and output name (i.e.
+//! `$steps.find.outputs.id` is `{{find_id}}`) and the base URL of each source description is
+//! `{{<source name>_base_url}}`. The variables that need to be passed to Hurl (with `--variable`)
+//! are listed in a comment at the top of the file.
+//!
+//! The operations of the steps are taken from their operation paths. With the `execute` feature,
+//! the operations can also be resolved from the OpenAPI source descriptions (see
+//! [`hurl_with_operations`]). Criteria that can not be converted to asserts are added as comments.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::docs::step_parameters;
+use crate::either::Either;
+use crate::extensions::AnyValue;
+#[cfg(feature = "execute")] use crate::operations::OperationResolver;
+use crate::payloads::FormPayload;
+use crate::step_summary::{step_operation, StepOperation};
+use crate::v1_0::{ArazzoDescription, Criterion, RequestBody, Step, Workflow};
+
+/// Renders the workflow as a Hurl file, with the operations of the steps from their operation
+/// paths. Steps with an operation ID are added as comments, as the operation can not be resolved.
+pub fn hurl(description: &ArazzoDescription, workflow_id: &str) -> anyhow::Result<String> {
+  render(description, workflow_id, &|step| step_operation(description, step))
+}
+
+/// Renders the workflow as a Hurl file, resolving the operations of the steps from the OpenAPI
+/// source descriptions
+#[cfg(feature = "execute")]
+pub fn hurl_with_operations(
+  description: &ArazzoDescription,
+  workflow_id: &str,
+  resolver: &OperationResolver
+) -> anyhow::Result<String> {
+  render(description, workflow_id, &|step| {
+    resolver.resolve_step(step).ok()
+      .map(|operation| StepOperation {
+        source_name: operation.source_name,
+        method: operation.method,
+        path: operation.path
+      })
+      .or_else(|| step_operation(description, step))
+  })
+}
+
+/// Renders all the workflows as Hurl files, keyed by file name (`<workflow ID>.hurl`)
+pub fn hurl_files(description: &ArazzoDescription) -> anyhow::Result<BTreeMap<String, String>> {
+  description.workflows.iter()
+    .map(|workflow| Ok((format!("{}.hurl", workflow.workflow_id), hurl(description, &workflow.workflow_id)?)))
+    .collect()
+}
+
+fn render(
+  description: &ArazzoDescription,
+  workflow_id: &str,
+  resolve: &dyn Fn(&Step) -> Option<StepOperation>
+) -> anyhow::Result<String> {
+  let workflow = description.workflows.iter()
+    .find(|workflow| workflow.workflow_id == workflow_id)
+    .ok_or_else(|| anyhow!("No workflow with ID '{}' was found", workflow_id))?;
+
+  let mut entries = String::new();
+  let mut variables = vec![];
+  let mut stack = vec![workflow.workflow_id.clone()];
+  write_workflow(&mut entries, description, workflow, resolve, &mut variables, &mut stack)?;
+
+  let mut file = format!("# {} - {}\n", description.info.title, workflow.workflow_id);
+  if let Some(summary) = &workflow.summary {
+    let _ = writeln!(file, "# {}", summary);
+  }
+  if !variables.is_empty() {
+    let _ = writeln!(file, "#\n# Variables: {}", variables.join(", "));
+  }
+  file.push('\n');
+  file.push_str(entries.trim_end());
+  file.push('\n');
+  Ok(file)
+}
+
+fn write_workflow(
+  output: &mut String,
+  description: &ArazzoDescription,
+  workflow: &Workflow,
+  resolve: &dyn Fn(&Step) -> Option<StepOperation>,
+  variables: &mut Vec<String>,
+  stack: &mut Vec<String>
+) -> anyhow::Result<()> {
+  for step in &workflow.steps {
+    let parameters = step_parameters(description, workflow, step)?;
+    if let Some(workflow_id) = &step.workflow_id {
+      let _ = writeln!(output, "# Step '{}': workflow '{}'", step.step_id, workflow_id);
+      for (parameter, value) in &parameters {
+        let _ = writeln!(output, "# Input {} = {}", parameter.name, template(value, variables));
+      }
+      match description.workflows.iter().find(|workflow| workflow.workflow_id == *workflow_id) {
+        Some(target) if !stack.contains(&target.workflow_id) => {
+          output.push('\n');
+          stack.push(target.workflow_id.clone());
+          write_workflow(output, description, target, resolve, variables, stack)?;
+          stack.pop();
+        }
+        Some(_) => { let _ = writeln!(output, "# The workflow calls itself, and is not inlined again\n"); }
+        None => { let _ = writeln!(output, "# The workflow is not in this description, and is not inlined\n"); }
+      }
+      continue;
+    }
+
+    let Some(operation) = resolve(step) else {
+      let target = step.operation_id.as_ref().or(step.operation_path.as_ref()).cloned().unwrap_or_default();
+      let _ = writeln!(output, "# Step '{}' is skipped, as the operation '{}' could not be resolved\n", step.step_id, target);
+      continue;
+    };
+
+    let _ = writeln!(output, "# Step '{}'", step.step_id);
+    if let Some(description) = &step.description {
+      for line in description.lines() {
+        let _ = writeln!(output, "# {}", line);
+      }
+    }
+
+    let base_url = format!("{}_base_url", variable_name(&operation.source_name));
+    add_variable(variables, &base_url);
+    let mut path = operation.path.clone();
+    let mut headers = vec![];
+    let mut query = vec![];
+    let mut cookies = vec![];
+    for (parameter, value) in &parameters {
+      let value = template(value, variables);
+      match parameter.r#in.as_deref() {
+        Some("path") => path = path.replace(&format!("{{{}}}", parameter.name), &value),
+        Some("query") => query.push(format!("{}: {}", parameter.name, value)),
+        Some("header") => headers.push(format!("{}: {}", parameter.name, value)),
+        Some("cookie") => cookies.push(format!("{}: {}", parameter.name, value)),
+        _ => {}
+      }
+    }
+    let _ = writeln!(output, "{} {{{{{}}}}}{}", operation.method.to_uppercase(), base_url, path);
+
+    let body = step.request_body.as_ref();
+    if let Some(content_type) = body.and_then(|body| body.effective_content_type()) {
+      headers.push(format!("Content-Type: {}", content_type));
+    }
+    for header in headers {
+      let _ = writeln!(output, "{}", header);
+    }
+    section(output, "QueryStringParams", &query);
+    section(output, "Cookies", &cookies);
+    if let Some(body) = body {
+      write_body(output, body, variables);
+    }
+
+    let (status, asserts) = criteria(&step.success_criteria);
+    let _ = writeln!(output, "HTTP {}", status.unwrap_or_else(|| "*".to_string()));
+    let captures = step.outputs.iter()
+      .map(|(name, expression)| match query_for(expression) {
+        Some(query) => format!("{}: {}", variable_name(&format!("{}_{}", step.step_id, name)), query),
+        None => format!("# Output '{}' is not captured: {}", name, expression)
+      })
+      .collect::<Vec<_>>();
+    section(output, "Captures", &captures);
+    section(output, "Asserts", &asserts);
+    output.push('\n');
+  }
+  Ok(())
+}
+
+fn write_body(output: &mut String, body: &RequestBody, variables: &mut Vec<String>) {
+  if let Some(form) = body.payload_as::<FormPayload>() {
+    let fields = form.0.iter()
+      .map(|(name, value)| format!("{}: {}", name, template(&value.to_text().unwrap_or_default(), variables)))
+      .collect::<Vec<_>>();
+    section(output, "FormParams", &fields);
+    return;
+  }
+  let Some(payload) = &body.payload else {
+    return;
+  };
+  match payload.as_json() {
+    Some(mut json) => {
+      for replacement in &body.replacements {
+        let value = match &replacement.value {
+          Either::First(AnyValue::String(value)) => Value::String(value.clone()),
+          Either::First(value) => value.to_json(),
+          Either::Second(expression) => Value::String(format!("{{{}}}", expression))
+        };
+        if let Some(target) = json.pointer_mut(&replacement.target) {
+          *target = value;
+        }
+      }
+      let text = serde_json::to_string_pretty(&json).unwrap_or_default();
+      let _ = writeln!(output, "{}", template(&text, variables));
+    }
+    None => {
+      let _ = writeln!(output, "```\n{}\n```", template(payload.as_string().trim_end(), variables));
+    }
+  }
+}
+
+fn section(output: &mut String, name: &str, lines: &[String]) {
+  if !lines.is_empty() {
+    let _ = writeln!(output, "[{}]", name);
+    for line in lines {
+      let _ = writeln!(output, "{}", line);
+    }
+  }
+}
+
+/// Status code for the status line and asserts for the success criteria
+fn criteria(criteria: &[Criterion]) -> (Option<String>, Vec<String>) {
+  let mut status = None;
+  let mut asserts = vec![];
+  for criterion in criteria {
+    let criterion_type = match &criterion.r#type {
+      Some(Either::First(criterion_type)) => criterion_type.as_str(),
+      Some(Either::Second(expression_type)) => expression_type.r#type.as_str(),
+      None => "simple"
+    };
+    let converted = match (criterion_type, criterion.context.as_deref()) {
+      ("simple", None) => simple_asserts(&criterion.condition, &mut status),
+      ("regex", Some(context)) => query_for(context)
+        .map(|query| vec![format!("{} matches /{}/", query, criterion.condition.replace('/', "\\/"))]),
+      ("jsonpath", Some("$response.body")) => Some(vec![format!("jsonpath {} exists", hurl_string(&criterion.condition))]),
+      ("xpath", Some("$response.body")) => Some(vec![format!("xpath {} exists", hurl_string(&criterion.condition))]),
+      _ => None
+    };
+    match converted {
+      Some(converted) => asserts.extend(converted),
+      None => asserts.push(format!("# Not converted: {}", criterion.condition))
+    }
+  }
+  (status, asserts)
+}
+
+const OPERATORS: [&str; 6] = [">=", "<=", "==", "!=", ">", "<"];
+
+/// Asserts for a simple condition. Only conditions joined with `&&` are converted. The first
+/// status code check is returned for the status line.
+fn simple_asserts(condition: &str, status: &mut Option<String>) -> Option<Vec<String>> {
+  if condition.contains("||") {
+    return None;
+  }
+  let mut asserts = vec![];
+  for part in condition.split("&&").map(|part| part.trim()) {
+    let operator = part.char_indices()
+      .find_map(|(index, _)| OPERATORS.iter().find(|operator| part[index..].starts_with(**operator)).map(|operator| (index, *operator)));
+    match operator {
+      Some((index, operator)) => {
+        let expression = part[..index].trim();
+        let value = hurl_literal(part[index + operator.len()..].trim());
+        if expression == "$statusCode" && operator == "==" && status.is_none() {
+          *status = Some(value);
+        } else {
+          asserts.push(format!("{} {} {}", query_for(expression)?, operator, value));
+        }
+      }
+      None => asserts.push(format!("{} exists", query_for(part)?))
+    }
+  }
+  Some(asserts)
+}
+
+/// Hurl query for a runtime expression of the response
+fn query_for(expression: &str) -> Option<String> {
+  let expression = expression.trim();
+  match expression {
+    "$statusCode" => Some("status".to_string()),
+    "$url" => Some("url".to_string()),
+    "$response.body" => Some("body".to_string()),
+    _ => if let Some(pointer) = expression.strip_prefix("$response.body#") {
+      Some(format!("jsonpath {}", hurl_string(&json_path(pointer))))
+    } else {
+      expression.strip_prefix("$response.header.").map(|name| format!("header {}", hurl_string(name)))
+    }
+  }
+}
+
+/// Converts a JSON Pointer to a JSONPath
+pub(crate) fn json_path(pointer: &str) -> String {
+  let mut path = "$".to_string();
+  for segment in pointer.split('/').skip(1) {
+    let segment = segment.replace("~1", "/").replace("~0", "~");
+    if segment.parse::<usize>().is_ok() {
+      let _ = write!(path, "[{}]", segment);
+    } else if !segment.is_empty() && segment.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_') {
+      let _ = write!(path, ".{}", segment);
+    } else {
+      let _ = write!(path, "['{}']", segment.replace('\'', "\\'"));
+    }
+  }
+  path
+}
+
+/// Converts a literal of a simple condition (with single quoted strings) to a Hurl literal
+fn hurl_literal(literal: &str) -> String {
+  match literal.strip_prefix('\'').and_then(|literal| literal.strip_suffix('\'')) {
+    Some(text) => hurl_string(text),
+    None => literal.to_string()
+  }
+}
+
+fn hurl_string(text: &str) -> String {
+  format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Converts the runtime expression, or the expressions embedded in the text, to Hurl variables
+fn template(text: &str, variables: &mut Vec<String>) -> String {
+  if text.trim().starts_with('$') {
+    return format!("{{{{{}}}}}", expression_variable(text.trim(), variables));
+  }
+  let mut result = String::new();
+  let mut rest = text;
+  while let Some(start) = rest.find("{$") {
+    let Some(end) = rest[start..].find('}') else {
+      break;
+    };
+    result.push_str(&rest[..start]);
+    let _ = write!(result, "{{{{{}}}}}", expression_variable(&rest[start + 1..start + end], variables));
+    rest = &rest[start + end + 1..];
+  }
+  result.push_str(rest);
+  result
+}
+
+/// Name of the Hurl variable for a runtime expression. Inputs are added to the variables that
+/// need to be passed to Hurl.
+fn expression_variable(expression: &str, variables: &mut Vec<String>) -> String {
+  if let Some(input) = expression.strip_prefix("$inputs.") {
+    let name = variable_name(input);
+    add_variable(variables, &name);
+    name
+  } else if let Some((step, output)) = expression.strip_prefix("$steps.").and_then(|rest| rest.split_once(".outputs.")) {
+    variable_name(&format!("{}_{}", step, output))
+  } else {
+    let name = variable_name(expression.trim_start_matches('$'));
+    add_variable(variables, &name);
+    name
+  }
+}
+
+fn add_variable(variables: &mut Vec<String>, name: &str) {
+  if !variables.iter().any(|variable| variable == name) {
+    variables.push(name.to_string());
+  }
+}
+
+fn variable_name(name: &str) -> String {
+  name.chars().map(|ch| if ch.is_ascii_alphanumeric() || ch == '_' { ch } else { '_' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::arazzo;
+  use crate::hurl::{hurl, hurl_files, json_path};
+  #[cfg(feature = "execute")] use crate::hurl::hurl_with_operations;
+  #[cfg(feature = "execute")] use crate::operations::{OpenApiSource, OperationResolver};
+  use crate::v1_0::ArazzoDescription;
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      info("Pet Store", "");
+      workflow("adopt") {
+        step("find") {
+          operation_path: "{$sourceDescriptions.petstore.url}#/paths/~1pets~1{petId}/get";
+          parameter("petId", "path", "$inputs.petId");
+          parameter("Authorization", "header", "Bearer {$inputs.token}");
+          success_criterion: "$statusCode == 200 && $response.body#/status == 'available'";
+          typed_success_criterion("regex", "$response.header.Content-Type", "^application/json");
+          success_criterion: "$statusCode == 200 || $statusCode == 304";
+          output("id", "$response.body#/id");
+        }
+        step("adopt") {
+          operation_path: "{$sourceDescriptions.petstore.url}#/paths/~1adoptions/post";
+          json_body: json!({ "pet": "{$steps.find.outputs.id}" });
+        }
+        step("notify") { operation_id: "notify"; }
+      }
+    }
+  }
+
+  #[test]
+  fn renders_the_workflow_as_a_hurl_file() {
+    expect!(hurl(&description(), "adopt").unwrap()).to(be_equal_to(
+r#"# Pet Store - adopt
+#
+# Variables: petstore_base_url, petId, token
+
+# Step 'find'
+GET {{petstore_base_url}}/pets/{{petId}}
+Authorization: Bearer {{token}}
+HTTP 200
+[Captures]
+find_id: jsonpath "$.id"
+[Asserts]
+jsonpath "$.status" == "available"
+header "Content-Type" matches /^application\/json/
+# Not converted: $statusCode == 200 || $statusCode == 304
+
+# Step 'adopt'
+POST {{petstore_base_url}}/adoptions
+Content-Type: application/json
+{
+  "pet": "{{find_id}}"
+}
+HTTP *
+
+# Step 'notify' is skipped, as the operation 'notify' could not be resolved
+"#.to_string()));
+
+    expect!(hurl(&description(), "missing")).to(be_err());
+    expect!(hurl_files(&description()).unwrap().keys().cloned().collect::<Vec<_>>()).to(be_equal_to(vec!["adopt.hurl".to_string()]));
+  }
+
+  #[test]
+  #[cfg(feature = "execute")]
+  fn resolves_the_operations_from_the_source_descriptions() {
+    let resolver = OperationResolver::new(vec![OpenApiSource::new("notifications", "notifications.json", json!({
+      "paths": { "/notifications": { "post": { "operationId": "notify" } } }
+    }))]);
+    let file = hurl_with_operations(&description(), "adopt", &resolver).unwrap();
+    expect!(file.contains("# Step 'notify'\nPOST {{notifications_base_url}}/notifications\nHTTP *\n")).to(be_true());
+  }
+
+  #[test]
+  fn converts_json_pointers_to_json_paths() {
+    expect!(json_path("/items/0/the~1id")).to(be_equal_to("$.items[0]['the/id']"));
+    expect!(json_path("")).to(be_equal_to("$"));
+  }
+}
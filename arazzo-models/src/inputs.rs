@@ -0,0 +1,509 @@
+//! Loads the input values for workflows from layers of JSON (or YAML, with the `yaml` feature)
+//! files and inline overrides, and validates them against the `inputs` schema of the workflow.
+//!
+//! Layers are merged in the order they are added, so later layers take precedence. The usual
+//! order is a base file, then an environment-specific file, then inline overrides (i.e. from the
+//! command line). Objects are merged key by key (recursively), while all other values (including
+//! arrays) replace the earlier value. A `null` value removes the key.
+//!
+//! Inputs can also be supplied with environment variables (i.e. secrets in CI pipelines) with an
+//! [`EnvInputProvider`]. Values that arrive as strings (from environment variables or command line
+//! arguments) are converted to the types declared in the inputs schema before they are validated
+//! (see [`coerce_inputs`]).
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use serde_json::{Map, Value};
+
+use crate::extensions::AnyValue;
+use crate::json_schema::validate_json_schema;
+use crate::v1_0::ArazzoDescription;
+
+/// Layers of input values
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InputLayers {
+  layers: Vec<Value>
+}
+
+impl InputLayers {
+  /// Creates an empty set of layers
+  pub fn new() -> Self {
+    InputLayers::default()
+  }
+
+  /// Adds the values from a JSON or YAML file. The file must contain an object.
+  pub fn with_file<P: AsRef<Path>>(self, path: P) -> anyhow::Result<Self> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path)
+      .with_context(|| format!("Failed to load inputs from '{}'", path.display()))?;
+    let value = parse_inputs(&contents)
+      .with_context(|| format!("Failed to load inputs from '{}'", path.display()))?;
+    self.with_values(value)
+  }
+
+  /// Adds the values from a file if it exists (i.e. an optional environment-specific file)
+  pub fn with_optional_file<P: AsRef<Path>>(self, path: P) -> anyhow::Result<Self> {
+    if path.as_ref().exists() {
+      self.with_file(path)
+    } else {
+      Ok(self)
+    }
+  }
+
+  /// Adds an object of values
+  pub fn with_values(mut self, values: Value) -> anyhow::Result<Self> {
+    if !values.is_object() {
+      return Err(anyhow!("Inputs must be an object"));
+    }
+    self.layers.push(values);
+    Ok(self)
+  }
+
+  /// Adds an inline override of the form `name=value`. The name can be a dotted path to set a
+  /// nested value (i.e. `user.name=fred`). The value is parsed as JSON if it is valid JSON,
+  /// otherwise it is used as a string.
+  pub fn with_override(self, assignment: &str) -> anyhow::Result<Self> {
+    let (name, value) = assignment.split_once('=')
+      .ok_or_else(|| anyhow!("'{}' is not a valid input override (it must be of the form name=value)", assignment))?;
+    let name = name.trim();
+    if name.is_empty() || name.split('.').any(|part| part.is_empty()) {
+      return Err(anyhow!("'{}' is not a valid input name", name));
+    }
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+    let layer = name.rsplit('.').fold(value, |value, part| {
+      let mut object = Map::new();
+      object.insert(part.to_string(), value);
+      Value::Object(object)
+    });
+    self.with_values(layer)
+  }
+
+  /// Adds the values from the environment variables for the workflow (see [`EnvInputProvider`])
+  pub fn with_env_vars(
+    self,
+    provider: &EnvInputProvider,
+    description: &ArazzoDescription,
+    workflow_id: &str
+  ) -> anyhow::Result<Self> {
+    let values = provider.inputs(description, workflow_id)?;
+    self.with_values(values)
+  }
+
+  /// Merges the layers, returning the combined object of values
+  pub fn merge(&self) -> Value {
+    let mut merged = Value::Object(Map::new());
+    for layer in &self.layers {
+      merge_values(&mut merged, layer);
+    }
+    merged
+  }
+
+  /// Merges the layers, converts any string values to the types in the `inputs` schema of the
+  /// workflow, and validates the values against the schema
+  pub fn resolve(&self, description: &ArazzoDescription, workflow_id: &str) -> anyhow::Result<AnyValue> {
+    let inputs = coerce_inputs(description, workflow_id, &self.merge())?;
+    validate_workflow_inputs(description, workflow_id, &inputs)?;
+    AnyValue::try_from(&inputs)
+  }
+}
+
+fn parse_inputs(contents: &str) -> anyhow::Result<Value> {
+  match serde_json::from_str(contents) {
+    Ok(value) => Ok(value),
+    #[cfg(feature = "yaml")]
+    Err(_) => {
+      let yaml = crate::yaml::yaml_load_documents(contents)?;
+      match yaml.first() {
+        Some(document) => crate::yaml::yaml_to_json(document),
+        None => Ok(Value::Object(Map::new()))
+      }
+    }
+    #[cfg(not(feature = "yaml"))]
+    Err(err) => Err(anyhow!(err))
+  }
+}
+
+fn merge_values(target: &mut Value, layer: &Value) {
+  match (target, layer) {
+    (Value::Object(target), Value::Object(layer)) => {
+      for (key, value) in layer {
+        if value.is_null() {
+          target.remove(key);
+        } else if let Some(existing) = target.get_mut(key) && existing.is_object() && value.is_object() {
+          merge_values(existing, value);
+        } else {
+          target.insert(key.clone(), value.clone());
+        }
+      }
+    }
+    (target, layer) => *target = layer.clone()
+  }
+}
+
+/// Returns the `inputs` schema of the workflow, with the component inputs added so references to
+/// them (`#/components/inputs/<name>`) can be resolved. Returns `None` if the workflow has no
+/// inputs schema.
+pub(crate) fn workflow_schema(description: &ArazzoDescription, workflow_id: &str) -> anyhow::Result<Option<Value>> {
+  let workflow = description.workflows.iter()
+    .find(|workflow| workflow.workflow_id == workflow_id)
+    .ok_or_else(|| anyhow!("No workflow with ID '{}' was found", workflow_id))?;
+  let schema = match &workflow.inputs {
+    Value::Null => return Ok(None),
+    Value::Object(schema) => {
+      // Component references are resolved against the root schema
+      let mut schema = schema.clone();
+      let components = description.components.inputs.iter()
+        .map(|(name, schema)| (name.clone(), schema.clone()))
+        .collect::<Map<_, _>>();
+      let mut inputs = Map::new();
+      inputs.insert("inputs".to_string(), Value::Object(components));
+      schema.insert("components".to_string(), Value::Object(inputs));
+      Value::Object(schema)
+    }
+    schema => schema.clone()
+  };
+  Ok(Some(schema))
+}
+
+/// Follows any local `$ref` of the schema
+pub(crate) fn resolve_schema<'a>(root: &'a Value, schema: &'a Value) -> &'a Value {
+  let mut schema = schema;
+  for _ in 0..32 {
+    match schema.get("$ref").and_then(|reference| reference.as_str())
+      .and_then(|reference| reference.strip_prefix('#'))
+      .and_then(|pointer| root.pointer(pointer)) {
+      Some(target) => schema = target,
+      None => break
+    }
+  }
+  schema
+}
+
+/// Validates the input values against the `inputs` schema of the workflow. References to the
+/// component inputs (`#/components/inputs/<name>`) are resolved. Returns an error listing all
+/// the values that do not match the schema.
+pub fn validate_workflow_inputs(description: &ArazzoDescription, workflow_id: &str, inputs: &Value) -> anyhow::Result<()> {
+  let Some(schema) = workflow_schema(description, workflow_id)? else {
+    return Ok(());
+  };
+  let violations = validate_json_schema(&schema, inputs);
+  if violations.is_empty() {
+    Ok(())
+  } else {
+    let violations = violations.iter().map(|violation| violation.to_string()).collect::<Vec<_>>();
+    Err(anyhow!("The inputs for workflow '{}' are not valid: {}", workflow_id, violations.join("; ")))
+  }
+}
+
+/// Supplies workflow inputs from environment variables with a prefix (by default `ARAZZO_INPUT_`).
+/// The rest of the variable name is matched to the properties of the `inputs` schema of the
+/// workflow, ignoring case, `_` and `-` (so `ARAZZO_INPUT_USER_NAME` sets `userName`), and `__`
+/// separates the names of nested properties (`ARAZZO_INPUT_USER__NAME` sets `user.name`).
+///
+/// Values are converted to the type of the property in the schema (`integer`, `number`, `boolean`,
+/// or JSON for `array` and `object`). Variables that do not match a property are added with the
+/// lowercase name as strings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvInputProvider {
+  /// Prefix of the environment variables
+  pub prefix: String
+}
+
+impl Default for EnvInputProvider {
+  fn default() -> Self {
+    EnvInputProvider { prefix: "ARAZZO_INPUT_".to_string() }
+  }
+}
+
+impl EnvInputProvider {
+  /// Creates a provider for the environment variables with the prefix
+  pub fn new<S: Into<String>>(prefix: S) -> Self {
+    EnvInputProvider { prefix: prefix.into() }
+  }
+
+  /// Returns the inputs for the workflow from the environment variables of the current process
+  pub fn inputs(&self, description: &ArazzoDescription, workflow_id: &str) -> anyhow::Result<Value> {
+    self.inputs_from_vars(description, workflow_id, env::vars())
+  }
+
+  /// Returns the inputs for the workflow from the variables (name and value pairs)
+  pub fn inputs_from_vars<I>(&self, description: &ArazzoDescription, workflow_id: &str, vars: I) -> anyhow::Result<Value>
+    where I: IntoIterator<Item = (String, String)> {
+    let root = workflow_schema(description, workflow_id)?.unwrap_or(Value::Null);
+    let mut vars = vars.into_iter()
+      .filter_map(|(name, value)| name.strip_prefix(&self.prefix)
+        .filter(|name| !name.is_empty())
+        .map(|name| (name.to_string(), value)))
+      .collect::<Vec<_>>();
+    vars.sort();
+
+    let mut inputs = Value::Object(Map::new());
+    for (name, value) in vars {
+      let mut schema = Some(resolve_schema(&root, &root));
+      let mut path = vec![];
+      for part in name.split("__") {
+        let property = schema.and_then(|schema| schema.get("properties"))
+          .and_then(|properties| properties.as_object())
+          .and_then(|properties| properties.iter().find(|(key, _)| normalise(key) == normalise(part)));
+        match property {
+          Some((key, property)) => {
+            path.push(key.clone());
+            schema = Some(resolve_schema(&root, property));
+          }
+          None => {
+            path.push(part.to_ascii_lowercase());
+            schema = None;
+          }
+        }
+      }
+      let value = coerce(&value, schema)
+        .map_err(|err| anyhow!("Environment variable '{}{}' is not valid: {}", self.prefix, name, err))?;
+      let layer = path.iter().rev().fold(value, |value, key| {
+        let mut object = Map::new();
+        object.insert(key.clone(), value);
+        Value::Object(object)
+      });
+      merge_values(&mut inputs, &layer);
+    }
+    Ok(inputs)
+  }
+}
+
+fn normalise(name: &str) -> String {
+  name.chars()
+    .filter(|ch| *ch != '_' && *ch != '-')
+    .map(|ch| ch.to_ascii_lowercase())
+    .collect()
+}
+
+/// Converts any string values in the inputs to the types declared in the `inputs` schema of the
+/// workflow (`integer`, `number`, `boolean`, or JSON for `array` and `object`). Nested properties
+/// and array items are also converted. The error names the input and the expected type.
+pub fn coerce_inputs(description: &ArazzoDescription, workflow_id: &str, inputs: &Value) -> anyhow::Result<Value> {
+  match workflow_schema(description, workflow_id)? {
+    Some(root) => coerce_value(&root, resolve_schema(&root, &root), inputs, ""),
+    None => Ok(inputs.clone())
+  }
+}
+
+fn coerce_value(root: &Value, schema: &Value, value: &Value, path: &str) -> anyhow::Result<Value> {
+  match value {
+    Value::String(text) => coerce(text, Some(schema))
+      .map_err(|err| anyhow!("Input '{}' is not valid: {}", path, err)),
+    Value::Object(object) => {
+      let properties = schema.get("properties").and_then(|properties| properties.as_object());
+      object.iter()
+        .map(|(key, value)| {
+          let key_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+          let value = match properties.and_then(|properties| properties.get(key)) {
+            Some(property) => coerce_value(root, resolve_schema(root, property), value, &key_path)?,
+            None => value.clone()
+          };
+          Ok((key.clone(), value))
+        })
+        .collect::<anyhow::Result<Map<_, _>>>()
+        .map(Value::Object)
+    }
+    Value::Array(items) => match schema.get("items") {
+      Some(item_schema) => items.iter()
+        .enumerate()
+        .map(|(index, item)| coerce_value(root, resolve_schema(root, item_schema), item, &format!("{}[{}]", path, index)))
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map(Value::Array),
+      None => Ok(value.clone())
+    },
+    _ => Ok(value.clone())
+  }
+}
+
+/// Converts a string value to the type of the schema
+fn coerce(value: &str, schema: Option<&Value>) -> anyhow::Result<Value> {
+  let schema_type = schema.and_then(|schema| schema.get("type"))
+    .and_then(|schema_type| match schema_type {
+      Value::String(schema_type) => Some(schema_type.as_str()),
+      // For a list of types (i.e. ["integer", "null"]), the first type that is not null is used
+      Value::Array(types) => types.iter().filter_map(|schema_type| schema_type.as_str()).find(|schema_type| *schema_type != "null"),
+      _ => None
+    });
+  match schema_type {
+    Some("integer") => value.trim().parse::<i64>()
+      .map(Value::from)
+      .map_err(|_| anyhow!("expected an integer, but got '{}'", value)),
+    Some("number") => value.trim().parse::<f64>().ok()
+      .and_then(serde_json::Number::from_f64)
+      .map(Value::Number)
+      .ok_or_else(|| anyhow!("expected a number, but got '{}'", value)),
+    Some("boolean") => match value.trim().to_ascii_lowercase().as_str() {
+      "true" | "1" | "yes" => Ok(Value::Bool(true)),
+      "false" | "0" | "no" => Ok(Value::Bool(false)),
+      _ => Err(anyhow!("expected a boolean, but got '{}'", value))
+    },
+    Some("array") | Some("object") => serde_json::from_str::<Value>(value)
+      .ok()
+      .filter(|json| json.is_array() || json.is_object())
+      .ok_or_else(|| anyhow!("expected a JSON {}, but got '{}'", schema_type.unwrap_or_default(), value)),
+    _ => Ok(Value::String(value.to_string()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use serde_json::json;
+
+  use crate::inputs::{coerce_inputs, validate_workflow_inputs, EnvInputProvider, InputLayers};
+  use crate::v1_0::{ArazzoDescription, Components, Workflow};
+
+  #[test]
+  fn merges_the_layers_in_order() {
+    let layers = InputLayers::new()
+      .with_values(json!({ "user": { "name": "fred", "password": "base" }, "limit": 10, "tags": ["a", "b"] })).unwrap()
+      .with_values(json!({ "user": { "password": "staging" }, "tags": ["c"], "limit": null })).unwrap()
+      .with_override("user.name=wilma").unwrap()
+      .with_override("count=2").unwrap();
+    expect!(layers.merge()).to(be_equal_to(json!({
+      "user": { "name": "wilma", "password": "staging" },
+      "tags": ["c"],
+      "count": 2
+    })));
+    expect!(InputLayers::new().with_override("novalue")).to(be_err());
+    expect!(InputLayers::new().with_override("user..name=1")).to(be_err());
+    expect!(InputLayers::new().with_values(json!([1]))).to(be_err());
+  }
+
+  #[test]
+  fn validates_the_inputs_against_the_workflow_schema() {
+    let description = ArazzoDescription {
+      workflows: vec![Workflow {
+        workflow_id: "login".to_string(),
+        inputs: json!({ "$ref": "#/components/inputs/credentials" }),
+        .. Workflow::default()
+      }],
+      components: Components {
+        inputs: hashmap!{
+          "credentials".to_string() => json!({
+            "type": "object",
+            "required": ["username", "password"],
+            "properties": { "username": { "type": "string" }, "password": { "type": "string" } }
+          })
+        },
+        .. Components::default()
+      },
+      .. ArazzoDescription::default()
+    };
+
+    expect!(validate_workflow_inputs(&description, "login", &json!({ "username": "fred", "password": "x" }))).to(be_ok());
+    expect!(validate_workflow_inputs(&description, "login", &json!({ "username": 1 })).unwrap_err().to_string())
+      .to(be_equal_to("The inputs for workflow 'login' are not valid: Required property 'password' is missing; \
+        /username: Expected a value of type string, got Number"));
+    expect!(validate_workflow_inputs(&description, "other", &json!({}))).to(be_err());
+
+    let inputs = InputLayers::new()
+      .with_override("username=fred").unwrap()
+      .with_override("password=secret").unwrap()
+      .resolve(&description, "login")
+      .unwrap();
+    expect!(inputs["username"].as_str()).to(be_some().value("fred"));
+  }
+
+  #[test]
+  fn maps_environment_variables_to_the_inputs() {
+    let description = ArazzoDescription {
+      workflows: vec![Workflow {
+        workflow_id: "order".to_string(),
+        inputs: json!({
+          "type": "object",
+          "properties": {
+            "userName": { "type": "string" },
+            "quantity": { "type": "integer" },
+            "express": { "type": ["boolean", "null"] },
+            "address": { "$ref": "#/components/inputs/address" }
+          }
+        }),
+        .. Workflow::default()
+      }],
+      components: Components {
+        inputs: hashmap!{
+          "address".to_string() => json!({
+            "type": "object",
+            "properties": { "postCode": { "type": "integer" }, "lines": { "type": "array" } }
+          })
+        },
+        .. Components::default()
+      },
+      .. ArazzoDescription::default()
+    };
+    let vars = vec![
+      ("ARAZZO_INPUT_USER_NAME".to_string(), "fred".to_string()),
+      ("ARAZZO_INPUT_QUANTITY".to_string(), "3".to_string()),
+      ("ARAZZO_INPUT_EXPRESS".to_string(), "yes".to_string()),
+      ("ARAZZO_INPUT_ADDRESS__POST_CODE".to_string(), "2000".to_string()),
+      ("ARAZZO_INPUT_ADDRESS__LINES".to_string(), "[\"1 Main St\"]".to_string()),
+      ("ARAZZO_INPUT_TOKEN".to_string(), "123".to_string()),
+      ("HOME".to_string(), "/root".to_string())
+    ];
+    let provider = EnvInputProvider::default();
+    expect!(provider.inputs_from_vars(&description, "order", vars).unwrap()).to(be_equal_to(json!({
+      "userName": "fred",
+      "quantity": 3,
+      "express": true,
+      "address": { "postCode": 2000, "lines": ["1 Main St"] },
+      "token": "123"
+    })));
+
+    let vars = vec![("ARAZZO_INPUT_QUANTITY".to_string(), "three".to_string())];
+    expect!(provider.inputs_from_vars(&description, "order", vars).unwrap_err().to_string())
+      .to(be_equal_to("Environment variable 'ARAZZO_INPUT_QUANTITY' is not valid: expected an integer, but got 'three'"));
+  }
+
+  #[test]
+  fn coerces_string_inputs_to_the_schema_types() {
+    let description = ArazzoDescription {
+      workflows: vec![Workflow {
+        workflow_id: "order".to_string(),
+        inputs: json!({
+          "type": "object",
+          "properties": {
+            "name": { "type": "string" },
+            "quantity": { "type": "integer" },
+            "ids": { "type": "array", "items": { "type": "integer" } },
+            "tags": { "type": "array" },
+            "options": { "type": "object", "properties": { "express": { "type": "boolean" } } }
+          }
+        }),
+        .. Workflow::default()
+      }],
+      .. ArazzoDescription::default()
+    };
+    let inputs = json!({
+      "name": "123",
+      "quantity": "3",
+      "ids": ["1", 2],
+      "tags": "[\"a\"]",
+      "options": { "express": "false" },
+      "other": "4"
+    });
+    expect!(coerce_inputs(&description, "order", &inputs).unwrap()).to(be_equal_to(json!({
+      "name": "123",
+      "quantity": 3,
+      "ids": [1, 2],
+      "tags": ["a"],
+      "options": { "express": false },
+      "other": "4"
+    })));
+
+    expect!(coerce_inputs(&description, "order", &json!({ "ids": ["1", "x"] })).unwrap_err().to_string())
+      .to(be_equal_to("Input 'ids[1]' is not valid: expected an integer, but got 'x'"));
+    expect!(coerce_inputs(&description, "order", &json!({ "options": { "express": "maybe" } })).unwrap_err().to_string())
+      .to(be_equal_to("Input 'options.express' is not valid: expected a boolean, but got 'maybe'"));
+    expect!(coerce_inputs(&description, "order", &json!({ "tags": "a,b" })).unwrap_err().to_string())
+      .to(be_equal_to("Input 'tags' is not valid: expected a JSON array, but got 'a,b'"));
+
+    let inputs = InputLayers::new().with_override("quantity=\"5\"").unwrap().resolve(&description, "order").unwrap();
+    expect!(inputs["quantity"].as_u64()).to(be_some().value(5));
+  }
+}
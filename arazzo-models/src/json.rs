@@ -1,7 +1,7 @@
 //! Functions and Traits for loading Arazzo objects from a JSON document
 
 use std::collections::BTreeMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use anyhow::anyhow;
 use maplit::{btreemap, hashmap};
@@ -9,7 +9,14 @@ use serde_json::{Map, Value};
 
 use crate::either::Either;
 use crate::extensions::{json_extract_extensions, AnyValue};
-use crate::payloads::{EmptyPayload, JsonPayload, Payload, StringPayload};
+use crate::fields::ObjectFields;
+use crate::payload_registry::{create_payload, PayloadValue};
+use crate::payloads::{
+  string_payload,
+  with_schema_hint,
+  EmptyPayload,
+  Payload
+};
 use crate::v1_0::{
   ArazzoDescription,
   Components,
@@ -52,7 +59,7 @@ impl TryFrom<&Value> for ArazzoDescription {
           source_descriptions,
           workflows,
           components,
-          extensions: json_extract_extensions(&map)?
+          extensions: json_extract_extensions(map)?
         })
       } else {
         Err(anyhow!("Arazzo version number is required [4.6.1.1 Fixed Fields]"))
@@ -69,10 +76,10 @@ impl TryFrom<&Value> for SourceDescription {
   fn try_from(value: &Value) -> Result<Self, Self::Error> {
     if let Some(map) = value.as_object() {
       Ok(SourceDescription {
-        name: json_object_require_string(&map, "name")?,
-        url: json_object_require_string(&map, "url")?,
-        r#type: json_object_lookup_string(&map, "type"),
-        extensions: json_extract_extensions(&map)?
+        name: json_object_require_string(map, "name")?,
+        url: json_object_require_string(map, "url")?,
+        r#type: json_object_lookup_string(map, "type"),
+        extensions: json_extract_extensions(map)?
       })
     } else {
       Err(anyhow!("JSON value must be an Object, got {:?}", value))
@@ -80,13 +87,13 @@ impl TryFrom<&Value> for SourceDescription {
   }
 }
 
-fn json_load_source_descriptions(map: &Map<String, Value>) -> anyhow::Result<Vec<SourceDescription>> {
+pub(crate) fn json_load_source_descriptions(map: &Map<String, Value>) -> anyhow::Result<Vec<SourceDescription>> {
   if let Some(descriptions) = map.get("sourceDescriptions") &&
     let Some(array) = descriptions.as_array() {
     if array.is_empty() {
       Err(anyhow!("Source Description list must have at least one entry [4.6.1.1 Fixed Fields]"))
     } else {
-      let mut list = vec![];
+      let mut list = Vec::with_capacity(array.len());
 
       for item in array {
         list.push(SourceDescription::try_from(item)?);
@@ -105,11 +112,11 @@ impl TryFrom<&Value> for Info {
   fn try_from(value: &Value) -> Result<Self, Self::Error> {
     if let Some(map) = value.as_object() {
       Ok(Info {
-        title: json_object_require_string(&map, "title")?,
-        summary: json_object_lookup_string(&map, "summary"),
-        description: json_object_lookup_string(&map, "description"),
-        version: json_object_require_string(&map, "version")?,
-        extensions: json_extract_extensions(&map)?
+        title: json_object_require_string(map, "title")?,
+        summary: json_object_lookup_string(map, "summary"),
+        description: json_object_lookup_string(map, "description"),
+        version: json_object_require_string(map, "version")?,
+        extensions: json_extract_extensions(map)?
       })
     } else {
       Err(anyhow!("JSON value must be an Object, got {:?}", value))
@@ -118,12 +125,11 @@ impl TryFrom<&Value> for Info {
 }
 
 fn json_load_workflows(map: &Map<String, Value>) -> anyhow::Result<Vec<Workflow>> {
-  if let Some(array) = map.get("workflows") &&
-     let Some(workflows) = array.as_array() {
+  if let Some(Value::Array(workflows)) = map.get("workflows") {
     if workflows.is_empty() {
       Err(anyhow!("Workflows list must have at least one entry [4.6.1.1 Fixed Fields]"))
     } else {
-      let mut list = vec![];
+      let mut list = Vec::with_capacity(workflows.len());
 
       for item in workflows {
         list.push(Workflow::try_from(item)?);
@@ -132,27 +138,42 @@ fn json_load_workflows(map: &Map<String, Value>) -> anyhow::Result<Vec<Workflow>
       Ok(list)
     }
   } else {
-    Err(anyhow!("Workflow Object is required [4.6.1.1 Fixed Fields]"))
+    Err(json_workflows_error(map.get("workflows")))
   }
 }
 
+/// Error returned when the workflows field is missing, or is not an Array
+pub(crate) fn json_workflows_error(workflows: Option<&Value>) -> anyhow::Error {
+  match workflows {
+    Some(value) => anyhow!("Workflows must be an Array, got {} [4.6.1.1 Fixed Fields]", json_type_name(value)),
+    None => anyhow!("Workflow Object is required [4.6.1.1 Fixed Fields]")
+  }
+}
+
+/// Fixed fields of a Workflow Object
+const WORKFLOW_FIELDS: [&str; 10] = [
+  "workflowId", "summary", "description", "inputs", "dependsOn", "steps", "successActions",
+  "failureActions", "outputs", "parameters"
+];
+
 impl TryFrom<&Value> for Workflow {
   type Error = anyhow::Error;
 
   fn try_from(value: &Value) -> Result<Self, Self::Error> {
     if let Some(map) = value.as_object() {
+      let fields = json_object_fields(map, &WORKFLOW_FIELDS);
       Ok(Workflow {
-        workflow_id: json_object_require_string(map, "workflowId")?,
-        summary: json_object_lookup_string(map, "summary"),
-        description: json_object_lookup_string(map, "description"),
-        inputs: map.get("inputs").cloned().unwrap_or_default(),
-        depends_on: json_object_lookup_string_list(map, "dependsOn").unwrap_or_default(),
-        steps: json_load_steps(map)?,
-        success_actions: json_load_success_actions(map)?,
-        failure_actions: json_load_failure_actions(map)?,
-        outputs: json_load_outputs(map),
-        parameters: json_load_parameters(map)?,
-        extensions: json_extract_extensions(&map)?
+        workflow_id: json_require_string(fields.get("workflowId"), "workflowId")?,
+        summary: fields.get("summary").and_then(json_value_string),
+        description: fields.get("description").and_then(json_value_string),
+        inputs: fields.get("inputs").cloned().unwrap_or_default(),
+        depends_on: fields.get("dependsOn").and_then(json_value_string_list).unwrap_or_default(),
+        steps: json_load_steps(fields.get("steps"))?,
+        success_actions: json_load_success_actions(fields.get("successActions"))?,
+        failure_actions: json_load_failure_actions(fields.get("failureActions"))?,
+        outputs: json_load_outputs(fields.get("outputs")),
+        parameters: json_load_parameters(fields.get("parameters"))?,
+        extensions: fields.extensions()?
       })
     } else {
       Err(anyhow!("JSON value must be an Object, got {:?}", value))
@@ -160,13 +181,13 @@ impl TryFrom<&Value> for Workflow {
   }
 }
 
-fn json_load_steps(map: &Map<String, Value>) -> anyhow::Result<Vec<Step>> {
-  if let Some(steps) = map.get("steps") &&
+fn json_load_steps(steps: Option<&Value>) -> anyhow::Result<Vec<Step>> {
+  if let Some(steps) = steps &&
      let Some(array) = steps.as_array() {
     if array.is_empty() {
       Err(anyhow!("At lest one Step is required [4.6.4.1 Fixed Fields]"))
     } else {
-      let mut list = vec![];
+      let mut list = Vec::with_capacity(array.len());
 
       for item in array {
         list.push(Step::try_from(item)?);
@@ -179,10 +200,10 @@ fn json_load_steps(map: &Map<String, Value>) -> anyhow::Result<Vec<Step>> {
   }
 }
 
-fn json_load_parameters(map: &Map<String, Value>) -> anyhow::Result<Vec<Either<ParameterObject, ReusableObject>>> {
-  if let Some(parameters) = map.get("parameters") &&
+fn json_load_parameters(parameters: Option<&Value>) -> anyhow::Result<Vec<Either<ParameterObject, ReusableObject>>> {
+  if let Some(parameters) = parameters &&
      let Some(array) = parameters.as_array() {
-    let mut list = vec![];
+    let mut list = Vec::with_capacity(array.len());
 
     for item in array {
       if let Some(map) = item.as_object() {
@@ -200,18 +221,16 @@ fn json_load_parameters(map: &Map<String, Value>) -> anyhow::Result<Vec<Either<P
   }
 }
 
-fn json_load_success_actions(map: &Map<String, Value>) -> anyhow::Result<Vec<Either<SuccessObject, ReusableObject>>> {
-  if let Some(array) = map.get("successActions") {
-    let mut list = vec![];
+fn json_load_success_actions(actions: Option<&Value>) -> anyhow::Result<Vec<Either<SuccessObject, ReusableObject>>> {
+  if let Some(array) = actions.and_then(|actions| actions.as_array()) {
+    let mut list = Vec::with_capacity(array.len());
 
-    if let Some(array) = array.as_array() {
-      for item in array {
-        if let Some(map) = item.as_object() {
-          if map.contains_key("reference") {
-            list.push(Either::Second(ReusableObject::try_from(item)?));
-          } else {
-            list.push(Either::First(SuccessObject::try_from(item)?));
-          }
+    for item in array {
+      if let Some(map) = item.as_object() {
+        if map.contains_key("reference") {
+          list.push(Either::Second(ReusableObject::try_from(item)?));
+        } else {
+          list.push(Either::First(SuccessObject::try_from(item)?));
         }
       }
     }
@@ -222,18 +241,16 @@ fn json_load_success_actions(map: &Map<String, Value>) -> anyhow::Result<Vec<Eit
   }
 }
 
-fn json_load_failure_actions(map: &Map<String, Value>) -> anyhow::Result<Vec<Either<FailureObject, ReusableObject>>> {
-  if let Some(array) = map.get("failureActions") {
-    let mut list = vec![];
+fn json_load_failure_actions(actions: Option<&Value>) -> anyhow::Result<Vec<Either<FailureObject, ReusableObject>>> {
+  if let Some(array) = actions.and_then(|actions| actions.as_array()) {
+    let mut list = Vec::with_capacity(array.len());
 
-    if let Some(array) = array.as_array() {
-      for item in array {
-        if let Some(map) = item.as_object() {
-          if map.contains_key("reference") {
-            list.push(Either::Second(ReusableObject::try_from(item)?));
-          } else {
-            list.push(Either::First(FailureObject::try_from(item)?));
-          }
+    for item in array {
+      if let Some(map) = item.as_object() {
+        if map.contains_key("reference") {
+          list.push(Either::Second(ReusableObject::try_from(item)?));
+        } else {
+          list.push(Either::First(FailureObject::try_from(item)?));
         }
       }
     }
@@ -244,8 +261,8 @@ fn json_load_failure_actions(map: &Map<String, Value>) -> anyhow::Result<Vec<Eit
   }
 }
 
-fn json_load_outputs(map: &Map<String, Value>) -> BTreeMap<String, String> {
-  map.get("outputs").map(|v | {
+fn json_load_outputs(outputs: Option<&Value>) -> BTreeMap<String, String> {
+  outputs.map(|v | {
     if let Some(outputs) = v.as_object() {
       outputs.iter()
         .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
@@ -256,26 +273,33 @@ fn json_load_outputs(map: &Map<String, Value>) -> BTreeMap<String, String> {
   }).unwrap_or_default()
 }
 
+/// Fixed fields of a Step Object
+const STEP_FIELDS: [&str; 11] = [
+  "stepId", "operationId", "operationPath", "workflowId", "description", "parameters",
+  "requestBody", "onSuccess", "successCriteria", "onFailure", "outputs"
+];
+
 impl TryFrom<&Value> for Step {
   type Error = anyhow::Error;
 
   fn try_from(value: &Value) -> Result<Self, Self::Error> {
     if let Some(map) = value.as_object() {
+      let fields = json_object_fields(map, &STEP_FIELDS);
       Ok(Step {
-        step_id: json_object_require_string(&map, "stepId")?,
-        operation_id: json_object_lookup_string(&map, "operationId"),
-        operation_path: json_object_lookup_string(&map, "operationPath"),
-        workflow_id: json_object_lookup_string(&map, "workflowId"),
-        description: json_object_lookup_string(&map, "description"),
-        parameters: json_load_parameters(map)?,
-        request_body: map.get("requestBody")
-          .map(|v| RequestBody::try_from(v))
+        step_id: json_require_string(fields.get("stepId"), "stepId")?,
+        operation_id: fields.get("operationId").and_then(json_value_string),
+        operation_path: fields.get("operationPath").and_then(json_value_string),
+        workflow_id: fields.get("workflowId").and_then(json_value_string),
+        description: fields.get("description").and_then(json_value_string),
+        parameters: json_load_parameters(fields.get("parameters"))?,
+        request_body: fields.get("requestBody")
+          .map(RequestBody::try_from)
           .transpose()?,
-        on_success: json_load_success_actions(map)?,
-        success_criteria: json_load_success_criteria(map)?,
-        on_failure: json_load_failure_actions(map)?,
-        outputs: json_load_outputs(map),
-        extensions: json_extract_extensions(map)?
+        on_success: json_load_success_actions(fields.get("onSuccess"))?,
+        success_criteria: json_load_criteria(fields.get("successCriteria"))?,
+        on_failure: json_load_failure_actions(fields.get("onFailure"))?,
+        outputs: json_load_outputs(fields.get("outputs")),
+        extensions: fields.extensions()?
       })
     } else {
       Err(anyhow!("JSON value must be an Object, got {:?}", value))
@@ -283,32 +307,20 @@ impl TryFrom<&Value> for Step {
   }
 }
 
-fn json_load_success_criteria(map: &Map<String, Value>) -> anyhow::Result<Vec<Criterion>> {
-  if let Some(criteria) = map.get("successCriteria") {
-    let mut result = vec![];
-
-    if let Some(array) = criteria.as_array() {
-      for value in array {
-        result.push(Criterion::try_from(value)?);
-      }
-    }
-
-    Ok(result)
-  } else {
-    Ok(vec![])
-  }
-}
+/// Fixed fields of a Parameter Object
+const PARAMETER_FIELDS: [&str; 3] = ["name", "in", "value"];
 
 impl TryFrom<&Value> for ParameterObject {
   type Error = anyhow::Error;
 
   fn try_from(value: &Value) -> Result<Self, Self::Error> {
     if let Some(map) = value.as_object() {
+      let fields = json_object_fields(map, &PARAMETER_FIELDS);
       Ok(ParameterObject {
-        name: json_object_require_string(map, "name")?,
-        r#in: json_object_lookup_string(map, "in"),
-        value: json_load_any_or_expression(map, "value")?,
-        extensions: json_extract_extensions(map)?
+        name: json_require_string(fields.get("name"), "name")?,
+        r#in: fields.get("in").and_then(json_value_string),
+        value: json_load_any_or_expression(fields.get("value"))?,
+        extensions: fields.extensions()?
       })
     } else {
       Err(anyhow!("JSON value must be an Object, got {:?}", value))
@@ -316,8 +328,8 @@ impl TryFrom<&Value> for ParameterObject {
   }
 }
 
-fn json_load_any_or_expression(map: &Map<String, Value>, key: &str) -> anyhow::Result<Either<AnyValue, String>> {
-  if let Some(value) = map.get(key) {
+fn json_load_any_or_expression(value: Option<&Value>) -> anyhow::Result<Either<AnyValue, String>> {
+  if let Some(value) = value {
     if let Some(s) = value.as_str() {
       if s.starts_with('$') {
         Ok(Either::Second(s.to_string()))
@@ -332,18 +344,22 @@ fn json_load_any_or_expression(map: &Map<String, Value>, key: &str) -> anyhow::R
   }
 }
 
+/// Fixed fields of Success and Failure Action Objects
+const ACTION_FIELDS: [&str; 7] = ["name", "type", "workflowId", "stepId", "retryAfter", "retryLimit", "criteria"];
+
 impl TryFrom<&Value> for SuccessObject {
   type Error = anyhow::Error;
 
   fn try_from(value: &Value) -> Result<Self, Self::Error> {
     if let Some(map) = value.as_object() {
+      let fields = json_object_fields(map, &ACTION_FIELDS);
       Ok(SuccessObject {
-        name: json_object_require_string(map, "name")?,
-        r#type: json_object_require_string(map, "type")?,
-        workflow_id: json_object_lookup_string(map, "workflowId"),
-        step_id: json_object_lookup_string(map, "stepId"),
-        criteria: json_load_criteria(map, "criteria")?,
-        extensions: json_extract_extensions(map)?
+        name: json_require_string(fields.get("name"), "name")?,
+        r#type: json_require_string(fields.get("type"), "type")?,
+        workflow_id: fields.get("workflowId").and_then(json_value_string),
+        step_id: fields.get("stepId").and_then(json_value_string),
+        criteria: json_load_criteria(fields.get("criteria"))?,
+        extensions: fields.extensions()?
       })
     } else {
       Err(anyhow!("JSON value must be an Object, got {:?}", value))
@@ -356,15 +372,16 @@ impl TryFrom<&Value> for FailureObject {
 
   fn try_from(value: &Value) -> Result<Self, Self::Error> {
     if let Some(map) = value.as_object() {
+      let fields = json_object_fields(map, &ACTION_FIELDS);
       Ok(FailureObject {
-        name: json_object_require_string(map, "name")?,
-        r#type: json_object_require_string(map, "type")?,
-        workflow_id: json_object_lookup_string(map, "workflowId"),
-        step_id: json_object_lookup_string(map, "stepId"),
-        retry_after: json_object_lookup_number(map, "retryAfter"),
-        retry_limit: json_object_lookup_integer(map, "retryLimit"),
-        criteria: json_load_criteria(map, "criteria")?,
-        extensions: json_extract_extensions(map)?
+        name: json_require_string(fields.get("name"), "name")?,
+        r#type: json_require_string(fields.get("type"), "type")?,
+        workflow_id: fields.get("workflowId").and_then(json_value_string),
+        step_id: fields.get("stepId").and_then(json_value_string),
+        retry_after: fields.get("retryAfter").and_then(json_value_number),
+        retry_limit: fields.get("retryLimit").and_then(json_value_integer),
+        criteria: json_load_criteria(fields.get("criteria"))?,
+        extensions: fields.extensions()?
       })
     } else {
       Err(anyhow!("JSON value must be an Object, got {:?}", value))
@@ -441,16 +458,20 @@ impl TryFrom<&Value> for ReusableObject {
   }
 }
 
+/// Fixed fields of a Criterion Object
+const CRITERION_FIELDS: [&str; 3] = ["context", "condition", "type"];
+
 impl TryFrom<&Value> for Criterion {
   type Error = anyhow::Error;
 
   fn try_from(value: &Value) -> Result<Self, Self::Error> {
     if let Some(map) = value.as_object() {
+      let fields = json_object_fields(map, &CRITERION_FIELDS);
       Ok(Criterion {
-        context: json_object_lookup_string(map, "context"),
-        condition: json_object_require_string(map, "condition")?,
-        r#type: json_load_criterion_expression_type(map)?,
-        extensions: json_extract_extensions(map)?
+        context: fields.get("context").and_then(json_value_string),
+        condition: json_require_string(fields.get("condition"), "condition")?,
+        r#type: json_load_criterion_expression_type(fields.get("type"))?,
+        extensions: fields.extensions()?
       })
     } else {
       Err(anyhow!("JSON value must be an Object, got {:?}", value))
@@ -458,16 +479,18 @@ impl TryFrom<&Value> for Criterion {
   }
 }
 
-fn json_load_criteria(map: &Map<String, Value>, key: &str) -> anyhow::Result<Vec<Criterion>> {
-  let mut criterion = vec![];
+fn json_load_criteria(criteria: Option<&Value>) -> anyhow::Result<Vec<Criterion>> {
+  if let Some(array) = criteria.and_then(|criteria| criteria.as_array()) {
+    let mut criterion = Vec::with_capacity(array.len());
 
-  if let Some(criteria) = map.get(key) && let Some(array) = criteria.as_array() {
     for item in array {
       criterion.push(Criterion::try_from(item)?);
     }
-  }
 
-  Ok(criterion)
+    Ok(criterion)
+  } else {
+    Ok(vec![])
+  }
 }
 
 impl TryFrom<&Value> for CriterionExpressionType {
@@ -486,8 +509,8 @@ impl TryFrom<&Value> for CriterionExpressionType {
   }
 }
 
-fn json_load_criterion_expression_type(json: &Map<String, Value>) -> anyhow::Result<Option<Either<String, CriterionExpressionType>>> {
-  json.get("type").map(|value| {
+fn json_load_criterion_expression_type(value: Option<&Value>) -> anyhow::Result<Option<Either<String, CriterionExpressionType>>> {
+  value.map(|value| {
     if let Some(s) = value.as_str() {
       Ok(Either::First(s.to_string()))
     } else {
@@ -504,11 +527,12 @@ impl TryFrom<&Value> for RequestBody {
       let content_type = json_object_lookup_string(map, "contentType");
       let payload = json_load_payload(map, "payload", content_type.as_ref())?;
       let replacements = json_load_replacements(map, "replacements")?;
+      let extensions = json_extract_extensions(map)?;
       Ok(RequestBody {
+        payload: payload.map(|payload| with_schema_hint(payload, content_type.as_deref(), &extensions)),
         content_type,
-        payload,
         replacements,
-        extensions: json_extract_extensions(&map)?
+        extensions
       })
     } else {
       Err(anyhow!("JSON value must be an Object, got {:?}", value))
@@ -519,17 +543,24 @@ impl TryFrom<&Value> for RequestBody {
 fn json_load_payload(
   map: &Map<String, Value>,
   key: &str,
-  _content_type: Option<&String>
-) -> anyhow::Result<Option<Rc<dyn Payload + Send + Sync>>> {
-  if let Some(value) = map.get(key) {
-    match value {
-      Value::Null => Ok(Some(Rc::new(EmptyPayload))),
-      Value::String(s) => Ok(Some(Rc::new(StringPayload(s.clone())))),
-      _ => Ok(Some(Rc::new(JsonPayload(value.clone()))))
-    }
-  } else {
-    Ok(None)
-  }
+  content_type: Option<&String>
+) -> anyhow::Result<Option<Arc<dyn Payload + Send + Sync>>> {
+  map.get(key)
+    .map(|value| json_value_payload(value, content_type.map(|ct| ct.as_str())))
+    .transpose()
+}
+
+/// Creates the payload for a request body payload value
+pub(crate) fn json_value_payload(
+  value: &Value,
+  content_type: Option<&str>
+) -> anyhow::Result<Arc<dyn Payload + Send + Sync>> {
+  let payload: Arc<dyn Payload + Send + Sync> = match value {
+    Value::Null => Arc::new(EmptyPayload),
+    Value::String(s) => string_payload(s, content_type),
+    _ => create_payload(&PayloadValue::Json(value), content_type)
+  };
+  Ok(payload)
 }
 
 fn json_load_replacements(map: &Map<String, Value>, key: &str) -> anyhow::Result<Vec<PayloadReplacement>> {
@@ -551,7 +582,7 @@ impl TryFrom<&Value> for PayloadReplacement {
     if let Some(map) = value.as_object() {
       Ok(PayloadReplacement {
         target: json_object_require_string(map, "target")?,
-        value: json_load_any_or_expression(map, "value")?,
+        value: json_load_any_or_expression(map.get("value"))?,
         extensions: json_extract_extensions(map)?
       })
     } else {
@@ -572,67 +603,84 @@ pub fn json_type_name(json: &Value) -> String {
   }.to_string()
 }
 
+/// Classifies the entries of the JSON Object in a single pass (see [`ObjectFields`])
+fn json_object_fields<'a, const N: usize>(
+  map: &'a Map<String, Value>,
+  keys: &'static [&'static str; N]
+) -> ObjectFields<'a, Value, N> {
+  ObjectFields::classify(keys, map.iter().map(|(key, value)| (key.as_str(), value)))
+}
+
 /// Looks up a value with the given key in a JSON Object. If the value is easily
 /// convertable to a String (is a Number or Boolean), `to_string()` will be called on it.
 pub fn json_object_lookup_string(map: &Map<String, Value>, key: &str) -> Option<String> {
-  if let Some(value) = map.get(key) {
-    match value {
-      Value::Bool(b) => Some(b.to_string()),
-      Value::Number(n) => Some(n.to_string()),
-      Value::String(s) => Some(s.clone()),
-      _ => None
-    }
-  } else {
-    None
+  map.get(key).and_then(json_value_string)
+}
+
+/// Converts the JSON value to a String, if it is a String, Number or Boolean
+fn json_value_string(value: &Value) -> Option<String> {
+  match value {
+    Value::Bool(b) => Some(b.to_string()),
+    Value::Number(n) => Some(n.to_string()),
+    Value::String(s) => Some(s.clone()),
+    _ => None
   }
 }
 
 /// Looks up a numeric value with the given key in an Object. If the value is an integer
 /// it will be converted to a double.
 pub fn json_object_lookup_number(map: &Map<String, Value>, key: &str) -> Option<f64> {
-  if let Some(value) = map.get(key) {
-    match value {
-      Value::Number(n) => {
-        if let Some(uint) = n.as_u64() {
-          Some(uint as f64)
-        } else if let Some(int) = n.as_i64() {
-          Some(int as f64)
-        } else {
-          n.as_f64()
-        }
-      },
-      _ => None
-    }
-  } else {
-    None
+  map.get(key).and_then(json_value_number)
+}
+
+/// Converts the JSON value to a double, if it is a Number
+fn json_value_number(value: &Value) -> Option<f64> {
+  match value {
+    Value::Number(n) => {
+      if let Some(uint) = n.as_u64() {
+        Some(uint as f64)
+      } else if let Some(int) = n.as_i64() {
+        Some(int as f64)
+      } else {
+        n.as_f64()
+      }
+    },
+    _ => None
   }
 }
 
 /// Looks up an integer value with the given key in an Object. If the value is a float
 /// it will be converted to an integer.
 pub fn json_object_lookup_integer(map: &Map<String, Value>, key: &str) -> Option<i64> {
-  if let Some(value) = map.get(key) {
-    match value {
-      Value::Number(n) => {
-        if let Some(uint) = n.as_u64() {
-          Some(uint as i64)
-        } else if let Some(int) = n.as_i64() {
-          Some(int)
-        } else {
-          n.as_f64().map(|f| f as i64)
-        }
-      },
-      _ => None
-    }
-  } else {
-    None
+  map.get(key).and_then(json_value_integer)
+}
+
+/// Converts the JSON value to an integer, if it is a Number
+fn json_value_integer(value: &Value) -> Option<i64> {
+  match value {
+    Value::Number(n) => {
+      if let Some(uint) = n.as_u64() {
+        Some(uint as i64)
+      } else if let Some(int) = n.as_i64() {
+        Some(int)
+      } else {
+        n.as_f64().map(|f| f as i64)
+      }
+    },
+    _ => None
   }
 }
 
 /// Looks up a required String value with the given key in a JSON Object. If the key does
 /// not exist, or the resulting value is not a String, an Error is returned.
 pub fn json_object_require_string(map: &Map<String, Value>, key: &str) -> anyhow::Result<String> {
-  if let Some(value) = map.get(key) {
+  json_require_string(map.get(key), key)
+}
+
+/// Returns the value of a required field with the given key as a String. If there is no value,
+/// or the value is not a String, an Error is returned.
+fn json_require_string(value: Option<&Value>, key: &str) -> anyhow::Result<String> {
+  if let Some(value) = value {
     if let Some(value) = value.as_str() {
       Ok(value.to_string())
     } else {
@@ -647,23 +695,17 @@ pub fn json_object_require_string(map: &Map<String, Value>, key: &str) -> anyhow
 /// is easily convertable to a String (is a Number or Boolean), `to_string()` will be called on it.
 /// All other values are ignored.
 pub fn json_object_lookup_string_list(map: &Map<String, Value>, key: &str) -> Option<Vec<String>> {
-  if let Some(value) = map.get(key) && let Some(array) = value.as_array() {
-    Some(array.iter().filter_map(|value| {
-      match value {
-        Value::Bool(b) => Some(b.to_string()),
-        Value::Number(n) => Some(n.to_string()),
-        Value::String(s) => Some(s.clone()),
-        _ => None
-      }
-    }).collect())
-  } else {
-    None
-  }
+  map.get(key).and_then(json_value_string_list)
+}
+
+/// Converts a JSON Array to a list of Strings, ignoring any values that can not be converted
+fn json_value_string_list(value: &Value) -> Option<Vec<String>> {
+  value.as_array()
+    .map(|array| array.iter().filter_map(json_value_string).collect())
 }
 
 #[cfg(test)]
 mod tests {
-  use std::any::Any;
 
   use expectest::prelude::*;
   use maplit::{btreemap, hashmap};
@@ -672,7 +714,7 @@ mod tests {
 
   use crate::either::Either;
   use crate::extensions::AnyValue;
-  use crate::payloads::{JsonPayload, StringPayload};
+  use crate::payloads::{BytesPayload, JsonPayload, ProtobufPayload, StringPayload};
   use crate::v1_0::*;
 
   #[test]
@@ -1035,8 +1077,8 @@ mod tests {
     expect!(&failure.r#type).to(be_equal_to("end"));
     expect!(failure.workflow_id.clone()).to(be_some().value("workflowId"));
     expect!(failure.step_id.clone()).to(be_some().value("stepId"));
-    expect!(failure.retry_after.clone()).to(be_some().value(10.5));
-    expect!(failure.retry_limit.clone()).to(be_some().value(10));
+    expect!(failure.retry_after).to(be_some().value(10.5));
+    expect!(failure.retry_limit).to(be_some().value(10));
 
     let json = json!({
       "name": "test",
@@ -1048,8 +1090,8 @@ mod tests {
     expect!(&failure.r#type).to(be_equal_to("end"));
     expect!(failure.workflow_id.clone()).to(be_none());
     expect!(failure.step_id.clone()).to(be_none());
-    expect!(failure.retry_after.clone()).to(be_none());
-    expect!(failure.retry_limit.clone()).to(be_none());
+    expect!(failure.retry_after).to(be_none());
+    expect!(failure.retry_limit).to(be_none());
   }
 
   #[test]
@@ -1190,6 +1232,30 @@ mod tests {
     ]));
   }
 
+  #[test]
+  fn load_step_actions() {
+    let json = json!({
+      "stepId": "test",
+      "operationId": "test",
+      "onSuccess": [
+        { "name": "done", "type": "end" }
+      ],
+      "onFailure": [
+        { "reference": "$components.failureActions.retry" }
+      ]
+    });
+
+    let step = Step::try_from(&json).unwrap();
+    expect!(step.on_success.len()).to(be_equal_to(1));
+    expect!(step.on_success[0].first().map(|action| action.name.as_str())).to(be_some().value("done"));
+    expect!(step.on_failure).to(be_equal_to(vec![
+      Either::Second(ReusableObject {
+        reference: "$components.failureActions.retry".to_string(),
+        value: None
+      })
+    ]));
+  }
+
   #[test]
   fn load_request_body() {
     let json = json!({
@@ -1217,6 +1283,33 @@ mod tests {
     }));
   }
 
+  #[test]
+  fn load_binary_payload() {
+    let body = RequestBody::try_from(&json!({
+      "contentType": "image/png",
+      "payload": "iVBORw0KGgo="
+    })).unwrap();
+    let p = body.payload_as::<BytesPayload>().unwrap();
+    expect!(p.0.to_vec()).to(be_equal_to(vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]));
+
+    let body = RequestBody::try_from(&json!({
+      "contentType": "application/octet-stream",
+      "payload": "not Base64!"
+    })).unwrap();
+    expect!(body.payload_as::<StringPayload>().is_some()).to(be_true());
+
+    let body = RequestBody::try_from(&json!({ "contentType": "text/plain", "payload": "AAAA" })).unwrap();
+    expect!(body.payload_as::<StringPayload>().is_some()).to(be_true());
+
+    let body = RequestBody::try_from(&json!({
+      "contentType": "application/x-protobuf; messageType=pets.v1.Pet",
+      "payload": "CgNQZXQ="
+    })).unwrap();
+    let p = body.payload_as::<ProtobufPayload>().unwrap();
+    expect!(p.hint.message_type.clone()).to(be_some().value("pets.v1.Pet"));
+    expect!(p.hint.descriptor.clone()).to(be_none());
+  }
+
   #[test]
   fn load_payload() {
     let body = json!({
@@ -1226,9 +1319,8 @@ mod tests {
       \"placed\",\"complete\":false}}"
     });
     let body = RequestBody::try_from(&body).unwrap();
-    expect!(body.content_type).to(be_some().value("application/json"));
-    let payload: &dyn Any = body.payload.as_ref().unwrap().as_ref();
-    let p = payload.downcast_ref::<StringPayload>().unwrap();
+    expect!(body.content_type.clone()).to(be_some().value("application/json"));
+    let p = body.payload_as::<StringPayload>().unwrap();
     assert_eq!(
       r#"{"petOrder":{"petId": "{$inputs.pet_id}","couponCode":"{$inputs.coupon_code}","quantity":"{$inputs.quantity}","status":"placed","complete":false}}"#,
       &p.0
@@ -1247,9 +1339,8 @@ mod tests {
       }
     });
     let body = RequestBody::try_from(&body).unwrap();
-    expect!(body.content_type).to(be_some().value("application/json"));
-    let payload: &dyn Any = body.payload.as_ref().unwrap().as_ref();
-    let p = payload.downcast_ref::<JsonPayload>().unwrap();
+    expect!(body.content_type.clone()).to(be_some().value("application/json"));
+    let p = body.payload_as::<JsonPayload>().unwrap();
     assert_eq!(
       &json!({
        "petOrder": {
@@ -0,0 +1,541 @@
+//! Minimal JSON Schema validator. This supports the keywords needed to check the structure of
+//! values (type, enum, const, properties, required, additionalProperties, items, prefixItems,
+//! the numeric, string, array and object size limits, pattern, format, allOf, anyOf, oneOf, not and
+//! local `$ref` references).
+//!
+//! Patterns are matched with the regex crate, which supports most of the ECMA-262 syntax the
+//! specification uses (but not look-around or backreferences). Each pattern is compiled once and
+//! cached, and patterns that can not be compiled are reported as violations. The `date-time`, `date`, `time`, `email`, `hostname`, `ipv4`, `ipv6`,
+//! `uri`, `uuid` and `regex` formats are checked, and other formats are ignored (as the
+//! specification allows for unknown formats).
+//!
+//! References that refer back to a schema that is already being applied to the same value (i.e.
+//! `{ "$ref": "#" }`) are reported as violations, instead of being followed forever.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::{LazyLock, Mutex};
+
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use crate::json::json_type_name;
+
+/// Location in a value that does not match a schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaViolation {
+  /// JSON Pointer to the location in the value
+  pub path: String,
+  /// Description of the problem
+  pub message: String
+}
+
+impl Display for SchemaViolation {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    if self.path.is_empty() {
+      write!(f, "{}", self.message)
+    } else {
+      write!(f, "{}: {}", self.path, self.message)
+    }
+  }
+}
+
+static DATE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(\d{4})-(\d{2})-(\d{2})$").unwrap());
+static TIME: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"^(\d{2}):(\d{2}):(\d{2})(\.\d+)?([Zz]|[+-](\d{2}):(\d{2}))$").unwrap()
+});
+static EMAIL: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"^[^\s@]+@[A-Za-z0-9]([A-Za-z0-9-]*[A-Za-z0-9])?(\.[A-Za-z0-9]([A-Za-z0-9-]*[A-Za-z0-9])?)*$").unwrap()
+});
+static HOSTNAME: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"^[A-Za-z0-9]([A-Za-z0-9-]{0,61}[A-Za-z0-9])?(\.[A-Za-z0-9]([A-Za-z0-9-]{0,61}[A-Za-z0-9])?)*$").unwrap()
+});
+static URI: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Za-z][A-Za-z0-9+.-]*:[^\s]*$").unwrap());
+static UUID: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$").unwrap()
+});
+
+/// Maximum number of compiled patterns that are cached
+const PATTERN_CACHE_SIZE: usize = 1024;
+
+/// Compiled patterns (or the compile errors), keyed by the pattern
+static PATTERNS: LazyLock<Mutex<HashMap<String, Result<Regex, String>>>> = LazyLock::new(Default::default);
+
+/// State of a validation
+struct Context<'a> {
+  /// Root schema, which references are resolved against
+  root: &'a Value,
+  /// References that are being followed, with the address of the value they are applied to
+  references: Vec<(&'a str, *const Value)>
+}
+
+/// Validates the value against the JSON Schema, returning all the locations that do not match
+pub fn validate_json_schema(schema: &Value, value: &Value) -> Vec<SchemaViolation> {
+  let mut violations = vec![];
+  let mut context = Context { root: schema, references: vec![] };
+  validate_value(&mut context, schema, value, "", &mut violations);
+  violations
+}
+
+/// Escapes a key so it can be used as a JSON Pointer token
+pub fn escape_pointer_token(key: &str) -> String {
+  key.replace('~', "~0").replace('/', "~1")
+}
+
+fn violation(path: &str, message: String, violations: &mut Vec<SchemaViolation>) {
+  violations.push(SchemaViolation { path: path.to_string(), message });
+}
+
+fn resolve_ref<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+  reference.strip_prefix('#').and_then(|pointer| root.pointer(pointer))
+}
+
+/// Returns the compiled pattern from the cache, compiling it if it is not cached
+fn compile_pattern(pattern: &str) -> Result<Regex, String> {
+  let mut patterns = PATTERNS.lock().unwrap_or_else(|err| err.into_inner());
+  if let Some(result) = patterns.get(pattern) {
+    return result.clone();
+  }
+  let result = Regex::new(pattern).map_err(|err| err.to_string());
+  if patterns.len() >= PATTERN_CACHE_SIZE {
+    patterns.clear();
+  }
+  patterns.insert(pattern.to_string(), result.clone());
+  result
+}
+
+fn type_matches(type_name: &str, value: &Value) -> bool {
+  match type_name {
+    "null" => value.is_null(),
+    "boolean" => value.is_boolean(),
+    "object" => value.is_object(),
+    "array" => value.is_array(),
+    "string" => value.is_string(),
+    "number" => value.is_number(),
+    "integer" => value.is_i64() || value.is_u64() || value.as_f64().map(|f| f.fract() == 0.0).unwrap_or_default(),
+    _ => false
+  }
+}
+
+fn validate_value<'a>(
+  context: &mut Context<'a>,
+  schema: &'a Value,
+  value: &Value,
+  path: &str,
+  violations: &mut Vec<SchemaViolation>
+) {
+  let schema = match schema {
+    Value::Bool(true) => return,
+    Value::Bool(false) => {
+      violation(path, "No values are allowed here".to_string(), violations);
+      return;
+    }
+    Value::Object(schema) => schema,
+    _ => return
+  };
+
+  if let Some(reference) = schema.get("$ref").and_then(|r| r.as_str()) {
+    let key = (reference, value as *const Value);
+    if context.references.contains(&key) {
+      violation(path, format!("Schema reference '{}' refers to itself for the same value", reference), violations);
+      return;
+    }
+    match resolve_ref(context.root, reference) {
+      Some(resolved) => {
+        context.references.push(key);
+        validate_value(context, resolved, value, path, violations);
+        context.references.pop();
+      }
+      None => violation(path, format!("Schema reference '{}' could not be resolved", reference), violations)
+    }
+  }
+
+  match schema.get("type") {
+    Some(Value::String(type_name)) if !type_matches(type_name, value) => {
+      violation(path, format!("Expected a value of type {}, got {}", type_name, json_type_name(value)), violations);
+      return;
+    }
+    Some(Value::Array(types)) => {
+      let names = types.iter().filter_map(|t| t.as_str()).collect::<Vec<_>>();
+      if !names.iter().any(|name| type_matches(name, value)) {
+        violation(path, format!("Expected a value of type {}, got {}", names.join(" or "), json_type_name(value)), violations);
+        return;
+      }
+    }
+    _ => {}
+  }
+
+  if let Some(Value::Array(values)) = schema.get("enum") && !values.contains(value) {
+    violation(path, format!("Value {} is not one of the allowed values", value), violations);
+  }
+  if let Some(expected) = schema.get("const") && expected != value {
+    violation(path, format!("Expected the value {}, got {}", expected, value), violations);
+  }
+
+  match value {
+    Value::Number(_) => validate_number(schema, value.as_f64().unwrap_or_default(), path, violations),
+    Value::String(s) => validate_string(schema, s, path, violations),
+    Value::Array(items) => validate_array(context, schema, items, path, violations),
+    Value::Object(map) => validate_object(context, schema, map, path, violations),
+    _ => {}
+  }
+
+  validate_combinations(context, schema, value, path, violations);
+}
+
+fn validate_number(schema: &Map<String, Value>, number: f64, path: &str, violations: &mut Vec<SchemaViolation>) {
+  if let Some(minimum) = schema.get("minimum").and_then(|m| m.as_f64()) && number < minimum {
+    violation(path, format!("Value {} is less than the minimum of {}", number, minimum), violations);
+  }
+  if let Some(maximum) = schema.get("maximum").and_then(|m| m.as_f64()) && number > maximum {
+    violation(path, format!("Value {} is greater than the maximum of {}", number, maximum), violations);
+  }
+  if let Some(minimum) = schema.get("exclusiveMinimum").and_then(|m| m.as_f64()) && number <= minimum {
+    violation(path, format!("Value {} must be greater than {}", number, minimum), violations);
+  }
+  if let Some(maximum) = schema.get("exclusiveMaximum").and_then(|m| m.as_f64()) && number >= maximum {
+    violation(path, format!("Value {} must be less than {}", number, maximum), violations);
+  }
+  if let Some(multiple) = schema.get("multipleOf").and_then(|m| m.as_f64()) &&
+    multiple > 0.0 && (number / multiple).fract() != 0.0 {
+    violation(path, format!("Value {} is not a multiple of {}", number, multiple), violations);
+  }
+}
+
+fn validate_string(schema: &Map<String, Value>, string: &str, path: &str, violations: &mut Vec<SchemaViolation>) {
+  let length = string.chars().count() as u64;
+  if let Some(min) = schema.get("minLength").and_then(|m| m.as_u64()) && length < min {
+    violation(path, format!("String is shorter than the minimum length of {}", min), violations);
+  }
+  if let Some(max) = schema.get("maxLength").and_then(|m| m.as_u64()) && length > max {
+    violation(path, format!("String is longer than the maximum length of {}", max), violations);
+  }
+  if let Some(pattern) = schema.get("pattern").and_then(|p| p.as_str()) {
+    match compile_pattern(pattern) {
+      Ok(regex) if !regex.is_match(string) =>
+        violation(path, format!("String does not match the pattern '{}'", pattern), violations),
+      Ok(_) => {}
+      Err(err) =>
+        violation(path, format!("Pattern '{}' is not a valid regular expression: {}", pattern, err), violations)
+    }
+  }
+  if let Some(format) = schema.get("format").and_then(|f| f.as_str()) &&
+    matches_format(format, string) == Some(false) {
+    violation(path, format!("String is not a valid {}", format), violations);
+  }
+}
+
+/// If the string matches the format. Returns `None` for formats that are not checked.
+fn matches_format(format: &str, string: &str) -> Option<bool> {
+  let matches = match format {
+    "date-time" => string.split_once(['T', 't', ' '])
+      .is_some_and(|(date, time)| is_date(date) && is_time(time)),
+    "date" => is_date(string),
+    "time" => is_time(string),
+    "email" => EMAIL.is_match(string),
+    "hostname" => string.len() <= 253 && HOSTNAME.is_match(string),
+    "ipv4" => string.parse::<Ipv4Addr>().is_ok(),
+    "ipv6" => string.parse::<Ipv6Addr>().is_ok(),
+    "uri" => URI.is_match(string),
+    "uuid" => UUID.is_match(string),
+    "regex" => Regex::new(string).is_ok(),
+    _ => return None
+  };
+  Some(matches)
+}
+
+/// If the string is a full date (RFC 3339)
+fn is_date(string: &str) -> bool {
+  DATE.captures(string).is_some_and(|captures| {
+    let number = |index: usize| captures[index].parse::<u32>().unwrap_or_default();
+    let (year, month, day) = (number(1), number(2), number(3));
+    let days = match month {
+      2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+      2 => 28,
+      4 | 6 | 9 | 11 => 30,
+      _ => 31
+    };
+    (1..=12).contains(&month) && (1..=days).contains(&day)
+  })
+}
+
+/// If the string is a full time with a time zone offset (RFC 3339)
+fn is_time(string: &str) -> bool {
+  TIME.captures(string).is_some_and(|captures| {
+    let number = |index: usize| captures.get(index)
+      .map(|value| value.as_str().parse::<u32>().unwrap_or_default())
+      .unwrap_or_default();
+    number(1) < 24 && number(2) < 60 && number(3) <= 60 && number(6) < 24 && number(7) < 60
+  })
+}
+
+fn validate_array<'a>(
+  context: &mut Context<'a>,
+  schema: &'a Map<String, Value>,
+  items: &[Value],
+  path: &str,
+  violations: &mut Vec<SchemaViolation>
+) {
+  let length = items.len() as u64;
+  if let Some(min) = schema.get("minItems").and_then(|m| m.as_u64()) && length < min {
+    violation(path, format!("Array has less than the minimum of {} items", min), violations);
+  }
+  if let Some(max) = schema.get("maxItems").and_then(|m| m.as_u64()) && length > max {
+    violation(path, format!("Array has more than the maximum of {} items", max), violations);
+  }
+  if schema.get("uniqueItems").and_then(|u| u.as_bool()).unwrap_or_default() {
+    let mut seen = HashSet::new();
+    for (index, item) in items.iter().enumerate() {
+      if !seen.insert(item.to_string()) {
+        violation(&format!("{}/{}", path, index), "Array items must be unique".to_string(), violations);
+      }
+    }
+  }
+
+  // Tuple validation can be specified with prefixItems (2020-12) or an array of items (draft 7)
+  let (prefix_items, items_schema) = match (schema.get("prefixItems"), schema.get("items")) {
+    (Some(Value::Array(prefix)), items) => (prefix.as_slice(), items),
+    (_, Some(Value::Array(prefix))) => (prefix.as_slice(), schema.get("additionalItems")),
+    (_, items) => (&[][..], items)
+  };
+  for (index, item) in items.iter().enumerate() {
+    let item_schema = prefix_items.get(index).or(items_schema);
+    if let Some(item_schema) = item_schema {
+      validate_value(context, item_schema, item, &format!("{}/{}", path, index), violations);
+    }
+  }
+}
+
+fn validate_object<'a>(
+  context: &mut Context<'a>,
+  schema: &'a Map<String, Value>,
+  map: &Map<String, Value>,
+  path: &str,
+  violations: &mut Vec<SchemaViolation>
+) {
+  let length = map.len() as u64;
+  if let Some(min) = schema.get("minProperties").and_then(|m| m.as_u64()) && length < min {
+    violation(path, format!("Object has less than the minimum of {} properties", min), violations);
+  }
+  if let Some(max) = schema.get("maxProperties").and_then(|m| m.as_u64()) && length > max {
+    violation(path, format!("Object has more than the maximum of {} properties", max), violations);
+  }
+  if let Some(Value::Array(required)) = schema.get("required") {
+    for name in required.iter().filter_map(|r| r.as_str()) {
+      if !map.contains_key(name) {
+        violation(path, format!("Required property '{}' is missing", name), violations);
+      }
+    }
+  }
+
+  let properties = schema.get("properties").and_then(|p| p.as_object());
+  for (key, value) in map {
+    let property_path = format!("{}/{}", path, escape_pointer_token(key));
+    if let Some(property_schema) = properties.and_then(|p| p.get(key)) {
+      validate_value(context, property_schema, value, &property_path, violations);
+    } else {
+      match schema.get("additionalProperties") {
+        Some(Value::Bool(false)) => violation(&property_path,
+          format!("Property '{}' is not allowed", key), violations),
+        Some(additional) => validate_value(context, additional, value, &property_path, violations),
+        None => {}
+      }
+    }
+  }
+}
+
+fn validate_combinations<'a>(
+  context: &mut Context<'a>,
+  schema: &'a Map<String, Value>,
+  value: &Value,
+  path: &str,
+  violations: &mut Vec<SchemaViolation>
+) {
+  if let Some(Value::Array(schemas)) = schema.get("allOf") {
+    for sub_schema in schemas {
+      validate_value(context, sub_schema, value, path, violations);
+    }
+  }
+
+  let mut matches = |schemas: &'a Vec<Value>| {
+    schemas.iter()
+      .filter(|sub_schema| {
+        let mut sub_violations = vec![];
+        validate_value(context, sub_schema, value, path, &mut sub_violations);
+        sub_violations.is_empty()
+      })
+      .count()
+  };
+  if let Some(Value::Array(schemas)) = schema.get("anyOf") && matches(schemas) == 0 {
+    violation(path, "Value does not match any of the schemas (anyOf)".to_string(), violations);
+  }
+  if let Some(Value::Array(schemas)) = schema.get("oneOf") {
+    let count = matches(schemas);
+    if count != 1 {
+      violation(path, format!("Value must match exactly one of the schemas (oneOf), but matched {}", count), violations);
+    }
+  }
+  if let Some(not) = schema.get("not") {
+    let mut sub_violations = vec![];
+    validate_value(context, not, value, path, &mut sub_violations);
+    if sub_violations.is_empty() {
+      violation(path, "Value must not match the schema (not)".to_string(), violations);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::json_schema::{PATTERNS, validate_json_schema, SchemaViolation};
+
+  fn messages(violations: Vec<SchemaViolation>) -> Vec<String> {
+    violations.iter().map(|v| v.to_string()).collect()
+  }
+
+  #[test]
+  fn validates_types_and_values() {
+    expect!(validate_json_schema(&json!({ "type": "string" }), &json!("a"))).to(be_equal_to(vec![]));
+    expect!(messages(validate_json_schema(&json!({ "type": "string" }), &json!(1))))
+      .to(be_equal_to(vec!["Expected a value of type string, got Number".to_string()]));
+    expect!(validate_json_schema(&json!({ "type": ["integer", "null"] }), &json!(null))).to(be_equal_to(vec![]));
+    expect!(validate_json_schema(&json!({ "type": "integer" }), &json!(1.0))).to(be_equal_to(vec![]));
+    expect!(validate_json_schema(&json!({ "type": "integer" }), &json!(1.5)).len()).to(be_equal_to(1));
+    expect!(validate_json_schema(&json!({ "enum": ["a", "b"] }), &json!("c")).len()).to(be_equal_to(1));
+    expect!(validate_json_schema(&json!({ "const": 1 }), &json!(1))).to(be_equal_to(vec![]));
+    expect!(validate_json_schema(&json!({ "minimum": 1, "exclusiveMaximum": 10, "multipleOf": 2 }), &json!(10)).len())
+      .to(be_equal_to(1));
+    expect!(validate_json_schema(&json!({ "minLength": 2, "maxLength": 3 }), &json!("abcd")).len()).to(be_equal_to(1));
+    expect!(validate_json_schema(&json!(false), &json!(1)).len()).to(be_equal_to(1));
+    expect!(validate_json_schema(&json!(true), &json!(1))).to(be_equal_to(vec![]));
+  }
+
+  #[test]
+  fn validates_patterns() {
+    let schema = json!({ "type": "string", "pattern": "^[a-z]+-\\d+$" });
+    expect!(validate_json_schema(&schema, &json!("pet-1"))).to(be_equal_to(vec![]));
+    expect!(messages(validate_json_schema(&schema, &json!("Pet 1")))).to(be_equal_to(vec![
+      "String does not match the pattern '^[a-z]+-\\d+$'".to_string()
+    ]));
+    expect!(validate_json_schema(&json!({ "pattern": "\\d" }), &json!("a1b"))).to(be_equal_to(vec![]));
+    expect!(validate_json_schema(&json!({ "pattern": "(" }), &json!("a"))[0].message.as_str())
+      .to(be_equal_to("Pattern '(' is not a valid regular expression: regex parse error:\n    (\n    ^\nerror: unclosed group"));
+
+    let schema = json!({ "items": { "pattern": "^[a-z]+$" } });
+    expect!(messages(validate_json_schema(&schema, &json!(["a", "b", "C"])))).to(be_equal_to(vec![
+      "/2: String does not match the pattern '^[a-z]+$'".to_string()
+    ]));
+    expect!(PATTERNS.lock().unwrap().contains_key("^[a-z]+$")).to(be_true());
+  }
+
+  #[test]
+  fn reports_references_that_refer_to_themselves() {
+    expect!(messages(validate_json_schema(&json!({ "$ref": "#" }), &json!(1)))).to(be_equal_to(vec![
+      "Schema reference '#' refers to itself for the same value".to_string()
+    ]));
+    let schema = json!({
+      "$defs": { "a": { "$ref": "#/$defs/b" }, "b": { "anyOf": [{ "$ref": "#/$defs/a" }] } },
+      "$ref": "#/$defs/a"
+    });
+    expect!(validate_json_schema(&schema, &json!(1)).is_empty()).to(be_false());
+
+    // Recursive schemas are applied to each level of nested values
+    let tree = json!({
+      "type": "object",
+      "properties": { "children": { "type": "array", "items": { "$ref": "#" } } }
+    });
+    expect!(validate_json_schema(&tree, &json!({ "children": [{ "children": [] }] }))).to(be_equal_to(vec![]));
+    expect!(messages(validate_json_schema(&tree, &json!({ "children": [{ "children": [1] }] })))).to(be_equal_to(vec![
+      "/children/0/children/0: Expected a value of type object, got Number".to_string()
+    ]));
+  }
+
+  #[test]
+  fn validates_formats() {
+    let valid = [
+      ("date-time", "2024-02-29T10:15:30.5Z"),
+      ("date-time", "2024-01-31 23:59:60+10:00"),
+      ("date", "2024-12-31"),
+      ("time", "08:30:00-05:00"),
+      ("email", "pets@example.com"),
+      ("hostname", "api.example.com"),
+      ("ipv4", "192.168.0.1"),
+      ("ipv6", "::1"),
+      ("uri", "https://example.com/pets?id=1"),
+      ("uuid", "123e4567-e89b-12d3-a456-426614174000"),
+      ("regex", "^a+$"),
+      ("custom", "anything")
+    ];
+    for (format, value) in valid {
+      expect!(validate_json_schema(&json!({ "format": format }), &json!(value))).to(be_equal_to(vec![]));
+    }
+
+    let invalid = [
+      ("date-time", "2024-02-30T10:15:30Z"),
+      ("date-time", "2024-01-01T10:15:30"),
+      ("date", "2023-02-29"),
+      ("time", "24:00:00Z"),
+      ("email", "pets.example.com"),
+      ("hostname", "-api.example.com"),
+      ("ipv4", "256.0.0.1"),
+      ("ipv6", "::g"),
+      ("uri", "/pets"),
+      ("uuid", "123e4567"),
+      ("regex", "(")
+    ];
+    for (format, value) in invalid {
+      expect!(messages(validate_json_schema(&json!({ "format": format }), &json!(value))))
+        .to(be_equal_to(vec![format!("String is not a valid {}", format)]));
+    }
+    expect!(validate_json_schema(&json!({ "format": "date" }), &json!(1))).to(be_equal_to(vec![]));
+  }
+
+  #[test]
+  fn validates_objects_and_arrays_with_locations() {
+    let schema = json!({
+      "type": "object",
+      "required": ["name", "tags"],
+      "properties": {
+        "name": { "type": "string" },
+        "tags": { "type": "array", "items": { "$ref": "#/$defs/tag" }, "maxItems": 2, "uniqueItems": true }
+      },
+      "additionalProperties": false,
+      "$defs": {
+        "tag": { "type": "string", "minLength": 1 }
+      }
+    });
+    expect!(validate_json_schema(&schema, &json!({ "name": "a", "tags": ["b"] }))).to(be_equal_to(vec![]));
+    expect!(messages(validate_json_schema(&schema, &json!({ "tags": ["b", "", "b"], "a/b": 1 })))).to(be_equal_to(vec![
+      "Required property 'name' is missing".to_string(),
+      "/a~1b: Property 'a/b' is not allowed".to_string(),
+      "/tags: Array has more than the maximum of 2 items".to_string(),
+      "/tags/2: Array items must be unique".to_string(),
+      "/tags/1: String is shorter than the minimum length of 1".to_string()
+    ]));
+
+    let tuple = json!({ "prefixItems": [{ "type": "string" }], "items": { "type": "integer" } });
+    expect!(messages(validate_json_schema(&tuple, &json!(["a", 1, "c"])))).to(be_equal_to(vec![
+      "/2: Expected a value of type integer, got String".to_string()
+    ]));
+  }
+
+  #[test]
+  fn validates_combinations() {
+    let schema = json!({ "oneOf": [{ "type": "string" }, { "minLength": 1 }] });
+    expect!(validate_json_schema(&schema, &json!(1))).to(be_equal_to(vec![]));
+    expect!(messages(validate_json_schema(&schema, &json!("a")))).to(be_equal_to(vec![
+      "Value must match exactly one of the schemas (oneOf), but matched 2".to_string()
+    ]));
+    expect!(validate_json_schema(&json!({ "anyOf": [{ "type": "string" }, { "type": "null" }] }), &json!(1)).len())
+      .to(be_equal_to(1));
+    expect!(validate_json_schema(&json!({ "allOf": [{ "minimum": 1 }, { "maximum": 2 }] }), &json!(3)).len())
+      .to(be_equal_to(1));
+    expect!(validate_json_schema(&json!({ "not": { "type": "null" } }), &json!(null)).len()).to(be_equal_to(1));
+    expect!(messages(validate_json_schema(&json!({ "$ref": "#/missing" }), &json!(null)))).to(be_equal_to(vec![
+      "Schema reference '#/missing' could not be resolved".to_string()
+    ]));
+  }
+}
@@ -0,0 +1,568 @@
+//! Minimal JSONPath ([RFC 9535](https://www.rfc-editor.org/rfc/rfc9535)) query implementation,
+//! used to evaluate `jsonpath` criteria. Supports the root (`$`), names (`.name` and `['name']`),
+//! wildcards (`*`), indices (`[0]` and `[-1]`), slices (`[1:3]`), unions (`[0,1]`), descendants
+//! (`..name`) and filters (`[?@.status == 'available']`) with comparisons, `&&`, `||`, `!` and the
+//! `length()` and `count()` functions.
+//!
+//! Note that, to support the usage in the Arazzo specification (i.e. `$[?count(@.pets) > 0]`),
+//! filters applied to an object test the object itself rather than the values of its members.
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+  Name(String),
+  Wildcard,
+  Index(i64),
+  Slice(Option<i64>, Option<i64>, Option<i64>),
+  Filter(Box<FilterExpression>)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Segment {
+  descendant: bool,
+  selectors: Vec<Selector>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Query {
+  relative: bool,
+  segments: Vec<Segment>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+  Literal(Value),
+  Query(Query),
+  Function(String, Vec<Operand>)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FilterExpression {
+  Or(Box<FilterExpression>, Box<FilterExpression>),
+  And(Box<FilterExpression>, Box<FilterExpression>),
+  Not(Box<FilterExpression>),
+  Compare(Operand, String, Operand),
+  Test(Operand)
+}
+
+/// Evaluates the JSONPath query against the JSON document, returning the selected values
+pub(crate) fn query<'a>(json: &'a Value, path: &str) -> anyhow::Result<Vec<&'a Value>> {
+  let query = parse(path)?;
+  Ok(evaluate_query(&query, json, json))
+}
+
+/// Evaluates the JSONPath query against the JSON document, returning the locations of the selected
+/// values as JSON Pointers
+#[cfg(all(feature = "json", feature = "serialize"))]
+pub(crate) fn query_pointers(json: &Value, path: &str) -> anyhow::Result<Vec<String>> {
+  let query = parse(path)?;
+  Ok(evaluate_located(&query, json, json).into_iter()
+    .map(|(location, _)| location.iter()
+      .map(|token| format!("/{}", token.replace('~', "~0").replace('/', "~1")))
+      .collect())
+    .collect())
+}
+
+fn parse(path: &str) -> anyhow::Result<Query> {
+  let mut parser = Parser { chars: path.trim().chars().collect(), pos: 0 };
+  let query = parser.parse_query()?;
+  if !query.relative && parser.pos == parser.chars.len() {
+    Ok(query)
+  } else {
+    Err(anyhow!("Invalid JSONPath '{}': expected a query starting with '$'", path))
+  }
+}
+
+struct Parser {
+  chars: Vec<char>,
+  pos: usize
+}
+
+impl Parser {
+  fn peek(&self) -> Option<char> {
+    self.chars.get(self.pos).copied()
+  }
+
+  fn skip_whitespace(&mut self) {
+    while self.peek().is_some_and(|ch| ch.is_whitespace()) {
+      self.pos += 1;
+    }
+  }
+
+  fn consume(&mut self, text: &str) -> bool {
+    let chars = text.chars().collect::<Vec<_>>();
+    if self.chars[self.pos..].starts_with(&chars) {
+      self.pos += chars.len();
+      true
+    } else {
+      false
+    }
+  }
+
+  fn error(&self, message: &str) -> anyhow::Error {
+    anyhow!("Invalid JSONPath '{}': {} at position {}", self.chars.iter().collect::<String>(), message, self.pos)
+  }
+
+  fn parse_query(&mut self) -> anyhow::Result<Query> {
+    let relative = match self.peek() {
+      Some('$') => false,
+      Some('@') => true,
+      _ => return Err(self.error("expected '$' or '@'"))
+    };
+    self.pos += 1;
+
+    let mut segments = vec![];
+    loop {
+      if self.consume("..") {
+        let selectors = if self.peek() == Some('[') {
+          self.parse_bracketed()?
+        } else {
+          vec![self.parse_member_name()?]
+        };
+        segments.push(Segment { descendant: true, selectors });
+      } else if self.consume(".") {
+        segments.push(Segment { descendant: false, selectors: vec![self.parse_member_name()?] });
+      } else if self.peek() == Some('[') {
+        segments.push(Segment { descendant: false, selectors: self.parse_bracketed()? });
+      } else {
+        break;
+      }
+    }
+    Ok(Query { relative, segments })
+  }
+
+  fn parse_member_name(&mut self) -> anyhow::Result<Selector> {
+    if self.consume("*") {
+      return Ok(Selector::Wildcard);
+    }
+    let start = self.pos;
+    while self.peek().is_some_and(|ch| ch.is_alphanumeric() || ch == '_' || ch == '-' || ch as u32 > 0x7F) {
+      self.pos += 1;
+    }
+    if start == self.pos {
+      Err(self.error("expected a member name"))
+    } else {
+      Ok(Selector::Name(self.chars[start..self.pos].iter().collect()))
+    }
+  }
+
+  fn parse_bracketed(&mut self) -> anyhow::Result<Vec<Selector>> {
+    self.pos += 1;
+    let mut selectors = vec![];
+    loop {
+      self.skip_whitespace();
+      selectors.push(self.parse_selector()?);
+      self.skip_whitespace();
+      if self.consume(",") {
+        continue;
+      }
+      if self.consume("]") {
+        return Ok(selectors);
+      }
+      return Err(self.error("expected ',' or ']'"));
+    }
+  }
+
+  fn parse_selector(&mut self) -> anyhow::Result<Selector> {
+    match self.peek() {
+      Some('\'') | Some('"') => Ok(Selector::Name(self.parse_string()?)),
+      Some('*') => {
+        self.pos += 1;
+        Ok(Selector::Wildcard)
+      }
+      Some('?') => {
+        self.pos += 1;
+        self.skip_whitespace();
+        Ok(Selector::Filter(Box::new(self.parse_or()?)))
+      }
+      _ => {
+        let start = self.parse_integer()?;
+        self.skip_whitespace();
+        if self.consume(":") {
+          self.skip_whitespace();
+          let end = self.parse_integer()?;
+          self.skip_whitespace();
+          let step = if self.consume(":") {
+            self.skip_whitespace();
+            self.parse_integer()?
+          } else {
+            None
+          };
+          Ok(Selector::Slice(start, end, step))
+        } else {
+          start.map(Selector::Index).ok_or_else(|| self.error("expected a selector"))
+        }
+      }
+    }
+  }
+
+  fn parse_integer(&mut self) -> anyhow::Result<Option<i64>> {
+    let start = self.pos;
+    if self.peek() == Some('-') {
+      self.pos += 1;
+    }
+    while self.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+      self.pos += 1;
+    }
+    if start == self.pos {
+      Ok(None)
+    } else {
+      self.chars[start..self.pos].iter().collect::<String>().parse::<i64>()
+        .map(Some)
+        .map_err(|_| self.error("invalid integer"))
+    }
+  }
+
+  fn parse_string(&mut self) -> anyhow::Result<String> {
+    let quote = self.peek().unwrap_or_default();
+    self.pos += 1;
+    let mut value = String::new();
+    loop {
+      match self.peek() {
+        Some('\\') => {
+          self.pos += 1;
+          match self.peek() {
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some(ch) => value.push(ch),
+            None => return Err(self.error("unterminated string"))
+          }
+        }
+        Some(ch) if ch == quote => {
+          self.pos += 1;
+          return Ok(value);
+        }
+        Some(ch) => value.push(ch),
+        None => return Err(self.error("unterminated string"))
+      }
+      self.pos += 1;
+    }
+  }
+
+  fn parse_or(&mut self) -> anyhow::Result<FilterExpression> {
+    let mut expression = self.parse_and()?;
+    loop {
+      self.skip_whitespace();
+      if self.consume("||") {
+        self.skip_whitespace();
+        expression = FilterExpression::Or(Box::new(expression), Box::new(self.parse_and()?));
+      } else {
+        return Ok(expression);
+      }
+    }
+  }
+
+  fn parse_and(&mut self) -> anyhow::Result<FilterExpression> {
+    let mut expression = self.parse_unary()?;
+    loop {
+      self.skip_whitespace();
+      if self.consume("&&") {
+        self.skip_whitespace();
+        expression = FilterExpression::And(Box::new(expression), Box::new(self.parse_unary()?));
+      } else {
+        return Ok(expression);
+      }
+    }
+  }
+
+  fn parse_unary(&mut self) -> anyhow::Result<FilterExpression> {
+    self.skip_whitespace();
+    if self.peek() == Some('!') && self.chars.get(self.pos + 1) != Some(&'=') {
+      self.pos += 1;
+      return Ok(FilterExpression::Not(Box::new(self.parse_unary()?)));
+    }
+    if self.consume("(") {
+      let expression = self.parse_or()?;
+      self.skip_whitespace();
+      if !self.consume(")") {
+        return Err(self.error("expected ')'"));
+      }
+      return Ok(expression);
+    }
+
+    let left = self.parse_operand()?;
+    self.skip_whitespace();
+    for op in ["==", "!=", "<=", ">=", "<", ">"] {
+      if self.consume(op) {
+        self.skip_whitespace();
+        let right = self.parse_operand()?;
+        return Ok(FilterExpression::Compare(left, op.to_string(), right));
+      }
+    }
+    Ok(FilterExpression::Test(left))
+  }
+
+  fn parse_operand(&mut self) -> anyhow::Result<Operand> {
+    match self.peek() {
+      Some('$') | Some('@') => Ok(Operand::Query(self.parse_query()?)),
+      Some('\'') | Some('"') => Ok(Operand::Literal(Value::String(self.parse_string()?))),
+      Some(ch) if ch == '-' || ch.is_ascii_digit() => {
+        let start = self.pos;
+        self.pos += 1;
+        while self.peek().is_some_and(|ch| ch.is_ascii_digit() || ch == '.' || ch == 'e' || ch == 'E' || ch == '+' || ch == '-') {
+          self.pos += 1;
+        }
+        let number = self.chars[start..self.pos].iter().collect::<String>();
+        serde_json::from_str::<Value>(&number)
+          .map(Operand::Literal)
+          .map_err(|_| self.error("invalid number"))
+      }
+      Some(ch) if ch.is_ascii_alphabetic() => {
+        let start = self.pos;
+        while self.peek().is_some_and(|ch| ch.is_ascii_alphanumeric() || ch == '_') {
+          self.pos += 1;
+        }
+        let name = self.chars[start..self.pos].iter().collect::<String>();
+        match name.as_str() {
+          "true" => Ok(Operand::Literal(Value::Bool(true))),
+          "false" => Ok(Operand::Literal(Value::Bool(false))),
+          "null" => Ok(Operand::Literal(Value::Null)),
+          _ => {
+            self.skip_whitespace();
+            if !self.consume("(") {
+              return Err(self.error(&format!("unknown literal '{}'", name)));
+            }
+            let mut arguments = vec![];
+            loop {
+              self.skip_whitespace();
+              if self.consume(")") {
+                break;
+              }
+              arguments.push(self.parse_operand()?);
+              self.skip_whitespace();
+              if !self.consume(",") && self.peek() != Some(')') {
+                return Err(self.error("expected ',' or ')'"));
+              }
+            }
+            Ok(Operand::Function(name, arguments))
+          }
+        }
+      }
+      _ => Err(self.error("expected a value"))
+    }
+  }
+}
+
+fn evaluate_query<'a>(query: &Query, root: &'a Value, current: &'a Value) -> Vec<&'a Value> {
+  evaluate_located(query, root, current).into_iter().map(|(_, value)| value).collect()
+}
+
+/// Evaluates the query, returning the selected values with their locations (the tokens of a
+/// JSON Pointer, relative to the start of the query)
+fn evaluate_located<'a>(query: &Query, root: &'a Value, current: &'a Value) -> Vec<(Vec<String>, &'a Value)> {
+  let start = if query.relative { current } else { root };
+  query.segments.iter().fold(vec![(vec![], start)], |nodes, segment| {
+    let nodes = if segment.descendant {
+      nodes.into_iter().flat_map(|(location, node)| descendants(location, node)).collect()
+    } else {
+      nodes
+    };
+    nodes.into_iter()
+      .flat_map(|(location, node)| segment.selectors.iter()
+        .flat_map(move |selector| select(selector, root, node))
+        .map(move |(token, value)| {
+          let mut location = location.clone();
+          location.extend(token);
+          (location, value)
+        }))
+      .collect()
+  })
+}
+
+fn descendants(location: Vec<String>, value: &Value) -> Vec<(Vec<String>, &Value)> {
+  let mut values = vec![(location.clone(), value)];
+  match value {
+    Value::Array(array) => values.extend(array.iter().enumerate()
+      .flat_map(|(index, item)| descendants(child_location(&location, index.to_string()), item))),
+    Value::Object(map) => values.extend(map.iter()
+      .flat_map(|(key, item)| descendants(child_location(&location, key.clone()), item))),
+    _ => {}
+  }
+  values
+}
+
+fn child_location(location: &[String], token: String) -> Vec<String> {
+  let mut location = location.to_vec();
+  location.push(token);
+  location
+}
+
+/// Values selected by the selector, with the token for the location of each value (filters
+/// applied to an object select the object itself, so there is no token)
+fn select<'a>(selector: &Selector, root: &'a Value, value: &'a Value) -> Vec<(Option<String>, &'a Value)> {
+  match (selector, value) {
+    (Selector::Name(name), Value::Object(map)) => map.get_key_value(name)
+      .map(|(key, value)| (Some(key.clone()), value))
+      .into_iter()
+      .collect(),
+    (Selector::Wildcard, Value::Object(map)) => map.iter().map(|(key, value)| (Some(key.clone()), value)).collect(),
+    (Selector::Wildcard, Value::Array(array)) => array.iter().enumerate()
+      .map(|(index, value)| (Some(index.to_string()), value))
+      .collect(),
+    (Selector::Index(index), Value::Array(array)) => {
+      let index = if *index < 0 { array.len() as i64 + index } else { *index };
+      usize::try_from(index).ok()
+        .and_then(|index| array.get(index).map(|value| (Some(index.to_string()), value)))
+        .into_iter()
+        .collect()
+    }
+    (Selector::Slice(start, end, step), Value::Array(array)) => {
+      let len = array.len() as i64;
+      let step = step.unwrap_or(1);
+      let normalize = |index: i64| if index < 0 { (len + index).max(0) } else { index.min(len) };
+      let mut values = vec![];
+      if step > 0 {
+        let mut index = normalize(start.unwrap_or(0));
+        let end = normalize(end.unwrap_or(len));
+        while index < end {
+          values.push((Some(index.to_string()), &array[index as usize]));
+          index += step;
+        }
+      } else if step < 0 {
+        let mut index = start.map(normalize).unwrap_or(len - 1).min(len - 1);
+        let end = end.map(normalize).unwrap_or(-1);
+        while index > end && index >= 0 {
+          values.push((Some(index.to_string()), &array[index as usize]));
+          index += step;
+        }
+      }
+      values
+    }
+    (Selector::Filter(filter), Value::Array(array)) => array.iter().enumerate()
+      .filter(|(_, item)| test_filter(filter, root, item))
+      .map(|(index, item)| (Some(index.to_string()), item))
+      .collect(),
+    (Selector::Filter(filter), Value::Object(_)) => if test_filter(filter, root, value) {
+      vec![(None, value)]
+    } else {
+      vec![]
+    },
+    _ => vec![]
+  }
+}
+
+fn test_filter(filter: &FilterExpression, root: &Value, current: &Value) -> bool {
+  match filter {
+    FilterExpression::Or(a, b) => test_filter(a, root, current) || test_filter(b, root, current),
+    FilterExpression::And(a, b) => test_filter(a, root, current) && test_filter(b, root, current),
+    FilterExpression::Not(a) => !test_filter(a, root, current),
+    FilterExpression::Test(operand) => match operand {
+      Operand::Query(query) => !evaluate_query(query, root, current).is_empty(),
+      _ => matches!(operand_value(operand, root, current), Some(value) if value != Value::Bool(false) && value != Value::Null)
+    },
+    FilterExpression::Compare(left, op, right) => {
+      let left = operand_value(left, root, current);
+      let right = operand_value(right, root, current);
+      compare(left.as_ref(), op, right.as_ref())
+    }
+  }
+}
+
+/// Value of the operand. Queries must select a single value.
+fn operand_value(operand: &Operand, root: &Value, current: &Value) -> Option<Value> {
+  match operand {
+    Operand::Literal(value) => Some(value.clone()),
+    Operand::Query(query) => {
+      let values = evaluate_query(query, root, current);
+      if values.len() == 1 { Some(values[0].clone()) } else { None }
+    }
+    Operand::Function(name, arguments) => match (name.as_str(), arguments.as_slice()) {
+      ("count", [Operand::Query(query)]) => Some(Value::from(evaluate_query(query, root, current).len())),
+      ("length", [argument]) => match operand_value(argument, root, current)? {
+        Value::String(s) => Some(Value::from(s.chars().count())),
+        Value::Array(array) => Some(Value::from(array.len())),
+        Value::Object(map) => Some(Value::from(map.len())),
+        _ => None
+      },
+      _ => None
+    }
+  }
+}
+
+fn compare(left: Option<&Value>, op: &str, right: Option<&Value>) -> bool {
+  let ordering = match (left, right) {
+    (Some(Value::Number(a)), Some(Value::Number(b))) => a.as_f64().zip(b.as_f64()).and_then(|(a, b)| a.partial_cmp(&b)),
+    (Some(Value::String(a)), Some(Value::String(b))) => Some(a.cmp(b)),
+    (Some(a), Some(b)) if a == b => Some(std::cmp::Ordering::Equal),
+    (None, None) => Some(std::cmp::Ordering::Equal),
+    _ => None
+  };
+  match op {
+    "==" => ordering == Some(std::cmp::Ordering::Equal),
+    "!=" => ordering != Some(std::cmp::Ordering::Equal),
+    "<" => ordering == Some(std::cmp::Ordering::Less),
+    "<=" => matches!(ordering, Some(std::cmp::Ordering::Less | std::cmp::Ordering::Equal)),
+    ">" => ordering == Some(std::cmp::Ordering::Greater),
+    ">=" => matches!(ordering, Some(std::cmp::Ordering::Greater | std::cmp::Ordering::Equal)),
+    _ => false
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::{json, Value};
+
+  use crate::jsonpath::query;
+  #[cfg(all(feature = "json", feature = "serialize"))] use crate::jsonpath::query_pointers;
+
+  fn pets() -> Value {
+    json!({
+      "pets": [
+        { "id": 1, "name": "Fido", "status": "available", "tags": ["dog"] },
+        { "id": 2, "name": "Tom", "status": "sold" },
+        { "id": 3, "name": "Polly", "status": "available", "tags": [] }
+      ],
+      "total": 3
+    })
+  }
+
+  #[test]
+  fn selects_values() {
+    let json = pets();
+    expect!(query(&json, "$.total").unwrap()).to(be_equal_to(vec![&json!(3)]));
+    expect!(query(&json, "$['pets'][0].name").unwrap()).to(be_equal_to(vec![&json!("Fido")]));
+    expect!(query(&json, "$.pets[-1].id").unwrap()).to(be_equal_to(vec![&json!(3)]));
+    expect!(query(&json, "$.pets[*].id").unwrap()).to(be_equal_to(vec![&json!(1), &json!(2), &json!(3)]));
+    expect!(query(&json, "$.pets[0:2].id").unwrap()).to(be_equal_to(vec![&json!(1), &json!(2)]));
+    expect!(query(&json, "$.pets[::-1].id").unwrap()).to(be_equal_to(vec![&json!(3), &json!(2), &json!(1)]));
+    expect!(query(&json, "$.pets[0,2].id").unwrap()).to(be_equal_to(vec![&json!(1), &json!(3)]));
+    expect!(query(&json, "$..tags[0]").unwrap()).to(be_equal_to(vec![&json!("dog")]));
+    expect!(query(&json, "$.missing").unwrap().is_empty()).to(be_true());
+  }
+
+  #[test]
+  fn filters_values() {
+    let json = pets();
+    expect!(query(&json, "$.pets[?(@.status == 'available')].id").unwrap()).to(be_equal_to(vec![&json!(1), &json!(3)]));
+    expect!(query(&json, "$.pets[?@.id > 1 && @.status != \"sold\"].name").unwrap()).to(be_equal_to(vec![&json!("Polly")]));
+    expect!(query(&json, "$.pets[?@.tags].id").unwrap()).to(be_equal_to(vec![&json!(1), &json!(3)]));
+    expect!(query(&json, "$.pets[?!@.tags].id").unwrap()).to(be_equal_to(vec![&json!(2)]));
+    expect!(query(&json, "$.pets[?length(@.name) == 3].id").unwrap()).to(be_equal_to(vec![&json!(2)]));
+    expect!(query(&json, "$.pets[?@.id == $.total].name").unwrap()).to(be_equal_to(vec![&json!("Polly")]));
+    expect!(query(&json, "$[?count(@.pets) > 0]").unwrap().len()).to(be_equal_to(1));
+    expect!(query(&json, "$[?count(@.pets[*]) > 3]").unwrap().is_empty()).to(be_true());
+  }
+
+  #[test]
+  fn invalid_queries() {
+    let json = pets();
+    expect!(query(&json, "pets")).to(be_err());
+    expect!(query(&json, "$.pets[")).to(be_err());
+    expect!(query(&json, "$.pets[?@.id ==]")).to(be_err());
+    expect!(query(&json, "$.pets junk")).to(be_err());
+  }
+
+  #[test]
+  #[cfg(all(feature = "json", feature = "serialize"))]
+  fn selects_the_locations_of_values() {
+    let json = pets();
+    expect!(query_pointers(&json, "$.pets[?@.status == 'available'].name").unwrap())
+      .to(be_equal_to(vec!["/pets/0/name".to_string(), "/pets/2/name".to_string()]));
+    expect!(query_pointers(&json, "$..tags[0]").unwrap()).to(be_equal_to(vec!["/pets/0/tags/0".to_string()]));
+    expect!(query_pointers(&json, "$.pets[-1]").unwrap()).to(be_equal_to(vec!["/pets/2".to_string()]));
+    expect!(query_pointers(&json, "$").unwrap()).to(be_equal_to(vec!["".to_string()]));
+  }
+}
@@ -0,0 +1,268 @@
+//! Renders [execution reports](crate::report::ExecutionReport) as JUnit XML, so CI servers (like
+//! Jenkins, GitLab and Buildkite) can display the results of workflow runs.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::executor::ExecutionStatus;
+use crate::report::{ExecutionReport, StepReport, WorkflowReport};
+
+/// What is written as a JUnit test case
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JUnitTestCases {
+  /// Each step execution is a test case, grouped in a test suite for each workflow. Steps of
+  /// nested workflows are included, with the class name of the nested workflow.
+  #[default]
+  Steps,
+  /// Each workflow is a test case, all in one test suite
+  Workflows
+}
+
+/// Options for rendering JUnit XML
+#[derive(Debug, Clone, PartialEq)]
+pub struct JUnitOptions {
+  /// Name of the set of test suites
+  pub name: String,
+  /// What is written as a test case
+  pub test_cases: JUnitTestCases
+}
+
+impl Default for JUnitOptions {
+  fn default() -> Self {
+    JUnitOptions { name: "arazzo".to_string(), test_cases: JUnitTestCases::default() }
+  }
+}
+
+struct TestCase {
+  class_name: String,
+  name: String,
+  duration: Duration,
+  status: ExecutionStatus,
+  message: String,
+  details: Vec<String>
+}
+
+struct TestSuite {
+  name: String,
+  duration: Duration,
+  test_cases: Vec<TestCase>
+}
+
+/// Renders the report as JUnit XML
+pub fn render_junit(report: &ExecutionReport, options: &JUnitOptions) -> String {
+  let suites = match options.test_cases {
+    JUnitTestCases::Steps => report.workflows.iter()
+      .map(|workflow| {
+        let mut test_cases = vec![];
+        step_test_cases(workflow, &workflow.workflow_id, &mut test_cases);
+        TestSuite { name: workflow.workflow_id.clone(), duration: workflow.duration, test_cases }
+      })
+      .collect::<Vec<_>>(),
+    JUnitTestCases::Workflows => vec![TestSuite {
+      name: options.name.clone(),
+      duration: report.duration,
+      test_cases: report.workflows.iter().map(workflow_test_case).collect()
+    }]
+  };
+
+  let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+  let all_cases = suites.iter().flat_map(|suite| suite.test_cases.iter()).collect::<Vec<_>>();
+  let _ = writeln!(xml, "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{}\">",
+    escape(&options.name), all_cases.len(), failures(&all_cases),
+    count(&all_cases, ExecutionStatus::Error), seconds(report.duration));
+  for suite in &suites {
+    let cases = suite.test_cases.iter().collect::<Vec<_>>();
+    let _ = writeln!(xml, "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{}\">",
+      escape(&suite.name), cases.len(), failures(&cases),
+      count(&cases, ExecutionStatus::Error), seconds(suite.duration));
+    for case in &suite.test_cases {
+      let _ = write!(xml, "    <testcase classname=\"{}\" name=\"{}\" time=\"{}\"",
+        escape(&case.class_name), escape(&case.name), seconds(case.duration));
+      let element = match case.status {
+        ExecutionStatus::Success => {
+          xml.push_str("/>\n");
+          continue;
+        }
+        ExecutionStatus::Failure | ExecutionStatus::Timeout => "failure",
+        ExecutionStatus::Error => "error"
+      };
+      let _ = writeln!(xml, ">\n      <{} message=\"{}\">{}</{}>\n    </testcase>",
+        element, escape(&case.message), escape(&case.details.join("\n")), element);
+    }
+    xml.push_str("  </testsuite>\n");
+  }
+  xml.push_str("</testsuites>\n");
+  xml
+}
+
+fn step_test_cases(workflow: &WorkflowReport, class_name: &str, test_cases: &mut Vec<TestCase>) {
+  for step in &workflow.steps {
+    let name = if step.attempt > 1 {
+      format!("{} (attempt {})", step.step_id, step.attempt)
+    } else {
+      step.step_id.clone()
+    };
+    test_cases.push(TestCase {
+      class_name: class_name.to_string(),
+      name,
+      duration: step.duration,
+      status: step.status,
+      message: step.error.clone().unwrap_or_else(|| format!("Step '{}' failed", step.step_id)),
+      details: step_details(step)
+    });
+    if let Some(nested) = &step.workflow {
+      step_test_cases(nested, &format!("{}.{}", class_name, nested.workflow_id), test_cases);
+    }
+  }
+}
+
+fn workflow_test_case(workflow: &WorkflowReport) -> TestCase {
+  let details = workflow.steps.iter()
+    .filter(|step| step.status != ExecutionStatus::Success)
+    .flat_map(|step| {
+      let mut details = vec![format!("Step '{}' (attempt {}): {:?}", step.step_id, step.attempt, step.status)];
+      details.extend(step_details(step).into_iter().map(|detail| format!("  {}", detail)));
+      details
+    })
+    .collect();
+  TestCase {
+    class_name: workflow.workflow_id.clone(),
+    name: workflow.workflow_id.clone(),
+    duration: workflow.duration,
+    status: workflow.status,
+    message: workflow.error.clone().unwrap_or_else(|| format!("Workflow '{}' failed", workflow.workflow_id)),
+    details
+  }
+}
+
+/// Describes the request, response and criteria of a step that did not succeed
+fn step_details(step: &StepReport) -> Vec<String> {
+  let mut details = vec![];
+  if let (Some(method), Some(url)) = (&step.method, &step.url) {
+    details.push(format!("Request: {} {}", method, url));
+  }
+  if let Some(status_code) = step.status_code {
+    details.push(format!("Response status: {}", status_code));
+  }
+  for criterion in step.criteria.iter().filter(|criterion| !criterion.passed) {
+    match &criterion.error {
+      Some(error) => details.push(format!("Criterion '{}' could not be evaluated: {}", criterion.condition, error)),
+      None => details.push(format!("Criterion '{}' did not pass", criterion.condition))
+    }
+  }
+  if let Some(error) = &step.error {
+    details.push(format!("Error: {}", error));
+  }
+  details
+}
+
+/// Timeouts are reported as failures, as JUnit has no separate count for them
+fn failures(cases: &[&TestCase]) -> usize {
+  count(cases, ExecutionStatus::Failure) + count(cases, ExecutionStatus::Timeout)
+}
+
+fn count(cases: &[&TestCase], status: ExecutionStatus) -> usize {
+  cases.iter().filter(|case| case.status == status).count()
+}
+
+fn seconds(duration: Duration) -> String {
+  format!("{:.3}", duration.as_secs_f64())
+}
+
+fn escape(text: &str) -> String {
+  let mut result = String::with_capacity(text.len());
+  for ch in text.chars() {
+    match ch {
+      '<' => result.push_str("&lt;"),
+      '>' => result.push_str("&gt;"),
+      '&' => result.push_str("&amp;"),
+      '"' => result.push_str("&quot;"),
+      '\n' | '\r' | '\t' => result.push(ch),
+      _ if ch.is_control() => {}
+      _ => result.push(ch)
+    }
+  }
+  result
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use expectest::prelude::*;
+  use pretty_assertions::assert_eq;
+
+  use crate::executor::{CriterionResult, ExecutionStatus};
+  use crate::junit::{render_junit, JUnitOptions, JUnitTestCases};
+  use crate::report::{ExecutionReport, ReportSummary, StepReport, WorkflowReport};
+
+  fn report() -> ExecutionReport {
+    let step = StepReport {
+      step_id: "find".to_string(),
+      status: ExecutionStatus::Success,
+      attempt: 1,
+      duration: Duration::from_millis(15),
+      method: Some("GET".to_string()),
+      url: Some("http://localhost/pets?limit=1&offset=0".to_string()),
+      status_code: Some(200),
+      request: None,
+      response: None,
+      criteria: vec![],
+      outputs: Default::default(),
+      action: None,
+      workflow: None,
+      error: None
+    };
+    ExecutionReport {
+      status: ExecutionStatus::Failure,
+      duration: Duration::from_millis(1500),
+      summary: ReportSummary::default(),
+      workflows: vec![WorkflowReport {
+        workflow_id: "pets".to_string(),
+        status: ExecutionStatus::Failure,
+        duration: Duration::from_millis(1500),
+        steps: vec![
+          step.clone(),
+          StepReport {
+            step_id: "owner".to_string(),
+            status: ExecutionStatus::Failure,
+            status_code: Some(404),
+            criteria: vec![
+              CriterionResult { condition: "$statusCode == 200".to_string(), passed: false, error: None },
+              CriterionResult { condition: "$response.body#/id > 0".to_string(), passed: false, error: Some("Not JSON".to_string()) }
+            ],
+            .. step
+          }
+        ],
+        outputs: Default::default(),
+        error: Some("Step 'owner' failed".to_string())
+      }]
+    }
+  }
+
+  #[test]
+  fn renders_a_test_case_for_each_step() {
+    assert_eq!(render_junit(&report(), &JUnitOptions::default()),
+      "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+       <testsuites name=\"arazzo\" tests=\"2\" failures=\"1\" errors=\"0\" time=\"1.500\">\n  \
+       <testsuite name=\"pets\" tests=\"2\" failures=\"1\" errors=\"0\" time=\"1.500\">\n    \
+       <testcase classname=\"pets\" name=\"find\" time=\"0.015\"/>\n    \
+       <testcase classname=\"pets\" name=\"owner\" time=\"0.015\">\n      \
+       <failure message=\"Step 'owner' failed\">Request: GET http://localhost/pets?limit=1&amp;offset=0\n\
+       Response status: 404\n\
+       Criterion '$statusCode == 200' did not pass\n\
+       Criterion '$response.body#/id &gt; 0' could not be evaluated: Not JSON</failure>\n    \
+       </testcase>\n  \
+       </testsuite>\n\
+       </testsuites>\n");
+  }
+
+  #[test]
+  fn renders_a_test_case_for_each_workflow() {
+    let options = JUnitOptions { name: "suite".to_string(), test_cases: JUnitTestCases::Workflows };
+    let xml = render_junit(&report(), &options);
+    expect!(xml.contains("<testsuite name=\"suite\" tests=\"1\" failures=\"1\" errors=\"0\" time=\"1.500\">")).to(be_true());
+    expect!(xml.contains("<testcase classname=\"pets\" name=\"pets\" time=\"1.500\">")).to(be_true());
+    expect!(xml.contains("Step 'owner' (attempt 1): Failure\n  Request: GET")).to(be_true());
+  }
+}
@@ -0,0 +1,560 @@
+//! Generates [k6](https://k6.io) load test scripts from workflows, so the functional workflow
+//! definitions can be reused for load testing. Each step is a request of the default function of
+//! the script (tagged with the step ID, so the metrics can be grouped by step), followed by a
+//! `check` of the success criteria of the step and the outputs of the step, which are extracted
+//! from the response. Steps that execute another workflow of the description have the requests
+//! of that workflow inlined.
+//!
+//! Runtime expressions are converted to JavaScript expressions: inputs are read from the `env`
+//! object (i.e. `$inputs.petId` is `env.petId`), step outputs from the `steps` object (i.e.
+//! `$steps.find.outputs.id` is `steps.find.id`) and the base URL of each source description is
+//! `env.<source name>_base_url`. The values of the `env` object are passed to k6 as environment
+//! variables (with `-e`), and inputs with a default in the inputs schema are optional.
+//!
+//! The operations of the steps are taken from their operation paths. With the `execute` feature,
+//! the operations can also be resolved from the OpenAPI source descriptions (see
+//! [`k6_script_with_operations`]). Success criteria that can not be converted are added as
+//! comments. The load profile (virtual users, duration, etc.) is left to the k6 command line.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::docs::{input_properties, step_parameters};
+use crate::either::Either;
+use crate::extensions::AnyValue;
+#[cfg(feature = "execute")] use crate::operations::OperationResolver;
+use crate::payloads::FormPayload;
+use crate::step_summary::{step_operation, StepOperation};
+use crate::v1_0::{ArazzoDescription, Criterion, ParameterObject, RequestBody, Step, Workflow};
+
+/// Comparison operators of simple conditions, with the JavaScript operators
+const OPERATORS: [(&str, &str); 6] = [
+  (">=", ">="),
+  ("<=", "<="),
+  ("==", "==="),
+  ("!=", "!=="),
+  (">", ">"),
+  ("<", "<")
+];
+
+/// Generates a k6 script for the workflow, with the operations of the steps from their operation
+/// paths. Steps with an operation ID are added as comments, as the operation can not be resolved.
+pub fn k6_script(description: &ArazzoDescription, workflow_id: &str) -> anyhow::Result<String> {
+  render(description, workflow_id, &|step| step_operation(description, step))
+}
+
+/// Generates a k6 script for the workflow, resolving the operations of the steps from the
+/// OpenAPI source descriptions
+#[cfg(feature = "execute")]
+pub fn k6_script_with_operations(
+  description: &ArazzoDescription,
+  workflow_id: &str,
+  resolver: &OperationResolver
+) -> anyhow::Result<String> {
+  render(description, workflow_id, &|step| {
+    resolver.resolve_step(step).ok()
+      .map(|operation| StepOperation {
+        source_name: operation.source_name,
+        method: operation.method,
+        path: operation.path
+      })
+      .or_else(|| step_operation(description, step))
+  })
+}
+
+/// Generates k6 scripts for all the workflows, keyed by file name (`<workflow ID>.js`)
+pub fn k6_scripts(description: &ArazzoDescription) -> anyhow::Result<BTreeMap<String, String>> {
+  description.workflows.iter()
+    .map(|workflow| Ok((format!("{}.js", workflow.workflow_id), k6_script(description, &workflow.workflow_id)?)))
+    .collect()
+}
+
+/// Environment variables used by the script, with their defaults
+#[derive(Debug, Default)]
+struct Variables {
+  names: Vec<String>,
+  defaults: HashMap<String, String>
+}
+
+impl Variables {
+  fn add(&mut self, name: &str) {
+    if !self.names.iter().any(|existing| existing == name) {
+      self.names.push(name.to_string());
+    }
+  }
+}
+
+fn render(
+  description: &ArazzoDescription,
+  workflow_id: &str,
+  resolve: &dyn Fn(&Step) -> Option<StepOperation>
+) -> anyhow::Result<String> {
+  let workflow = description.workflows.iter()
+    .find(|workflow| workflow.workflow_id == workflow_id)
+    .ok_or_else(|| anyhow!("No workflow with ID '{}' was found", workflow_id))?;
+
+  let mut variables = Variables::default();
+  for property in input_properties(description, workflow) {
+    if let Some(default) = property.default {
+      variables.defaults.insert(variable_name(&property.path), default);
+    }
+  }
+  let mut steps = String::new();
+  let mut stack = vec![workflow.workflow_id.clone()];
+  write_workflow(&mut steps, description, workflow, resolve, &mut variables, &mut stack)?;
+
+  let mut script = format!("// {} - {}\n", description.info.title, workflow.workflow_id);
+  if let Some(summary) = &workflow.summary {
+    let _ = writeln!(script, "// {}", summary);
+  }
+  script.push_str("//\n// Generated from an Arazzo description.\n");
+  if !variables.names.is_empty() {
+    let _ = writeln!(script, "// Environment variables (passed with -e): {}", variables.names.join(", "));
+  }
+  script.push_str("import http from 'k6/http';\nimport { check } from 'k6';\n\n");
+
+  script.push_str("const env = {");
+  for (index, name) in variables.names.iter().enumerate() {
+    let separator = if index + 1 < variables.names.len() { "," } else { "" };
+    match variables.defaults.get(name) {
+      Some(default) => { let _ = write!(script, "\n  {}: __ENV{} || {}{}", property_key(name), member(name), js_string(default), separator); }
+      None => { let _ = write!(script, "\n  {}: __ENV{}{}", property_key(name), member(name), separator); }
+    }
+  }
+  script.push_str(if variables.names.is_empty() { "};\n" } else { "\n};\n" });
+  if !variables.names.is_empty() {
+    script.push_str(r#"for (const [name, value] of Object.entries(env)) {
+  if (value === undefined) {
+    throw new Error(`Environment variable ${name} is not set`);
+  }
+}
+"#);
+  }
+
+  script.push_str("\nexport default function () {\n  const steps = {};\n  let response;\n\n");
+  for line in steps.trim_end().lines() {
+    if line.is_empty() {
+      script.push('\n');
+    } else {
+      let _ = writeln!(script, "  {}", line);
+    }
+  }
+  script.push_str("}\n");
+  Ok(script)
+}
+
+fn write_workflow(
+  output: &mut String,
+  description: &ArazzoDescription,
+  workflow: &Workflow,
+  resolve: &dyn Fn(&Step) -> Option<StepOperation>,
+  variables: &mut Variables,
+  stack: &mut Vec<String>
+) -> anyhow::Result<()> {
+  for step in &workflow.steps {
+    let parameters = step_parameters(description, workflow, step)?;
+    if let Some(workflow_id) = &step.workflow_id {
+      let _ = writeln!(output, "// Step '{}': workflow '{}'", step.step_id, workflow_id);
+      match description.workflows.iter().find(|workflow| workflow.workflow_id == *workflow_id) {
+        Some(target) if !stack.contains(&target.workflow_id) => {
+          for (parameter, value) in &parameters {
+            let _ = writeln!(output, "env{} = {};", member(&variable_name(&parameter.name)), js_value(value, variables));
+          }
+          output.push('\n');
+          stack.push(target.workflow_id.clone());
+          write_workflow(output, description, target, resolve, variables, stack)?;
+          stack.pop();
+        }
+        Some(_) => { let _ = writeln!(output, "// The workflow calls itself, and is not inlined again\n"); }
+        None => { let _ = writeln!(output, "// The workflow is not in this description, and is not inlined\n"); }
+      }
+      continue;
+    }
+
+    let Some(operation) = resolve(step) else {
+      let target = step.operation_id.as_ref().or(step.operation_path.as_ref()).cloned().unwrap_or_default();
+      let _ = writeln!(output, "// Step '{}' is skipped, as the operation '{}' could not be resolved\n", step.step_id, target);
+      continue;
+    };
+    write_step(output, step, &operation, &parameters, variables);
+  }
+  Ok(())
+}
+
+fn write_step(
+  output: &mut String,
+  step: &Step,
+  operation: &StepOperation,
+  parameters: &[(ParameterObject, String)],
+  variables: &mut Variables
+) {
+  let _ = writeln!(output, "// Step '{}'", step.step_id);
+  if let Some(description) = &step.description {
+    for line in description.lines() {
+      let _ = writeln!(output, "// {}", line);
+    }
+  }
+
+  let base_url = format!("{}_base_url", variable_name(&operation.source_name));
+  variables.add(&base_url);
+  let mut path = template_text(&operation.path);
+  let mut query = vec![];
+  let mut headers = vec![];
+  let mut cookies = vec![];
+  for (parameter, value) in parameters {
+    let value = js_value(value, variables);
+    match parameter.r#in.as_deref() {
+      Some("path") => path = path.replace(&format!("{{{}}}", parameter.name), &format!("${{{}}}", value)),
+      Some("query") => query.push(format!("{}=${{encodeURIComponent({})}}", template_text(&parameter.name), value)),
+      Some("header") => headers.push(format!("{}: {}", js_string(&parameter.name), value)),
+      Some("cookie") => cookies.push(format!("{}: {}", js_string(&parameter.name), value)),
+      _ => {}
+    }
+  }
+  let url = if query.is_empty() {
+    format!("`${{env{}}}{}`", member(&base_url), path)
+  } else {
+    format!("`${{env{}}}{}?{}`", member(&base_url), path, query.join("&"))
+  };
+
+  let body = step.request_body.as_ref();
+  if let Some(content_type) = body.and_then(|body| body.effective_content_type()) {
+    headers.push(format!("\"Content-Type\": {}", js_string(&content_type)));
+  }
+  let body = body.map(|body| body_value(body, variables)).unwrap_or_else(|| "null".to_string());
+
+  let _ = writeln!(output, "response = http.request({}, {}, {}, {{", js_string(&operation.method.to_uppercase()), url, body);
+  if !headers.is_empty() {
+    let _ = writeln!(output, "  headers: {{ {} }},", headers.join(", "));
+  }
+  if !cookies.is_empty() {
+    let _ = writeln!(output, "  cookies: {{ {} }},", cookies.join(", "));
+  }
+  let _ = writeln!(output, "  tags: {{ name: {} }}", js_string(&step.step_id));
+  output.push_str("});\n");
+
+  let mut checks = vec![];
+  for criterion in &step.success_criteria {
+    match criterion_check(criterion) {
+      Some(check) => checks.push(format!("  {}: (r) => {}", js_string(&criterion.condition), check)),
+      None => { let _ = writeln!(output, "// Not checked: {}", criterion.condition); }
+    }
+  }
+  if !checks.is_empty() {
+    let _ = writeln!(output, "check(response, {{\n{}\n}});", checks.join(",\n"));
+  }
+
+  let mut outputs = vec![];
+  for (name, expression) in &step.outputs {
+    match response_value(expression, "response") {
+      Some(value) => outputs.push(format!("  {}: {}", property_key(name), value)),
+      None => { let _ = writeln!(output, "// Output '{}' is not extracted: {}", name, expression); }
+    }
+  }
+  if !outputs.is_empty() {
+    let _ = writeln!(output, "steps{} = {{\n{}\n}};", member(&step.step_id), outputs.join(",\n"));
+  }
+  output.push('\n');
+}
+
+/// JavaScript value of the request body. Forms are passed as objects (which k6 URL encodes) and
+/// JSON payloads are serialised with `JSON.stringify`.
+fn body_value(body: &RequestBody, variables: &mut Variables) -> String {
+  if let Some(form) = body.payload_as::<FormPayload>() {
+    let fields = form.0.iter()
+      .map(|(name, value)| format!("{}: {}", js_string(name), js_value(&value.to_text().unwrap_or_default(), variables)))
+      .collect::<Vec<_>>();
+    return format!("{{ {} }}", fields.join(", "));
+  }
+  let Some(payload) = &body.payload else {
+    return "null".to_string();
+  };
+  match payload.as_json() {
+    Some(mut json) => {
+      for replacement in &body.replacements {
+        let value = match &replacement.value {
+          Either::First(AnyValue::String(value)) => Value::String(value.clone()),
+          Either::First(value) => value.to_json(),
+          Either::Second(expression) => Value::String(format!("{{{}}}", expression))
+        };
+        if let Some(target) = json.pointer_mut(&replacement.target) {
+          *target = value;
+        }
+      }
+      format!("JSON.stringify({})", json_value(&json, variables))
+    }
+    None => js_value(&payload.as_string(), variables)
+  }
+}
+
+/// Converts the JSON to a JavaScript literal, with the runtime expressions in strings converted
+/// to JavaScript expressions
+fn json_value(json: &Value, variables: &mut Variables) -> String {
+  match json {
+    Value::String(text) => js_value(text, variables),
+    Value::Array(items) => format!("[{}]", items.iter().map(|item| json_value(item, variables)).collect::<Vec<_>>().join(", ")),
+    Value::Object(fields) if fields.is_empty() => "{}".to_string(),
+    Value::Object(fields) => {
+      let fields = fields.iter()
+        .map(|(name, value)| format!("{}: {}", js_string(name), json_value(value, variables)))
+        .collect::<Vec<_>>();
+      format!("{{ {} }}", fields.join(", "))
+    }
+    _ => json.to_string()
+  }
+}
+
+/// JavaScript expression to check the criterion, or `None` if it can not be converted
+fn criterion_check(criterion: &Criterion) -> Option<String> {
+  let criterion_type = match &criterion.r#type {
+    Some(Either::First(criterion_type)) => criterion_type.as_str(),
+    Some(Either::Second(expression_type)) => expression_type.r#type.as_str(),
+    None => "simple"
+  };
+  match (criterion_type, criterion.context.as_deref()) {
+    ("simple", None) => simple_check(&criterion.condition),
+    ("regex", Some(context)) => response_value(context, "r")
+      .map(|value| format!("new RegExp({}).test({})", js_string(&criterion.condition), value)),
+    _ => None
+  }
+}
+
+/// Converts a simple condition, with the comparisons joined with `&&` and `||`
+fn simple_check(condition: &str) -> Option<String> {
+  let alternatives = condition.split("||")
+    .map(|alternative| {
+      alternative.split("&&")
+        .map(|part| comparison(part.trim()))
+        .collect::<Option<Vec<_>>>()
+        .map(|parts| parts.join(" && "))
+    })
+    .collect::<Option<Vec<_>>>()?;
+  Some(alternatives.join(" || "))
+}
+
+fn comparison(condition: &str) -> Option<String> {
+  let operator = condition.char_indices()
+    .find_map(|(index, _)| OPERATORS.iter().find(|(operator, _)| condition[index..].starts_with(operator)).map(|operator| (index, operator)));
+  let Some((index, (operator, js_operator))) = operator else {
+    return response_value(condition, "r").map(|value| format!("{} !== undefined", value));
+  };
+  let value = response_value(condition[..index].trim(), "r")?;
+  let literal = condition[index + operator.len()..].trim();
+  let literal = match literal.strip_prefix('\'').and_then(|literal| literal.strip_suffix('\'')) {
+    Some(text) => js_string(text),
+    None => literal.to_string()
+  };
+  Some(format!("{} {} {}", value, js_operator, literal))
+}
+
+/// JavaScript expression for a runtime expression of the response, or `None` if it is not for
+/// the response
+fn response_value(expression: &str, response: &str) -> Option<String> {
+  match expression.trim() {
+    "$statusCode" => Some(format!("{}.status", response)),
+    "$url" => Some(format!("{}.url", response)),
+    "$response.body" => Some(format!("{}.body", response)),
+    expression => if let Some(pointer) = expression.strip_prefix("$response.body#") {
+      Some(format!("{}.json(){}", response, pointer_path(pointer)))
+    } else {
+      expression.strip_prefix("$response.header.").map(|name| format!("{}.headers{}", response, member(name)))
+    }
+  }
+}
+
+/// Converts a JSON Pointer to JavaScript member accesses
+fn pointer_path(pointer: &str) -> String {
+  let mut path = String::new();
+  for segment in pointer.split('/').skip(1) {
+    let segment = segment.replace("~1", "/").replace("~0", "~");
+    if segment.parse::<usize>().is_ok() {
+      let _ = write!(path, "[{}]", segment);
+    } else {
+      path.push_str(&member(&segment));
+    }
+  }
+  path
+}
+
+/// Converts the runtime expression, or the expressions embedded in the text, to a JavaScript
+/// expression. Text with embedded expressions is a template literal.
+fn js_value(text: &str, variables: &mut Variables) -> String {
+  let trimmed = text.trim();
+  if trimmed.starts_with('$') {
+    return expression_value(trimmed, variables);
+  }
+  if let Some(expression) = trimmed.strip_prefix("{$").and_then(|rest| rest.strip_suffix('}'))
+    && !expression.contains('}') {
+    return expression_value(&format!("${}", expression), variables);
+  }
+  if !text.contains("{$") {
+    return js_string(text);
+  }
+  let mut result = String::from("`");
+  let mut rest = text;
+  while let Some(start) = rest.find("{$") {
+    let Some(end) = rest[start..].find('}') else {
+      break;
+    };
+    result.push_str(&template_text(&rest[..start]));
+    let _ = write!(result, "${{{}}}", expression_value(&rest[start + 1..start + end], variables));
+    rest = &rest[start + end + 1..];
+  }
+  result.push_str(&template_text(rest));
+  result.push('`');
+  result
+}
+
+/// JavaScript expression for a runtime expression. Inputs (and expressions that are not step
+/// outputs) are added to the environment variables of the script.
+fn expression_value(expression: &str, variables: &mut Variables) -> String {
+  if let Some((step, output)) = expression.strip_prefix("$steps.").and_then(|rest| rest.split_once(".outputs.")) {
+    format!("steps{}{}", member(step), member(output))
+  } else {
+    let name = variable_name(expression.strip_prefix("$inputs.").unwrap_or(expression.trim_start_matches('$')));
+    variables.add(&name);
+    format!("env{}", member(&name))
+  }
+}
+
+fn variable_name(name: &str) -> String {
+  name.chars().map(|ch| if ch.is_ascii_alphanumeric() || ch == '_' { ch } else { '_' }).collect()
+}
+
+fn is_identifier(name: &str) -> bool {
+  !name.is_empty()
+    && name.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '$')
+    && !name.starts_with(|ch: char| ch.is_ascii_digit())
+}
+
+/// Member access for the property (i.e. `.id` or `["the id"]`)
+fn member(name: &str) -> String {
+  if is_identifier(name) { format!(".{}", name) } else { format!("[{}]", js_string(name)) }
+}
+
+/// Key for the property in an object literal
+fn property_key(name: &str) -> String {
+  if is_identifier(name) { name.to_string() } else { js_string(name) }
+}
+
+fn js_string(text: &str) -> String {
+  Value::String(text.to_string()).to_string()
+}
+
+/// Escapes the text for a template literal
+fn template_text(text: &str) -> String {
+  text.replace('\\', "\\\\").replace('`', "\\`").replace("${", "\\${")
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::arazzo;
+  use crate::k6::{k6_script, k6_scripts, pointer_path};
+  #[cfg(feature = "execute")] use crate::k6::k6_script_with_operations;
+  #[cfg(feature = "execute")] use crate::operations::{OpenApiSource, OperationResolver};
+  use crate::v1_0::ArazzoDescription;
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      info("Pet Store", "");
+      workflow("adopt") {
+        inputs: json!({ "type": "object", "properties": { "petId": { "type": "string", "default": "1" } } });
+        step("find") {
+          operation_path: "{$sourceDescriptions.petstore.url}#/paths/~1pets~1{petId}/get";
+          parameter("petId", "path", "$inputs.petId");
+          parameter("Authorization", "header", "Bearer {$inputs.token}");
+          success_criterion: "$statusCode == 200 && $response.body#/status == 'available'";
+          typed_success_criterion("regex", "$response.header.Content-Type", "^application/json");
+          typed_success_criterion("jsonpath", "$response.body", "$.tags[?(@ == 'cute')]");
+          output("id", "$response.body#/id");
+          output("location", "$response.header.Location");
+        }
+        step("adopt") {
+          operation_path: "{$sourceDescriptions.petstore.url}#/paths/~1adoptions/post";
+          json_body: json!({ "pet": "{$steps.find.outputs.id}", "note": "Adopted by {$inputs.token}" });
+          success_criterion: "$statusCode == 201 || $statusCode == 202";
+        }
+        step("notify") { operation_id: "notify"; }
+      }
+    }
+  }
+
+  #[test]
+  fn generates_a_k6_script() {
+    expect!(k6_script(&description(), "adopt").unwrap()).to(be_equal_to(
+r#"// Pet Store - adopt
+//
+// Generated from an Arazzo description.
+// Environment variables (passed with -e): petstore_base_url, petId, token
+import http from 'k6/http';
+import { check } from 'k6';
+
+const env = {
+  petstore_base_url: __ENV.petstore_base_url,
+  petId: __ENV.petId || "1",
+  token: __ENV.token
+};
+for (const [name, value] of Object.entries(env)) {
+  if (value === undefined) {
+    throw new Error(`Environment variable ${name} is not set`);
+  }
+}
+
+export default function () {
+  const steps = {};
+  let response;
+
+  // Step 'find'
+  response = http.request("GET", `${env.petstore_base_url}/pets/${env.petId}`, null, {
+    headers: { "Authorization": `Bearer ${env.token}` },
+    tags: { name: "find" }
+  });
+  // Not checked: $.tags[?(@ == 'cute')]
+  check(response, {
+    "$statusCode == 200 && $response.body#/status == 'available'": (r) => r.status === 200 && r.json().status === "available",
+    "^application/json": (r) => new RegExp("^application/json").test(r.headers["Content-Type"])
+  });
+  steps.find = {
+    id: response.json().id,
+    location: response.headers.Location
+  };
+
+  // Step 'adopt'
+  response = http.request("POST", `${env.petstore_base_url}/adoptions`, JSON.stringify({ "note": `Adopted by ${env.token}`, "pet": steps.find.id }), {
+    headers: { "Content-Type": "application/json" },
+    tags: { name: "adopt" }
+  });
+  check(response, {
+    "$statusCode == 201 || $statusCode == 202": (r) => r.status === 201 || r.status === 202
+  });
+
+  // Step 'notify' is skipped, as the operation 'notify' could not be resolved
+}
+"#.to_string()));
+
+    expect!(k6_script(&description(), "missing")).to(be_err());
+    expect!(k6_scripts(&description()).unwrap().keys().cloned().collect::<Vec<_>>()).to(be_equal_to(vec!["adopt.js".to_string()]));
+  }
+
+  #[test]
+  #[cfg(feature = "execute")]
+  fn resolves_the_operations_from_the_source_descriptions() {
+    let resolver = OperationResolver::new(vec![OpenApiSource::new("notifications", "notifications.json", json!({
+      "paths": { "/notifications": { "post": { "operationId": "notify" } } }
+    }))]);
+    let script = k6_script_with_operations(&description(), "adopt", &resolver).unwrap();
+    expect!(script.contains("response = http.request(\"POST\", `${env.notifications_base_url}/notifications`, null, {")).to(be_true());
+  }
+
+  #[test]
+  fn converts_json_pointers_to_member_accesses() {
+    expect!(pointer_path("/items/0/the~1id")).to(be_equal_to(".items[0][\"the/id\"]"));
+    expect!(pointer_path("")).to(be_equal_to(""));
+  }
+}
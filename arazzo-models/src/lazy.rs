@@ -0,0 +1,302 @@
+//! Lazy loading of Arazzo descriptions from JSON documents. The top-level structure of the
+//! description (the version, Info Object, source descriptions, components and the IDs, summaries
+//! and descriptions of the workflows) is loaded straight away, while each workflow is only loaded
+//! the first time it is accessed.
+//!
+//! This is for tools that only need the metadata of very large documents (i.e. to list the
+//! workflows), where loading all the steps of every workflow would be wasted work. Errors in a
+//! workflow are only reported when that workflow is accessed.
+//!
+//! ```
+//! use arazzo_models::lazy::LazyDescription;
+//! use serde_json::json;
+//!
+//! let description = LazyDescription::try_from(json!({
+//!   "arazzo": "1.0.1",
+//!   "info": { "title": "Pets", "version": "1.0.0" },
+//!   "sourceDescriptions": [ { "name": "petstore", "url": "petstore.json" } ],
+//!   "workflows": [ { "workflowId": "listPets", "steps": [ { "stepId": "list", "operationId": "listPets" } ] } ]
+//! })).unwrap();
+//! assert_eq!(description.workflow_ids().collect::<Vec<_>>(), vec!["listPets"]);
+//!
+//! let workflow = description.workflow("listPets").unwrap();
+//! assert!(!workflow.is_loaded());
+//! assert_eq!(workflow.load().unwrap().steps[0].step_id, "list");
+//! ```
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use anyhow::anyhow;
+use serde_json::{Map, Value};
+
+use crate::extensions::{json_extract_extensions, AnyValue};
+use crate::json::{
+  json_load_source_descriptions,
+  json_object_lookup_string,
+  json_object_require_string,
+  json_workflows_error
+};
+use crate::v1_0::{ArazzoDescription, Components, Info, SourceDescription, Workflow};
+
+/// Arazzo description where the workflows are loaded on demand
+#[derive(Debug)]
+pub struct LazyDescription {
+  /// Version number of the Arazzo Specification.
+  pub arazzo: String,
+  /// Provides metadata about the workflows contain within the Arazzo Description.
+  pub info: Info,
+  /// List of source descriptions.
+  pub source_descriptions: Vec<SourceDescription>,
+  /// List of workflows, which are loaded when first accessed.
+  pub workflows: Vec<LazyWorkflow>,
+  /// Element to hold various schemas for the Arazzo Description.
+  pub components: Components,
+  /// Extension values, keyed without the `x-` prefix
+  pub extensions: HashMap<String, AnyValue>
+}
+
+/// Workflow that is loaded from its JSON value the first time it is accessed
+#[derive(Debug)]
+pub struct LazyWorkflow {
+  /// Unique string to represent the workflow.
+  pub workflow_id: String,
+  /// Summary of the purpose or objective of the workflow.
+  pub summary: Option<String>,
+  /// Description of the workflow.
+  pub description: Option<String>,
+  source: WorkflowSource,
+  workflow: OnceLock<Result<Workflow, String>>
+}
+
+/// Where the JSON of a lazy workflow is kept until it is loaded
+#[derive(Debug)]
+pub(crate) enum WorkflowSource {
+  /// JSON value of the workflow
+  Json(Value),
+  /// Text of the workflow in a memory-mapped document
+  #[cfg(feature = "mmap")]
+  Mapped(crate::mmap::MappedText)
+}
+
+impl WorkflowSource {
+  fn json(&self) -> anyhow::Result<Cow<'_, Value>> {
+    match self {
+      WorkflowSource::Json(json) => Ok(Cow::Borrowed(json)),
+      #[cfg(feature = "mmap")]
+      WorkflowSource::Mapped(text) => Ok(Cow::Owned(serde_json::from_slice(text.as_bytes())?))
+    }
+  }
+}
+
+impl LazyWorkflow {
+  /// Creates a lazy workflow from the JSON of the workflow, loading only the workflow ID, summary
+  /// and description
+  pub(crate) fn new(json: &Value, source: WorkflowSource) -> anyhow::Result<Self> {
+    let map = json.as_object()
+      .ok_or_else(|| anyhow!("JSON value must be an Object, got {:?}", json))?;
+    Ok(LazyWorkflow {
+      workflow_id: json_object_require_string(map, "workflowId")?,
+      summary: json_object_lookup_string(map, "summary"),
+      description: json_object_lookup_string(map, "description"),
+      source,
+      workflow: OnceLock::new()
+    })
+  }
+
+  fn from_json(json: Value) -> anyhow::Result<Self> {
+    let workflow = LazyWorkflow::new(&json, WorkflowSource::Json(Value::Null))?;
+    Ok(LazyWorkflow { source: WorkflowSource::Json(json), .. workflow })
+  }
+
+  /// Returns the workflow, loading it if this is the first time it is accessed. Returns an error
+  /// if the workflow is not valid (the error is returned on every access).
+  pub fn load(&self) -> anyhow::Result<&Workflow> {
+    self.workflow.get_or_init(|| self.load_workflow().map_err(|err| err.to_string()))
+      .as_ref()
+      .map_err(|err| anyhow!("Failed to load workflow '{}': {}", self.workflow_id, err))
+  }
+
+  fn load_workflow(&self) -> anyhow::Result<Workflow> {
+    Workflow::try_from(self.source.json()?.as_ref())
+  }
+
+  /// If the workflow has been loaded
+  pub fn is_loaded(&self) -> bool {
+    self.workflow.get().is_some()
+  }
+
+  /// Returns the JSON value of the workflow. For workflows in a memory-mapped document, the
+  /// value is parsed from the document on every call.
+  pub fn json(&self) -> anyhow::Result<Cow<'_, Value>> {
+    self.source.json()
+  }
+
+  /// Consumes this lazy workflow, returning the loaded workflow
+  pub fn into_workflow(self) -> anyhow::Result<Workflow> {
+    match self.workflow.into_inner() {
+      Some(Ok(workflow)) => Ok(workflow),
+      Some(Err(err)) => Err(anyhow!("Failed to load workflow '{}': {}", self.workflow_id, err)),
+      None => Workflow::try_from(self.source.json()?.as_ref())
+    }
+  }
+}
+
+impl LazyDescription {
+  /// Returns the IDs of all the workflows, without loading them
+  pub fn workflow_ids(&self) -> impl Iterator<Item = &str> {
+    self.workflows.iter().map(|workflow| workflow.workflow_id.as_str())
+  }
+
+  /// Returns the workflow with the given ID, without loading it
+  pub fn workflow(&self, workflow_id: &str) -> Option<&LazyWorkflow> {
+    self.workflows.iter().find(|workflow| workflow.workflow_id == workflow_id)
+  }
+
+  /// Loads all the workflows, and returns the complete description. Returns an error if any of
+  /// the workflows are not valid.
+  pub fn into_description(self) -> anyhow::Result<ArazzoDescription> {
+    Ok(ArazzoDescription {
+      arazzo: self.arazzo,
+      info: self.info,
+      source_descriptions: self.source_descriptions,
+      workflows: self.workflows.into_iter()
+        .map(LazyWorkflow::into_workflow)
+        .collect::<anyhow::Result<_>>()?,
+      components: self.components,
+      extensions: self.extensions
+    })
+  }
+}
+
+impl TryFrom<Value> for LazyDescription {
+  type Error = anyhow::Error;
+
+  fn try_from(value: Value) -> Result<Self, Self::Error> {
+    let Value::Object(mut map) = value else {
+      return Err(anyhow!("JSON value must be an Object, got {:?}", value));
+    };
+    let workflows = map.remove("workflows");
+    LazyDescription::from_parts(&map, || match workflows {
+      Some(Value::Array(workflows)) => {
+        check_workflows_not_empty(workflows.len())?;
+        workflows.into_iter().map(LazyWorkflow::from_json).collect()
+      }
+      workflows => Err(json_workflows_error(workflows.as_ref()))
+    })
+  }
+}
+
+impl LazyDescription {
+  /// Creates the description from the fields of the top-level object (without the workflows),
+  /// and a function to create the lazy workflows. The fields are loaded in the same order as the
+  /// JSON loader, so the same errors are returned.
+  pub(crate) fn from_parts(
+    map: &Map<String, Value>,
+    workflows: impl FnOnce() -> anyhow::Result<Vec<LazyWorkflow>>
+  ) -> anyhow::Result<Self> {
+    let arazzo = json_object_require_string(map, "arazzo")
+      .map_err(|_| anyhow!("Arazzo version number is required [4.6.1.1 Fixed Fields]"))?;
+    let info = map.get("info")
+      .ok_or_else(|| anyhow!("Info Object is required [4.6.1.1 Fixed Fields]"))
+      .and_then(Info::try_from)?;
+    let source_descriptions = json_load_source_descriptions(map)?;
+    let workflows = workflows()?;
+    let components = map.get("components")
+      .map(Components::try_from)
+      .transpose()?
+      .unwrap_or_default();
+
+    Ok(LazyDescription {
+      arazzo,
+      info,
+      source_descriptions,
+      workflows,
+      components,
+      extensions: json_extract_extensions(map)?
+    })
+  }
+}
+
+pub(crate) fn check_workflows_not_empty(count: usize) -> anyhow::Result<()> {
+  if count == 0 {
+    Err(anyhow!("Workflows list must have at least one entry [4.6.1.1 Fixed Fields]"))
+  } else {
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::lazy::LazyDescription;
+  use crate::v1_0::ArazzoDescription;
+
+  fn document() -> serde_json::Value {
+    json!({
+      "arazzo": "1.0.1",
+      "info": { "title": "Pets", "version": "1.0.0" },
+      "sourceDescriptions": [ { "name": "petstore", "url": "petstore.json" } ],
+      "workflows": [
+        {
+          "workflowId": "listPets",
+          "summary": "Lists the pets",
+          "steps": [ { "stepId": "list", "operationId": "listPets" } ]
+        },
+        {
+          "workflowId": "broken",
+          "steps": []
+        }
+      ],
+      "x-owner": "pets"
+    })
+  }
+
+  #[test]
+  fn loads_the_metadata_without_the_workflows() {
+    let description = LazyDescription::try_from(document()).unwrap();
+    expect!(description.info.title.as_str()).to(be_equal_to("Pets"));
+    expect!(description.workflow_ids().collect::<Vec<_>>()).to(be_equal_to(vec!["listPets", "broken"]));
+    expect!(description.workflows[0].summary.clone()).to(be_some().value("Lists the pets"));
+    expect!(description.workflows.iter().any(|workflow| workflow.is_loaded())).to(be_false());
+    expect!(description.extensions.contains_key("owner")).to(be_true());
+  }
+
+  #[test]
+  fn loads_workflows_on_first_access() {
+    let description = LazyDescription::try_from(document()).unwrap();
+    let workflow = description.workflow("listPets").unwrap();
+    expect!(workflow.load().unwrap().steps.len()).to(be_equal_to(1));
+    expect!(workflow.is_loaded()).to(be_true());
+    expect!(description.workflow("broken").unwrap().is_loaded()).to(be_false());
+
+    let broken = description.workflow("broken").unwrap();
+    expect!(broken.load().unwrap_err().to_string())
+      .to(be_equal_to("Failed to load workflow 'broken': At lest one Step is required [4.6.4.1 Fixed Fields]"));
+    expect!(broken.load()).to(be_err());
+  }
+
+  #[test]
+  fn into_description() {
+    let mut json = document();
+    json["workflows"][1]["steps"] = json!([ { "stepId": "one", "workflowId": "listPets" } ]);
+    let expected = ArazzoDescription::try_from(&json).unwrap();
+
+    let description = LazyDescription::try_from(json).unwrap();
+    description.workflow("listPets").unwrap().load().unwrap();
+    expect!(description.into_description().unwrap()).to(be_equal_to(expected));
+
+    expect!(LazyDescription::try_from(document()).unwrap().into_description()).to(be_err());
+  }
+
+  #[test]
+  fn fails_if_the_top_level_structure_is_not_valid() {
+    expect!(LazyDescription::try_from(json!({}))).to(be_err());
+    let mut json = document();
+    json["workflows"] = json!([ { "summary": "No ID" } ]);
+    expect!(LazyDescription::try_from(json)).to(be_err());
+  }
+}
@@ -55,13 +55,88 @@
 //! Note that Serde implementations (like JSON and YAML) may sort the keys on writing. So reading in a file
 //! and then writing it out again will result in changes.
 //!
+//! To write a document without some (or all) of the extension values (i.e. to publish a document without
+//! any internal `x-` metadata), use `ArazzoDescription::without_extensions` with an `ExtensionFilter`
+//! before serializing it.
+//!
 //!
 //! ## Crate features
-//! All features are enabled by default
+//! The `yaml`, `json` and `serialize` features are enabled by default
 //!
 //! * `yaml`: Enables loading the models from a YAML document (uses yaml-rust2 crate)
 //! * `json`: Enables loading the models from a JSON document (uses serde_json crate)
 //! * `serialize`: Adds Serde Serialize implementations
+//! * `arbitrary_precision`: Keeps numbers that do not fit into 64 bits as they were written (enables the
+//!   `arbitrary_precision` feature of serde_json)
+//! * `xml`: Loads payloads with an XML content type as an `XmlPayload` (parsed with quick-xml), which supports
+//!   querying and updating the document with XPath 1.0 expressions (evaluated with sxd-xpath, see the `xml` module)
+//! * `execute`: Adds an `Executor` that runs workflows end-to-end against the APIs in the source descriptions
+//!   (see the `executor` module). Requests are sent with a blocking reqwest client using rustls (enables the
+//!   `http` feature)
+//! * `http`: Fetches `http:` and `https:` URLs (source descriptions and external payloads) with a blocking
+//!   reqwest client using rustls (see the `http_config` module)
+//! * `schemars`: Adds schemars `JsonSchema` implementations for the models, describing the form they are
+//!   serialized in (see the `schema` module)
+//! * `proptest`: Adds proptest `Arbitrary` implementations for the models, which generate valid documents
+//!   (see the `arbitrary` module)
+//! * `simd_json`: Loads the models from JSON documents parsed with simd-json (see the `simd`
+//!   module)
+//! * `mmap`: Loads the models from memory-mapped files, optionally leaving the workflows in the mapped
+//!   file until they are accessed (see the `mmap` module)
+//! * `parallel`: Loads many documents in parallel with rayon, reporting all the documents that failed to
+//!   load (see the `batch` module)
+//! * `python`: Adds the `arazzo` Python module (uses the pyo3 crate), which is built with maturin (see the
+//!   `python` module)
+//!
+//! ## Extension keys
+//!
+//! Extension values are stored keyed without the `x-` prefix (i.e. `x-owner` is stored as `owner`),
+//! and the prefix is always added back when the models are serialized. This means a loaded document
+//! is written out with exactly the same extension keys.
+//!
+//! ## Order of keys in extension values
+//!
+//! Object values of extensions keep the order their keys were authored in. The JSON loader can only
+//! preserve this order if the `preserve_order` feature of serde_json is enabled (add it to the serde_json
+//! dependency of your project). YAML documents always preserve the order.
+//!
+//! ## Borrowed models
+//!
+//! For services that load large numbers of JSON documents, the `borrowed` module has a variant of
+//! the models that borrows the strings and values from the `serde_json` document instead of copying
+//! them, which avoids most of the allocations of loading the owned models. The borrowed models can
+//! be converted to the owned models with `into_owned`. Run `cargo bench --bench borrowed` to compare
+//! the two.
+//!
+//! ## Lazy loading
+//!
+//! For tools that only need the metadata of very large documents (i.e. the Info Object and the
+//! workflow IDs), `lazy::LazyDescription` loads the top-level structure of a JSON document, and
+//! only loads each workflow the first time it is accessed. With the `mmap` feature, the document can
+//! be loaded from a memory-mapped file with `mmap::mmap_load_lazy`, so the workflows are not read
+//! into memory until they are accessed.
+//!
+//! ## Streaming YAML documents
+//!
+//! The YAML loader needs the whole document loaded as a `Yaml` tree first. For very large documents,
+//! `yaml_stream::yaml_stream_load` (or `yaml_stream_load_reader`) loads the description straight from
+//! the parser events, converting each workflow as it is parsed, so only one workflow is held as a
+//! `Yaml` tree at a time. `yaml_stream_load_with_spans` (and `yaml_stream_load_reader_with_spans`)
+//! also return the source spans (line and column) of all the objects and lists in the document,
+//! keyed by their JSON Pointer, which can be used to show where a validation issue is in the
+//! document.
+//!
+//! ## Sharing descriptions
+//!
+//! Cloning an `ArazzoDescription` copies all of it. To pass a description to a number of concurrent
+//! analyses, convert it to a `shared::SharedDescription` with `into_shared`, which can be cloned in
+//! constant time and copies the description on write.
+//!
+//! ## Binary extension values
+//!
+//! Extension values can hold binary data (`AnyValue::Binary`), which is written as a Base64 encoded
+//! string to JSON and YAML. The yaml-rust2 `YamlLoader` does not keep the tags of values, so to load
+//! `!!binary` values from a YAML document, use `arazzo_models::yaml::yaml_load_documents` instead.
 //!
 //! ## Note on the Arazzo Specification and Any types
 //!
@@ -70,11 +145,80 @@
 
 #![warn(missing_docs)]
 #[doc = include_str!("../README.md")]
-
 pub mod v1_0;
 pub mod extensions;
+pub mod extension_registry;
 pub mod payloads;
+pub mod payload_registry;
+pub mod content_types;
+pub(crate) mod base64;
+#[cfg(any(feature = "json", feature = "yaml"))] pub(crate) mod fields;
+pub mod http_config;
 pub mod either;
+pub mod expressions;
+pub mod context;
+pub mod builder;
+pub mod merge;
+pub mod remove;
+pub mod split;
+pub mod bundle;
+pub mod usages;
+pub mod validation;
+pub mod lint;
+pub mod wiring;
+pub mod plan;
+pub mod plan_printer;
+pub mod step_summary;
+pub mod tree;
+pub mod shared;
 #[cfg(feature = "serialize")] pub mod serialize;
 #[cfg(feature = "json")] pub mod json;
+#[cfg(feature = "json")] pub mod borrowed;
+#[cfg(feature = "json")] pub mod lazy;
+#[cfg(feature = "json")] pub mod json_schema;
+#[cfg(feature = "json")] pub mod replacements;
+#[cfg(feature = "json")] pub mod templates;
+#[cfg(feature = "json")] pub(crate) mod jsonpath;
+#[cfg(feature = "json")] pub mod criteria;
+#[cfg(feature = "json")] pub mod render;
+#[cfg(feature = "json")] pub mod execution_context;
+#[cfg(feature = "json")] pub mod redaction;
+#[cfg(feature = "json")] pub mod inputs;
+#[cfg(feature = "json")] pub mod preview;
+#[cfg(feature = "json")] pub mod docs;
+#[cfg(feature = "json")] pub mod html_docs;
+#[cfg(feature = "json")] pub mod graph;
+#[cfg(feature = "json")] pub mod postman_import;
+#[cfg(feature = "json")] pub mod har_import;
+#[cfg(feature = "json")] pub mod hurl;
+#[cfg(feature = "json")] pub mod stepci;
+#[cfg(feature = "json")] pub mod curl;
+#[cfg(feature = "json")] pub mod k6;
 #[cfg(feature = "yaml")] pub mod yaml;
+#[cfg(feature = "yaml")] pub mod yaml_stream;
+#[cfg(all(feature = "json", feature = "serialize"))] pub mod patch;
+#[cfg(all(feature = "json", feature = "serialize"))] pub mod overlay;
+#[cfg(feature = "xml")] pub mod xml;
+#[cfg(feature = "schemars")] pub mod schema;
+#[cfg(feature = "proptest")] pub mod arbitrary;
+#[cfg(feature = "simd_json")] pub mod simd;
+#[cfg(feature = "mmap")] pub mod mmap;
+#[cfg(feature = "parallel")] pub mod batch;
+#[cfg(feature = "python")] pub mod python;
+#[cfg(feature = "execute")] pub mod operations;
+#[cfg(feature = "execute")] pub mod executor;
+#[cfg(feature = "execute")] pub mod auth;
+#[cfg(feature = "execute")] pub mod environments;
+#[cfg(feature = "execute")] pub mod listener;
+#[cfg(feature = "execute")] pub mod mock_executor;
+#[cfg(feature = "execute")] pub mod cassette;
+#[cfg(feature = "execute")] pub mod dry_run;
+#[cfg(feature = "execute")] pub mod report;
+#[cfg(feature = "execute")] pub mod junit;
+#[cfg(feature = "execute")] pub mod html_report;
+#[cfg(feature = "execute")] pub mod checkpoint;
+#[cfg(feature = "execute")] pub mod rate_limit;
+#[cfg(feature = "execute")] pub mod simulation;
+#[cfg(feature = "execute")] pub mod dataset;
+#[cfg(feature = "execute")] pub mod postman;
+#[cfg(feature = "execute")] pub mod pact;
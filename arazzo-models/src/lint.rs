@@ -0,0 +1,112 @@
+//! Linting of Arazzo descriptions. Lint issues are not errors (the description is still valid
+//! according to the specification), but point out parts of the description that are probably
+//! mistakes or make the workflows harder to understand.
+
+use crate::expressions::ReferenceKind;
+use crate::usages::{ObjectRef, UsageIndex};
+use crate::v1_0::ArazzoDescription;
+use crate::validation::ValidationIssue;
+
+/// Lints the description, returning all the issues found. The issues are:
+/// * Components that are not used by any workflow or other component
+/// * Workflows without a summary or description
+/// * Steps without any success criteria
+pub fn lint(description: &ArazzoDescription) -> Vec<ValidationIssue> {
+  let mut issues = vec![];
+
+  for (index, workflow) in description.workflows.iter().enumerate() {
+    let path = format!("/workflows/{}", index);
+    if workflow.summary.is_none() && workflow.description.is_none() {
+      issues.push(ValidationIssue::new(path.as_str(),
+        format!("Workflow '{}' has no summary or description", workflow.workflow_id)));
+    }
+    for (step_index, step) in workflow.steps.iter().enumerate() {
+      if step.success_criteria.is_empty() {
+        issues.push(ValidationIssue::new(format!("{}/steps/{}", path, step_index),
+          format!("Step '{}' has no success criteria", step.step_id)));
+      }
+    }
+  }
+
+  lint_unused_components(description, &mut issues);
+
+  issues
+}
+
+fn lint_unused_components(description: &ArazzoDescription, issues: &mut Vec<ValidationIssue>) {
+  let index = UsageIndex::build(description);
+  let components = &description.components;
+  let mut keys = components.inputs.keys().map(|key| (ReferenceKind::ComponentInput, "inputs", key))
+    .chain(components.parameters.keys().map(|key| (ReferenceKind::ComponentParameter, "parameters", key)))
+    .chain(components.success_actions.keys().map(|key| (ReferenceKind::ComponentSuccessAction, "successActions", key)))
+    .chain(components.failure_actions.keys().map(|key| (ReferenceKind::ComponentFailureAction, "failureActions", key)))
+    .collect::<Vec<_>>();
+  keys.sort();
+
+  for (kind, field, key) in keys {
+    if !index.is_used(kind, key) {
+      issues.push(ValidationIssue::new(format!("/components/{}/{}", field, key),
+        format!("{} is not used", ObjectRef::new(kind, key.as_str()))));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::arazzo;
+  use crate::lint::lint;
+  use crate::validation::ValidationIssue;
+  use crate::v1_0::*;
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      info("Test", "1.0.0");
+      source("api", "http://api");
+      workflow("test") {
+        summary: "Test workflow";
+        step("one") {
+          operation_id: "getOne";
+          reusable_parameter: "page";
+          success_criterion: "$statusCode == 200";
+        }
+      }
+      component_parameter("page", ParameterObject {
+        name: "page".to_string(),
+        r#in: Some("query".to_string()),
+        .. ParameterObject::default()
+      });
+    }
+  }
+
+  #[test]
+  fn no_issues() {
+    expect!(lint(&description())).to(be_equal_to(vec![]));
+  }
+
+  #[test]
+  fn lints_the_workflows_and_steps() {
+    let mut description = description();
+    description.workflows[0].summary = None;
+    description.workflows[0].steps[0].success_criteria.clear();
+
+    expect!(lint(&description)).to(be_equal_to(vec![
+      ValidationIssue::new("/workflows/0", "Workflow 'test' has no summary or description"),
+      ValidationIssue::new("/workflows/0/steps/0", "Step 'one' has no success criteria")
+    ]));
+  }
+
+  #[test]
+  fn lints_unused_components() {
+    let mut description = description();
+    description.workflows[0].steps[0].parameters.clear();
+    description.components.inputs.insert("pet".to_string(), json!({ "type": "object" }));
+
+    expect!(lint(&description)).to(be_equal_to(vec![
+      ValidationIssue::new("/components/inputs/pet", "Component Input 'pet' is not used"),
+      ValidationIssue::new("/components/parameters/page", "Component Parameter 'page' is not used")
+    ]));
+  }
+}
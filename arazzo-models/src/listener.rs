@@ -0,0 +1,288 @@
+//! Hooks that are notified of the progress of an [`Executor`](crate::executor::Executor), so
+//! progress bars, logging and custom reporting can be implemented without changing the engine.
+//! Wrap a listener in a [`RedactingListener`] so that it is never passed any secrets (i.e. when
+//! it writes logs).
+
+use std::time::Duration;
+
+use crate::execution_context::{CaptureStore, StepExchange, StepRequest, StepResponse};
+use crate::executor::{CriterionResult, StepResult, WorkflowResult};
+use crate::extensions::AnyValue;
+use crate::redaction::Redactor;
+use crate::v1_0::{Step, Workflow};
+
+/// Listener for the events of an execution. All the callbacks do nothing by default, so
+/// implementations only need to implement the events they are interested in. When independent
+/// workflows are executed concurrently, the callbacks can be called from different threads.
+pub trait ExecutionListener {
+  /// Called before the steps of a workflow are executed
+  fn workflow_started(&self, _workflow: &Workflow, _inputs: &AnyValue) {}
+
+  /// Called after a workflow has completed (successfully or not)
+  fn workflow_finished(&self, _result: &WorkflowResult) {}
+
+  /// Called before a step is executed. The attempt starts at 1, and is incremented each time the
+  /// step is retried.
+  fn step_started(&self, _step: &Step, _attempt: usize) {}
+
+  /// Called after a step has been executed and the next action selected
+  fn step_finished(&self, _step: &Step, _result: &StepResult) {}
+
+  /// Called before the request for a step is sent
+  fn request_sent(&self, _step: &Step, _request: &StepRequest) {}
+
+  /// Called when the response for a step has been received
+  fn response_received(&self, _step: &Step, _response: &StepResponse) {}
+
+  /// Called after each success criterion of a step has been evaluated
+  fn criterion_evaluated(&self, _step: &Step, _result: &CriterionResult) {}
+
+  /// Called when a step is going to be retried (after the delay, if there is one)
+  fn retry_scheduled(&self, _step: &Step, _attempt: usize, _delay: Option<Duration>) {}
+
+  /// Called when a success or failure action has been selected after a step. The action type is
+  /// `end`, `goto` or `retry`.
+  fn action_taken(&self, _step: &Step, _action_name: &str, _action_type: &str) {}
+}
+
+/// Listener that redacts the inputs, requests, responses and results (see the
+/// [redaction module](crate::redaction)) before passing them to another listener
+#[derive(Debug, Clone)]
+pub struct RedactingListener<L> {
+  listener: L,
+  redactor: Redactor
+}
+
+impl <L: ExecutionListener> RedactingListener<L> {
+  /// Wraps the listener
+  pub fn new(listener: L, redactor: Redactor) -> Self {
+    RedactingListener { listener, redactor }
+  }
+
+  fn redact_workflow_result(&self, result: &WorkflowResult) -> WorkflowResult {
+    WorkflowResult {
+      steps: result.steps.iter().map(|step| self.redact_step_result(step)).collect(),
+      outputs: result.outputs.iter()
+        .map(|(name, value)| (name.clone(), self.redactor.redact_value(name, value)))
+        .collect(),
+      error: result.error.as_ref().map(|error| self.redactor.redact_str(error)),
+      captures: CaptureStore {
+        exchanges: result.captures.exchanges.iter()
+          .map(|(step_id, exchange)| (step_id.clone(), StepExchange {
+            request: exchange.request.as_ref().map(|request| self.redactor.redact_request(request)),
+            response: exchange.response.as_ref().map(|response| self.redactor.redact_response(response)),
+            path_parameters: exchange.path_parameters.iter()
+              .map(|(name, value)| (name.clone(), self.redactor.redact_value(name, value)))
+              .collect()
+          }))
+          .collect()
+      },
+      .. result.clone()
+    }
+  }
+
+  fn redact_step_result(&self, result: &StepResult) -> StepResult {
+    StepResult {
+      request: result.request.as_ref().map(|request| self.redactor.redact_request(request)),
+      response: result.response.as_ref().map(|response| self.redactor.redact_response(response)),
+      criteria: result.criteria.iter().map(|criterion| self.redact_criterion(criterion)).collect(),
+      outputs: result.outputs.iter()
+        .map(|(name, value)| (name.clone(), self.redactor.redact_value(name, value)))
+        .collect(),
+      workflow: result.workflow.as_ref().map(|workflow| Box::new(self.redact_workflow_result(workflow))),
+      error: result.error.as_ref().map(|error| self.redactor.redact_str(error)),
+      .. result.clone()
+    }
+  }
+
+  fn redact_criterion(&self, result: &CriterionResult) -> CriterionResult {
+    CriterionResult {
+      error: result.error.as_ref().map(|error| self.redactor.redact_str(error)),
+      .. result.clone()
+    }
+  }
+}
+
+impl <L: ExecutionListener> ExecutionListener for RedactingListener<L> {
+  fn workflow_started(&self, workflow: &Workflow, inputs: &AnyValue) {
+    self.listener.workflow_started(workflow, &self.redactor.redact_value("", inputs));
+  }
+
+  fn workflow_finished(&self, result: &WorkflowResult) {
+    self.listener.workflow_finished(&self.redact_workflow_result(result));
+  }
+
+  fn step_started(&self, step: &Step, attempt: usize) {
+    self.listener.step_started(step, attempt);
+  }
+
+  fn step_finished(&self, step: &Step, result: &StepResult) {
+    self.listener.step_finished(step, &self.redact_step_result(result));
+  }
+
+  fn request_sent(&self, step: &Step, request: &StepRequest) {
+    self.listener.request_sent(step, &self.redactor.redact_request(request));
+  }
+
+  fn response_received(&self, step: &Step, response: &StepResponse) {
+    self.listener.response_received(step, &self.redactor.redact_response(response));
+  }
+
+  fn criterion_evaluated(&self, step: &Step, result: &CriterionResult) {
+    self.listener.criterion_evaluated(step, &self.redact_criterion(result));
+  }
+
+  fn retry_scheduled(&self, step: &Step, attempt: usize, delay: Option<Duration>) {
+    self.listener.retry_scheduled(step, attempt, delay);
+  }
+
+  fn action_taken(&self, step: &Step, action_name: &str, action_type: &str) {
+    self.listener.action_taken(step, action_name, action_type);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{Arc, Mutex};
+  use std::time::Duration;
+
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::either::Either;
+  use crate::execution_context::{StepRequest, StepResponse};
+  use crate::executor::{CriterionResult, Executor, ExecutorOptions, StepResult, WorkflowResult};
+  use crate::extensions::AnyValue;
+  use crate::listener::{ExecutionListener, RedactingListener};
+  use crate::mock_executor::MockExecutor;
+  use crate::operations::{OpenApiSource, OperationResolver};
+  use crate::redaction::Redactor;
+  use crate::v1_0::{ArazzoDescription, Criterion, FailureObject, Step, Workflow};
+
+  #[derive(Default)]
+  struct RecordingListener {
+    events: Arc<Mutex<Vec<String>>>
+  }
+
+  impl RecordingListener {
+    fn record(&self, event: String) {
+      self.events.lock().unwrap().push(event);
+    }
+  }
+
+  impl ExecutionListener for RecordingListener {
+    fn workflow_started(&self, workflow: &Workflow, _inputs: &AnyValue) {
+      self.record(format!("workflow started {}", workflow.workflow_id));
+    }
+
+    fn workflow_finished(&self, result: &WorkflowResult) {
+      self.record(format!("workflow finished {} {:?}", result.workflow_id, result.status));
+    }
+
+    fn step_started(&self, step: &Step, attempt: usize) {
+      self.record(format!("step started {} {}", step.step_id, attempt));
+    }
+
+    fn step_finished(&self, step: &Step, result: &StepResult) {
+      self.record(format!("step finished {} {:?}", step.step_id, result.status));
+    }
+
+    fn request_sent(&self, _step: &Step, request: &StepRequest) {
+      self.record(format!("request {} {}", request.method, request.url));
+    }
+
+    fn response_received(&self, _step: &Step, response: &StepResponse) {
+      self.record(format!("response {}", response.status));
+    }
+
+    fn criterion_evaluated(&self, _step: &Step, result: &CriterionResult) {
+      self.record(format!("criterion {} {}", result.condition, result.passed));
+    }
+
+    fn retry_scheduled(&self, _step: &Step, attempt: usize, delay: Option<Duration>) {
+      self.record(format!("retry {} {:?}", attempt, delay));
+    }
+
+    fn action_taken(&self, _step: &Step, action_name: &str, action_type: &str) {
+      self.record(format!("action {} {}", action_name, action_type));
+    }
+  }
+
+  #[test]
+  fn notifies_the_listeners_of_the_execution_events() {
+    let description = ArazzoDescription {
+      workflows: vec![Workflow {
+        workflow_id: "pets".to_string(),
+        steps: vec![Step {
+          step_id: "find".to_string(),
+          operation_id: Some("findPets".to_string()),
+          success_criteria: vec![Criterion {
+            context: None,
+            condition: "$statusCode == 200".to_string(),
+            r#type: None,
+            extensions: Default::default()
+          }],
+          on_failure: vec![Either::First(FailureObject {
+            name: "again".to_string(),
+            r#type: "retry".to_string(),
+            workflow_id: None,
+            step_id: None,
+            retry_after: None,
+            retry_limit: Some(1),
+            criteria: vec![],
+            extensions: Default::default()
+          })],
+          .. Step::default()
+        }],
+        .. Workflow::default()
+      }],
+      .. ArazzoDescription::default()
+    };
+    let operations = OperationResolver::new(vec![OpenApiSource::new("petstore", "petstore.json", json!({
+      "servers": [{ "url": "http://localhost" }],
+      "paths": { "/pets": { "get": { "operationId": "findPets" } } }
+    }))]);
+    let mock = MockExecutor::new();
+    mock.respond_to_step("find", StepResponse::new(500))
+      .respond_to_step("find", StepResponse::new(200));
+    let listener = RecordingListener::default();
+    let events = listener.events.clone();
+    let executor = Executor::with_operations(&description, operations, ExecutorOptions::default())
+      .with_step_executor(mock)
+      .with_listener(listener);
+
+    let result = executor.execute("pets", AnyValue::Null).unwrap();
+    expect!(result.is_success()).to(be_true());
+    expect!(events.lock().unwrap().clone()).to(be_equal_to(vec![
+      "workflow started pets".to_string(),
+      "step started find 1".to_string(),
+      "request GET http://localhost/pets".to_string(),
+      "response 500".to_string(),
+      "criterion $statusCode == 200 false".to_string(),
+      "action again retry".to_string(),
+      "step finished find Failure".to_string(),
+      "retry 2 None".to_string(),
+      "step started find 2".to_string(),
+      "request GET http://localhost/pets".to_string(),
+      "response 200".to_string(),
+      "criterion $statusCode == 200 true".to_string(),
+      "step finished find Success".to_string(),
+      "workflow finished pets Success".to_string()
+    ]));
+  }
+
+  #[test]
+  fn redacts_the_events_passed_to_the_listener() {
+    let listener = RecordingListener::default();
+    let events = listener.events.clone();
+    let listener = RedactingListener::new(listener, Redactor::new());
+    let step = Step { step_id: "login".to_string(), .. Step::default() };
+    listener.request_sent(&step, &StepRequest {
+      method: "GET".to_string(),
+      url: "http://localhost/login?token=abc".to_string(),
+      headers: vec![],
+      body: None
+    });
+    expect!(events.lock().unwrap().clone()).to(be_equal_to(vec!["request GET http://localhost/login?token=***".to_string()]));
+  }
+}
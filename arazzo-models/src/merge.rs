@@ -0,0 +1,500 @@
+//! Support for merging multiple Arazzo descriptions into a single description
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::either::Either;
+use crate::expressions::{
+  for_each_component_expression_mut,
+  for_each_expression_mut,
+  rewrite_expression_references,
+  ReferenceKind
+};
+use crate::v1_0::{ArazzoDescription, Components, FailureObject, ReusableObject, SuccessObject, Workflow};
+
+/// What to do when an entry being merged has the same name or ID as a different existing entry.
+/// Entries that are identical are always merged into a single entry.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CollisionStrategy {
+  /// Fail the merge, reporting all the collisions
+  #[default]
+  Fail,
+  /// Keep the existing entry, and drop the one being merged. References to the dropped entry will
+  /// then refer to the existing one.
+  KeepExisting,
+  /// Rename the entry being merged, and rewrite all references to it
+  Rename
+}
+
+/// Options to control how descriptions are merged
+#[derive(Debug, Clone, Default)]
+pub struct MergeOptions {
+  /// What to do with entries that have the same name or ID
+  pub on_collision: CollisionStrategy,
+  /// Prefix to add to the names of renamed entries. If not set, a numeric suffix is added.
+  pub rename_prefix: Option<String>
+}
+
+/// How a collision was resolved
+#[derive(Debug, Clone, PartialEq)]
+pub enum CollisionResolution {
+  /// The merge failed
+  Failed,
+  /// The existing entry was kept
+  KeptExisting,
+  /// The merged entry was renamed to the given name
+  Renamed(String)
+}
+
+/// Entry with the same name or ID found in both descriptions
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeCollision {
+  /// Type of entry
+  pub kind: ReferenceKind,
+  /// Name or ID of the entry
+  pub name: String,
+  /// How the collision was resolved
+  pub resolution: CollisionResolution
+}
+
+impl Display for MergeCollision {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match &self.resolution {
+      CollisionResolution::Failed => write!(f, "{} '{}'", self.kind, self.name),
+      CollisionResolution::KeptExisting => write!(f, "{} '{}' (kept existing)", self.kind, self.name),
+      CollisionResolution::Renamed(name) => write!(f, "{} '{}' (renamed to '{}')", self.kind, self.name, name)
+    }
+  }
+}
+
+/// Result of merging descriptions
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MergeReport {
+  /// All the collisions that were found
+  pub collisions: Vec<MergeCollision>
+}
+
+/// Names of entries from the description being merged that have to be renamed or dropped
+#[derive(Debug, Default)]
+struct MergePlan {
+  renames: HashMap<ReferenceKind, HashMap<String, String>>,
+  skipped: HashSet<(ReferenceKind, String)>
+}
+
+impl MergePlan {
+  fn renamed(&self, kind: ReferenceKind, name: &str) -> Option<&String> {
+    self.renames.get(&kind).and_then(|names| names.get(name))
+  }
+
+  fn name_for(&self, kind: ReferenceKind, name: &str) -> String {
+    self.renamed(kind, name).cloned().unwrap_or_else(|| name.to_string())
+  }
+
+  fn is_skipped(&self, kind: ReferenceKind, name: &str) -> bool {
+    self.skipped.contains(&(kind, name.to_string()))
+  }
+
+  fn rewrite(&self, text: &mut String) {
+    if text.contains('$') {
+      *text = rewrite_expression_references(text, |reference| {
+        self.renamed(reference.kind, &reference.name).cloned()
+      });
+    }
+  }
+}
+
+impl ArazzoDescription {
+  /// Merges the source descriptions, workflows and components from the other description into
+  /// this one. The Info Object of this description is kept, and any extensions from the other
+  /// description that are not already set are added.
+  ///
+  /// Entries with the same name or ID are resolved using the collision strategy from the options.
+  /// If the merge fails, this description is left unchanged.
+  pub fn merge(&mut self, other: &ArazzoDescription, options: &MergeOptions) -> anyhow::Result<MergeReport> {
+    let mut report = MergeReport::default();
+    let mut plan = MergePlan::default();
+
+    merge_resolve_entries(
+      ReferenceKind::SourceDescription,
+      self.source_descriptions.iter().map(|sd| (sd.name.as_str(), sd)).collect(),
+      other.source_descriptions.iter().map(|sd| (sd.name.as_str(), sd)).collect(),
+      options, &mut plan, &mut report
+    );
+    merge_resolve_entries(
+      ReferenceKind::Workflow,
+      self.workflows.iter().map(|wf| (wf.workflow_id.as_str(), wf)).collect(),
+      other.workflows.iter().map(|wf| (wf.workflow_id.as_str(), wf)).collect(),
+      options, &mut plan, &mut report
+    );
+    merge_resolve_entries(
+      ReferenceKind::ComponentInput,
+      merge_sorted_entries(&self.components.inputs),
+      merge_sorted_entries(&other.components.inputs),
+      options, &mut plan, &mut report
+    );
+    merge_resolve_entries(
+      ReferenceKind::ComponentParameter,
+      merge_sorted_entries(&self.components.parameters),
+      merge_sorted_entries(&other.components.parameters),
+      options, &mut plan, &mut report
+    );
+    merge_resolve_entries(
+      ReferenceKind::ComponentSuccessAction,
+      merge_sorted_entries(&self.components.success_actions),
+      merge_sorted_entries(&other.components.success_actions),
+      options, &mut plan, &mut report
+    );
+    merge_resolve_entries(
+      ReferenceKind::ComponentFailureAction,
+      merge_sorted_entries(&self.components.failure_actions),
+      merge_sorted_entries(&other.components.failure_actions),
+      options, &mut plan, &mut report
+    );
+
+    if options.on_collision == CollisionStrategy::Fail && !report.collisions.is_empty() {
+      return Err(anyhow!("Can not merge descriptions as there are entries with the same name: {}",
+        report.collisions.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(", ")));
+    }
+
+    for source in &other.source_descriptions {
+      if !plan.is_skipped(ReferenceKind::SourceDescription, &source.name) {
+        let mut source = source.clone();
+        source.name = plan.name_for(ReferenceKind::SourceDescription, &source.name);
+        self.source_descriptions.push(source);
+      }
+    }
+
+    for workflow in &other.workflows {
+      if !plan.is_skipped(ReferenceKind::Workflow, &workflow.workflow_id) {
+        let mut workflow = workflow.clone();
+        merge_rewrite_workflow(&mut workflow, &plan);
+        self.workflows.push(workflow);
+      }
+    }
+
+    let mut components = other.components.clone();
+    merge_rewrite_components(&mut components, &plan);
+    merge_component_entries(&mut self.components.inputs, components.inputs, ReferenceKind::ComponentInput, &plan);
+    merge_component_entries(&mut self.components.parameters, components.parameters, ReferenceKind::ComponentParameter, &plan);
+    merge_component_entries(&mut self.components.success_actions, components.success_actions, ReferenceKind::ComponentSuccessAction, &plan);
+    merge_component_entries(&mut self.components.failure_actions, components.failure_actions, ReferenceKind::ComponentFailureAction, &plan);
+    for (key, value) in &other.components.extensions {
+      self.components.extensions.entry(key.clone()).or_insert_with(|| value.clone());
+    }
+
+    for (key, value) in &other.extensions {
+      self.extensions.entry(key.clone()).or_insert_with(|| value.clone());
+    }
+
+    Ok(report)
+  }
+}
+
+fn merge_sorted_entries<T>(map: &HashMap<String, T>) -> Vec<(&str, &T)> {
+  let mut entries = map.iter()
+    .map(|(k, v)| (k.as_str(), v))
+    .collect::<Vec<_>>();
+  entries.sort_by_key(|(k, _)| *k);
+  entries
+}
+
+fn merge_resolve_entries<T: PartialEq>(
+  kind: ReferenceKind,
+  existing: Vec<(&str, &T)>,
+  incoming: Vec<(&str, &T)>,
+  options: &MergeOptions,
+  plan: &mut MergePlan,
+  report: &mut MergeReport
+) {
+  let mut taken: HashSet<String> = existing.iter()
+    .chain(incoming.iter())
+    .map(|(name, _)| name.to_string())
+    .collect();
+  let existing: HashMap<&str, &T> = existing.into_iter().collect();
+
+  for (name, entry) in incoming {
+    if let Some(existing_entry) = existing.get(name) {
+      if *existing_entry == entry {
+        plan.skipped.insert((kind, name.to_string()));
+      } else {
+        let resolution = match options.on_collision {
+          CollisionStrategy::Fail => CollisionResolution::Failed,
+          CollisionStrategy::KeepExisting => {
+            plan.skipped.insert((kind, name.to_string()));
+            CollisionResolution::KeptExisting
+          }
+          CollisionStrategy::Rename => {
+            let new_name = merge_new_name(name, options.rename_prefix.as_deref(), &taken);
+            taken.insert(new_name.clone());
+            plan.renames.entry(kind).or_default().insert(name.to_string(), new_name.clone());
+            CollisionResolution::Renamed(new_name)
+          }
+        };
+        report.collisions.push(MergeCollision {
+          kind,
+          name: name.to_string(),
+          resolution
+        });
+      }
+    }
+  }
+}
+
+fn merge_new_name(name: &str, prefix: Option<&str>, taken: &HashSet<String>) -> String {
+  let base = if let Some(prefix) = prefix {
+    format!("{}{}", prefix, name)
+  } else {
+    name.to_string()
+  };
+
+  if prefix.is_some() && !taken.contains(&base) {
+    base
+  } else {
+    let mut count = 2;
+    loop {
+      let candidate = format!("{}-{}", base, count);
+      if !taken.contains(&candidate) {
+        return candidate;
+      }
+      count += 1;
+    }
+  }
+}
+
+fn merge_component_entries<T>(
+  existing: &mut HashMap<String, T>,
+  incoming: HashMap<String, T>,
+  kind: ReferenceKind,
+  plan: &MergePlan
+) {
+  for (key, value) in incoming {
+    if !plan.is_skipped(kind, &key) {
+      existing.insert(plan.name_for(kind, &key), value);
+    }
+  }
+}
+
+fn merge_rewrite_workflow(workflow: &mut Workflow, plan: &MergePlan) {
+  workflow.workflow_id = plan.name_for(ReferenceKind::Workflow, &workflow.workflow_id);
+
+  for depends_on in &mut workflow.depends_on {
+    if !depends_on.starts_with('$') {
+      *depends_on = plan.name_for(ReferenceKind::Workflow, depends_on);
+    }
+  }
+  merge_rewrite_success_actions(&mut workflow.success_actions, plan);
+  merge_rewrite_failure_actions(&mut workflow.failure_actions, plan);
+  for step in &mut workflow.steps {
+    if let Some(workflow_id) = &step.workflow_id && !workflow_id.starts_with('$') {
+      step.workflow_id = Some(plan.name_for(ReferenceKind::Workflow, workflow_id));
+    }
+    merge_rewrite_success_actions(&mut step.on_success, plan);
+    merge_rewrite_failure_actions(&mut step.on_failure, plan);
+  }
+  merge_rewrite_input_refs(&mut workflow.inputs, plan);
+
+  for_each_expression_mut(workflow, &mut |text| plan.rewrite(text));
+}
+
+fn merge_rewrite_components(components: &mut Components, plan: &MergePlan) {
+  for action in components.success_actions.values_mut() {
+    merge_rewrite_success_action(action, plan);
+  }
+  for action in components.failure_actions.values_mut() {
+    merge_rewrite_failure_action(action, plan);
+  }
+  for input in components.inputs.values_mut() {
+    merge_rewrite_input_refs(input, plan);
+  }
+
+  for_each_component_expression_mut(components, &mut |text| plan.rewrite(text));
+}
+
+fn merge_rewrite_success_actions(actions: &mut [Either<SuccessObject, ReusableObject>], plan: &MergePlan) {
+  for action in actions {
+    if let Either::First(action) = action {
+      merge_rewrite_success_action(action, plan);
+    }
+  }
+}
+
+fn merge_rewrite_success_action(action: &mut SuccessObject, plan: &MergePlan) {
+  if let Some(workflow_id) = &action.workflow_id && !workflow_id.starts_with('$') {
+    action.workflow_id = Some(plan.name_for(ReferenceKind::Workflow, workflow_id));
+  }
+}
+
+fn merge_rewrite_failure_actions(actions: &mut [Either<FailureObject, ReusableObject>], plan: &MergePlan) {
+  for action in actions {
+    if let Either::First(action) = action {
+      merge_rewrite_failure_action(action, plan);
+    }
+  }
+}
+
+fn merge_rewrite_failure_action(action: &mut FailureObject, plan: &MergePlan) {
+  if let Some(workflow_id) = &action.workflow_id && !workflow_id.starts_with('$') {
+    action.workflow_id = Some(plan.name_for(ReferenceKind::Workflow, workflow_id));
+  }
+}
+
+/// Rewrites any JSON Schema references to renamed component inputs (`#/components/inputs/<name>`)
+fn merge_rewrite_input_refs(json: &mut Value, plan: &MergePlan) {
+  match json {
+    Value::Object(map) => {
+      for (key, value) in map.iter_mut() {
+        if key == "$ref" && let Value::String(reference) = value {
+          if let Some(name) = reference.strip_prefix("#/components/inputs/") &&
+             let Some(new_name) = plan.renamed(ReferenceKind::ComponentInput, name) {
+            *reference = format!("#/components/inputs/{}", new_name);
+          }
+        } else {
+          merge_rewrite_input_refs(value, plan);
+        }
+      }
+    }
+    Value::Array(array) => for item in array {
+      merge_rewrite_input_refs(item, plan);
+    },
+    _ => {}
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::{btreemap, hashmap};
+  use serde_json::json;
+
+  use crate::either::Either;
+  use crate::expressions::ReferenceKind;
+  use crate::merge::{CollisionResolution, CollisionStrategy, MergeCollision, MergeOptions};
+  use crate::v1_0::*;
+
+  fn description(source_url: &str, workflow_id: &str, operation_id: &str) -> ArazzoDescription {
+    ArazzoDescription {
+      source_descriptions: vec![
+        SourceDescription {
+          name: "api".to_string(),
+          url: source_url.to_string(),
+          .. SourceDescription::default()
+        }
+      ],
+      workflows: vec![
+        Workflow {
+          workflow_id: workflow_id.to_string(),
+          inputs: json!({ "$ref": "#/components/inputs/credentials" }),
+          steps: vec![
+            Step {
+              step_id: "one".to_string(),
+              operation_id: Some(format!("$sourceDescriptions.api.{}", operation_id)),
+              parameters: vec![
+                Either::Second(ReusableObject {
+                  reference: "$components.parameters.page".to_string(),
+                  value: None
+                })
+              ],
+              .. Step::default()
+            }
+          ],
+          outputs: btreemap!{
+            "result".to_string() => "$steps.one.outputs.result".to_string()
+          },
+          .. Workflow::default()
+        }
+      ],
+      components: Components {
+        inputs: hashmap!{
+          "credentials".to_string() => json!({ "type": "object", "description": source_url })
+        },
+        parameters: hashmap!{
+          "page".to_string() => ParameterObject {
+            name: "page".to_string(),
+            value: Either::Second(format!("$inputs.{}", operation_id)),
+            .. ParameterObject::default()
+          }
+        },
+        .. Components::default()
+      },
+      .. ArazzoDescription::default()
+    }
+  }
+
+  #[test]
+  fn merge_descriptions_without_collisions() {
+    let mut first = description("http://one", "first", "getOne");
+    let mut second = description("http://one", "second", "getOne");
+    second.components = first.components.clone();
+
+    let report = first.merge(&second, &MergeOptions::default()).unwrap();
+    expect!(report.collisions.is_empty()).to(be_true());
+    expect!(first.source_descriptions.len()).to(be_equal_to(1));
+    expect!(first.workflows.iter().map(|wf| wf.workflow_id.as_str()).collect::<Vec<_>>())
+      .to(be_equal_to(vec!["first", "second"]));
+    expect!(first.components.parameters.len()).to(be_equal_to(1));
+  }
+
+  #[test]
+  fn merge_fails_if_there_are_collisions() {
+    let mut first = description("http://one", "first", "getOne");
+    let original = first.clone();
+    let second = description("http://two", "first", "getTwo");
+
+    let result = first.merge(&second, &MergeOptions::default());
+    expect!(result.as_ref()).to(be_err());
+    expect!(result.unwrap_err().to_string()).to(be_equal_to(
+      "Can not merge descriptions as there are entries with the same name: Source Description 'api', \
+      Workflow 'first', Component Input 'credentials', Component Parameter 'page'"));
+    expect!(first).to(be_equal_to(original));
+  }
+
+  #[test]
+  fn merge_can_keep_the_existing_entries() {
+    let mut first = description("http://one", "first", "getOne");
+    let second = description("http://two", "second", "getTwo");
+
+    let options = MergeOptions { on_collision: CollisionStrategy::KeepExisting, .. MergeOptions::default() };
+    let report = first.merge(&second, &options).unwrap();
+    expect!(report.collisions.len()).to(be_equal_to(3));
+    expect!(first.source_descriptions.len()).to(be_equal_to(1));
+    expect!(first.workflows.len()).to(be_equal_to(2));
+    expect!(first.components.parameters["page"].value.clone()).to(be_equal_to(Either::Second("$inputs.getOne".to_string())));
+  }
+
+  #[test]
+  fn merge_can_rename_entries_and_rewrite_the_references() {
+    let mut first = description("http://one", "first", "getOne");
+    let second = description("http://two", "first", "getTwo");
+
+    let options = MergeOptions { on_collision: CollisionStrategy::Rename, .. MergeOptions::default() };
+    let report = first.merge(&second, &options).unwrap();
+    expect!(report.collisions.clone()).to(be_equal_to(vec![
+      MergeCollision { kind: ReferenceKind::SourceDescription, name: "api".to_string(), resolution: CollisionResolution::Renamed("api-2".to_string()) },
+      MergeCollision { kind: ReferenceKind::Workflow, name: "first".to_string(), resolution: CollisionResolution::Renamed("first-2".to_string()) },
+      MergeCollision { kind: ReferenceKind::ComponentInput, name: "credentials".to_string(), resolution: CollisionResolution::Renamed("credentials-2".to_string()) },
+      MergeCollision { kind: ReferenceKind::ComponentParameter, name: "page".to_string(), resolution: CollisionResolution::Renamed("page-2".to_string()) }
+    ]));
+
+    expect!(first.source_descriptions.iter().map(|sd| sd.name.as_str()).collect::<Vec<_>>())
+      .to(be_equal_to(vec!["api", "api-2"]));
+    let workflow = &first.workflows[1];
+    expect!(workflow.workflow_id.as_str()).to(be_equal_to("first-2"));
+    expect!(&workflow.inputs).to(be_equal_to(&json!({ "$ref": "#/components/inputs/credentials-2" })));
+    expect!(workflow.steps[0].operation_id.clone()).to(be_some().value("$sourceDescriptions.api-2.getTwo"));
+    expect!(workflow.steps[0].parameters[0].clone()).to(be_equal_to(Either::Second(ReusableObject {
+      reference: "$components.parameters.page-2".to_string(),
+      value: None
+    })));
+    expect!(workflow.outputs["result"].as_str()).to(be_equal_to("$steps.one.outputs.result"));
+    expect!(first.components.parameters.contains_key("page-2")).to(be_true());
+    expect!(first.components.inputs.contains_key("credentials-2")).to(be_true());
+
+    let mut first = description("http://one", "first", "getOne");
+    let options = MergeOptions { on_collision: CollisionStrategy::Rename, rename_prefix: Some("other_".to_string()) };
+    first.merge(&second, &options).unwrap();
+    expect!(first.workflows[1].workflow_id.as_str()).to(be_equal_to("other_first"));
+  }
+}
@@ -0,0 +1,276 @@
+//! Loading of Arazzo descriptions from memory-mapped files. This is for very large documents (i.e.
+//! generated workflow suites of hundreds of megabytes), where reading the whole file into memory
+//! before parsing it would double the memory needed to load it.
+//!
+//! [`mmap_load`] parses the complete description straight from the mapped file (YAML documents are
+//! loaded with the streaming loader from the `yaml_stream` module). [`mmap_load_lazy`]
+//! only loads the top-level structure of a JSON document into memory. The workflows are left in the
+//! mapped file, and each one is only parsed the first time it is accessed (see the `lazy` module),
+//! so the metadata of a document can be inspected without materializing all the workflows.
+//!
+//! The file must not be modified (or truncated) while it is mapped, which is for as long as the
+//! [`LazyDescription`] returned by [`mmap_load_lazy`] is in use.
+//!
+//! ```rust,no_run
+//! use arazzo_models::mmap::mmap_load_lazy;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let description = mmap_load_lazy("workflows.json")?;
+//! for workflow in &description.workflows {
+//!   println!("{}: {}", workflow.workflow_id, workflow.summary.clone().unwrap_or_default());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use memmap2::Mmap;
+use serde_json::value::RawValue;
+use serde_json::{Map, Value};
+
+use crate::json::json_workflows_error;
+use crate::lazy::{check_workflows_not_empty, LazyDescription, LazyWorkflow, WorkflowSource};
+use crate::v1_0::ArazzoDescription;
+
+/// Keys of a workflow that are loaded straight away by the lazy loader
+const WORKFLOW_METADATA_KEYS: [&str; 3] = ["workflowId", "summary", "description"];
+
+/// Text of a value in a memory-mapped document
+#[derive(Clone)]
+pub(crate) struct MappedText {
+  mmap: Arc<Mmap>,
+  range: Range<usize>
+}
+
+impl MappedText {
+  /// Bytes of the value
+  pub(crate) fn as_bytes(&self) -> &[u8] {
+    &self.mmap[self.range.clone()]
+  }
+}
+
+impl Debug for MappedText {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "MappedText({:?})", self.range)
+  }
+}
+
+/// Maps the file into memory
+fn map_file(path: &Path) -> anyhow::Result<Mmap> {
+  let file = File::open(path)
+    .with_context(|| format!("Failed to open '{}'", path.display()))?;
+  // SAFETY: The mapped memory is only valid while the file is not modified. This is the same
+  // requirement as any other reader of the file, and is documented on the public functions.
+  unsafe { Mmap::map(&file) }
+    .with_context(|| format!("Failed to map '{}' into memory", path.display()))
+}
+
+/// If the document is a JSON document (the first non-whitespace character is a `{`)
+fn is_json(bytes: &[u8]) -> bool {
+  bytes.iter()
+    .find(|b| !b.is_ascii_whitespace())
+    .is_some_and(|b| *b == b'{')
+}
+
+/// Loads an Arazzo description from a JSON or YAML file, parsing the document straight from the
+/// memory-mapped file. The file must not be modified while it is being loaded.
+pub fn mmap_load<P: AsRef<Path>>(path: P) -> anyhow::Result<ArazzoDescription> {
+  let path = path.as_ref();
+  let mmap = map_file(path)?;
+  if is_json(&mmap) {
+    let json: Value = serde_json::from_slice(&mmap)
+      .with_context(|| format!("Failed to parse '{}' as JSON", path.display()))?;
+    ArazzoDescription::try_from(&json)
+  } else {
+    load_yaml(path, &mmap)
+  }
+}
+
+#[cfg(feature = "yaml")]
+fn load_yaml(path: &Path, bytes: &[u8]) -> anyhow::Result<ArazzoDescription> {
+  let source = std::str::from_utf8(bytes)
+    .with_context(|| format!("'{}' is not a valid UTF-8 document", path.display()))?;
+  crate::yaml_stream::yaml_stream_load(source)
+}
+
+#[cfg(not(feature = "yaml"))]
+fn load_yaml(path: &Path, _bytes: &[u8]) -> anyhow::Result<ArazzoDescription> {
+  Err(anyhow!("'{}' is not a JSON document, and the yaml feature is not enabled", path.display()))
+}
+
+/// Lazily loads an Arazzo description from a memory-mapped JSON file. Only the top-level structure
+/// of the description and the IDs, summaries and descriptions of the workflows are loaded. Each
+/// workflow is parsed from the mapped file the first time it is accessed.
+///
+/// The file stays mapped until the returned description (and all its workflows) are dropped, and
+/// must not be modified before then.
+pub fn mmap_load_lazy<P: AsRef<Path>>(path: P) -> anyhow::Result<LazyDescription> {
+  let path = path.as_ref();
+  let mmap = Arc::new(map_file(path)?);
+  if !is_json(&mmap) {
+    return Err(anyhow!("'{}' is not a JSON document, only JSON documents can be loaded lazily", path.display()));
+  }
+
+  let fields: HashMap<String, &RawValue> = serde_json::from_slice(&mmap)
+    .with_context(|| format!("Failed to parse '{}' as JSON", path.display()))?;
+  let mut map = Map::new();
+  let mut workflows = None;
+  for (key, raw) in fields {
+    if key == "workflows" {
+      workflows = Some(raw);
+    } else {
+      map.insert(key, serde_json::from_str(raw.get())?);
+    }
+  }
+
+  LazyDescription::from_parts(&map, || {
+    let workflows: Vec<&RawValue> = match workflows {
+      Some(raw) if raw.get().starts_with('[') => serde_json::from_str(raw.get())?,
+      Some(raw) => return Err(json_workflows_error(Some(&serde_json::from_str(raw.get())?))),
+      None => return Err(json_workflows_error(None))
+    };
+    check_workflows_not_empty(workflows.len())?;
+    workflows.iter()
+      .map(|raw| mapped_workflow(&mmap, raw))
+      .collect()
+  })
+}
+
+/// Creates a lazy workflow for the workflow in the mapped document, only parsing the keys needed
+/// for the workflow metadata
+fn mapped_workflow(mmap: &Arc<Mmap>, raw: &RawValue) -> anyhow::Result<LazyWorkflow> {
+  let start = raw.get().as_ptr() as usize - mmap.as_ptr() as usize;
+  let source = WorkflowSource::Mapped(MappedText {
+    mmap: mmap.clone(),
+    range: start..start + raw.get().len()
+  });
+
+  let metadata = match serde_json::from_str::<HashMap<&str, &RawValue>>(raw.get()) {
+    Ok(fields) => {
+      let mut metadata = Map::new();
+      for key in WORKFLOW_METADATA_KEYS {
+        if let Some(value) = fields.get(key) {
+          metadata.insert(key.to_string(), serde_json::from_str(value.get())?);
+        }
+      }
+      Value::Object(metadata)
+    }
+    // Not an object, so parse the value to return the same error as the other loaders
+    Err(_) => serde_json::from_str(raw.get())?
+  };
+  LazyWorkflow::new(&metadata, source)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+  use std::path::PathBuf;
+
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::mmap::{mmap_load, mmap_load_lazy};
+  use crate::v1_0::ArazzoDescription;
+
+  fn document() -> serde_json::Value {
+    json!({
+      "arazzo": "1.0.1",
+      "info": { "title": "Pets", "version": "1.0.0" },
+      "sourceDescriptions": [ { "name": "petstore", "url": "petstore.json" } ],
+      "workflows": [
+        {
+          "workflowId": "listPets",
+          "summary": "Lists the pets",
+          "steps": [ { "stepId": "list", "operationId": "listPets" } ]
+        },
+        {
+          "workflowId": "broken",
+          "steps": []
+        }
+      ],
+      "x-owner": "pets"
+    })
+  }
+
+  fn write_file(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("arazzo-mmap-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, contents).unwrap();
+    path
+  }
+
+  #[test]
+  fn loads_json_and_yaml_files() {
+    let mut json = document();
+    json["workflows"].as_array_mut().unwrap().pop();
+    let expected = ArazzoDescription::try_from(&json).unwrap();
+
+    let path = write_file("load.json", json.to_string().as_str());
+    expect!(mmap_load(&path).unwrap()).to(be_equal_to(expected.clone()));
+
+    let path = write_file("load.yaml", serde_yaml::to_string(&json).unwrap().as_str());
+    expect!(mmap_load(&path).unwrap()).to(be_equal_to(expected));
+
+    expect!(mmap_load(path.with_file_name("missing.json"))).to(be_err());
+  }
+
+  #[test]
+  fn reports_the_type_of_workflows_that_are_not_an_array() {
+    let mut json = document();
+    json["workflows"] = json!("listPets");
+
+    let path = write_file("not-array.json", json.to_string().as_str());
+    expect!(mmap_load(&path).unwrap_err().to_string())
+      .to(be_equal_to("Workflows must be an Array, got String [4.6.1.1 Fixed Fields]"));
+
+    let path = write_file("not-array.yaml", serde_yaml::to_string(&json).unwrap().as_str());
+    expect!(mmap_load(&path).unwrap_err().to_string())
+      .to(be_equal_to("Workflows must be an Array, got String [4.6.1.1 Fixed Fields]"));
+  }
+
+  #[test]
+  fn lazily_loads_workflows_from_the_mapped_file() {
+    let path = write_file("lazy.json", serde_json::to_string_pretty(&document()).unwrap().as_str());
+    let description = mmap_load_lazy(&path).unwrap();
+    expect!(description.info.title.as_str()).to(be_equal_to("Pets"));
+    expect!(description.workflow_ids().collect::<Vec<_>>()).to(be_equal_to(vec!["listPets", "broken"]));
+    expect!(description.workflows[0].summary.clone()).to(be_some().value("Lists the pets"));
+    expect!(description.extensions.contains_key("owner")).to(be_true());
+
+    let workflow = description.workflow("listPets").unwrap();
+    expect!(workflow.is_loaded()).to(be_false());
+    expect!(workflow.load().unwrap().steps[0].step_id.as_str()).to(be_equal_to("list"));
+    expect!(workflow.json().unwrap().into_owned()).to(be_equal_to(document()["workflows"][0].clone()));
+    expect!(description.workflow("broken").unwrap().load().unwrap_err().to_string())
+      .to(be_equal_to("Failed to load workflow 'broken': At lest one Step is required [4.6.4.1 Fixed Fields]"));
+  }
+
+  #[test]
+  fn lazy_loading_fails_if_the_top_level_structure_is_not_valid() {
+    let mut json = document();
+    json["workflows"] = json!({});
+    let path = write_file("invalid.json", json.to_string().as_str());
+    expect!(mmap_load_lazy(&path).unwrap_err().to_string())
+      .to(be_equal_to("Workflows must be an Array, got Object [4.6.1.1 Fixed Fields]"));
+
+    json.as_object_mut().unwrap().remove("workflows");
+    let path = write_file("invalid.json", json.to_string().as_str());
+    expect!(mmap_load_lazy(&path).unwrap_err().to_string())
+      .to(be_equal_to("Workflow Object is required [4.6.1.1 Fixed Fields]"));
+
+    json["workflows"] = json!([ { "summary": "No ID" } ]);
+    let path = write_file("invalid.json", json.to_string().as_str());
+    expect!(mmap_load_lazy(&path)).to(be_err());
+
+    let path = write_file("lazy.yaml", "arazzo: 1.0.1\n");
+    expect!(mmap_load_lazy(&path)).to(be_err());
+  }
+}
@@ -0,0 +1,264 @@
+//! Step executor that returns canned responses, for unit testing workflows (their criteria, actions
+//! and output wiring) without any network access.
+//!
+//! ```rust
+//! # use arazzo_models::execution_context::StepResponse;
+//! # use arazzo_models::executor::{Executor, ExecutorOptions};
+//! # use arazzo_models::extensions::AnyValue;
+//! # use arazzo_models::mock_executor::MockExecutor;
+//! # use arazzo_models::operations::OperationResolver;
+//! # use arazzo_models::v1_0::{ArazzoDescription, Step, Workflow};
+//! # fn main() -> anyhow::Result<()> {
+//! # let description = ArazzoDescription {
+//! #   workflows: vec![Workflow {
+//! #     workflow_id: "login".to_string(),
+//! #     steps: vec![Step { step_id: "login".to_string(), operation_id: Some("loginUser".to_string()), .. Step::default() }],
+//! #     .. Workflow::default()
+//! #   }],
+//! #   .. ArazzoDescription::default()
+//! # };
+//! # let operations = OperationResolver::new(vec![arazzo_models::operations::OpenApiSource::new("api", "api.json",
+//! #   serde_json::json!({ "servers": [{ "url": "http://localhost" }], "paths": { "/login": { "post": { "operationId": "loginUser" } } } }))]);
+//! let mock = MockExecutor::new();
+//! mock.respond_to_operation("loginUser", StepResponse::new(200).with_body("token"));
+//!
+//! let executor = Executor::with_operations(&description, operations, ExecutorOptions::default())
+//!   .with_step_executor(mock.clone());
+//! let result = executor.execute("login", AnyValue::Null)?;
+//!
+//! assert!(result.is_success());
+//! mock.assert_steps_invoked(&["login"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use anyhow::anyhow;
+
+use crate::execution_context::{ExecutionContext, StepRequest, StepResponse};
+use crate::executor::StepExecutor;
+use crate::operations::Operation;
+use crate::v1_0::Step;
+
+/// Request that the mock executor received
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockInvocation {
+  /// ID of the step
+  pub step_id: String,
+  /// ID of the operation, if the operation has one
+  pub operation_id: Option<String>,
+  /// Request for the step
+  pub request: StepRequest
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+  step_responses: HashMap<String, VecDeque<StepResponse>>,
+  operation_responses: HashMap<String, VecDeque<StepResponse>>,
+  invocations: Vec<MockInvocation>
+}
+
+/// Step executor that returns the responses registered for the step ID or operation ID (responses
+/// registered for a step take precedence). If more than one response is registered for a step or
+/// operation, they are returned in order, with the last one repeated for any further requests.
+///
+/// Clones share the same responses and invocations, so a clone can be given to the [`Executor`](crate::executor::Executor)
+/// and the original used to check which steps were invoked.
+#[derive(Debug, Clone, Default)]
+pub struct MockExecutor {
+  state: Arc<Mutex<MockState>>
+}
+
+impl MockExecutor {
+  /// Creates a mock executor with no responses
+  pub fn new() -> Self {
+    MockExecutor::default()
+  }
+
+  fn state(&self) -> MutexGuard<'_, MockState> {
+    self.state.lock().unwrap_or_else(|err| err.into_inner())
+  }
+
+  /// Registers a response for the step
+  pub fn respond_to_step<S: Into<String>>(&self, step_id: S, response: StepResponse) -> &Self {
+    self.state().step_responses.entry(step_id.into()).or_default().push_back(response);
+    self
+  }
+
+  /// Registers a response for the operation
+  pub fn respond_to_operation<S: Into<String>>(&self, operation_id: S, response: StepResponse) -> &Self {
+    self.state().operation_responses.entry(operation_id.into()).or_default().push_back(response);
+    self
+  }
+
+  /// Returns all the requests received, in the order they were received
+  pub fn invocations(&self) -> Vec<MockInvocation> {
+    self.state().invocations.clone()
+  }
+
+  /// Returns the IDs of the steps that were invoked, in the order they were invoked
+  pub fn invoked_steps(&self) -> Vec<String> {
+    self.state().invocations.iter()
+      .map(|invocation| invocation.step_id.clone())
+      .collect()
+  }
+
+  /// Returns the number of times the step was invoked
+  pub fn invocation_count(&self, step_id: &str) -> usize {
+    self.state().invocations.iter()
+      .filter(|invocation| invocation.step_id == step_id)
+      .count()
+  }
+
+  /// If the step was invoked
+  pub fn was_invoked(&self, step_id: &str) -> bool {
+    self.invocation_count(step_id) > 0
+  }
+
+  /// Panics if the steps invoked do not match the expected step IDs (in order)
+  #[track_caller]
+  pub fn assert_steps_invoked(&self, expected: &[&str]) {
+    let invoked = self.invoked_steps();
+    if invoked != expected {
+      panic!("Expected steps {:?} to be invoked, but the steps invoked were {:?}", expected, invoked);
+    }
+  }
+
+  /// Panics if the step was invoked
+  #[track_caller]
+  pub fn assert_not_invoked(&self, step_id: &str) {
+    let count = self.invocation_count(step_id);
+    if count > 0 {
+      panic!("Expected step '{}' not to be invoked, but it was invoked {} time(s)", step_id, count);
+    }
+  }
+
+  /// Clears the recorded invocations (the registered responses are kept)
+  pub fn clear_invocations(&self) {
+    self.state().invocations.clear();
+  }
+}
+
+fn next_response(responses: Option<&mut VecDeque<StepResponse>>) -> Option<StepResponse> {
+  let responses = responses?;
+  if responses.len() > 1 {
+    responses.pop_front()
+  } else {
+    responses.front().cloned()
+  }
+}
+
+impl StepExecutor for MockExecutor {
+  fn execute(
+    &self,
+    step: &Step,
+    operation: &Operation,
+    request: &StepRequest,
+    _context: &ExecutionContext
+  ) -> anyhow::Result<StepResponse> {
+    let mut state = self.state();
+    state.invocations.push(MockInvocation {
+      step_id: step.step_id.clone(),
+      operation_id: operation.operation_id.clone(),
+      request: request.clone()
+    });
+
+    let response = next_response(state.step_responses.get_mut(&step.step_id));
+    let response = match (response, &operation.operation_id) {
+      (Some(response), _) => Some(response),
+      (None, Some(operation_id)) => next_response(state.operation_responses.get_mut(operation_id)),
+      (None, None) => None
+    };
+    response.ok_or_else(|| anyhow!("No mock response was registered for step '{}' (operation '{}')",
+      step.step_id, operation.operation_id.as_deref().unwrap_or(&operation.path)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::arazzo;
+  use crate::execution_context::StepResponse;
+  use crate::executor::{ExecutionStatus, Executor, ExecutorOptions};
+  use crate::extensions::AnyValue;
+  use crate::mock_executor::MockExecutor;
+  use crate::operations::{OpenApiSource, OperationResolver};
+  use crate::v1_0::ArazzoDescription;
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      workflow("pets") {
+        step("find") {
+          operation_id: "findPets";
+          success_criterion: "$statusCode == 200";
+          output("id", "$response.body#/0/id");
+        }
+        step("get") {
+          operation_id: "getPet";
+          success_criterion: "$statusCode == 200";
+        }
+      }
+    }
+  }
+
+  fn operations() -> OperationResolver {
+    OperationResolver::new(vec![OpenApiSource::new("petstore", "petstore.json", json!({
+      "servers": [{ "url": "http://localhost" }],
+      "paths": {
+        "/pets": { "get": { "operationId": "findPets" } },
+        "/pets/1": { "get": { "operationId": "getPet" } }
+      }
+    }))])
+  }
+
+  #[test]
+  fn returns_the_registered_responses() {
+    let description = description();
+    let mock = MockExecutor::new();
+    mock.respond_to_operation("findPets", StepResponse::new(200).with_json(&json!([{ "id": 1 }])))
+      .respond_to_operation("getPet", StepResponse::new(404))
+      .respond_to_step("get", StepResponse::new(200));
+    let executor = Executor::with_operations(&description, operations(), ExecutorOptions::default())
+      .with_step_executor(mock.clone());
+
+    let result = executor.execute("pets", AnyValue::Null).unwrap();
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Success));
+    expect!(result.step("find").unwrap().outputs.get("id")).to(be_some().value(&AnyValue::UInteger(1)));
+    mock.assert_steps_invoked(&["find", "get"]);
+    expect!(mock.invocations()[1].operation_id.as_deref()).to(be_some().value("getPet"));
+    expect!(mock.invocations()[1].request.url.as_str()).to(be_equal_to("http://localhost/pets/1"));
+  }
+
+  #[test]
+  fn returns_the_responses_in_order() {
+    let description = description();
+    let mock = MockExecutor::new();
+    mock.respond_to_operation("findPets", StepResponse::new(500))
+      .respond_to_operation("findPets", StepResponse::new(200).with_json(&json!([{ "id": 1 }])));
+    let executor = Executor::with_operations(&description, operations(), ExecutorOptions::default())
+      .with_step_executor(mock.clone());
+
+    let result = executor.execute("pets", AnyValue::Null).unwrap();
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Failure));
+    mock.assert_steps_invoked(&["find"]);
+    mock.assert_not_invoked("get");
+
+    mock.clear_invocations();
+    let result = executor.execute("pets", AnyValue::Null).unwrap();
+    expect!(result.status).to(be_equal_to(ExecutionStatus::Error));
+    expect!(result.step("get").unwrap().error.as_deref())
+      .to(be_some().value("No mock response was registered for step 'get' (operation 'getPet')"));
+    expect!(mock.invocation_count("find")).to(be_equal_to(1));
+    expect!(mock.was_invoked("get")).to(be_true());
+  }
+
+  #[test]
+  #[should_panic(expected = "Expected steps [\"get\"] to be invoked, but the steps invoked were []")]
+  fn assert_steps_invoked_panics_if_the_steps_do_not_match() {
+    MockExecutor::new().assert_steps_invoked(&["get"]);
+  }
+}
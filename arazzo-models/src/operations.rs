@@ -0,0 +1,308 @@
+//! Resolves the operations that steps reference (with `operationId` or `operationPath`) from the
+//! OpenAPI source descriptions.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+
+use crate::http_config::HttpClientConfig;
+use crate::v1_0::{ArazzoDescription, SourceDescription, Step};
+
+/// HTTP methods that can be used for operations in an OpenAPI Path Item
+const METHODS: [&str; 8] = ["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// Operation from an OpenAPI description
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+  /// Name of the source description the operation is from
+  pub source_name: String,
+  /// Operation ID, if the operation has one
+  pub operation_id: Option<String>,
+  /// HTTP method (uppercase)
+  pub method: String,
+  /// Path template of the operation (i.e. `/pets/{petId}`)
+  pub path: String,
+  /// URL of the server to send requests to (from the `servers` of the operation, path or
+  /// document), with any server variables replaced with their default values
+  pub server_url: Option<String>
+}
+
+/// OpenAPI description loaded from a source description
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenApiSource {
+  /// Name of the source description
+  pub name: String,
+  /// URL of the source description
+  pub url: String,
+  /// OpenAPI document
+  pub document: Value
+}
+
+impl OpenApiSource {
+  /// Creates a source from an OpenAPI document
+  pub fn new<N: Into<String>, U: Into<String>>(name: N, url: U, document: Value) -> Self {
+    OpenApiSource { name: name.into(), url: url.into(), document }
+  }
+
+  /// Loads the OpenAPI document for the source description. The URL can be a `http:`, `https:`, `file:` or
+  /// `data:` URL, or a file path (relative paths are resolved against the base directory, if given). The
+  /// document can be JSON, or YAML if the `yaml` feature is enabled.
+  pub fn load(source: &SourceDescription, base_dir: Option<&Path>) -> anyhow::Result<Self> {
+    OpenApiSource::load_with_config(source, base_dir, &HttpClientConfig::default())
+  }
+
+  /// Loads the OpenAPI document for the source description, fetching HTTP URLs with the
+  /// client configuration (i.e. through a proxy)
+  pub fn load_with_config(
+    source: &SourceDescription,
+    base_dir: Option<&Path>,
+    config: &HttpClientConfig
+  ) -> anyhow::Result<Self> {
+    let contents = if source.url.starts_with("http://") || source.url.starts_with("https://") {
+      crate::http_config::get(&source.url, config)?
+    } else if let Some(data) = source.url.strip_prefix("data:") {
+      data_url_contents(data)
+        .with_context(|| format!("Failed to load source description '{}' from a data URL", source.name))?
+    } else {
+      let path = PathBuf::from(source.url.strip_prefix("file://").unwrap_or(&source.url));
+      let path = match base_dir {
+        Some(base_dir) if path.is_relative() => base_dir.join(path),
+        _ => path
+      };
+      fs::read(&path)
+        .map(bytes::Bytes::from)
+        .with_context(|| format!("Failed to load source description '{}' from '{}'", source.name, path.display()))?
+    };
+    let document = parse_document(&contents)
+      .with_context(|| format!("Failed to load source description '{}' from '{}'", source.name, source.url))?;
+    Ok(OpenApiSource::new(source.name.as_str(), source.url.as_str(), document))
+  }
+
+  /// Returns a `data:` URL with the document encoded as JSON, which can be used as the URL of the
+  /// source description to embed the document in an Arazzo description
+  pub fn to_data_url(&self) -> String {
+    format!("data:application/json;base64,{}", crate::base64::encode(self.document.to_string().as_bytes()))
+  }
+
+  /// Finds the operation with the operation ID
+  pub fn find_operation_by_id(&self, operation_id: &str) -> Option<Operation> {
+    let paths = self.document.get("paths")?.as_object()?;
+    paths.iter()
+      .flat_map(|(path, item)| METHODS.iter().map(move |method| (path, item, *method)))
+      .find(|(_, item, method)| item.get(method)
+        .and_then(|operation| operation.get("operationId"))
+        .and_then(|id| id.as_str()) == Some(operation_id))
+      .map(|(path, item, method)| self.operation(path, item, method))
+  }
+
+  /// Finds the operation with the JSON Pointer (i.e. `/paths/~1pets~1{petId}/get`)
+  pub fn find_operation_by_pointer(&self, pointer: &str) -> Option<Operation> {
+    let (item_pointer, method) = pointer.rsplit_once('/')?;
+    let path = item_pointer.strip_prefix("/paths/")?
+      .replace("~1", "/")
+      .replace("~0", "~");
+    let method = method.to_lowercase();
+    let item = self.document.get("paths")?.get(&path)?;
+    item.get(&method)?;
+    Some(self.operation(&path, item, &method))
+  }
+
+  fn operation(&self, path: &str, item: &Value, method: &str) -> Operation {
+    let operation = &item[method];
+    let server_url = [operation, item, &self.document].iter()
+      .find_map(|value| value.get("servers")?.as_array()?.first().and_then(server_url));
+    Operation {
+      source_name: self.name.clone(),
+      operation_id: operation.get("operationId").and_then(|id| id.as_str()).map(|id| id.to_string()),
+      method: method.to_uppercase(),
+      path: path.to_string(),
+      server_url
+    }
+  }
+}
+
+/// Returns the contents of a `data:` URL (without the scheme). Only Base64 encoded data and data
+/// that does not need to be percent-decoded are supported.
+fn data_url_contents(data: &str) -> anyhow::Result<bytes::Bytes> {
+  let (media_type, data) = data.split_once(',')
+    .ok_or_else(|| anyhow!("The data URL does not have any data"))?;
+  if media_type.ends_with(";base64") {
+    crate::base64::decode(data).map(bytes::Bytes::from)
+  } else {
+    Ok(bytes::Bytes::from(data.to_string()))
+  }
+}
+
+pub(crate) fn parse_document(contents: &[u8]) -> anyhow::Result<Value> {
+  match serde_json::from_slice(contents) {
+    Ok(document) => Ok(document),
+    #[cfg(feature = "yaml")]
+    Err(_) => {
+      let yaml = crate::yaml::yaml_load_documents(&String::from_utf8_lossy(contents))?;
+      let document = yaml.first().ok_or_else(|| anyhow!("The YAML document is empty"))?;
+      crate::yaml::yaml_to_json(document)
+    }
+    #[cfg(not(feature = "yaml"))]
+    Err(err) => Err(anyhow!(err))
+  }
+}
+
+/// Returns the URL of the Server Object, with any variables replaced with their default values
+fn server_url(server: &Value) -> Option<String> {
+  let mut url = server.get("url")?.as_str()?.to_string();
+  if let Some(variables) = server.get("variables").and_then(|variables| variables.as_object()) {
+    for (name, variable) in variables {
+      if let Some(default) = variable.get("default").and_then(|default| default.as_str()) {
+        url = url.replace(&format!("{{{}}}", name), default);
+      }
+    }
+  }
+  Some(url)
+}
+
+/// Resolves operations from a set of OpenAPI source descriptions
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OperationResolver {
+  /// Loaded OpenAPI descriptions
+  pub sources: Vec<OpenApiSource>
+}
+
+impl OperationResolver {
+  /// Creates a resolver for the OpenAPI descriptions
+  pub fn new(sources: Vec<OpenApiSource>) -> Self {
+    OperationResolver { sources }
+  }
+
+  /// Loads all the OpenAPI source descriptions of the Arazzo description (any `arazzo` source
+  /// descriptions are skipped). Relative file paths are resolved against the base directory.
+  pub fn load(description: &ArazzoDescription, base_dir: Option<&Path>) -> anyhow::Result<Self> {
+    OperationResolver::load_with_config(description, base_dir, &HttpClientConfig::default())
+  }
+
+  /// Loads all the OpenAPI source descriptions of the Arazzo description, fetching HTTP URLs
+  /// with the client configuration
+  pub fn load_with_config(
+    description: &ArazzoDescription,
+    base_dir: Option<&Path>,
+    config: &HttpClientConfig
+  ) -> anyhow::Result<Self> {
+    let sources = description.source_descriptions.iter()
+      .filter(|source| source.r#type.as_deref() != Some("arazzo"))
+      .map(|source| OpenApiSource::load_with_config(source, base_dir, config))
+      .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(OperationResolver::new(sources))
+  }
+
+  /// Resolves the operation ID. The ID can be qualified with the source description
+  /// (`$sourceDescriptions.<name>.<operationId>`), otherwise all the sources are searched.
+  pub fn resolve_operation_id(&self, operation_id: &str) -> anyhow::Result<Operation> {
+    let operation = match operation_id.strip_prefix("$sourceDescriptions.")
+      .and_then(|rest| rest.split_once('.')) {
+      Some((name, operation_id)) => self.source(name)?.find_operation_by_id(operation_id),
+      None => self.sources.iter().find_map(|source| source.find_operation_by_id(operation_id))
+    };
+    operation.ok_or_else(|| anyhow!("No operation with ID '{}' was found in the source descriptions", operation_id))
+  }
+
+  /// Resolves the operation path, which is a source description URL expression with a JSON
+  /// Pointer to the operation (i.e. `{$sourceDescriptions.petstore.url}#/paths/~1pets/get`)
+  pub fn resolve_operation_path(&self, operation_path: &str) -> anyhow::Result<Operation> {
+    let (source, pointer) = operation_path.split_once('#')
+      .ok_or_else(|| anyhow!("Operation path '{}' does not have a JSON Pointer to the operation", operation_path))?;
+    let source = source.trim();
+    let source = match source.strip_prefix("{$sourceDescriptions.")
+      .and_then(|rest| rest.strip_suffix(".url}")) {
+      Some(name) => self.source(name)?,
+      None => self.sources.iter()
+        .find(|s| s.url == source)
+        .ok_or_else(|| anyhow!("No source description with URL '{}' was found", source))?
+    };
+    source.find_operation_by_pointer(pointer)
+      .ok_or_else(|| anyhow!("No operation was found at '{}' in source description '{}'", pointer, source.name))
+  }
+
+  /// Resolves the operation the step references. Returns an error if the step does not reference
+  /// an operation (i.e. it references a workflow).
+  pub fn resolve_step(&self, step: &Step) -> anyhow::Result<Operation> {
+    if let Some(operation_id) = &step.operation_id {
+      self.resolve_operation_id(operation_id)
+    } else if let Some(operation_path) = &step.operation_path {
+      self.resolve_operation_path(operation_path)
+    } else {
+      Err(anyhow!("Step '{}' does not reference an operation", step.step_id))
+    }
+  }
+
+  fn source(&self, name: &str) -> anyhow::Result<&OpenApiSource> {
+    self.sources.iter()
+      .find(|source| source.name == name)
+      .ok_or_else(|| anyhow!("No source description with name '{}' was found", name))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::operations::{OpenApiSource, Operation, OperationResolver};
+  use crate::v1_0::{SourceDescription, Step};
+
+  fn petstore() -> OpenApiSource {
+    OpenApiSource::new("petstore", "petstore.json", json!({
+      "openapi": "3.1.0",
+      "servers": [{ "url": "http://{host}/v1", "variables": { "host": { "default": "localhost:8080" } } }],
+      "paths": {
+        "/pets": {
+          "get": { "operationId": "findPets" },
+          "post": { "operationId": "addPet", "servers": [{ "url": "http://admin.local" }] }
+        },
+        "/pets/{petId}": {
+          "get": { "operationId": "getPet" }
+        }
+      }
+    }))
+  }
+
+  #[test]
+  fn resolves_operations() {
+    let resolver = OperationResolver::new(vec![petstore()]);
+    expect!(resolver.resolve_operation_id("getPet").unwrap()).to(be_equal_to(Operation {
+      source_name: "petstore".to_string(),
+      operation_id: Some("getPet".to_string()),
+      method: "GET".to_string(),
+      path: "/pets/{petId}".to_string(),
+      server_url: Some("http://localhost:8080/v1".to_string())
+    }));
+    expect!(resolver.resolve_operation_id("$sourceDescriptions.petstore.addPet").unwrap().server_url)
+      .to(be_some().value("http://admin.local"));
+    expect!(resolver.resolve_operation_id("$sourceDescriptions.other.addPet")).to(be_err());
+    expect!(resolver.resolve_operation_id("deletePet").unwrap_err().to_string())
+      .to(be_equal_to("No operation with ID 'deletePet' was found in the source descriptions"));
+
+    let operation = resolver.resolve_operation_path("{$sourceDescriptions.petstore.url}#/paths/~1pets~1{petId}/get").unwrap();
+    expect!(operation.operation_id).to(be_some().value("getPet"));
+    expect!(resolver.resolve_operation_path("petstore.json#/paths/~1pets/post").unwrap().method).to(be_equal_to("POST"));
+    expect!(resolver.resolve_operation_path("{$sourceDescriptions.petstore.url}#/paths/~1pets/delete")).to(be_err());
+
+    let step = Step { step_id: "one".to_string(), operation_id: Some("findPets".to_string()), .. Step::default() };
+    expect!(resolver.resolve_step(&step).unwrap().path).to(be_equal_to("/pets"));
+    let step = Step { operation_id: None, workflow_id: Some("other".to_string()), .. step };
+    expect!(resolver.resolve_step(&step)).to(be_err());
+  }
+
+  #[test]
+  fn loads_sources_from_data_urls() {
+    let source = SourceDescription {
+      name: "petstore".to_string(),
+      url: petstore().to_data_url(),
+      .. SourceDescription::default()
+    };
+    expect!(OpenApiSource::load(&source, None).unwrap().document).to(be_equal_to(petstore().document));
+
+    let source = SourceDescription { url: "data:application/json,{\"paths\":{}}".to_string(), .. source };
+    expect!(OpenApiSource::load(&source, None).unwrap().document).to(be_equal_to(json!({ "paths": {} })));
+  }
+}
@@ -0,0 +1,295 @@
+//! Support for applying [OpenAPI Overlay](https://spec.openapis.org/overlay/v1.0.0.html) documents
+//! to an Arazzo description, so variants of a description (i.e. for an environment or a consumer)
+//! can be produced from a base description and an overlay.
+//!
+//! The actions of the overlay are applied in order, with the JSONPath target of each action
+//! evaluated against the description as changed by the previous actions. An update is merged into
+//! each target object (nested objects are merged, any other value is replaced) or appended to
+//! each target array, and a remove removes each target from its parent. Targets that select
+//! nothing are ignored. The description is re-loaded after all the actions have been applied.
+
+use anyhow::anyhow;
+use serde_json::{Map, Value};
+
+use crate::json::{json_object_lookup_string, json_object_require_string, json_type_name};
+use crate::jsonpath::query_pointers;
+use crate::patch::json_pointer_tokens;
+use crate::v1_0::ArazzoDescription;
+
+/// Overlay document
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Overlay {
+  /// Version of the Overlay specification (`overlay`)
+  pub overlay: String,
+  /// Title of the overlay
+  pub title: String,
+  /// Version of the overlay
+  pub version: String,
+  /// URL of the document the overlay is meant to be applied to (`extends`)
+  pub extends: Option<String>,
+  /// Actions to apply, in order
+  pub actions: Vec<OverlayAction>
+}
+
+/// Action of an overlay
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OverlayAction {
+  /// JSONPath query selecting the targets of the action
+  pub target: String,
+  /// Description of the action
+  pub description: Option<String>,
+  /// Value to merge into (or append to) each target
+  pub update: Option<Value>,
+  /// If each target should be removed
+  pub remove: bool
+}
+
+impl TryFrom<&Value> for Overlay {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &Value) -> Result<Self, Self::Error> {
+    let map = value.as_object()
+      .ok_or_else(|| anyhow!("Overlay document must be an Object, got {}", json_type_name(value)))?;
+    let info = map.get("info")
+      .and_then(|info| info.as_object())
+      .ok_or_else(|| anyhow!("Overlay document requires an 'info' Object"))?;
+    let actions = map.get("actions")
+      .and_then(|actions| actions.as_array())
+      .ok_or_else(|| anyhow!("Overlay document requires an 'actions' Array"))?
+      .iter()
+      .enumerate()
+      .map(|(index, action)| OverlayAction::try_from(action)
+        .map_err(|err| anyhow!("Overlay action {} is not valid: {}", index, err)))
+      .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Overlay {
+      overlay: json_object_require_string(map, "overlay")?,
+      title: json_object_require_string(info, "title")?,
+      version: json_object_require_string(info, "version")?,
+      extends: json_object_lookup_string(map, "extends"),
+      actions
+    })
+  }
+}
+
+impl TryFrom<&Value> for OverlayAction {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &Value) -> Result<Self, Self::Error> {
+    let map = value.as_object()
+      .ok_or_else(|| anyhow!("Overlay action must be an Object, got {}", json_type_name(value)))?;
+    let remove = match map.get("remove") {
+      Some(Value::Bool(remove)) => *remove,
+      Some(value) => return Err(anyhow!("'remove' must be a Boolean, got {}", json_type_name(value))),
+      None => false
+    };
+    let update = map.get("update").cloned();
+    if update.is_none() && !remove {
+      return Err(anyhow!("Overlay action requires an 'update' value or 'remove'"));
+    }
+    Ok(OverlayAction {
+      target: json_object_require_string(map, "target")?,
+      description: json_object_lookup_string(map, "description"),
+      update,
+      remove
+    })
+  }
+}
+
+impl ArazzoDescription {
+  /// Applies the actions of the overlay to this description. Either all the actions are applied,
+  /// or if any of them fail (including re-loading the description), the description is left
+  /// unchanged and an error is returned.
+  pub fn apply_overlay(&mut self, overlay: &Overlay) -> anyhow::Result<()> {
+    let mut json = serde_json::to_value(&*self)?;
+    for (index, action) in overlay.actions.iter().enumerate() {
+      json_apply_overlay_action(&mut json, action)
+        .map_err(|err| anyhow!("Overlay action {} ({}) failed: {}", index, action.target, err))?;
+    }
+    *self = ArazzoDescription::try_from(&json)?;
+    Ok(())
+  }
+}
+
+/// Applies a single overlay action to a JSON document
+pub fn json_apply_overlay_action(json: &mut Value, action: &OverlayAction) -> anyhow::Result<()> {
+  let targets = query_pointers(json, &action.target)?;
+  if action.remove {
+    // Remove the later array entries first, so the earlier locations are still valid
+    for pointer in targets.iter().rev() {
+      overlay_remove(json, pointer)?;
+    }
+  } else if let Some(update) = &action.update {
+    for pointer in &targets {
+      if let Some(target) = json.pointer_mut(pointer) {
+        overlay_update(target, update);
+      }
+    }
+  }
+  Ok(())
+}
+
+fn overlay_remove(json: &mut Value, pointer: &str) -> anyhow::Result<()> {
+  let mut tokens = json_pointer_tokens(pointer)?;
+  let Some(last) = tokens.pop() else {
+    return Err(anyhow!("The root of the document can not be removed"));
+  };
+  let parent_pointer = tokens.iter()
+    .map(|token| format!("/{}", token.replace('~', "~0").replace('/', "~1")))
+    .collect::<String>();
+  match json.pointer_mut(&parent_pointer) {
+    Some(Value::Object(map)) => {
+      map.remove(&last);
+    }
+    Some(Value::Array(array)) => if let Ok(index) = last.parse::<usize>() && index < array.len() {
+      array.remove(index);
+    },
+    _ => {}
+  }
+  Ok(())
+}
+
+fn overlay_update(target: &mut Value, update: &Value) {
+  match (target, update) {
+    (Value::Object(target), Value::Object(update)) => overlay_merge(target, update),
+    (Value::Array(target), Value::Array(update)) => target.extend(update.iter().cloned()),
+    (Value::Array(target), update) => target.push(update.clone()),
+    (target, update) => *target = update.clone()
+  }
+}
+
+fn overlay_merge(target: &mut Map<String, Value>, update: &Map<String, Value>) {
+  for (key, value) in update {
+    match (target.get_mut(key), value) {
+      (Some(Value::Object(existing)), Value::Object(value)) => overlay_merge(existing, value),
+      _ => {
+        target.insert(key.clone(), value.clone());
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use crate::arazzo;
+  use crate::overlay::{json_apply_overlay_action, Overlay, OverlayAction};
+  use crate::v1_0::{ArazzoDescription, SourceDescription};
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      info("test", "1.0.0");
+      source_description(SourceDescription {
+        name: "petStore".to_string(),
+        url: "http://petstore".to_string(),
+        .. SourceDescription::default()
+      });
+      workflow("one") {
+        step("a") { operation_id: "getPets"; }
+        step("b") {
+          operation_id: "getPet";
+          extension("x-debug", true);
+        }
+      }
+      workflow("two") {
+        step("c") {
+          operation_id: "getPets";
+          extension("x-debug", true);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn parses_overlay_documents() {
+    let overlay = Overlay::try_from(&json!({
+      "overlay": "1.0.0",
+      "info": { "title": "Production", "version": "1.0.0" },
+      "extends": "arazzo.yaml",
+      "actions": [
+        { "target": "$.info", "update": { "title": "Production" } },
+        { "target": "$..x-debug", "description": "No debugging", "remove": true }
+      ]
+    })).unwrap();
+    expect!(overlay.extends.as_deref()).to(be_some().value("arazzo.yaml"));
+    expect!(overlay.actions).to(be_equal_to(vec![
+      OverlayAction { target: "$.info".to_string(), update: Some(json!({ "title": "Production" })), .. OverlayAction::default() },
+      OverlayAction { target: "$..x-debug".to_string(), description: Some("No debugging".to_string()), remove: true, .. OverlayAction::default() }
+    ]));
+
+    expect!(Overlay::try_from(&json!({ "overlay": "1.0.0", "info": { "title": "a", "version": "1" } }))).to(be_err());
+    expect!(Overlay::try_from(&json!({
+      "overlay": "1.0.0",
+      "info": { "title": "a", "version": "1" },
+      "actions": [{ "target": "$.info" }]
+    }))).to(be_err());
+  }
+
+  #[test]
+  fn applies_the_actions_to_the_description() {
+    let overlay = Overlay::try_from(&json!({
+      "overlay": "1.0.0",
+      "info": { "title": "Production", "version": "1.0.0" },
+      "actions": [
+        { "target": "$.info", "update": { "title": "test (production)", "x-environment": { "name": "prod" } } },
+        { "target": "$.sourceDescriptions[?@.name == 'petStore']", "update": { "url": "https://petstore.example.com" } },
+        { "target": "$.workflows[?@.workflowId == 'one'].steps", "update": { "stepId": "c", "operationId": "deletePet" } },
+        { "target": "$.workflows[*].steps[*].x-debug", "remove": true },
+        { "target": "$.workflows[?@.workflowId == 'two']", "remove": true }
+      ]
+    })).unwrap();
+    let mut description = description();
+    description.apply_overlay(&overlay).unwrap();
+
+    assert_eq!(serde_json::to_value(&description).unwrap(), json!({
+      "arazzo": "1.0.1",
+      "info": { "title": "test (production)", "version": "1.0.0", "x-environment": { "name": "prod" } },
+      "sourceDescriptions": [
+        { "name": "petStore", "url": "https://petstore.example.com" }
+      ],
+      "workflows": [
+        {
+          "workflowId": "one",
+          "steps": [
+            { "stepId": "a", "operationId": "getPets" },
+            { "stepId": "b", "operationId": "getPet" },
+            { "stepId": "c", "operationId": "deletePet" }
+          ]
+        }
+      ]
+    }));
+  }
+
+  #[test]
+  fn leaves_the_description_unchanged_if_an_action_fails() {
+    let overlay = Overlay {
+      actions: vec![
+        OverlayAction { target: "$.info".to_string(), update: Some(json!({ "title": "changed" })), .. OverlayAction::default() },
+        OverlayAction { target: "$.workflows[".to_string(), remove: true, .. OverlayAction::default() }
+      ],
+      .. Overlay::default()
+    };
+    let mut description = description();
+    expect!(description.apply_overlay(&overlay)).to(be_err());
+    expect!(&description).to(be_equal_to(&self::description()));
+
+    let overlay = Overlay {
+      actions: vec![OverlayAction { target: "$.info".to_string(), update: Some(json!({ "version": 1 })), .. OverlayAction::default() }],
+      .. Overlay::default()
+    };
+    expect!(description.apply_overlay(&overlay)).to(be_err());
+    expect!(&description).to(be_equal_to(&self::description()));
+  }
+
+  #[test]
+  fn appends_updates_to_arrays_and_ignores_missing_targets() {
+    let mut json = json!({ "tags": ["a"], "count": 1 });
+    json_apply_overlay_action(&mut json, &OverlayAction { target: "$.tags".to_string(), update: Some(json!(["b", "c"])), .. OverlayAction::default() }).unwrap();
+    json_apply_overlay_action(&mut json, &OverlayAction { target: "$.count".to_string(), update: Some(json!(2)), .. OverlayAction::default() }).unwrap();
+    json_apply_overlay_action(&mut json, &OverlayAction { target: "$.missing".to_string(), remove: true, .. OverlayAction::default() }).unwrap();
+    expect!(&json).to(be_equal_to(&json!({ "tags": ["a", "b", "c"], "count": 2 })));
+    expect!(json_apply_overlay_action(&mut json, &OverlayAction { target: "$".to_string(), remove: true, .. OverlayAction::default() })).to(be_err());
+  }
+}
@@ -0,0 +1,1001 @@
+//! Derives [Pact](https://docs.pact.io) consumer contracts (Pact specification V3) from the
+//! workflows, so the API calls a workflow makes can be verified against the providers. Each step
+//! that calls an operation is an interaction in the pact of the source description the operation
+//! is from, with the description (its title) as the consumer.
+//!
+//! The request of an interaction is taken from the resolved operation and the parameters and
+//! request body of the step. Runtime expressions are replaced with sample values (the defaults
+//! from the inputs schema, or a placeholder like `<$steps.find.outputs.id>`), and matching rules
+//! are added for the values that came from expressions, as they change between runs.
+//!
+//! The response of an interaction is derived from the success criteria and outputs of the step:
+//! the status code from the first `$statusCode ==` condition (or 200), body values and headers
+//! compared with `==` as exact values, and the body values and headers that other criteria and
+//! the outputs use as examples with matching rules. Examples for body values are taken from the
+//! response schema of the operation, if there is one. Criteria that do not map to the response
+//! (i.e. JSONPath or XPath criteria) are not included.
+//!
+//! For provider verification pipelines, [`verification_scenario`] converts a workflow to an
+//! ordered scenario, where the provider states of each interaction are the steps (or the
+//! workflows it depends on) that come before it, and the outputs of those steps are injected into
+//! the request from the provider states.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use serde_json::{json, Map, Value};
+
+use crate::docs::{input_properties, step_parameters};
+use crate::either::Either;
+use crate::expressions::ExpressionResolver;
+use crate::extensions::AnyValue;
+use crate::hurl::json_path;
+use crate::operations::{Operation, OperationResolver};
+use crate::preview::{diff_json, PayloadChangeKind};
+use crate::templates::{find_templates, render_template, TemplateOptions};
+use crate::v1_0::{ArazzoDescription, Criterion, Step, Workflow};
+
+/// Version of the Pact specification of the generated pacts
+pub const PACT_SPECIFICATION_VERSION: &str = "3.0.0";
+
+/// Pact between the consumer (the description) and a provider (a source description)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pact {
+  /// Name of the consumer
+  pub consumer: String,
+  /// Name of the provider
+  pub provider: String,
+  /// Interactions, in the Pact JSON format
+  pub interactions: Vec<Value>
+}
+
+impl Pact {
+  /// Returns the pact in the Pact JSON format
+  pub fn to_json(&self) -> Value {
+    json!({
+      "consumer": { "name": self.consumer },
+      "provider": { "name": self.provider },
+      "interactions": self.interactions,
+      "metadata": { "pactSpecification": { "version": PACT_SPECIFICATION_VERSION } }
+    })
+  }
+
+  /// File name of the pact (`<consumer>-<provider>.json`)
+  pub fn file_name(&self) -> String {
+    format!("{}-{}.json", file_name_part(&self.consumer), file_name_part(&self.provider))
+  }
+
+  /// Writes the pact to the directory, returning the path of the file
+  pub fn save<P: AsRef<Path>>(&self, dir: P) -> anyhow::Result<PathBuf> {
+    let path = dir.as_ref().join(self.file_name());
+    let contents = serde_json::to_string_pretty(&self.to_json())?;
+    fs::write(&path, contents)
+      .with_context(|| format!("Failed to write pact to '{}'", path.display()))?;
+    Ok(path)
+  }
+}
+
+/// Derives the consumer pacts for all the workflows of the description, with a pact for each
+/// provider (source description), sorted by provider name. The operations of the steps are
+/// resolved with the resolver, and it is an error if one can not be resolved.
+pub fn consumer_pacts(description: &ArazzoDescription, resolver: &OperationResolver) -> anyhow::Result<Vec<Pact>> {
+  let mut providers: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+  for workflow in &description.workflows {
+    let samples = SampleResolver::new(description, workflow);
+    for step in workflow.steps.iter().filter(|step| step.workflow_id.is_none()) {
+      let operation = resolver.resolve_step(step)
+        .with_context(|| format!("Could not resolve the operation of step '{}'", step.step_id))?;
+      let interaction = interaction(description, workflow, step, &operation, resolver, &samples, false)
+        .with_context(|| format!("Could not create the interaction for step '{}'", step.step_id))?;
+      providers.entry(operation.source_name.clone()).or_default().push(interaction);
+    }
+  }
+  Ok(providers.into_iter()
+    .map(|(provider, interactions)| Pact {
+      consumer: description.info.title.clone(),
+      provider,
+      interactions
+    })
+    .collect())
+}
+
+/// Derives the consumer pacts for all the workflows of the description, and writes them to the
+/// directory. Returns the paths of the files.
+pub fn write_consumer_pacts<P: AsRef<Path>>(
+  description: &ArazzoDescription,
+  resolver: &OperationResolver,
+  dir: P
+) -> anyhow::Result<Vec<PathBuf>> {
+  consumer_pacts(description, resolver)?.iter()
+    .map(|pact| pact.save(dir.as_ref()))
+    .collect()
+}
+
+/// Ordered provider verification scenario for a workflow
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerificationScenario {
+  /// Name of the consumer
+  pub consumer: String,
+  /// ID of the workflow
+  pub workflow_id: String,
+  /// Interactions, in the order the steps are executed
+  pub interactions: Vec<ScenarioInteraction>
+}
+
+/// Interaction of a verification scenario
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenarioInteraction {
+  /// ID of the step
+  pub step_id: String,
+  /// Name of the provider (source description) of the operation of the step
+  pub provider: String,
+  /// Interaction in the Pact JSON format, with the provider states and generators
+  pub interaction: Value
+}
+
+impl VerificationScenario {
+  /// Returns the interactions of the scenario as a pact for each provider, sorted by provider
+  /// name. The interactions of each pact are in the order of the scenario.
+  pub fn pacts(&self) -> Vec<Pact> {
+    let mut providers: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+    for interaction in &self.interactions {
+      providers.entry(interaction.provider.clone()).or_default().push(interaction.interaction.clone());
+    }
+    providers.into_iter()
+      .map(|(provider, interactions)| Pact { consumer: self.consumer.clone(), provider, interactions })
+      .collect()
+  }
+}
+
+/// Converts the workflow to an ordered provider verification scenario. Each step that calls an
+/// operation is an interaction (steps that execute another workflow of the description have the
+/// interactions of that workflow inlined), with provider states for the steps before it:
+///
+/// * the first interaction has a `workflow '<ID>' has completed` state for each workflow the
+///   workflow depends on (`dependsOn`),
+/// * the other interactions have a `step '<ID>' of workflow '<ID>' has completed` state for the
+///   previous step,
+/// * and there is also a state for any other step whose outputs the interaction uses.
+///
+/// Step (and workflow) outputs used in the request are injected from the provider states: the
+/// request has `ProviderState` generators with an expression for each output (i.e.
+/// `$steps.find.outputs.id` is `${find_id}`), and the parameters of the state of the step that
+/// produces the output are the keys the state change handler needs to return, with the runtime
+/// expressions of the outputs as values.
+pub fn verification_scenario(
+  description: &ArazzoDescription,
+  workflow_id: &str,
+  resolver: &OperationResolver
+) -> anyhow::Result<VerificationScenario> {
+  let workflow = description.workflows.iter()
+    .find(|workflow| workflow.workflow_id == workflow_id)
+    .ok_or_else(|| anyhow!("No workflow with ID '{}' was found", workflow_id))?;
+
+  let mut scenario = Scenario::default();
+  let mut stack = vec![workflow.workflow_id.clone()];
+  scenario_steps(description, workflow, resolver, &mut scenario, &mut stack)?;
+  Ok(VerificationScenario {
+    consumer: description.info.title.clone(),
+    workflow_id: workflow.workflow_id.clone(),
+    interactions: scenario.interactions
+  })
+}
+
+/// State of a scenario as it is built
+#[derive(Debug, Default)]
+struct Scenario {
+  interactions: Vec<ScenarioInteraction>,
+  /// Previous step, with the ID of its workflow
+  previous: Option<(String, String)>,
+  /// Outputs of the steps so far, keyed by step ID, with the workflow ID
+  outputs: HashMap<String, (String, BTreeMap<String, String>)>
+}
+
+fn scenario_steps(
+  description: &ArazzoDescription,
+  workflow: &Workflow,
+  resolver: &OperationResolver,
+  scenario: &mut Scenario,
+  stack: &mut Vec<String>
+) -> anyhow::Result<()> {
+  let samples = SampleResolver::new(description, workflow);
+  for step in &workflow.steps {
+    if let Some(workflow_id) = &step.workflow_id {
+      let target = description.workflows.iter().find(|workflow| workflow.workflow_id == *workflow_id);
+      if let Some(target) = target && !stack.contains(&target.workflow_id) {
+        stack.push(target.workflow_id.clone());
+        scenario_steps(description, target, resolver, scenario, stack)?;
+        stack.pop();
+      }
+      continue;
+    }
+
+    let operation = resolver.resolve_step(step)
+      .with_context(|| format!("Could not resolve the operation of step '{}'", step.step_id))?;
+    let mut interaction = interaction(description, workflow, step, &operation, resolver, &samples, true)
+      .with_context(|| format!("Could not create the interaction for step '{}'", step.step_id))?;
+
+    // Provider states, keyed by name, with the parameters (the keys of the injected values)
+    let mut states: Vec<(String, Map<String, Value>)> = vec![];
+    match &scenario.previous {
+      Some((previous_workflow, previous_step)) => states.push((step_state(previous_workflow, previous_step), Map::new())),
+      None => for depends_on in &workflow.depends_on {
+        states.push((format!("workflow '{}' has completed", depends_on), Map::new()));
+      }
+    }
+    for reference in step_output_references(description, workflow, step)? {
+      let (name, source) = match &reference {
+        OutputReference::Step(step_id, output) => match scenario.outputs.get(step_id) {
+          Some((workflow_id, outputs)) => (step_state(workflow_id, step_id), outputs.get(output).cloned()),
+          None => continue
+        },
+        OutputReference::Workflow(workflow_id, output) => {
+          let source = description.workflows.iter()
+            .find(|workflow| workflow.workflow_id == *workflow_id)
+            .and_then(|workflow| workflow.outputs.get(output).cloned());
+          (format!("workflow '{}' has completed", workflow_id), source)
+        }
+      };
+      let index = match states.iter().position(|(state, _)| *state == name) {
+        Some(index) => index,
+        None => {
+          states.push((name, Map::new()));
+          states.len() - 1
+        }
+      };
+      states[index].1.insert(reference.key(), source.map(Value::String).unwrap_or(Value::Null));
+    }
+    if !states.is_empty() {
+      interaction["providerStates"] = Value::Array(states.into_iter()
+        .map(|(name, params)| if params.is_empty() {
+          json!({ "name": name })
+        } else {
+          json!({ "name": name, "params": params })
+        })
+        .collect());
+    }
+
+    scenario.outputs.insert(step.step_id.clone(), (workflow.workflow_id.clone(), step.outputs.clone()));
+    scenario.previous = Some((workflow.workflow_id.clone(), step.step_id.clone()));
+    scenario.interactions.push(ScenarioInteraction {
+      step_id: step.step_id.clone(),
+      provider: operation.source_name.clone(),
+      interaction
+    });
+  }
+  Ok(())
+}
+
+fn step_state(workflow_id: &str, step_id: &str) -> String {
+  format!("step '{}' of workflow '{}' has completed", step_id, workflow_id)
+}
+
+/// Resolves the inputs of the workflow to their defaults, and any other expression to a
+/// placeholder
+struct SampleResolver {
+  defaults: HashMap<String, String>
+}
+
+impl SampleResolver {
+  fn new(description: &ArazzoDescription, workflow: &Workflow) -> Self {
+    let defaults = input_properties(description, workflow).into_iter()
+      .filter_map(|property| property.default.map(|default| (format!("$inputs.{}", property.path), default)))
+      .collect();
+    SampleResolver { defaults }
+  }
+
+  /// Sample of a parameter value, which is a runtime expression or text with embedded expressions
+  fn text(&self, value: &str) -> anyhow::Result<String> {
+    if value.trim().starts_with('$') {
+      Ok(self.resolve(value)?.to_text().unwrap_or_default())
+    } else {
+      render_template(value, self, &TemplateOptions::default())
+    }
+  }
+}
+
+impl ExpressionResolver for SampleResolver {
+  fn resolve(&self, expression: &str) -> anyhow::Result<AnyValue> {
+    let expression = expression.trim();
+    Ok(AnyValue::String(self.defaults.get(expression).cloned().unwrap_or_else(|| format!("<{}>", expression))))
+  }
+}
+
+fn is_dynamic(value: &str) -> bool {
+  value.trim().starts_with('$') || value.contains("{$")
+}
+
+/// Reference to an output of a step or workflow
+#[derive(Debug, Clone, PartialEq)]
+enum OutputReference {
+  Step(String, String),
+  Workflow(String, String)
+}
+
+impl OutputReference {
+  /// Parses an output expression (i.e. `$steps.find.outputs.id`)
+  fn parse(expression: &str) -> Option<Self> {
+    let expression = expression.trim();
+    if let Some((step, output)) = expression.strip_prefix("$steps.").and_then(|rest| rest.split_once(".outputs.")) {
+      Some(OutputReference::Step(step.to_string(), output.to_string()))
+    } else {
+      expression.strip_prefix("$workflows.")
+        .and_then(|rest| rest.split_once(".outputs."))
+        .map(|(workflow, output)| OutputReference::Workflow(workflow.to_string(), output.to_string()))
+    }
+  }
+
+  /// Key of the value injected from the provider state (i.e. `find_id`)
+  fn key(&self) -> String {
+    let (name, output) = match self {
+      OutputReference::Step(name, output) | OutputReference::Workflow(name, output) => (name, output)
+    };
+    format!("{}_{}", name, output).chars()
+      .map(|ch| if ch.is_ascii_alphanumeric() || ch == '_' { ch } else { '_' })
+      .collect()
+  }
+}
+
+/// References to step and workflow outputs in a runtime expression or text with embedded
+/// expressions
+fn output_references(value: &str) -> Vec<OutputReference> {
+  if value.trim().starts_with('$') {
+    OutputReference::parse(value).into_iter().collect()
+  } else {
+    find_templates(value).iter()
+      .filter_map(|template| OutputReference::parse(template.expression))
+      .collect()
+  }
+}
+
+/// References to step and workflow outputs in the parameters and request body of the step
+fn step_output_references(
+  description: &ArazzoDescription,
+  workflow: &Workflow,
+  step: &Step
+) -> anyhow::Result<Vec<OutputReference>> {
+  let mut values = step_parameters(description, workflow, step)?.into_iter()
+    .map(|(_, value)| value)
+    .collect::<Vec<_>>();
+  if let Some(body) = &step.request_body {
+    if let Some(payload) = &body.payload {
+      values.push(payload.as_string());
+    }
+    for replacement in &body.replacements {
+      if let Either::Second(expression) = &replacement.value {
+        values.push(expression.clone());
+      }
+    }
+  }
+  let mut references = vec![];
+  for reference in values.iter().flat_map(|value| output_references(value)) {
+    if !references.contains(&reference) {
+      references.push(reference);
+    }
+  }
+  Ok(references)
+}
+
+/// Provider state expression for the value, with the step and workflow outputs as the injected
+/// values (i.e. `/pets/${find_id}`) and any other expression as a sample value
+fn state_expression(value: &str, samples: &SampleResolver) -> anyhow::Result<String> {
+  let resolver = |expression: &str| match OutputReference::parse(expression) {
+    Some(reference) => Ok(AnyValue::String(format!("${{{}}}", reference.key()))),
+    None => samples.resolve(expression)
+  };
+  if value.trim().starts_with('$') {
+    Ok(resolver(value)?.to_text().unwrap_or_default())
+  } else {
+    render_template(value, &resolver, &TemplateOptions::default())
+  }
+}
+
+fn state_generator(value: &str, samples: &SampleResolver) -> anyhow::Result<Value> {
+  Ok(json!({ "type": "ProviderState", "expression": state_expression(value, samples)? }))
+}
+
+fn interaction(
+  description: &ArazzoDescription,
+  workflow: &Workflow,
+  step: &Step,
+  operation: &Operation,
+  resolver: &OperationResolver,
+  samples: &SampleResolver,
+  inject: bool
+) -> anyhow::Result<Value> {
+  let (mut request, generators) = request(description, workflow, step, operation, samples)?;
+  if inject && !generators.is_empty() {
+    request["generators"] = Value::Object(generators);
+  }
+  let schema = response_schema(resolver, operation, &step.success_criteria);
+  Ok(json!({
+    "description": format!("{} - {}", workflow.workflow_id, step.description.as_deref().unwrap_or(&step.step_id)),
+    "request": request,
+    "response": response(step, schema.as_ref())
+  }))
+}
+
+/// Request of the interaction, with the generators for the values that are injected from the
+/// provider states (see [`verification_scenario`])
+fn request(
+  description: &ArazzoDescription,
+  workflow: &Workflow,
+  step: &Step,
+  operation: &Operation,
+  samples: &SampleResolver
+) -> anyhow::Result<(Value, Map<String, Value>)> {
+  let mut path = operation.path.clone();
+  let mut path_regex = regex_escape(&operation.path);
+  let mut path_expression = operation.path.clone();
+  let mut dynamic_path = false;
+  let mut injected_path = false;
+  let mut query = Map::new();
+  let mut headers = Map::new();
+  let mut cookies = vec![];
+  let mut rules = Map::new();
+  let mut generators = Map::new();
+  for (parameter, value) in step_parameters(description, workflow, step)? {
+    let sample = samples.text(&value)?;
+    let dynamic = is_dynamic(&value);
+    let injected = !output_references(&value).is_empty();
+    match parameter.r#in.as_deref() {
+      Some("path") => {
+        let placeholder = format!("{{{}}}", parameter.name);
+        path = path.replace(&placeholder, &sample);
+        path_regex = path_regex.replace(&regex_escape(&placeholder),
+          &if dynamic { "[^/]+".to_string() } else { regex_escape(&sample) });
+        path_expression = path_expression.replace(&placeholder, &state_expression(&value, samples)?);
+        dynamic_path |= dynamic;
+        injected_path |= injected;
+      }
+      Some("query") => {
+        query.insert(parameter.name.clone(), json!([sample]));
+        if dynamic {
+          rules.entry("query").or_insert_with(|| json!({}))[&parameter.name] = regex_rule(".+");
+        }
+        if injected {
+          generators.entry("query").or_insert_with(|| json!({}))[&parameter.name] = state_generator(&value, samples)?;
+        }
+      }
+      Some("header") => {
+        headers.insert(parameter.name.clone(), json!(sample));
+        if dynamic {
+          rules.entry("header").or_insert_with(|| json!({}))[&parameter.name] = regex_rule(".+");
+        }
+        if injected {
+          generators.entry("header").or_insert_with(|| json!({}))[&parameter.name] = state_generator(&value, samples)?;
+        }
+      }
+      Some("cookie") => cookies.push(format!("{}={}", parameter.name, sample)),
+      _ => {}
+    }
+  }
+  if !cookies.is_empty() {
+    headers.insert("Cookie".to_string(), json!(cookies.join("; ")));
+  }
+  if dynamic_path {
+    rules.insert("path".to_string(), json!({ "matchers": [{ "match": "regex", "regex": path_regex }] }));
+  }
+  if injected_path {
+    generators.insert("path".to_string(), json!({ "type": "ProviderState", "expression": path_expression, "dataType": "STRING" }));
+  }
+
+  let mut request = json!({ "method": operation.method.to_uppercase(), "path": path });
+  if let Some(body) = &step.request_body {
+    if let Some(content_type) = body.effective_content_type() {
+      headers.insert("Content-Type".to_string(), json!(content_type));
+    }
+    if let Some(rendered) = body.render_payload(samples)? {
+      match (body.payload.as_ref().and_then(|payload| payload.as_json()), rendered.as_json()) {
+        (Some(original), Some(rendered)) => {
+          let changes = diff_json(&original, &rendered).into_iter()
+            .filter(|change| change.kind != PayloadChangeKind::Removed)
+            .collect::<Vec<_>>();
+          let matchers = changes.iter()
+            .map(|change| (json_path(&change.path), json!({ "matchers": [{ "match": "type" }] })))
+            .collect::<Map<_, _>>();
+          if !matchers.is_empty() {
+            rules.insert("body".to_string(), Value::Object(matchers));
+          }
+          let mut body_generators = Map::new();
+          for change in &changes {
+            let replacement = body.replacements.iter()
+              .find(|replacement| replacement.target == change.path)
+              .and_then(|replacement| match &replacement.value {
+                Either::Second(expression) => Some(expression.clone()),
+                Either::First(_) => None
+              });
+            let value = replacement.or_else(|| change.original.as_ref().and_then(|value| value.as_str()).map(|value| value.to_string()));
+            if let Some(value) = value && !output_references(&value).is_empty() {
+              body_generators.insert(json_path(&change.path), state_generator(&value, samples)?);
+            }
+          }
+          if !body_generators.is_empty() {
+            generators.insert("body".to_string(), Value::Object(body_generators));
+          }
+          request["body"] = rendered;
+        }
+        (_, Some(rendered)) => request["body"] = rendered,
+        (_, None) => request["body"] = json!(rendered.as_string())
+      }
+    }
+  }
+  if !query.is_empty() {
+    request["query"] = Value::Object(query);
+  }
+  if !headers.is_empty() {
+    request["headers"] = Value::Object(headers);
+  }
+  if !rules.is_empty() {
+    request["matchingRules"] = Value::Object(rules);
+  }
+  Ok((request, generators))
+}
+
+/// Response of the interaction, from the success criteria and outputs of the step
+fn response(step: &Step, schema: Option<&(Value, Value)>) -> Value {
+  let mut status = None;
+  let mut body = None;
+  let mut headers = Map::new();
+  let mut body_rules = Map::new();
+  let mut header_rules = Map::new();
+  let mut exact = vec![];
+
+  for criterion in &step.success_criteria {
+    match criterion_type(criterion) {
+      "simple" if criterion.context.is_none() && !criterion.condition.contains("||") => {
+        for part in criterion.condition.split("&&").map(|part| part.trim()) {
+          let (expression, operator, literal) = split_condition(part);
+          if expression == "$statusCode" {
+            if operator == Some("==") && status.is_none() {
+              status = literal.and_then(|literal| literal.parse::<u16>().ok());
+            }
+          } else if let Some(pointer) = expression.strip_prefix("$response.body#") {
+            match (operator, literal) {
+              (Some("=="), Some(literal)) => {
+                set_pointer(&mut body, pointer, literal_value(literal));
+                exact.push(pointer.to_string());
+              }
+              _ => {
+                set_pointer(&mut body, pointer, schema_example(schema, pointer, expression));
+                body_rules.insert(json_path(pointer), json!({ "matchers": [{ "match": "type" }] }));
+              }
+            }
+          } else if let Some(name) = expression.strip_prefix("$response.header.") {
+            match (operator, literal) {
+              (Some("=="), Some(literal)) => {
+                let value = match literal_value(literal) {
+                  Value::String(value) => value,
+                  value => value.to_string()
+                };
+                headers.insert(name.to_string(), json!(value));
+              }
+              _ => {
+                headers.entry(name.to_string()).or_insert_with(|| json!(format!("<{}>", expression)));
+                header_rules.insert(name.to_string(), regex_rule(".+"));
+              }
+            }
+          }
+        }
+      }
+      "regex" => if let Some(name) = criterion.context.as_deref().and_then(|context| context.strip_prefix("$response.header.")) {
+        headers.entry(name.to_string()).or_insert_with(|| json!(format!("<{}>", criterion.context.as_deref().unwrap_or_default())));
+        header_rules.insert(name.to_string(), regex_rule(&criterion.condition));
+      }
+      _ => {}
+    }
+  }
+
+  for expression in step.outputs.values() {
+    if let Some(pointer) = expression.strip_prefix("$response.body#") && !pointer.is_empty() && !exact.iter().any(|path| path == pointer) {
+      if !pointer_exists(&body, pointer) {
+        set_pointer(&mut body, pointer, schema_example(schema, pointer, expression));
+      }
+      body_rules.insert(json_path(pointer), json!({ "matchers": [{ "match": "type" }] }));
+    } else if let Some(name) = expression.strip_prefix("$response.header.") {
+      headers.entry(name.to_string()).or_insert_with(|| json!(format!("<{}>", expression)));
+      header_rules.entry(name.to_string()).or_insert_with(|| regex_rule(".+"));
+    }
+  }
+
+  let mut response = json!({ "status": status.unwrap_or(200) });
+  if !headers.is_empty() {
+    response["headers"] = Value::Object(headers);
+  }
+  if let Some(body) = body {
+    response["body"] = body;
+  }
+  let mut rules = Map::new();
+  if !body_rules.is_empty() {
+    rules.insert("body".to_string(), Value::Object(body_rules));
+  }
+  if !header_rules.is_empty() {
+    rules.insert("header".to_string(), Value::Object(header_rules));
+  }
+  if !rules.is_empty() {
+    response["matchingRules"] = Value::Object(rules);
+  }
+  response
+}
+
+fn criterion_type(criterion: &Criterion) -> &str {
+  match &criterion.r#type {
+    Some(Either::First(criterion_type)) => criterion_type.as_str(),
+    Some(Either::Second(expression_type)) => expression_type.r#type.as_str(),
+    None => "simple"
+  }
+}
+
+const OPERATORS: [&str; 6] = [">=", "<=", "==", "!=", ">", "<"];
+
+/// Splits a comparison into the expression, operator and literal
+fn split_condition(condition: &str) -> (&str, Option<&str>, Option<&str>) {
+  let operator = condition.char_indices()
+    .find_map(|(index, _)| OPERATORS.iter().find(|operator| condition[index..].starts_with(**operator)).map(|operator| (index, *operator)));
+  match operator {
+    Some((index, operator)) => (condition[..index].trim(), Some(operator), Some(condition[index + operator.len()..].trim())),
+    None => (condition.trim(), None, None)
+  }
+}
+
+/// Converts a literal of a simple condition (with single quoted strings) to a JSON value
+fn literal_value(literal: &str) -> Value {
+  match literal.strip_prefix('\'').and_then(|literal| literal.strip_suffix('\'')) {
+    Some(text) => Value::String(text.to_string()),
+    None => serde_json::from_str(literal).unwrap_or_else(|_| Value::String(literal.to_string()))
+  }
+}
+
+fn regex_rule(regex: &str) -> Value {
+  json!({ "matchers": [{ "match": "regex", "regex": regex }] })
+}
+
+fn regex_escape(text: &str) -> String {
+  let mut result = String::new();
+  for ch in text.chars() {
+    if "\\.+*?()|[]{}^$".contains(ch) {
+      result.push('\\');
+    }
+    result.push(ch);
+  }
+  result
+}
+
+/// Sets the value at the JSON Pointer, creating any objects and arrays that are missing
+fn set_pointer(root: &mut Option<Value>, pointer: &str, value: Value) {
+  let segments = pointer.split('/').skip(1)
+    .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+    .collect::<Vec<_>>();
+  if segments.is_empty() {
+    return;
+  }
+  let mut current = root.get_or_insert_with(|| container_for(&segments[0]));
+  for (index, segment) in segments.iter().enumerate() {
+    let next = segments.get(index + 1);
+    let child = match (current, segment.parse::<usize>()) {
+      (Value::Array(items), Ok(position)) => {
+        if items.len() <= position {
+          items.resize(position + 1, Value::Null);
+        }
+        &mut items[position]
+      }
+      (Value::Object(fields), _) => fields.entry(segment.clone()).or_insert(Value::Null),
+      _ => return
+    };
+    match next {
+      Some(next) => {
+        if child.is_null() {
+          *child = container_for(next);
+        }
+        current = child;
+      }
+      None => {
+        *child = value;
+        return;
+      }
+    }
+  }
+}
+
+fn container_for(segment: &str) -> Value {
+  if segment.parse::<usize>().is_ok() { json!([]) } else { json!({}) }
+}
+
+fn pointer_exists(root: &Option<Value>, pointer: &str) -> bool {
+  root.as_ref().and_then(|root| root.pointer(pointer)).is_some_and(|value| !value.is_null())
+}
+
+/// Schema of the JSON response body of the operation (for the status code of the criteria, or
+/// the first success response), with the OpenAPI document it is from
+fn response_schema(resolver: &OperationResolver, operation: &Operation, criteria: &[Criterion]) -> Option<(Value, Value)> {
+  let source = resolver.sources.iter().find(|source| source.name == operation.source_name)?;
+  let responses = source.document.get("paths")?
+    .get(&operation.path)?
+    .get(operation.method.to_lowercase())?
+    .get("responses")?
+    .as_object()?;
+  let status = criteria.iter()
+    .filter(|criterion| criterion_type(criterion) == "simple")
+    .flat_map(|criterion| criterion.condition.split("&&").map(split_condition).collect::<Vec<_>>())
+    .find_map(|(expression, operator, literal)| (expression == "$statusCode" && operator == Some("==")).then_some(literal).flatten());
+  let response = match status {
+    Some(status) => responses.get(status),
+    None => responses.iter().find(|(code, _)| code.starts_with('2')).map(|(_, response)| response)
+  }?;
+  let response = resolve_ref(&source.document, response);
+  let schema = response.get("content")?
+    .as_object()?
+    .iter()
+    .find(|(media_type, _)| media_type.contains("json"))?
+    .1
+    .get("schema")?;
+  Some((schema.clone(), source.document.clone()))
+}
+
+fn resolve_ref<'a>(document: &'a Value, value: &'a Value) -> &'a Value {
+  value.get("$ref")
+    .and_then(|reference| reference.as_str())
+    .and_then(|reference| reference.strip_prefix('#'))
+    .and_then(|pointer| document.pointer(pointer))
+    .unwrap_or(value)
+}
+
+/// Example for the value at the JSON Pointer from the response schema, or a placeholder if the
+/// schema does not describe the value
+fn schema_example(schema: Option<&(Value, Value)>, pointer: &str, expression: &str) -> Value {
+  let placeholder = Value::String(format!("<{}>", expression));
+  let Some((schema, document)) = schema else {
+    return placeholder;
+  };
+  let mut current = resolve_ref(document, schema);
+  for segment in pointer.split('/').skip(1) {
+    let segment = segment.replace("~1", "/").replace("~0", "~");
+    let next = if segment.parse::<usize>().is_ok() && current.get("items").is_some() {
+      current.get("items")
+    } else {
+      current.get("properties").and_then(|properties| properties.get(&segment))
+    };
+    match next {
+      Some(next) => current = resolve_ref(document, next),
+      None => return placeholder
+    }
+  }
+  if let Some(example) = current.get("example").or_else(|| current.get("default")) {
+    return example.clone();
+  }
+  if let Some(value) = current.get("enum").and_then(|values| values.as_array()).and_then(|values| values.first()) {
+    return value.clone();
+  }
+  match current.get("type").and_then(|schema_type| schema_type.as_str()) {
+    Some("integer") => json!(1),
+    Some("number") => json!(1.0),
+    Some("boolean") => json!(true),
+    Some("array") => json!([]),
+    Some("object") => json!({}),
+    Some("string") => json!("string"),
+    _ => placeholder
+  }
+}
+
+fn file_name_part(name: &str) -> String {
+  name.chars().map(|ch| if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' { ch } else { '_' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use crate::arazzo;
+  use crate::either::Either;
+  use crate::extensions::AnyValue;
+  use crate::operations::{OpenApiSource, OperationResolver};
+  use crate::pact::{consumer_pacts, set_pointer, verification_scenario, write_consumer_pacts};
+  use crate::v1_0::{ArazzoDescription, ParameterObject, Step, Workflow};
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      info("Pet Shop", "");
+      workflow("adopt") {
+        inputs: json!({ "type": "object", "properties": { "petId": { "type": "string", "default": "1" } } });
+        step("find") {
+          operation_id: "getPet";
+          parameter("petId", "path", "$inputs.petId");
+          parameter("verbose", "query", "true");
+          success_criterion: "$statusCode == 200 && $response.body#/status == 'available'";
+          typed_success_criterion("regex", "$response.header.Content-Type", "^application/json");
+          output("id", "$response.body#/id");
+          output("owner", "$response.body#/owner/name");
+        }
+        step("adopt") {
+          operation_id: "adopt";
+          json_body: json!({ "pet": "{$steps.find.outputs.id}", "note": "Adopted" });
+          success_criterion: "$statusCode == 201";
+          output("location", "$response.header.Location");
+        }
+        step("notify") { operation_id: "notify"; }
+      }
+    }
+  }
+
+  fn resolver() -> OperationResolver {
+    OperationResolver::new(vec![
+      OpenApiSource::new("petstore", "petstore.json", json!({
+        "paths": {
+          "/pets/{petId}": { "get": {
+            "operationId": "getPet",
+            "responses": { "200": { "$ref": "#/components/responses/Pet" } }
+          } },
+          "/adoptions": { "post": { "operationId": "adopt", "responses": { "201": {} } } }
+        },
+        "components": {
+          "responses": {
+            "Pet": { "content": { "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } } } }
+          },
+          "schemas": {
+            "Pet": { "type": "object", "properties": {
+              "id": { "type": "integer" },
+              "owner": { "type": "object", "properties": { "name": { "type": "string", "example": "Jane" } } }
+            } }
+          }
+        }
+      })),
+      OpenApiSource::new("notifications", "notifications.json", json!({
+        "paths": { "/notifications": { "post": { "operationId": "notify" } } }
+      }))
+    ])
+  }
+
+  #[test]
+  fn derives_a_pact_for_each_provider() {
+    let pacts = consumer_pacts(&description(), &resolver()).unwrap();
+    expect!(pacts.iter().map(|pact| pact.file_name()).collect::<Vec<_>>())
+      .to(be_equal_to(vec!["Pet_Shop-notifications.json".to_string(), "Pet_Shop-petstore.json".to_string()]));
+
+    assert_eq!(pacts[1].to_json(), json!({
+      "consumer": { "name": "Pet Shop" },
+      "provider": { "name": "petstore" },
+      "interactions": [
+        {
+          "description": "adopt - find",
+          "request": {
+            "method": "GET",
+            "path": "/pets/1",
+            "query": { "verbose": ["true"] },
+            "matchingRules": { "path": { "matchers": [{ "match": "regex", "regex": "/pets/[^/]+" }] } }
+          },
+          "response": {
+            "status": 200,
+            "headers": { "Content-Type": "<$response.header.Content-Type>" },
+            "body": { "status": "available", "id": 1, "owner": { "name": "Jane" } },
+            "matchingRules": {
+              "body": {
+                "$.id": { "matchers": [{ "match": "type" }] },
+                "$.owner.name": { "matchers": [{ "match": "type" }] }
+              },
+              "header": { "Content-Type": { "matchers": [{ "match": "regex", "regex": "^application/json" }] } }
+            }
+          }
+        },
+        {
+          "description": "adopt - adopt",
+          "request": {
+            "method": "POST",
+            "path": "/adoptions",
+            "headers": { "Content-Type": "application/json" },
+            "body": { "pet": "<$steps.find.outputs.id>", "note": "Adopted" },
+            "matchingRules": { "body": { "$.pet": { "matchers": [{ "match": "type" }] } } }
+          },
+          "response": {
+            "status": 201,
+            "headers": { "Location": "<$response.header.Location>" },
+            "matchingRules": { "header": { "Location": { "matchers": [{ "match": "regex", "regex": ".+" }] } } }
+          }
+        }
+      ],
+      "metadata": { "pactSpecification": { "version": "3.0.0" } }
+    }));
+  }
+
+  #[test]
+  fn fails_if_an_operation_can_not_be_resolved() {
+    let resolver = OperationResolver::new(vec![]);
+    expect!(consumer_pacts(&description(), &resolver)).to(be_err());
+  }
+
+  #[test]
+  fn writes_the_pact_files() {
+    let dir = std::env::temp_dir().join(format!("arazzo-pacts-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let paths = write_consumer_pacts(&description(), &resolver(), &dir).unwrap();
+    expect!(paths.len()).to(be_equal_to(2));
+    let json: serde_json::Value = serde_json::from_slice(&std::fs::read(&paths[0]).unwrap()).unwrap();
+    expect!(json["provider"]["name"].as_str()).to(be_some().value("notifications"));
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn sets_values_at_json_pointers() {
+    let mut root = None;
+    set_pointer(&mut root, "/items/1/id", json!(10));
+    set_pointer(&mut root, "/items/0", json!("first"));
+    set_pointer(&mut root, "", json!("ignored"));
+    expect!(root).to(be_some().value(json!({ "items": ["first", { "id": 10 }] })));
+  }
+
+  #[test]
+  fn converts_a_workflow_to_an_ordered_verification_scenario() {
+    let mut description = description();
+    description.workflows[0].depends_on = vec!["register".to_string()];
+    description.workflows[0].steps[2].parameters = vec![Either::First(ParameterObject {
+      name: "X-Pet".to_string(),
+      r#in: Some("header".to_string()),
+      value: Either::First(AnyValue::String("pet-{$steps.find.outputs.id}".to_string())),
+      .. ParameterObject::default()
+    })];
+    let scenario = verification_scenario(&description, "adopt", &resolver()).unwrap();
+
+    expect!(scenario.interactions.iter().map(|interaction| interaction.step_id.as_str()).collect::<Vec<_>>())
+      .to(be_equal_to(vec!["find", "adopt", "notify"]));
+    assert_eq!(scenario.interactions[0].interaction["providerStates"], json!([{ "name": "workflow 'register' has completed" }]));
+    expect!(scenario.interactions[0].interaction["request"].get("generators")).to(be_none());
+
+    let adopt = &scenario.interactions[1].interaction;
+    assert_eq!(adopt["providerStates"], json!([{
+      "name": "step 'find' of workflow 'adopt' has completed",
+      "params": { "find_id": "$response.body#/id" }
+    }]));
+    assert_eq!(adopt["request"]["generators"], json!({
+      "body": { "$.pet": { "type": "ProviderState", "expression": "${find_id}" } }
+    }));
+
+    let notify = &scenario.interactions[2].interaction;
+    assert_eq!(notify["providerStates"], json!([
+      { "name": "step 'adopt' of workflow 'adopt' has completed" },
+      { "name": "step 'find' of workflow 'adopt' has completed", "params": { "find_id": "$response.body#/id" } }
+    ]));
+    assert_eq!(notify["request"]["generators"], json!({
+      "header": { "X-Pet": { "type": "ProviderState", "expression": "pet-${find_id}" } }
+    }));
+
+    let pacts = scenario.pacts();
+    expect!(pacts.iter().map(|pact| (pact.provider.as_str(), pact.interactions.len())).collect::<Vec<_>>())
+      .to(be_equal_to(vec![("notifications", 1), ("petstore", 2)]));
+    expect!(verification_scenario(&description, "missing", &resolver())).to(be_err());
+  }
+
+  #[test]
+  fn inlines_the_steps_of_nested_workflows_in_the_scenario() {
+    let mut description = description();
+    description.workflows.push(Workflow {
+      workflow_id: "adopt-and-check".to_string(),
+      steps: vec![
+        Step { step_id: "run".to_string(), workflow_id: Some("adopt".to_string()), .. Step::default() },
+        Step {
+          step_id: "check".to_string(),
+          operation_id: Some("getPet".to_string()),
+          parameters: vec![Either::First(ParameterObject {
+            name: "petId".to_string(),
+            r#in: Some("path".to_string()),
+            value: Either::Second("$steps.find.outputs.id".to_string()),
+            .. ParameterObject::default()
+          })],
+          .. Step::default()
+        }
+      ],
+      .. Workflow::default()
+    });
+    let scenario = verification_scenario(&description, "adopt-and-check", &resolver()).unwrap();
+
+    expect!(scenario.interactions.iter().map(|interaction| interaction.step_id.as_str()).collect::<Vec<_>>())
+      .to(be_equal_to(vec!["find", "adopt", "notify", "check"]));
+    let check = &scenario.interactions[3].interaction;
+    expect!(check["providerStates"][0]["name"].as_str()).to(be_some().value("step 'notify' of workflow 'adopt' has completed"));
+    assert_eq!(check["request"]["generators"], json!({
+      "path": { "type": "ProviderState", "expression": "/pets/${find_id}", "dataType": "STRING" }
+    }));
+  }
+}
@@ -0,0 +1,730 @@
+//! Support for applying JSON Patch ([RFC 6902](https://www.rfc-editor.org/rfc/rfc6902)) and
+//! JSON Merge Patch ([RFC 7386](https://www.rfc-editor.org/rfc/rfc7386)) documents directly to
+//! an Arazzo description.
+//!
+//! Each operation is applied to the smallest model node that contains the target location (the
+//! Info Object, a single Source Description or Workflow, the Components Object or the whole
+//! description), and only that node is re-loaded. Once all the operations have been applied, the
+//! patched nodes are validated (see the [validation module](crate::validation)), and the patch
+//! fails if it introduced any problems in them. Problems that were already in the description, or
+//! that are outside the patched nodes, do not stop the patch from being applied.
+//!
+//! The nodes are re-loaded from JSON, which only creates the payload and extension value types
+//! that the JSON loader does (i.e. binary or XML payloads, and binary extension values, are written
+//! as strings and loaded back as strings). To keep the types of the values that the patch did not
+//! change, any workflow, step, request body or extension value of the patched description that is
+//! written as the same JSON as one in the original description is replaced with the original. The
+//! values that the patch did change, and the other objects inside a changed step (i.e. parameters
+//! and actions), have the types the JSON loader creates.
+
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::extensions::AnyValue;
+use crate::json::{json_object_require_string, json_type_name};
+use crate::v1_0::{ArazzoDescription, Components, Info, SourceDescription, Workflow};
+use crate::validation::validate;
+
+/// A single JSON Patch operation
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOperation {
+  /// Adds a value at the target location
+  Add {
+    /// JSON Pointer to the target location
+    path: String,
+    /// Value to add
+    value: Value
+  },
+  /// Removes the value at the target location
+  Remove {
+    /// JSON Pointer to the target location
+    path: String
+  },
+  /// Replaces the value at the target location
+  Replace {
+    /// JSON Pointer to the target location
+    path: String,
+    /// Value to replace the existing value with
+    value: Value
+  },
+  /// Removes the value at the `from` location and adds it to the target location
+  Move {
+    /// JSON Pointer to the source location
+    from: String,
+    /// JSON Pointer to the target location
+    path: String
+  },
+  /// Copies the value at the `from` location to the target location
+  Copy {
+    /// JSON Pointer to the source location
+    from: String,
+    /// JSON Pointer to the target location
+    path: String
+  },
+  /// Tests that the value at the target location is equal to the given value
+  Test {
+    /// JSON Pointer to the target location
+    path: String,
+    /// Value to test against
+    value: Value
+  }
+}
+
+impl PatchOperation {
+  /// Target location of the operation
+  pub fn path(&self) -> &str {
+    match self {
+      PatchOperation::Add { path, .. } => path,
+      PatchOperation::Remove { path } => path,
+      PatchOperation::Replace { path, .. } => path,
+      PatchOperation::Move { path, .. } => path,
+      PatchOperation::Copy { path, .. } => path,
+      PatchOperation::Test { path, .. } => path
+    }
+  }
+
+  /// Source location of the operation (only for move and copy operations)
+  pub fn from(&self) -> Option<&str> {
+    match self {
+      PatchOperation::Move { from, .. } => Some(from),
+      PatchOperation::Copy { from, .. } => Some(from),
+      _ => None
+    }
+  }
+
+  fn with_paths(&self, path: String, from: Option<String>) -> PatchOperation {
+    match self {
+      PatchOperation::Add { value, .. } => PatchOperation::Add { path, value: value.clone() },
+      PatchOperation::Remove { .. } => PatchOperation::Remove { path },
+      PatchOperation::Replace { value, .. } => PatchOperation::Replace { path, value: value.clone() },
+      PatchOperation::Move { .. } => PatchOperation::Move { from: from.unwrap_or_default(), path },
+      PatchOperation::Copy { .. } => PatchOperation::Copy { from: from.unwrap_or_default(), path },
+      PatchOperation::Test { value, .. } => PatchOperation::Test { path, value: value.clone() }
+    }
+  }
+}
+
+impl TryFrom<&Value> for PatchOperation {
+  type Error = anyhow::Error;
+
+  fn try_from(value: &Value) -> Result<Self, Self::Error> {
+    if let Some(map) = value.as_object() {
+      let op = json_object_require_string(map, "op")?;
+      let path = json_object_require_string(map, "path")?;
+      match op.as_str() {
+        "add" => Ok(PatchOperation::Add { path, value: patch_require_value(map)? }),
+        "remove" => Ok(PatchOperation::Remove { path }),
+        "replace" => Ok(PatchOperation::Replace { path, value: patch_require_value(map)? }),
+        "move" => Ok(PatchOperation::Move { from: json_object_require_string(map, "from")?, path }),
+        "copy" => Ok(PatchOperation::Copy { from: json_object_require_string(map, "from")?, path }),
+        "test" => Ok(PatchOperation::Test { path, value: patch_require_value(map)? }),
+        _ => Err(anyhow!("'{}' is not a valid JSON Patch operation", op))
+      }
+    } else {
+      Err(anyhow!("JSON Patch operation must be an Object, got {:?}", value))
+    }
+  }
+}
+
+fn patch_require_value(map: &Map<String, Value>) -> anyhow::Result<Value> {
+  map.get("value")
+    .cloned()
+    .ok_or_else(|| anyhow!("JSON Patch operation requires a value"))
+}
+
+/// Parses a JSON Patch document, which must be an array of operations.
+pub fn parse_json_patch(patch: &Value) -> anyhow::Result<Vec<PatchOperation>> {
+  if let Some(array) = patch.as_array() {
+    array.iter().map(PatchOperation::try_from).collect()
+  } else {
+    Err(anyhow!("JSON Patch document must be an Array, got {:?}", patch))
+  }
+}
+
+/// Model node that a patch operation is applied to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Node {
+  Document,
+  Info,
+  Components,
+  SourceDescription(usize),
+  Workflow(usize)
+}
+
+impl Node {
+  fn prefix(&self) -> Vec<String> {
+    match self {
+      Node::Document => vec![],
+      Node::Info => vec!["info".to_string()],
+      Node::Components => vec!["components".to_string()],
+      Node::SourceDescription(index) => vec!["sourceDescriptions".to_string(), index.to_string()],
+      Node::Workflow(index) => vec!["workflows".to_string(), index.to_string()]
+    }
+  }
+
+  /// If the JSON Pointer is to a location in this node
+  fn contains(&self, pointer: &str) -> bool {
+    let prefix = json_pointer_from_tokens(&self.prefix());
+    pointer.strip_prefix(&prefix).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+  }
+}
+
+/// Determines the deepest node that fully contains the given location. Operations that add or
+/// remove list entries are applied to the document.
+fn patch_node_for(tokens: &[String]) -> Node {
+  match tokens.first().map(|t| t.as_str()) {
+    Some("info") if tokens.len() > 1 => Node::Info,
+    Some("components") if tokens.len() > 1 => Node::Components,
+    Some("sourceDescriptions") if tokens.len() > 2 => tokens[1].parse::<usize>()
+      .map(Node::SourceDescription)
+      .unwrap_or(Node::Document),
+    Some("workflows") if tokens.len() > 2 => tokens[1].parse::<usize>()
+      .map(Node::Workflow)
+      .unwrap_or(Node::Document),
+    _ => Node::Document
+  }
+}
+
+/// Splits a JSON Pointer into its reference tokens, un-escaping each one.
+pub fn json_pointer_tokens(pointer: &str) -> anyhow::Result<Vec<String>> {
+  if pointer.is_empty() {
+    Ok(vec![])
+  } else if let Some(rest) = pointer.strip_prefix('/') {
+    Ok(rest.split('/')
+      .map(|token| token.replace("~1", "/").replace("~0", "~"))
+      .collect())
+  } else {
+    Err(anyhow!("'{}' is not a valid JSON Pointer", pointer))
+  }
+}
+
+fn json_pointer_from_tokens(tokens: &[String]) -> String {
+  tokens.iter()
+    .map(|token| format!("/{}", token.replace('~', "~0").replace('/', "~1")))
+    .collect()
+}
+
+impl ArazzoDescription {
+  /// Applies the JSON Patch operations to this description. Either all the operations are
+  /// applied, or if any of them fail (including re-loading any changed node, or the validation of
+  /// the changed nodes), the description is left unchanged and an error is returned.
+  pub fn apply_json_patch(&mut self, operations: &[PatchOperation]) -> anyhow::Result<()> {
+    let mut patched = self.clone();
+    let mut nodes = vec![];
+
+    for (index, operation) in operations.iter().enumerate() {
+      let node = patch_apply_operation(&mut patched, operation)
+        .map_err(|err| anyhow!("JSON Patch operation {} ({}) failed: {}", index, operation.path(), err))?;
+      nodes.push(node);
+    }
+
+    patch_keep_unchanged(self, &mut patched)?;
+    patch_validate(self, &patched, &nodes)?;
+    *self = patched;
+    Ok(())
+  }
+
+  /// Applies a JSON Merge Patch document to this description. Changes to the `info` and
+  /// `components` keys only re-load those nodes. If the patch fails to apply, or the changed nodes
+  /// are not valid, the description is left unchanged.
+  pub fn apply_merge_patch(&mut self, patch: &Value) -> anyhow::Result<()> {
+    let mut patched = self.clone();
+    let mut nodes = vec![];
+
+    if let Some(map) = patch.as_object() {
+      let mut remaining = Map::new();
+
+      for (key, value) in map {
+        match key.as_str() {
+          "info" if value.is_object() => {
+            let mut json = patch_node_to_json(&patched, Node::Info)?;
+            json_merge_patch(&mut json, value);
+            patch_node_from_json(&mut patched, Node::Info, &json)?;
+            nodes.push(Node::Info);
+          }
+          "components" if value.is_object() => {
+            let mut json = patch_node_to_json(&patched, Node::Components)?;
+            json_merge_patch(&mut json, value);
+            patch_node_from_json(&mut patched, Node::Components, &json)?;
+            nodes.push(Node::Components);
+          }
+          _ => {
+            remaining.insert(key.clone(), value.clone());
+          }
+        }
+      }
+
+      if !remaining.is_empty() {
+        let mut json = patch_node_to_json(&patched, Node::Document)?;
+        json_merge_patch(&mut json, &Value::Object(remaining));
+        patch_node_from_json(&mut patched, Node::Document, &json)?;
+        nodes.push(Node::Document);
+      }
+    } else {
+      // A merge patch that is not an object replaces the whole document
+      patch_node_from_json(&mut patched, Node::Document, patch)?;
+      nodes.push(Node::Document);
+    }
+
+    patch_keep_unchanged(self, &mut patched)?;
+    patch_validate(self, &patched, &nodes)?;
+    *self = patched;
+    Ok(())
+  }
+}
+
+/// Replaces the parts of the patched description that are written as the same JSON as in the
+/// original description with the original values, so that they keep their payload and extension
+/// value types
+fn patch_keep_unchanged(original: &ArazzoDescription, patched: &mut ArazzoDescription) -> anyhow::Result<()> {
+  if !patch_keep_if_unchanged(&original.info, &mut patched.info)? {
+    patch_keep_unchanged_extensions(&original.info.extensions, &mut patched.info.extensions)?;
+  }
+  if !patch_keep_if_unchanged(&original.components, &mut patched.components)? {
+    patch_keep_unchanged_extensions(&original.components.extensions, &mut patched.components.extensions)?;
+  }
+  for source in patch_keep_unchanged_items(&original.source_descriptions, &mut patched.source_descriptions)? {
+    if let Some(original) = original.source_descriptions.iter().find(|original| original.name == source.name) {
+      patch_keep_unchanged_extensions(&original.extensions, &mut source.extensions)?;
+    }
+  }
+  for workflow in patch_keep_unchanged_items(&original.workflows, &mut patched.workflows)? {
+    if let Some(original) = original.workflows.iter().find(|original| original.workflow_id == workflow.workflow_id) {
+      patch_keep_unchanged_steps(original, workflow)?;
+      patch_keep_unchanged_extensions(&original.extensions, &mut workflow.extensions)?;
+    }
+  }
+  patch_keep_unchanged_extensions(&original.extensions, &mut patched.extensions)
+}
+
+fn patch_keep_unchanged_steps(original: &Workflow, patched: &mut Workflow) -> anyhow::Result<()> {
+  for step in patch_keep_unchanged_items(&original.steps, &mut patched.steps)? {
+    if let Some(original) = original.steps.iter().find(|original| original.step_id == step.step_id) {
+      if let (Some(original), Some(body)) = (&original.request_body, &mut step.request_body) {
+        patch_keep_if_unchanged(original, body)?;
+      }
+      patch_keep_unchanged_extensions(&original.extensions, &mut step.extensions)?;
+    }
+  }
+  Ok(())
+}
+
+fn patch_keep_unchanged_extensions(
+  original: &HashMap<String, AnyValue>,
+  patched: &mut HashMap<String, AnyValue>
+) -> anyhow::Result<()> {
+  for (key, value) in patched.iter_mut() {
+    if let Some(original) = original.get(key) {
+      patch_keep_if_unchanged(original, value)?;
+    }
+  }
+  Ok(())
+}
+
+/// Replaces the patched value with the original one if they are written as the same JSON,
+/// returning if the value was unchanged
+fn patch_keep_if_unchanged<T: Serialize + Clone>(original: &T, patched: &mut T) -> anyhow::Result<bool> {
+  if serde_json::to_value(original)? == serde_json::to_value(&*patched)? {
+    *patched = original.clone();
+    Ok(true)
+  } else {
+    Ok(false)
+  }
+}
+
+/// Replaces each patched item with the original item that is written as the same JSON (the items
+/// may have been moved by the patch), returning the items that were changed
+fn patch_keep_unchanged_items<'a, T: Serialize + Clone>(
+  original: &[T],
+  patched: &'a mut [T]
+) -> anyhow::Result<Vec<&'a mut T>> {
+  let original_json = original.iter()
+    .map(serde_json::to_value)
+    .collect::<Result<Vec<_>, _>>()?;
+  let mut changed = vec![];
+  for item in patched.iter_mut() {
+    let json = serde_json::to_value(&*item)?;
+    match original_json.iter().position(|original| *original == json) {
+      Some(index) => *item = original[index].clone(),
+      None => changed.push(item)
+    }
+  }
+  Ok(changed)
+}
+
+/// Validates the patched description, failing if there are any problems in the patched nodes that
+/// were not in the original description
+fn patch_validate(original: &ArazzoDescription, patched: &ArazzoDescription, nodes: &[Node]) -> anyhow::Result<()> {
+  let existing = validate(original);
+  let issues = validate(patched).into_iter()
+    .filter(|issue| nodes.iter().any(|node| node.contains(&issue.path)) && !existing.contains(issue))
+    .map(|issue| issue.to_string())
+    .collect::<Vec<_>>();
+  if issues.is_empty() {
+    Ok(())
+  } else {
+    Err(anyhow!("The patched description is not valid: {}", issues.join(", ")))
+  }
+}
+
+/// Applies the operation, returning the node that was re-loaded
+fn patch_apply_operation(description: &mut ArazzoDescription, operation: &PatchOperation) -> anyhow::Result<Node> {
+  let path = json_pointer_tokens(operation.path())?;
+  let from = operation.from().map(json_pointer_tokens).transpose()?;
+
+  let node = match &from {
+    Some(from) if patch_node_for(from) != patch_node_for(&path) => Node::Document,
+    _ => patch_node_for(&path)
+  };
+  let prefix = node.prefix();
+  let relative_path = json_pointer_from_tokens(&path[prefix.len()..]);
+  let relative_from = from.map(|from| json_pointer_from_tokens(&from[prefix.len()..]));
+
+  let mut json = patch_node_to_json(description, node)?;
+  json_apply_patch_operation(&mut json, &operation.with_paths(relative_path, relative_from))?;
+  patch_node_from_json(description, node, &json)?;
+  Ok(node)
+}
+
+fn patch_node_to_json(description: &ArazzoDescription, node: Node) -> anyhow::Result<Value> {
+  let json = match node {
+    Node::Document => serde_json::to_value(description)?,
+    Node::Info => serde_json::to_value(&description.info)?,
+    Node::Components => serde_json::to_value(&description.components)?,
+    Node::SourceDescription(index) => description.source_descriptions.get(index)
+      .map(serde_json::to_value)
+      .transpose()?
+      .ok_or_else(|| anyhow!("There is no Source Description with index {}", index))?,
+    Node::Workflow(index) => description.workflows.get(index)
+      .map(serde_json::to_value)
+      .transpose()?
+      .ok_or_else(|| anyhow!("There is no Workflow with index {}", index))?
+  };
+  Ok(json)
+}
+
+fn patch_node_from_json(description: &mut ArazzoDescription, node: Node, json: &Value) -> anyhow::Result<()> {
+  match node {
+    Node::Document => *description = ArazzoDescription::try_from(json)?,
+    Node::Info => description.info = Info::try_from(json)?,
+    Node::Components => description.components = Components::try_from(json)?,
+    Node::SourceDescription(index) => description.source_descriptions[index] = SourceDescription::try_from(json)?,
+    Node::Workflow(index) => description.workflows[index] = Workflow::try_from(json)?
+  }
+  Ok(())
+}
+
+/// Applies a single JSON Patch operation to a JSON document.
+pub fn json_apply_patch_operation(json: &mut Value, operation: &PatchOperation) -> anyhow::Result<()> {
+  match operation {
+    PatchOperation::Add { path, value } => json_patch_add(json, path, value.clone()),
+    PatchOperation::Remove { path } => json_patch_remove(json, path).map(|_| ()),
+    PatchOperation::Replace { path, value } => {
+      let target = json.pointer_mut(path)
+        .ok_or_else(|| anyhow!("Path '{}' does not exist", path))?;
+      *target = value.clone();
+      Ok(())
+    }
+    PatchOperation::Move { from, path } => {
+      if path.starts_with(&format!("{}/", from)) {
+        return Err(anyhow!("Can not move '{}' into one of its children", from));
+      }
+      let value = json_patch_remove(json, from)?;
+      json_patch_add(json, path, value)
+    }
+    PatchOperation::Copy { from, path } => {
+      let value = json.pointer(from)
+        .cloned()
+        .ok_or_else(|| anyhow!("Path '{}' does not exist", from))?;
+      json_patch_add(json, path, value)
+    }
+    PatchOperation::Test { path, value } => {
+      let actual = json.pointer(path)
+        .ok_or_else(|| anyhow!("Path '{}' does not exist", path))?;
+      if actual == value {
+        Ok(())
+      } else {
+        Err(anyhow!("Value at '{}' was {}, expected {}", path, actual, value))
+      }
+    }
+  }
+}
+
+fn json_patch_parent<'a>(json: &'a mut Value, path: &str) -> anyhow::Result<(&'a mut Value, String)> {
+  let mut tokens = json_pointer_tokens(path)?;
+  let last = tokens.pop()
+    .ok_or_else(|| anyhow!("Operation can not be applied to the root of the document"))?;
+  let parent_pointer = json_pointer_from_tokens(&tokens);
+  let parent = json.pointer_mut(&parent_pointer)
+    .ok_or_else(|| anyhow!("Path '{}' does not exist", parent_pointer))?;
+  Ok((parent, last))
+}
+
+fn json_patch_add(json: &mut Value, path: &str, value: Value) -> anyhow::Result<()> {
+  if path.is_empty() {
+    *json = value;
+    return Ok(());
+  }
+
+  let (parent, key) = json_patch_parent(json, path)?;
+  match parent {
+    Value::Object(map) => {
+      map.insert(key, value);
+      Ok(())
+    }
+    Value::Array(array) => {
+      if key == "-" {
+        array.push(value);
+        Ok(())
+      } else {
+        let index = json_patch_array_index(&key)?;
+        if index > array.len() {
+          Err(anyhow!("Index {} is out of bounds", index))
+        } else {
+          array.insert(index, value);
+          Ok(())
+        }
+      }
+    }
+    _ => Err(anyhow!("Can not add a value to a {} value", json_type_name(parent)))
+  }
+}
+
+fn json_patch_remove(json: &mut Value, path: &str) -> anyhow::Result<Value> {
+  let (parent, key) = json_patch_parent(json, path)?;
+  match parent {
+    Value::Object(map) => map.remove(&key)
+      .ok_or_else(|| anyhow!("Path '{}' does not exist", path)),
+    Value::Array(array) => {
+      let index = json_patch_array_index(&key)?;
+      if index < array.len() {
+        Ok(array.remove(index))
+      } else {
+        Err(anyhow!("Index {} is out of bounds", index))
+      }
+    }
+    _ => Err(anyhow!("Path '{}' does not exist", path))
+  }
+}
+
+fn json_patch_array_index(token: &str) -> anyhow::Result<usize> {
+  if token.len() > 1 && token.starts_with('0') {
+    Err(anyhow!("'{}' is not a valid array index", token))
+  } else {
+    token.parse::<usize>()
+      .map_err(|_| anyhow!("'{}' is not a valid array index", token))
+  }
+}
+
+/// Applies a JSON Merge Patch to a JSON document, following the algorithm from RFC 7386.
+pub fn json_merge_patch(target: &mut Value, patch: &Value) {
+  if let Some(patch_map) = patch.as_object() {
+    if !target.is_object() {
+      *target = Value::Object(Map::new());
+    }
+    if let Some(target_map) = target.as_object_mut() {
+      for (key, value) in patch_map {
+        if value.is_null() {
+          target_map.remove(key);
+        } else {
+          json_merge_patch(target_map.entry(key.clone()).or_insert(Value::Null), value);
+        }
+      }
+    }
+  } else {
+    *target = patch.clone();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use bytes::Bytes;
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use crate::arazzo;
+  use crate::extensions::{AnyValue, Extensible};
+  use crate::patch::{json_merge_patch, parse_json_patch, PatchOperation};
+  use crate::payloads::BytesPayload;
+  use crate::v1_0::{ArazzoDescription, Info, RequestBody};
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      info_object(Info {
+        title: "test".to_string(),
+        version: "1.0.0".to_string(),
+        extensions: hashmap!{ "owner".to_string() => AnyValue::String("team-a".to_string()) },
+        .. Info::default()
+      });
+      source("petStore", "http://petstore");
+      workflow("one") {
+        step("a") { operation_id: "getPets"; }
+        step("b") { operation_id: "getPet"; }
+      }
+    }
+  }
+
+  #[test]
+  fn parse_json_patch_test() {
+    let patch = json!([
+      { "op": "add", "path": "/a", "value": 1 },
+      { "op": "remove", "path": "/b" },
+      { "op": "move", "from": "/c", "path": "/d" }
+    ]);
+    expect!(parse_json_patch(&patch)).to(be_ok().value(vec![
+      PatchOperation::Add { path: "/a".to_string(), value: json!(1) },
+      PatchOperation::Remove { path: "/b".to_string() },
+      PatchOperation::Move { from: "/c".to_string(), path: "/d".to_string() }
+    ]));
+
+    expect!(parse_json_patch(&json!({}))).to(be_err());
+    expect!(parse_json_patch(&json!([{ "op": "add", "path": "/a" }]))).to(be_err());
+    expect!(parse_json_patch(&json!([{ "op": "other", "path": "/a" }]))).to(be_err());
+  }
+
+  #[test]
+  fn apply_json_patch_to_a_step() {
+    let mut description = description();
+    let patch = parse_json_patch(&json!([
+      { "op": "replace", "path": "/workflows/0/steps/1/operationId", "value": "findPet" },
+      { "op": "add", "path": "/workflows/0/steps/-", "value": { "stepId": "c", "operationId": "addPet" } },
+      { "op": "test", "path": "/workflows/0/steps/2/stepId", "value": "c" }
+    ])).unwrap();
+
+    expect!(description.apply_json_patch(&patch)).to(be_ok());
+    let steps = &description.workflows[0].steps;
+    expect!(steps.len()).to(be_equal_to(3));
+    expect!(steps[1].operation_id.clone()).to(be_some().value("findPet"));
+    expect!(steps[2].step_id.as_str()).to(be_equal_to("c"));
+  }
+
+  #[test]
+  fn apply_json_patch_keeps_extensions() {
+    let mut description = description();
+    let patch = parse_json_patch(&json!([
+      { "op": "replace", "path": "/info/title", "value": "patched" }
+    ])).unwrap();
+
+    expect!(description.apply_json_patch(&patch)).to(be_ok());
+    expect!(description.info.title.as_str()).to(be_equal_to("patched"));
+    expect!(description.info.extensions).to(be_equal_to(hashmap!{
+      "owner".to_string() => AnyValue::String("team-a".to_string())
+    }));
+  }
+
+  #[test]
+  fn apply_json_patch_keeps_the_types_of_unchanged_payloads_and_extension_values() {
+    let mut description = description();
+    description.workflows[0].steps[0].request_body = Some(RequestBody {
+      content_type: Some("application/octet-stream".to_string()),
+      payload: Some(Arc::new(BytesPayload(Bytes::from_static(b"\x00\x01binary")))),
+      replacements: vec![],
+      extensions: Default::default()
+    });
+    description.workflows[0].steps[1].set_extension("x-image", AnyValue::Binary(Bytes::from_static(b"\x89PNG")));
+    let patch = parse_json_patch(&json!([
+      { "op": "replace", "path": "/workflows/0/steps/1/operationId", "value": "findPet" }
+    ])).unwrap();
+
+    expect!(description.apply_json_patch(&patch)).to(be_ok());
+    let steps = &description.workflows[0].steps;
+    expect!(steps[1].operation_id.clone()).to(be_some().value("findPet"));
+    expect!(steps[1].extensions.get("image")).to(be_some().value(&AnyValue::Binary(Bytes::from_static(b"\x89PNG"))));
+    let payload = steps[0].request_body.as_ref().and_then(|body| body.payload_as::<BytesPayload>());
+    expect!(payload.map(|payload| payload.0.clone())).to(be_some().value(Bytes::from_static(b"\x00\x01binary")));
+
+    expect!(description.apply_merge_patch(&json!({ "x-merged": true }))).to(be_ok());
+    let steps = &description.workflows[0].steps;
+    expect!(steps[0].request_body.as_ref().and_then(|body| body.payload_as::<BytesPayload>()).is_some()).to(be_true());
+    expect!(steps[1].extensions.get("image")).to(be_some().value(&AnyValue::Binary(Bytes::from_static(b"\x89PNG"))));
+  }
+
+  #[test]
+  fn apply_json_patch_is_atomic() {
+    let mut description = description();
+    let original = description.clone();
+    let patch = parse_json_patch(&json!([
+      { "op": "replace", "path": "/info/title", "value": "patched" },
+      { "op": "remove", "path": "/workflows/0/steps/0/stepId" }
+    ])).unwrap();
+
+    expect!(description.apply_json_patch(&patch)).to(be_err());
+    assert_eq!(original, description);
+  }
+
+  #[test]
+  fn apply_json_patch_validates_the_patched_nodes() {
+    let mut description = description();
+    let original = description.clone();
+    let patch = parse_json_patch(&json!([
+      { "op": "replace", "path": "/workflows/0/steps/1/stepId", "value": "a" }
+    ])).unwrap();
+
+    expect!(description.apply_json_patch(&patch).unwrap_err().to_string()).to(be_equal_to(
+      "The patched description is not valid: /workflows/0/steps/1: Duplicate Step ID 'a' [4.6.5.1 Fixed Fields]"));
+    assert_eq!(original, description);
+
+    let patch = json!({ "info": { "version": "" } });
+    expect!(description.apply_merge_patch(&patch).unwrap_err().to_string()).to(be_equal_to(
+      "The patched description is not valid: /info/version: Version is required [4.6.2.1 Fixed Fields]"));
+    assert_eq!(original, description);
+  }
+
+  #[test]
+  fn apply_json_patch_across_nodes() {
+    let mut description = description();
+    let patch = parse_json_patch(&json!([
+      { "op": "copy", "from": "/workflows/0", "path": "/workflows/-" },
+      { "op": "replace", "path": "/workflows/1/workflowId", "value": "two" },
+      { "op": "move", "from": "/info/x-owner", "path": "/x-owner" }
+    ])).unwrap();
+
+    expect!(description.apply_json_patch(&patch)).to(be_ok());
+    expect!(description.workflows.len()).to(be_equal_to(2));
+    expect!(description.workflows[1].workflow_id.as_str()).to(be_equal_to("two"));
+    expect!(description.info.extensions.is_empty()).to(be_true());
+    expect!(description.extensions).to(be_equal_to(hashmap!{
+      "owner".to_string() => AnyValue::String("team-a".to_string())
+    }));
+  }
+
+  #[test]
+  fn apply_merge_patch() {
+    let mut description = description();
+    let patch = json!({
+      "info": {
+        "summary": "Merged",
+        "x-owner": null
+      },
+      "x-merged": true
+    });
+
+    expect!(description.apply_merge_patch(&patch)).to(be_ok());
+    expect!(description.info.summary.clone()).to(be_some().value("Merged"));
+    expect!(description.info.extensions.is_empty()).to(be_true());
+    expect!(description.extensions).to(be_equal_to(hashmap!{
+      "merged".to_string() => AnyValue::Boolean(true)
+    }));
+
+    let mut description = self::description();
+    expect!(description.apply_merge_patch(&json!({ "workflows": [] }))).to(be_err());
+  }
+
+  #[test]
+  fn json_merge_patch_test() {
+    let mut json = json!({ "a": "b", "c": { "d": "e", "f": "g" } });
+    json_merge_patch(&mut json, &json!({ "a": "z", "c": { "f": null } }));
+    expect!(json).to(be_equal_to(json!({ "a": "z", "c": { "d": "e" } })));
+
+    let mut json = json!({ "a": [ "b" ] });
+    json_merge_patch(&mut json, &json!({ "a": [ "c" ] }));
+    expect!(json).to(be_equal_to(json!({ "a": [ "c" ] })));
+  }
+}
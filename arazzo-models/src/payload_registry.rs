@@ -0,0 +1,321 @@
+//! Registry of payload constructors keyed by content type. The loaders use the global registry to
+//! create the payload of a request body based on its `contentType`.
+//!
+//! Content types are matched against patterns like `application/json`, `image/*` or
+//! `application/*+json` (any parameters of the content type are ignored). When more than one
+//! pattern matches, the constructor registered last is used, so constructors registered with
+//! [`register_payload_type`] take precedence over the defaults.
+//!
+//! The default registry has constructors for:
+//! * JSON types (`application/json` and `*/*+json`), which keep the payload as loaded (JSON payloads
+//!   authored as strings are kept as strings, as they are generally templates that contain runtime
+//!   expressions).
+//! * Binary types (see [`is_binary_content_type`](crate::payloads::is_binary_content_type)), where
+//!   Base64 encoded strings are decoded into a [`BytesPayload`].
+//! * XML types (`application/xml`, `text/xml` and `*/*+xml`), where strings are parsed into an
+//!   `XmlPayload` (requires the `xml` feature).
+//! * Forms (`application/x-www-form-urlencoded`), where objects are loaded as a [`FormPayload`].
+//! * GraphQL requests (`application/graphql+json`), where objects are loaded as a
+//!   [`GraphQlPayload`].
+//!
+//! If no constructor matches, or the constructor fails, the value is loaded as a
+//! [`StringPayload`], [`JsonPayload`] or `YamlPayload`, depending on the source of the value.
+
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, LazyLock, RwLock};
+
+use anyhow::anyhow;
+use bytes::Bytes;
+#[cfg(feature = "json")] use serde_json::Value;
+#[cfg(feature = "yaml")] use yaml_rust2::Yaml;
+
+use crate::base64;
+use crate::extensions::AnyValue;
+use crate::payloads::{
+  BytesPayload,
+  FormPayload,
+  Payload,
+  StringPayload,
+  BINARY_CONTENT_TYPES,
+  XML_CONTENT_TYPES
+};
+#[cfg(feature = "json")] use crate::payloads::JsonPayload;
+#[cfg(any(feature = "json", feature = "yaml"))] use crate::payloads::GraphQlPayload;
+#[cfg(feature = "xml")] use crate::payloads::XmlPayload;
+#[cfg(feature = "yaml")] use crate::payloads::YamlPayload;
+#[cfg(feature = "yaml")] use crate::yaml::yaml_to_json;
+
+/// Payload value loaded from a document
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PayloadValue<'a> {
+  /// String value
+  String(&'a str),
+  /// Structured value loaded from a JSON document
+  #[cfg(feature = "json")]
+  Json(&'a Value),
+  /// Structured value loaded from a YAML document
+  #[cfg(feature = "yaml")]
+  Yaml(&'a Yaml)
+}
+
+impl PayloadValue<'_> {
+  /// Returns the default payload for the value, which is used when there is no constructor for the
+  /// content type.
+  pub fn default_payload(&self) -> Arc<dyn Payload + Send + Sync> {
+    match self {
+      PayloadValue::String(s) => Arc::new(StringPayload(s.to_string())),
+      #[cfg(feature = "json")]
+      PayloadValue::Json(json) => Arc::new(JsonPayload((*json).clone())),
+      #[cfg(feature = "yaml")]
+      PayloadValue::Yaml(yaml) => Arc::new(YamlPayload((*yaml).clone()))
+    }
+  }
+
+  /// Converts the value to an [`AnyValue`]
+  pub fn to_any_value(&self) -> anyhow::Result<AnyValue> {
+    match self {
+      PayloadValue::String(s) => Ok(AnyValue::String(s.to_string())),
+      #[cfg(feature = "json")]
+      PayloadValue::Json(json) => AnyValue::try_from(*json),
+      #[cfg(feature = "yaml")]
+      PayloadValue::Yaml(yaml) => AnyValue::try_from(*yaml)
+    }
+  }
+}
+
+/// Function that constructs a payload from a loaded value
+pub type PayloadConstructor = Arc<dyn Fn(&PayloadValue<'_>) -> anyhow::Result<Arc<dyn Payload + Send + Sync>> + Send + Sync>;
+
+/// Registry of payload constructors keyed by content type patterns
+#[derive(Clone)]
+pub struct PayloadRegistry {
+  constructors: Vec<(String, PayloadConstructor)>
+}
+
+impl PayloadRegistry {
+  /// Creates an empty registry. Use [`PayloadRegistry::default`] for a registry with the default
+  /// constructors.
+  pub fn new() -> Self {
+    PayloadRegistry { constructors: vec![] }
+  }
+
+  /// Registers the constructor for all content types that match the pattern
+  pub fn register<F>(&mut self, pattern: &str, constructor: F)
+    where F: Fn(&PayloadValue<'_>) -> anyhow::Result<Arc<dyn Payload + Send + Sync>> + Send + Sync + 'static {
+    self.constructors.push((pattern.trim().to_lowercase(), Arc::new(constructor)));
+  }
+
+  /// Returns the constructor for the content type
+  pub fn constructor(&self, content_type: &str) -> Option<&PayloadConstructor> {
+    // The patterns are normalised when they are registered, so only the content type needs to be
+    // normalised, and only once (this is called for every request body that is loaded)
+    let content_type = normalise_content_type(content_type);
+    self.constructors.iter()
+      .rev()
+      .find(|(pattern, _)| normalised_content_type_matches(pattern, &content_type))
+      .map(|(_, constructor)| constructor)
+  }
+
+  /// Creates the payload for the value using the constructor for the content type. Falls back to
+  /// the default payload for the value if there is no constructor or the constructor fails.
+  pub fn create(&self, value: &PayloadValue<'_>, content_type: Option<&str>) -> Arc<dyn Payload + Send + Sync> {
+    content_type
+      .and_then(|content_type| self.constructor(content_type))
+      .and_then(|constructor| constructor(value).ok())
+      .unwrap_or_else(|| value.default_payload())
+  }
+}
+
+impl Default for PayloadRegistry {
+  fn default() -> Self {
+    let mut registry = PayloadRegistry::new();
+
+    for pattern in ["application/json", "*/*+json"] {
+      registry.register(pattern, |value| Ok(value.default_payload()));
+    }
+
+    for pattern in BINARY_CONTENT_TYPES {
+      registry.register(pattern, bytes_payload);
+    }
+
+    // Registered after the binary types, so that SVG images (image/svg+xml) are treated as XML
+    for pattern in XML_CONTENT_TYPES {
+      registry.register(pattern, xml_payload);
+    }
+
+    // Form bodies authored as strings are already encoded
+    #[allow(unreachable_patterns)]
+    registry.register("application/x-www-form-urlencoded", |value| match value {
+      PayloadValue::String(_) => Ok(value.default_payload()),
+      _ => {
+        let payload: Arc<dyn Payload + Send + Sync> = Arc::new(FormPayload::from_value(&value.to_any_value()?)?);
+        Ok(payload)
+      }
+    });
+
+    // GraphQL requests are JSON, so this is registered after the JSON types
+    registry.register("application/graphql+json", graphql_payload);
+
+    registry
+  }
+}
+
+impl Debug for PayloadRegistry {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("PayloadRegistry")
+      .field("content_types", &self.constructors.iter().map(|(pattern, _)| pattern).collect::<Vec<_>>())
+      .finish()
+  }
+}
+
+fn bytes_payload(value: &PayloadValue<'_>) -> anyhow::Result<Arc<dyn Payload + Send + Sync>> {
+  #[allow(unreachable_patterns)]
+  match value {
+    PayloadValue::String(s) => Ok(Arc::new(BytesPayload(Bytes::from(base64::decode(s)?)))),
+    _ => Ok(value.default_payload())
+  }
+}
+
+#[cfg(feature = "xml")]
+fn xml_payload(value: &PayloadValue<'_>) -> anyhow::Result<Arc<dyn Payload + Send + Sync>> {
+  #[allow(unreachable_patterns)]
+  match value {
+    PayloadValue::String(s) => Ok(Arc::new(XmlPayload::parse(s)?)),
+    _ => Ok(value.default_payload())
+  }
+}
+
+#[cfg(not(feature = "xml"))]
+fn xml_payload(value: &PayloadValue<'_>) -> anyhow::Result<Arc<dyn Payload + Send + Sync>> {
+  Ok(value.default_payload())
+}
+
+fn graphql_payload(value: &PayloadValue<'_>) -> anyhow::Result<Arc<dyn Payload + Send + Sync>> {
+  match value {
+    PayloadValue::String(_) => Ok(value.default_payload()),
+    #[cfg(feature = "json")]
+    PayloadValue::Json(json) => Ok(Arc::new(GraphQlPayload::from_json(json)?)),
+    #[cfg(feature = "yaml")]
+    PayloadValue::Yaml(yaml) => Ok(Arc::new(GraphQlPayload::from_json(&yaml_to_json(yaml)?)?))
+  }
+}
+
+/// If the content type matches the pattern. Patterns can use `*` for the type or subtype, or a
+/// suffix like `*+json` for the subtype. Any parameters of the content type are ignored, and the
+/// match is case-insensitive.
+pub fn content_type_matches(pattern: &str, content_type: &str) -> bool {
+  normalised_content_type_matches(&pattern.trim().to_lowercase(), &normalise_content_type(content_type))
+}
+
+/// Removes any parameters from the content type, and converts it to lowercase
+fn normalise_content_type(content_type: &str) -> String {
+  content_type.split(';').next().unwrap_or_default().trim().to_lowercase()
+}
+
+/// If the content type matches the pattern, where both have already been normalised
+fn normalised_content_type_matches(pattern: &str, content_type: &str) -> bool {
+  if pattern == "*" || pattern == "*/*" {
+    return true;
+  }
+
+  match (pattern.split_once('/'), content_type.split_once('/')) {
+    (Some((pattern_type, pattern_subtype)), Some((main_type, subtype))) => {
+      (pattern_type == "*" || pattern_type == main_type) && match pattern_subtype.strip_prefix('*') {
+        Some(suffix) => subtype.ends_with(suffix),
+        None => pattern_subtype == subtype
+      }
+    }
+    _ => pattern == content_type
+  }
+}
+
+static REGISTRY: LazyLock<RwLock<PayloadRegistry>> = LazyLock::new(|| RwLock::new(PayloadRegistry::default()));
+
+/// Registers a payload constructor for the content type pattern in the global registry used by
+/// the loaders. This will take precedence over any existing constructors.
+pub fn register_payload_type<F>(pattern: &str, constructor: F) -> anyhow::Result<()>
+  where F: Fn(&PayloadValue<'_>) -> anyhow::Result<Arc<dyn Payload + Send + Sync>> + Send + Sync + 'static {
+  let mut registry = REGISTRY.write()
+    .map_err(|_| anyhow!("Payload registry lock is poisoned"))?;
+  registry.register(pattern, constructor);
+  Ok(())
+}
+
+/// Creates the payload for the value using the global registry
+pub fn create_payload(value: &PayloadValue<'_>, content_type: Option<&str>) -> Arc<dyn Payload + Send + Sync> {
+  match REGISTRY.read() {
+    Ok(registry) => registry.create(value, content_type),
+    Err(_) => value.default_payload()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::payload_registry::{content_type_matches, create_payload, register_payload_type, PayloadRegistry, PayloadValue};
+  use crate::payloads::{BytesPayload, FormPayload, GraphQlPayload, JsonPayload, Payload, StringPayload};
+
+  #[test]
+  fn matches_content_types() {
+    expect!(content_type_matches("application/json", "application/json; charset=UTF-8")).to(be_true());
+    expect!(content_type_matches("application/json", "Application/JSON")).to(be_true());
+    expect!(content_type_matches("application/json", "application/xml")).to(be_false());
+    expect!(content_type_matches("image/*", "image/png")).to(be_true());
+    expect!(content_type_matches("image/*", "text/png")).to(be_false());
+    expect!(content_type_matches("application/*+json", "application/vnd.api+json")).to(be_true());
+    expect!(content_type_matches("*/*+json", "text/problem+json")).to(be_true());
+    expect!(content_type_matches("*/*+json", "application/json")).to(be_false());
+    expect!(content_type_matches("*/*", "text/plain")).to(be_true());
+  }
+
+  #[test]
+  fn default_constructors() {
+    let registry = PayloadRegistry::default();
+    let payload = registry.create(&PayloadValue::String("AAH/"), Some("image/png"));
+    expect!(payload.downcast_ref::<BytesPayload>().map(|p| p.0.to_vec())).to(be_some().value(vec![0, 1, 0xFF]));
+    let payload = registry.create(&PayloadValue::String("not Base64!"), Some("image/png"));
+    expect!(payload.downcast_ref::<StringPayload>()).to(be_some());
+    let payload = registry.create(&PayloadValue::String("AAAA"), Some("text/plain"));
+    expect!(payload.downcast_ref::<StringPayload>()).to(be_some());
+    let payload = registry.create(&PayloadValue::String("AAAA"), None);
+    expect!(payload.downcast_ref::<StringPayload>()).to(be_some());
+
+    let json = json!({ "a": 1 });
+    let payload = registry.create(&PayloadValue::Json(&json), Some("application/vnd.api+json"));
+    expect!(payload.downcast_ref::<JsonPayload>()).to(be_some());
+    let payload = registry.create(&PayloadValue::Json(&json), Some("application/x-www-form-urlencoded"));
+    expect!(payload.as_string()).to(be_equal_to("a=1"));
+    expect!(payload.downcast_ref::<FormPayload>()).to(be_some());
+    let payload = registry.create(&PayloadValue::String("a=1"), Some("application/x-www-form-urlencoded"));
+    expect!(payload.downcast_ref::<StringPayload>()).to(be_some());
+    let json = json!({ "a": [1] });
+    let payload = registry.create(&PayloadValue::Json(&json), Some("application/x-www-form-urlencoded"));
+    expect!(payload.downcast_ref::<JsonPayload>()).to(be_some());
+
+    let json = json!({ "query": "{ pets { id } }" });
+    let payload = registry.create(&PayloadValue::Json(&json), Some("application/graphql+json"));
+    expect!(payload.downcast_ref::<GraphQlPayload>()).to(be_some().value(&GraphQlPayload::new("{ pets { id } }")));
+    let payload = registry.create(&PayloadValue::Json(&json!({ "a": 1 })), Some("application/graphql+json"));
+    expect!(payload.downcast_ref::<JsonPayload>()).to(be_some());
+  }
+
+  #[test]
+  fn custom_constructors() {
+    let mut registry = PayloadRegistry::new();
+    expect!(registry.constructor("image/png").is_none()).to(be_true());
+    registry.register("text/*", |value| {
+      let payload: Arc<dyn Payload + Send + Sync> = Arc::new(StringPayload(format!("{:?}", value)));
+      Ok(payload)
+    });
+    let payload = registry.create(&PayloadValue::String("hello"), Some("text/plain"));
+    expect!(payload.as_string()).to(be_equal_to("String(\"hello\")"));
+
+    register_payload_type("application/x-test-payload", |_| Ok(Arc::new(StringPayload("test".to_string())))).unwrap();
+    let payload = create_payload(&PayloadValue::String("hello"), Some("application/x-test-payload"));
+    expect!(payload.as_string()).to(be_equal_to("test"));
+  }
+}
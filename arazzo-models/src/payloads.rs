@@ -1,10 +1,26 @@
 //! Structs and Traits for dealing with body payloads
 
 use std::any::Any;
-use std::fmt::Debug;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use anyhow::{anyhow, Context};
 use bytes::Bytes;
-use serde_json::Value;
+use indexmap::IndexMap;
+use serde_json::{Map, Value};
+
+use crate::base64;
+use crate::content_types::parse_content_type;
+use crate::extensions::AnyValue;
+use crate::payload_registry::{content_type_matches, create_payload, PayloadValue};
+use crate::v1_0::ArazzoDescription;
+#[cfg(feature = "xml")] use crate::xml::{parse_xml, XmlDocument};
+#[cfg(feature = "yaml")] use yaml_rust2::{Yaml, YamlEmitter};
+#[cfg(feature = "yaml")] use crate::yaml::yaml_to_json;
 
 /// Body Payload
 pub trait Payload: Debug + Any {
@@ -19,6 +35,48 @@ pub trait Payload: Debug + Any {
   fn as_json(&self) -> Option<Value> {
     None
   }
+
+  /// Returns a reader over the raw bytes of the payload. The default implementation reads from a
+  /// copy of the bytes, payloads that can read their bytes without copying them (or stream them
+  /// from another location) should override this.
+  fn reader(&self) -> Box<dyn Read + '_> {
+    Box::new(Cursor::new(self.as_bytes()))
+  }
+
+  /// Writes the raw bytes of the payload to the writer. The default implementation copies from
+  /// [`Payload::reader`].
+  fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+    io::copy(&mut self.reader(), writer).map(|_| ())
+  }
+
+  /// If this payload is equal to the other payload. The default implementation compares the raw
+  /// bytes of the payloads.
+  fn eq_payload(&self, other: &dyn Payload) -> bool {
+    self.as_bytes() == other.as_bytes()
+  }
+}
+
+/// Compares payloads that can be converted to JSON by their JSON form (so the order of keys is not
+/// significant), otherwise by the raw bytes.
+fn eq_structured_payload(payload: &dyn Payload, other: &dyn Payload) -> bool {
+  match (payload.as_json(), other.as_json()) {
+    (Some(json), Some(other_json)) => json == other_json,
+    _ => payload.as_bytes() == other.as_bytes()
+  }
+}
+
+impl dyn Payload {
+  /// Returns the payload as the given payload type, if it is of that type
+  pub fn downcast_ref<T: Payload>(&self) -> Option<&T> {
+    (self as &dyn Any).downcast_ref::<T>()
+  }
+}
+
+impl dyn Payload + Send + Sync {
+  /// Returns the payload as the given payload type, if it is of that type
+  pub fn downcast_ref<T: Payload>(&self) -> Option<&T> {
+    (self as &dyn Any).downcast_ref::<T>()
+  }
 }
 
 /// Payload stored as a String value
@@ -33,6 +91,17 @@ impl Payload for StringPayload {
   fn as_string(&self) -> String {
     self.0.clone()
   }
+
+  fn reader(&self) -> Box<dyn Read + '_> {
+    Box::new(self.0.as_bytes())
+  }
+
+  fn eq_payload(&self, other: &dyn Payload) -> bool {
+    match other.downcast_ref::<StringPayload>() {
+      Some(other) => self.0 == other.0,
+      None => self.0.as_bytes() == other.as_bytes()
+    }
+  }
 }
 
 /// Empty Payload
@@ -47,6 +116,14 @@ impl Payload for EmptyPayload {
   fn as_string(&self) -> String {
     String::new()
   }
+
+  fn reader(&self) -> Box<dyn Read + '_> {
+    Box::new(io::empty())
+  }
+
+  fn eq_payload(&self, other: &dyn Payload) -> bool {
+    other.downcast_ref::<EmptyPayload>().is_some() || other.as_bytes().is_empty()
+  }
 }
 
 /// Payload stored as a JSON document. Note that this does not mean a JSON payload (that would be
@@ -66,4 +143,875 @@ impl Payload for JsonPayload {
   fn as_json(&self) -> Option<Value> {
     Some(self.0.clone())
   }
+
+  /// Serializes the JSON document directly to the writer, without creating a copy in memory
+  fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+    serde_json::to_writer(writer, &self.0).map_err(io::Error::from)
+  }
+
+  fn eq_payload(&self, other: &dyn Payload) -> bool {
+    match other.downcast_ref::<JsonPayload>() {
+      Some(other) => self.0 == other.0,
+      None => eq_structured_payload(self, other)
+    }
+  }
+}
+
+/// Payload stored as a YAML node. This is used for payloads loaded from a YAML document that are
+/// not strings, so that the original node can be written back out as YAML.
+#[cfg(feature = "yaml")]
+#[derive(Clone, Debug)]
+pub struct YamlPayload(pub Yaml);
+
+#[cfg(feature = "yaml")]
+impl Payload for YamlPayload {
+  fn as_bytes(&self) -> Bytes {
+    Bytes::from(self.as_string())
+  }
+
+  /// Returns the YAML node written out as a YAML document (without the document start marker)
+  fn as_string(&self) -> String {
+    let mut buffer = String::new();
+    let mut emitter = YamlEmitter::new(&mut buffer);
+    if emitter.dump(&self.0).is_err() {
+      return String::new();
+    }
+    buffer.strip_prefix("---")
+      .map(|s| s.strip_prefix(|ch: char| ch == ' ' || ch == '\n').unwrap_or(s))
+      .unwrap_or(buffer.as_str())
+      .to_string()
+  }
+
+  fn as_json(&self) -> Option<Value> {
+    yaml_to_json(&self.0).ok()
+  }
+
+  fn eq_payload(&self, other: &dyn Payload) -> bool {
+    eq_structured_payload(self, other)
+  }
+}
+
+/// Binary payload (i.e. images, protobuf messages or other octet streams). This is written as a
+/// Base64 encoded string.
+#[derive(Clone, Debug)]
+pub struct BytesPayload(pub Bytes);
+
+impl Payload for BytesPayload {
+  fn as_bytes(&self) -> Bytes {
+    self.0.clone()
+  }
+
+  /// Returns the Base64 encoded form of the binary data
+  fn as_string(&self) -> String {
+    base64::encode(&self.0)
+  }
+
+  fn reader(&self) -> Box<dyn Read + '_> {
+    Box::new(&self.0[..])
+  }
+
+  fn eq_payload(&self, other: &dyn Payload) -> bool {
+    match other.downcast_ref::<BytesPayload>() {
+      Some(other) => self.0 == other.0,
+      None => self.0 == other.as_bytes()
+    }
+  }
+}
+
+/// Extension (without the `x-` prefix) of a Request Body with the protobuf message type of the
+/// payload
+pub const PROTOBUF_MESSAGE_TYPE_EXTENSION: &str = "protobuf-message-type";
+
+/// Extension (without the `x-` prefix) of a Request Body with a reference to the protobuf
+/// descriptor (i.e. the `.proto` file or a descriptor set) for the payload
+pub const PROTOBUF_DESCRIPTOR_EXTENSION: &str = "protobuf-descriptor";
+
+/// Hint of the schema of a binary payload
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct SchemaHint {
+  /// Fully qualified message type (i.e. `pets.v1.Pet`)
+  pub message_type: Option<String>,
+  /// Reference to the descriptor that defines the message type
+  pub descriptor: Option<String>
+}
+
+impl SchemaHint {
+  /// Returns the schema hint for a Request Body, from the `x-protobuf-message-type` and
+  /// `x-protobuf-descriptor` extensions, or the `messageType` parameter of the content type (i.e.
+  /// `application/x-protobuf; messageType=pets.v1.Pet`). Returns `None` if there is no hint.
+  pub fn from_request_body(content_type: Option<&str>, extensions: &HashMap<String, AnyValue>) -> Option<Self> {
+    let message_type = extensions.get(PROTOBUF_MESSAGE_TYPE_EXTENSION)
+      .and_then(|value| value.as_str())
+      .map(|value| value.to_string())
+      .or_else(|| content_type.and_then(|content_type| {
+        parse_content_type(content_type).1.into_iter()
+          .find(|(name, _)| name == "messagetype")
+          .map(|(_, value)| value)
+      }));
+    let descriptor = extensions.get(PROTOBUF_DESCRIPTOR_EXTENSION)
+      .and_then(|value| value.as_str())
+      .map(|value| value.to_string());
+    if message_type.is_some() || descriptor.is_some() {
+      Some(SchemaHint { message_type, descriptor })
+    } else {
+      None
+    }
+  }
+
+  /// Returns the extensions (without the `x-` prefix) that record this hint
+  pub fn to_extensions(&self) -> Vec<(String, AnyValue)> {
+    let mut extensions = vec![];
+    if let Some(message_type) = &self.message_type {
+      extensions.push((PROTOBUF_MESSAGE_TYPE_EXTENSION.to_string(), AnyValue::String(message_type.clone())));
+    }
+    if let Some(descriptor) = &self.descriptor {
+      extensions.push((PROTOBUF_DESCRIPTOR_EXTENSION.to_string(), AnyValue::String(descriptor.clone())));
+    }
+    extensions
+  }
+}
+
+/// Binary protobuf message, with a hint of the schema of the message. This is written as a Base64
+/// encoded string, with the hint stored in the extensions of the Request Body (see
+/// [`SchemaHint::from_request_body`]).
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct ProtobufPayload {
+  /// Encoded message
+  pub bytes: Bytes,
+  /// Hint of the schema of the message
+  pub hint: SchemaHint
+}
+
+impl Payload for ProtobufPayload {
+  fn as_bytes(&self) -> Bytes {
+    self.bytes.clone()
+  }
+
+  /// Returns the Base64 encoded form of the message
+  fn as_string(&self) -> String {
+    base64::encode(&self.bytes)
+  }
+
+  fn reader(&self) -> Box<dyn Read + '_> {
+    Box::new(&self.bytes[..])
+  }
+
+  fn eq_payload(&self, other: &dyn Payload) -> bool {
+    match other.downcast_ref::<ProtobufPayload>() {
+      Some(other) => self == other,
+      None => self.bytes == other.as_bytes()
+    }
+  }
+}
+
+/// Converts binary payloads of a Request Body into a [`ProtobufPayload`] if the Request Body has a
+/// schema hint (see [`SchemaHint::from_request_body`]).
+#[cfg(any(feature = "json", feature = "yaml"))]
+pub(crate) fn with_schema_hint(
+  payload: Arc<dyn Payload + Send + Sync>,
+  content_type: Option<&str>,
+  extensions: &HashMap<String, AnyValue>
+) -> Arc<dyn Payload + Send + Sync> {
+  // Only look for a hint for binary payloads, as parsing the content type is not free
+  if let Some(bytes_payload) = payload.downcast_ref::<BytesPayload>() &&
+     let Some(hint) = SchemaHint::from_request_body(content_type, extensions) {
+    Arc::new(ProtobufPayload { bytes: bytes_payload.0.clone(), hint })
+  } else {
+    payload
+  }
+}
+
+/// XML payload, stored as both the original source and the parsed document so that the values can
+/// be accessed with XPath expressions. This is written as the original XML string, unless it has
+/// been modified.
+#[cfg(feature = "xml")]
+#[derive(Clone, Debug)]
+pub struct XmlPayload {
+  source: String,
+  document: XmlDocument
+}
+
+#[cfg(feature = "xml")]
+impl XmlPayload {
+  /// Parses the XML source into a payload
+  pub fn parse(source: &str) -> anyhow::Result<Self> {
+    Ok(XmlPayload {
+      source: source.to_string(),
+      document: parse_xml(source)?
+    })
+  }
+
+  /// The parsed XML document
+  pub fn document(&self) -> &XmlDocument {
+    &self.document
+  }
+
+  /// Returns the string value of the first node selected by the XPath expression
+  pub fn select(&self, xpath: &str) -> anyhow::Result<Option<String>> {
+    self.document.select_string(xpath)
+  }
+
+  /// Sets the value of all the nodes selected by the XPath expression, returning the number of
+  /// nodes updated. The XML source will be regenerated from the modified document.
+  pub fn set(&mut self, xpath: &str, value: &str) -> anyhow::Result<usize> {
+    let count = self.document.set_value(xpath, value)?;
+    if count > 0 {
+      self.source = self.document.to_xml_string();
+    }
+    Ok(count)
+  }
+
+  /// Updates the parsed document with the callback, and then regenerates the XML source
+  pub fn update<F, R>(&mut self, callback: F) -> R where F: FnOnce(&mut XmlDocument) -> R {
+    let result = callback(&mut self.document);
+    self.source = self.document.to_xml_string();
+    result
+  }
+}
+
+#[cfg(feature = "xml")]
+impl Payload for XmlPayload {
+  fn as_bytes(&self) -> Bytes {
+    Bytes::from(self.source.clone())
+  }
+
+  fn as_string(&self) -> String {
+    self.source.clone()
+  }
+
+  fn reader(&self) -> Box<dyn Read + '_> {
+    Box::new(self.source.as_bytes())
+  }
+
+  /// XML payloads are equal if the parsed root elements are equal (i.e. differences in the XML
+  /// declaration or any whitespace outside the root element are ignored)
+  fn eq_payload(&self, other: &dyn Payload) -> bool {
+    match other.downcast_ref::<XmlPayload>() {
+      Some(other) => self.document.root == other.document.root,
+      None => self.source.as_bytes() == other.as_bytes()
+    }
+  }
+}
+
+/// GraphQL request payload, written as the standard GraphQL over HTTP JSON body
+/// (`{"query": "...", "operationName": "...", "variables": {...}}`). The operation name and
+/// variables are omitted if they are not set.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct GraphQlPayload {
+  /// GraphQL query document
+  pub query: String,
+  /// Name of the operation to execute, if the query contains more than one
+  pub operation_name: Option<String>,
+  /// Values for the variables of the query
+  pub variables: Map<String, Value>
+}
+
+impl GraphQlPayload {
+  /// Creates a payload for the query, with no variables
+  pub fn new<S: Into<String>>(query: S) -> Self {
+    GraphQlPayload {
+      query: query.into(),
+      .. GraphQlPayload::default()
+    }
+  }
+
+  /// Creates a payload from a GraphQL request JSON body, which must be an object with a `query`
+  /// string, and optionally an `operationName` string and `variables` object.
+  pub fn from_json(json: &Value) -> anyhow::Result<Self> {
+    let map = json.as_object()
+      .ok_or_else(|| anyhow!("GraphQL payloads must be an object"))?;
+    let query = map.get("query")
+      .and_then(|query| query.as_str())
+      .ok_or_else(|| anyhow!("GraphQL payloads must have a query string"))?;
+    let operation_name = match map.get("operationName") {
+      Some(Value::String(name)) => Some(name.clone()),
+      None | Some(Value::Null) => None,
+      Some(_) => return Err(anyhow!("GraphQL operation name must be a string"))
+    };
+    let variables = match map.get("variables") {
+      Some(Value::Object(variables)) => variables.clone(),
+      None | Some(Value::Null) => Map::new(),
+      Some(_) => return Err(anyhow!("GraphQL variables must be an object"))
+    };
+    Ok(GraphQlPayload { query: query.to_string(), operation_name, variables })
+  }
+
+  /// Returns the GraphQL request JSON body
+  pub fn to_json(&self) -> Value {
+    let mut map = Map::new();
+    map.insert("query".to_string(), Value::String(self.query.clone()));
+    if let Some(operation_name) = &self.operation_name {
+      map.insert("operationName".to_string(), Value::String(operation_name.clone()));
+    }
+    if !self.variables.is_empty() {
+      map.insert("variables".to_string(), Value::Object(self.variables.clone()));
+    }
+    Value::Object(map)
+  }
+}
+
+impl Payload for GraphQlPayload {
+  fn as_bytes(&self) -> Bytes {
+    Bytes::from(self.as_string())
+  }
+
+  fn as_string(&self) -> String {
+    self.to_json().to_string()
+  }
+
+  fn as_json(&self) -> Option<Value> {
+    Some(self.to_json())
+  }
+
+  fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+    serde_json::to_writer(writer, &self.to_json()).map_err(io::Error::from)
+  }
+
+  fn eq_payload(&self, other: &dyn Payload) -> bool {
+    match other.downcast_ref::<GraphQlPayload>() {
+      Some(other) => self == other,
+      None => eq_structured_payload(self, other)
+    }
+  }
+}
+
+/// Form payload (`application/x-www-form-urlencoded`) authored as an object of field names to
+/// values. The order of the fields is preserved, and it is written back out as an object.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct FormPayload(pub IndexMap<String, AnyValue>);
+
+impl FormPayload {
+  /// Creates a form payload from the value, which must be an object with scalar values.
+  pub fn from_value(value: &AnyValue) -> anyhow::Result<Self> {
+    match value {
+      AnyValue::Object(map) => {
+        if let Some((key, _)) = map.iter().find(|(_, value)| matches!(value, AnyValue::Object(_) | AnyValue::Array(_))) {
+          Err(anyhow!("Form field '{}' must be a scalar value", key))
+        } else {
+          Ok(FormPayload(map.clone()))
+        }
+      }
+      _ => Err(anyhow!("Form payloads must be an object"))
+    }
+  }
+}
+
+impl Payload for FormPayload {
+  fn as_bytes(&self) -> Bytes {
+    Bytes::from(self.as_string())
+  }
+
+  /// Returns the fields encoded as `application/x-www-form-urlencoded`
+  fn as_string(&self) -> String {
+    self.0.iter()
+      .map(|(key, value)| {
+        format!("{}={}", form_encode(key), form_encode(&value.to_text().unwrap_or_default()))
+      })
+      .collect::<Vec<_>>()
+      .join("&")
+  }
+
+  #[cfg(feature = "json")]
+  fn as_json(&self) -> Option<Value> {
+    Some(Value::from(&AnyValue::Object(self.0.clone())))
+  }
+
+  fn eq_payload(&self, other: &dyn Payload) -> bool {
+    match other.downcast_ref::<FormPayload>() {
+      Some(other) => self.0 == other.0,
+      None => eq_structured_payload(self, other)
+    }
+  }
+}
+
+fn form_encode(value: &str) -> String {
+  let mut result = String::with_capacity(value.len());
+  for byte in value.bytes() {
+    match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'*' => result.push(byte as char),
+      b' ' => result.push('+'),
+      _ => result.push_str(&format!("%{:02X}", byte))
+    }
+  }
+  result
+}
+
+/// Location of an external payload
+#[derive(Clone, Debug, PartialEq)]
+pub enum PayloadLocation {
+  /// Path to a file
+  File(PathBuf),
+  /// URL to fetch the payload from (`file:` URLs, and `http:` and `https:` URLs with the
+  /// `http` feature)
+  Url(String)
+}
+
+impl Display for PayloadLocation {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      PayloadLocation::File(path) => write!(f, "{}", path.display()),
+      PayloadLocation::Url(url) => write!(f, "{}", url)
+    }
+  }
+}
+
+/// Payload that references a file or URL. The contents are only loaded when the bytes are
+/// requested (and are loaded again each time), so large payloads do not need to be embedded in the
+/// document or held in memory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExternalPayload {
+  /// Location of the payload
+  pub location: PayloadLocation,
+  /// Maximum size (in bytes) of the contents. Loading the payload fails if it is larger than this.
+  pub max_size: Option<usize>
+}
+
+impl ExternalPayload {
+  /// Payload loaded from a file
+  pub fn file<P: Into<PathBuf>>(path: P) -> Self {
+    ExternalPayload { location: PayloadLocation::File(path.into()), max_size: None }
+  }
+
+  /// Payload loaded from a URL
+  pub fn url<S: Into<String>>(url: S) -> Self {
+    ExternalPayload { location: PayloadLocation::Url(url.into()), max_size: None }
+  }
+
+  /// Sets the maximum size (in bytes) of the contents of the payload
+  pub fn with_max_size(mut self, max_size: Option<usize>) -> Self {
+    self.max_size = max_size;
+    self
+  }
+
+  /// Loads the contents of the payload. Fails if the contents are larger than the maximum size of
+  /// the payload (see [`ExternalPayload::with_max_size`]).
+  pub fn load(&self) -> anyhow::Result<Bytes> {
+    let bytes = match self.file_path() {
+      Some(path) => {
+        check_file_size(path, self.max_size)?;
+        fs::read(path)
+          .map(Bytes::from)
+          .with_context(|| format!("Failed to load payload from '{}'", path.display()))?
+      }
+      #[cfg(feature = "http")]
+      None => crate::http_config::get(&self.location.to_string(), &crate::http_config::HttpClientConfig::default())?,
+      #[cfg(not(feature = "http"))]
+      None => return Err(anyhow!("Loading the payload from '{}' requires the http feature", self.location))
+    };
+    check_size(bytes.len(), self.max_size)?;
+    Ok(bytes)
+  }
+
+  /// Opens a reader over the contents of the payload. Files are streamed from disk, while URLs are
+  /// loaded into memory first.
+  pub fn open(&self) -> anyhow::Result<Box<dyn Read>> {
+    match self.file_path() {
+      Some(path) => {
+        check_file_size(path, self.max_size)?;
+        let file = File::open(path)
+          .with_context(|| format!("Failed to load payload from '{}'", path.display()))?;
+        Ok(Box::new(file))
+      }
+      None => self.load().map(|bytes| Box::new(Cursor::new(bytes)) as Box<dyn Read>)
+    }
+  }
+
+  fn file_path(&self) -> Option<&Path> {
+    match &self.location {
+      PayloadLocation::File(path) => Some(path.as_path()),
+      PayloadLocation::Url(url) => url.strip_prefix("file://").map(Path::new)
+    }
+  }
+}
+
+fn check_file_size(path: &Path, max_size: Option<usize>) -> anyhow::Result<()> {
+  match fs::metadata(path) {
+    Ok(metadata) => check_size(metadata.len() as usize, max_size),
+    Err(_) => Ok(())
+  }
+}
+
+impl Payload for ExternalPayload {
+  /// Loads the contents of the payload. If the payload can not be loaded, empty bytes are returned,
+  /// so anything that needs to report the error (like [`RequestBody::render`](crate::v1_0::RequestBody::render))
+  /// should use [`ExternalPayload::load`] instead.
+  fn as_bytes(&self) -> Bytes {
+    self.load().unwrap_or_default()
+  }
+
+  /// Loads the contents of the payload as a String. If the contents are not valid UTF-8, they are
+  /// returned Base64 encoded.
+  fn as_string(&self) -> String {
+    let bytes = self.as_bytes();
+    match std::str::from_utf8(&bytes) {
+      Ok(s) => s.to_string(),
+      Err(_) => base64::encode(&bytes)
+    }
+  }
+
+  /// Streams the contents of the payload. If the payload can not be loaded, the reader will be
+  /// empty (use [`ExternalPayload::open`] to get the error).
+  fn reader(&self) -> Box<dyn Read + '_> {
+    self.open().unwrap_or_else(|_| Box::new(io::empty()))
+  }
+
+  /// Streams the contents of the payload to the writer, failing if the payload can not be loaded
+  fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+    let mut reader = self.open().map_err(io::Error::other)?;
+    io::copy(&mut reader, writer).map(|_| ())
+  }
+
+  /// External payloads referencing the same location are equal without loading the contents
+  fn eq_payload(&self, other: &dyn Payload) -> bool {
+    match other.downcast_ref::<ExternalPayload>() {
+      Some(other) if self.location == other.location => true,
+      _ => self.as_bytes() == other.as_bytes()
+    }
+  }
+}
+
+/// Options for loading Arazzo descriptions
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LoadOptions {
+  /// Maximum size (in bytes) of request body payloads. Loading fails if a request body has a larger
+  /// payload. `None` is no limit (the default). External payloads are not loaded, so are not
+  /// checked (see [`ExternalPayload::with_max_size`]).
+  pub max_payload_size: Option<usize>
+}
+
+impl LoadOptions {
+  /// Loads the description from a value (i.e. a JSON or YAML document), and checks it against the
+  /// options
+  pub fn load<T>(&self, value: T) -> anyhow::Result<ArazzoDescription>
+    where ArazzoDescription: TryFrom<T, Error = anyhow::Error> {
+    let description = ArazzoDescription::try_from(value)?;
+    self.check(&description)?;
+    Ok(description)
+  }
+
+  /// Checks the loaded description against the options
+  pub fn check(&self, description: &ArazzoDescription) -> anyhow::Result<()> {
+    if self.max_payload_size.is_none() {
+      return Ok(());
+    }
+    for workflow in &description.workflows {
+      for step in &workflow.steps {
+        if let Some(payload) = step.request_body.as_ref().and_then(|body| body.payload.as_ref()) {
+          check_payload_size(payload.as_ref(), self.max_payload_size)
+            .map_err(|err| anyhow!("Request body of step '{}' in workflow '{}': {}", step.step_id,
+              workflow.workflow_id, err))?;
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Returns the size of the raw bytes of the payload. This writes the payload (see
+/// [`Payload::write_to`]), but does not keep a copy of the bytes.
+pub fn payload_size(payload: &dyn Payload) -> usize {
+  let mut counter = ByteCounter(0);
+  let _ = payload.write_to(&mut counter);
+  counter.0
+}
+
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.0 += buf.len();
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+fn check_size(size: usize, max_size: Option<usize>) -> anyhow::Result<()> {
+  match max_size {
+    Some(max_size) if size > max_size => Err(anyhow!("Payload is {} bytes, which is larger than the maximum \
+      payload size of {} bytes", size, max_size)),
+    _ => Ok(())
+  }
+}
+
+/// Checks that the payload is not larger than the maximum size. External payloads are not loaded,
+/// as they are checked when they are loaded.
+pub fn check_payload_size(payload: &dyn Payload, max_size: Option<usize>) -> anyhow::Result<()> {
+  if max_size.is_none() || payload.downcast_ref::<ExternalPayload>().is_some() {
+    Ok(())
+  } else {
+    check_size(payload_size(payload), max_size)
+  }
+}
+
+/// If the content type is for XML documents (`application/xml`, `text/xml` or any `+xml` type).
+/// Any parameters of the content type are ignored.
+pub fn is_xml_content_type(content_type: &str) -> bool {
+  XML_CONTENT_TYPES.iter().any(|pattern| content_type_matches(pattern, content_type))
+}
+
+/// Content types (or patterns) for binary data
+pub(crate) const BINARY_CONTENT_TYPES: [&str; 18] = [
+  "application/octet-stream", "application/protobuf", "application/x-protobuf",
+  "application/vnd.google.protobuf", "application/grpc", "application/pdf", "application/zip",
+  "application/gzip", "application/x-gzip", "application/x-tar", "application/wasm",
+  "application/cbor", "application/msgpack", "application/x-msgpack",
+  "image/*", "audio/*", "video/*", "font/*"
+];
+
+/// Content type patterns for XML documents
+pub(crate) const XML_CONTENT_TYPES: [&str; 3] = ["application/xml", "text/xml", "*/*+xml"];
+
+/// If the content type is for binary data (like `application/octet-stream`, protobuf messages or
+/// images). Any parameters of the content type are ignored.
+pub fn is_binary_content_type(content_type: &str) -> bool {
+  !content_type_matches("image/svg+xml", content_type) &&
+    BINARY_CONTENT_TYPES.iter().any(|pattern| content_type_matches(pattern, content_type))
+}
+
+/// Creates the payload for a String value loaded from a document, using the constructor registered
+/// in the global [payload registry](crate::payload_registry) for the content type. For example,
+/// if the content type is for binary data and the value is Base64 encoded, the decoded bytes are
+/// returned as a [`BytesPayload`].
+pub fn string_payload(value: &str, content_type: Option<&str>) -> Arc<dyn Payload + Send + Sync> {
+  create_payload(&PayloadValue::String(value), content_type)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Read;
+  use std::path::PathBuf;
+
+  use bytes::Bytes;
+  use expectest::prelude::*;
+  use indexmap::indexmap;
+  use serde_json::json;
+
+  use crate::extensions::AnyValue;
+  use crate::payloads::{
+    check_payload_size,
+    is_binary_content_type,
+    is_xml_content_type,
+    payload_size,
+    BytesPayload,
+    EmptyPayload,
+    ExternalPayload,
+    FormPayload,
+    GraphQlPayload,
+    JsonPayload,
+    LoadOptions,
+    Payload,
+    StringPayload
+  };
+  #[cfg(feature = "xml")] use crate::payloads::{string_payload, XmlPayload};
+  #[cfg(feature = "yaml")] use yaml_rust2::Yaml;
+  #[cfg(feature = "yaml")] use crate::payloads::YamlPayload;
+
+  #[test]
+  fn bytes_payload() {
+    let payload = BytesPayload(Bytes::from_static(&[0, 1, 0xFF]));
+    expect!(payload.as_bytes()).to(be_equal_to(Bytes::from_static(&[0, 1, 0xFF])));
+    expect!(payload.as_string()).to(be_equal_to("AAH/"));
+    expect!(payload.as_json()).to(be_none());
+  }
+
+  #[test]
+  fn graphql_payload() {
+    let json = json!({
+      "query": "query Pet($id: ID!) { pet(id: $id) { name } }",
+      "operationName": "Pet",
+      "variables": { "id": "{$inputs.pet_id}" }
+    });
+    let payload = GraphQlPayload::from_json(&json).unwrap();
+    expect!(payload.operation_name.clone()).to(be_some().value("Pet"));
+    expect!(payload.as_json()).to(be_some().value(json.clone()));
+    expect!(payload.eq_payload(&JsonPayload(json))).to(be_true());
+    expect!(GraphQlPayload::new("{ pets { id } }").as_string()).to(be_equal_to("{\"query\":\"{ pets { id } }\"}"));
+    expect!(GraphQlPayload::from_json(&json!({ "variables": {} }))).to(be_err());
+    expect!(GraphQlPayload::from_json(&json!({ "query": "{ a }", "variables": [] }))).to(be_err());
+  }
+
+  #[test]
+  fn payload_readers() {
+    let payloads: Vec<Box<dyn Payload>> = vec![
+      Box::new(StringPayload("some text".to_string())),
+      Box::new(BytesPayload(Bytes::from_static(&[0, 1, 0xFF]))),
+      Box::new(JsonPayload(json!({ "a": [1, 2] }))),
+      Box::new(EmptyPayload)
+    ];
+    for payload in payloads {
+      let mut buffer = vec![];
+      payload.reader().read_to_end(&mut buffer).unwrap();
+      expect!(Bytes::from(buffer)).to(be_equal_to(payload.as_bytes()));
+      let mut buffer = vec![];
+      payload.write_to(&mut buffer).unwrap();
+      expect!(Bytes::from(buffer)).to(be_equal_to(payload.as_bytes()));
+      expect!(payload_size(payload.as_ref())).to(be_equal_to(payload.as_bytes().len()));
+    }
+  }
+
+  #[test]
+  fn payload_size_limits() {
+    let payload = JsonPayload(json!({ "a": [1, 2] }));
+    expect!(check_payload_size(&payload, None)).to(be_ok());
+    expect!(check_payload_size(&payload, Some(11))).to(be_ok());
+    expect!(check_payload_size(&payload, Some(10)).unwrap_err().to_string())
+      .to(be_equal_to("Payload is 11 bytes, which is larger than the maximum payload size of 10 bytes"));
+    expect!(check_payload_size(&ExternalPayload::file("/does/not/exist"), Some(1))).to(be_ok());
+
+    let path = std::env::temp_dir().join(format!("arazzo-payload-limit-{}.txt", std::process::id()));
+    std::fs::write(&path, "some text").unwrap();
+    expect!(ExternalPayload::file(&path).with_max_size(Some(9)).load()).to(be_ok());
+    expect!(ExternalPayload::file(&path).with_max_size(Some(8)).load().unwrap_err().to_string())
+      .to(be_equal_to("Payload is 9 bytes, which is larger than the maximum payload size of 8 bytes"));
+    expect!(ExternalPayload::file(&path).with_max_size(Some(8)).open().is_err()).to(be_true());
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  #[cfg(feature = "json")]
+  fn load_options_limit_payload_sizes() {
+    let document = json!({
+      "arazzo": "1.0.0",
+      "info": { "title": "Pets", "version": "1.0.0" },
+      "sourceDescriptions": [{ "name": "pets", "url": "pets.json" }],
+      "workflows": [{
+        "workflowId": "adopt",
+        "steps": [{
+          "stepId": "create",
+          "operationId": "createPet",
+          "requestBody": { "contentType": "application/json", "payload": { "a": [1, 2] } }
+        }]
+      }]
+    });
+    expect!(LoadOptions::default().load(&document)).to(be_ok());
+    expect!(LoadOptions { max_payload_size: Some(11) }.load(&document)).to(be_ok());
+    expect!(LoadOptions { max_payload_size: Some(10) }.load(&document).unwrap_err().to_string())
+      .to(be_equal_to("Request body of step 'create' in workflow 'adopt': Payload is 11 bytes, which is \
+        larger than the maximum payload size of 10 bytes"));
+  }
+
+  #[test]
+  fn binary_content_types() {
+    expect!(is_binary_content_type("application/octet-stream")).to(be_true());
+    expect!(is_binary_content_type("application/x-protobuf; messageType=Pet")).to(be_true());
+    expect!(is_binary_content_type("IMAGE/PNG")).to(be_true());
+    expect!(is_binary_content_type("image/svg+xml")).to(be_false());
+    expect!(is_binary_content_type("application/json")).to(be_false());
+    expect!(is_binary_content_type("text/plain")).to(be_false());
+  }
+
+  #[test]
+  #[cfg(feature = "yaml")]
+  fn yaml_payload() {
+    let yaml = yaml_rust2::YamlLoader::load_from_str("petId: 10\nname: Fido\ntags:\n  - dog").unwrap();
+    let payload = YamlPayload(yaml[0].clone());
+    expect!(payload.as_string()).to(be_equal_to("petId: 10\nname: Fido\ntags:\n  - dog"));
+    expect!(payload.as_json()).to(be_some().value(serde_json::json!({
+      "petId": 10, "name": "Fido", "tags": ["dog"]
+    })));
+    expect!(YamlPayload(Yaml::Integer(100)).as_string()).to(be_equal_to("100"));
+  }
+
+  #[test]
+  fn external_payload() {
+    let path = std::env::temp_dir().join(format!("arazzo-payload-{}.txt", std::process::id()));
+    std::fs::write(&path, "some text").unwrap();
+    let payload = ExternalPayload::file(&path);
+    expect!(payload.as_string()).to(be_equal_to("some text"));
+    let payload = ExternalPayload::url(format!("file://{}", path.display()));
+    expect!(payload.as_bytes()).to(be_equal_to(Bytes::from_static(b"some text")));
+    let mut text = String::new();
+    payload.open().unwrap().read_to_string(&mut text).unwrap();
+    expect!(text).to(be_equal_to("some text"));
+    std::fs::write(&path, [0xFF, 0]).unwrap();
+    expect!(payload.as_string()).to(be_equal_to("/wA="));
+    std::fs::remove_file(&path).unwrap();
+    expect!(payload.load()).to(be_err());
+    expect!(payload.as_bytes().is_empty()).to(be_true());
+    expect!(payload.write_to(&mut vec![])).to(be_err());
+
+    #[cfg(feature = "http")]
+    {
+      let url = crate::http_config::tests::serve_once("HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nPets");
+      expect!(ExternalPayload::url(url).as_string()).to(be_equal_to("Pets"));
+    }
+  }
+
+  #[test]
+  fn payload_equality() {
+    let json = JsonPayload(json!({ "a": 1, "b": [true, null] }));
+    expect!(json.eq_payload(&JsonPayload(json!({ "b": [true, null], "a": 1 })))).to(be_true());
+    expect!(json.eq_payload(&JsonPayload(json!({ "a": 2 })))).to(be_false());
+    expect!(json.eq_payload(&StringPayload(json.as_string()))).to(be_true());
+    expect!(StringPayload(json.as_string()).eq_payload(&json)).to(be_true());
+    expect!(json.eq_payload(&EmptyPayload)).to(be_false());
+
+    expect!(StringPayload("a".to_string()).eq_payload(&StringPayload("a".to_string()))).to(be_true());
+    expect!(StringPayload("a".to_string()).eq_payload(&StringPayload("b".to_string()))).to(be_false());
+    expect!(StringPayload("YQ==".to_string()).eq_payload(&BytesPayload(Bytes::from_static(b"a")))).to(be_false());
+    expect!(BytesPayload(Bytes::from_static(b"a")).eq_payload(&BytesPayload(Bytes::from_static(b"a")))).to(be_true());
+    expect!(EmptyPayload.eq_payload(&StringPayload(String::new()))).to(be_true());
+
+    let form = FormPayload(indexmap!{
+      "a".to_string() => AnyValue::Integer(1),
+      "b".to_string() => AnyValue::String("x".to_string())
+    });
+    let reordered = FormPayload(indexmap!{
+      "b".to_string() => AnyValue::String("x".to_string()),
+      "a".to_string() => AnyValue::Integer(1)
+    });
+    expect!(form.eq_payload(&reordered)).to(be_true());
+    expect!(form.eq_payload(&JsonPayload(json!({ "b": "x", "a": 1 })))).to(be_true());
+
+    let path = PathBuf::from("/does/not/exist");
+    expect!(ExternalPayload::file(&path).eq_payload(&ExternalPayload::file(&path))).to(be_true());
+    expect!(ExternalPayload::file(&path).eq_payload(&ExternalPayload::url("file:///other"))).to(be_true());
+  }
+
+  #[test]
+  fn form_payload() {
+    let value = AnyValue::Object(indexmap!{
+      "grant_type".to_string() => AnyValue::String("client credentials".to_string()),
+      "scope".to_string() => AnyValue::String("read&write".to_string()),
+      "count".to_string() => AnyValue::Integer(2),
+      "empty".to_string() => AnyValue::Null
+    });
+    let payload = FormPayload::from_value(&value).unwrap();
+    expect!(payload.as_string()).to(be_equal_to("grant_type=client+credentials&scope=read%26write&count=2&empty="));
+    expect!(FormPayload::from_value(&AnyValue::String("a=b".to_string()))).to(be_err());
+    expect!(FormPayload::from_value(&AnyValue::Object(indexmap!{
+      "a".to_string() => AnyValue::Array(vec![])
+    }))).to(be_err());
+  }
+
+  #[test]
+  fn xml_content_types() {
+    expect!(is_xml_content_type("application/xml")).to(be_true());
+    expect!(is_xml_content_type("text/xml; charset=UTF-8")).to(be_true());
+    expect!(is_xml_content_type("application/atom+xml")).to(be_true());
+    expect!(is_xml_content_type("application/json")).to(be_false());
+  }
+
+  #[test]
+  #[cfg(feature = "xml")]
+  fn xml_payload() {
+    let source = "<?xml version=\"1.0\"?>\n<pet id='1'>\n  <name>Fido</name>\n</pet>";
+    let mut payload = XmlPayload::parse(source).unwrap();
+    expect!(payload.as_string()).to(be_equal_to(source));
+    expect!(payload.select("/pet/name").unwrap()).to(be_some().value("Fido"));
+    expect!(payload.select("/pet/@id").unwrap()).to(be_some().value("1"));
+
+    expect!(payload.set("/pet/owner", "Jo").unwrap()).to(be_equal_to(0));
+    expect!(payload.as_string()).to(be_equal_to(source));
+    expect!(payload.set("/pet/name", "Tom").unwrap()).to(be_equal_to(1));
+    expect!(payload.as_string()).to(be_equal_to("<?xml version=\"1.0\"?>\n<pet id=\"1\">\n  <name>Tom</name>\n</pet>"));
+
+    expect!(XmlPayload::parse("<pet>")).to(be_err());
+    expect!(string_payload("<pet/>", Some("application/xml")).downcast_ref::<XmlPayload>()).to(be_some());
+    expect!(string_payload("<pet>", Some("application/xml")).downcast_ref::<StringPayload>()).to(be_some());
+    expect!(string_payload("<pet/>", Some("text/plain")).downcast_ref::<StringPayload>()).to(be_some());
+  }
 }
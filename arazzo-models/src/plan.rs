@@ -0,0 +1,246 @@
+//! Execution planning for the workflows of an Arazzo description. Workflows are ordered so that
+//! the workflows they depend on (with `dependsOn`) are executed first.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::v1_0::{ArazzoDescription, Step, Workflow};
+
+/// Step of a planned workflow
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedStep {
+  /// Step ID
+  pub step_id: String,
+  /// Operation ID, operation path or workflow ID that the step executes
+  pub target: String
+}
+
+/// Workflow in an execution plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedWorkflow {
+  /// Workflow ID
+  pub workflow_id: String,
+  /// IDs of the workflows in this description that must be completed first
+  pub depends_on: Vec<String>,
+  /// Dependencies on workflows from other Arazzo source descriptions
+  /// (`$sourceDescriptions.<name>.<workflowId>`), which are not part of the plan
+  pub external_depends_on: Vec<String>,
+  /// Steps of the workflow, in order
+  pub steps: Vec<PlannedStep>,
+  /// Names of the inputs that are required by the input schema of the workflow
+  pub required_inputs: Vec<String>
+}
+
+/// Ordered list of the workflows to execute, where each workflow comes after the workflows it
+/// depends on
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionPlan {
+  /// Workflows to execute, in order
+  pub workflows: Vec<PlannedWorkflow>
+}
+
+impl ExecutionPlan {
+  /// Returns the planned workflow with the ID
+  pub fn workflow(&self, workflow_id: &str) -> Option<&PlannedWorkflow> {
+    self.workflows.iter().find(|workflow| workflow.workflow_id == workflow_id)
+  }
+
+  /// IDs of the workflows, in the order they are to be executed
+  pub fn workflow_ids(&self) -> Vec<&str> {
+    self.workflows.iter().map(|workflow| workflow.workflow_id.as_str()).collect()
+  }
+}
+
+impl Display for ExecutionPlan {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    for (index, workflow) in self.workflows.iter().enumerate() {
+      write!(f, "{}. {}", index + 1, workflow.workflow_id)?;
+      let depends_on = workflow.depends_on.iter()
+        .chain(workflow.external_depends_on.iter())
+        .map(|id| id.as_str())
+        .collect::<Vec<_>>();
+      if !depends_on.is_empty() {
+        write!(f, " (depends on {})", depends_on.join(", "))?;
+      }
+      writeln!(f)?;
+      if !workflow.required_inputs.is_empty() {
+        writeln!(f, "   inputs: {}", workflow.required_inputs.join(", "))?;
+      }
+      for step in &workflow.steps {
+        writeln!(f, "   - {} -> {}", step.step_id, step.target)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Plans the execution of the workflows with the IDs (or all the workflows if no IDs are given),
+/// including any workflows they depend on. Returns an error if a workflow can not be found, or
+/// the dependencies form a cycle.
+pub fn plan(description: &ArazzoDescription, workflow_ids: &[&str]) -> anyhow::Result<ExecutionPlan> {
+  let workflows = description.workflows.iter()
+    .map(|workflow| (workflow.workflow_id.as_str(), workflow))
+    .collect::<HashMap<_, _>>();
+  let roots = if workflow_ids.is_empty() {
+    description.workflows.iter().map(|workflow| workflow.workflow_id.as_str()).collect()
+  } else {
+    workflow_ids.to_vec()
+  };
+
+  let mut planner = Planner { description, workflows, visiting: vec![], plan: ExecutionPlan::default() };
+  for workflow_id in roots {
+    planner.visit(workflow_id, None)?;
+  }
+  Ok(planner.plan)
+}
+
+struct Planner<'a> {
+  description: &'a ArazzoDescription,
+  workflows: HashMap<&'a str, &'a Workflow>,
+  visiting: Vec<&'a str>,
+  plan: ExecutionPlan
+}
+
+impl <'a> Planner<'a> {
+  fn visit(&mut self, workflow_id: &'a str, dependent: Option<&str>) -> anyhow::Result<()> {
+    if self.plan.workflow(workflow_id).is_some() {
+      return Ok(());
+    }
+    if let Some(position) = self.visiting.iter().position(|id| *id == workflow_id) {
+      let mut cycle = self.visiting[position..].to_vec();
+      cycle.push(workflow_id);
+      return Err(anyhow!("Workflows have a circular dependency: {} [4.6.4.1 Fixed Fields]", cycle.join(" -> ")));
+    }
+    let workflow = *self.workflows.get(workflow_id)
+      .ok_or_else(|| match dependent {
+        Some(dependent) => anyhow!("Workflow '{}' depends on '{}', which was not found [4.6.4.1 Fixed Fields]", dependent, workflow_id),
+        None => anyhow!("No workflow with ID '{}' was found", workflow_id)
+      })?;
+
+    self.visiting.push(workflow_id);
+    let (external, local): (Vec<_>, Vec<_>) = workflow.depends_on.iter()
+      .partition(|id| id.starts_with("$sourceDescriptions."));
+    for depends_on in &local {
+      self.visit(depends_on.as_str(), Some(workflow_id))?;
+    }
+    self.visiting.pop();
+
+    self.plan.workflows.push(PlannedWorkflow {
+      workflow_id: workflow_id.to_string(),
+      depends_on: local.into_iter().cloned().collect(),
+      external_depends_on: external.into_iter().cloned().collect(),
+      steps: workflow.steps.iter().map(planned_step).collect(),
+      required_inputs: required_inputs(self.description, &workflow.inputs)
+    });
+    Ok(())
+  }
+}
+
+fn planned_step(step: &Step) -> PlannedStep {
+  let target = step.operation_id.as_ref()
+    .or(step.operation_path.as_ref())
+    .or(step.workflow_id.as_ref())
+    .cloned()
+    .unwrap_or_default();
+  PlannedStep { step_id: step.step_id.clone(), target }
+}
+
+/// Returns the required properties of the input schema, following any references to the
+/// component inputs
+fn required_inputs(description: &ArazzoDescription, schema: &Value) -> Vec<String> {
+  let mut schema = schema;
+  let mut depth = 0;
+  while let Some(name) = schema.get("$ref")
+    .and_then(|reference| reference.as_str())
+    .and_then(|reference| reference.strip_prefix("#/components/inputs/")) {
+    match description.components.inputs.get(name) {
+      Some(input) if depth < 32 => schema = input,
+      _ => break
+    }
+    depth += 1;
+  }
+  schema.get("required")
+    .and_then(|required| required.as_array())
+    .map(|required| required.iter().filter_map(|name| name.as_str().map(|name| name.to_string())).collect())
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use serde_json::json;
+
+  use crate::plan::plan;
+  use crate::v1_0::{ArazzoDescription, Components, Step, Workflow};
+
+  fn workflow(workflow_id: &str, depends_on: &[&str]) -> Workflow {
+    Workflow {
+      workflow_id: workflow_id.to_string(),
+      depends_on: depends_on.iter().map(|id| id.to_string()).collect(),
+      steps: vec![Step {
+        step_id: format!("{}-step", workflow_id),
+        operation_id: Some(format!("{}Op", workflow_id)),
+        .. Step::default()
+      }],
+      .. Workflow::default()
+    }
+  }
+
+  #[test]
+  fn orders_the_workflows_by_their_dependencies() {
+    let description = ArazzoDescription {
+      workflows: vec![
+        Workflow {
+          inputs: json!({ "$ref": "#/components/inputs/order" }),
+          .. workflow("order", &["login", "cart"])
+        },
+        workflow("cart", &["login", "$sourceDescriptions.shop.browse"]),
+        Workflow {
+          inputs: json!({ "type": "object", "required": ["username", "password"] }),
+          .. workflow("login", &[])
+        },
+        workflow("logout", &[])
+      ],
+      components: Components {
+        inputs: hashmap!{ "order".to_string() => json!({ "type": "object", "required": ["item"] }) },
+        .. Components::default()
+      },
+      .. ArazzoDescription::default()
+    };
+
+    let execution_plan = plan(&description, &["order"]).unwrap();
+    expect!(execution_plan.workflow_ids()).to(be_equal_to(vec!["login", "cart", "order"]));
+    let cart = execution_plan.workflow("cart").unwrap();
+    expect!(cart.depends_on.clone()).to(be_equal_to(vec!["login".to_string()]));
+    expect!(cart.external_depends_on.clone()).to(be_equal_to(vec!["$sourceDescriptions.shop.browse".to_string()]));
+    expect!(execution_plan.workflow("order").unwrap().required_inputs.clone()).to(be_equal_to(vec!["item".to_string()]));
+    expect!(execution_plan.to_string()).to(be_equal_to(
+      "1. login\n   inputs: username, password\n   - login-step -> loginOp\n\
+       2. cart (depends on login, $sourceDescriptions.shop.browse)\n   - cart-step -> cartOp\n\
+       3. order (depends on login, cart)\n   inputs: item\n   - order-step -> orderOp\n"));
+
+    expect!(plan(&description, &[]).unwrap().workflow_ids()).to(be_equal_to(vec!["login", "cart", "order", "logout"]));
+    expect!(plan(&description, &["other"]).unwrap_err().to_string()).to(be_equal_to("No workflow with ID 'other' was found"));
+  }
+
+  #[test]
+  fn returns_an_error_if_the_dependencies_form_a_cycle() {
+    let description = ArazzoDescription {
+      workflows: vec![workflow("one", &["two"]), workflow("two", &["three"]), workflow("three", &["one"])],
+      .. ArazzoDescription::default()
+    };
+    expect!(plan(&description, &["one"]).unwrap_err().to_string())
+      .to(be_equal_to("Workflows have a circular dependency: one -> two -> three -> one [4.6.4.1 Fixed Fields]"));
+
+    let description = ArazzoDescription {
+      workflows: vec![workflow("one", &["missing"])],
+      .. ArazzoDescription::default()
+    };
+    expect!(plan(&description, &[]).unwrap_err().to_string())
+      .to(be_equal_to("Workflow 'one' depends on 'missing', which was not found [4.6.4.1 Fixed Fields]"));
+  }
+}
@@ -0,0 +1,249 @@
+//! Prints [execution plans](crate::plan::ExecutionPlan) as an indented tree for display in a
+//! terminal, with the workflows in the order they are executed, their dependencies and required
+//! inputs, and the steps of each workflow. With the `execute` feature, the operations and
+//! requests resolved by a [dry-run](crate::executor::Executor::dry_run) can be added to the steps.
+
+use std::fmt::Write;
+
+#[cfg(feature = "execute")] use crate::dry_run::{DryRunReport, DryRunStep, DryRunWorkflow};
+use crate::plan::{ExecutionPlan, PlannedWorkflow};
+use crate::tree::{write_tree, TreeNode as Node};
+
+/// Options for printing execution plans
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlanPrinter {
+  /// If ANSI colours are used (for terminals that support them)
+  pub color: bool,
+  /// If the tree is drawn with ASCII characters instead of box drawing characters
+  pub ascii: bool
+}
+
+impl PlanPrinter {
+  /// Creates a printer that uses ANSI colours
+  pub fn colored() -> Self {
+    PlanPrinter { color: true, .. PlanPrinter::default() }
+  }
+
+  /// Prints the execution plan
+  pub fn print(&self, plan: &ExecutionPlan) -> String {
+    let steps = plan.workflows.iter().map(|workflow| workflow.steps.len()).sum::<usize>();
+    let nodes = plan.workflows.iter()
+      .map(|workflow| {
+        let steps = workflow.steps.iter()
+          .map(|step| Node::new(format!("{} {} {}", step.step_id, self.arrow(), step.target)))
+          .collect();
+        self.workflow_node(workflow, steps)
+      })
+      .collect::<Vec<_>>();
+    self.render(&nodes, &format!("{} workflow(s), {} step(s)", plan.workflows.len(), steps))
+  }
+
+  /// Prints the execution plan with the operations and requests of the dry-run of the plan. Steps
+  /// that execute another workflow have the steps of that workflow nested under them.
+  #[cfg(feature = "execute")]
+  pub fn print_dry_run(&self, plan: &ExecutionPlan, report: &DryRunReport) -> String {
+    let mut steps = 0;
+    let mut errors = 0;
+    let nodes = plan.workflows.iter()
+      .map(|workflow| {
+        let steps = match report.workflow(&workflow.workflow_id) {
+          Some(dry_run) => self.dry_run_steps(dry_run, &mut steps, &mut errors),
+          None => vec![Node::new(self.paint("not in the dry-run", "33"))]
+        };
+        self.workflow_node(workflow, steps)
+      })
+      .collect::<Vec<_>>();
+    let mut summary = format!("{} workflow(s), {} step(s)", plan.workflows.len(), steps);
+    if errors > 0 {
+      summary.push_str(&self.paint(&format!(", {} error(s)", errors), "31"));
+    }
+    self.render(&nodes, &summary)
+  }
+
+  #[cfg(feature = "execute")]
+  fn dry_run_steps(&self, workflow: &DryRunWorkflow, steps: &mut usize, errors: &mut usize) -> Vec<Node> {
+    workflow.steps.iter()
+      .map(|step| {
+        *steps += 1;
+        self.dry_run_step(step, steps, errors)
+      })
+      .collect()
+  }
+
+  #[cfg(feature = "execute")]
+  fn dry_run_step(&self, step: &DryRunStep, steps: &mut usize, errors: &mut usize) -> Node {
+    let mut children = vec![];
+    let label = if let Some(request) = &step.request {
+      format!("{} {} {} {}", step.step_id, self.arrow(), self.paint(&request.method, "1"), request.url)
+    } else if let Some(workflow) = &step.workflow {
+      format!("{} {} workflow {}", step.step_id, self.arrow(), self.paint(&workflow.workflow_id, "1"))
+    } else {
+      step.step_id.clone()
+    };
+    if let Some(operation) = &step.operation {
+      let name = operation.operation_id.as_ref().unwrap_or(&operation.path);
+      children.push(Node::new(format!("operation: {}.{} ({} {})", operation.source_name, name, operation.method,
+        operation.path)));
+    }
+    if !step.placeholders.is_empty() {
+      children.push(Node::new(self.paint(&format!("known after the run: {}", step.placeholders.join(", ")), "33")));
+    }
+    if let Some(error) = &step.error {
+      *errors += 1;
+      children.push(Node::new(self.paint(&format!("error: {}", error), "31")));
+    }
+    if let Some(workflow) = &step.workflow {
+      children.extend(self.dry_run_steps(workflow, steps, errors));
+    }
+    Node::new(label).with_children(children)
+  }
+
+  fn workflow_node(&self, workflow: &PlannedWorkflow, steps: Vec<Node>) -> Node {
+    let mut children = vec![];
+    if !workflow.depends_on.is_empty() {
+      children.push(Node::new(format!("after: {}", workflow.depends_on.join(", "))));
+    }
+    if !workflow.external_depends_on.is_empty() {
+      children.push(Node::new(format!("external: {}", workflow.external_depends_on.join(", "))));
+    }
+    if !workflow.required_inputs.is_empty() {
+      children.push(Node::new(format!("inputs: {}", workflow.required_inputs.join(", "))));
+    }
+    children.push(Node::new("steps").with_children(steps));
+    Node::new(self.paint(&workflow.workflow_id, "1")).with_children(children)
+  }
+
+  fn render(&self, workflows: &[Node], summary: &str) -> String {
+    let mut output = String::new();
+    for (index, workflow) in workflows.iter().enumerate() {
+      let _ = writeln!(output, "{}. {}", index + 1, workflow.label);
+      let indent = " ".repeat(format!("{}. ", index + 1).len());
+      write_tree(&mut output, &workflow.children, &indent, self.ascii);
+    }
+    let _ = writeln!(output, "\nPlan: {}", summary);
+    output
+  }
+
+  fn arrow(&self) -> &'static str {
+    if self.ascii { "->" } else { "→" }
+  }
+
+  /// Wraps the text in the ANSI code, if colours are enabled
+  fn paint(&self, text: &str, code: &str) -> String {
+    if self.color {
+      format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+      text.to_string()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  #[cfg(feature = "execute")] use crate::dry_run::{DryRunReport, DryRunStep, DryRunWorkflow};
+  #[cfg(feature = "execute")] use crate::execution_context::StepRequest;
+  #[cfg(feature = "execute")] use crate::operations::Operation;
+  use crate::plan::{ExecutionPlan, PlannedStep, PlannedWorkflow};
+  use crate::plan_printer::PlanPrinter;
+
+  fn execution_plan() -> ExecutionPlan {
+    ExecutionPlan {
+      workflows: vec![
+        PlannedWorkflow {
+          workflow_id: "login".to_string(),
+          depends_on: vec![],
+          external_depends_on: vec![],
+          steps: vec![PlannedStep { step_id: "token".to_string(), target: "createToken".to_string() }],
+          required_inputs: vec!["username".to_string(), "password".to_string()]
+        },
+        PlannedWorkflow {
+          workflow_id: "order".to_string(),
+          depends_on: vec!["login".to_string()],
+          external_depends_on: vec!["$sourceDescriptions.shop.browse".to_string()],
+          steps: vec![
+            PlannedStep { step_id: "cart".to_string(), target: "addToCart".to_string() },
+            PlannedStep { step_id: "checkout".to_string(), target: "/checkout".to_string() }
+          ],
+          required_inputs: vec![]
+        }
+      ]
+    }
+  }
+
+  #[test]
+  fn prints_the_plan_as_a_tree() {
+    expect!(PlanPrinter::default().print(&execution_plan())).to(be_equal_to(
+"1. login
+   ├── inputs: username, password
+   └── steps
+       └── token → createToken
+2. order
+   ├── after: login
+   ├── external: $sourceDescriptions.shop.browse
+   └── steps
+       ├── cart → addToCart
+       └── checkout → /checkout
+
+Plan: 2 workflow(s), 3 step(s)
+".to_string()));
+
+    let printer = PlanPrinter { ascii: true, .. PlanPrinter::colored() };
+    let output = printer.print(&execution_plan());
+    expect!(output.starts_with("1. \x1b[1mlogin\x1b[0m\n   |-- inputs: username, password\n   `-- steps\n       `-- token -> createToken\n"))
+      .to(be_true());
+  }
+
+  #[test]
+  #[cfg(feature = "execute")]
+  fn prints_the_dry_run_of_the_plan() {
+    let report = DryRunReport {
+      workflows: vec![
+        DryRunWorkflow {
+          workflow_id: "login".to_string(),
+          steps: vec![DryRunStep {
+            operation: Some(Operation {
+              source_name: "auth".to_string(),
+              operation_id: Some("createToken".to_string()),
+              method: "POST".to_string(),
+              path: "/tokens".to_string(),
+              server_url: None
+            }),
+            request: Some(StepRequest {
+              method: "POST".to_string(),
+              url: "https://auth.example.com/tokens".to_string(),
+              headers: vec![],
+              body: None
+            }),
+            .. DryRunStep::new("token")
+          }]
+        },
+        DryRunWorkflow {
+          workflow_id: "order".to_string(),
+          steps: vec![
+            DryRunStep { placeholders: vec!["$steps.token.outputs.token".to_string()], .. DryRunStep::new("cart") },
+            DryRunStep { error: Some("Operation '/checkout' was not found".to_string()), .. DryRunStep::new("checkout") }
+          ]
+        }
+      ]
+    };
+    expect!(PlanPrinter::default().print_dry_run(&execution_plan(), &report)).to(be_equal_to(
+"1. login
+   ├── inputs: username, password
+   └── steps
+       └── token → POST https://auth.example.com/tokens
+           └── operation: auth.createToken (POST /tokens)
+2. order
+   ├── after: login
+   ├── external: $sourceDescriptions.shop.browse
+   └── steps
+       ├── cart
+       │   └── known after the run: $steps.token.outputs.token
+       └── checkout
+           └── error: Operation '/checkout' was not found
+
+Plan: 2 workflow(s), 3 step(s), 1 error(s)
+".to_string()));
+  }
+}
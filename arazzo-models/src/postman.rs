@@ -0,0 +1,517 @@
+//! Exports workflows as [Postman](https://www.postman.com) collections (v2.1), so the workflows
+//! can be run from Postman. Each step is a request in the collection, and steps that execute
+//! another workflow of the description are a folder with the requests of that workflow.
+//!
+//! The runtime expressions used in the steps are converted to Postman variables, with the `$`
+//! removed (i.e. `$inputs.petId` is `{{inputs.petId}}` and `$steps.find.outputs.id` is
+//! `{{steps.find.outputs.id}}`). The base URL of each source description is the
+//! `baseUrl.<source name>` variable. The collection has a variable for each input of the workflow
+//! (with the default from the inputs schema) and each variable used by the requests.
+//!
+//! The test script of each request approximates the success criteria of the step and sets the
+//! variables for the outputs of the step. Criteria and outputs that can not be translated to
+//! JavaScript are added as comments. Success and failure actions are not translated, as the
+//! requests run in the order of the steps.
+
+use anyhow::{anyhow, Context};
+use indexmap::IndexMap;
+use serde_json::{json, Value};
+
+use crate::docs::{input_properties, step_parameters};
+use crate::either::Either;
+use crate::extensions::AnyValue;
+use crate::operations::{Operation, OperationResolver};
+use crate::v1_0::{ArazzoDescription, Criterion, ParameterObject, RequestBody, Step, Workflow};
+
+/// Schema of Postman collections
+pub const POSTMAN_SCHEMA: &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+
+/// Creates a Postman collection for the workflow, with a request for each step. The operations of
+/// the steps are resolved with the resolver.
+pub fn postman_collection(
+  description: &ArazzoDescription,
+  workflow_id: &str,
+  resolver: &OperationResolver
+) -> anyhow::Result<Value> {
+  let workflow = find_workflow(description, workflow_id)?;
+
+  let mut variables = IndexMap::new();
+  for property in input_properties(description, workflow) {
+    if property.schema_type != "object" {
+      variables.insert(format!("inputs.{}", property.path), property.default.unwrap_or_default());
+    }
+  }
+
+  let mut stack = vec![workflow.workflow_id.clone()];
+  let items = workflow_items(description, workflow, resolver, &mut variables, &mut stack)?;
+
+  let mut info = json!({
+    "name": format!("{} - {}", description.info.title, workflow.workflow_id),
+    "schema": POSTMAN_SCHEMA
+  });
+  if let Some(text) = workflow.description.as_ref().or(workflow.summary.as_ref()) {
+    info["description"] = json!(text);
+  }
+
+  Ok(json!({
+    "info": info,
+    "item": items,
+    "variable": variables.iter()
+      .map(|(key, value)| json!({ "key": key, "value": value }))
+      .collect::<Vec<_>>()
+  }))
+}
+
+fn find_workflow<'a>(description: &'a ArazzoDescription, workflow_id: &str) -> anyhow::Result<&'a Workflow> {
+  description.workflows.iter()
+    .find(|workflow| workflow.workflow_id == workflow_id)
+    .ok_or_else(|| anyhow!("No workflow with ID '{}' was found", workflow_id))
+}
+
+fn workflow_items(
+  description: &ArazzoDescription,
+  workflow: &Workflow,
+  resolver: &OperationResolver,
+  variables: &mut IndexMap<String, String>,
+  stack: &mut Vec<String>
+) -> anyhow::Result<Vec<Value>> {
+  let mut items = vec![];
+  for step in &workflow.steps {
+    let parameters = step_parameters(description, workflow, step)?;
+    let item = if let Some(workflow_id) = &step.workflow_id {
+      workflow_folder(description, step, workflow_id, parameters, resolver, variables, stack)?
+    } else {
+      let operation = resolver.resolve_step(step)
+        .with_context(|| format!("Could not resolve the operation of step '{}'", step.step_id))?;
+      step_item(step, &operation, parameters, variables)
+    };
+    items.push(item);
+  }
+  Ok(items)
+}
+
+/// Folder with the requests of the workflow a step executes. The pre-request script of the folder
+/// sets the inputs of the workflow from the parameters of the step.
+fn workflow_folder(
+  description: &ArazzoDescription,
+  step: &Step,
+  workflow_id: &str,
+  parameters: Vec<(ParameterObject, String)>,
+  resolver: &OperationResolver,
+  variables: &mut IndexMap<String, String>,
+  stack: &mut Vec<String>
+) -> anyhow::Result<Value> {
+  let mut folder = json!({ "name": step.step_id });
+  let target = description.workflows.iter().find(|workflow| workflow.workflow_id == *workflow_id);
+  match target {
+    Some(target) if !stack.contains(&target.workflow_id) => {
+      stack.push(target.workflow_id.clone());
+      folder["item"] = json!(workflow_items(description, target, resolver, variables, stack)?);
+      stack.pop();
+    }
+    Some(_) => {
+      folder["item"] = json!([]);
+      folder["description"] = json!(format!("Workflow '{}' calls itself, and is not expanded again", workflow_id));
+    }
+    None => {
+      folder["item"] = json!([]);
+      folder["description"] = json!(format!("Workflow '{}' is not in this description, and is not exported", workflow_id));
+    }
+  }
+
+  let script = parameters.iter()
+    .map(|(parameter, value)| format!("pm.collectionVariables.set({}, pm.variables.replaceIn({}));",
+      js_string(&format!("inputs.{}", parameter.name)), js_string(&postman_template(value, variables))))
+    .collect::<Vec<_>>();
+  if !script.is_empty() {
+    folder["event"] = json!([script_event("prerequest", script)]);
+  }
+  Ok(folder)
+}
+
+fn step_item(
+  step: &Step,
+  operation: &Operation,
+  parameters: Vec<(ParameterObject, String)>,
+  variables: &mut IndexMap<String, String>
+) -> Value {
+  let base_url = format!("baseUrl.{}", operation.source_name);
+  variables.entry(base_url.clone()).or_insert_with(|| operation.server_url.clone().unwrap_or_default());
+
+  let mut path = operation.path.clone();
+  let mut query = vec![];
+  let mut headers = vec![];
+  let mut cookies = vec![];
+  for (parameter, value) in &parameters {
+    let value = postman_template(value, variables);
+    match parameter.r#in.as_deref() {
+      Some("path") => path = path.replace(&format!("{{{}}}", parameter.name), &value),
+      Some("query") => query.push(json!({ "key": parameter.name, "value": value })),
+      Some("header") => headers.push(json!({ "key": parameter.name, "value": value })),
+      Some("cookie") => cookies.push(format!("{}={}", parameter.name, value)),
+      _ => {}
+    }
+  }
+  if !cookies.is_empty() {
+    headers.push(json!({ "key": "Cookie", "value": cookies.join("; ") }));
+  }
+
+  let mut raw = format!("{{{{{}}}}}{}", base_url, path);
+  if !query.is_empty() {
+    let query_string = query.iter()
+      .map(|param| format!("{}={}", param["key"].as_str().unwrap_or_default(), param["value"].as_str().unwrap_or_default()))
+      .collect::<Vec<_>>();
+    raw.push('?');
+    raw.push_str(&query_string.join("&"));
+  }
+  let mut url = json!({
+    "raw": raw,
+    "host": [format!("{{{{{}}}}}", base_url)],
+    "path": path.split('/').filter(|segment| !segment.is_empty()).collect::<Vec<_>>()
+  });
+  if !query.is_empty() {
+    url["query"] = json!(query);
+  }
+
+  let mut request = json!({ "method": operation.method.to_uppercase(), "url": url });
+  let mut notes = vec![];
+  if let Some(body) = &step.request_body {
+    if let Some(content_type) = body.effective_content_type() {
+      headers.push(json!({ "key": "Content-Type", "value": content_type }));
+    }
+    request["body"] = request_body(body, variables, &mut notes);
+  }
+  request["header"] = json!(headers);
+
+  let mut item = json!({ "name": step.step_id, "request": request });
+  if let Some(description) = &step.description {
+    notes.insert(0, description.clone());
+  }
+  if !notes.is_empty() {
+    item["description"] = json!(notes.join("\n\n"));
+  }
+
+  let mut events = vec![];
+  let pre_request = placeholders(&item["request"].to_string()).iter()
+    .filter(|name| **name != base_url)
+    .map(|name| format!("if (!pm.variables.has({})) {{ console.warn({}); }}", js_string(name),
+      js_string(&format!("The variable '{}' is not set", name))))
+    .collect::<Vec<_>>();
+  if !pre_request.is_empty() {
+    events.push(script_event("prerequest", pre_request));
+  }
+  let test = test_script(step);
+  if !test.is_empty() {
+    events.push(script_event("test", test));
+  }
+  if !events.is_empty() {
+    item["event"] = json!(events);
+  }
+  item
+}
+
+/// Raw body of the request. Replacements in JSON payloads are applied with the Postman variables,
+/// other replacements are added to the notes of the request.
+fn request_body(body: &RequestBody, variables: &mut IndexMap<String, String>, notes: &mut Vec<String>) -> Value {
+  let content_type = body.effective_content_type().unwrap_or_default();
+  let mut json_payload = body.payload.as_ref().and_then(|payload| payload.as_json());
+  for replacement in &body.replacements {
+    let value = match &replacement.value {
+      Either::First(AnyValue::String(value)) => Value::String(postman_template(value, variables)),
+      Either::First(value) => value.to_json(),
+      Either::Second(expression) => Value::String(postman_template(expression, variables))
+    };
+    match json_payload.as_mut().and_then(|payload| payload.pointer_mut(&replacement.target)) {
+      Some(target) => *target = value,
+      None => notes.push(format!("Replace '{}' in the body with {}", replacement.target, value))
+    }
+  }
+
+  let raw = match json_payload {
+    Some(payload) => serde_json::to_string_pretty(&payload).unwrap_or_default(),
+    None => body.payload.as_ref().map(|payload| payload.as_string()).unwrap_or_default()
+  };
+  let language = if content_type.contains("json") {
+    "json"
+  } else if content_type.contains("xml") {
+    "xml"
+  } else {
+    "text"
+  };
+  json!({
+    "mode": "raw",
+    "raw": postman_template(&raw, variables),
+    "options": { "raw": { "language": language } }
+  })
+}
+
+/// Test script with the success criteria and outputs of the step
+fn test_script(step: &Step) -> Vec<String> {
+  let mut script = vec![];
+  for criterion in &step.success_criteria {
+    script.extend(criterion_test(criterion));
+  }
+  for (name, expression) in &step.outputs {
+    let variable = js_string(&format!("steps.{}.outputs.{}", step.step_id, name));
+    match expression_js(expression) {
+      Some(js) => script.push(format!("pm.collectionVariables.set({}, {});", variable, js)),
+      None => script.push(format!("// Output '{}' is not set: {}", name, expression))
+    }
+  }
+  script
+}
+
+fn criterion_test(criterion: &Criterion) -> Vec<String> {
+  let criterion_type = match &criterion.r#type {
+    Some(Either::First(criterion_type)) => criterion_type.as_str(),
+    Some(Either::Second(expression_type)) => expression_type.r#type.as_str(),
+    None => "simple"
+  };
+  let assertion = match (criterion_type, &criterion.context) {
+    ("simple", None) => condition_js(&criterion.condition)
+      .map(|js| format!("pm.expect({}).to.be.true;", js)),
+    ("regex", Some(context)) => expression_js(context)
+      .map(|js| format!("pm.expect(String({})).to.match(new RegExp({}));", js, js_string(&criterion.condition))),
+    _ => None
+  };
+  match assertion {
+    Some(assertion) => vec![
+      format!("pm.test({}, function () {{", js_string(&criterion.condition)),
+      format!("  {}", assertion),
+      "});".to_string()
+    ],
+    None => vec![format!("// The {} criterion is not translated: {}", criterion_type, criterion.condition)]
+  }
+}
+
+/// Translates a simple condition to JavaScript, replacing the runtime expressions with the
+/// values from Postman. Returns `None` if an expression can not be translated.
+fn condition_js(condition: &str) -> Option<String> {
+  let mut js = String::new();
+  let mut chars = condition.chars().peekable();
+  while let Some(ch) = chars.next() {
+    match ch {
+      '\'' => {
+        js.push(ch);
+        for ch in chars.by_ref() {
+          js.push(ch);
+          if ch == '\'' {
+            break;
+          }
+        }
+      }
+      '$' => {
+        let mut expression = String::from("$");
+        while let Some(ch) = chars.peek() {
+          if ch.is_whitespace() || "=!<>&|()".contains(*ch) {
+            break;
+          }
+          expression.push(*ch);
+          chars.next();
+        }
+        js.push_str(&expression_js(&expression)?);
+      }
+      _ => js.push(ch)
+    }
+  }
+  Some(format!("({})", js.trim()))
+}
+
+/// JavaScript to get the value of the runtime expression in a Postman script
+fn expression_js(expression: &str) -> Option<String> {
+  let expression = expression.trim();
+  match expression {
+    "$statusCode" => Some("pm.response.code".to_string()),
+    "$url" => Some("pm.request.url.toString()".to_string()),
+    "$method" => Some("pm.request.method".to_string()),
+    "$response.body" => Some("pm.response.json()".to_string()),
+    _ => if let Some(pointer) = expression.strip_prefix("$response.body#") {
+      let segments = pointer.split('/')
+        .skip(1)
+        .map(|segment| format!("[{}]", js_string(&segment.replace("~1", "/").replace("~0", "~"))))
+        .collect::<String>();
+      Some(format!("pm.response.json(){}", segments))
+    } else if let Some(name) = expression.strip_prefix("$response.header.") {
+      Some(format!("pm.response.headers.get({})", js_string(name)))
+    } else if expression.starts_with("$inputs.") || expression.starts_with("$steps.") {
+      Some(format!("pm.variables.get({})", js_string(&expression[1..])))
+    } else {
+      None
+    }
+  }
+}
+
+/// Converts the runtime expression, or the expressions embedded in the text, to Postman
+/// variables, adding them to the variables of the collection
+fn postman_template(text: &str, variables: &mut IndexMap<String, String>) -> String {
+  if text.trim().starts_with('$') {
+    let name = text.trim()[1..].to_string();
+    let template = format!("{{{{{}}}}}", name);
+    variables.entry(name).or_default();
+    return template;
+  }
+
+  let mut result = String::new();
+  let mut rest = text;
+  while let Some(start) = rest.find("{$") {
+    let Some(end) = rest[start..].find('}') else {
+      break;
+    };
+    let name = &rest[start + 2..start + end];
+    result.push_str(&rest[..start]);
+    result.push_str(&format!("{{{{{}}}}}", name));
+    variables.entry(name.to_string()).or_default();
+    rest = &rest[start + end + 1..];
+  }
+  result.push_str(rest);
+  result
+}
+
+/// Names of the Postman variables used in the text, in order
+fn placeholders(text: &str) -> Vec<String> {
+  let mut names: Vec<String> = vec![];
+  let mut rest = text;
+  while let Some(start) = rest.find("{{") {
+    let Some(end) = rest[start..].find("}}") else {
+      break;
+    };
+    let name = rest[start + 2..start + end].to_string();
+    if !names.contains(&name) {
+      names.push(name);
+    }
+    rest = &rest[start + end + 2..];
+  }
+  names
+}
+
+fn script_event(listen: &str, exec: Vec<String>) -> Value {
+  json!({
+    "listen": listen,
+    "script": { "type": "text/javascript", "exec": exec }
+  })
+}
+
+fn js_string(text: &str) -> String {
+  Value::String(text.to_string()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::arazzo;
+  use crate::either::Either;
+  use crate::operations::{OpenApiSource, OperationResolver};
+  use crate::payloads::JsonPayload;
+  use crate::postman::{condition_js, postman_collection, POSTMAN_SCHEMA};
+  use crate::v1_0::{ArazzoDescription, PayloadReplacement, RequestBody};
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      info("Pet Store", "");
+      workflow("adopt") {
+        inputs: json!({
+          "type": "object",
+          "properties": {
+            "petId": { "type": "string", "default": "1" },
+            "name": { "type": "string" }
+          }
+        });
+        step("find") {
+          operation_id: "getPet";
+          parameter("petId", "path", "$inputs.petId");
+          parameter("include", "query", "owner");
+          success_criterion: "$statusCode == 200";
+          typed_success_criterion("jsonpath", "$response.body", "$.owner");
+          output("id", "$response.body#/id");
+        }
+        step("adopt") {
+          operation_id: "adoptPet";
+          request_body: RequestBody {
+            content_type: None,
+            payload: Some(Arc::new(JsonPayload(json!({ "pet": null, "name": "{$inputs.name}" })))),
+            replacements: vec![PayloadReplacement {
+              target: "/pet".to_string(),
+              value: Either::Second("$steps.find.outputs.id".to_string()),
+              extensions: Default::default()
+            }],
+            extensions: Default::default()
+          };
+        }
+        step("owner") { workflow_id: "owner"; }
+      }
+      workflow("owner") {
+        step("get-owner") { operation_id: "getOwner"; }
+      }
+    }
+  }
+
+  fn resolver() -> OperationResolver {
+    OperationResolver::new(vec![OpenApiSource::new("petstore", "petstore.json", json!({
+      "servers": [{ "url": "https://petstore.example.com" }],
+      "paths": {
+        "/pets/{petId}": { "get": { "operationId": "getPet" } },
+        "/adoptions": { "post": { "operationId": "adoptPet" } },
+        "/owner": { "get": { "operationId": "getOwner" } }
+      }
+    }))])
+  }
+
+  #[test]
+  fn exports_the_workflow_as_a_postman_collection() {
+    let collection = postman_collection(&description(), "adopt", &resolver()).unwrap();
+    expect!(collection["info"].clone()).to(be_equal_to(json!({ "name": "Pet Store - adopt", "schema": POSTMAN_SCHEMA })));
+    expect!(collection["variable"].clone()).to(be_equal_to(json!([
+      { "key": "inputs.name", "value": "" },
+      { "key": "inputs.petId", "value": "1" },
+      { "key": "baseUrl.petstore", "value": "https://petstore.example.com" },
+      { "key": "steps.find.outputs.id", "value": "" }
+    ])));
+
+    let find = &collection["item"][0];
+    expect!(find["request"].clone()).to(be_equal_to(json!({
+      "method": "GET",
+      "url": {
+        "raw": "{{baseUrl.petstore}}/pets/{{inputs.petId}}?include=owner",
+        "host": ["{{baseUrl.petstore}}"],
+        "path": ["pets", "{{inputs.petId}}"],
+        "query": [{ "key": "include", "value": "owner" }]
+      },
+      "header": []
+    })));
+    expect!(find["event"][1]["script"]["exec"].clone()).to(be_equal_to(json!([
+      "pm.test(\"$statusCode == 200\", function () {",
+      "  pm.expect((pm.response.code == 200)).to.be.true;",
+      "});",
+      "// The jsonpath criterion is not translated: $.owner",
+      "pm.collectionVariables.set(\"steps.find.outputs.id\", pm.response.json()[\"id\"]);"
+    ])));
+
+    let adopt = &collection["item"][1];
+    expect!(adopt["request"]["body"]["raw"].clone())
+      .to(be_equal_to(json!("{\n  \"name\": \"{{inputs.name}}\",\n  \"pet\": \"{{steps.find.outputs.id}}\"\n}")));
+    expect!(adopt["request"]["header"].clone()).to(be_equal_to(json!([{ "key": "Content-Type", "value": "application/json" }])));
+    expect!(adopt["event"][0]["listen"].clone()).to(be_equal_to(json!("prerequest")));
+
+    let owner = &collection["item"][2];
+    expect!(owner["name"].clone()).to(be_equal_to(json!("owner")));
+    expect!(owner["item"][0]["request"]["url"]["raw"].clone()).to(be_equal_to(json!("{{baseUrl.petstore}}/owner")));
+  }
+
+  #[test]
+  fn returns_an_error_for_unknown_workflows_and_operations() {
+    expect!(postman_collection(&description(), "missing", &resolver())).to(be_err());
+    expect!(postman_collection(&description(), "adopt", &OperationResolver::new(vec![]))).to(be_err());
+  }
+
+  #[test]
+  fn translates_simple_conditions() {
+    expect!(condition_js("$statusCode == 200 && $response.header.Location != null")).to(be_some().value(
+      "(pm.response.code == 200 && pm.response.headers.get(\"Location\") != null)".to_string()));
+    expect!(condition_js("$inputs.name == 'a $b'")).to(be_some().value("(pm.variables.get(\"inputs.name\") == 'a $b')".to_string()));
+    expect!(condition_js("$workflows.other.outputs.a == 1")).to(be_none());
+  }
+}
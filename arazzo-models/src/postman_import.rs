@@ -0,0 +1,696 @@
+//! Imports [Postman](https://www.postman.com) collections (v2.1) as Arazzo descriptions, as a
+//! starting point for moving collections to Arazzo. This is the reverse of the
+//! [Postman export](crate::postman) (with the `execute` feature).
+//!
+//! The requests at the top level of the collection are the steps of a workflow named after the
+//! collection, and each folder is a workflow. Folders in a folder are also workflows, and are
+//! executed by a step of the workflow of the folder they are in. The operations of the steps are
+//! operation paths in a source description for each host of the requests. Postman has no OpenAPI
+//! documents, so the URL of the source descriptions is the host of the requests, which needs to
+//! be changed to the URL of the OpenAPI document of the API.
+//!
+//! Postman variables are converted to runtime expressions. Variables set from the response in the
+//! test script of a request (i.e. `pm.collectionVariables.set("id", pm.response.json().id)`) are
+//! outputs of the step, and other variables are inputs of the workflow, with the values of the
+//! collection variables as defaults. The success criteria of the steps are guessed from the
+//! assertions of the test scripts (`pm.response.to.have.status(200)`,
+//! `pm.expect(pm.response.code).to.eql(200)`, etc.), and assertions that can not be converted are
+//! ignored, so the imported workflows need to be reviewed.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use indexmap::IndexMap;
+use serde_json::{json, Map, Value};
+
+use crate::either::Either;
+use crate::extensions::AnyValue;
+use crate::payloads::{FormPayload, JsonPayload, Payload, StringPayload};
+use crate::v1_0::{
+  ArazzoDescription,
+  Criterion,
+  Info,
+  ParameterObject,
+  RequestBody,
+  ReusableObject,
+  SourceDescription,
+  Step,
+  Workflow
+};
+
+/// Imports the Postman collection as an Arazzo description
+pub fn import_postman_collection(collection: &Value) -> anyhow::Result<ArazzoDescription> {
+  let info = collection.get("info")
+    .ok_or_else(|| anyhow!("The Postman collection has no 'info'"))?;
+  let name = info.get("name").and_then(|name| name.as_str()).unwrap_or("collection");
+  let items = collection.get("item").and_then(|items| items.as_array())
+    .ok_or_else(|| anyhow!("The Postman collection has no 'item' array"))?;
+
+  let mut importer = Importer {
+    collection_variables: collection.get("variable").and_then(|variables| variables.as_array())
+      .map(|variables| variables.iter()
+        .filter_map(|variable| Some((variable.get("key")?.as_str()?.to_string(), variable.get("value")?.clone())))
+        .collect())
+      .unwrap_or_default(),
+    .. Importer::default()
+  };
+  let requests = items.iter().filter(|item| item.get("item").is_none()).cloned().collect::<Vec<_>>();
+  let folders = items.iter().filter(|item| item.get("item").is_some()).collect::<Vec<_>>();
+  if !requests.is_empty() {
+    importer.workflow(name, text(info.get("description")), &requests)?;
+  }
+  for folder in folders {
+    importer.folder(folder)?;
+  }
+
+  Ok(ArazzoDescription {
+    info: Info {
+      title: name.to_string(),
+      description: text(info.get("description")),
+      version: "1.0.0".to_string(),
+      .. Info::default()
+    },
+    source_descriptions: importer.sources.into_iter()
+      .map(|(name, url)| SourceDescription {
+        name,
+        url,
+        r#type: Some("openapi".to_string()),
+        extensions: Default::default()
+      })
+      .collect(),
+    workflows: importer.workflows,
+    .. ArazzoDescription::default()
+  })
+}
+
+#[derive(Debug, Default)]
+struct Importer {
+  collection_variables: HashMap<String, Value>,
+  /// Source descriptions, keyed by name
+  sources: IndexMap<String, String>,
+  workflows: Vec<Workflow>
+}
+
+/// Variables of the workflow being imported
+#[derive(Debug, Default)]
+struct WorkflowVariables {
+  /// Variables set by the test scripts of previous steps, with the expression of the output
+  outputs: HashMap<String, String>,
+  /// Names of the inputs used by the steps
+  inputs: Vec<String>
+}
+
+impl Importer {
+  /// Imports the folder as a workflow, returning the ID of the workflow
+  fn folder(&mut self, folder: &Value) -> anyhow::Result<String> {
+    let name = folder.get("name").and_then(|name| name.as_str()).unwrap_or("folder");
+    let items = folder.get("item").and_then(|items| items.as_array()).cloned().unwrap_or_default();
+    self.workflow(name, text(folder.get("description")), &items)
+  }
+
+  fn workflow(&mut self, name: &str, description: Option<String>, items: &[Value]) -> anyhow::Result<String> {
+    let workflow_id = unique_id(&sanitise_id(name), |id| self.workflows.iter().any(|workflow| workflow.workflow_id == id));
+    // Reserve the ID, so folders in the folder do not use it
+    let index = self.workflows.len();
+    self.workflows.push(Workflow { workflow_id: workflow_id.clone(), .. Workflow::default() });
+
+    let mut variables = WorkflowVariables::default();
+    let mut steps: Vec<Step> = vec![];
+    for item in items {
+      let name = item.get("name").and_then(|name| name.as_str()).unwrap_or("step");
+      let step_id = unique_id(&sanitise_id(name), |id| steps.iter().any(|step| step.step_id == id));
+      let step = if item.get("item").is_some() {
+        Step {
+          step_id,
+          workflow_id: Some(self.folder(item)?),
+          description: text(item.get("description")),
+          .. Step::default()
+        }
+      } else {
+        self.step(step_id, item, &mut variables)?
+      };
+      steps.push(step);
+    }
+
+    let workflow = &mut self.workflows[index];
+    workflow.summary = Some(name.to_string());
+    workflow.description = description;
+    workflow.steps = steps;
+    if !variables.inputs.is_empty() {
+      let properties = variables.inputs.iter()
+        .map(|input| {
+          let mut property = json!({ "type": "string" });
+          let default = self.collection_variables.get(input)
+            .or_else(|| self.collection_variables.get(&format!("inputs.{}", input)));
+          if let Some(default) = default && default != "" {
+            property["default"] = default.clone();
+          }
+          (input.clone(), property)
+        })
+        .collect::<Map<_, _>>();
+      workflow.inputs = json!({ "type": "object", "properties": properties });
+    }
+    Ok(workflow_id)
+  }
+
+  fn step(&mut self, step_id: String, item: &Value, variables: &mut WorkflowVariables) -> anyhow::Result<Step> {
+    let request = item.get("request")
+      .ok_or_else(|| anyhow!("Request '{}' has no 'request'", step_id))?;
+    let (method, url) = match request {
+      Value::String(url) => ("GET".to_string(), json!(url)),
+      _ => (
+        request.get("method").and_then(|method| method.as_str()).unwrap_or("GET").to_uppercase(),
+        request.get("url").cloned().unwrap_or(Value::Null)
+      )
+    };
+    let url = PostmanUrl::new(&url);
+
+    let source = self.source(&url.host);
+    let mut parameters = vec![];
+    let mut path = String::new();
+    for segment in &url.path {
+      path.push('/');
+      if let Some(name) = segment.strip_prefix(':') {
+        path.push_str(&format!("{{{}}}", name));
+        let value = url.variables.get(name).cloned().unwrap_or_else(|| format!("{{{{{}}}}}", name));
+        parameters.push(parameter(name, "path", &value, variables));
+      } else {
+        let mut rest = segment.as_str();
+        while let Some((start, name, end)) = next_variable(rest) {
+          let parameter_name = name.rsplit('.').next().unwrap_or(name);
+          path.push_str(&rest[..start]);
+          path.push_str(&format!("{{{}}}", parameter_name));
+          parameters.push(parameter(parameter_name, "path", &format!("{{{{{}}}}}", name), variables));
+          rest = &rest[end..];
+        }
+        path.push_str(rest);
+      }
+    }
+    for (name, value) in &url.query {
+      parameters.push(parameter(name, "query", value, variables));
+    }
+
+    let mut content_type = None;
+    for header in request.get("header").and_then(|headers| headers.as_array()).into_iter().flatten() {
+      if header.get("disabled").and_then(|disabled| disabled.as_bool()).unwrap_or(false) {
+        continue;
+      }
+      let (Some(name), Some(value)) = (header.get("key").and_then(|key| key.as_str()), header.get("value").and_then(|value| value.as_str())) else {
+        continue;
+      };
+      if name.eq_ignore_ascii_case("content-type") {
+        content_type = Some(value.to_string());
+      } else {
+        parameters.push(parameter(name, "header", value, variables));
+      }
+    }
+
+    let request_body = request.get("body")
+      .and_then(|body| self.request_body(body, content_type, variables));
+
+    let script = event_script(item, "test");
+    let success_criteria = success_criteria(&script);
+    let outputs = outputs(&script);
+    for name in outputs.keys() {
+      variables.outputs.insert(name.clone(), format!("$steps.{}.outputs.{}", step_id, name));
+    }
+
+    Ok(Step {
+      step_id,
+      operation_path: Some(format!("{{$sourceDescriptions.{}.url}}#/paths/{}/{}", source,
+        path.replace('~', "~0").replace('/', "~1"), method.to_lowercase())),
+      description: text(item.get("description")).or_else(|| text(request.get("description"))),
+      parameters,
+      request_body,
+      success_criteria,
+      outputs,
+      .. Step::default()
+    })
+  }
+
+  /// Name of the source description for the host, adding it if it is new
+  fn source(&mut self, host: &str) -> String {
+    let (name, url) = match next_variable(host) {
+      Some((0, variable, end)) if end == host.len() => {
+        let name = variable.strip_prefix("baseUrl.").unwrap_or(variable);
+        let url = self.collection_variables.get(variable)
+          .and_then(|value| value.as_str())
+          .filter(|value| !value.is_empty())
+          .unwrap_or(host);
+        (sanitise_id(name), url.to_string())
+      }
+      _ => {
+        let domain = host.split_once("://").map(|(_, domain)| domain).unwrap_or(host);
+        (sanitise_id(domain), host.to_string())
+      }
+    };
+    if let Some((existing, _)) = self.sources.iter().find(|(_, existing)| **existing == url) {
+      return existing.clone();
+    }
+    let name = unique_id(&name, |name| self.sources.contains_key(name));
+    self.sources.insert(name.clone(), url);
+    name
+  }
+
+  fn request_body(&self, body: &Value, content_type: Option<String>, variables: &mut WorkflowVariables) -> Option<RequestBody> {
+    let (payload, content_type): (Arc<dyn Payload + Send + Sync>, Option<String>) = match body.get("mode")?.as_str()? {
+      "raw" => {
+        let raw = arazzo_text(body.get("raw")?.as_str()?, variables);
+        let language = body.pointer("/options/raw/language").and_then(|language| language.as_str());
+        let content_type = content_type.or_else(|| match language {
+          Some("json") => Some("application/json".to_string()),
+          Some("xml") => Some("application/xml".to_string()),
+          _ => None
+        });
+        let is_json = content_type.as_deref().map(|content_type| content_type.contains("json")).unwrap_or(false);
+        match serde_json::from_str::<Value>(&raw) {
+          Ok(json) if is_json => (Arc::new(JsonPayload(json)), content_type),
+          _ => (Arc::new(StringPayload(raw)), content_type)
+        }
+      }
+      "urlencoded" => {
+        let fields = body.get("urlencoded")?.as_array()?.iter()
+          .filter_map(|field| Some((field.get("key")?.as_str()?.to_string(),
+            AnyValue::String(arazzo_text(field.get("value")?.as_str()?, variables)))))
+          .collect();
+        (Arc::new(FormPayload(fields)), content_type.or(Some("application/x-www-form-urlencoded".to_string())))
+      }
+      _ => return None
+    };
+    Some(RequestBody {
+      content_type,
+      payload: Some(payload),
+      replacements: vec![],
+      extensions: Default::default()
+    })
+  }
+}
+
+/// Parts of a Postman request URL
+#[derive(Debug, Default)]
+struct PostmanUrl {
+  host: String,
+  path: Vec<String>,
+  query: Vec<(String, String)>,
+  variables: HashMap<String, String>
+}
+
+impl PostmanUrl {
+  fn new(url: &Value) -> Self {
+    let raw = match url {
+      Value::String(raw) => raw.as_str(),
+      _ => url.get("raw").and_then(|raw| raw.as_str()).unwrap_or_default()
+    };
+    let (without_query, query_string) = raw.split_once('?').unwrap_or((raw, ""));
+    let path_start = without_query.find("://").map(|index| index + 3).unwrap_or(0);
+    let (host, path) = match without_query[path_start..].find('/') {
+      Some(index) => without_query.split_at(path_start + index),
+      None => (without_query, "")
+    };
+
+    let query = match url.get("query").and_then(|query| query.as_array()) {
+      Some(query) => query.iter()
+        .filter(|param| !param.get("disabled").and_then(|disabled| disabled.as_bool()).unwrap_or(false))
+        .filter_map(|param| Some((param.get("key")?.as_str()?.to_string(),
+          param.get("value").and_then(|value| value.as_str()).unwrap_or_default().to_string())))
+        .collect(),
+      None => query_string.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+          let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+          (key.to_string(), value.to_string())
+        })
+        .collect()
+    };
+
+    PostmanUrl {
+      host: host.to_string(),
+      path: path.split('/').filter(|segment| !segment.is_empty()).map(|segment| segment.to_string()).collect(),
+      query,
+      variables: url.get("variable").and_then(|variables| variables.as_array()).into_iter().flatten()
+        .filter_map(|variable| Some((variable.get("key")?.as_str()?.to_string(),
+          variable.get("value").and_then(|value| value.as_str()).unwrap_or_default().to_string())))
+        .collect()
+    }
+  }
+}
+
+fn parameter(name: &str, location: &str, value: &str, variables: &mut WorkflowVariables) -> Either<ParameterObject, ReusableObject> {
+  let value = arazzo_text(value, variables);
+  Either::First(ParameterObject {
+    name: name.to_string(),
+    r#in: Some(location.to_string()),
+    value: if value.starts_with('$') { Either::Second(value) } else { Either::First(AnyValue::String(value)) },
+    extensions: Default::default()
+  })
+}
+
+/// Converts the Postman variables in the text to runtime expressions. If the text is only a
+/// variable, the expression is returned, otherwise the expressions are embedded in the text.
+fn arazzo_text(text: &str, variables: &mut WorkflowVariables) -> String {
+  if let Some((0, name, end)) = next_variable(text) && end == text.len() {
+    return expression(name, variables);
+  }
+  let mut result = String::new();
+  let mut rest = text;
+  while let Some((start, name, end)) = next_variable(rest) {
+    result.push_str(&rest[..start]);
+    result.push_str(&format!("{{{}}}", expression(name, variables)));
+    rest = &rest[end..];
+  }
+  result.push_str(rest);
+  result
+}
+
+/// Runtime expression for a Postman variable. Variables set by previous steps are the outputs of
+/// those steps, and other variables are inputs of the workflow.
+fn expression(name: &str, variables: &mut WorkflowVariables) -> String {
+  if let Some(expression) = variables.outputs.get(name) {
+    return expression.clone();
+  }
+  if name.starts_with("steps.") {
+    return format!("${}", name);
+  }
+  let input = name.strip_prefix("inputs.").unwrap_or(name);
+  if !variables.inputs.iter().any(|existing| existing == input) {
+    variables.inputs.push(input.to_string());
+  }
+  format!("$inputs.{}", input)
+}
+
+/// Finds the next `{{name}}` variable in the text, returning the start, name and end
+fn next_variable(text: &str) -> Option<(usize, &str, usize)> {
+  let start = text.find("{{")?;
+  let length = text[start..].find("}}")?;
+  Some((start, text[start + 2..start + length].trim(), start + length + 2))
+}
+
+/// Success criteria from the assertions in the test script
+fn success_criteria(script: &str) -> Vec<Criterion> {
+  let mut conditions: Vec<String> = vec![];
+  for (name, _) in calls(script, "pm.test(") {
+    if let Some(condition) = string_literal(&name).filter(|name| name.starts_with('$')) {
+      conditions.push(condition);
+    }
+  }
+  for (status, _) in calls(script, "pm.response.to.have.status(") {
+    conditions.push(format!("$statusCode == {}", status.trim()));
+  }
+  if script.contains("pm.response.to.be.ok") || script.contains("pm.response.to.be.success") {
+    conditions.push("$statusCode >= 200 && $statusCode < 300".to_string());
+  }
+  for (actual, rest) in calls(script, "pm.expect(") {
+    let Some(expression) = js_expression(&actual) else {
+      continue;
+    };
+    for (method, operator) in [(".to.eql(", "=="), (".to.equal(", "=="), (".to.eq(", "=="),
+      (".to.not.eql(", "!="), (".to.not.equal(", "!="), (".to.be.above(", ">"), (".to.be.below(", "<")] {
+      if rest.starts_with(method) && let Some((expected, _)) = calls(rest, method).into_iter().next() {
+        let expected = string_literal(&expected).map(|value| format!("'{}'", value)).unwrap_or_else(|| expected.trim().to_string());
+        conditions.push(format!("{} {} {}", expression, operator, expected));
+      }
+    }
+  }
+
+  let mut criteria: Vec<Criterion> = vec![];
+  for condition in conditions {
+    if !criteria.iter().any(|criterion| criterion.condition == condition) {
+      criteria.push(Criterion { condition, .. Criterion::default() });
+    }
+  }
+  criteria
+}
+
+/// Outputs for the variables set from the response in the test script
+fn outputs(script: &str) -> BTreeMap<String, String> {
+  let mut outputs = BTreeMap::new();
+  for scope in ["collectionVariables", "environment", "globals", "variables"] {
+    for (arguments, _) in calls(script, &format!("pm.{}.set(", scope)) {
+      let Some((name, value)) = split_arguments(&arguments) else {
+        continue;
+      };
+      if let (Some(name), Some(expression)) = (string_literal(name), js_expression(value)) {
+        let name = name.rsplit('.').next().unwrap_or(&name).to_string();
+        outputs.insert(name, expression);
+      }
+    }
+  }
+  outputs
+}
+
+/// Runtime expression for a JavaScript expression of the response
+fn js_expression(js: &str) -> Option<String> {
+  let js = js.trim().trim_end_matches(';').trim();
+  let js = js.strip_prefix('(').and_then(|js| js.strip_suffix(')')).unwrap_or(js);
+  match js {
+    "pm.response.code" => return Some("$statusCode".to_string()),
+    "pm.response.json()" | "pm.response.text()" => return Some("$response.body".to_string()),
+    "pm.request.method" => return Some("$method".to_string()),
+    _ => {}
+  }
+  if let Some((header, rest)) = calls(js, "pm.response.headers.get(").into_iter().next() && rest.is_empty() {
+    return string_literal(&header).map(|header| format!("$response.header.{}", header));
+  }
+
+  let mut rest = js.strip_prefix("pm.response.json()")?;
+  let mut pointer = String::new();
+  while !rest.is_empty() {
+    let segment;
+    if let Some(property) = rest.strip_prefix('.') {
+      let end = property.find(['.', '[']).unwrap_or(property.len());
+      segment = property[..end].to_string();
+      if !segment.chars().all(|ch| ch.is_alphanumeric() || ch == '_' || ch == '$') {
+        return None;
+      }
+      rest = &property[end..];
+    } else if let Some(index) = rest.strip_prefix('[') {
+      let end = index.find(']')?;
+      segment = string_literal(&index[..end]).unwrap_or_else(|| index[..end].trim().to_string());
+      rest = &index[end + 1..];
+    } else {
+      return None;
+    }
+    if segment.is_empty() {
+      return None;
+    }
+    pointer.push('/');
+    pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+  }
+  Some(format!("$response.body#{}", pointer))
+}
+
+/// Finds the calls of the function in the script, returning the arguments and the script after
+/// the call
+fn calls<'a>(script: &'a str, function: &str) -> Vec<(String, &'a str)> {
+  let mut result = vec![];
+  let mut rest = script;
+  while let Some(index) = rest.find(function) {
+    let arguments_start = index + function.len();
+    let mut depth = 1;
+    let mut quote = None;
+    let mut end = None;
+    for (offset, ch) in rest[arguments_start..].char_indices() {
+      match (quote, ch) {
+        (Some(q), ch) if ch == q => quote = None,
+        (Some(_), _) => {}
+        (None, '"' | '\'' | '`') => quote = Some(ch),
+        (None, '(') => depth += 1,
+        (None, ')') => {
+          depth -= 1;
+          if depth == 0 {
+            end = Some(arguments_start + offset);
+            break;
+          }
+        }
+        _ => {}
+      }
+    }
+    let Some(end) = end else {
+      break;
+    };
+    result.push((rest[arguments_start..end].to_string(), &rest[end + 1..]));
+    rest = &rest[end + 1..];
+  }
+  result
+}
+
+/// Splits the arguments of a call with two arguments, where the first is a string
+fn split_arguments(arguments: &str) -> Option<(&str, &str)> {
+  let arguments = arguments.trim();
+  let quote = arguments.chars().next().filter(|ch| *ch == '"' || *ch == '\'')?;
+  let end = arguments[1..].find(quote)? + 2;
+  let value = arguments[end..].trim_start().strip_prefix(',')?;
+  Some((&arguments[..end], value))
+}
+
+/// Value of a JavaScript string literal
+fn string_literal(text: &str) -> Option<String> {
+  let text = text.trim();
+  if text.len() >= 2 && ((text.starts_with('"') && text.ends_with('"')) || (text.starts_with('\'') && text.ends_with('\''))) {
+    if text.starts_with('"') {
+      serde_json::from_str(text).ok()
+    } else {
+      Some(text[1..text.len() - 1].to_string())
+    }
+  } else {
+    None
+  }
+}
+
+/// Source of the script of the event (i.e. `test` or `prerequest`) of the item
+fn event_script(item: &Value, listen: &str) -> String {
+  item.get("event").and_then(|events| events.as_array()).into_iter().flatten()
+    .filter(|event| event.get("listen").and_then(|value| value.as_str()) == Some(listen))
+    .filter_map(|event| event.pointer("/script/exec"))
+    .map(|exec| match exec {
+      Value::Array(lines) => lines.iter().filter_map(|line| line.as_str()).collect::<Vec<_>>().join("\n"),
+      Value::String(source) => source.clone(),
+      _ => String::new()
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Text of a Postman description, which is either a string or an object with `content`
+fn text(description: Option<&Value>) -> Option<String> {
+  match description? {
+    Value::String(text) => Some(text.clone()),
+    description => description.get("content")?.as_str().map(|text| text.to_string())
+  }.filter(|text| !text.is_empty())
+}
+
+/// Converts a name to an ID with only the characters allowed by the Arazzo specification
+pub(crate) fn sanitise_id(name: &str) -> String {
+  let id = name.chars()
+    .map(|ch| if ch.is_ascii_alphanumeric() || ch == '_' || ch == '-' { ch } else { '-' })
+    .collect::<String>()
+    .split('-')
+    .filter(|part| !part.is_empty())
+    .collect::<Vec<_>>()
+    .join("-");
+  if id.is_empty() { "id".to_string() } else { id }
+}
+
+/// Adds a number to the ID if it is already used
+pub(crate) fn unique_id<F: Fn(&str) -> bool>(id: &str, exists: F) -> String {
+  if !exists(id) {
+    return id.to_string();
+  }
+  (2..).map(|index| format!("{}-{}", id, index)).find(|id| !exists(id)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::either::Either;
+  use crate::extensions::AnyValue;
+  use crate::payloads::JsonPayload;
+  use crate::postman_import::{import_postman_collection, js_expression, sanitise_id};
+
+  #[test]
+  fn imports_folders_as_workflows() {
+    let collection = json!({
+      "info": { "name": "Pet Store", "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json" },
+      "variable": [
+        { "key": "baseUrl", "value": "https://petstore.example.com" },
+        { "key": "petId", "value": "1" }
+      ],
+      "item": [{
+        "name": "Adopt a pet",
+        "item": [
+          {
+            "name": "Find pet",
+            "request": {
+              "method": "GET",
+              "url": { "raw": "{{baseUrl}}/pets/:petId?include=owner", "variable": [{ "key": "petId", "value": "{{petId}}" }] }
+            },
+            "event": [{
+              "listen": "test",
+              "script": { "exec": [
+                "pm.test('Status is OK', function () {",
+                "  pm.response.to.have.status(200);",
+                "  pm.expect(pm.response.json().status).to.eql(\"available\");",
+                "});",
+                "pm.collectionVariables.set(\"petRef\", pm.response.json().ref);"
+              ] }
+            }]
+          },
+          {
+            "name": "Adopt pet",
+            "request": {
+              "method": "POST",
+              "header": [{ "key": "Content-Type", "value": "application/json" }, { "key": "X-Token", "value": "{{token}}" }],
+              "url": "{{baseUrl}}/adoptions",
+              "body": { "mode": "raw", "raw": "{\"pet\": \"{{petRef}}\"}" }
+            }
+          }
+        ]
+      }]
+    });
+    let description = import_postman_collection(&collection).unwrap();
+    expect!(description.info.title).to(be_equal_to("Pet Store"));
+    expect!(description.source_descriptions.len()).to(be_equal_to(1));
+    expect!(description.source_descriptions[0].name.clone()).to(be_equal_to("baseUrl"));
+    expect!(description.source_descriptions[0].url.clone()).to(be_equal_to("https://petstore.example.com"));
+
+    let workflow = &description.workflows[0];
+    expect!(workflow.workflow_id.clone()).to(be_equal_to("Adopt-a-pet"));
+    expect!(workflow.inputs.clone()).to(be_equal_to(json!({
+      "type": "object",
+      "properties": {
+        "petId": { "type": "string", "default": "1" },
+        "token": { "type": "string" }
+      }
+    })));
+
+    let find = &workflow.steps[0];
+    expect!(find.step_id.clone()).to(be_equal_to("Find-pet"));
+    expect!(find.operation_path.clone()).to(be_some().value("{$sourceDescriptions.baseUrl.url}#/paths/~1pets~1{petId}/get"));
+    expect!(find.parameters.len()).to(be_equal_to(2));
+    let Either::First(parameter) = &find.parameters[0] else { panic!("Expected a parameter") };
+    expect!(parameter.value.clone()).to(be_equal_to(Either::Second("$inputs.petId".to_string())));
+    let Either::First(parameter) = &find.parameters[1] else { panic!("Expected a parameter") };
+    expect!(parameter.value.clone()).to(be_equal_to(Either::First(AnyValue::String("owner".to_string()))));
+    let conditions = find.success_criteria.iter().map(|criterion| criterion.condition.clone()).collect::<Vec<_>>();
+    expect!(conditions).to(be_equal_to(vec!["$statusCode == 200".to_string(), "$response.body#/status == 'available'".to_string()]));
+    expect!(find.outputs.get("petRef").cloned()).to(be_some().value("$response.body#/ref"));
+
+    let adopt = &workflow.steps[1];
+    let body = adopt.request_body.clone().unwrap();
+    expect!(body.content_type.clone()).to(be_some().value("application/json"));
+    expect!(body.payload_as::<JsonPayload>().unwrap().0.clone()).to(be_equal_to(json!({ "pet": "{$steps.Find-pet.outputs.petRef}" })));
+    expect!(adopt.parameters.len()).to(be_equal_to(1));
+  }
+
+  #[test]
+  fn imports_nested_folders_as_workflow_steps() {
+    let collection = json!({
+      "info": { "name": "Shop" },
+      "item": [
+        { "name": "Health", "request": "https://shop.example.com/health" },
+        { "name": "Orders", "item": [{ "name": "Customers", "item": [
+          { "name": "List", "request": { "method": "GET", "url": "https://shop.example.com/customers" } }
+        ] }] }
+      ]
+    });
+    let description = import_postman_collection(&collection).unwrap();
+    let ids = description.workflows.iter().map(|workflow| workflow.workflow_id.as_str()).collect::<Vec<_>>();
+    expect!(ids).to(be_equal_to(vec!["Shop", "Orders", "Customers"]));
+    expect!(description.workflows[1].steps[0].workflow_id.clone()).to(be_some().value("Customers"));
+    expect!(description.source_descriptions[0].name.clone()).to(be_equal_to("shop-example-com"));
+
+    expect!(import_postman_collection(&json!({ "item": [] }))).to(be_err());
+  }
+
+  #[test]
+  fn converts_response_accessors_to_expressions() {
+    expect!(js_expression("pm.response.json().items[0][\"the/id\"]")).to(be_some().value("$response.body#/items/0/the~1id"));
+    expect!(js_expression("pm.response.headers.get('Location')")).to(be_some().value("$response.header.Location"));
+    expect!(js_expression("(pm.response.code)")).to(be_some().value("$statusCode"));
+    expect!(js_expression("jsonData.id")).to(be_none());
+    expect!(sanitise_id("Get a pet (by ID)")).to(be_equal_to("Get-a-pet-by-ID"));
+  }
+}
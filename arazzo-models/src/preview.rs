@@ -0,0 +1,264 @@
+//! Support for previewing the payload of a Request Body that will be sent, without executing the
+//! workflow. The templates and replacements are applied (using placeholder or sample values), and
+//! the changes between the original and rendered payloads are returned as a structural diff.
+
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::expressions::ExpressionResolver;
+use crate::extensions::AnyValue;
+use crate::payloads::{Payload, StringPayload};
+use crate::v1_0::RequestBody;
+
+/// Resolves every runtime expression to a placeholder string value (i.e. `<$inputs.pet_id>`).
+/// Note that placeholders are strings, so templates in non-string positions of string payloads
+/// (like `{"id": {$inputs.pet_id}}`) will not render valid JSON. Use a resolver with sample values
+/// for those.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlaceholderResolver;
+
+impl ExpressionResolver for PlaceholderResolver {
+  fn resolve(&self, expression: &str) -> anyhow::Result<AnyValue> {
+    Ok(AnyValue::String(format!("<{}>", expression.trim())))
+  }
+}
+
+/// Type of change to a payload
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PayloadChangeKind {
+  /// Value was added
+  Added,
+  /// Value was removed
+  Removed,
+  /// Value was changed
+  Modified
+}
+
+/// Change between the original and rendered payloads
+#[derive(Debug, Clone, PartialEq)]
+pub struct PayloadChange {
+  /// JSON Pointer to the location of the change. Changes to unstructured payloads are at the root
+  /// (empty pointer).
+  pub path: String,
+  /// Type of change
+  pub kind: PayloadChangeKind,
+  /// Original value, if there was one
+  pub original: Option<Value>,
+  /// Rendered value, if there is one
+  pub rendered: Option<Value>
+}
+
+impl Display for PayloadChange {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let path = if self.path.is_empty() { "/" } else { self.path.as_str() };
+    match (&self.original, &self.rendered) {
+      (Some(original), Some(rendered)) => write!(f, "{}: {} -> {}", path, original, rendered),
+      (None, Some(rendered)) => write!(f, "{}: + {}", path, rendered),
+      (Some(original), None) => write!(f, "{}: - {}", path, original),
+      (None, None) => write!(f, "{}", path)
+    }
+  }
+}
+
+/// Preview of the payload of a Request Body
+#[derive(Debug, Clone)]
+pub struct PayloadPreview {
+  /// Payload as declared in the Request Body
+  pub original: Option<Arc<dyn Payload + Send + Sync>>,
+  /// Payload with the templates and replacements applied
+  pub rendered: Option<Arc<dyn Payload + Send + Sync>>,
+  /// Changes between the original and rendered payloads
+  pub changes: Vec<PayloadChange>
+}
+
+impl RequestBody {
+  /// Previews the payload that will be sent. The templates in the payload are rendered, and then
+  /// the replacements are applied, with the runtime expressions resolved using the resolver
+  /// (see [`PlaceholderResolver`] to use placeholder values).
+  pub fn preview(&self, resolver: &dyn ExpressionResolver) -> anyhow::Result<PayloadPreview> {
+    let rendered = self.render_payload(resolver)?;
+
+    let changes = match (&self.payload, &rendered) {
+      (Some(original), Some(rendered)) => diff_payloads(original.as_ref(), rendered.as_ref()),
+      _ => vec![]
+    };
+    Ok(PayloadPreview { original: self.payload.clone(), rendered, changes })
+  }
+}
+
+/// Returns the changes between two payloads. Structured payloads (and string payloads that are
+/// valid JSON) are compared structurally, otherwise the text of the payloads is compared.
+pub fn diff_payloads(original: &(dyn Payload + Send + Sync), rendered: &(dyn Payload + Send + Sync)) -> Vec<PayloadChange> {
+  if let (Some(original), Some(rendered)) = (payload_json(original), payload_json(rendered)) {
+    return diff_json(&original, &rendered);
+  }
+
+  let original = original.as_string();
+  let rendered = rendered.as_string();
+  if original == rendered {
+    vec![]
+  } else {
+    vec![PayloadChange {
+      path: String::default(),
+      kind: PayloadChangeKind::Modified,
+      original: Some(Value::String(original)),
+      rendered: Some(Value::String(rendered))
+    }]
+  }
+}
+
+fn payload_json(payload: &(dyn Payload + Send + Sync)) -> Option<Value> {
+  payload.as_json().or_else(|| {
+    if payload.downcast_ref::<StringPayload>().is_some() {
+      serde_json::from_str(&payload.as_string()).ok()
+    } else {
+      None
+    }
+  })
+}
+
+/// Returns the changes between two JSON values. Objects are compared by key and arrays by index.
+pub fn diff_json(original: &Value, rendered: &Value) -> Vec<PayloadChange> {
+  let mut changes = vec![];
+  diff_json_values(original, rendered, "", &mut changes);
+  changes
+}
+
+fn diff_json_values(original: &Value, rendered: &Value, path: &str, changes: &mut Vec<PayloadChange>) {
+  match (original, rendered) {
+    (Value::Object(original), Value::Object(rendered)) => {
+      for (key, value) in original {
+        let path = format!("{}/{}", path, escape_token(key));
+        match rendered.get(key) {
+          Some(rendered) => diff_json_values(value, rendered, &path, changes),
+          None => changes.push(PayloadChange {
+            path, kind: PayloadChangeKind::Removed, original: Some(value.clone()), rendered: None
+          })
+        }
+      }
+      for (key, value) in rendered {
+        if !original.contains_key(key) {
+          changes.push(PayloadChange {
+            path: format!("{}/{}", path, escape_token(key)),
+            kind: PayloadChangeKind::Added,
+            original: None,
+            rendered: Some(value.clone())
+          });
+        }
+      }
+    }
+    (Value::Array(original), Value::Array(rendered)) => {
+      for index in 0..original.len().max(rendered.len()) {
+        let path = format!("{}/{}", path, index);
+        match (original.get(index), rendered.get(index)) {
+          (Some(original), Some(rendered)) => diff_json_values(original, rendered, &path, changes),
+          (Some(original), None) => changes.push(PayloadChange {
+            path, kind: PayloadChangeKind::Removed, original: Some(original.clone()), rendered: None
+          }),
+          (None, Some(rendered)) => changes.push(PayloadChange {
+            path, kind: PayloadChangeKind::Added, original: None, rendered: Some(rendered.clone())
+          }),
+          (None, None) => {}
+        }
+      }
+    }
+    _ => if original != rendered {
+      changes.push(PayloadChange {
+        path: path.to_string(),
+        kind: PayloadChangeKind::Modified,
+        original: Some(original.clone()),
+        rendered: Some(rendered.clone())
+      });
+    }
+  }
+}
+
+fn escape_token(token: &str) -> String {
+  token.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::either::Either;
+  use crate::extensions::AnyValue;
+  use crate::payloads::{JsonPayload, StringPayload};
+  use crate::preview::*;
+  use crate::v1_0::{PayloadReplacement, RequestBody};
+
+  #[test]
+  fn json_diff() {
+    let original = json!({ "a": 1, "b": [1, 2], "c/d": "x", "e": true });
+    let rendered = json!({ "a": 1, "b": [1, 3, 4], "c/d": "y", "f": null });
+    expect!(diff_json(&original, &rendered).iter().map(|c| c.to_string()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "/b/1: 2 -> 3".to_string(),
+      "/b/2: + 4".to_string(),
+      "/c~1d: \"x\" -> \"y\"".to_string(),
+      "/e: - true".to_string(),
+      "/f: + null".to_string()
+    ]));
+    expect!(diff_json(&original, &original).is_empty()).to(be_true());
+  }
+
+  #[test]
+  fn preview_request_body() {
+    let body = RequestBody {
+      content_type: Some("application/json".to_string()),
+      payload: Some(Arc::new(JsonPayload(json!({ "name": "{$inputs.name}", "id": 0 })))),
+      replacements: vec![
+        PayloadReplacement {
+          target: "/id".to_string(),
+          value: Either::Second("$inputs.id".to_string()),
+          extensions: Default::default()
+        },
+        PayloadReplacement {
+          target: "/status".to_string(),
+          value: Either::First(AnyValue::String("available".to_string())),
+          extensions: Default::default()
+        }
+      ],
+      extensions: Default::default()
+    };
+    let preview = body.preview(&PlaceholderResolver).unwrap();
+    expect!(preview.rendered.unwrap().as_json()).to(be_some().value(json!({
+      "name": "<$inputs.name>", "id": "<$inputs.id>", "status": "available"
+    })));
+    expect!(preview.changes).to(be_equal_to(vec![
+      PayloadChange {
+        path: "/id".to_string(),
+        kind: PayloadChangeKind::Modified,
+        original: Some(json!(0)),
+        rendered: Some(json!("<$inputs.id>"))
+      },
+      PayloadChange {
+        path: "/name".to_string(),
+        kind: PayloadChangeKind::Modified,
+        original: Some(json!("{$inputs.name}")),
+        rendered: Some(json!("<$inputs.name>"))
+      },
+      PayloadChange {
+        path: "/status".to_string(),
+        kind: PayloadChangeKind::Added,
+        original: None,
+        rendered: Some(json!("available"))
+      }
+    ]));
+
+    let body = RequestBody {
+      content_type: Some("text/plain".to_string()),
+      payload: Some(Arc::new(StringPayload("Hello {$inputs.name}".to_string()))),
+      replacements: vec![],
+      extensions: Default::default()
+    };
+    let preview = body.preview(&PlaceholderResolver).unwrap();
+    expect!(preview.changes.iter().map(|c| c.to_string()).collect::<Vec<_>>()).to(be_equal_to(vec![
+      "/: \"Hello {$inputs.name}\" -> \"Hello <$inputs.name>\"".to_string()
+    ]));
+  }
+}
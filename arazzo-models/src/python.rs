@@ -0,0 +1,569 @@
+//! Python bindings for the Arazzo models, as the `arazzo` Python module (enabled with the `python`
+//! feature). The module has functions to load descriptions (from JSON or YAML) and validate them,
+//! and classes that mirror the Rust models (`ArazzoDescription`, `Info`, `SourceDescription`,
+//! `Workflow`, `Step`, `Criterion` and `ValidationIssue`).
+//!
+//! The objects that are not mirrored by a class (i.e. parameters, actions, request bodies and the
+//! components) are returned as dictionaries in the same form as the Arazzo document. The Python
+//! objects are copies of the models, so changes to them do not change the description.
+//!
+//! The module is built with [maturin](https://www.maturin.rs) (`maturin build --release` in the
+//! crate directory), which enables the `python` feature (see `pyproject.toml`). The Python tests are
+//! in the `python-tests` directory.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyTypeError};
+use pyo3::prelude::*;
+use pyo3::IntoPyObjectExt;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyList, PyString, PyTuple};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::either::Either;
+use crate::extensions::AnyValue;
+use crate::v1_0::{ArazzoDescription, Criterion, Info, SourceDescription, Step, Workflow};
+use crate::validation::{validate as validate_description, ValidationIssue};
+
+create_exception!(arazzo, ArazzoError, PyException, "Error loading or converting an Arazzo description");
+
+fn arazzo_error(err: impl ToString) -> PyErr {
+  ArazzoError::new_err(err.to_string())
+}
+
+/// Converts a JSON value to the equivalent Python object
+fn to_python(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+  Ok(match value {
+    Value::Null => py.None(),
+    Value::Bool(b) => b.into_py_any(py)?,
+    Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+      (Some(i), _) => i.into_py_any(py)?,
+      (None, Some(u)) => u.into_py_any(py)?,
+      _ => n.as_f64().unwrap_or_default().into_py_any(py)?
+    },
+    Value::String(s) => s.into_py_any(py)?,
+    Value::Array(items) => {
+      let list = PyList::empty(py);
+      for item in items {
+        list.append(to_python(py, item)?)?;
+      }
+      list.into_py_any(py)?
+    }
+    Value::Object(map) => {
+      let dict = PyDict::new(py);
+      for (key, value) in map {
+        dict.set_item(key, to_python(py, value)?)?;
+      }
+      dict.into_py_any(py)?
+    }
+  })
+}
+
+/// Converts a Python object (of the types that can be represented in JSON) to a JSON value
+fn from_python(value: &Bound<'_, PyAny>) -> PyResult<Value> {
+  if value.is_none() {
+    Ok(Value::Null)
+  } else if let Ok(b) = value.downcast::<PyBool>() {
+    Ok(Value::Bool(b.is_true()))
+  } else if let Ok(s) = value.downcast::<PyString>() {
+    Ok(Value::String(s.to_str()?.to_string()))
+  } else if let Ok(f) = value.downcast::<PyFloat>() {
+    Ok(serde_json::Number::from_f64(f.value()).map(Value::Number).unwrap_or(Value::Null))
+  } else if let Ok(i) = value.extract::<i64>() {
+    Ok(Value::from(i))
+  } else if let Ok(u) = value.extract::<u64>() {
+    Ok(Value::from(u))
+  } else if let Ok(dict) = value.downcast::<PyDict>() {
+    let mut map = Map::new();
+    for (key, value) in dict.iter() {
+      let key = match key.downcast::<PyString>() {
+        Ok(key) => key,
+        Err(_) => return Err(PyTypeError::new_err(format!("Dictionary keys must be strings, got {}", key.get_type().name()?)))
+      };
+      map.insert(key.to_str()?.to_string(), from_python(&value)?);
+    }
+    Ok(Value::Object(map))
+  } else if let Ok(list) = value.downcast::<PyList>() {
+    list.iter().map(|item| from_python(&item)).collect::<PyResult<Vec<_>>>().map(Value::Array)
+  } else if let Ok(tuple) = value.downcast::<PyTuple>() {
+    tuple.iter().map(|item| from_python(&item)).collect::<PyResult<Vec<_>>>().map(Value::Array)
+  } else {
+    Err(PyTypeError::new_err(format!("Values of type {} can not be converted", value.get_type().name()?)))
+  }
+}
+
+fn extensions_to_python(py: Python<'_>, extensions: &HashMap<String, AnyValue>) -> PyResult<PyObject> {
+  let map = extensions.iter()
+    .map(|(key, value)| (format!("x-{}", key), Value::from(value)))
+    .collect::<Map<_, _>>();
+  to_python(py, &Value::Object(map))
+}
+
+/// Converts a model (or a field of one) to a Python object in the same form as the Arazzo document.
+/// The getters pass the field, so only the field is serialized and not the whole model.
+fn serialize_to_python<T: Serialize>(py: Python<'_>, field: &T) -> PyResult<PyObject> {
+  let json = serde_json::to_value(field).map_err(arazzo_error)?;
+  to_python(py, &json)
+}
+
+/// Arazzo description document
+#[pyclass(name = "ArazzoDescription", module = "arazzo")]
+#[derive(Clone)]
+struct PyArazzoDescription {
+  inner: ArazzoDescription
+}
+
+#[pymethods]
+impl PyArazzoDescription {
+  /// Version of the Arazzo specification
+  #[getter]
+  fn arazzo(&self) -> String {
+    self.inner.arazzo.clone()
+  }
+
+  /// Metadata about the workflows
+  #[getter]
+  fn info(&self) -> PyInfo {
+    PyInfo { inner: self.inner.info.clone() }
+  }
+
+  /// Source descriptions the workflows use
+  #[getter]
+  fn source_descriptions(&self) -> Vec<PySourceDescription> {
+    self.inner.source_descriptions.iter()
+      .map(|source| PySourceDescription { inner: source.clone() })
+      .collect()
+  }
+
+  /// Workflows of the description
+  #[getter]
+  fn workflows(&self) -> Vec<PyWorkflow> {
+    self.inner.workflows.iter()
+      .map(|workflow| PyWorkflow { inner: workflow.clone() })
+      .collect()
+  }
+
+  /// Components of the description, as a dictionary
+  #[getter]
+  fn components(&self, py: Python<'_>) -> PyResult<PyObject> {
+    serialize_to_python(py, &self.inner.components)
+  }
+
+  /// Extension values, keyed with the `x-` prefix
+  #[getter]
+  fn extensions(&self, py: Python<'_>) -> PyResult<PyObject> {
+    extensions_to_python(py, &self.inner.extensions)
+  }
+
+  /// Returns the workflow with the ID, or `None` if there is no workflow with that ID
+  fn workflow(&self, workflow_id: &str) -> Option<PyWorkflow> {
+    self.inner.workflows.iter()
+      .find(|workflow| workflow.workflow_id == workflow_id)
+      .map(|workflow| PyWorkflow { inner: workflow.clone() })
+  }
+
+  /// Validates the description, returning all the problems found
+  fn validate(&self) -> Vec<PyValidationIssue> {
+    validate(self)
+  }
+
+  /// Returns the description as a dictionary, in the same form as the Arazzo document
+  fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+    serialize_to_python(py, &self.inner)
+  }
+
+  /// Returns the description as a JSON document
+  #[pyo3(signature = (pretty = false))]
+  fn to_json(&self, pretty: bool) -> PyResult<String> {
+    if pretty {
+      serde_json::to_string_pretty(&self.inner).map_err(arazzo_error)
+    } else {
+      serde_json::to_string(&self.inner).map_err(arazzo_error)
+    }
+  }
+
+  fn __repr__(&self) -> String {
+    format!("ArazzoDescription(title={:?}, version={:?}, workflows={})", self.inner.info.title,
+      self.inner.info.version, self.inner.workflows.len())
+  }
+}
+
+/// Metadata about the workflows of a description
+#[pyclass(name = "Info", module = "arazzo")]
+#[derive(Clone)]
+struct PyInfo {
+  inner: Info
+}
+
+#[pymethods]
+impl PyInfo {
+  /// Title of the description
+  #[getter]
+  fn title(&self) -> String {
+    self.inner.title.clone()
+  }
+
+  /// Short summary of the description
+  #[getter]
+  fn summary(&self) -> Option<String> {
+    self.inner.summary.clone()
+  }
+
+  /// Description of the purpose of the workflows
+  #[getter]
+  fn description(&self) -> Option<String> {
+    self.inner.description.clone()
+  }
+
+  /// Version of the description
+  #[getter]
+  fn version(&self) -> String {
+    self.inner.version.clone()
+  }
+
+  /// Extension values, keyed with the `x-` prefix
+  #[getter]
+  fn extensions(&self, py: Python<'_>) -> PyResult<PyObject> {
+    extensions_to_python(py, &self.inner.extensions)
+  }
+
+  fn __repr__(&self) -> String {
+    format!("Info(title={:?}, version={:?})", self.inner.title, self.inner.version)
+  }
+}
+
+/// Source description (OpenAPI or Arazzo document) used by the workflows
+#[pyclass(name = "SourceDescription", module = "arazzo")]
+#[derive(Clone)]
+struct PySourceDescription {
+  inner: SourceDescription
+}
+
+#[pymethods]
+impl PySourceDescription {
+  /// Name of the source description
+  #[getter]
+  fn name(&self) -> String {
+    self.inner.name.clone()
+  }
+
+  /// URL of the source description
+  #[getter]
+  fn url(&self) -> String {
+    self.inner.url.clone()
+  }
+
+  /// Type of the source description (`openapi` or `arazzo`)
+  #[getter]
+  fn r#type(&self) -> Option<String> {
+    self.inner.r#type.clone()
+  }
+
+  /// Extension values, keyed with the `x-` prefix
+  #[getter]
+  fn extensions(&self, py: Python<'_>) -> PyResult<PyObject> {
+    extensions_to_python(py, &self.inner.extensions)
+  }
+
+  fn __repr__(&self) -> String {
+    format!("SourceDescription(name={:?}, url={:?})", self.inner.name, self.inner.url)
+  }
+}
+
+/// Workflow of a description
+#[pyclass(name = "Workflow", module = "arazzo")]
+#[derive(Clone)]
+struct PyWorkflow {
+  inner: Workflow
+}
+
+#[pymethods]
+impl PyWorkflow {
+  /// ID of the workflow
+  #[getter]
+  fn workflow_id(&self) -> String {
+    self.inner.workflow_id.clone()
+  }
+
+  /// Summary of the purpose of the workflow
+  #[getter]
+  fn summary(&self) -> Option<String> {
+    self.inner.summary.clone()
+  }
+
+  /// Description of the workflow
+  #[getter]
+  fn description(&self) -> Option<String> {
+    self.inner.description.clone()
+  }
+
+  /// JSON Schema of the inputs of the workflow
+  #[getter]
+  fn inputs(&self, py: Python<'_>) -> PyResult<PyObject> {
+    to_python(py, &self.inner.inputs)
+  }
+
+  /// Workflows that must complete before this workflow runs
+  #[getter]
+  fn depends_on(&self) -> Vec<String> {
+    self.inner.depends_on.clone()
+  }
+
+  /// Steps of the workflow
+  #[getter]
+  fn steps(&self) -> Vec<PyStep> {
+    self.inner.steps.iter()
+      .map(|step| PyStep { inner: step.clone() })
+      .collect()
+  }
+
+  /// Success actions of the workflow, as dictionaries
+  #[getter]
+  fn success_actions(&self, py: Python<'_>) -> PyResult<PyObject> {
+    serialize_to_python(py, &self.inner.success_actions)
+  }
+
+  /// Failure actions of the workflow, as dictionaries
+  #[getter]
+  fn failure_actions(&self, py: Python<'_>) -> PyResult<PyObject> {
+    serialize_to_python(py, &self.inner.failure_actions)
+  }
+
+  /// Outputs of the workflow, as runtime expressions keyed by name
+  #[getter]
+  fn outputs(&self) -> BTreeMap<String, String> {
+    self.inner.outputs.clone()
+  }
+
+  /// Parameters that apply to all the steps of the workflow, as dictionaries
+  #[getter]
+  fn parameters(&self, py: Python<'_>) -> PyResult<PyObject> {
+    serialize_to_python(py, &self.inner.parameters)
+  }
+
+  /// Extension values, keyed with the `x-` prefix
+  #[getter]
+  fn extensions(&self, py: Python<'_>) -> PyResult<PyObject> {
+    extensions_to_python(py, &self.inner.extensions)
+  }
+
+  /// Returns the step with the ID, or `None` if there is no step with that ID
+  fn step(&self, step_id: &str) -> Option<PyStep> {
+    self.inner.steps.iter()
+      .find(|step| step.step_id == step_id)
+      .map(|step| PyStep { inner: step.clone() })
+  }
+
+  fn __repr__(&self) -> String {
+    format!("Workflow(workflow_id={:?}, steps={})", self.inner.workflow_id, self.inner.steps.len())
+  }
+}
+
+/// Step of a workflow
+#[pyclass(name = "Step", module = "arazzo")]
+#[derive(Clone)]
+struct PyStep {
+  inner: Step
+}
+
+#[pymethods]
+impl PyStep {
+  /// ID of the step
+  #[getter]
+  fn step_id(&self) -> String {
+    self.inner.step_id.clone()
+  }
+
+  /// Description of the step
+  #[getter]
+  fn description(&self) -> Option<String> {
+    self.inner.description.clone()
+  }
+
+  /// ID of the operation the step calls
+  #[getter]
+  fn operation_id(&self) -> Option<String> {
+    self.inner.operation_id.clone()
+  }
+
+  /// Reference to the operation the step calls
+  #[getter]
+  fn operation_path(&self) -> Option<String> {
+    self.inner.operation_path.clone()
+  }
+
+  /// ID of the workflow the step executes
+  #[getter]
+  fn workflow_id(&self) -> Option<String> {
+    self.inner.workflow_id.clone()
+  }
+
+  /// Parameters of the step, as dictionaries
+  #[getter]
+  fn parameters(&self, py: Python<'_>) -> PyResult<PyObject> {
+    serialize_to_python(py, &self.inner.parameters)
+  }
+
+  /// Request body of the step, as a dictionary
+  #[getter]
+  fn request_body(&self, py: Python<'_>) -> PyResult<PyObject> {
+    serialize_to_python(py, &self.inner.request_body)
+  }
+
+  /// Success criteria of the step
+  #[getter]
+  fn success_criteria(&self) -> Vec<PyCriterion> {
+    self.inner.success_criteria.iter()
+      .map(|criterion| PyCriterion { inner: criterion.clone() })
+      .collect()
+  }
+
+  /// Success actions of the step, as dictionaries
+  #[getter]
+  fn on_success(&self, py: Python<'_>) -> PyResult<PyObject> {
+    serialize_to_python(py, &self.inner.on_success)
+  }
+
+  /// Failure actions of the step, as dictionaries
+  #[getter]
+  fn on_failure(&self, py: Python<'_>) -> PyResult<PyObject> {
+    serialize_to_python(py, &self.inner.on_failure)
+  }
+
+  /// Outputs of the step, as runtime expressions keyed by name
+  #[getter]
+  fn outputs(&self) -> BTreeMap<String, String> {
+    self.inner.outputs.clone()
+  }
+
+  /// Extension values, keyed with the `x-` prefix
+  #[getter]
+  fn extensions(&self, py: Python<'_>) -> PyResult<PyObject> {
+    extensions_to_python(py, &self.inner.extensions)
+  }
+
+  fn __repr__(&self) -> String {
+    format!("Step(step_id={:?})", self.inner.step_id)
+  }
+}
+
+/// Criterion of a step or action
+#[pyclass(name = "Criterion", module = "arazzo")]
+#[derive(Clone)]
+struct PyCriterion {
+  inner: Criterion
+}
+
+#[pymethods]
+impl PyCriterion {
+  /// Condition to evaluate
+  #[getter]
+  fn condition(&self) -> String {
+    self.inner.condition.clone()
+  }
+
+  /// Runtime expression of the value the condition is applied to
+  #[getter]
+  fn context(&self) -> Option<String> {
+    self.inner.context.clone()
+  }
+
+  /// Type of the condition (`simple`, `regex`, `jsonpath` or `xpath`)
+  #[getter]
+  fn r#type(&self) -> String {
+    match &self.inner.r#type {
+      Some(Either::First(criterion_type)) => criterion_type.clone(),
+      Some(Either::Second(expression_type)) => expression_type.r#type.clone(),
+      None => "simple".to_string()
+    }
+  }
+
+  fn __repr__(&self) -> String {
+    format!("Criterion(condition={:?})", self.inner.condition)
+  }
+}
+
+/// Problem found when validating a description
+#[pyclass(name = "ValidationIssue", module = "arazzo", get_all)]
+#[derive(Clone)]
+struct PyValidationIssue {
+  /// JSON Pointer to the location of the problem in the document
+  path: String,
+  /// Description of the problem
+  message: String
+}
+
+impl From<ValidationIssue> for PyValidationIssue {
+  fn from(issue: ValidationIssue) -> Self {
+    PyValidationIssue { path: issue.path, message: issue.message }
+  }
+}
+
+#[pymethods]
+impl PyValidationIssue {
+  fn __repr__(&self) -> String {
+    format!("ValidationIssue(path={:?}, message={:?})", self.path, self.message)
+  }
+
+  fn __str__(&self) -> String {
+    format!("{}: {}", self.path, self.message)
+  }
+}
+
+/// Loads a description from the text of a JSON or YAML document
+#[pyfunction]
+fn loads(text: &str) -> PyResult<PyArazzoDescription> {
+  let inner = match serde_json::from_str::<Value>(text) {
+    Ok(json) => ArazzoDescription::try_from(&json).map_err(arazzo_error)?,
+    Err(_) => {
+      let documents = crate::yaml::yaml_load_documents(text).map_err(arazzo_error)?;
+      let document = documents.first().ok_or_else(|| arazzo_error("The document is empty"))?;
+      ArazzoDescription::try_from(document).map_err(arazzo_error)?
+    }
+  };
+  Ok(PyArazzoDescription { inner })
+}
+
+/// Loads a description from a JSON or YAML file
+#[pyfunction]
+fn load(path: &str) -> PyResult<PyArazzoDescription> {
+  let text = fs::read_to_string(path)
+    .map_err(|err| arazzo_error(format!("Failed to load '{}': {}", path, err)))?;
+  loads(&text)
+}
+
+/// Loads a description from a dictionary in the same form as the Arazzo document
+#[pyfunction]
+fn from_dict(value: &Bound<'_, PyAny>) -> PyResult<PyArazzoDescription> {
+  let json = from_python(value)?;
+  let inner = ArazzoDescription::try_from(&json).map_err(arazzo_error)?;
+  Ok(PyArazzoDescription { inner })
+}
+
+/// Validates the description, returning all the problems found
+#[pyfunction]
+fn validate(description: &PyArazzoDescription) -> Vec<PyValidationIssue> {
+  validate_description(&description.inner).into_iter()
+    .map(PyValidationIssue::from)
+    .collect()
+}
+
+/// Python module for Arazzo descriptions
+#[pymodule]
+pub fn arazzo(m: &Bound<'_, PyModule>) -> PyResult<()> {
+  m.add("ArazzoError", m.py().get_type::<ArazzoError>())?;
+  m.add_class::<PyArazzoDescription>()?;
+  m.add_class::<PyInfo>()?;
+  m.add_class::<PySourceDescription>()?;
+  m.add_class::<PyWorkflow>()?;
+  m.add_class::<PyStep>()?;
+  m.add_class::<PyCriterion>()?;
+  m.add_class::<PyValidationIssue>()?;
+  m.add_function(wrap_pyfunction!(load, m)?)?;
+  m.add_function(wrap_pyfunction!(loads, m)?)?;
+  m.add_function(wrap_pyfunction!(from_dict, m)?)?;
+  m.add_function(wrap_pyfunction!(validate, m)?)?;
+  Ok(())
+}
@@ -0,0 +1,237 @@
+//! Request rate limits and retry backoff for the [`Executor`](crate::executor::Executor), so
+//! workflow runs do not trip the rate limits of APIs in shared environments.
+//!
+//! Rate limits are set per source description or host in the
+//! [`ExecutorOptions`](crate::executor::ExecutorOptions). Before a request is sent, the executor
+//! waits (with its [`RetryTimer`](crate::executor::RetryTimer)) until the request is within the
+//! limits. Requests to a source or host share the limit, even when workflows are executed
+//! concurrently.
+//!
+//! A [`BackoffStrategy`] sets the delay before a step is retried by a `retry` failure action. The
+//! strategy is layered on the `retryAfter` of the action, and the `Retry-After` header of `429` and
+//! `503` responses: the longest of the delays is used.
+
+use std::collections::{HashMap, VecDeque};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+
+use crate::execution_context::StepResponse;
+
+/// Maximum number of requests in a period of time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+  /// Number of requests allowed in the period
+  pub requests: u32,
+  /// Length of the period
+  pub per: Duration
+}
+
+impl RateLimit {
+  /// Creates a rate limit of the number of requests in the period
+  pub fn new(requests: u32, per: Duration) -> Self {
+    RateLimit { requests, per }
+  }
+
+  /// Rate limit of the number of requests per second
+  pub fn per_second(requests: u32) -> Self {
+    RateLimit::new(requests, Duration::from_secs(1))
+  }
+
+  /// Rate limit of the number of requests per minute
+  pub fn per_minute(requests: u32) -> Self {
+    RateLimit::new(requests, Duration::from_secs(60))
+  }
+
+  /// Checks that requests are allowed in a non-empty period
+  pub fn validate(&self) -> anyhow::Result<()> {
+    if self.requests == 0 || self.per.is_zero() {
+      Err(anyhow!("Rate limits must allow at least one request in a non-zero period, got {} per {:?}",
+        self.requests, self.per))
+    } else {
+      Ok(())
+    }
+  }
+}
+
+/// Strategy for the delay before a step is retried
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BackoffStrategy {
+  /// Only wait for the `retryAfter` of the action (and the `Retry-After` header)
+  #[default]
+  None,
+  /// Wait the same delay before each retry
+  Fixed(Duration),
+  /// Wait `initial * multiplier ^ (retry - 1)`, up to `max`. With `jitter`, a random delay of up
+  /// to half the delay is taken off (so clients that failed together do not retry together).
+  Exponential {
+    /// Delay before the first retry
+    initial: Duration,
+    /// Factor the delay is multiplied by for each retry
+    multiplier: f64,
+    /// Maximum delay
+    max: Duration,
+    /// If the delays are randomised
+    jitter: bool
+  }
+}
+
+impl BackoffStrategy {
+  /// Exponential backoff, doubling the delay for each retry up to the maximum, with jitter
+  pub fn exponential(initial: Duration, max: Duration) -> Self {
+    BackoffStrategy::Exponential { initial, multiplier: 2.0, max, jitter: true }
+  }
+
+  /// Returns the delay of the strategy before the retry (starting at 1 for the first retry)
+  pub fn backoff(&self, retry: usize) -> Duration {
+    match self {
+      BackoffStrategy::None => Duration::ZERO,
+      BackoffStrategy::Fixed(delay) => *delay,
+      BackoffStrategy::Exponential { initial, multiplier, max, jitter } => {
+        let exponent = i32::try_from(retry.saturating_sub(1)).unwrap_or(i32::MAX);
+        let delay = initial.as_secs_f64() * multiplier.max(1.0).powi(exponent);
+        let delay = Duration::try_from_secs_f64(delay).unwrap_or(*max).min(*max);
+        if *jitter {
+          delay.mul_f64(1.0 - random_fraction() / 2.0)
+        } else {
+          delay
+        }
+      }
+    }
+  }
+
+  /// Returns the delay before the retry (starting at 1 for the first retry), which is the longest
+  /// of the backoff, the `retryAfter` of the action, and the `Retry-After` header of the response
+  /// (for `429` and `503` responses). Returns `None` if there is no delay.
+  pub fn delay(&self, retry: usize, retry_after: Option<Duration>, response: Option<&StepResponse>) -> Option<Duration> {
+    let delay = [Some(self.backoff(retry)), retry_after, response.and_then(retry_after_header)]
+      .into_iter()
+      .flatten()
+      .max()
+      .unwrap_or_default();
+    (!delay.is_zero()).then_some(delay)
+  }
+
+  /// Checks that the delays are valid
+  pub fn validate(&self) -> anyhow::Result<()> {
+    match self {
+      BackoffStrategy::Exponential { multiplier, .. } if !multiplier.is_finite() || *multiplier < 1.0 =>
+        Err(anyhow!("The backoff multiplier must be at least 1, got {}", multiplier)),
+      BackoffStrategy::Exponential { initial, max, .. } if initial > max =>
+        Err(anyhow!("The initial backoff ({:?}) is greater than the maximum ({:?})", initial, max)),
+      _ => Ok(())
+    }
+  }
+}
+
+/// Delay of the `Retry-After` header (in seconds) of `429` and `503` responses
+fn retry_after_header(response: &StepResponse) -> Option<Duration> {
+  if response.status != 429 && response.status != 503 {
+    return None;
+  }
+  response.headers.iter()
+    .find(|(name, _)| name.eq_ignore_ascii_case("retry-after"))
+    .and_then(|(_, value)| value.trim().parse::<u64>().ok())
+    .map(Duration::from_secs)
+}
+
+/// Random number in the range `[0, 1)`
+fn random_fraction() -> f64 {
+  (RandomState::new().build_hasher().finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Tracks the requests sent for each key (source description or host), and schedules new requests
+/// so they are within the rate limit
+#[derive(Debug, Default)]
+pub(crate) struct RateLimiter {
+  requests: Mutex<HashMap<String, VecDeque<Instant>>>
+}
+
+impl RateLimiter {
+  /// Reserves a slot for a request, returning how long to wait before the request can be sent
+  pub(crate) fn acquire(&self, key: &str, limit: &RateLimit, now: Instant) -> Duration {
+    let mut requests = self.requests.lock().unwrap_or_else(|err| err.into_inner());
+    let sent = requests.entry(key.to_string()).or_default();
+    while sent.front().is_some_and(|sent_at| *sent_at + limit.per <= now) {
+      sent.pop_front();
+    }
+    let allowed = limit.requests.max(1) as usize;
+    let slot = if sent.len() < allowed {
+      now
+    } else {
+      (sent[sent.len() - allowed] + limit.per).max(now)
+    };
+    sent.push_back(slot);
+    slot - now
+  }
+}
+
+/// Host (and port, if there is one) of the URL, in lowercase
+pub(crate) fn url_host(url: &str) -> Option<String> {
+  let (_, rest) = url.split_once("://")?;
+  let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+  let host = authority.rsplit_once('@').map(|(_, host)| host).unwrap_or(authority);
+  (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::{Duration, Instant};
+
+  use expectest::prelude::*;
+
+  use crate::execution_context::StepResponse;
+  use crate::rate_limit::{url_host, BackoffStrategy, RateLimit, RateLimiter};
+
+  #[test]
+  fn schedules_requests_within_the_rate_limit() {
+    let limiter = RateLimiter::default();
+    let limit = RateLimit::new(2, Duration::from_secs(1));
+    let now = Instant::now();
+    expect!(limiter.acquire("petstore", &limit, now)).to(be_equal_to(Duration::ZERO));
+    expect!(limiter.acquire("petstore", &limit, now)).to(be_equal_to(Duration::ZERO));
+    expect!(limiter.acquire("petstore", &limit, now)).to(be_equal_to(Duration::from_secs(1)));
+    expect!(limiter.acquire("petstore", &limit, now)).to(be_equal_to(Duration::from_secs(1)));
+    expect!(limiter.acquire("petstore", &limit, now)).to(be_equal_to(Duration::from_secs(2)));
+    expect!(limiter.acquire("other", &limit, now)).to(be_equal_to(Duration::ZERO));
+    expect!(limiter.acquire("petstore", &limit, now + Duration::from_secs(5))).to(be_equal_to(Duration::ZERO));
+
+    expect!(RateLimit::per_second(0).validate()).to(be_err());
+    expect!(RateLimit::per_minute(10).validate()).to(be_ok());
+    expect!(url_host("https://user@API.example.com:8443/v1?q=1")).to(be_some().value("api.example.com:8443"));
+  }
+
+  #[test]
+  fn calculates_the_retry_delays() {
+    expect!(BackoffStrategy::None.delay(1, None, None)).to(be_none());
+    expect!(BackoffStrategy::None.delay(1, Some(Duration::from_secs(2)), None)).to(be_some().value(Duration::from_secs(2)));
+    expect!(BackoffStrategy::Fixed(Duration::from_secs(3)).delay(1, Some(Duration::from_secs(2)), None))
+      .to(be_some().value(Duration::from_secs(3)));
+
+    let backoff = BackoffStrategy::Exponential {
+      initial: Duration::from_millis(100),
+      multiplier: 2.0,
+      max: Duration::from_secs(1),
+      jitter: false
+    };
+    expect!(backoff.backoff(1)).to(be_equal_to(Duration::from_millis(100)));
+    expect!(backoff.backoff(3)).to(be_equal_to(Duration::from_millis(400)));
+    expect!(backoff.backoff(10)).to(be_equal_to(Duration::from_secs(1)));
+    expect!(backoff.backoff(usize::MAX)).to(be_equal_to(Duration::from_secs(1)));
+
+    let jittered = BackoffStrategy::exponential(Duration::from_millis(100), Duration::from_secs(1)).backoff(2);
+    expect!(jittered >= Duration::from_millis(100) && jittered <= Duration::from_millis(200)).to(be_true());
+
+    let response = StepResponse::new(429).with_header("Retry-After", "5");
+    expect!(backoff.delay(1, None, Some(&response))).to(be_some().value(Duration::from_secs(5)));
+    let response = StepResponse::new(500).with_header("Retry-After", "5");
+    expect!(backoff.delay(1, None, Some(&response))).to(be_some().value(Duration::from_millis(100)));
+
+    expect!(BackoffStrategy::Exponential {
+      initial: Duration::from_secs(2), multiplier: 2.0, max: Duration::from_secs(1), jitter: false
+    }.validate()).to(be_err());
+  }
+}
@@ -0,0 +1,429 @@
+//! Redaction of secrets, so tokens and passwords do not end up in execution reports, logs or
+//! CI artefacts. A [`Redactor`] marks values as sensitive by the name they are stored under
+//! (headers, query parameters, JSON body fields, inputs and outputs), and replaces any values
+//! known to be secrets (i.e. the values of sensitive inputs) wherever they appear.
+//!
+//! Names are sensitive if they match one of the patterns of the redactor, or they are the names
+//! of parameters or input properties marked with the `x-secret` extension:
+//!
+//! ```yaml
+//! inputs:
+//!   type: object
+//!   properties:
+//!     clientKey:
+//!       type: string
+//!       x-secret: true
+//! ```
+
+use std::collections::BTreeSet;
+
+use bytes::Bytes;
+use serde_json::Value;
+
+use crate::either::Either;
+use crate::execution_context::{StepRequest, StepResponse};
+use crate::extensions::AnyValue;
+use crate::v1_0::{ArazzoDescription, ParameterObject, ReusableObject};
+
+/// Value that redacted secrets are replaced with
+pub const MASK: &str = "***";
+
+/// Key of the extension that marks parameters and input properties as secrets (without the `x-`
+/// prefix)
+pub const SECRET_EXTENSION: &str = "secret";
+
+/// Redacts sensitive values
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redactor {
+  /// Patterns of sensitive names. Names are matched ignoring case, `-` and `_`, and `*` in a
+  /// pattern matches any characters (i.e. `*token*` matches `X-Access-Token`).
+  pub patterns: Vec<String>,
+  /// Values that are secrets, which are replaced wherever they appear
+  pub values: BTreeSet<String>
+}
+
+impl Default for Redactor {
+  fn default() -> Self {
+    Redactor {
+      patterns: [
+        "*password*", "*secret*", "*token*", "*apikey*", "*credential*", "authorization",
+        "proxy-authorization", "cookie", "set-cookie"
+      ].iter().map(|pattern| pattern.to_string()).collect(),
+      values: BTreeSet::new()
+    }
+  }
+}
+
+impl Redactor {
+  /// Creates a redactor with the default patterns (passwords, secrets, tokens, API keys,
+  /// credentials, and the authorization and cookie headers)
+  pub fn new() -> Self {
+    Redactor::default()
+  }
+
+  /// Creates a redactor without any patterns
+  pub fn empty() -> Self {
+    Redactor { patterns: vec![], values: BTreeSet::new() }
+  }
+
+  /// Adds a pattern of sensitive names
+  pub fn with_pattern<S: Into<String>>(mut self, pattern: S) -> Self {
+    self.patterns.push(pattern.into());
+    self
+  }
+
+  /// Adds a secret value. Empty values are ignored.
+  pub fn with_value<S: Into<String>>(mut self, value: S) -> Self {
+    let value = value.into();
+    if !value.is_empty() {
+      self.values.insert(value);
+    }
+    self
+  }
+
+  /// Adds the names of the parameters and input properties of the Arazzo description that are
+  /// marked with the `x-secret` extension
+  pub fn with_description(mut self, description: &ArazzoDescription) -> Self {
+    let parameters = description.workflows.iter()
+      .flat_map(|workflow| workflow.parameters.iter()
+        .chain(workflow.steps.iter().flat_map(|step| step.parameters.iter())))
+      .filter_map(|parameter| match parameter {
+        Either::First(parameter) => Some(parameter),
+        Either::Second(_) => None
+      })
+      .chain(description.components.parameters.values());
+    for parameter in parameters {
+      if is_marked_secret(parameter) && !self.patterns.contains(&parameter.name) {
+        self.patterns.push(parameter.name.clone());
+      }
+    }
+
+    let mut names = BTreeSet::new();
+    for schema in description.workflows.iter().map(|workflow| &workflow.inputs)
+      .chain(description.components.inputs.values()) {
+      secret_properties(schema, &mut names);
+    }
+    for name in names {
+      if !self.patterns.contains(&name) {
+        self.patterns.push(name);
+      }
+    }
+    self
+  }
+
+  /// Adds the values of the sensitive inputs as secret values (so they are also redacted where
+  /// they are used under another name, i.e. in a URL)
+  pub fn with_inputs(mut self, inputs: &AnyValue) -> Self {
+    let mut values = vec![];
+    self.collect_secrets(inputs, false, &mut values);
+    for value in values {
+      self = self.with_value(value);
+    }
+    self
+  }
+
+  fn collect_secrets(&self, value: &AnyValue, sensitive: bool, secrets: &mut Vec<String>) {
+    match value {
+      AnyValue::Object(map) => for (key, value) in map {
+        self.collect_secrets(value, sensitive || self.is_sensitive(key), secrets);
+      },
+      AnyValue::Array(values) => for value in values {
+        self.collect_secrets(value, sensitive, secrets);
+      },
+      AnyValue::Null | AnyValue::Binary(_) => {}
+      AnyValue::String(value) if sensitive => secrets.push(value.clone()),
+      value if sensitive => secrets.push(value.to_json().to_string()),
+      _ => {}
+    }
+  }
+
+  /// If values stored under the name are sensitive
+  pub fn is_sensitive(&self, name: &str) -> bool {
+    let name = normalise(name);
+    self.patterns.iter().any(|pattern| glob_match(&normalise(pattern), &name))
+  }
+
+  /// Replaces the secret values in the text
+  pub fn redact_str(&self, text: &str) -> String {
+    // Longer values first, so a secret that contains another secret is fully replaced
+    let mut values = self.values.iter().collect::<Vec<_>>();
+    values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+    values.iter().fold(text.to_string(), |text, value| text.replace(value.as_str(), MASK))
+  }
+
+  /// Redacts the values of sensitive query parameters, and any secret values, in the URL
+  pub fn redact_url(&self, url: &str) -> String {
+    let url = match url.split_once('?') {
+      Some((base, query)) => {
+        let query = query.split('&')
+          .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if self.is_sensitive(name) => format!("{}={}", name, MASK),
+            _ => pair.to_string()
+          })
+          .collect::<Vec<_>>();
+        format!("{}?{}", base, query.join("&"))
+      }
+      None => url.to_string()
+    };
+    self.redact_str(&url)
+  }
+
+  /// Redacts the values of sensitive headers, and any secret values in the other headers
+  pub fn redact_headers(&self, headers: &[(String, String)]) -> Vec<(String, String)> {
+    headers.iter()
+      .map(|(name, value)| if self.is_sensitive(name) {
+        (name.clone(), MASK.to_string())
+      } else {
+        (name.clone(), self.redact_str(value))
+      })
+      .collect()
+  }
+
+  /// Redacts the sensitive fields of a JSON body, and any secret values in the body
+  pub fn redact_body(&self, body: &Bytes) -> Bytes {
+    match serde_json::from_slice::<Value>(body) {
+      Ok(mut json) => {
+        self.redact_json(&mut json);
+        Bytes::from(json.to_string())
+      }
+      Err(_) => Bytes::from(self.redact_str(&String::from_utf8_lossy(body)))
+    }
+  }
+
+  /// Redacts the sensitive fields of the JSON value, and any secret values in strings
+  pub fn redact_json(&self, json: &mut Value) {
+    match json {
+      Value::Object(map) => for (key, value) in map.iter_mut() {
+        if self.is_sensitive(key) && !value.is_object() && !value.is_array() {
+          *value = Value::String(MASK.to_string());
+        } else {
+          self.redact_json(value);
+        }
+      },
+      Value::Array(values) => for value in values {
+        self.redact_json(value);
+      },
+      Value::String(value) => *value = self.redact_str(value),
+      _ => {}
+    }
+  }
+
+  /// Redacts the value stored under the name. The whole value is replaced if the name is
+  /// sensitive, otherwise the sensitive fields of objects, and secret values in strings, are.
+  pub fn redact_value(&self, name: &str, value: &AnyValue) -> AnyValue {
+    if self.is_sensitive(name) {
+      return AnyValue::String(MASK.to_string());
+    }
+    match value {
+      AnyValue::Object(map) => AnyValue::Object(map.iter()
+        .map(|(key, value)| (key.clone(), self.redact_value(key, value)))
+        .collect()),
+      AnyValue::Array(values) => AnyValue::Array(values.iter()
+        .map(|value| self.redact_value("", value))
+        .collect()),
+      AnyValue::String(value) => AnyValue::String(self.redact_str(value)),
+      value => value.clone()
+    }
+  }
+
+  /// Returns the request with the secrets redacted
+  pub fn redact_request(&self, request: &StepRequest) -> StepRequest {
+    StepRequest {
+      method: request.method.clone(),
+      url: self.redact_url(&request.url),
+      headers: self.redact_headers(&request.headers),
+      body: request.body.as_ref().map(|body| self.redact_body(body))
+    }
+  }
+
+  /// Returns the response with the secrets redacted
+  pub fn redact_response(&self, response: &StepResponse) -> StepResponse {
+    StepResponse {
+      status: response.status,
+      headers: self.redact_headers(&response.headers),
+      body: self.redact_body(&response.body)
+    }
+  }
+
+  /// Returns a copy of the Arazzo description with the literal values of sensitive parameters
+  /// replaced, so it can be serialized without leaking secrets. Parameters with runtime
+  /// expressions are kept, as the expressions are not secrets.
+  pub fn redact_description(&self, description: &ArazzoDescription) -> ArazzoDescription {
+    let mut description = description.clone();
+    for workflow in &mut description.workflows {
+      self.redact_parameters(&mut workflow.parameters);
+      for step in &mut workflow.steps {
+        self.redact_parameters(&mut step.parameters);
+      }
+    }
+    for parameter in description.components.parameters.values_mut() {
+      self.redact_parameter(parameter);
+    }
+    description
+  }
+
+  fn redact_parameters(&self, parameters: &mut [Either<ParameterObject, ReusableObject>]) {
+    for parameter in parameters {
+      if let Either::First(parameter) = parameter {
+        self.redact_parameter(parameter);
+      }
+    }
+  }
+
+  fn redact_parameter(&self, parameter: &mut ParameterObject) {
+    if let Either::First(value) = &parameter.value {
+      let value = if self.is_sensitive(&parameter.name) || is_marked_secret(parameter) {
+        AnyValue::String(MASK.to_string())
+      } else {
+        self.redact_value("", value)
+      };
+      parameter.value = Either::First(value);
+    }
+  }
+}
+
+fn is_marked_secret(parameter: &ParameterObject) -> bool {
+  parameter.extensions.get(SECRET_EXTENSION).and_then(|value| value.as_bool()).unwrap_or_default()
+}
+
+fn secret_properties(schema: &Value, names: &mut BTreeSet<String>) {
+  match schema {
+    Value::Object(map) => {
+      if let Some(Value::Object(properties)) = map.get("properties") {
+        for (name, property) in properties {
+          if property.get("x-secret").and_then(|value| value.as_bool()).unwrap_or_default() {
+            names.insert(name.clone());
+          }
+        }
+      }
+      for value in map.values() {
+        secret_properties(value, names);
+      }
+    }
+    Value::Array(values) => for value in values {
+      secret_properties(value, names);
+    },
+    _ => {}
+  }
+}
+
+fn normalise(name: &str) -> String {
+  name.to_lowercase().replace(['-', '_'], "")
+}
+
+fn glob_match(pattern: &str, name: &str) -> bool {
+  let mut parts = pattern.split('*');
+  let first = parts.next().unwrap_or_default();
+  let Some(mut rest) = name.strip_prefix(first) else {
+    return false;
+  };
+  let parts = parts.collect::<Vec<_>>();
+  match parts.split_last() {
+    None => rest.is_empty(),
+    Some((last, middle)) => {
+      for part in middle {
+        match rest.find(part) {
+          Some(index) => rest = &rest[index + part.len()..],
+          None => return false
+        }
+      }
+      rest.len() >= last.len() && rest.ends_with(last)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use bytes::Bytes;
+  use expectest::prelude::*;
+  use indexmap::indexmap;
+  use maplit::hashmap;
+  use serde_json::json;
+
+  use crate::either::Either;
+  use crate::execution_context::StepRequest;
+  use crate::extensions::AnyValue;
+  use crate::redaction::Redactor;
+  use crate::v1_0::{ArazzoDescription, ParameterObject, Step, Workflow};
+
+  #[test]
+  fn matches_sensitive_names() {
+    let redactor = Redactor::new().with_pattern("pin");
+    expect!(redactor.is_sensitive("X-Access-Token")).to(be_true());
+    expect!(redactor.is_sensitive("api_key")).to(be_true());
+    expect!(redactor.is_sensitive("Authorization")).to(be_true());
+    expect!(redactor.is_sensitive("PIN")).to(be_true());
+    expect!(redactor.is_sensitive("pinned")).to(be_false());
+    expect!(redactor.is_sensitive("petId")).to(be_false());
+    expect!(Redactor::empty().is_sensitive("password")).to(be_false());
+  }
+
+  #[test]
+  fn redacts_requests() {
+    let redactor = Redactor::new()
+      .with_pattern("clientKey")
+      .with_inputs(&AnyValue::Object(indexmap!{
+        "clientKey".to_string() => AnyValue::String("k-123".to_string()),
+        "user".to_string() => AnyValue::String("fido".to_string())
+      }));
+    let request = redactor.redact_request(&StepRequest {
+      method: "POST".to_string(),
+      url: "http://localhost/login?token=abc&user=fido&key=k-123".to_string(),
+      headers: vec![
+        ("Authorization".to_string(), "Bearer abc".to_string()),
+        ("X-Client".to_string(), "client k-123".to_string())
+      ],
+      body: Some(Bytes::from(json!({ "password": "hunter2", "user": { "name": "fido", "note": "k-123" } }).to_string()))
+    });
+    expect!(request.url).to(be_equal_to("http://localhost/login?token=***&user=fido&key=***"));
+    expect!(request.headers).to(be_equal_to(vec![
+      ("Authorization".to_string(), "***".to_string()),
+      ("X-Client".to_string(), "client ***".to_string())
+    ]));
+    let body: serde_json::Value = serde_json::from_slice(&request.body.unwrap()).unwrap();
+    expect!(body).to(be_equal_to(json!({ "password": "***", "user": { "name": "fido", "note": "***" } })));
+    expect!(redactor.redact_value("outputs", &AnyValue::Object(indexmap!{
+      "accessToken".to_string() => AnyValue::String("abc".to_string())
+    }))).to(be_equal_to(AnyValue::Object(indexmap!{
+      "accessToken".to_string() => AnyValue::String("***".to_string())
+    })));
+  }
+
+  #[test]
+  fn redacts_parameters_marked_as_secrets() {
+    let description = ArazzoDescription {
+      workflows: vec![Workflow {
+        workflow_id: "login".to_string(),
+        inputs: json!({ "type": "object", "properties": { "pin": { "type": "string", "x-secret": true } } }),
+        steps: vec![Step {
+          step_id: "login".to_string(),
+          parameters: vec![
+            Either::First(ParameterObject {
+              name: "X-Client".to_string(),
+              r#in: Some("header".to_string()),
+              value: Either::First(AnyValue::String("c-1".to_string())),
+              extensions: hashmap!{ "secret".to_string() => AnyValue::Boolean(true) }
+            }),
+            Either::First(ParameterObject {
+              name: "pin".to_string(),
+              r#in: Some("query".to_string()),
+              value: Either::Second("$inputs.pin".to_string()),
+              extensions: Default::default()
+            })
+          ],
+          .. Step::default()
+        }],
+        .. Workflow::default()
+      }],
+      .. ArazzoDescription::default()
+    };
+    let redactor = Redactor::new().with_description(&description);
+    expect!(redactor.is_sensitive("x-client")).to(be_true());
+    expect!(redactor.is_sensitive("pin")).to(be_true());
+
+    let redacted = redactor.redact_description(&description);
+    let parameters = &redacted.workflows[0].steps[0].parameters;
+    expect!(parameters[0].first().unwrap().value.clone()).to(be_equal_to(Either::First(AnyValue::String("***".to_string()))));
+    expect!(parameters[1].first().unwrap().value.clone()).to(be_equal_to(Either::Second("$inputs.pin".to_string())));
+  }
+}
@@ -0,0 +1,385 @@
+//! Support for removing workflows, steps and components from a description without leaving
+//! dangling references behind.
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::expressions::ReferenceKind;
+use crate::usages::{ObjectRef, Usage, UsageIndex};
+use crate::v1_0::ArazzoDescription;
+
+/// What to do when the entry being removed is still used
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RemoveStrategy {
+  /// Fail the removal (default)
+  #[default]
+  Fail,
+  /// Also remove everything that uses the entry. Steps and components that use it are removed,
+  /// while dependencies, parameters, actions, outputs and input schemas that refer to it are
+  /// removed from the workflow or step that contains them.
+  Cascade,
+  /// Remove the entry, and report all the references that are left dangling
+  Report
+}
+
+/// Options to control how entries are removed
+#[derive(Debug, Clone, Default)]
+pub struct RemoveOptions {
+  /// What to do if the entry being removed is still used
+  pub on_usage: RemoveStrategy
+}
+
+/// Entry that was removed from a description
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemovedEntry {
+  /// Workflow or component
+  Object(ObjectRef),
+  /// Step of a workflow
+  Step {
+    /// Workflow the step belonged to
+    workflow_id: String,
+    /// ID of the step
+    step_id: String
+  },
+  /// Value that referred to a removed entry
+  Value(Usage)
+}
+
+/// Result of removing an entry from a description
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RemoveReport {
+  /// All the entries that were removed, in the order they were removed
+  pub removed: Vec<RemovedEntry>,
+  /// References to the removed entries that were left behind
+  pub dangling: Vec<Usage>
+}
+
+impl ArazzoDescription {
+  /// Removes the workflow with the given ID. Returns an error if the workflow does not exist, or
+  /// it is still used and the strategy is to fail.
+  pub fn remove_workflow(&mut self, workflow_id: &str, options: &RemoveOptions) -> anyhow::Result<RemoveReport> {
+    self.remove_entry(RemovedEntry::Object(ObjectRef::new(ReferenceKind::Workflow, workflow_id)), options)
+  }
+
+  /// Removes the step with the given ID from the workflow. Returns an error if the step does not
+  /// exist, or it is still used and the strategy is to fail.
+  pub fn remove_step(&mut self, workflow_id: &str, step_id: &str, options: &RemoveOptions) -> anyhow::Result<RemoveReport> {
+    self.remove_entry(RemovedEntry::Step {
+      workflow_id: workflow_id.to_string(),
+      step_id: step_id.to_string()
+    }, options)
+  }
+
+  /// Removes the component with the given type and name. Returns an error if the component does
+  /// not exist, or it is still used and the strategy is to fail.
+  pub fn remove_component(&mut self, kind: ReferenceKind, name: &str, options: &RemoveOptions) -> anyhow::Result<RemoveReport> {
+    match kind {
+      ReferenceKind::ComponentInput | ReferenceKind::ComponentParameter |
+      ReferenceKind::ComponentSuccessAction | ReferenceKind::ComponentFailureAction => {
+        self.remove_entry(RemovedEntry::Object(ObjectRef::new(kind, name)), options)
+      }
+      _ => Err(anyhow!("{} is not a type of component", kind))
+    }
+  }
+
+  fn remove_entry(&mut self, entry: RemovedEntry, options: &RemoveOptions) -> anyhow::Result<RemoveReport> {
+    let mut description = self.clone();
+    let mut report = RemoveReport::default();
+
+    match options.on_usage {
+      RemoveStrategy::Fail | RemoveStrategy::Report => {
+        remove_entry_from(&mut description, &entry)?;
+        let usages = remove_entry_usages(&description, &entry);
+        if options.on_usage == RemoveStrategy::Fail && !usages.is_empty() {
+          return Err(anyhow!("{} can not be removed as it is still used: {}", remove_entry_name(&entry),
+            usages.iter().map(|usage| usage.to_string()).collect::<Vec<_>>().join(", ")));
+        }
+        report.removed.push(entry);
+        report.dangling = usages;
+      }
+      RemoveStrategy::Cascade => remove_cascade(&mut description, entry, &mut report)?
+    }
+
+    *self = description;
+    Ok(report)
+  }
+}
+
+fn remove_entry_name(entry: &RemovedEntry) -> String {
+  match entry {
+    RemovedEntry::Object(object) => object.to_string(),
+    RemovedEntry::Step { workflow_id, step_id } => format!("Step '{}' of workflow '{}'", step_id, workflow_id),
+    RemovedEntry::Value(usage) => usage.to_string()
+  }
+}
+
+fn remove_cascade(
+  description: &mut ArazzoDescription,
+  entry: RemovedEntry,
+  report: &mut RemoveReport
+) -> anyhow::Result<()> {
+  remove_entry_from(description, &entry)?;
+
+  loop {
+    let usages = remove_entry_usages(description, &entry);
+    let Some(usage) = usages.first() else { break };
+    match remove_cascade_target(description, usage) {
+      Some(next) => remove_cascade(description, next, report)?,
+      None => {
+        remove_value(description, usage)?;
+        report.removed.push(RemovedEntry::Value(usage.clone()));
+      }
+    }
+  }
+
+  report.removed.push(entry);
+  Ok(())
+}
+
+fn remove_entry_from(description: &mut ArazzoDescription, entry: &RemovedEntry) -> anyhow::Result<()> {
+  let not_found = || anyhow!("{} does not exist", remove_entry_name(entry));
+  match entry {
+    RemovedEntry::Object(object) => {
+      let components = &mut description.components;
+      let found = match object.kind {
+        ReferenceKind::Workflow => {
+          let index = description.workflows.iter().position(|wf| wf.workflow_id == object.name);
+          index.map(|index| description.workflows.remove(index)).is_some()
+        }
+        ReferenceKind::ComponentInput => components.inputs.remove(&object.name).is_some(),
+        ReferenceKind::ComponentParameter => components.parameters.remove(&object.name).is_some(),
+        ReferenceKind::ComponentSuccessAction => components.success_actions.remove(&object.name).is_some(),
+        ReferenceKind::ComponentFailureAction => components.failure_actions.remove(&object.name).is_some(),
+        _ => false
+      };
+      if found { Ok(()) } else { Err(not_found()) }
+    }
+    RemovedEntry::Step { workflow_id, step_id } => {
+      let workflow = description.workflows.iter_mut()
+        .find(|wf| &wf.workflow_id == workflow_id)
+        .ok_or_else(not_found)?;
+      let index = workflow.steps.iter()
+        .position(|step| &step.step_id == step_id)
+        .ok_or_else(not_found)?;
+      workflow.steps.remove(index);
+      Ok(())
+    }
+    RemovedEntry::Value(usage) => remove_value(description, usage)
+  }
+}
+
+fn remove_entry_usages(description: &ArazzoDescription, entry: &RemovedEntry) -> Vec<Usage> {
+  let index = UsageIndex::build(description);
+  match entry {
+    RemovedEntry::Object(object) => index.usages_of(object.kind, &object.name)
+      .into_iter()
+      .cloned()
+      .collect(),
+    RemovedEntry::Step { workflow_id, step_id } => index.usages_of(ReferenceKind::Step, step_id)
+      .into_iter()
+      .filter(|usage| usage.owner.kind == ReferenceKind::Workflow && &usage.owner.name == workflow_id)
+      .cloned()
+      .collect(),
+    RemovedEntry::Value(_) => vec![]
+  }
+}
+
+fn unescape_token(token: &str) -> String {
+  token.replace("~1", "/").replace("~0", "~")
+}
+
+fn path_tokens(path: &str) -> Vec<String> {
+  path.split('/').skip(1).map(unescape_token).collect()
+}
+
+/// Works out if the whole step or component that contains the usage needs to be removed
+fn remove_cascade_target(description: &ArazzoDescription, usage: &Usage) -> Option<RemovedEntry> {
+  if usage.owner.kind != ReferenceKind::Workflow {
+    return Some(RemovedEntry::Object(usage.owner.clone()));
+  }
+
+  let tokens = path_tokens(&usage.path);
+  match tokens.as_slice() {
+    [steps, index, field, ..] if steps == "steps" => {
+      if ["parameters", "onSuccess", "onFailure", "outputs"].contains(&field.as_str()) {
+        None
+      } else {
+        let workflow = description.workflows.iter().find(|wf| wf.workflow_id == usage.owner.name)?;
+        let step = workflow.steps.get(index.parse::<usize>().ok()?)?;
+        Some(RemovedEntry::Step {
+          workflow_id: workflow.workflow_id.clone(),
+          step_id: step.step_id.clone()
+        })
+      }
+    }
+    _ => None
+  }
+}
+
+/// Removes the value that contains the usage from its workflow
+fn remove_value(description: &mut ArazzoDescription, usage: &Usage) -> anyhow::Result<()> {
+  let not_found = || anyhow!("{} does not exist", usage);
+  let workflow = description.workflows.iter_mut()
+    .find(|wf| usage.owner.kind == ReferenceKind::Workflow && wf.workflow_id == usage.owner.name)
+    .ok_or_else(not_found)?;
+  let tokens = path_tokens(&usage.path);
+  let index = |token: &String| token.parse::<usize>().map_err(|_| not_found());
+
+  match tokens.as_slice() {
+    [field, i, ..] if field == "dependsOn" => remove_index(&mut workflow.depends_on, index(i)?),
+    [field, i, ..] if field == "parameters" => remove_index(&mut workflow.parameters, index(i)?),
+    [field, i, ..] if field == "successActions" => remove_index(&mut workflow.success_actions, index(i)?),
+    [field, i, ..] if field == "failureActions" => remove_index(&mut workflow.failure_actions, index(i)?),
+    [field, key, ..] if field == "outputs" => workflow.outputs.remove(key).is_some(),
+    [field, rest @ ..] if field == "inputs" => remove_json_schema(&mut workflow.inputs, rest),
+    [steps, s, field, rest @ ..] if steps == "steps" => {
+      let step = workflow.steps.get_mut(index(s)?).ok_or_else(not_found)?;
+      match (field.as_str(), rest) {
+        ("parameters", [i, ..]) => remove_index(&mut step.parameters, index(i)?),
+        ("onSuccess", [i, ..]) => remove_index(&mut step.on_success, index(i)?),
+        ("onFailure", [i, ..]) => remove_index(&mut step.on_failure, index(i)?),
+        ("outputs", [key, ..]) => step.outputs.remove(key).is_some(),
+        _ => false
+      }
+    }
+    _ => false
+  }.then_some(()).ok_or_else(not_found)
+}
+
+fn remove_index<T>(list: &mut Vec<T>, index: usize) -> bool {
+  if index < list.len() {
+    list.remove(index);
+    true
+  } else {
+    false
+  }
+}
+
+/// Removes the schema that contains the `$ref` at the given path
+fn remove_json_schema(json: &mut Value, tokens: &[String]) -> bool {
+  match tokens {
+    [] => false,
+    [_] => {
+      *json = Value::Null;
+      true
+    }
+    [key, last] if last == "$ref" => match json {
+      Value::Object(map) => map.remove(key).is_some(),
+      Value::Array(array) => key.parse::<usize>().map(|i| remove_index(array, i)).unwrap_or_default(),
+      _ => false
+    },
+    [key, rest @ ..] => match json {
+      Value::Object(map) => map.get_mut(key).map(|value| remove_json_schema(value, rest)).unwrap_or_default(),
+      Value::Array(array) => key.parse::<usize>().ok()
+        .and_then(|i| array.get_mut(i))
+        .map(|value| remove_json_schema(value, rest))
+        .unwrap_or_default(),
+      _ => false
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::arazzo;
+  use crate::expressions::ReferenceKind;
+  use crate::remove::{RemovedEntry, RemoveOptions, RemoveStrategy};
+  use crate::usages::{ObjectRef, Usage};
+  use crate::v1_0::*;
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      workflow("login") {
+        inputs: json!({
+          "type": "object",
+          "properties": {
+            "user": { "$ref": "#/components/inputs/user" },
+            "password": { "type": "string" }
+          }
+        });
+        step("login") { operation_id: "login"; }
+      }
+      workflow("buy") {
+        depends_on: "login";
+        step("find") {
+          operation_id: "find";
+          reusable_parameter: "page";
+          output("id", "$response.body#/id");
+        }
+        step("buy") { workflow_id: "$workflows.login"; }
+        step("confirm") {
+          operation_id: "confirm";
+          success_criterion: "$steps.buy.outputs.ok == true";
+        }
+        output("id", "$steps.find.outputs.id");
+      }
+      component_input("user", json!({ "type": "string" }));
+      component_parameter("page", ParameterObject { name: "page".to_string(), .. ParameterObject::default() });
+    }
+  }
+
+  #[test]
+  fn remove_fails_if_the_entry_is_still_used() {
+    let mut description = description();
+    let result = description.remove_workflow("login", &RemoveOptions::default());
+    expect!(result.unwrap_err().to_string()).to(be_equal_to(
+      "Workflow 'login' can not be removed as it is still used: Workflow 'buy' (/dependsOn/0), \
+      Workflow 'buy' (/steps/1/workflowId)"));
+    expect!(description.workflows.len()).to(be_equal_to(2));
+
+    let result = description.remove_step("buy", "other", &RemoveOptions::default());
+    expect!(result.unwrap_err().to_string()).to(be_equal_to("Step 'other' of workflow 'buy' does not exist"));
+  }
+
+  #[test]
+  fn remove_unused_entries() {
+    let mut description = description();
+    let report = description.remove_step("buy", "confirm", &RemoveOptions::default()).unwrap();
+    expect!(report.dangling.is_empty()).to(be_true());
+    expect!(description.workflows[1].steps.len()).to(be_equal_to(2));
+  }
+
+  #[test]
+  fn remove_can_report_dangling_references() {
+    let mut description = description();
+    let options = RemoveOptions { on_usage: RemoveStrategy::Report };
+    let report = description.remove_component(ReferenceKind::ComponentParameter, "page", &options).unwrap();
+    expect!(report.dangling).to(be_equal_to(vec![
+      Usage { owner: ObjectRef::new(ReferenceKind::Workflow, "buy"), path: "/steps/0/parameters/0/reference".to_string() }
+    ]));
+    expect!(description.components.parameters.is_empty()).to(be_true());
+    expect!(description.workflows[1].steps[0].parameters.len()).to(be_equal_to(1));
+  }
+
+  #[test]
+  fn remove_can_cascade() {
+    let mut description = description();
+    let options = RemoveOptions { on_usage: RemoveStrategy::Cascade };
+    let report = description.remove_workflow("login", &options).unwrap();
+    expect!(report.removed).to(be_equal_to(vec![
+      RemovedEntry::Value(Usage { owner: ObjectRef::new(ReferenceKind::Workflow, "buy"), path: "/dependsOn/0".to_string() }),
+      RemovedEntry::Step { workflow_id: "buy".to_string(), step_id: "confirm".to_string() },
+      RemovedEntry::Step { workflow_id: "buy".to_string(), step_id: "buy".to_string() },
+      RemovedEntry::Object(ObjectRef::new(ReferenceKind::Workflow, "login"))
+    ]));
+    let workflow = &description.workflows[0];
+    expect!(workflow.depends_on.is_empty()).to(be_true());
+    expect!(workflow.steps.iter().map(|step| step.step_id.as_str()).collect::<Vec<_>>()).to(be_equal_to(vec!["find"]));
+
+    let report = description.remove_component(ReferenceKind::ComponentParameter, "page", &options).unwrap();
+    expect!(report.removed.len()).to(be_equal_to(2));
+    expect!(description.workflows[0].steps[0].parameters.is_empty()).to(be_true());
+
+    let mut description = self::description();
+    description.remove_component(ReferenceKind::ComponentInput, "user", &options).unwrap();
+    expect!(&description.workflows[0].inputs).to(be_equal_to(&json!({
+      "type": "object",
+      "properties": {
+        "password": { "type": "string" }
+      }
+    })));
+  }
+}
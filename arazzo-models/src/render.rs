@@ -0,0 +1,924 @@
+//! Support for rendering the final request body that is sent for a step. The templates in the
+//! payload are rendered (see the [templates module](crate::templates)), and then the replacements
+//! are applied (see the [replacements module](crate::replacements)).
+//!
+//! The workflows of a document can also be rendered as a Graphviz DOT graph with [`dot`], for
+//! documentation pipelines to draw the flow of the workflows, or as Mermaid flowcharts and
+//! sequence diagrams with [`mermaid_flowchart`] and [`mermaid_sequence`], which can be added to
+//! Markdown documents. For teams using PlantUML, [`plantuml_activity`] renders a workflow as an
+//! activity diagram.
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Write};
+use std::sync::Arc;
+
+use bytes::Bytes;
+
+use anyhow::anyhow;
+
+use crate::expressions::ExpressionResolver;
+use crate::payloads::{ExternalPayload, Payload};
+use crate::templates::{render_payload_templates, TemplateOptions};
+use crate::either::Either;
+use crate::v1_0::{ArazzoDescription, Criterion, ReusableObject, RequestBody, Step, Workflow};
+
+/// Rendered request body
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedBody {
+  /// Content type of the request body
+  pub content_type: Option<String>,
+  /// Bytes of the request body
+  pub bytes: Bytes
+}
+
+impl RequestBody {
+  /// Renders the payload of the request body. The templates in the payload are rendered, and then
+  /// the replacements are applied, with the runtime expressions resolved using the resolver.
+  /// Returns `None` if the request body has no payload.
+  pub fn render_payload(
+    &self,
+    resolver: &dyn ExpressionResolver
+  ) -> anyhow::Result<Option<Arc<dyn Payload + Send + Sync>>> {
+    let rendered = match &self.payload {
+      Some(payload) => Some(render_payload_templates(payload, resolver, &TemplateOptions::default())?),
+      None => None
+    };
+    RequestBody { payload: rendered, .. self.clone() }.apply_replacements(resolver)
+  }
+
+  /// Renders the request body that is sent, using the context (i.e. an [`ExpressionContext`](crate::context::ExpressionContext)) to
+  /// resolve any runtime expressions. If the request body does not declare a content type, it is
+  /// inferred from the payload. Fails if the payload is an [`ExternalPayload`] that can not be
+  /// loaded.
+  pub fn render(&self, context: &dyn ExpressionResolver) -> anyhow::Result<RenderedBody> {
+    let bytes = match self.render_payload(context)? {
+      Some(payload) => match payload.downcast_ref::<ExternalPayload>() {
+        Some(external_payload) => external_payload.load()?,
+        None => payload.as_bytes()
+      },
+      None => Bytes::new()
+    };
+    Ok(RenderedBody { content_type: self.effective_content_type(), bytes })
+  }
+}
+
+/// Options for rendering DOT graphs
+#[derive(Debug, Clone, PartialEq)]
+pub struct DotOptions {
+  /// Name of the graph
+  pub name: String,
+  /// Direction of the graph (`TB` for top to bottom, or `LR` for left to right)
+  pub rank_direction: String,
+  /// If the steps of the workflows are drawn. Otherwise, only the workflows and the references
+  /// between them are.
+  pub include_steps: bool,
+  /// If the operation of each step is added to its label
+  pub show_operations: bool,
+  /// Font of the nodes and edges
+  pub font_name: String,
+  /// Colour of the edges of success actions
+  pub success_color: String,
+  /// Colour of the edges of failure actions
+  pub failure_color: String,
+  /// Colour of the `dependsOn` edges
+  pub depends_on_color: String
+}
+
+impl Default for DotOptions {
+  fn default() -> Self {
+    DotOptions {
+      name: "arazzo".to_string(),
+      rank_direction: "TB".to_string(),
+      include_steps: true,
+      show_operations: true,
+      font_name: "Helvetica".to_string(),
+      success_color: "darkgreen".to_string(),
+      failure_color: "firebrick".to_string(),
+      depends_on_color: "gray40".to_string()
+    }
+  }
+}
+
+/// Renders the workflows of the Arazzo description as a Graphviz DOT graph, with the default
+/// options (see [`dot_with_options`])
+pub fn dot(description: &ArazzoDescription) -> String {
+  dot_with_options(description, &DotOptions::default())
+}
+
+/// Renders the workflows of the Arazzo description as a Graphviz DOT graph. Each workflow is a
+/// cluster with its steps in order, and the graph has edges for:
+/// * `dependsOn` workflows (dashed), including workflows of other source descriptions
+/// * `goto` and `retry` actions (labelled with the action name), to steps or workflows
+/// * steps that execute another workflow
+///
+/// `end` actions have no edges. Actions of the workflow (`successActions` and `failureActions`)
+/// are drawn for every step that does not override them.
+pub fn dot_with_options(description: &ArazzoDescription, options: &DotOptions) -> String {
+  let mut dot = String::new();
+  let _ = writeln!(dot, "digraph {} {{", quote(&options.name));
+  let _ = writeln!(dot, "  rankdir={};", quote(&options.rank_direction));
+  let _ = writeln!(dot, "  node [shape=box, fontname={}];", quote(&options.font_name));
+  let _ = writeln!(dot, "  edge [fontname={}];", quote(&options.font_name));
+
+  let mut edges = vec![];
+  for workflow in &description.workflows {
+    let workflow_node = workflow_node(&workflow.workflow_id);
+    if options.include_steps {
+      let _ = writeln!(dot, "  subgraph {} {{", quote(&format!("cluster_{}", workflow.workflow_id)));
+      let _ = writeln!(dot, "    label={};", quote(&workflow_label(workflow)));
+      let _ = writeln!(dot, "    {} [label={}, shape=ellipse];", quote(&workflow_node), quote(&workflow.workflow_id));
+      let mut previous = workflow_node.clone();
+      for step in &workflow.steps {
+        let node = step_node(&workflow.workflow_id, &step.step_id);
+        let _ = writeln!(dot, "    {} [label={}];", quote(&node), quote(&step_label(step, options.show_operations)));
+        let _ = writeln!(dot, "    {} -> {};", quote(&previous), quote(&node));
+        previous = node;
+      }
+      let _ = writeln!(dot, "  }}");
+      for step in &workflow.steps {
+        step_edges(description, workflow, step, options, &mut edges);
+      }
+    } else {
+      let _ = writeln!(dot, "  {} [label={}, shape=ellipse];", quote(&workflow_node), quote(&workflow_label(workflow)));
+      for step in &workflow.steps {
+        let mut step_edges_list = vec![];
+        step_edges(description, workflow, step, options, &mut step_edges_list);
+        for (_, to, attributes) in step_edges_list {
+          if to.starts_with("workflow:") && to != workflow_node {
+            let edge = (workflow_node.clone(), to, attributes);
+            if !edges.iter().any(|(from, to, _)| *from == edge.0 && *to == edge.1) {
+              edges.push(edge);
+            }
+          }
+        }
+      }
+    }
+    for dependency in &workflow.depends_on {
+      let attributes = format!("style=dashed, color={}, label=\"dependsOn\"", quote(&options.depends_on_color));
+      edges.push((workflow_node.clone(), workflow_reference(dependency), attributes));
+    }
+  }
+
+  let known = description.workflows.iter()
+    .map(|workflow| workflow_node(&workflow.workflow_id))
+    .collect::<Vec<_>>();
+  let mut external: Vec<&String> = vec![];
+  for (_, to, _) in &edges {
+    if to.starts_with("workflow:") && !known.contains(to) && !external.contains(&to) {
+      external.push(to);
+    }
+  }
+  for node in external {
+    let label = node.trim_start_matches("workflow:");
+    let _ = writeln!(dot, "  {} [label={}, shape=ellipse, style=dashed];", quote(node), quote(label));
+  }
+  for (from, to, attributes) in edges {
+    let _ = writeln!(dot, "  {} -> {} [{}];", quote(&from), quote(&to), attributes);
+  }
+  dot.push_str("}\n");
+  dot
+}
+
+fn step_edges(
+  description: &ArazzoDescription,
+  workflow: &Workflow,
+  step: &Step,
+  options: &DotOptions,
+  edges: &mut Vec<(String, String, String)>
+) {
+  let node = step_node(&workflow.workflow_id, &step.step_id);
+  if let Some(workflow_id) = &step.workflow_id {
+    edges.push((node.clone(), workflow_reference(workflow_id), "style=bold, label=\"workflow\"".to_string()));
+  }
+
+  for transition in transitions(description, workflow, step) {
+    let target = match (transition.action_type.as_str(), &transition.step_id, &transition.workflow_id) {
+      ("goto" | "retry", Some(step_id), _) => step_node(&workflow.workflow_id, step_id),
+      ("goto" | "retry", None, Some(workflow_id)) => workflow_reference(workflow_id),
+      ("retry", None, None) => node.clone(),
+      _ => continue
+    };
+    let color = if transition.failure { &options.failure_color } else { &options.success_color };
+    let label = if transition.action_type == "retry" {
+      format!("{} (retry)", transition.name)
+    } else {
+      transition.name
+    };
+    edges.push((node.clone(), target, format!("color={}, fontcolor={}, label={}", quote(color), quote(color), quote(&label))));
+  }
+}
+
+/// Success or failure action that applies to a step
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Transition {
+  pub(crate) name: String,
+  pub(crate) action_type: String,
+  pub(crate) step_id: Option<String>,
+  pub(crate) workflow_id: Option<String>,
+  pub(crate) retry_after: Option<f64>,
+  pub(crate) retry_limit: Option<i64>,
+  pub(crate) criteria: Vec<String>,
+  pub(crate) failure: bool
+}
+
+impl Transition {
+  /// Annotation for a retry, i.e. `retry after 1s, up to 3 times`
+  pub(crate) fn retry_annotation(&self) -> String {
+    let mut annotation = "retry".to_string();
+    if let Some(retry_after) = self.retry_after {
+      let _ = write!(annotation, " after {}s", retry_after);
+    }
+    if let Some(retry_limit) = self.retry_limit {
+      let _ = write!(annotation, ", up to {} times", retry_limit);
+    }
+    annotation
+  }
+}
+
+/// Returns the success actions and then the failure actions that apply to the step
+pub(crate) fn transitions(description: &ArazzoDescription, workflow: &Workflow, step: &Step) -> Vec<Transition> {
+  let success_actions = actions(&step.on_success, &workflow.success_actions, &description.components.success_actions,
+    |action| Transition {
+      name: action.name.clone(),
+      action_type: action.r#type.clone(),
+      step_id: action.step_id.clone(),
+      workflow_id: action.workflow_id.clone(),
+      retry_after: None,
+      retry_limit: None,
+      criteria: action.criteria.iter().map(criterion_label).collect(),
+      failure: false
+    });
+  let failure_actions = actions(&step.on_failure, &workflow.failure_actions, &description.components.failure_actions,
+    |action| Transition {
+      name: action.name.clone(),
+      action_type: action.r#type.clone(),
+      step_id: action.step_id.clone(),
+      workflow_id: action.workflow_id.clone(),
+      retry_after: action.retry_after,
+      retry_limit: action.retry_limit,
+      criteria: action.criteria.iter().map(criterion_label).collect(),
+      failure: true
+    });
+  success_actions.into_iter().chain(failure_actions).collect()
+}
+
+/// Returns the actions of the step (with any reusable actions resolved), followed by the actions
+/// of the workflow that the step does not override
+fn actions<A, F>(
+  step_actions: &[Either<A, ReusableObject>],
+  workflow_actions: &[Either<A, ReusableObject>],
+  components: &HashMap<String, A>,
+  fields: F
+) -> Vec<Transition>
+  where A: Debug + Clone + PartialEq,
+        F: Fn(&A) -> Transition
+{
+  let resolve = |action: &Either<A, ReusableObject>| match action {
+    Either::First(action) => Some(fields(action)),
+    Either::Second(reusable) => reusable.reference.rsplit_once('.')
+      .and_then(|(_, name)| components.get(name))
+      .map(&fields)
+  };
+  let mut result = step_actions.iter().filter_map(resolve).collect::<Vec<_>>();
+  for action in workflow_actions.iter().filter_map(resolve) {
+    if !result.iter().any(|transition| transition.name == action.name) {
+      result.push(action);
+    }
+  }
+  result
+}
+
+fn workflow_node(workflow_id: &str) -> String {
+  format!("workflow:{}", workflow_id)
+}
+
+fn step_node(workflow_id: &str, step_id: &str) -> String {
+  format!("step:{}/{}", workflow_id, step_id)
+}
+
+/// Node for a workflow reference, which is either a workflow ID or a
+/// `$sourceDescriptions.<name>.<workflowId>` expression
+fn workflow_reference(reference: &str) -> String {
+  match reference.strip_prefix("$sourceDescriptions.") {
+    Some(reference) => workflow_node(reference),
+    None => workflow_node(reference)
+  }
+}
+
+fn workflow_label(workflow: &Workflow) -> String {
+  match &workflow.summary {
+    Some(summary) => format!("{}\n{}", workflow.workflow_id, summary),
+    None => workflow.workflow_id.clone()
+  }
+}
+
+fn step_label(step: &Step, show_operations: bool) -> String {
+  let operation = step.operation_id.as_ref().or(step.operation_path.as_ref());
+  match operation {
+    Some(operation) if show_operations => format!("{}\n{}", step.step_id, operation),
+    _ => step.step_id.clone()
+  }
+}
+
+/// Options for rendering Mermaid diagrams
+#[derive(Debug, Clone, PartialEq)]
+pub struct MermaidOptions {
+  /// Direction of flowcharts (`TD` for top down, or `LR` for left to right)
+  pub direction: String,
+  /// If the operation of each step is added to its label
+  pub show_operations: bool,
+  /// If the diagram is wrapped in a ```` ```mermaid ```` code block, for adding to Markdown
+  pub fenced: bool
+}
+
+impl Default for MermaidOptions {
+  fn default() -> Self {
+    MermaidOptions {
+      direction: "TD".to_string(),
+      show_operations: true,
+      fenced: true
+    }
+  }
+}
+
+/// Renders a workflow as a Mermaid flowchart (see [`mermaid_flowchart_with_options`]), with the
+/// default options
+pub fn mermaid_flowchart(description: &ArazzoDescription, workflow_id: &str) -> anyhow::Result<String> {
+  mermaid_flowchart_with_options(description, workflow_id, &MermaidOptions::default())
+}
+
+/// Renders a workflow as a Mermaid flowchart. The workflow starts at a stadium shaped node, with
+/// the steps in order after it. Success actions are solid edges, and failure actions are dotted
+/// edges, labelled with the action name (and the delay and limit of retries). Other workflows are
+/// drawn as subroutine shaped nodes, and `end` actions go to an `end` node.
+pub fn mermaid_flowchart_with_options(
+  description: &ArazzoDescription,
+  workflow_id: &str,
+  options: &MermaidOptions
+) -> anyhow::Result<String> {
+  let workflow = find_workflow(description, workflow_id)?;
+  let nodes = MermaidNodes::new(description, workflow);
+
+  let mut diagram = String::new();
+  let _ = writeln!(diagram, "flowchart {}", options.direction);
+  let _ = writeln!(diagram, "  start([{}])", mermaid_text(&workflow.workflow_id));
+  for (index, step) in workflow.steps.iter().enumerate() {
+    let label = step_label(step, options.show_operations).replace('\n', "<br/>");
+    let _ = writeln!(diagram, "  s{}[{}]", index, mermaid_text(&label));
+  }
+  for (index, reference) in nodes.workflows.iter().enumerate() {
+    let _ = writeln!(diagram, "  w{}[[{}]]", index, mermaid_text(reference));
+  }
+
+  let mut previous = "start".to_string();
+  let mut ends = false;
+  for (index, step) in workflow.steps.iter().enumerate() {
+    let node = format!("s{}", index);
+    let _ = writeln!(diagram, "  {} --> {}", previous, node);
+    if let Some(workflow_id) = &step.workflow_id {
+      let _ = writeln!(diagram, "  {} ==>|\"workflow\"| {}", node, nodes.workflow(workflow_id));
+    }
+    for transition in transitions(description, workflow, step) {
+      let (target, label) = match (transition.action_type.as_str(), &transition.step_id, &transition.workflow_id) {
+        ("goto", Some(step_id), _) => (nodes.step(step_id), transition.name.clone()),
+        ("goto", None, Some(workflow_id)) => (nodes.workflow(workflow_id), transition.name.clone()),
+        ("retry", Some(step_id), _) => (nodes.step(step_id), format!("{} ({})", transition.name, transition.retry_annotation())),
+        ("retry", None, Some(workflow_id)) =>
+          (nodes.workflow(workflow_id), format!("{} ({})", transition.name, transition.retry_annotation())),
+        ("retry", None, None) => (node.clone(), format!("{} ({})", transition.name, transition.retry_annotation())),
+        ("end", _, _) => {
+          ends = true;
+          ("finish".to_string(), transition.name.clone())
+        }
+        _ => continue
+      };
+      let arrow = if transition.failure { "-.->" } else { "-->" };
+      let _ = writeln!(diagram, "  {} {}|{}| {}", node, arrow, mermaid_text(&label), target);
+    }
+    previous = node;
+  }
+  if ends {
+    let _ = writeln!(diagram, "  finish([\"end\"])");
+  }
+  Ok(fence(diagram, options))
+}
+
+/// Renders a workflow as a Mermaid sequence diagram (see [`mermaid_sequence_with_options`]), with
+/// the default options
+pub fn mermaid_sequence(description: &ArazzoDescription, workflow_id: &str) -> anyhow::Result<String> {
+  mermaid_sequence_with_options(description, workflow_id, &MermaidOptions::default())
+}
+
+/// Renders a workflow as a Mermaid sequence diagram. The workflow and each of its steps are
+/// participants, and the workflow calls the steps in order (with the operation of the step as the
+/// message). Success actions are solid messages, and failure actions are dotted messages from the
+/// step, and retries have a note with their delay and limit.
+pub fn mermaid_sequence_with_options(
+  description: &ArazzoDescription,
+  workflow_id: &str,
+  options: &MermaidOptions
+) -> anyhow::Result<String> {
+  let workflow = find_workflow(description, workflow_id)?;
+  let nodes = MermaidNodes::new(description, workflow);
+
+  let mut diagram = "sequenceDiagram\n".to_string();
+  let _ = writeln!(diagram, "  participant start as {}", mermaid_participant(&workflow.workflow_id));
+  for (index, step) in workflow.steps.iter().enumerate() {
+    let _ = writeln!(diagram, "  participant s{} as {}", index, mermaid_participant(&step.step_id));
+  }
+  for (index, reference) in nodes.workflows.iter().enumerate() {
+    let _ = writeln!(diagram, "  participant w{} as {}", index, mermaid_participant(reference));
+  }
+
+  for (index, step) in workflow.steps.iter().enumerate() {
+    let node = format!("s{}", index);
+    let operation = step.operation_id.as_ref().or(step.operation_path.as_ref());
+    let message = match operation {
+      Some(operation) if options.show_operations => operation.clone(),
+      _ => step.step_id.clone()
+    };
+    let _ = writeln!(diagram, "  start->>{}: {}", node, mermaid_message(&message));
+    if let Some(workflow_id) = &step.workflow_id {
+      let _ = writeln!(diagram, "  {}->>{}: workflow", node, nodes.workflow(workflow_id));
+    }
+    for transition in transitions(description, workflow, step) {
+      let target = match (transition.action_type.as_str(), &transition.step_id, &transition.workflow_id) {
+        ("goto" | "retry", Some(step_id), _) => nodes.step(step_id),
+        ("goto" | "retry", None, Some(workflow_id)) => nodes.workflow(workflow_id),
+        ("retry", None, None) => node.clone(),
+        ("end", _, _) => "start".to_string(),
+        _ => continue
+      };
+      let arrow = if transition.failure { "-->>" } else { "->>" };
+      let label = format!("{} ({})", transition.name, transition.action_type);
+      let _ = writeln!(diagram, "  {}{}{}: {}", node, arrow, target, mermaid_message(&label));
+      if transition.action_type == "retry" {
+        let _ = writeln!(diagram, "  Note over {}: {}", node, mermaid_message(&transition.retry_annotation()));
+      }
+    }
+  }
+  Ok(fence(diagram, options))
+}
+
+/// Renders a workflow as a PlantUML activity diagram. Each step is an activity, followed by a
+/// decision on its success criteria. The success actions of the step are in the branch where the
+/// criteria pass, and the failure actions in the other branch. Actions with criteria are decisions
+/// in the order they are checked, and the step continues to the next step if no success action
+/// applies, or the workflow ends if no failure action applies. Actions that go to another step or
+/// workflow, or retry the step, detach from the flow (PlantUML activity diagrams can not have
+/// arbitrary arrows), and have the target in their label.
+pub fn plantuml_activity(description: &ArazzoDescription, workflow_id: &str) -> anyhow::Result<String> {
+  let workflow = find_workflow(description, workflow_id)?;
+  let mut diagram = "@startuml\n".to_string();
+  let _ = writeln!(diagram, "title {}", plantuml_text(&workflow_label(workflow)));
+  diagram.push_str("start\n");
+  for step in &workflow.steps {
+    let mut label = step_label(step, true);
+    if let Some(workflow_id) = &step.workflow_id {
+      let _ = write!(label, "\nworkflow {}", workflow_name(workflow_id));
+    }
+    let _ = writeln!(diagram, ":{};", plantuml_text(&label));
+
+    let (success, failure): (Vec<_>, Vec<_>) = transitions(description, workflow, step).into_iter()
+      .partition(|transition| !transition.failure);
+    let criteria = step.success_criteria.iter().map(criterion_label).collect::<Vec<_>>();
+    if criteria.is_empty() && success.is_empty() && failure.is_empty() {
+      continue;
+    }
+    let condition = if criteria.is_empty() { "success".to_string() } else { criteria.join("\nand ") };
+    let _ = writeln!(diagram, "if ({}) then (pass)", plantuml_text(&condition));
+    plantuml_actions(&mut diagram, &success, false, "  ");
+    diagram.push_str("else (fail)\n");
+    plantuml_actions(&mut diagram, &failure, true, "  ");
+    diagram.push_str("endif\n");
+  }
+  diagram.push_str("stop\n@enduml\n");
+  Ok(diagram)
+}
+
+/// Writes the actions as a chain of decisions (for the actions with criteria). If no action
+/// applies, failures end the workflow and successes continue to the next step.
+fn plantuml_actions(diagram: &mut String, actions: &[Transition], failure: bool, indent: &str) {
+  let mut branches = 0;
+  for action in actions {
+    if action.criteria.is_empty() {
+      if branches > 0 {
+        let _ = writeln!(diagram, "{}else", indent);
+        plantuml_action(diagram, action, &format!("{}  ", indent));
+        let _ = writeln!(diagram, "{}endif", indent);
+      } else {
+        plantuml_action(diagram, action, indent);
+      }
+      return;
+    }
+    let keyword = if branches == 0 { "if" } else { "elseif" };
+    let _ = writeln!(diagram, "{}{} ({}) then ({})", indent, keyword, plantuml_text(&action.criteria.join("\nand ")),
+      plantuml_text(&action.name));
+    plantuml_action(diagram, action, &format!("{}  ", indent));
+    branches += 1;
+  }
+  if branches > 0 {
+    if failure {
+      let _ = writeln!(diagram, "{}else", indent);
+      let _ = writeln!(diagram, "{}  end", indent);
+    }
+    let _ = writeln!(diagram, "{}endif", indent);
+  } else if failure {
+    let _ = writeln!(diagram, "{}end", indent);
+  }
+}
+
+fn plantuml_action(diagram: &mut String, action: &Transition, indent: &str) {
+  let target = match (&action.step_id, &action.workflow_id) {
+    (Some(step_id), _) => format!("step {}", step_id),
+    (None, Some(workflow_id)) => format!("workflow {}", workflow_name(workflow_id)),
+    (None, None) => String::new()
+  };
+  match action.action_type.as_str() {
+    "end" => {
+      let _ = writeln!(diagram, "{}:{};", indent, plantuml_text(&format!("{}\nend", action.name)));
+      if action.failure {
+        let _ = writeln!(diagram, "{}end", indent);
+      } else {
+        let _ = writeln!(diagram, "{}stop", indent);
+      }
+    }
+    "retry" => {
+      let mut label = format!("{}\n{}", action.name, action.retry_annotation());
+      if !target.is_empty() {
+        let _ = write!(label, " then go to {}", target);
+      }
+      let _ = writeln!(diagram, "{}:{};", indent, plantuml_text(&label));
+      let _ = writeln!(diagram, "{}detach", indent);
+    }
+    _ => {
+      let _ = writeln!(diagram, "{}:{};", indent, plantuml_text(&format!("{}\ngo to {}", action.name, target)));
+      let _ = writeln!(diagram, "{}detach", indent);
+    }
+  }
+}
+
+/// Text of a criterion, with its context and type (i.e. `$[?@.id] on $response.body (jsonpath)`)
+fn criterion_label(criterion: &Criterion) -> String {
+  let mut label = criterion.condition.clone();
+  if let Some(context) = &criterion.context {
+    let _ = write!(label, " on {}", context);
+  }
+  match &criterion.r#type {
+    Some(Either::First(criterion_type)) if criterion_type != "simple" => {
+      let _ = write!(label, " ({})", criterion_type);
+    }
+    Some(Either::Second(expression_type)) => {
+      let _ = write!(label, " ({} {})", expression_type.r#type, expression_type.version);
+    }
+    _ => {}
+  }
+  label
+}
+
+/// Text of a PlantUML label, on a single line (newlines are written as `\n`)
+fn plantuml_text(text: &str) -> String {
+  text.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn find_workflow<'a>(description: &'a ArazzoDescription, workflow_id: &str) -> anyhow::Result<&'a Workflow> {
+  description.workflows.iter()
+    .find(|workflow| workflow.workflow_id == workflow_id)
+    .ok_or_else(|| anyhow!("No workflow with ID '{}' was found", workflow_id))
+}
+
+/// Mermaid IDs of the steps of a workflow (`s0`, `s1`, ...), and the workflows it references
+/// (`w0`, `w1`, ...)
+struct MermaidNodes<'a> {
+  workflow: &'a Workflow,
+  workflows: Vec<String>
+}
+
+impl <'a> MermaidNodes<'a> {
+  fn new(description: &ArazzoDescription, workflow: &'a Workflow) -> Self {
+    let mut workflows: Vec<String> = vec![];
+    for step in &workflow.steps {
+      let actions = transitions(description, workflow, step).into_iter()
+        .filter(|transition| transition.step_id.is_none())
+        .filter_map(|transition| transition.workflow_id);
+      for reference in step.workflow_id.iter().cloned().chain(actions) {
+        let reference = workflow_name(&reference);
+        if !workflows.contains(&reference) {
+          workflows.push(reference);
+        }
+      }
+    }
+    MermaidNodes { workflow, workflows }
+  }
+
+  fn step(&self, step_id: &str) -> String {
+    match self.workflow.steps.iter().position(|step| step.step_id == step_id) {
+      Some(index) => format!("s{}", index),
+      None => "start".to_string()
+    }
+  }
+
+  fn workflow(&self, reference: &str) -> String {
+    let reference = workflow_name(reference);
+    let index = self.workflows.iter().position(|workflow| *workflow == reference).unwrap_or_default();
+    format!("w{}", index)
+  }
+}
+
+/// Name of a workflow reference, with any `$sourceDescriptions.` prefix removed
+fn workflow_name(reference: &str) -> String {
+  reference.strip_prefix("$sourceDescriptions.").unwrap_or(reference).to_string()
+}
+
+fn fence(diagram: String, options: &MermaidOptions) -> String {
+  if options.fenced {
+    format!("```mermaid\n{}```\n", diagram)
+  } else {
+    diagram
+  }
+}
+
+/// Quotes the text of a flowchart node or edge (quotes are written as `#quot;`)
+fn mermaid_text(text: &str) -> String {
+  format!("\"{}\"", text.replace('"', "#quot;"))
+}
+
+/// Text of a participant alias, which can not have semicolons or newlines
+fn mermaid_participant(text: &str) -> String {
+  text.replace([';', '\n'], " ")
+}
+
+/// Text of a message or note, which can not have semicolons, `#` or newlines
+fn mermaid_message(text: &str) -> String {
+  text.replace(';', "#59;").replace('\n', "<br/>")
+}
+
+/// Quotes an ID for DOT (newlines are written as `\n`)
+fn quote(id: &str) -> String {
+  format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use bytes::Bytes;
+  use expectest::prelude::*;
+  use indexmap::indexmap;
+  use maplit::hashmap;
+  use serde_json::json;
+
+  use crate::context::ExpressionContext;
+  use crate::either::Either;
+  use crate::extensions::AnyValue;
+  use crate::payloads::{ExternalPayload, JsonPayload, StringPayload};
+  use crate::render::{
+    dot,
+    dot_with_options,
+    mermaid_flowchart,
+    mermaid_flowchart_with_options,
+    mermaid_sequence,
+    plantuml_activity,
+    DotOptions,
+    MermaidOptions,
+    RenderedBody
+  };
+  use crate::v1_0::{
+    ArazzoDescription,
+    Components,
+    Criterion,
+    FailureObject,
+    PayloadReplacement,
+    RequestBody,
+    ReusableObject,
+    Step,
+    SuccessObject,
+    Workflow
+  };
+
+  #[test]
+  fn render_request_body() {
+    let mut context = ExpressionContext::new(AnyValue::Object(indexmap!{
+      "name".to_string() => AnyValue::String("Fido".to_string())
+    }));
+    context.set_step_output("create", "id", AnyValue::Integer(100));
+
+    let body = RequestBody {
+      content_type: Some("application/json".to_string()),
+      payload: Some(Arc::new(JsonPayload(json!({ "id": 0, "name": "{$inputs.name}" })))),
+      replacements: vec![
+        PayloadReplacement {
+          target: "/id".to_string(),
+          value: Either::Second("$steps.create.outputs.id".to_string()),
+          extensions: Default::default()
+        }
+      ],
+      extensions: Default::default()
+    };
+    expect!(body.render(&context).unwrap()).to(be_equal_to(RenderedBody {
+      content_type: Some("application/json".to_string()),
+      bytes: Bytes::from("{\"id\":100,\"name\":\"Fido\"}")
+    }));
+
+    let body = RequestBody {
+      content_type: Some("text/plain".to_string()),
+      payload: Some(Arc::new(StringPayload("Hello {$inputs.name}".to_string()))),
+      replacements: vec![],
+      extensions: Default::default()
+    };
+    expect!(body.render(&context).unwrap().bytes).to(be_equal_to(Bytes::from("Hello Fido")));
+
+    let body = RequestBody { payload: None, .. body };
+    expect!(body.render(&context).unwrap().bytes.is_empty()).to(be_true());
+
+    let body = RequestBody { payload: Some(Arc::new(ExternalPayload::file("/does/not/exist.json"))), .. body };
+    expect!(body.render(&context).unwrap_err().to_string())
+      .to(be_equal_to("Failed to load payload from '/does/not/exist.json'"));
+  }
+
+  fn workflow_graph() -> ArazzoDescription {
+    ArazzoDescription {
+      workflows: vec![
+        Workflow {
+          workflow_id: "adopt".to_string(),
+          summary: Some("Adopt a \"pet\"".to_string()),
+          depends_on: vec!["$sourceDescriptions.users.login".to_string()],
+          steps: vec![
+            Step {
+              step_id: "find".to_string(),
+              operation_id: Some("findPets".to_string()),
+              on_failure: vec![Either::First(FailureObject {
+                name: "again".to_string(),
+                r#type: "retry".to_string(),
+                workflow_id: None,
+                step_id: None,
+                retry_after: None,
+                retry_limit: None,
+                criteria: vec![],
+                extensions: Default::default()
+              })],
+              .. Step::default()
+            },
+            Step {
+              step_id: "owner".to_string(),
+              workflow_id: Some("get-owner".to_string()),
+              on_success: vec![Either::Second(ReusableObject {
+                reference: "$components.successActions.restart".to_string(),
+                value: None
+              })],
+              .. Step::default()
+            }
+          ],
+          .. Workflow::default()
+        },
+        Workflow { workflow_id: "get-owner".to_string(), .. Workflow::default() }
+      ],
+      components: Components {
+        success_actions: hashmap!{
+          "restart".to_string() => SuccessObject {
+            name: "restart".to_string(),
+            r#type: "goto".to_string(),
+            workflow_id: None,
+            step_id: Some("find".to_string()),
+            criteria: vec![],
+            extensions: Default::default()
+          }
+        },
+        .. Components::default()
+      },
+      .. ArazzoDescription::default()
+    }
+  }
+
+  #[test]
+  fn renders_workflow_graphs_as_dot() {
+    let description = workflow_graph();
+    expect!(dot(&description)).to(be_equal_to(r#"digraph "arazzo" {
+  rankdir="TB";
+  node [shape=box, fontname="Helvetica"];
+  edge [fontname="Helvetica"];
+  subgraph "cluster_adopt" {
+    label="adopt\nAdopt a \"pet\"";
+    "workflow:adopt" [label="adopt", shape=ellipse];
+    "step:adopt/find" [label="find\nfindPets"];
+    "workflow:adopt" -> "step:adopt/find";
+    "step:adopt/owner" [label="owner"];
+    "step:adopt/find" -> "step:adopt/owner";
+  }
+  subgraph "cluster_get-owner" {
+    label="get-owner";
+    "workflow:get-owner" [label="get-owner", shape=ellipse];
+  }
+  "workflow:users.login" [label="users.login", shape=ellipse, style=dashed];
+  "step:adopt/find" -> "step:adopt/find" [color="firebrick", fontcolor="firebrick", label="again (retry)"];
+  "step:adopt/owner" -> "workflow:get-owner" [style=bold, label="workflow"];
+  "step:adopt/owner" -> "step:adopt/find" [color="darkgreen", fontcolor="darkgreen", label="restart"];
+  "workflow:adopt" -> "workflow:users.login" [style=dashed, color="gray40", label="dependsOn"];
+}
+"#.to_string()));
+
+    let options = DotOptions { include_steps: false, rank_direction: "LR".to_string(), .. DotOptions::default() };
+    let graph = dot_with_options(&description, &options);
+    expect!(graph.contains("rankdir=\"LR\";")).to(be_true());
+    expect!(graph.contains("\"workflow:adopt\" -> \"workflow:get-owner\" [style=bold, label=\"workflow\"];")).to(be_true());
+    expect!(graph.contains("step:")).to(be_false());
+  }
+
+  #[test]
+  fn renders_workflows_as_mermaid_flowcharts() {
+    let description = workflow_graph();
+    expect!(mermaid_flowchart(&description, "adopt").unwrap()).to(be_equal_to(r#"```mermaid
+flowchart TD
+  start(["adopt"])
+  s0["find<br/>findPets"]
+  s1["owner"]
+  w0[["get-owner"]]
+  start --> s0
+  s0 -.->|"again (retry)"| s0
+  s0 --> s1
+  s1 ==>|"workflow"| w0
+  s1 -->|"restart"| s0
+```
+"#.to_string()));
+
+    let options = MermaidOptions { direction: "LR".to_string(), fenced: false, show_operations: false };
+    let flowchart = mermaid_flowchart_with_options(&description, "adopt", &options).unwrap();
+    expect!(flowchart.starts_with("flowchart LR\n")).to(be_true());
+    expect!(flowchart.contains("s0[\"find\"]")).to(be_true());
+    expect!(mermaid_flowchart(&description, "other").unwrap_err().to_string())
+      .to(be_equal_to("No workflow with ID 'other' was found"));
+  }
+
+  #[test]
+  fn renders_workflows_as_mermaid_sequence_diagrams() {
+    let mut description = workflow_graph();
+    if let Either::First(action) = &mut description.workflows[0].steps[0].on_failure[0] {
+      action.retry_after = Some(1.5);
+      action.retry_limit = Some(3);
+    }
+    expect!(mermaid_sequence(&description, "adopt").unwrap()).to(be_equal_to(r#"```mermaid
+sequenceDiagram
+  participant start as adopt
+  participant s0 as find
+  participant s1 as owner
+  participant w0 as get-owner
+  start->>s0: findPets
+  s0-->>s0: again (retry)
+  Note over s0: retry after 1.5s, up to 3 times
+  start->>s1: owner
+  s1->>w0: workflow
+  s1->>s0: restart (goto)
+```
+"#.to_string()));
+  }
+
+  #[test]
+  fn renders_workflows_as_plantuml_activity_diagrams() {
+    let mut description = workflow_graph();
+    description.workflows[0].steps[0].success_criteria = vec![Criterion {
+      condition: "$statusCode == 200".to_string(),
+      .. Criterion::default()
+    }];
+    description.workflows[0].steps[1].on_failure = vec![Either::First(FailureObject {
+      name: "missing".to_string(),
+      r#type: "end".to_string(),
+      workflow_id: None,
+      step_id: None,
+      retry_after: None,
+      retry_limit: None,
+      criteria: vec![Criterion {
+        context: Some("$response.body".to_string()),
+        condition: "$.code".to_string(),
+        r#type: Some(Either::First("jsonpath".to_string())),
+        extensions: Default::default()
+      }],
+      extensions: Default::default()
+    })];
+
+    expect!(plantuml_activity(&description, "adopt").unwrap()).to(be_equal_to(r#"@startuml
+title adopt\nAdopt a "pet"
+start
+:find\nfindPets;
+if ($statusCode == 200) then (pass)
+else (fail)
+  :again\nretry;
+  detach
+endif
+:owner\nworkflow get-owner;
+if (success) then (pass)
+  :restart\ngo to step find;
+  detach
+else (fail)
+  if ($.code on $response.body (jsonpath)) then (missing)
+    :missing\nend;
+    end
+  else
+    end
+  endif
+endif
+stop
+@enduml
+"#.to_string()));
+    expect!(plantuml_activity(&description, "other")).to(be_err());
+  }
+}
@@ -0,0 +1,422 @@
+//! Support for applying the Payload Replacement Objects of a Request Body to its payload. The
+//! targets of the replacements are treated as JSON Pointers
+//! ([RFC 6901](https://www.rfc-editor.org/rfc/rfc6901)), or XPath expressions for XML payloads
+//! (requires the `xml` feature, see the [xml module](crate::xml) for the supported expressions).
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::either::Either;
+use crate::expressions::ExpressionResolver;
+use crate::extensions::AnyValue;
+use crate::payload_registry::{content_type_matches, create_payload, PayloadValue};
+use crate::payloads::{ExternalPayload, Payload, StringPayload};
+#[cfg(feature = "xml")] use crate::payloads::{is_xml_content_type, XmlPayload};
+use crate::v1_0::{PayloadReplacement, RequestBody};
+
+impl PayloadReplacement {
+  /// Returns the value to set at the target location. Runtime expressions are resolved using the
+  /// resolver.
+  pub fn resolve_value(&self, resolver: &dyn ExpressionResolver) -> anyhow::Result<AnyValue> {
+    match &self.value {
+      Either::First(value) => Ok(value.clone()),
+      Either::Second(expression) => resolver.resolve(expression)
+        .map_err(|err| anyhow!("[4.6.14.1 Fixed Fields] Failed to resolve the value for replacement target '{}': {}",
+          self.target, err))
+    }
+  }
+}
+
+impl RequestBody {
+  /// Applies the payload replacements to the payload, returning the modified payload. The payload
+  /// of the request body is not changed. Values that are runtime expressions are resolved using
+  /// the resolver.
+  ///
+  /// The targets are treated as JSON Pointers, and the payload must be able to be converted to
+  /// JSON (string payloads are parsed as JSON). The parent of each target location must exist in
+  /// the payload. With the `xml` feature, if the payload is XML (an `XmlPayload`, or a string
+  /// payload with an XML content type), the targets are treated as XPath expressions instead, and
+  /// each must select at least one element or attribute. Returns `None` if the request body has no
+  /// payload and no replacements.
+  pub fn apply_replacements(
+    &self,
+    resolver: &dyn ExpressionResolver
+  ) -> anyhow::Result<Option<Arc<dyn Payload + Send + Sync>>> {
+    let payload = match &self.payload {
+      Some(payload) => payload,
+      None if self.replacements.is_empty() => return Ok(None),
+      None => return Err(anyhow!("[4.6.13.1 Fixed Fields] Request body has replacements but no payload"))
+    };
+    if self.replacements.is_empty() {
+      return Ok(Some(payload.clone()));
+    }
+
+    #[cfg(feature = "xml")]
+    if let Some(xml) = xml_payload(payload.as_ref(), self.content_type.as_deref()) {
+      return self.apply_xpath_replacements(xml, resolver)
+        .map(|payload| Some(Arc::new(payload) as Arc<dyn Payload + Send + Sync>));
+    }
+
+    let is_string = payload.downcast_ref::<StringPayload>().is_some();
+    let mut json = match payload.as_json() {
+      Some(json) => json,
+      None => serde_json::from_str::<Value>(&payload_text(payload.as_ref())?)
+        .map_err(|err| anyhow!("[4.6.14.1 Fixed Fields] Replacements can only be applied to JSON payloads, \
+          but the payload could not be parsed as JSON: {}", err))?
+    };
+
+    for replacement in &self.replacements {
+      let value = replacement.resolve_value(resolver)?;
+      json_pointer_set(&mut json, &replacement.target, Value::from(&value))?;
+    }
+
+    if is_string {
+      Ok(Some(Arc::new(StringPayload(json.to_string()))))
+    } else {
+      Ok(Some(create_payload(&PayloadValue::Json(&json), self.content_type.as_deref())))
+    }
+  }
+}
+
+#[cfg(feature = "xml")]
+impl RequestBody {
+  fn apply_xpath_replacements(
+    &self,
+    mut payload: XmlPayload,
+    resolver: &dyn ExpressionResolver
+  ) -> anyhow::Result<XmlPayload> {
+    for replacement in &self.replacements {
+      let value = replacement.resolve_value(resolver)?;
+      let text = value.to_text()
+        .ok_or_else(|| anyhow!("[4.6.14.1 Fixed Fields] Replacement target '{}' can not be set to an \
+          array or object value in an XML payload", replacement.target))?;
+      let count = payload.set(&replacement.target, &text)
+        .map_err(|err| anyhow!("[4.6.14.1 Fixed Fields] Replacement target '{}' is not a supported XPath \
+          expression: {}", replacement.target, err))?;
+      if count == 0 {
+        return Err(anyhow!("[4.6.14.1 Fixed Fields] Replacement target '{}' does not match anything in the \
+          XML payload", replacement.target));
+      }
+    }
+    Ok(payload)
+  }
+}
+
+/// Returns the payload as an XML payload, if it is one, or if it is a string payload with an XML
+/// content type
+#[cfg(feature = "xml")]
+fn xml_payload(payload: &(dyn Payload + Send + Sync), content_type: Option<&str>) -> Option<XmlPayload> {
+  if let Some(xml) = payload.downcast_ref::<XmlPayload>() {
+    Some(xml.clone())
+  } else if payload.downcast_ref::<StringPayload>().is_some() && content_type.map(is_xml_content_type).unwrap_or_default() {
+    XmlPayload::parse(&payload.as_string()).ok()
+  } else {
+    None
+  }
+}
+
+/// Returns the text of the payload, loading external payloads (so any error loading them is
+/// returned)
+fn payload_text(payload: &(dyn Payload + Send + Sync)) -> anyhow::Result<String> {
+  match payload.downcast_ref::<ExternalPayload>() {
+    Some(external_payload) => Ok(String::from_utf8_lossy(&external_payload.load()?).to_string()),
+    None => Ok(payload.as_string())
+  }
+}
+
+/// Splits the JSON Pointer into the pointer to the parent and the (un-escaped) last token
+fn json_pointer_split(pointer: &str) -> anyhow::Result<(&str, String)> {
+  let (parent_pointer, token) = pointer.rsplit_once('/')
+    .ok_or_else(|| anyhow!("Replacement target '{}' is not a valid JSON Pointer", pointer))?;
+  Ok((parent_pointer, token.replace("~1", "/").replace("~0", "~")))
+}
+
+/// Index in an array for the token. `-` or the length of the array refer to the end of the array.
+fn json_pointer_index(token: &str, len: usize, pointer: &str) -> anyhow::Result<usize> {
+  let index = if token == "-" {
+    len
+  } else {
+    token.parse::<usize>()
+      .map_err(|_| anyhow!("Replacement target '{}' is not a valid array index", pointer))?
+  };
+  if index <= len {
+    Ok(index)
+  } else {
+    Err(anyhow!("Replacement target '{}' is past the end of the array", pointer))
+  }
+}
+
+/// Checks that a value can be set at the location of the JSON Pointer (see [`json_pointer_set`])
+pub fn json_pointer_check(json: &Value, pointer: &str) -> anyhow::Result<()> {
+  if pointer.is_empty() {
+    return Ok(());
+  }
+
+  let (parent_pointer, token) = json_pointer_split(pointer)?;
+  match json.pointer(parent_pointer) {
+    Some(Value::Object(_)) => Ok(()),
+    Some(Value::Array(array)) => json_pointer_index(&token, array.len(), pointer).map(|_| ()),
+    _ => Err(anyhow!("Replacement target '{}' does not exist in the payload", pointer))
+  }
+}
+
+/// Sets the value at the location of the JSON Pointer. Existing values are replaced, and new keys
+/// are added to objects. For arrays, the index must refer to an existing item, or be the length of
+/// the array (or `-`) to append the value. The parent of the location must exist.
+pub fn json_pointer_set(json: &mut Value, pointer: &str, value: Value) -> anyhow::Result<()> {
+  if pointer.is_empty() {
+    *json = value;
+    return Ok(());
+  }
+
+  let (parent_pointer, token) = json_pointer_split(pointer)?;
+  match json.pointer_mut(parent_pointer) {
+    Some(Value::Object(map)) => {
+      map.insert(token, value);
+      Ok(())
+    }
+    Some(Value::Array(array)) => {
+      let index = json_pointer_index(&token, array.len(), pointer)?;
+      if index < array.len() {
+        array[index] = value;
+      } else {
+        array.push(value);
+      }
+      Ok(())
+    }
+    _ => Err(anyhow!("Replacement target '{}' does not exist in the payload", pointer))
+  }
+}
+
+/// Checks that the targets of all the replacements address a location in the payload of the
+/// request body, returning the index and error for each invalid target. Only payloads with a known
+/// structure are checked (JSON and XML payloads, or string payloads that can be parsed as JSON or
+/// XML based on the content type). Runtime expressions are not resolved.
+pub fn check_replacement_targets(body: &RequestBody) -> Vec<(usize, anyhow::Error)> {
+  let Some(payload) = &body.payload else {
+    return body.replacements.iter().enumerate()
+      .map(|(index, _)| (index, anyhow!("Request body has replacements but no payload")))
+      .collect();
+  };
+
+  #[cfg(feature = "xml")]
+  if let Some(xml) = xml_payload(payload.as_ref(), body.content_type.as_deref()) {
+    return body.replacements.iter().enumerate()
+      .filter_map(|(index, replacement)| match xml.document().select(&replacement.target) {
+        Ok(nodes) if nodes.is_empty() => Some((index, anyhow!("Replacement target '{}' does not match anything in the \
+          XML payload", replacement.target))),
+        Ok(_) => None,
+        Err(err) => Some((index, err))
+      })
+      .collect();
+  }
+
+  let is_string = payload.downcast_ref::<StringPayload>().is_some();
+  let json = match payload.as_json() {
+    Some(json) => json,
+    None if is_string && body.content_type.as_deref().map(is_json_content_type).unwrap_or_default() => {
+      match serde_json::from_str::<Value>(&payload.as_string()) {
+        Ok(json) => json,
+        // String payloads are often templates that are not valid JSON until rendered
+        Err(_) => return vec![]
+      }
+    }
+    None => return vec![]
+  };
+
+  body.replacements.iter().enumerate()
+    .filter_map(|(index, replacement)| json_pointer_check(&json, &replacement.target).err().map(|err| (index, err)))
+    .collect()
+}
+
+fn is_json_content_type(content_type: &str) -> bool {
+  content_type_matches("application/json", content_type) || content_type_matches("*/*+json", content_type)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+  use std::sync::Arc;
+
+  use expectest::prelude::*;
+  use maplit::hashmap;
+  use serde_json::json;
+
+  use crate::either::Either;
+  use crate::extensions::AnyValue;
+  use crate::payloads::{ExternalPayload, FormPayload, JsonPayload, StringPayload};
+  #[cfg(feature = "xml")] use crate::payloads::XmlPayload;
+  use crate::replacements::{check_replacement_targets, json_pointer_check, json_pointer_set};
+  use crate::v1_0::{PayloadReplacement, RequestBody};
+
+  fn replacement(target: &str, value: Either<AnyValue, String>) -> PayloadReplacement {
+    PayloadReplacement { target: target.to_string(), value, extensions: Default::default() }
+  }
+
+  #[test]
+  fn set_values_with_json_pointers() {
+    let mut json = json!({ "a": { "b/c": 1 }, "list": [1, 2] });
+    json_pointer_set(&mut json, "/a/b~1c", json!(2)).unwrap();
+    json_pointer_set(&mut json, "/a/d", json!("new")).unwrap();
+    json_pointer_set(&mut json, "/list/0", json!(10)).unwrap();
+    json_pointer_set(&mut json, "/list/-", json!(3)).unwrap();
+    json_pointer_set(&mut json, "/list/3", json!(4)).unwrap();
+    expect!(&json).to(be_equal_to(&json!({ "a": { "b/c": 2, "d": "new" }, "list": [10, 2, 3, 4] })));
+
+    expect!(json_pointer_set(&mut json, "/list/10", json!(1))).to(be_err());
+    expect!(json_pointer_set(&mut json, "/list/x", json!(1))).to(be_err());
+    expect!(json_pointer_set(&mut json, "/missing/a", json!(1)).unwrap_err().to_string())
+      .to(be_equal_to("Replacement target '/missing/a' does not exist in the payload"));
+    expect!(json_pointer_set(&mut json, "/a/d/e", json!(1))).to(be_err());
+    expect!(json_pointer_set(&mut json, "a", json!(1))).to(be_err());
+
+    expect!(json_pointer_check(&json, "/list/4")).to(be_ok());
+    expect!(json_pointer_check(&json, "/list/5")).to(be_err());
+    expect!(json_pointer_check(&json, "/a/new")).to(be_ok());
+    expect!(json_pointer_check(&json, "/a/d/e")).to(be_err());
+    expect!(json_pointer_check(&json, "")).to(be_ok());
+
+    json_pointer_set(&mut json, "", json!(true)).unwrap();
+    expect!(json).to(be_equal_to(json!(true)));
+  }
+
+  #[test]
+  fn apply_replacements() {
+    let body = RequestBody {
+      content_type: Some("application/json".to_string()),
+      payload: Some(Arc::new(JsonPayload(json!({ "petId": null, "quantity": 1, "tags": [] })))),
+      replacements: vec![
+        replacement("/petId", Either::Second("$inputs.pet_id".to_string())),
+        replacement("/quantity", Either::First(AnyValue::Integer(10))),
+        replacement("/tags/-", Either::First(AnyValue::String("dog".to_string())))
+      ],
+      extensions: Default::default()
+    };
+    let resolver = hashmap!{ "$inputs.pet_id".to_string() => AnyValue::Integer(100) };
+    let payload = body.apply_replacements(&resolver).unwrap().unwrap();
+    expect!(payload.downcast_ref::<JsonPayload>().map(|p| p.0.clone()))
+      .to(be_some().value(json!({ "petId": 100, "quantity": 10, "tags": ["dog"] })));
+    expect!(body.payload_json()).to(be_some().value(json!({ "petId": null, "quantity": 1, "tags": [] })));
+
+    let err = body.apply_replacements(&HashMap::new()).unwrap_err();
+    expect!(err.to_string()).to(be_equal_to("[4.6.14.1 Fixed Fields] Failed to resolve the value for replacement \
+      target '/petId': Runtime expression '$inputs.pet_id' could not be resolved"));
+  }
+
+  #[test]
+  fn apply_replacements_to_other_payloads() {
+    let resolver = |expression: &str| Ok(AnyValue::String(expression.to_uppercase()));
+
+    let body = RequestBody {
+      content_type: Some("application/json".to_string()),
+      payload: Some(Arc::new(StringPayload("{\"id\": \"\"}".to_string()))),
+      replacements: vec![replacement("/id", Either::Second("$inputs.id".to_string()))],
+      extensions: Default::default()
+    };
+    let payload = body.apply_replacements(&resolver).unwrap().unwrap();
+    expect!(payload.downcast_ref::<StringPayload>().map(|p| p.0.clone()))
+      .to(be_some().value("{\"id\":\"$INPUTS.ID\"}"));
+
+    let body = RequestBody {
+      content_type: Some("application/x-www-form-urlencoded".to_string()),
+      payload: Some(Arc::new(FormPayload::default())),
+      .. body.clone()
+    };
+    let payload = body.apply_replacements(&resolver).unwrap().unwrap();
+    expect!(payload.downcast_ref::<FormPayload>().is_some()).to(be_true());
+    expect!(payload.as_string()).to(be_equal_to("id=%24INPUTS.ID"));
+
+    let body = RequestBody {
+      payload: Some(Arc::new(StringPayload("not JSON".to_string()))),
+      .. body.clone()
+    };
+    expect!(body.apply_replacements(&resolver)).to(be_err());
+
+    let body = RequestBody {
+      payload: Some(Arc::new(ExternalPayload::file("/does/not/exist.json"))),
+      .. body.clone()
+    };
+    expect!(body.apply_replacements(&resolver).unwrap_err().to_string())
+      .to(be_equal_to("Failed to load payload from '/does/not/exist.json'"));
+
+    let body = RequestBody { payload: None, .. body.clone() };
+    expect!(body.apply_replacements(&resolver)).to(be_err());
+    let body = RequestBody { replacements: vec![], .. body.clone() };
+    expect!(body.apply_replacements(&resolver).unwrap().is_none()).to(be_true());
+  }
+
+  #[test]
+  #[cfg(feature = "xml")]
+  fn apply_xpath_replacements() {
+    let body = RequestBody {
+      content_type: Some("application/xml".to_string()),
+      payload: Some(Arc::new(XmlPayload::parse("<order id=\"\"><pet>?</pet><qty>1</qty><qty>2</qty></order>").unwrap())),
+      replacements: vec![
+        replacement("/order/@id", Either::Second("$inputs.order_id".to_string())),
+        replacement("/order/pet", Either::First(AnyValue::String("Fido & Co".to_string()))),
+        replacement("//qty", Either::First(AnyValue::Integer(5)))
+      ],
+      extensions: Default::default()
+    };
+    let resolver = hashmap!{ "$inputs.order_id".to_string() => AnyValue::Integer(100) };
+    let payload = body.apply_replacements(&resolver).unwrap().unwrap();
+    expect!(payload.downcast_ref::<XmlPayload>().is_some()).to(be_true());
+    expect!(payload.as_string())
+      .to(be_equal_to("<order id=\"100\"><pet>Fido &amp; Co</pet><qty>5</qty><qty>5</qty></order>"));
+
+    let body = RequestBody {
+      payload: Some(Arc::new(StringPayload("<order><pet/></order>".to_string()))),
+      replacements: vec![replacement("/order/pet", Either::First(AnyValue::Boolean(true)))],
+      .. body.clone()
+    };
+    expect!(body.apply_replacements(&resolver).unwrap().unwrap().as_string()).to(be_equal_to("<order><pet>true</pet></order>"));
+
+    let body = RequestBody {
+      replacements: vec![replacement("/order/owner", Either::First(AnyValue::Boolean(true)))],
+      .. body.clone()
+    };
+    expect!(body.apply_replacements(&resolver).unwrap_err().to_string()).to(be_equal_to(
+      "[4.6.14.1 Fixed Fields] Replacement target '/order/owner' does not match anything in the XML payload"));
+
+    let body = RequestBody {
+      replacements: vec![replacement("/order/pet[", Either::First(AnyValue::Boolean(true)))],
+      .. body.clone()
+    };
+    expect!(body.apply_replacements(&resolver)).to(be_err());
+
+    let body = RequestBody {
+      replacements: vec![replacement("/order/pet", Either::First(AnyValue::Array(vec![])))],
+      .. body.clone()
+    };
+    expect!(body.apply_replacements(&resolver)).to(be_err());
+  }
+
+  #[test]
+  fn check_targets() {
+    let body = RequestBody {
+      content_type: Some("application/json".to_string()),
+      payload: Some(Arc::new(JsonPayload(json!({ "pet": { "id": 1 }, "tags": [] })))),
+      replacements: vec![
+        replacement("/pet/id", Either::Second("$inputs.pet_id".to_string())),
+        replacement("/owner/id", Either::First(AnyValue::Integer(1))),
+        replacement("/tags/-", Either::First(AnyValue::Integer(1)))
+      ],
+      extensions: Default::default()
+    };
+    let errors = check_replacement_targets(&body).iter()
+      .map(|(index, err)| (*index, err.to_string()))
+      .collect::<Vec<_>>();
+    expect!(errors).to(be_equal_to(vec![(1, "Replacement target '/owner/id' does not exist in the payload".to_string())]));
+
+    let body = RequestBody {
+      payload: Some(Arc::new(StringPayload("{\"pet\": {$inputs.pet}}".to_string()))),
+      .. body.clone()
+    };
+    expect!(check_replacement_targets(&body).is_empty()).to(be_true());
+
+    let body = RequestBody { payload: None, .. body.clone() };
+    expect!(check_replacement_targets(&body).len()).to(be_equal_to(3));
+  }
+}
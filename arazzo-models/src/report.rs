@@ -0,0 +1,340 @@
+//! Structured reports of the results of executing workflows, for CI systems and dashboards. With
+//! the `serialize` feature, reports can be written as JSON. Secrets can be removed from a report
+//! before it is written with [`ExecutionReport::redact`] (see the [redaction module](crate::redaction)).
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::execution_context::{StepRequest, StepResponse};
+use crate::executor::{CriterionResult, ExecutionStatus, StepResult, WorkflowResult};
+use crate::extensions::AnyValue;
+use crate::html_report::{render_html, HtmlOptions};
+use crate::junit::{render_junit, JUnitOptions};
+use crate::redaction::Redactor;
+
+/// Counts of the workflows and steps in a report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReportSummary {
+  /// Number of workflows that were executed (excluding nested workflows)
+  pub workflows: usize,
+  /// Number of workflows that completed successfully
+  pub passed: usize,
+  /// Number of workflows that failed (including workflows that timed out)
+  pub failed: usize,
+  /// Number of workflows that could not be executed
+  pub errors: usize,
+  /// Number of step executions (including retries and the steps of nested workflows)
+  pub steps: usize
+}
+
+/// Outcome of a step execution
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepReport {
+  /// Step ID
+  pub step_id: String,
+  /// Outcome of the step
+  pub status: ExecutionStatus,
+  /// Attempt number (starting at 1)
+  pub attempt: usize,
+  /// Time taken to execute the step
+  pub duration: Duration,
+  /// HTTP method of the request (for operation steps)
+  pub method: Option<String>,
+  /// URL of the request (for operation steps)
+  pub url: Option<String>,
+  /// Status code of the response (for operation steps)
+  pub status_code: Option<u16>,
+  /// Request that was sent, including the headers and body (for operation steps). This is not
+  /// written to the JSON report, as it can contain secrets.
+  pub request: Option<StepRequest>,
+  /// Response that was received (for operation steps). This is not written to the JSON report.
+  pub response: Option<StepResponse>,
+  /// Results of the success criteria
+  pub criteria: Vec<CriterionResult>,
+  /// Outputs extracted from the step
+  pub outputs: BTreeMap<String, AnyValue>,
+  /// Name of the success or failure action that was followed
+  pub action: Option<String>,
+  /// Report of the workflow that was executed (for workflow steps and `goto` actions)
+  pub workflow: Option<Box<WorkflowReport>>,
+  /// Error if the step could not be executed
+  pub error: Option<String>
+}
+
+impl From<&StepResult> for StepReport {
+  fn from(result: &StepResult) -> Self {
+    StepReport {
+      step_id: result.step_id.clone(),
+      status: result.status,
+      attempt: result.attempt,
+      duration: result.duration,
+      method: result.request.as_ref().map(|request| request.method.clone()),
+      url: result.request.as_ref().map(|request| request.url.clone()),
+      status_code: result.response.as_ref().map(|response| response.status),
+      request: result.request.clone(),
+      response: result.response.clone(),
+      criteria: result.criteria.clone(),
+      outputs: result.outputs.clone(),
+      action: result.action.clone(),
+      workflow: result.workflow.as_ref().map(|workflow| Box::new(WorkflowReport::from(workflow.as_ref()))),
+      error: result.error.clone()
+    }
+  }
+}
+
+/// Outcome of a workflow execution
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkflowReport {
+  /// Workflow ID
+  pub workflow_id: String,
+  /// Outcome of the workflow
+  pub status: ExecutionStatus,
+  /// Time taken to execute the workflow
+  pub duration: Duration,
+  /// Step executions, in the order they were executed
+  pub steps: Vec<StepReport>,
+  /// Outputs of the workflow
+  pub outputs: BTreeMap<String, AnyValue>,
+  /// Error if the workflow could not be executed
+  pub error: Option<String>
+}
+
+impl StepReport {
+  fn redact(&mut self, redactor: &Redactor) {
+    self.url = self.url.as_ref().map(|url| redactor.redact_url(url));
+    self.request = self.request.as_ref().map(|request| redactor.redact_request(request));
+    self.response = self.response.as_ref().map(|response| redactor.redact_response(response));
+    for criterion in &mut self.criteria {
+      criterion.error = criterion.error.as_ref().map(|error| redactor.redact_str(error));
+    }
+    redact_outputs(&mut self.outputs, redactor);
+    if let Some(workflow) = &mut self.workflow {
+      workflow.redact(redactor);
+    }
+    self.error = self.error.as_ref().map(|error| redactor.redact_str(error));
+  }
+}
+
+impl WorkflowReport {
+  fn redact(&mut self, redactor: &Redactor) {
+    for step in &mut self.steps {
+      step.redact(redactor);
+    }
+    redact_outputs(&mut self.outputs, redactor);
+    self.error = self.error.as_ref().map(|error| redactor.redact_str(error));
+  }
+
+  fn step_count(&self) -> usize {
+    self.steps.iter()
+      .map(|step| 1 + step.workflow.as_ref().map(|workflow| workflow.step_count()).unwrap_or_default())
+      .sum()
+  }
+}
+
+impl From<&WorkflowResult> for WorkflowReport {
+  fn from(result: &WorkflowResult) -> Self {
+    WorkflowReport {
+      workflow_id: result.workflow_id.clone(),
+      status: result.status,
+      duration: result.duration,
+      steps: result.steps.iter().map(StepReport::from).collect(),
+      outputs: result.outputs.clone(),
+      error: result.error.clone()
+    }
+  }
+}
+
+/// Report of the results of executing a set of workflows
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionReport {
+  /// Overall outcome (the worst outcome of the workflows)
+  pub status: ExecutionStatus,
+  /// Total time taken to execute the workflows
+  pub duration: Duration,
+  /// Counts of the workflows and steps
+  pub summary: ReportSummary,
+  /// Reports of the workflows, in the order they were executed
+  pub workflows: Vec<WorkflowReport>
+}
+
+impl ExecutionReport {
+  /// Creates a report from the results of executing workflows
+  pub fn new(results: &[WorkflowResult]) -> Self {
+    let workflows = results.iter().map(WorkflowReport::from).collect::<Vec<_>>();
+    let count = |status: ExecutionStatus| workflows.iter().filter(|workflow| workflow.status == status).count();
+    let summary = ReportSummary {
+      workflows: workflows.len(),
+      passed: count(ExecutionStatus::Success),
+      failed: count(ExecutionStatus::Failure) + count(ExecutionStatus::Timeout),
+      errors: count(ExecutionStatus::Error),
+      steps: workflows.iter().map(|workflow| workflow.step_count()).sum()
+    };
+    let status = if summary.errors > 0 {
+      ExecutionStatus::Error
+    } else if summary.failed > 0 {
+      ExecutionStatus::Failure
+    } else {
+      ExecutionStatus::Success
+    };
+    ExecutionReport {
+      status,
+      duration: workflows.iter().map(|workflow| workflow.duration).sum(),
+      summary,
+      workflows
+    }
+  }
+
+  /// Redacts the secrets in the requests and responses, URLs, outputs and errors of the report
+  pub fn redact(&mut self, redactor: &Redactor) {
+    for workflow in &mut self.workflows {
+      workflow.redact(redactor);
+    }
+  }
+
+  /// If all the workflows completed successfully
+  pub fn is_success(&self) -> bool {
+    self.status == ExecutionStatus::Success
+  }
+
+  /// Returns the report as JUnit XML (see the [junit module](crate::junit))
+  pub fn to_junit_xml(&self, options: &JUnitOptions) -> String {
+    render_junit(self, options)
+  }
+
+  /// Returns the report as a self-contained HTML page (see the [html_report module](crate::html_report))
+  pub fn to_html(&self, options: &HtmlOptions) -> String {
+    render_html(self, options)
+  }
+
+  /// Returns the report as pretty-printed JSON
+  #[cfg(feature = "serialize")]
+  pub fn to_json_string(&self) -> anyhow::Result<String> {
+    Ok(serde_json::to_string_pretty(self)?)
+  }
+}
+
+fn redact_outputs(outputs: &mut BTreeMap<String, AnyValue>, redactor: &Redactor) {
+  for (name, value) in outputs.iter_mut() {
+    *value = redactor.redact_value(name, value);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use expectest::prelude::*;
+  use maplit::btreemap;
+
+  use crate::execution_context::{StepRequest, StepResponse};
+  use crate::executor::{CriterionResult, ExecutionStatus, StepResult, WorkflowResult};
+  use crate::extensions::AnyValue;
+  use crate::redaction::Redactor;
+  use crate::report::{ExecutionReport, ReportSummary};
+
+  fn results() -> Vec<WorkflowResult> {
+    let step = StepResult {
+      step_id: "find".to_string(),
+      status: ExecutionStatus::Success,
+      request: Some(StepRequest {
+        method: "GET".to_string(),
+        url: "http://localhost/pets".to_string(),
+        headers: vec![],
+        body: None
+      }),
+      response: Some(StepResponse::new(200)),
+      criteria: vec![CriterionResult { condition: "$statusCode == 200".to_string(), passed: true, error: None }],
+      outputs: btreemap!{ "id".to_string() => AnyValue::UInteger(1) },
+      workflow: None,
+      action: None,
+      attempt: 1,
+      duration: Duration::from_millis(15),
+      error: None
+    };
+    vec![
+      WorkflowResult {
+        workflow_id: "pets".to_string(),
+        status: ExecutionStatus::Success,
+        steps: vec![step.clone()],
+        outputs: btreemap!{ "id".to_string() => AnyValue::UInteger(1) },
+        duration: Duration::from_millis(20),
+        error: None,
+        captures: Default::default()
+      },
+      WorkflowResult {
+        workflow_id: "owners".to_string(),
+        status: ExecutionStatus::Failure,
+        steps: vec![StepResult {
+          step_id: "owner".to_string(),
+          status: ExecutionStatus::Failure,
+          response: Some(StepResponse::new(404)),
+          criteria: vec![CriterionResult { condition: "$statusCode == 200".to_string(), passed: false, error: None }],
+          outputs: Default::default(),
+          .. step
+        }],
+        outputs: Default::default(),
+        duration: Duration::from_millis(5),
+        error: Some("Step 'owner' failed".to_string()),
+        captures: Default::default()
+      }
+    ]
+  }
+
+  #[test]
+  fn summarises_the_results() {
+    let report = ExecutionReport::new(&results());
+    expect!(report.status).to(be_equal_to(ExecutionStatus::Failure));
+    expect!(report.is_success()).to(be_false());
+    expect!(report.duration).to(be_equal_to(Duration::from_millis(25)));
+    expect!(report.summary).to(be_equal_to(ReportSummary { workflows: 2, passed: 1, failed: 1, errors: 0, steps: 2 }));
+    let step = &report.workflows[1].steps[0];
+    expect!(step.status_code).to(be_some().value(404));
+    expect!(step.url.as_deref()).to(be_some().value("http://localhost/pets"));
+  }
+
+  #[test]
+  fn redacts_secrets_in_the_report() {
+    let mut results = results();
+    results[0].steps[0].request.as_mut().unwrap().url = "http://localhost/pets?api_key=k-1".to_string();
+    results[0].steps[0].outputs.insert("token".to_string(), AnyValue::String("t-1".to_string()));
+    results[1].error = Some("Login failed for key k-1".to_string());
+    let mut report = ExecutionReport::new(&results);
+    report.redact(&Redactor::new().with_value("k-1"));
+
+    let step = &report.workflows[0].steps[0];
+    expect!(step.url.as_deref()).to(be_some().value("http://localhost/pets?api_key=***"));
+    expect!(step.request.as_ref().unwrap().url.as_str()).to(be_equal_to("http://localhost/pets?api_key=***"));
+    expect!(step.outputs.get("token")).to(be_some().value(&AnyValue::String("***".to_string())));
+    expect!(step.outputs.get("id")).to(be_some().value(&AnyValue::UInteger(1)));
+    expect!(report.workflows[1].error.as_deref()).to(be_some().value("Login failed for key ***"));
+  }
+
+  #[test]
+  #[cfg(feature = "serialize")]
+  fn serialises_the_report_to_json() {
+    let report = ExecutionReport::new(&results()[..1]);
+    let json: serde_json::Value = serde_json::from_str(&report.to_json_string().unwrap()).unwrap();
+    expect!(json).to(be_equal_to(serde_json::json!({
+      "status": "success",
+      "durationMs": 20,
+      "summary": { "workflows": 1, "passed": 1, "failed": 0, "errors": 0, "steps": 1 },
+      "workflows": [{
+        "workflowId": "pets",
+        "status": "success",
+        "durationMs": 20,
+        "steps": [{
+          "stepId": "find",
+          "status": "success",
+          "attempt": 1,
+          "durationMs": 15,
+          "method": "GET",
+          "url": "http://localhost/pets",
+          "statusCode": 200,
+          "criteria": [{ "condition": "$statusCode == 200", "passed": true }],
+          "outputs": { "id": 1 }
+        }],
+        "outputs": { "id": 1 }
+      }]
+    })));
+  }
+}
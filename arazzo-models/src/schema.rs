@@ -0,0 +1,567 @@
+//! Implementations of the schemars `JsonSchema` trait for the models (requires the `schemars`
+//! feature). The schemas describe the form the models are written in by the `serialize` feature
+//! (i.e. with the camel case keys of the Arazzo document, and extension values as `x-` keys), so
+//! structures that embed the models can publish a JSON Schema of their configuration.
+//!
+//! ```rust
+//! # use arazzo_models::v1_0::ArazzoDescription;
+//! let schema = schemars::schema_for!(ArazzoDescription);
+//! let json = serde_json::to_string_pretty(&schema).unwrap();
+//! ```
+
+use std::borrow::Cow;
+use std::fmt::Debug;
+
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+
+use crate::either::Either;
+use crate::extensions::AnyValue;
+use crate::v1_0::*;
+
+/// Pattern of the keys of extension values
+const EXTENSION_PATTERN: &str = "^x-";
+
+/// Pattern of the keys of the component maps
+const COMPONENT_KEY_PATTERN: &str = "^[a-zA-Z0-9\\.\\-_]+$";
+
+impl JsonSchema for AnyValue {
+  fn schema_name() -> Cow<'static, str> {
+    "AnyValue".into()
+  }
+
+  fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+      "description": "Any value that can be expressed in JSON form"
+    })
+  }
+}
+
+impl <A, B> JsonSchema for Either<A, B>
+  where A: Debug + Clone + PartialEq + JsonSchema,
+        B: Debug + Clone + PartialEq + JsonSchema {
+  fn inline_schema() -> bool {
+    true
+  }
+
+  fn schema_name() -> Cow<'static, str> {
+    format!("Either_{}_or_{}", A::schema_name(), B::schema_name()).into()
+  }
+
+  fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+      "anyOf": [
+        generator.subschema_for::<A>(),
+        generator.subschema_for::<B>()
+      ]
+    })
+  }
+}
+
+impl JsonSchema for ArazzoDescription {
+  fn schema_name() -> Cow<'static, str> {
+    "ArazzoDescription".into()
+  }
+
+  fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+      "description": "4.6.1 Arazzo Description is the root object of the Arazzo document",
+      "type": "object",
+      "properties": {
+        "arazzo": {
+          "description": "Version number of the Arazzo Specification",
+          "type": "string",
+          "pattern": "^1\\.0\\.\\d+(-.+)?$"
+        },
+        "info": generator.subschema_for::<Info>(),
+        "sourceDescriptions": {
+          "description": "List of source descriptions",
+          "type": "array",
+          "items": generator.subschema_for::<SourceDescription>(),
+          "minItems": 1
+        },
+        "workflows": {
+          "description": "List of workflows",
+          "type": "array",
+          "items": generator.subschema_for::<Workflow>(),
+          "minItems": 1
+        },
+        "components": generator.subschema_for::<Components>()
+      },
+      "required": ["arazzo", "info", "sourceDescriptions", "workflows"],
+      "patternProperties": { EXTENSION_PATTERN: true },
+      "additionalProperties": false
+    })
+  }
+}
+
+impl JsonSchema for Info {
+  fn schema_name() -> Cow<'static, str> {
+    "Info".into()
+  }
+
+  fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+      "description": "4.6.2 Info Object, metadata about API workflows defined in the Arazzo document",
+      "type": "object",
+      "properties": {
+        "title": { "description": "A human-readable title of the Arazzo Description", "type": "string" },
+        "summary": { "description": "A short summary of the Arazzo Description", "type": "string" },
+        "description": { "description": "A description of the purpose of the workflows defined", "type": "string" },
+        "version": { "description": "Document version", "type": "string" }
+      },
+      "required": ["title", "version"],
+      "patternProperties": { EXTENSION_PATTERN: true },
+      "additionalProperties": false
+    })
+  }
+}
+
+impl JsonSchema for SourceDescription {
+  fn schema_name() -> Cow<'static, str> {
+    "SourceDescription".into()
+  }
+
+  fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+      "description": "4.6.3 Source Description Object",
+      "type": "object",
+      "properties": {
+        "name": {
+          "description": "Unique name for the source description",
+          "type": "string",
+          "pattern": "^[A-Za-z0-9_\\-]+$"
+        },
+        "url": { "description": "URL to a source description to be used by a workflow", "type": "string" },
+        "type": { "description": "The type of source description", "enum": ["openapi", "arazzo"] }
+      },
+      "required": ["name", "url"],
+      "patternProperties": { EXTENSION_PATTERN: true },
+      "additionalProperties": false
+    })
+  }
+}
+
+impl JsonSchema for Workflow {
+  fn schema_name() -> Cow<'static, str> {
+    "Workflow".into()
+  }
+
+  fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+      "description": "4.6.4 Workflow Object",
+      "type": "object",
+      "properties": {
+        "workflowId": { "description": "Unique string to represent the workflow", "type": "string" },
+        "summary": { "description": "Summary of the purpose or objective of the workflow", "type": "string" },
+        "description": { "description": "Description of the workflow", "type": "string" },
+        "inputs": {
+          "description": "JSON Schema 2020-12 object representing the input parameters used by the workflow",
+          "type": "object"
+        },
+        "dependsOn": {
+          "description": "List of workflows that must be completed before this workflow can be processed",
+          "type": "array",
+          "items": { "type": "string" },
+          "uniqueItems": true
+        },
+        "steps": {
+          "description": "An ordered list of workflow steps",
+          "type": "array",
+          "items": generator.subschema_for::<Step>(),
+          "minItems": 1
+        },
+        "successActions": {
+          "description": "List of success actions that are applicable for all steps described under the workflow",
+          "type": "array",
+          "items": generator.subschema_for::<Either<SuccessObject, ReusableObject>>()
+        },
+        "failureActions": {
+          "description": "List of failure actions that are applicable for all steps described under the workflow",
+          "type": "array",
+          "items": generator.subschema_for::<Either<FailureObject, ReusableObject>>()
+        },
+        "outputs": outputs_schema("Defined outputs of the workflow"),
+        "parameters": {
+          "description": "List of parameters that are applicable for all steps described under the workflow",
+          "type": "array",
+          "items": generator.subschema_for::<Either<ParameterObject, ReusableObject>>()
+        }
+      },
+      "required": ["workflowId", "steps"],
+      "patternProperties": { EXTENSION_PATTERN: true },
+      "additionalProperties": false
+    })
+  }
+}
+
+impl JsonSchema for Step {
+  fn schema_name() -> Cow<'static, str> {
+    "Step".into()
+  }
+
+  fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+      "description": "4.6.5 Step Object",
+      "type": "object",
+      "properties": {
+        "stepId": { "description": "Unique string to represent the step", "type": "string" },
+        "operationId": {
+          "description": "Name of an existing, resolvable operation within one of the source descriptions",
+          "type": "string"
+        },
+        "operationPath": {
+          "description": "Reference to a Source Description Object combined with a JSON Pointer to reference an operation",
+          "type": "string"
+        },
+        "workflowId": {
+          "description": "The workflow Id referencing an existing workflow within the Arazzo Description",
+          "type": "string"
+        },
+        "description": { "description": "Description of the step", "type": "string" },
+        "parameters": {
+          "description": "List of parameters that must be passed to an operation or workflow",
+          "type": "array",
+          "items": generator.subschema_for::<Either<ParameterObject, ReusableObject>>()
+        },
+        "requestBody": generator.subschema_for::<RequestBody>(),
+        "successCriteria": {
+          "description": "List of assertions to determine the success of the step",
+          "type": "array",
+          "items": generator.subschema_for::<Criterion>()
+        },
+        "onSuccess": {
+          "description": "Array of success action objects that specify what to do upon step success",
+          "type": "array",
+          "items": generator.subschema_for::<Either<SuccessObject, ReusableObject>>()
+        },
+        "onFailure": {
+          "description": "Array of failure action objects that specify what to do upon step failure",
+          "type": "array",
+          "items": generator.subschema_for::<Either<FailureObject, ReusableObject>>()
+        },
+        "outputs": outputs_schema("Defined outputs of the step")
+      },
+      "required": ["stepId"],
+      "oneOf": [
+        { "required": ["operationId"] },
+        { "required": ["operationPath"] },
+        { "required": ["workflowId"] }
+      ],
+      "patternProperties": { EXTENSION_PATTERN: true },
+      "additionalProperties": false
+    })
+  }
+}
+
+/// Schema of the outputs of a workflow or step, which are runtime expressions keyed by name
+fn outputs_schema(description: &str) -> Schema {
+  json_schema!({
+    "description": description,
+    "type": "object",
+    "propertyNames": { "pattern": COMPONENT_KEY_PATTERN },
+    "additionalProperties": { "type": "string" }
+  })
+}
+
+impl JsonSchema for ParameterObject {
+  fn schema_name() -> Cow<'static, str> {
+    "ParameterObject".into()
+  }
+
+  fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+      "description": "4.6.6 Parameter Object",
+      "type": "object",
+      "properties": {
+        "name": { "description": "The name of the parameter", "type": "string" },
+        "in": { "description": "The location of the parameter", "enum": ["path", "query", "header", "cookie"] },
+        "value": { "description": "Value to pass in the parameter, or a runtime expression" }
+      },
+      "required": ["name", "value"],
+      "patternProperties": { EXTENSION_PATTERN: true },
+      "additionalProperties": false
+    })
+  }
+}
+
+impl JsonSchema for SuccessObject {
+  fn schema_name() -> Cow<'static, str> {
+    "SuccessObject".into()
+  }
+
+  fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+      "description": "4.6.7 Success Action Object",
+      "type": "object",
+      "properties": {
+        "name": { "description": "The name of the success action", "type": "string" },
+        "type": { "description": "The type of action to take", "enum": ["end", "goto"] },
+        "workflowId": {
+          "description": "The workflowId of the workflow to transfer to upon success of the step",
+          "type": "string"
+        },
+        "stepId": { "description": "The stepId to transfer to upon success of the step", "type": "string" },
+        "criteria": {
+          "description": "List of assertions to determine if this action shall be executed",
+          "type": "array",
+          "items": generator.subschema_for::<Criterion>()
+        }
+      },
+      "required": ["name", "type"],
+      "patternProperties": { EXTENSION_PATTERN: true },
+      "additionalProperties": false
+    })
+  }
+}
+
+impl JsonSchema for FailureObject {
+  fn schema_name() -> Cow<'static, str> {
+    "FailureObject".into()
+  }
+
+  fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+      "description": "4.6.8 Failure Action Object",
+      "type": "object",
+      "properties": {
+        "name": { "description": "The name of the failure action", "type": "string" },
+        "type": { "description": "The type of action to take", "enum": ["end", "goto", "retry"] },
+        "workflowId": {
+          "description": "The workflowId of the workflow to transfer to upon failure of the step",
+          "type": "string"
+        },
+        "stepId": { "description": "The stepId to transfer to upon failure of the step", "type": "string" },
+        "retryAfter": {
+          "description": "Seconds to delay after the step failure before another attempt shall be made",
+          "type": "number",
+          "minimum": 0
+        },
+        "retryLimit": {
+          "description": "How many attempts to retry the step may be attempted before failing the overall step",
+          "type": "integer",
+          "minimum": 0
+        },
+        "criteria": {
+          "description": "List of assertions to determine if this action shall be executed",
+          "type": "array",
+          "items": generator.subschema_for::<Criterion>()
+        }
+      },
+      "required": ["name", "type"],
+      "patternProperties": { EXTENSION_PATTERN: true },
+      "additionalProperties": false
+    })
+  }
+}
+
+impl JsonSchema for Components {
+  fn schema_name() -> Cow<'static, str> {
+    "Components".into()
+  }
+
+  fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+      "description": "4.6.9 Components Object, holds reusable objects for the workflows",
+      "type": "object",
+      "properties": {
+        "inputs": {
+          "description": "Reusable JSON Schema objects to be referenced from workflow inputs",
+          "type": "object",
+          "propertyNames": { "pattern": COMPONENT_KEY_PATTERN },
+          "additionalProperties": { "type": "object" }
+        },
+        "parameters": {
+          "description": "Reusable Parameter Objects",
+          "type": "object",
+          "propertyNames": { "pattern": COMPONENT_KEY_PATTERN },
+          "additionalProperties": generator.subschema_for::<ParameterObject>()
+        },
+        "successActions": {
+          "description": "Reusable Success Actions Objects",
+          "type": "object",
+          "propertyNames": { "pattern": COMPONENT_KEY_PATTERN },
+          "additionalProperties": generator.subschema_for::<SuccessObject>()
+        },
+        "failureActions": {
+          "description": "Reusable Failure Actions Objects",
+          "type": "object",
+          "propertyNames": { "pattern": COMPONENT_KEY_PATTERN },
+          "additionalProperties": generator.subschema_for::<FailureObject>()
+        }
+      },
+      "patternProperties": { EXTENSION_PATTERN: true },
+      "additionalProperties": false
+    })
+  }
+}
+
+impl JsonSchema for ReusableObject {
+  fn schema_name() -> Cow<'static, str> {
+    "ReusableObject".into()
+  }
+
+  fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+      "description": "4.6.10 Reusable Object, references an object in the components",
+      "type": "object",
+      "properties": {
+        "reference": { "description": "Runtime Expression used to reference the desired object", "type": "string" },
+        "value": { "description": "Sets a value of the referenced parameter", "type": "string" }
+      },
+      "required": ["reference"],
+      "additionalProperties": false
+    })
+  }
+}
+
+impl JsonSchema for Criterion {
+  fn schema_name() -> Cow<'static, str> {
+    "Criterion".into()
+  }
+
+  fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+      "description": "4.6.11 Criterion Object",
+      "type": "object",
+      "properties": {
+        "context": {
+          "description": "Runtime Expression used to set the context for the condition to be applied on",
+          "type": "string"
+        },
+        "condition": { "description": "The condition to apply", "type": "string" },
+        "type": {
+          "description": "The type of condition to be applied",
+          "anyOf": [
+            { "enum": ["simple", "regex", "jsonpath", "xpath"] },
+            generator.subschema_for::<CriterionExpressionType>()
+          ]
+        }
+      },
+      "required": ["condition"],
+      "patternProperties": { EXTENSION_PATTERN: true },
+      "additionalProperties": false
+    })
+  }
+}
+
+impl JsonSchema for CriterionExpressionType {
+  fn schema_name() -> Cow<'static, str> {
+    "CriterionExpressionType".into()
+  }
+
+  fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+      "description": "4.6.12 Criterion Expression Type Object",
+      "type": "object",
+      "properties": {
+        "type": { "description": "The type of condition to be applied", "enum": ["jsonpath", "xpath"] },
+        "version": {
+          "description": "A shorthand string representing the version of the expression type being used",
+          "type": "string"
+        }
+      },
+      "required": ["type", "version"],
+      "patternProperties": { EXTENSION_PATTERN: true },
+      "additionalProperties": false
+    })
+  }
+}
+
+impl JsonSchema for RequestBody {
+  fn schema_name() -> Cow<'static, str> {
+    "RequestBody".into()
+  }
+
+  fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+      "description": "4.6.13 Request Body Object",
+      "type": "object",
+      "properties": {
+        "contentType": { "description": "Content-Type for the request content", "type": "string" },
+        "payload": { "description": "Value representing the request body payload" },
+        "replacements": {
+          "description": "List of locations and values to set within a payload",
+          "type": "array",
+          "items": generator.subschema_for::<PayloadReplacement>()
+        }
+      },
+      "patternProperties": { EXTENSION_PATTERN: true },
+      "additionalProperties": false
+    })
+  }
+}
+
+impl JsonSchema for PayloadReplacement {
+  fn schema_name() -> Cow<'static, str> {
+    "PayloadReplacement".into()
+  }
+
+  fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+      "description": "4.6.14 Payload Replacement Object",
+      "type": "object",
+      "properties": {
+        "target": {
+          "description": "A JSON Pointer or XPath Expression which must be resolved against the request body",
+          "type": "string"
+        },
+        "value": { "description": "The value set within the target location, or a runtime expression" }
+      },
+      "required": ["target", "value"],
+      "patternProperties": { EXTENSION_PATTERN: true },
+      "additionalProperties": false
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use pretty_assertions::assert_eq;
+  use schemars::schema_for;
+  use serde_json::{json, Value};
+
+  use crate::either::Either;
+  use crate::v1_0::*;
+
+  #[test]
+  fn arazzo_description_schema() {
+    let schema = serde_json::to_value(schema_for!(ArazzoDescription)).unwrap();
+
+    expect!(schema.pointer("/required")).to(be_some().value(&json!(["arazzo", "info", "sourceDescriptions", "workflows"])));
+    expect!(schema.pointer("/properties/info/$ref")).to(be_some().value(&json!("#/$defs/Info")));
+    expect!(schema.pointer("/properties/workflows/items/$ref")).to(be_some().value(&json!("#/$defs/Workflow")));
+    expect!(schema.pointer("/patternProperties/^x-")).to(be_some().value(&Value::Bool(true)));
+
+    let defs = schema["$defs"].as_object().unwrap();
+    let mut names = defs.keys().cloned().collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec![
+      "Components", "Criterion", "CriterionExpressionType", "FailureObject", "Info",
+      "ParameterObject", "PayloadReplacement", "RequestBody", "ReusableObject", "SourceDescription",
+      "Step", "SuccessObject", "Workflow"
+    ].iter().map(|name| name.to_string()).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn either_schema_is_inlined() {
+    let schema = serde_json::to_value(schema_for!(Either<SuccessObject, ReusableObject>)).unwrap();
+    expect!(schema.pointer("/anyOf")).to(be_some().value(&json!([
+      { "$ref": "#/$defs/SuccessObject" },
+      { "$ref": "#/$defs/ReusableObject" }
+    ])));
+  }
+
+  #[test]
+  fn step_schema_uses_the_document_keys() {
+    let schema = serde_json::to_value(schema_for!(Step)).unwrap();
+    let properties = schema["properties"].as_object().unwrap();
+    let mut keys = properties.keys().cloned().collect::<Vec<_>>();
+    keys.sort();
+    expect!(keys).to(be_equal_to(vec![
+      "description", "onFailure", "onSuccess", "operationId", "operationPath", "outputs",
+      "parameters", "requestBody", "stepId", "successCriteria", "workflowId"
+    ]));
+    expect!(schema.pointer("/properties/requestBody/$ref")).to(be_some().value(&json!("#/$defs/RequestBody")));
+  }
+}
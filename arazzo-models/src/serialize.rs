@@ -1,13 +1,36 @@
 //! Implementations to support serialization of the models using serde
 
-use std::any::Any;
 use std::fmt::Debug;
 use serde::ser::{SerializeMap, SerializeSeq};
 use serde::{Serialize, Serializer};
 
+use crate::base64;
 use crate::either::Either;
 use crate::extensions::AnyValue;
-use crate::payloads::{EmptyPayload, JsonPayload, Payload, StringPayload};
+use crate::payloads::{
+  BytesPayload,
+  EmptyPayload,
+  ExternalPayload,
+  FormPayload,
+  GraphQlPayload,
+  JsonPayload,
+  Payload,
+  ProtobufPayload,
+  StringPayload
+};
+#[cfg(feature = "xml")] use crate::payloads::XmlPayload;
+#[cfg(feature = "json")] use crate::execution_context::{body_value, CaptureStore, StepExchange, StepRequest, StepResponse};
+#[cfg(feature = "execute")] use crate::executor::{CriterionResult, ExecutionStatus};
+#[cfg(feature = "execute")] use crate::report::{ExecutionReport, ReportSummary, StepReport, WorkflowReport};
+#[cfg(feature = "yaml")] use crate::payloads::YamlPayload;
+#[cfg(feature = "yaml")] use yaml_rust2::Yaml;
+
+/// Returns the key to write an extension value with. Extension keys are always stored without
+/// the `x-` prefix (the loaders strip it off), so it needs to be added back. Keys are never checked
+/// for an existing prefix, so that a document key like `x-x-value` is written back unchanged.
+pub(crate) fn extension_key(key: &str) -> String {
+  format!("x-{}", key)
+}
 
 impl Serialize for AnyValue {
   fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -20,7 +43,12 @@ impl Serialize for AnyValue {
       AnyValue::Integer(i) => serializer.serialize_i64(*i),
       AnyValue::UInteger(u) => serializer.serialize_u64(*u),
       AnyValue::Float(f) => serializer.serialize_f64(*f),
+      AnyValue::BigNumber(n) => match n.parse::<serde_json::Number>() {
+        Ok(number) => number.serialize(serializer),
+        Err(_) => serializer.serialize_str(n.as_str())
+      },
       AnyValue::String(s) => serializer.serialize_str(s.as_str()),
+      AnyValue::Binary(b) => serializer.serialize_str(base64::encode(b).as_str()),
       AnyValue::Array(a) => {
         let mut seq = serializer.serialize_seq(Some(a.len()))?;
         for e in a {
@@ -30,9 +58,7 @@ impl Serialize for AnyValue {
       }
       AnyValue::Object(o) => {
         let mut map = serializer.serialize_map(Some(o.len()))?;
-        let mut entries = o.iter().collect::<Vec<_>>();
-        entries.sort_by(|(a, _), (b, _)| Ord::cmp(a, b));
-        for (k, v) in entries {
+        for (k, v) in o {
           map.serialize_entry(k, v)?;
         }
         map.end()
@@ -60,17 +86,89 @@ impl Serialize for dyn Payload + Send + Sync {
   where
     S: Serializer
   {
-    let payload: &dyn Any = self;
-    if let Some(string_payload) = payload.downcast_ref::<StringPayload>() {
+    if let Some(string_payload) = self.downcast_ref::<StringPayload>() {
       string_payload.serialize(serializer)
-    } else if let Some(json_payload) = payload.downcast_ref::<JsonPayload>() {
+    } else if let Some(json_payload) = self.downcast_ref::<JsonPayload>() {
       json_payload.serialize(serializer)
+    } else if let Some(bytes_payload) = self.downcast_ref::<BytesPayload>() {
+      bytes_payload.serialize(serializer)
+    } else if let Some(protobuf_payload) = self.downcast_ref::<ProtobufPayload>() {
+      serializer.serialize_str(protobuf_payload.as_string().as_str())
+    } else if let Some(form_payload) = self.downcast_ref::<FormPayload>() {
+      form_payload.serialize(serializer)
+    } else if let Some(graphql_payload) = self.downcast_ref::<GraphQlPayload>() {
+      graphql_payload.serialize(serializer)
+    } else if let Some(external_payload) = self.downcast_ref::<ExternalPayload>() {
+      external_payload.serialize(serializer)
     } else {
+      #[cfg(feature = "yaml")]
+      if let Some(yaml_payload) = self.downcast_ref::<YamlPayload>() {
+        return yaml_payload.serialize(serializer);
+      }
+      #[cfg(feature = "xml")]
+      if let Some(xml_payload) = self.downcast_ref::<XmlPayload>() {
+        return xml_payload.serialize(serializer);
+      }
       serializer.serialize_unit()
     }
   }
 }
 
+impl Serialize for BytesPayload {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    serializer.serialize_str(base64::encode(&self.0).as_str())
+  }
+}
+
+#[cfg(feature = "xml")]
+impl Serialize for XmlPayload {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    serializer.serialize_str(self.as_string().as_str())
+  }
+}
+
+impl Serialize for FormPayload {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    let mut map = serializer.serialize_map(Some(self.0.len()))?;
+    for (key, value) in &self.0 {
+      map.serialize_entry(key, value)?;
+    }
+    map.end()
+  }
+}
+
+impl Serialize for GraphQlPayload {
+  /// Writes the GraphQL request JSON body
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    self.to_json().serialize(serializer)
+  }
+}
+
+impl Serialize for ExternalPayload {
+  /// Writes a reference to the location of the payload (`{"$ref": "<file or URL>"}`). The contents
+  /// are not loaded.
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry("$ref", &self.location.to_string())?;
+    map.end()
+  }
+}
+
 impl Serialize for StringPayload {
   fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
   where
@@ -98,10 +196,266 @@ impl Serialize for JsonPayload {
   }
 }
 
+#[cfg(feature = "yaml")]
+impl Serialize for YamlPayload {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    YamlNode(&self.0).serialize(serializer)
+  }
+}
+
+/// Serializes a YAML node, keeping the order of the keys in any hashes
+#[cfg(feature = "yaml")]
+struct YamlNode<'a>(&'a Yaml);
+
+#[cfg(feature = "yaml")]
+impl Serialize for YamlNode<'_> {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    match self.0 {
+      Yaml::Real(n) => match n.parse::<f64>() {
+        Ok(number) => serializer.serialize_f64(number),
+        Err(_) => serializer.serialize_str(n)
+      },
+      Yaml::Integer(i) => serializer.serialize_i64(*i),
+      Yaml::String(s) => serializer.serialize_str(s),
+      Yaml::Boolean(b) => serializer.serialize_bool(*b),
+      Yaml::Array(array) => {
+        let mut seq = serializer.serialize_seq(Some(array.len()))?;
+        for item in array {
+          seq.serialize_element(&YamlNode(item))?;
+        }
+        seq.end()
+      }
+      Yaml::Hash(hash) => {
+        let mut map = serializer.serialize_map(Some(hash.len()))?;
+        for (key, value) in hash {
+          map.serialize_entry(&YamlNode(key), &YamlNode(value))?;
+        }
+        map.end()
+      }
+      Yaml::Alias(_) | Yaml::Null | Yaml::BadValue => serializer.serialize_unit()
+    }
+  }
+}
+
+/// Headers, written as an object (in order, and with any repeated headers)
+#[cfg(feature = "json")]
+struct Headers<'a>(&'a [(String, String)]);
+
+#[cfg(feature = "json")]
+impl Serialize for Headers<'_> {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    let mut map = serializer.serialize_map(Some(self.0.len()))?;
+    for (name, value) in self.0 {
+      map.serialize_entry(name, value)?;
+    }
+    map.end()
+  }
+}
+
+#[cfg(feature = "json")]
+impl Serialize for StepRequest {
+  /// Writes the request, with the body parsed if it is JSON (otherwise it is a string)
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    let mut map = serializer.serialize_map(None)?;
+    map.serialize_entry("method", &self.method)?;
+    map.serialize_entry("url", &self.url)?;
+    map.serialize_entry("headers", &Headers(&self.headers))?;
+    if let Some(body) = &self.body {
+      map.serialize_entry("body", &body_value(body))?;
+    }
+    map.end()
+  }
+}
+
+#[cfg(feature = "json")]
+impl Serialize for StepResponse {
+  /// Writes the response, with the body parsed if it is JSON (otherwise it is a string). Empty
+  /// bodies are left out.
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    let mut map = serializer.serialize_map(None)?;
+    map.serialize_entry("statusCode", &self.status)?;
+    map.serialize_entry("headers", &Headers(&self.headers))?;
+    if !self.body.is_empty() {
+      map.serialize_entry("body", &body_value(&self.body))?;
+    }
+    map.end()
+  }
+}
+
+#[cfg(feature = "json")]
+impl Serialize for StepExchange {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    let mut map = serializer.serialize_map(None)?;
+    if let Some(request) = &self.request {
+      map.serialize_entry("request", request)?;
+    }
+    if let Some(response) = &self.response {
+      map.serialize_entry("response", response)?;
+    }
+    if !self.path_parameters.is_empty() {
+      map.serialize_entry("pathParameters", &self.path_parameters)?;
+    }
+    map.end()
+  }
+}
+
+#[cfg(feature = "json")]
+impl Serialize for CaptureStore {
+  /// Writes the exchanges keyed by step ID
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    self.exchanges.serialize(serializer)
+  }
+}
+
+#[cfg(feature = "execute")]
+impl Serialize for ExecutionStatus {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    serializer.serialize_str(match self {
+      ExecutionStatus::Success => "success",
+      ExecutionStatus::Failure => "failure",
+      ExecutionStatus::Error => "error",
+      ExecutionStatus::Timeout => "timeout"
+    })
+  }
+}
+
+#[cfg(feature = "execute")]
+impl Serialize for CriterionResult {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    let mut map = serializer.serialize_map(None)?;
+    map.serialize_entry("condition", &self.condition)?;
+    map.serialize_entry("passed", &self.passed)?;
+    if let Some(error) = &self.error {
+      map.serialize_entry("error", error)?;
+    }
+    map.end()
+  }
+}
+
+#[cfg(feature = "execute")]
+impl Serialize for ReportSummary {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    let mut map = serializer.serialize_map(Some(5))?;
+    map.serialize_entry("workflows", &self.workflows)?;
+    map.serialize_entry("passed", &self.passed)?;
+    map.serialize_entry("failed", &self.failed)?;
+    map.serialize_entry("errors", &self.errors)?;
+    map.serialize_entry("steps", &self.steps)?;
+    map.end()
+  }
+}
+
+#[cfg(feature = "execute")]
+impl Serialize for StepReport {
+  /// Writes the step report, with the duration in milliseconds. Empty values are left out.
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    let mut map = serializer.serialize_map(None)?;
+    map.serialize_entry("stepId", &self.step_id)?;
+    map.serialize_entry("status", &self.status)?;
+    map.serialize_entry("attempt", &self.attempt)?;
+    map.serialize_entry("durationMs", &(self.duration.as_millis() as u64))?;
+    if let Some(method) = &self.method {
+      map.serialize_entry("method", method)?;
+    }
+    if let Some(url) = &self.url {
+      map.serialize_entry("url", url)?;
+    }
+    if let Some(status_code) = &self.status_code {
+      map.serialize_entry("statusCode", status_code)?;
+    }
+    if !self.criteria.is_empty() {
+      map.serialize_entry("criteria", &self.criteria)?;
+    }
+    if !self.outputs.is_empty() {
+      map.serialize_entry("outputs", &self.outputs)?;
+    }
+    if let Some(action) = &self.action {
+      map.serialize_entry("action", action)?;
+    }
+    if let Some(workflow) = &self.workflow {
+      map.serialize_entry("workflow", workflow.as_ref())?;
+    }
+    if let Some(error) = &self.error {
+      map.serialize_entry("error", error)?;
+    }
+    map.end()
+  }
+}
+
+#[cfg(feature = "execute")]
+impl Serialize for WorkflowReport {
+  /// Writes the workflow report, with the duration in milliseconds. Empty values are left out.
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    let mut map = serializer.serialize_map(None)?;
+    map.serialize_entry("workflowId", &self.workflow_id)?;
+    map.serialize_entry("status", &self.status)?;
+    map.serialize_entry("durationMs", &(self.duration.as_millis() as u64))?;
+    map.serialize_entry("steps", &self.steps)?;
+    if !self.outputs.is_empty() {
+      map.serialize_entry("outputs", &self.outputs)?;
+    }
+    if let Some(error) = &self.error {
+      map.serialize_entry("error", error)?;
+    }
+    map.end()
+  }
+}
+
+#[cfg(feature = "execute")]
+impl Serialize for ExecutionReport {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer
+  {
+    let mut map = serializer.serialize_map(Some(4))?;
+    map.serialize_entry("status", &self.status)?;
+    map.serialize_entry("durationMs", &(self.duration.as_millis() as u64))?;
+    map.serialize_entry("summary", &self.summary)?;
+    map.serialize_entry("workflows", &self.workflows)?;
+    map.end()
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use expectest::prelude::*;
-  use maplit::hashmap;
+  use indexmap::indexmap;
   use pretty_assertions::assert_eq;
   use serde_json::json;
   use trim_margin::MarginTrimmable;
@@ -140,10 +494,10 @@ mod tests {
     let json = serde_json::to_string(&value).unwrap();
     expect!(json).to(be_equal_to("[null,100,[-1,0,1]]"));
 
-    let value = AnyValue::Object(hashmap!{
+    let value = AnyValue::Object(indexmap!{
       "a".to_string() => AnyValue::Null,
       "b".to_string() => AnyValue::UInteger(100),
-      "c".to_string() => AnyValue::Object(hashmap!{
+      "c".to_string() => AnyValue::Object(indexmap!{
         "-1".to_string() => AnyValue::String("A".to_string()),
         "0".to_string() => AnyValue::String("B".to_string()),
         "1".to_string() => AnyValue::String("C".to_string())
@@ -199,10 +553,10 @@ mod tests {
          |  - 1
          |"#.trim_margin().as_ref().unwrap(), yaml.as_str());
 
-    let value = AnyValue::Object(hashmap!{
+    let value = AnyValue::Object(indexmap!{
       "a".to_string() => AnyValue::Null,
       "b".to_string() => AnyValue::UInteger(100),
-      "c".to_string() => AnyValue::Object(hashmap!{
+      "c".to_string() => AnyValue::Object(indexmap!{
         "-1".to_string() => AnyValue::String("A".to_string()),
         "0".to_string() => AnyValue::String("B".to_string()),
         "1".to_string() => AnyValue::String("C".to_string())
@@ -227,6 +581,8 @@ pub mod v1_0 {
   use serde::{Serialize, Serializer};
 
   use crate::either::Either;
+  use crate::payloads::ProtobufPayload;
+  use crate::serialize::extension_key;
   use crate::v1_0::*;
 
   impl Serialize for ArazzoDescription {
@@ -253,7 +609,7 @@ pub mod v1_0 {
       let mut extensions = self.extensions.iter().collect::<Vec<_>>();
       extensions.sort_by(|(a, _), (b, _)| Ord::cmp(a, b));
       for (k, v) in extensions {
-        map.serialize_entry(k, v)?;
+        map.serialize_entry(extension_key(k).as_str(), v)?;
       }
 
       map.end()
@@ -286,7 +642,7 @@ pub mod v1_0 {
       let mut extensions = self.extensions.iter().collect::<Vec<_>>();
       extensions.sort_by(|(a, _), (b, _)| Ord::cmp(a, b));
       for (k, v) in extensions {
-        map.serialize_entry(k, v)?;
+        map.serialize_entry(extension_key(k).as_str(), v)?;
       }
 
       map.end()
@@ -315,7 +671,7 @@ pub mod v1_0 {
       let mut extensions = self.extensions.iter().collect::<Vec<_>>();
       extensions.sort_by(|(a, _), (b, _)| Ord::cmp(a, b));
       for (k, v) in extensions {
-        map.serialize_entry(k, v)?;
+        map.serialize_entry(extension_key(k).as_str(), v)?;
       }
 
       map.end()
@@ -380,7 +736,7 @@ pub mod v1_0 {
       let mut extensions = self.extensions.iter().collect::<Vec<_>>();
       extensions.sort_by(|(a, _), (b, _)| Ord::cmp(a, b));
       for (k, v) in extensions {
-        map.serialize_entry(k, v)?;
+        map.serialize_entry(extension_key(k).as_str(), v)?;
       }
 
       map.end()
@@ -453,7 +809,7 @@ pub mod v1_0 {
       let mut extensions = self.extensions.iter().collect::<Vec<_>>();
       extensions.sort_by(|(a, _), (b, _)| Ord::cmp(a, b));
       for (k, v) in extensions {
-        map.serialize_entry(k, v)?;
+        map.serialize_entry(extension_key(k).as_str(), v)?;
       }
 
       map.end()
@@ -483,7 +839,7 @@ pub mod v1_0 {
       let mut extensions = self.extensions.iter().collect::<Vec<_>>();
       extensions.sort_by(|(a, _), (b, _)| Ord::cmp(a, b));
       for (k, v) in extensions {
-        map.serialize_entry(k, v)?;
+        map.serialize_entry(extension_key(k).as_str(), v)?;
       }
 
       map.end()
@@ -516,7 +872,7 @@ pub mod v1_0 {
       let mut extensions = self.extensions.iter().collect::<Vec<_>>();
       extensions.sort_by(|(a, _), (b, _)| Ord::cmp(a, b));
       for (k, v) in extensions {
-        map.serialize_entry(k, v)?;
+        map.serialize_entry(extension_key(k).as_str(), v)?;
       }
 
       map.end()
@@ -528,7 +884,15 @@ pub mod v1_0 {
     where
       S: Serializer
     {
-      let extensions_len = self.extensions.len();
+      // Keep the schema hint of protobuf payloads if it is not already in the extensions
+      let hint_extensions = self.payload.as_ref()
+        .and_then(|payload| payload.downcast_ref::<ProtobufPayload>())
+        .map(|payload| payload.hint.to_extensions())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(key, _)| !self.extensions.contains_key(key))
+        .collect::<Vec<_>>();
+      let extensions_len = self.extensions.len() + hint_extensions.len();
       let content_type_len = if self.content_type.is_some() { 1 } else { 0 };
       let payload_len = if self.payload.is_some() { 1 } else { 0 };
       let replacements_len = if self.replacements.is_empty() { 0 } else { 1 };
@@ -546,10 +910,12 @@ pub mod v1_0 {
         map.serialize_entry("replacements", &self.replacements)?;
       }
 
-      let mut extensions = self.extensions.iter().collect::<Vec<_>>();
+      let mut extensions = self.extensions.iter()
+        .chain(hint_extensions.iter().map(|(k, v)| (k, v)))
+        .collect::<Vec<_>>();
       extensions.sort_by(|(a, _), (b, _)| Ord::cmp(a, b));
       for (k, v) in extensions {
-        map.serialize_entry(k, v)?;
+        map.serialize_entry(extension_key(k).as_str(), v)?;
       }
 
       map.end()
@@ -574,7 +940,7 @@ pub mod v1_0 {
       let mut extensions = self.extensions.iter().collect::<Vec<_>>();
       extensions.sort_by(|(a, _), (b, _)| Ord::cmp(a, b));
       for (k, v) in extensions {
-        map.serialize_entry(k, v)?;
+        map.serialize_entry(extension_key(k).as_str(), v)?;
       }
 
       map.end()
@@ -596,7 +962,7 @@ pub mod v1_0 {
       let mut extensions = self.extensions.iter().collect::<Vec<_>>();
       extensions.sort_by(|(a, _), (b, _)| Ord::cmp(a, b));
       for (k, v) in extensions {
-        map.serialize_entry(k, v)?;
+        map.serialize_entry(extension_key(k).as_str(), v)?;
       }
 
       map.end()
@@ -656,7 +1022,7 @@ pub mod v1_0 {
       let mut extensions = self.extensions.iter().collect::<Vec<_>>();
       extensions.sort_by(|(a, _), (b, _)| Ord::cmp(a, b));
       for (k, v) in extensions {
-        map.serialize_entry(k, v)?;
+        map.serialize_entry(extension_key(k).as_str(), v)?;
       }
 
       map.end()
@@ -704,7 +1070,7 @@ pub mod v1_0 {
       let mut extensions = self.extensions.iter().collect::<Vec<_>>();
       extensions.sort_by(|(a, _), (b, _)| Ord::cmp(a, b));
       for (k, v) in extensions {
-        map.serialize_entry(k, v)?;
+        map.serialize_entry(extension_key(k).as_str(), v)?;
       }
 
       map.end()
@@ -738,13 +1104,13 @@ pub mod v1_0 {
       }
 
       if !self.success_actions.is_empty() {
-        map.serialize_entry("success_actions", &self.success_actions)?;
+        map.serialize_entry("successActions", &self.success_actions)?;
       }
 
       let mut extensions = self.extensions.iter().collect::<Vec<_>>();
       extensions.sort_by(|(a, _), (b, _)| Ord::cmp(a, b));
       for (k, v) in extensions {
-        map.serialize_entry(k, v)?;
+        map.serialize_entry(extension_key(k).as_str(), v)?;
       }
 
       map.end()
@@ -753,8 +1119,9 @@ pub mod v1_0 {
 
   #[cfg(test)]
   mod tests {
-    use std::rc::Rc;
+    use std::sync::Arc;
 
+    use bytes::Bytes;
     use expectest::prelude::*;
     use maplit::{btreemap, hashmap};
     use pretty_assertions::assert_eq;
@@ -763,9 +1130,91 @@ pub mod v1_0 {
 
     use crate::either::Either;
     use crate::extensions::AnyValue;
-    use crate::payloads::{JsonPayload, StringPayload};
+    use crate::payloads::{BytesPayload, ExternalPayload, JsonPayload, ProtobufPayload, SchemaHint, StringPayload};
+    #[cfg(feature = "yaml")] use crate::payloads::YamlPayload;
     use crate::v1_0::*;
 
+    #[test]
+    fn binary_request_body() {
+      let body = RequestBody {
+        content_type: Some("application/octet-stream".to_string()),
+        payload: Some(Arc::new(BytesPayload(Bytes::from_static(b"foobar")))),
+        replacements: vec![],
+        extensions: Default::default()
+      };
+      let json = serde_json::to_string(&body).unwrap();
+      expect!(json).to(be_equal_to(json!({
+        "contentType": "application/octet-stream",
+        "payload": "Zm9vYmFy"
+      }).to_string()));
+    }
+
+    #[test]
+    fn external_request_body() {
+      let body = RequestBody {
+        content_type: Some("application/json".to_string()),
+        payload: Some(Arc::new(ExternalPayload::file("/does/not/exist.json"))),
+        replacements: vec![],
+        extensions: Default::default()
+      };
+      let json = serde_json::to_string(&body).unwrap();
+      expect!(json).to(be_equal_to(json!({
+        "contentType": "application/json",
+        "payload": { "$ref": "/does/not/exist.json" }
+      }).to_string()));
+    }
+
+    #[test]
+    fn protobuf_request_body() {
+      let body = RequestBody {
+        content_type: Some("application/x-protobuf".to_string()),
+        payload: Some(Arc::new(ProtobufPayload {
+          bytes: Bytes::from_static(b"\n\x03Pet"),
+          hint: SchemaHint { message_type: Some("pets.v1.Pet".to_string()), descriptor: Some("pets.proto".to_string()) }
+        })),
+        replacements: vec![],
+        extensions: hashmap!{ "protobuf-descriptor".to_string() => AnyValue::String("pets.desc".to_string()) }
+      };
+      let json = serde_json::to_value(&body).unwrap();
+      expect!(json.clone()).to(be_equal_to(json!({
+        "contentType": "application/x-protobuf",
+        "payload": "CgNQZXQ=",
+        "x-protobuf-descriptor": "pets.desc",
+        "x-protobuf-message-type": "pets.v1.Pet"
+      })));
+
+      #[cfg(feature = "json")]
+      {
+        let loaded = RequestBody::try_from(&json).unwrap();
+        let payload = loaded.payload_as::<ProtobufPayload>().unwrap();
+        expect!(payload.bytes.clone()).to(be_equal_to(Bytes::from_static(b"\n\x03Pet")));
+        expect!(payload.hint.message_type.clone()).to(be_some().value("pets.v1.Pet"));
+        expect!(payload.hint.descriptor.clone()).to(be_some().value("pets.desc"));
+      }
+    }
+
+    #[test]
+    #[cfg(feature = "yaml")]
+    fn yaml_request_body() {
+      let yaml = yaml_rust2::YamlLoader::load_from_str("petOrder:\n  status: placed\n  quantity: 1\n  price: 10.50\n  id: ~").unwrap();
+      let body = RequestBody {
+        content_type: Some("application/json".to_string()),
+        payload: Some(Arc::new(YamlPayload(yaml[0].clone()))),
+        replacements: vec![],
+        extensions: Default::default()
+      };
+      let yaml = serde_yaml::to_string(&body).unwrap();
+      assert_eq!(
+        r#"|contentType: application/json
+           |payload:
+           |  petOrder:
+           |    status: placed
+           |    quantity: 1
+           |    price: 10.5
+           |    id: null
+           |"#.trim_margin().as_ref().unwrap(), yaml.as_str());
+    }
+
     #[test]
     fn request_body() {
       let body = RequestBody {
@@ -783,7 +1232,7 @@ pub mod v1_0 {
 
       let body = RequestBody {
         content_type: Some("application/json".to_string()),
-        payload: Some(Rc::new(StringPayload(r#"
+        payload: Some(Arc::new(StringPayload(r#"
         {
           "petOrder": {
             "petId": "{$inputs.pet_id}",
@@ -796,8 +1245,8 @@ pub mod v1_0 {
         "#.to_string()))),
         replacements: vec![],
         extensions: hashmap!{
-          "x-one".to_string() => AnyValue::String("one".to_string()),
-          "x-two".to_string() => AnyValue::Integer(2),
+          "one".to_string() => AnyValue::String("one".to_string()),
+          "two".to_string() => AnyValue::Integer(2),
         }
       };
       let json = serde_json::to_string(&body).unwrap();
@@ -817,7 +1266,7 @@ pub mod v1_0 {
 
       let body = RequestBody {
         content_type: Some("application/json".to_string()),
-        payload: Some(Rc::new(JsonPayload(json!({
+        payload: Some(Arc::new(JsonPayload(json!({
           "petOrder": {
             "petId": "{$inputs.pet_id}",
             "couponCode": "{$inputs.coupon_code}",
@@ -893,8 +1342,8 @@ pub mod v1_0 {
         target: "/petId".to_string(),
         value: Either::Second("$inputs.pet_id".to_string()),
         extensions: hashmap!{
-          "x-one".to_string() => AnyValue::String("one".to_string()),
-          "x-two".to_string() => AnyValue::Integer(2),
+          "one".to_string() => AnyValue::String("one".to_string()),
+          "two".to_string() => AnyValue::Integer(2),
         }
       };
       let json = serde_json::to_string(&payload_replacement).unwrap();
@@ -935,8 +1384,8 @@ pub mod v1_0 {
         condition: "^200$".to_string(),
         r#type: Some(Either::First("regex".to_string())),
         extensions: hashmap!{
-          "x-one".to_string() => AnyValue::String("one".to_string()),
-          "x-two".to_string() => AnyValue::Integer(2),
+          "one".to_string() => AnyValue::String("one".to_string()),
+          "two".to_string() => AnyValue::Integer(2),
         }
       };
       let json = serde_json::to_string(&criterion).unwrap();
@@ -1011,8 +1460,8 @@ pub mod v1_0 {
         r#in: None,
         value: Either::Second("$inputs.username".to_string()),
         extensions: hashmap!{
-          "x-one".to_string() => AnyValue::String("one".to_string()),
-          "x-two".to_string() => AnyValue::Integer(2),
+          "one".to_string() => AnyValue::String("one".to_string()),
+          "two".to_string() => AnyValue::Integer(2),
         }
       };
       let json = serde_json::to_string(&parameter).unwrap();
@@ -1145,8 +1594,8 @@ pub mod v1_0 {
         on_failure: vec![],
         outputs: Default::default(),
         extensions: hashmap!{
-          "x-one".to_string() => AnyValue::String("one".to_string()),
-          "x-two".to_string() => AnyValue::Integer(2),
+          "one".to_string() => AnyValue::String("one".to_string()),
+          "two".to_string() => AnyValue::Integer(2),
         }
       };
       let json = serde_json::to_string(&step).unwrap();
@@ -1214,8 +1663,8 @@ pub mod v1_0 {
           "tokenExpires".to_string() => "$steps.loginStep.outputs.tokenExpires".to_string()
         },
         extensions: hashmap!{
-          "x-one".to_string() => AnyValue::String("one".to_string()),
-          "x-two".to_string() => AnyValue::Integer(2),
+          "one".to_string() => AnyValue::String("one".to_string()),
+          "two".to_string() => AnyValue::Integer(2),
         },
         .. Workflow::default()
       };
@@ -0,0 +1,137 @@
+//! Shared Arazzo descriptions, which can be cloned in constant time. Cloning an
+//! [`ArazzoDescription`] copies every workflow, step and parameter in it, which adds up when a
+//! description is passed to a number of concurrent analyses (i.e. validating, linting and
+//! rendering it on different threads). A [`SharedDescription`] holds the description behind an
+//! `Arc`, so clones share the same description.
+//!
+//! The description can still be changed with [`SharedDescription::make_mut`], which copies it first
+//! if any other clones are sharing it (copy-on-write), so the changes are never seen by the other
+//! clones. Request body payloads are already shared between copies, so the payload data is never
+//! copied.
+//!
+//! ```
+//! use arazzo_models::shared::SharedDescription;
+//! use arazzo_models::v1_0::ArazzoDescription;
+//!
+//! let shared = SharedDescription::new(ArazzoDescription::default());
+//! let mut copy = shared.clone();
+//! assert!(copy.ptr_eq(&shared));
+//!
+//! copy.make_mut().info.title = "Pets".to_string();
+//! assert!(!copy.ptr_eq(&shared));
+//! assert_eq!(shared.info.title, "");
+//! ```
+
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::v1_0::ArazzoDescription;
+
+/// Arazzo description that is shared between clones. This dereferences to the description, so it
+/// can be used anywhere a `&ArazzoDescription` is expected.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SharedDescription(Arc<ArazzoDescription>);
+
+impl SharedDescription {
+  /// Creates a shared description
+  pub fn new(description: ArazzoDescription) -> Self {
+    SharedDescription(Arc::new(description))
+  }
+
+  /// Returns a mutable reference to the description. If any other clones are sharing the
+  /// description, it is copied first, so this clone no longer shares it with them.
+  pub fn make_mut(&mut self) -> &mut ArazzoDescription {
+    Arc::make_mut(&mut self.0)
+  }
+
+  /// If the two clones are sharing the same description
+  pub fn ptr_eq(&self, other: &SharedDescription) -> bool {
+    Arc::ptr_eq(&self.0, &other.0)
+  }
+
+  /// Returns the description. It is only copied if any other clones are sharing it.
+  pub fn into_description(self) -> ArazzoDescription {
+    Arc::unwrap_or_clone(self.0)
+  }
+}
+
+impl Deref for SharedDescription {
+  type Target = ArazzoDescription;
+
+  fn deref(&self) -> &Self::Target {
+    &self.0
+  }
+}
+
+impl AsRef<ArazzoDescription> for SharedDescription {
+  fn as_ref(&self) -> &ArazzoDescription {
+    &self.0
+  }
+}
+
+impl From<ArazzoDescription> for SharedDescription {
+  fn from(description: ArazzoDescription) -> Self {
+    SharedDescription::new(description)
+  }
+}
+
+impl ArazzoDescription {
+  /// Converts this description into a [`SharedDescription`], which can be cloned in constant time
+  pub fn into_shared(self) -> SharedDescription {
+    SharedDescription::new(self)
+  }
+}
+
+#[cfg(feature = "serialize")]
+impl serde::Serialize for SharedDescription {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: serde::Serializer {
+    self.0.serialize(serializer)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::thread;
+
+  use expectest::prelude::*;
+
+  use crate::arazzo;
+  use crate::v1_0::ArazzoDescription;
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      info("Pets", "1.0.0");
+    }
+  }
+
+  #[test]
+  fn clones_share_the_description() {
+    let shared = description().into_shared();
+    let clone = shared.clone();
+    expect!(clone.ptr_eq(&shared)).to(be_true());
+    expect!(clone.info.title.as_str()).to(be_equal_to("Pets"));
+
+    let titles = (0..4).map(|_| {
+      let shared = shared.clone();
+      thread::spawn(move || shared.info.title.clone())
+    }).map(|handle| handle.join().unwrap()).collect::<Vec<_>>();
+    expect!(titles).to(be_equal_to(vec!["Pets".to_string(); 4]));
+    expect!(shared.into_description()).to(be_equal_to(description()));
+  }
+
+  #[test]
+  fn copies_the_description_when_it_is_changed() {
+    let shared = description().into_shared();
+    let mut clone = shared.clone();
+    clone.make_mut().info.title = "Orders".to_string();
+
+    expect!(clone.ptr_eq(&shared)).to(be_false());
+    expect!(shared.info.title.as_str()).to(be_equal_to("Pets"));
+    expect!(clone.info.title.as_str()).to(be_equal_to("Orders"));
+
+    let mut unshared = description().into_shared();
+    let before = &*unshared as *const ArazzoDescription;
+    unshared.make_mut().info.title = "Orders".to_string();
+    expect!(&*unshared as *const ArazzoDescription).to(be_equal_to(before));
+  }
+}
@@ -0,0 +1,65 @@
+//! Loading of Arazzo descriptions with [simd-json](https://docs.rs/simd-json). JSON documents are
+//! parsed from bytes with simd-json, which builds the `serde_json` document directly from the
+//! simd-json tape, so the document is only parsed once.
+//!
+//! Values that have already been parsed into simd-json values (`OwnedValue` or `BorrowedValue`)
+//! are not supported, as the loaders work with `serde_json` documents and converting the values
+//! would build the document a second time. Parse the bytes with [`simd_json_parse`] instead.
+//!
+//! Note that simd-json parses the bytes in place, so the buffer will be modified.
+
+use serde_json::Value;
+
+use crate::v1_0::ArazzoDescription;
+
+/// Parses the JSON document in the buffer with simd-json. The resulting document can be used to
+/// load either the owned or borrowed models.
+pub fn simd_json_parse(bytes: &mut [u8]) -> anyhow::Result<Value> {
+  Ok(simd_json::serde::from_slice(bytes)?)
+}
+
+/// Loads an Arazzo description from the JSON document in the buffer, parsing it with simd-json
+pub fn simd_json_load(bytes: &mut [u8]) -> anyhow::Result<ArazzoDescription> {
+  ArazzoDescription::try_from(&simd_json_parse(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use crate::simd::{simd_json_load, simd_json_parse};
+  use crate::v1_0::ArazzoDescription;
+
+  const DOCUMENT: &str = r#"{
+    "arazzo": "1.0.1",
+    "info": { "title": "Pets", "version": "1.0.0", "x-owner": { "team": "pets", "size": 3 } },
+    "sourceDescriptions": [ { "name": "petstore", "url": "petstore.json" } ],
+    "workflows": [
+      {
+        "workflowId": "listPets",
+        "steps": [
+          {
+            "stepId": "list",
+            "operationId": "listPets",
+            "parameters": [ { "name": "limit", "in": "query", "value": 10 } ],
+            "successCriteria": [ { "condition": "$statusCode == 200" } ]
+          }
+        ]
+      }
+    ]
+  }"#;
+
+  fn expected() -> ArazzoDescription {
+    let json: serde_json::Value = serde_json::from_str(DOCUMENT).unwrap();
+    ArazzoDescription::try_from(&json).unwrap()
+  }
+
+  #[test]
+  fn loads_from_bytes() {
+    let mut bytes = DOCUMENT.as_bytes().to_vec();
+    expect!(simd_json_load(&mut bytes).unwrap()).to(be_equal_to(expected()));
+
+    let mut bytes = b"{ \"arazzo\": ".to_vec();
+    expect!(simd_json_parse(&mut bytes)).to(be_err());
+  }
+}
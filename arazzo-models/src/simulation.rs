@@ -0,0 +1,216 @@
+//! Simulation of workflows with stubbed step outputs, for checking the control flow of a document
+//! while it is being written. No requests are sent: each step gets the response and outputs from
+//! its stub, and the [`Executor`](crate::executor::Executor) evaluates the success criteria and
+//! follows the success and failure actions as it would for a real run (see
+//! [`Executor::simulate`](crate::executor::Executor::simulate)). The report shows the path taken
+//! through the workflow, the actions that were followed, and the steps that were never reached.
+//!
+//! Stubs are registered for step IDs. If more than one stub is registered for a step, they are
+//! used in order (i.e. to fail and then succeed a retried step), with the last one repeated. Steps
+//! without a stub get an empty `200` response. Stubbed outputs replace the output expressions of
+//! the step, and outputs that are not stubbed are evaluated against the stubbed response.
+//!
+//! The operations of the steps are still resolved from the OpenAPI descriptions, so the requests
+//! that would have been sent are in the report.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use crate::execution_context::StepResponse;
+use crate::executor::{ExecutionStatus, StepResult, WorkflowResult};
+use crate::extensions::AnyValue;
+use crate::v1_0::Workflow;
+
+/// Assumed outcome of a step
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepStub {
+  /// Response used in place of sending the request (for operation steps)
+  pub response: StepResponse,
+  /// Outputs of the step, keyed by output name
+  pub outputs: BTreeMap<String, AnyValue>
+}
+
+impl Default for StepStub {
+  fn default() -> Self {
+    StepStub { response: StepResponse::new(200), outputs: BTreeMap::new() }
+  }
+}
+
+impl StepStub {
+  /// Creates a stub with an empty `200` response and no outputs
+  pub fn new() -> Self {
+    StepStub::default()
+  }
+
+  /// Sets the status code of the response
+  pub fn with_status(mut self, status: u16) -> Self {
+    self.response.status = status;
+    self
+  }
+
+  /// Sets the response
+  pub fn with_response(mut self, response: StepResponse) -> Self {
+    self.response = response;
+    self
+  }
+
+  /// Sets the value of an output
+  pub fn with_output<S: Into<String>>(mut self, name: S, value: AnyValue) -> Self {
+    self.outputs.insert(name.into(), value);
+    self
+  }
+}
+
+/// Stubs for the steps of the workflows, keyed by step ID
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimulationStubs {
+  /// Stubs for each step, used in order
+  pub stubs: HashMap<String, Vec<StepStub>>
+}
+
+impl SimulationStubs {
+  /// Creates an empty set of stubs
+  pub fn new() -> Self {
+    SimulationStubs::default()
+  }
+
+  /// Registers a stub for the step
+  pub fn stub<S: Into<String>>(mut self, step_id: S, stub: StepStub) -> Self {
+    self.stubs.entry(step_id.into()).or_default().push(stub);
+    self
+  }
+}
+
+/// State of a simulation, handing out the stubs as the steps are executed
+#[derive(Debug, Default)]
+pub(crate) struct Simulation {
+  stubs: SimulationStubs,
+  executions: Mutex<HashMap<String, usize>>
+}
+
+impl Simulation {
+  pub(crate) fn new(stubs: SimulationStubs) -> Self {
+    Simulation { stubs, executions: Mutex::default() }
+  }
+
+  /// Returns the stub for the next execution of the step, or `None` if there is no stub
+  pub(crate) fn next_stub(&self, step_id: &str) -> Option<&StepStub> {
+    let mut executions = self.executions.lock().unwrap_or_else(|err| err.into_inner());
+    let count = executions.entry(step_id.to_string()).or_default();
+    *count += 1;
+    let stubs = self.stubs.stubs.get(step_id)?;
+    stubs.get(*count - 1).or(stubs.last())
+  }
+}
+
+/// Step on the path taken through a workflow
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedStep {
+  /// Step ID
+  pub step_id: String,
+  /// Attempt number (starting at 1)
+  pub attempt: usize,
+  /// Outcome of the step
+  pub status: ExecutionStatus,
+  /// Name of the success or failure action that was followed
+  pub action: Option<String>,
+  /// If a stub was registered for the step
+  pub stubbed: bool,
+  /// Outputs of the step
+  pub outputs: BTreeMap<String, AnyValue>,
+  /// Path taken through the workflow that was executed (for workflow steps and `goto` actions)
+  pub workflow: Option<Box<SimulationReport>>,
+  /// Error if the step could not be executed
+  pub error: Option<String>
+}
+
+/// Path taken through a workflow in a simulation
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationReport {
+  /// Workflow ID
+  pub workflow_id: String,
+  /// Outcome of the workflow
+  pub status: ExecutionStatus,
+  /// Steps in the order they were executed
+  pub path: Vec<SimulatedStep>,
+  /// Steps of the workflow that were never executed
+  pub unreached_steps: Vec<String>,
+  /// Outputs of the workflow
+  pub outputs: BTreeMap<String, AnyValue>,
+  /// Error if the workflow did not complete
+  pub error: Option<String>,
+  /// Full result of the simulated run
+  pub result: WorkflowResult
+}
+
+impl SimulationReport {
+  pub(crate) fn new(
+    result: WorkflowResult,
+    workflows: &[Workflow],
+    stubs: &SimulationStubs
+  ) -> Self {
+    let path = result.steps.iter()
+      .map(|step| SimulatedStep::new(step, workflows, stubs))
+      .collect::<Vec<_>>();
+    let unreached_steps = workflows.iter()
+      .find(|workflow| workflow.workflow_id == result.workflow_id)
+      .map(|workflow| workflow.steps.iter()
+        .filter(|step| !path.iter().any(|simulated| simulated.step_id == step.step_id))
+        .map(|step| step.step_id.clone())
+        .collect())
+      .unwrap_or_default();
+    SimulationReport {
+      workflow_id: result.workflow_id.clone(),
+      status: result.status,
+      path,
+      unreached_steps,
+      outputs: result.outputs.clone(),
+      error: result.error.clone(),
+      result
+    }
+  }
+
+  /// Returns the IDs of the steps on the path, in the order they were executed
+  pub fn step_ids(&self) -> Vec<&str> {
+    self.path.iter().map(|step| step.step_id.as_str()).collect()
+  }
+
+  /// Returns the names of the actions that were followed, in order
+  pub fn actions(&self) -> Vec<&str> {
+    self.path.iter().filter_map(|step| step.action.as_deref()).collect()
+  }
+}
+
+impl SimulatedStep {
+  fn new(result: &StepResult, workflows: &[Workflow], stubs: &SimulationStubs) -> Self {
+    SimulatedStep {
+      step_id: result.step_id.clone(),
+      attempt: result.attempt,
+      status: result.status,
+      action: result.action.clone(),
+      stubbed: stubs.stubs.contains_key(&result.step_id),
+      outputs: result.outputs.clone(),
+      workflow: result.workflow.as_ref()
+        .map(|workflow| Box::new(SimulationReport::new(workflow.as_ref().clone(), workflows, stubs))),
+      error: result.error.clone()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use crate::simulation::{Simulation, SimulationStubs, StepStub};
+
+  #[test]
+  fn hands_out_the_stubs_in_order() {
+    let simulation = Simulation::new(SimulationStubs::new()
+      .stub("login", StepStub::new().with_status(503))
+      .stub("login", StepStub::new()));
+    expect!(simulation.next_stub("login").map(|stub| stub.response.status)).to(be_some().value(503));
+    expect!(simulation.next_stub("login").map(|stub| stub.response.status)).to(be_some().value(200));
+    expect!(simulation.next_stub("login").map(|stub| stub.response.status)).to(be_some().value(200));
+    expect!(simulation.next_stub("other")).to(be_none());
+  }
+}
@@ -0,0 +1,166 @@
+//! Support for splitting an Arazzo description into smaller descriptions
+
+use std::collections::BTreeSet;
+
+use anyhow::anyhow;
+
+use crate::expressions::ReferenceKind;
+use crate::usages::{ObjectRef, UsageIndex};
+use crate::v1_0::{ArazzoDescription, Components};
+
+impl ArazzoDescription {
+  /// Splits this description into a description per workflow. Each description will contain the
+  /// workflow, along with any workflows, source descriptions and components it uses (directly or
+  /// indirectly).
+  pub fn split(&self) -> Vec<ArazzoDescription> {
+    let index = UsageIndex::build(self);
+    self.workflows.iter()
+      .map(|workflow| self.extract_with_index(&[workflow.workflow_id.as_str()], &index))
+      .collect()
+  }
+
+  /// Extracts the given workflows into a new description, along with any workflows, source
+  /// descriptions and components they use (directly or indirectly). Returns an error if any of the
+  /// workflows do not exist in this description.
+  ///
+  /// As operation IDs that are not qualified with a source description can refer to an operation
+  /// from any OpenAPI source description, all the OpenAPI source descriptions will be included if
+  /// any of the extracted steps use one.
+  pub fn extract_workflows(&self, workflow_ids: &[&str]) -> anyhow::Result<ArazzoDescription> {
+    let missing = workflow_ids.iter()
+      .filter(|id| !self.workflows.iter().any(|wf| wf.workflow_id == **id))
+      .map(|id| id.to_string())
+      .collect::<Vec<_>>();
+    if !missing.is_empty() {
+      return Err(anyhow!("Workflows not found in the description: {}", missing.join(", ")));
+    }
+
+    Ok(self.extract_with_index(workflow_ids, &UsageIndex::build(self)))
+  }
+
+  fn extract_with_index(&self, workflow_ids: &[&str], index: &UsageIndex) -> ArazzoDescription {
+    let mut used = BTreeSet::new();
+    for workflow_id in workflow_ids {
+      let workflow = ObjectRef::new(ReferenceKind::Workflow, *workflow_id);
+      used.extend(index.transitive_references_from(&workflow));
+      used.insert(workflow);
+    }
+    let is_used = |kind: ReferenceKind, name: &str| used.contains(&ObjectRef::new(kind, name));
+
+    let workflows = self.workflows.iter()
+      .filter(|wf| is_used(ReferenceKind::Workflow, &wf.workflow_id))
+      .cloned()
+      .collect::<Vec<_>>();
+
+    let uses_plain_operation_ids = workflows.iter()
+      .flat_map(|wf| wf.steps.iter())
+      .any(|step| step.operation_id.as_ref().map(|id| !id.starts_with('$')).unwrap_or_default());
+    let source_descriptions = self.source_descriptions.iter()
+      .filter(|sd| {
+        is_used(ReferenceKind::SourceDescription, &sd.name) ||
+          (uses_plain_operation_ids && sd.r#type.as_deref().unwrap_or("openapi") == "openapi")
+      })
+      .cloned()
+      .collect();
+
+    let components = Components {
+      inputs: self.components.inputs.iter()
+        .filter(|(name, _)| is_used(ReferenceKind::ComponentInput, name))
+        .map(|(name, input)| (name.clone(), input.clone()))
+        .collect(),
+      parameters: self.components.parameters.iter()
+        .filter(|(name, _)| is_used(ReferenceKind::ComponentParameter, name))
+        .map(|(name, parameter)| (name.clone(), parameter.clone()))
+        .collect(),
+      success_actions: self.components.success_actions.iter()
+        .filter(|(name, _)| is_used(ReferenceKind::ComponentSuccessAction, name))
+        .map(|(name, action)| (name.clone(), action.clone()))
+        .collect(),
+      failure_actions: self.components.failure_actions.iter()
+        .filter(|(name, _)| is_used(ReferenceKind::ComponentFailureAction, name))
+        .map(|(name, action)| (name.clone(), action.clone()))
+        .collect(),
+      extensions: self.components.extensions.clone()
+    };
+
+    ArazzoDescription {
+      arazzo: self.arazzo.clone(),
+      info: self.info.clone(),
+      source_descriptions,
+      workflows,
+      components,
+      extensions: self.extensions.clone()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use crate::arazzo;
+  use crate::v1_0::*;
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      info("Pet store", "1.0.0");
+      source("pets", "http://pets");
+      source("auth", "http://auth");
+      source("orders", "http://orders");
+      workflow("login") {
+        step("login") { operation_id: "$sourceDescriptions.auth.login"; }
+      }
+      workflow("findPets") {
+        depends_on: "login";
+        step("find") {
+          operation_id: "$sourceDescriptions.pets.findPets";
+          reusable_parameter: "page";
+        }
+      }
+      workflow("placeOrder") {
+        step("order") { operation_id: "$sourceDescriptions.orders.placeOrder"; }
+      }
+      component_parameter("page", ParameterObject { name: "page".to_string(), .. ParameterObject::default() });
+      component_parameter("unused", ParameterObject { name: "unused".to_string(), .. ParameterObject::default() });
+    }
+  }
+
+  #[test]
+  fn split_creates_a_description_per_workflow() {
+    let descriptions = description().split();
+    expect!(descriptions.len()).to(be_equal_to(3));
+
+    let login = &descriptions[0];
+    expect!(login.workflows.iter().map(|wf| wf.workflow_id.as_str()).collect::<Vec<_>>())
+      .to(be_equal_to(vec!["login"]));
+    expect!(login.source_descriptions.iter().map(|sd| sd.name.as_str()).collect::<Vec<_>>())
+      .to(be_equal_to(vec!["auth"]));
+    expect!(login.components.parameters.is_empty()).to(be_true());
+
+    let find_pets = &descriptions[1];
+    expect!(find_pets.workflows.iter().map(|wf| wf.workflow_id.as_str()).collect::<Vec<_>>())
+      .to(be_equal_to(vec!["login", "findPets"]));
+    expect!(find_pets.source_descriptions.iter().map(|sd| sd.name.as_str()).collect::<Vec<_>>())
+      .to(be_equal_to(vec!["pets", "auth"]));
+    expect!(find_pets.components.parameters.keys().collect::<Vec<_>>()).to(be_equal_to(vec!["page"]));
+    expect!(find_pets.info.title.as_str()).to(be_equal_to("Pet store"));
+
+    let place_order = &descriptions[2];
+    expect!(place_order.source_descriptions.iter().map(|sd| sd.name.as_str()).collect::<Vec<_>>())
+      .to(be_equal_to(vec!["orders"]));
+  }
+
+  #[test]
+  fn extract_workflows_includes_all_openapi_sources_for_plain_operation_ids() {
+    let mut description = description();
+    description.workflows[2].steps[0].operation_id = Some("placeOrder".to_string());
+    description.source_descriptions[0].r#type = Some("arazzo".to_string());
+
+    let extracted = description.extract_workflows(&["placeOrder"]).unwrap();
+    expect!(extracted.source_descriptions.iter().map(|sd| sd.name.as_str()).collect::<Vec<_>>())
+      .to(be_equal_to(vec!["auth", "orders"]));
+
+    expect!(description.extract_workflows(&["placeOrder", "other"]).unwrap_err().to_string())
+      .to(be_equal_to("Workflows not found in the description: other"));
+  }
+}
@@ -0,0 +1,239 @@
+//! Flattens the steps of all the workflows of an Arazzo description into rows, which can be
+//! exported as CSV to audit the operations, content types and criteria of the steps in a
+//! spreadsheet.
+//!
+//! The HTTP method and path of a step are taken from its `operationPath` (i.e.
+//! `{$sourceDescriptions.petstore.url}#/paths/~1pets/get`). With the `execute` feature, the
+//! operations can also be resolved from the OpenAPI source descriptions (see
+//! [`step_summaries_with_operations`]), which resolves the `operationId` of steps.
+
+use std::fmt::Write;
+
+#[cfg(feature = "execute")] use crate::operations::OperationResolver;
+use crate::v1_0::{ArazzoDescription, Step, Workflow};
+
+const HTTP_METHODS: [&str; 8] = ["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// Columns of the CSV export
+pub const CSV_COLUMNS: [&str; 12] = [
+  "workflowId",
+  "stepId",
+  "operationId",
+  "operationPath",
+  "targetWorkflowId",
+  "method",
+  "path",
+  "contentType",
+  "parameters",
+  "successCriteria",
+  "onSuccess",
+  "onFailure"
+];
+
+/// Summary of a step
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StepSummary {
+  /// ID of the workflow the step is in
+  pub workflow_id: String,
+  /// Step ID
+  pub step_id: String,
+  /// Operation ID of the step
+  pub operation_id: Option<String>,
+  /// Operation path of the step
+  pub operation_path: Option<String>,
+  /// Workflow the step executes (for workflow steps)
+  pub target_workflow_id: Option<String>,
+  /// HTTP method (uppercase), if it could be resolved
+  pub method: Option<String>,
+  /// Path template of the operation, if it could be resolved
+  pub path: Option<String>,
+  /// Content type of the request body
+  pub content_type: Option<String>,
+  /// Number of parameters
+  pub parameters: usize,
+  /// Number of success criteria
+  pub success_criteria: usize,
+  /// Number of success actions of the step
+  pub on_success: usize,
+  /// Number of failure actions of the step
+  pub on_failure: usize
+}
+
+impl StepSummary {
+  fn new(workflow: &Workflow, step: &Step) -> Self {
+    let (path, method) = step.operation_path.as_deref()
+      .and_then(operation_path_method)
+      .map(|(path, method)| (Some(path), Some(method)))
+      .unwrap_or_default();
+    StepSummary {
+      workflow_id: workflow.workflow_id.clone(),
+      step_id: step.step_id.clone(),
+      operation_id: step.operation_id.clone(),
+      operation_path: step.operation_path.clone(),
+      target_workflow_id: step.workflow_id.clone(),
+      method,
+      path,
+      content_type: step.request_body.as_ref().and_then(|body| body.effective_content_type()),
+      parameters: step.parameters.len(),
+      success_criteria: step.success_criteria.len(),
+      on_success: step.on_success.len(),
+      on_failure: step.on_failure.len()
+    }
+  }
+
+  /// Values of the row, in the order of [`CSV_COLUMNS`]
+  pub fn values(&self) -> Vec<String> {
+    vec![
+      self.workflow_id.clone(),
+      self.step_id.clone(),
+      self.operation_id.clone().unwrap_or_default(),
+      self.operation_path.clone().unwrap_or_default(),
+      self.target_workflow_id.clone().unwrap_or_default(),
+      self.method.clone().unwrap_or_default(),
+      self.path.clone().unwrap_or_default(),
+      self.content_type.clone().unwrap_or_default(),
+      self.parameters.to_string(),
+      self.success_criteria.to_string(),
+      self.on_success.to_string(),
+      self.on_failure.to_string()
+    ]
+  }
+}
+
+/// Returns a summary of every step of the workflows, in order. The method and path are resolved
+/// from the operation paths of the steps.
+pub fn step_summaries(description: &ArazzoDescription) -> Vec<StepSummary> {
+  description.workflows.iter()
+    .flat_map(|workflow| workflow.steps.iter().map(move |step| StepSummary::new(workflow, step)))
+    .collect()
+}
+
+/// Returns a summary of every step of the workflows, in order, with the method and path of the
+/// operations resolved from the OpenAPI source descriptions. Steps whose operation can not be
+/// resolved have the method and path from the operation path, if there is one.
+#[cfg(feature = "execute")]
+pub fn step_summaries_with_operations(description: &ArazzoDescription, resolver: &OperationResolver) -> Vec<StepSummary> {
+  description.workflows.iter()
+    .flat_map(|workflow| workflow.steps.iter().map(move |step| {
+      let mut summary = StepSummary::new(workflow, step);
+      if step.workflow_id.is_none() && let Ok(operation) = resolver.resolve_step(step) {
+        summary.method = Some(operation.method);
+        summary.path = Some(operation.path);
+      }
+      summary
+    }))
+    .collect()
+}
+
+/// Renders the summaries as CSV, with a header row of the [`CSV_COLUMNS`]. Fields are quoted if
+/// they have commas, quotes or newlines.
+pub fn to_csv(summaries: &[StepSummary]) -> String {
+  let mut csv = String::new();
+  let _ = writeln!(csv, "{}", CSV_COLUMNS.join(","));
+  for summary in summaries {
+    let row = summary.values().iter().map(|value| csv_field(value)).collect::<Vec<_>>();
+    let _ = writeln!(csv, "{}", row.join(","));
+  }
+  csv
+}
+
+/// Operation of a step, from its operation path
+#[cfg(feature = "json")]
+pub(crate) struct StepOperation {
+  /// Name of the source description
+  pub(crate) source_name: String,
+  /// HTTP method (uppercase)
+  pub(crate) method: String,
+  /// Path template
+  pub(crate) path: String
+}
+
+/// Source description, method and path from the operation path of the step. The source
+/// description is the first one of the description if the operation path does not refer to one.
+#[cfg(feature = "json")]
+pub(crate) fn step_operation(description: &ArazzoDescription, step: &Step) -> Option<StepOperation> {
+  let operation_path = step.operation_path.as_deref()?;
+  let (path, method) = operation_path_method(operation_path)?;
+  let source_name = operation_path.strip_prefix("{$sourceDescriptions.")
+    .and_then(|rest| rest.split_once('.'))
+    .map(|(name, _)| name.to_string())
+    .or_else(|| description.source_descriptions.first().map(|source| source.name.clone()))
+    .unwrap_or_else(|| "api".to_string());
+  Some(StepOperation { source_name, method, path })
+}
+
+/// Path and HTTP method (uppercase) from the JSON Pointer of an operation path
+fn operation_path_method(operation_path: &str) -> Option<(String, String)> {
+  let (_, pointer) = operation_path.split_once('#')?;
+  let (item_pointer, method) = pointer.rsplit_once('/')?;
+  let path = item_pointer.strip_prefix("/paths/")?
+    .replace("~1", "/")
+    .replace("~0", "~");
+  let method = method.to_lowercase();
+  HTTP_METHODS.contains(&method.as_str()).then(|| (path, method.to_uppercase()))
+}
+
+fn csv_field(value: &str) -> String {
+  if value.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", value.replace('"', "\"\""))
+  } else {
+    value.to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::arazzo;
+  #[cfg(feature = "execute")] use crate::operations::{OpenApiSource, OperationResolver};
+  use crate::step_summary::{step_summaries, to_csv};
+  #[cfg(feature = "execute")] use crate::step_summary::step_summaries_with_operations;
+  use crate::v1_0::ArazzoDescription;
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      workflow("adopt") {
+        step("find") {
+          operation_path: "{$sourceDescriptions.petstore.url}#/paths/~1pets~1{petId}/get";
+          success_criterion: "$statusCode == 200";
+          success_criterion: "$response.body#/id != null";
+        }
+        step("create, again") {
+          operation_id: "createPet";
+          json_body: json!({ "name": "Rex" });
+        }
+        step("owner") { workflow_id: "get-owner"; }
+      }
+    }
+  }
+
+  #[test]
+  fn exports_the_steps_as_csv() {
+    let summaries = step_summaries(&description());
+    expect!(summaries.len()).to(be_equal_to(3));
+    expect!(summaries[0].method.clone()).to(be_some().value("GET"));
+    expect!(summaries[0].path.clone()).to(be_some().value("/pets/{petId}"));
+    expect!(summaries[1].method.clone()).to(be_none());
+
+    expect!(to_csv(&summaries)).to(be_equal_to(
+      "workflowId,stepId,operationId,operationPath,targetWorkflowId,method,path,contentType,parameters,successCriteria,onSuccess,onFailure\n\
+      adopt,find,,{$sourceDescriptions.petstore.url}#/paths/~1pets~1{petId}/get,,GET,/pets/{petId},,0,2,0,0\n\
+      adopt,\"create, again\",createPet,,,,,application/json,0,0,0,0\n\
+      adopt,owner,,,get-owner,,,,0,0,0,0\n"));
+  }
+
+  #[test]
+  #[cfg(feature = "execute")]
+  fn resolves_the_operations_from_the_source_descriptions() {
+    let resolver = OperationResolver::new(vec![OpenApiSource::new("petstore", "petstore.json", json!({
+      "paths": { "/pets": { "post": { "operationId": "createPet" } } }
+    }))]);
+    let summaries = step_summaries_with_operations(&description(), &resolver);
+    expect!(summaries[1].method.clone()).to(be_some().value("POST"));
+    expect!(summaries[1].path.clone()).to(be_some().value("/pets"));
+    expect!(summaries[0].method.clone()).to(be_some().value("GET"));
+    expect!(summaries[2].method.clone()).to(be_none());
+  }
+}
@@ -0,0 +1,876 @@
+//! Converts between Arazzo descriptions and [Step CI](https://stepci.com) workflows. Step CI
+//! workflows are YAML or JSON documents, and are passed to and returned from the conversions as
+//! JSON values. Anything that can not be converted is skipped, and reported as a warning of the
+//! [`Conversion`].
+//!
+//! # Export (Arazzo to Step CI)
+//!
+//! | Arazzo | Step CI |
+//! |--------|---------|
+//! | `info.title` | `name` |
+//! | Workflow | Test (keyed by the workflow ID) |
+//! | Step | Step (named with the step ID), with an `http` request |
+//! | Operation path (or operation ID, with [`export_stepci_with_operations`]) | `http.method` and `http.url` |
+//! | Path, query, header and cookie parameters | `http.url`, `http.params`, `http.headers` and `http.cookies` |
+//! | JSON, form and other request bodies | `http.json`, `http.form` and `http.body` |
+//! | `$inputs.name` | `${{env.name}}` (with the defaults of the inputs schema in `env`) |
+//! | `$steps.step.outputs.name` | `${{captures.step_name}}` |
+//! | Base URL of a source description | `${{env.<source name>_base_url}}` |
+//! | Outputs (`$response.body#/pointer`, `$response.header.name`) | `http.captures` (`jsonpath`, `header`) |
+//! | `$statusCode == 200` | `http.check.status` |
+//! | `$response.body#/pointer <op> value` | `http.check.jsonpath` |
+//! | `$response.header.name <op> value` | `http.check.headers` |
+//! | Regex criteria on the body or a header | `http.check.body` or `http.check.headers` with a `match` matcher |
+//!
+//! Steps that execute workflows, success and failure actions, criteria with `||` and other types of
+//! criteria are not exported.
+//!
+//! # Import (Step CI to Arazzo)
+//!
+//! The import is the reverse of the export. Each host of the request URLs is a source description,
+//! with the host as the URL (which needs to be changed to the URL of the OpenAPI document), and
+//! the operations are operation paths. `env` variables are inputs of the workflow, and captures are
+//! outputs of the step that captured them. Only HTTP steps are imported, and captures and checks
+//! other than the ones in the table above are reported as warnings.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use indexmap::IndexMap;
+use serde_json::{json, Map, Value};
+
+use crate::docs::{input_properties, step_parameters};
+use crate::either::Either;
+use crate::extensions::AnyValue;
+use crate::har_import::split_url;
+use crate::hurl::json_path;
+#[cfg(feature = "execute")] use crate::operations::OperationResolver;
+use crate::payloads::{FormPayload, JsonPayload, Payload, StringPayload};
+use crate::postman_import::{sanitise_id, unique_id};
+use crate::step_summary::{step_operation, StepOperation};
+use crate::v1_0::{
+  ArazzoDescription,
+  Criterion,
+  Info,
+  ParameterObject,
+  RequestBody,
+  ReusableObject,
+  SourceDescription,
+  Step,
+  Workflow
+};
+
+/// Result of a conversion, with warnings for anything that was not converted
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversion<T> {
+  /// Converted document
+  pub result: T,
+  /// Warnings for the parts of the document that could not be converted
+  pub warnings: Vec<String>
+}
+
+/// Step CI matchers for the comparison operators of simple conditions
+const MATCHERS: [(&str, &str); 6] = [(">=", "gte"), ("<=", "lte"), ("==", "eq"), ("!=", "ne"), (">", "gt"), ("<", "lt")];
+
+/// Exports the workflows of the Arazzo description as a Step CI workflow, with the operations of
+/// the steps from their operation paths
+pub fn export_stepci(description: &ArazzoDescription) -> Conversion<Value> {
+  export(description, &|step| step_operation(description, step))
+}
+
+/// Exports the workflows of the Arazzo description as a Step CI workflow, resolving the operations
+/// of the steps from the OpenAPI source descriptions
+#[cfg(feature = "execute")]
+pub fn export_stepci_with_operations(description: &ArazzoDescription, resolver: &OperationResolver) -> Conversion<Value> {
+  export(description, &|step| {
+    resolver.resolve_step(step).ok()
+      .map(|operation| StepOperation {
+        source_name: operation.source_name,
+        method: operation.method,
+        path: operation.path
+      })
+      .or_else(|| step_operation(description, step))
+  })
+}
+
+fn export(description: &ArazzoDescription, resolve: &dyn Fn(&Step) -> Option<StepOperation>) -> Conversion<Value> {
+  let mut warnings = vec![];
+  let mut env = Map::new();
+  let mut tests = Map::new();
+  for workflow in &description.workflows {
+    for property in input_properties(description, workflow) {
+      if property.schema_type != "object" {
+        env.entry(env_name(&property.path)).or_insert_with(|| json!(property.default.unwrap_or_default()));
+      }
+    }
+    if !workflow.success_actions.is_empty() || !workflow.failure_actions.is_empty() {
+      warnings.push(format!("Workflow '{}': success and failure actions are not exported", workflow.workflow_id));
+    }
+    let steps = workflow.steps.iter()
+      .filter_map(|step| export_step(description, workflow, step, resolve, &mut env, &mut warnings))
+      .collect::<Vec<_>>();
+    tests.insert(workflow.workflow_id.clone(), json!({ "steps": steps }));
+  }
+
+  let mut stepci = json!({ "version": "1.1", "name": description.info.title });
+  if !env.is_empty() {
+    stepci["env"] = Value::Object(env);
+  }
+  stepci["tests"] = Value::Object(tests);
+  Conversion { result: stepci, warnings }
+}
+
+fn export_step(
+  description: &ArazzoDescription,
+  workflow: &Workflow,
+  step: &Step,
+  resolve: &dyn Fn(&Step) -> Option<StepOperation>,
+  env: &mut Map<String, Value>,
+  warnings: &mut Vec<String>
+) -> Option<Value> {
+  let location = format!("Step '{}' of workflow '{}'", step.step_id, workflow.workflow_id);
+  if let Some(workflow_id) = &step.workflow_id {
+    warnings.push(format!("{}: executes workflow '{}', which Step CI does not support", location, workflow_id));
+    return None;
+  }
+  let Some(operation) = resolve(step) else {
+    warnings.push(format!("{}: the operation could not be resolved", location));
+    return None;
+  };
+  if !step.on_success.is_empty() || !step.on_failure.is_empty() {
+    warnings.push(format!("{}: success and failure actions are not exported", location));
+  }
+
+  let parameters = match step_parameters(description, workflow, step) {
+    Ok(parameters) => parameters,
+    Err(err) => {
+      warnings.push(format!("{}: {}", location, err));
+      vec![]
+    }
+  };
+  let base_url = format!("{}_base_url", env_name(&operation.source_name));
+  env.entry(base_url.clone()).or_insert_with(|| json!(""));
+
+  let mut path = operation.path.clone();
+  let mut http = Map::new();
+  for (parameter, value) in &parameters {
+    let value = stepci_template(value, &location, warnings);
+    let section = match parameter.r#in.as_deref() {
+      Some("path") => {
+        path = path.replace(&format!("{{{}}}", parameter.name), &value);
+        continue;
+      }
+      Some("query") => "params",
+      Some("header") => "headers",
+      Some("cookie") => "cookies",
+      _ => continue
+    };
+    if let Value::Object(values) = http.entry(section).or_insert_with(|| json!({})) {
+      values.insert(parameter.name.clone(), json!(value));
+    }
+  }
+  http.insert("url".to_string(), json!(format!("${{{{env.{}}}}}{}", base_url, path)));
+  http.insert("method".to_string(), json!(operation.method.to_uppercase()));
+  if let Some(body) = &step.request_body {
+    export_body(body, &mut http, &location, warnings);
+  }
+
+  let mut captures = Map::new();
+  for (name, expression) in &step.outputs {
+    let capture = if let Some(pointer) = expression.strip_prefix("$response.body#") {
+      json!({ "jsonpath": json_path(pointer) })
+    } else if let Some(header) = expression.strip_prefix("$response.header.") {
+      json!({ "header": header })
+    } else {
+      warnings.push(format!("{}: output '{}' ({}) is not exported", location, name, expression));
+      continue;
+    };
+    captures.insert(capture_name(&step.step_id, name), capture);
+  }
+  if !captures.is_empty() {
+    http.insert("captures".to_string(), Value::Object(captures));
+  }
+
+  let mut check = Map::new();
+  for criterion in &step.success_criteria {
+    if !export_criterion(criterion, &mut check) {
+      warnings.push(format!("{}: criterion '{}' is not exported", location, criterion.condition));
+    }
+  }
+  if !check.is_empty() {
+    http.insert("check".to_string(), Value::Object(check));
+  }
+
+  Some(json!({ "name": step.step_id, "http": http }))
+}
+
+fn export_body(body: &RequestBody, http: &mut Map<String, Value>, location: &str, warnings: &mut Vec<String>) {
+  if let Some(form) = body.payload_as::<FormPayload>() {
+    let fields = form.0.iter()
+      .map(|(name, value)| (name.clone(), json!(stepci_template(&value.to_text().unwrap_or_default(), location, warnings))))
+      .collect::<Map<_, _>>();
+    http.insert("form".to_string(), Value::Object(fields));
+    return;
+  }
+  let Some(payload) = &body.payload else {
+    return;
+  };
+  if let Some(content_type) = body.effective_content_type()
+    && let Value::Object(headers) = http.entry("headers").or_insert_with(|| json!({})) {
+    headers.entry("Content-Type").or_insert_with(|| json!(content_type));
+  }
+  match payload.as_json() {
+    Some(mut json) => {
+      for replacement in &body.replacements {
+        let value = match &replacement.value {
+          Either::First(AnyValue::String(value)) => json!(stepci_template(value, location, warnings)),
+          Either::First(value) => value.to_json(),
+          Either::Second(expression) => json!(stepci_template(expression, location, warnings))
+        };
+        match json.pointer_mut(&replacement.target) {
+          Some(target) => *target = value,
+          None => warnings.push(format!("{}: replacement of '{}' is not exported", location, replacement.target))
+        }
+      }
+      http.insert("json".to_string(), template_json(json, location, warnings));
+    }
+    None => {
+      if !body.replacements.is_empty() {
+        warnings.push(format!("{}: replacements in the request body are not exported", location));
+      }
+      http.insert("body".to_string(), json!(stepci_template(&payload.as_string(), location, warnings)));
+    }
+  }
+}
+
+/// Converts the runtime expressions in the strings of the JSON value
+fn template_json(value: Value, location: &str, warnings: &mut Vec<String>) -> Value {
+  match value {
+    Value::String(text) => json!(stepci_template(&text, location, warnings)),
+    Value::Array(values) => Value::Array(values.into_iter().map(|value| template_json(value, location, warnings)).collect()),
+    Value::Object(map) => Value::Object(map.into_iter().map(|(key, value)| (key, template_json(value, location, warnings))).collect()),
+    value => value
+  }
+}
+
+/// Adds the check for the criterion, returning false if it can not be exported
+fn export_criterion(criterion: &Criterion, check: &mut Map<String, Value>) -> bool {
+  let criterion_type = match &criterion.r#type {
+    Some(Either::First(criterion_type)) => criterion_type.as_str(),
+    Some(Either::Second(expression_type)) => expression_type.r#type.as_str(),
+    None => "simple"
+  };
+  match (criterion_type, criterion.context.as_deref()) {
+    ("simple", None) => {
+      if criterion.condition.contains("||") {
+        return false;
+      }
+      let mut checks = vec![];
+      for part in criterion.condition.split("&&").map(|part| part.trim()) {
+        match simple_check(part) {
+          Some(part_check) => checks.push(part_check),
+          None => return false
+        }
+      }
+      for (section, key, value) in checks {
+        add_check(check, section, key, value);
+      }
+      true
+    }
+    ("regex", Some("$response.body")) => {
+      check.insert("body".to_string(), json!([{ "match": criterion.condition }]));
+      true
+    }
+    ("regex", Some(context)) if context.starts_with("$response.header.") => {
+      let header = context.trim_start_matches("$response.header.").to_string();
+      add_check(check, "headers", Some(header), json!([{ "match": criterion.condition }]));
+      true
+    }
+    _ => false
+  }
+}
+
+fn add_check(check: &mut Map<String, Value>, section: &str, key: Option<String>, value: Value) {
+  match key {
+    Some(key) => {
+      if let Value::Object(values) = check.entry(section).or_insert_with(|| json!({})) {
+        values.insert(key, value);
+      }
+    }
+    None => {
+      check.insert(section.to_string(), value);
+    }
+  }
+}
+
+/// Check for a comparison of a simple condition, as the section of the checks, the key in the
+/// section and the value (or matchers)
+fn simple_check(condition: &str) -> Option<(&'static str, Option<String>, Value)> {
+  let (index, operator, matcher) = condition.char_indices()
+    .find_map(|(index, _)| MATCHERS.iter().find(|(operator, _)| condition[index..].starts_with(operator))
+      .map(|(operator, matcher)| (index, *operator, *matcher)))?;
+  let expression = condition[..index].trim();
+  let literal = literal_value(condition[index + operator.len()..].trim());
+  let value = if operator == "==" { literal } else { json!([{ matcher: literal }]) };
+  if expression == "$statusCode" {
+    Some(("status", None, value))
+  } else if let Some(pointer) = expression.strip_prefix("$response.body#") {
+    Some(("jsonpath", Some(json_path(pointer)), value))
+  } else {
+    expression.strip_prefix("$response.header.").map(|header| ("headers", Some(header.to_string()), value))
+  }
+}
+
+/// JSON value of a literal of a simple condition
+fn literal_value(literal: &str) -> Value {
+  match literal.strip_prefix('\'').and_then(|literal| literal.strip_suffix('\'')) {
+    Some(text) => json!(text),
+    None => serde_json::from_str(literal).unwrap_or_else(|_| json!(literal))
+  }
+}
+
+/// Converts the runtime expression, or the expressions embedded in the text, to Step CI
+/// templates
+fn stepci_template(text: &str, location: &str, warnings: &mut Vec<String>) -> String {
+  if text.trim().starts_with('$') {
+    return stepci_expression(text.trim(), location, warnings);
+  }
+  let mut result = String::new();
+  let mut rest = text;
+  while let Some(start) = rest.find("{$") {
+    let Some(end) = rest[start..].find('}') else {
+      break;
+    };
+    result.push_str(&rest[..start]);
+    result.push_str(&stepci_expression(&rest[start + 1..start + end], location, warnings));
+    rest = &rest[start + end + 1..];
+  }
+  result.push_str(rest);
+  result
+}
+
+fn stepci_expression(expression: &str, location: &str, warnings: &mut Vec<String>) -> String {
+  if let Some(input) = expression.strip_prefix("$inputs.") {
+    format!("${{{{env.{}}}}}", env_name(input))
+  } else if let Some((step, output)) = expression.strip_prefix("$steps.").and_then(|rest| rest.split_once(".outputs.")) {
+    format!("${{{{captures.{}}}}}", capture_name(step, output))
+  } else {
+    warnings.push(format!("{}: expression '{}' is not exported", location, expression));
+    expression.to_string()
+  }
+}
+
+fn env_name(name: &str) -> String {
+  name.chars().map(|ch| if ch.is_ascii_alphanumeric() || ch == '_' { ch } else { '_' }).collect()
+}
+
+fn capture_name(step_id: &str, output: &str) -> String {
+  env_name(&format!("{}_{}", step_id, output))
+}
+
+/// Imports the Step CI workflow as an Arazzo description
+pub fn import_stepci(stepci: &Value) -> anyhow::Result<Conversion<ArazzoDescription>> {
+  let tests = stepci.get("tests").and_then(|tests| tests.as_object())
+    .ok_or_else(|| anyhow!("The Step CI workflow has no 'tests'"))?;
+  let env = stepci.get("env").and_then(|env| env.as_object()).cloned().unwrap_or_default();
+  let mut importer = StepCiImporter::default();
+  for key in ["config", "components", "before", "after"] {
+    if stepci.get(key).is_some() {
+      importer.warnings.push(format!("'{}' is not imported", key));
+    }
+  }
+
+  let mut workflows: Vec<Workflow> = vec![];
+  for (name, test) in tests {
+    let workflow_id = unique_id(&sanitise_id(name), |id| workflows.iter().any(|workflow| workflow.workflow_id == id));
+    let mut state = ImportState::default();
+    let mut steps: Vec<Step> = vec![];
+    for (index, step) in test.get("steps").and_then(|steps| steps.as_array()).into_iter().flatten().enumerate() {
+      let name = step.get("name").and_then(|name| name.as_str()).map(|name| name.to_string())
+        .unwrap_or_else(|| format!("step-{}", index + 1));
+      let step_id = unique_id(&sanitise_id(&name), |id| steps.iter().any(|step| step.step_id == id));
+      let location = format!("Step '{}' of test '{}'", name, workflow_id);
+      match step.get("http") {
+        Some(http) => steps.push(importer.step(step_id, http, &location, &env, &mut state)),
+        None => importer.warnings.push(format!("{}: only HTTP steps are imported", location))
+      }
+    }
+
+    let mut workflow = Workflow { workflow_id, summary: Some(name.clone()), steps, .. Workflow::default() };
+    if !state.inputs.is_empty() {
+      let properties = state.inputs.iter()
+        .map(|input| {
+          let mut property = json!({ "type": "string" });
+          if let Some(default) = env.get(input).filter(|default| **default != json!("")) {
+            property["default"] = default.clone();
+          }
+          (input.clone(), property)
+        })
+        .collect::<Map<_, _>>();
+      workflow.inputs = json!({ "type": "object", "properties": properties });
+    }
+    workflows.push(workflow);
+  }
+
+  Ok(Conversion {
+    result: ArazzoDescription {
+      info: Info {
+        title: stepci.get("name").and_then(|name| name.as_str()).unwrap_or("Step CI workflow").to_string(),
+        version: "1.0.0".to_string(),
+        .. Info::default()
+      },
+      source_descriptions: importer.sources.into_iter()
+        .map(|(name, url)| SourceDescription { name, url, r#type: Some("openapi".to_string()), extensions: Default::default() })
+        .collect(),
+      workflows,
+      .. ArazzoDescription::default()
+    },
+    warnings: importer.warnings
+  })
+}
+
+#[derive(Debug, Default)]
+struct StepCiImporter {
+  /// Source descriptions, keyed by name
+  sources: IndexMap<String, String>,
+  warnings: Vec<String>
+}
+
+/// Inputs and captures of the test being imported
+#[derive(Debug, Default)]
+struct ImportState {
+  /// Captures of previous steps, with the expression of the output
+  captures: HashMap<String, String>,
+  inputs: Vec<String>
+}
+
+impl StepCiImporter {
+  fn step(&mut self, step_id: String, http: &Value, location: &str, env: &Map<String, Value>, state: &mut ImportState) -> Step {
+    let url = http.get("url").and_then(|url| url.as_str()).unwrap_or_default();
+    let method = http.get("method").and_then(|method| method.as_str()).unwrap_or("GET").to_lowercase();
+    let (origin, path) = split_url(url);
+    let source = self.source(origin, env);
+
+    let mut parameters = vec![];
+    let mut template = String::new();
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+      template.push('/');
+      match next_template(segment) {
+        Some((0, variable, end)) if end == segment.len() => {
+          let name = variable.rsplit('.').next().unwrap_or(variable).to_string();
+          let _ = write!(template, "{{{}}}", name);
+          parameters.push(parameter(&name, "path", self.expression(variable, location, state)));
+        }
+        _ => template.push_str(&self.text(segment, location, state))
+      }
+    }
+    for (section, location_in) in [("params", "query"), ("headers", "header"), ("cookies", "cookie")] {
+      for (name, value) in http.get(section).and_then(|values| values.as_object()).into_iter().flatten() {
+        if location_in == "header" && name.eq_ignore_ascii_case("content-type") {
+          continue;
+        }
+        let value = self.text(&value_text(value), location, state);
+        parameters.push(parameter(name, location_in, value));
+      }
+    }
+    let content_type = http.get("headers").and_then(|headers| headers.as_object())
+      .and_then(|headers| headers.iter().find(|(name, _)| name.eq_ignore_ascii_case("content-type")))
+      .and_then(|(_, value)| value.as_str())
+      .map(|value| value.to_string());
+    let request_body = self.request_body(http, content_type, location, state);
+    for key in ["auth", "graphql", "trpc", "files", "formData", "followRedirects"] {
+      if http.get(key).is_some() {
+        self.warnings.push(format!("{}: '{}' is not imported", location, key));
+      }
+    }
+
+    let mut outputs = BTreeMap::new();
+    for (name, capture) in http.get("captures").and_then(|captures| captures.as_object()).into_iter().flatten() {
+      let expression = if let Some(path) = capture.get("jsonpath").and_then(|path| path.as_str()) {
+        json_pointer(path).map(|pointer| format!("$response.body#{}", pointer))
+      } else {
+        capture.get("header").and_then(|header| header.as_str()).map(|header| format!("$response.header.{}", header))
+      };
+      match expression {
+        Some(expression) => {
+          let output = sanitise_id(name);
+          state.captures.insert(name.clone(), format!("$steps.{}.outputs.{}", step_id, output));
+          outputs.insert(output, expression);
+        }
+        None => self.warnings.push(format!("{}: capture '{}' is not imported", location, name))
+      }
+    }
+
+    let mut success_criteria = vec![];
+    for (name, check) in http.get("check").and_then(|check| check.as_object()).into_iter().flatten() {
+      match name.as_str() {
+        "status" => self.import_check("$statusCode", check, location, &mut success_criteria),
+        "jsonpath" | "headers" => {
+          for (key, check) in check.as_object().into_iter().flatten() {
+            let expression = if name == "headers" {
+              Some(format!("$response.header.{}", key))
+            } else {
+              json_pointer(key).map(|pointer| format!("$response.body#{}", pointer))
+            };
+            match expression {
+              Some(expression) => self.import_check(&expression, check, location, &mut success_criteria),
+              None => self.warnings.push(format!("{}: check of '{}' is not imported", location, key))
+            }
+          }
+        }
+        "body" => self.import_check("$response.body", check, location, &mut success_criteria),
+        _ => self.warnings.push(format!("{}: '{}' checks are not imported", location, name))
+      }
+    }
+
+    Step {
+      step_id,
+      operation_path: Some(format!("{{$sourceDescriptions.{}.url}}#/paths/{}/{}", source,
+        template.replace('~', "~0").replace('/', "~1"), method)),
+      parameters,
+      request_body,
+      success_criteria,
+      outputs,
+      .. Step::default()
+    }
+  }
+
+  /// Adds the criteria for a check, which is either a value or a list of matchers
+  fn import_check(&mut self, expression: &str, check: &Value, location: &str, criteria: &mut Vec<Criterion>) {
+    let matchers = match check {
+      Value::Array(matchers) => matchers.clone(),
+      value => vec![json!({ "eq": value })]
+    };
+    for matcher in matchers {
+      let Some((name, value)) = matcher.as_object().and_then(|matcher| matcher.iter().next()) else {
+        continue;
+      };
+      if name == "match" && let Some(regex) = value.as_str() {
+        criteria.push(Criterion {
+          context: Some(expression.to_string()),
+          condition: regex.to_string(),
+          r#type: Some(Either::First("regex".to_string())),
+          .. Criterion::default()
+        });
+      } else if let Some((operator, _)) = MATCHERS.iter().find(|(_, matcher)| matcher == name) {
+        let literal = match value {
+          Value::String(text) => format!("'{}'", text),
+          value => value.to_string()
+        };
+        criteria.push(Criterion { condition: format!("{} {} {}", expression, operator, literal), .. Criterion::default() });
+      } else {
+        self.warnings.push(format!("{}: '{}' matcher of {} is not imported", location, name, expression));
+      }
+    }
+  }
+
+  fn request_body(&mut self, http: &Value, content_type: Option<String>, location: &str, state: &mut ImportState) -> Option<RequestBody> {
+    let (payload, content_type): (Arc<dyn Payload + Send + Sync>, Option<String>) = if let Some(json) = http.get("json") {
+      let json = self.template_json(json, location, state);
+      (Arc::new(JsonPayload(json)), content_type.or_else(|| Some("application/json".to_string())))
+    } else if let Some(form) = http.get("form").and_then(|form| form.as_object()) {
+      let fields = form.iter()
+        .map(|(name, value)| (name.clone(), AnyValue::String(self.text(&value_text(value), location, state))))
+        .collect();
+      (Arc::new(FormPayload(fields)), content_type.or_else(|| Some("application/x-www-form-urlencoded".to_string())))
+    } else if let Some(body) = http.get("body") {
+      (Arc::new(StringPayload(self.text(&value_text(body), location, state))), content_type)
+    } else {
+      return None;
+    };
+    Some(RequestBody { content_type, payload: Some(payload), replacements: vec![], extensions: Default::default() })
+  }
+
+  fn template_json(&mut self, value: &Value, location: &str, state: &mut ImportState) -> Value {
+    match value {
+      Value::String(text) => {
+        // Expressions in JSON payloads are embedded in the strings
+        let text = self.text(text, location, state);
+        if text.starts_with('$') { json!(format!("{{{}}}", text)) } else { json!(text) }
+      }
+      Value::Array(values) => Value::Array(values.iter().map(|value| self.template_json(value, location, state)).collect()),
+      Value::Object(map) => Value::Object(map.iter().map(|(key, value)| (key.clone(), self.template_json(value, location, state))).collect()),
+      value => value.clone()
+    }
+  }
+
+  /// Name of the source description for the origin of a URL, adding it if it is new
+  fn source(&mut self, origin: &str, env: &Map<String, Value>) -> String {
+    let (name, url) = match next_template(origin) {
+      Some((0, variable, end)) if end == origin.len() => {
+        let variable = variable.strip_prefix("env.").unwrap_or(variable);
+        let name = variable.strip_suffix("_base_url").unwrap_or(variable);
+        let url = env.get(variable).and_then(|url| url.as_str()).filter(|url| !url.is_empty()).unwrap_or(origin);
+        (sanitise_id(name), url.to_string())
+      }
+      _ => {
+        let domain = origin.split_once("://").map(|(_, domain)| domain).unwrap_or(origin);
+        (sanitise_id(domain), origin.to_string())
+      }
+    };
+    if let Some((existing, _)) = self.sources.iter().find(|(_, existing)| **existing == url) {
+      return existing.clone();
+    }
+    let name = unique_id(&name, |name| self.sources.contains_key(name));
+    self.sources.insert(name.clone(), url);
+    name
+  }
+
+  /// Converts the Step CI templates in the text to runtime expressions. If the text is only a
+  /// template, the expression is returned, otherwise the expressions are embedded in the text.
+  fn text(&mut self, text: &str, location: &str, state: &mut ImportState) -> String {
+    if let Some((0, variable, end)) = next_template(text) && end == text.len() {
+      return self.expression(variable, location, state);
+    }
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some((start, variable, end)) = next_template(rest) {
+      result.push_str(&rest[..start]);
+      let _ = write!(result, "{{{}}}", self.expression(variable, location, state));
+      rest = &rest[end..];
+    }
+    result.push_str(rest);
+    result
+  }
+
+  fn expression(&mut self, variable: &str, location: &str, state: &mut ImportState) -> String {
+    if let Some(capture) = variable.strip_prefix("captures.") {
+      if let Some(expression) = state.captures.get(capture) {
+        return expression.clone();
+      }
+      self.warnings.push(format!("{}: capture '{}' is not from a previous step, and is an input", location, capture));
+    }
+    let input = variable.strip_prefix("env.").or_else(|| variable.strip_prefix("captures.")).unwrap_or(variable);
+    if !state.inputs.iter().any(|existing| existing == input) {
+      state.inputs.push(input.to_string());
+    }
+    format!("$inputs.{}", input)
+  }
+}
+
+fn parameter(name: &str, location: &str, value: String) -> Either<ParameterObject, ReusableObject> {
+  Either::First(ParameterObject {
+    name: name.to_string(),
+    r#in: Some(location.to_string()),
+    value: if value.starts_with('$') { Either::Second(value) } else { Either::First(AnyValue::String(value)) },
+    extensions: Default::default()
+  })
+}
+
+/// Finds the next `${{ variable }}` template in the text, returning the start, variable and end
+fn next_template(text: &str) -> Option<(usize, &str, usize)> {
+  let start = text.find("${{")?;
+  let length = text[start..].find("}}")?;
+  Some((start, text[start + 3..start + length].trim(), start + length + 2))
+}
+
+fn value_text(value: &Value) -> String {
+  match value {
+    Value::String(text) => text.clone(),
+    value => value.to_string()
+  }
+}
+
+/// Converts a simple JSONPath (i.e. `$.items[0].id`) to a JSON Pointer. Returns `None` for
+/// JSONPaths with wildcards, filters or other expressions.
+fn json_pointer(path: &str) -> Option<String> {
+  let mut rest = path.strip_prefix('$')?;
+  let mut pointer = String::new();
+  while !rest.is_empty() {
+    let segment;
+    if let Some(property) = rest.strip_prefix('.') {
+      let end = property.find(['.', '[']).unwrap_or(property.len());
+      segment = property[..end].to_string();
+      rest = &property[end..];
+    } else if let Some(index) = rest.strip_prefix('[') {
+      let end = index.find(']')?;
+      let inner = index[..end].trim();
+      segment = inner.strip_prefix('\'').and_then(|inner| inner.strip_suffix('\''))
+        .or_else(|| inner.strip_prefix('"').and_then(|inner| inner.strip_suffix('"')))
+        .map(|inner| inner.to_string())
+        .or_else(|| inner.parse::<usize>().ok().map(|index| index.to_string()))?;
+      rest = &index[end + 1..];
+    } else {
+      return None;
+    }
+    if segment.is_empty() || segment == "*" {
+      return None;
+    }
+    pointer.push('/');
+    pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+  }
+  Some(pointer)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeMap;
+
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::either::Either;
+  use crate::stepci::{export_stepci, import_stepci, json_pointer};
+  use crate::v1_0::{ArazzoDescription, Criterion, Info, ParameterObject, Step, SuccessObject, Workflow};
+
+  #[test]
+  fn exports_workflows_as_step_ci_tests() {
+    let description = ArazzoDescription {
+      info: Info { title: "Pet Store".to_string(), .. Info::default() },
+      workflows: vec![Workflow {
+        workflow_id: "adopt".to_string(),
+        inputs: json!({ "type": "object", "properties": { "petId": { "type": "string", "default": "1" } } }),
+        steps: vec![
+          Step {
+            step_id: "find".to_string(),
+            operation_path: Some("{$sourceDescriptions.petstore.url}#/paths/~1pets~1{petId}/get".to_string()),
+            parameters: vec![Either::First(ParameterObject {
+              name: "petId".to_string(),
+              r#in: Some("path".to_string()),
+              value: Either::Second("$inputs.petId".to_string()),
+              .. ParameterObject::default()
+            })],
+            success_criteria: vec![
+              Criterion { condition: "$statusCode == 200 && $response.body#/age > 1".to_string(), .. Criterion::default() },
+              Criterion { condition: "$response.body#/name == 'Rex' || $response.body#/name == 'Fido'".to_string(), .. Criterion::default() }
+            ],
+            outputs: BTreeMap::from([("id".to_string(), "$response.body#/id".to_string())]),
+            on_success: vec![Either::First(SuccessObject {
+              name: "done".to_string(),
+              r#type: "end".to_string(),
+              workflow_id: None,
+              step_id: None,
+              criteria: vec![],
+              extensions: Default::default()
+            })],
+            .. Step::default()
+          },
+          Step {
+            step_id: "owner".to_string(),
+            operation_path: Some("{$sourceDescriptions.petstore.url}#/paths/~1owners/get".to_string()),
+            parameters: vec![Either::First(ParameterObject {
+              name: "pet".to_string(),
+              r#in: Some("query".to_string()),
+              value: Either::Second("$steps.find.outputs.id".to_string()),
+              .. ParameterObject::default()
+            })],
+            .. Step::default()
+          },
+          Step { step_id: "other".to_string(), workflow_id: Some("other".to_string()), .. Step::default() }
+        ],
+        .. Workflow::default()
+      }],
+      .. ArazzoDescription::default()
+    };
+
+    let conversion = export_stepci(&description);
+    expect!(conversion.result.clone()).to(be_equal_to(json!({
+      "version": "1.1",
+      "name": "Pet Store",
+      "env": { "petId": "1", "petstore_base_url": "" },
+      "tests": {
+        "adopt": {
+          "steps": [
+            {
+              "name": "find",
+              "http": {
+                "url": "${{env.petstore_base_url}}/pets/${{env.petId}}",
+                "method": "GET",
+                "captures": { "find_id": { "jsonpath": "$.id" } },
+                "check": { "status": 200, "jsonpath": { "$.age": [{ "gt": 1 }] } }
+              }
+            },
+            {
+              "name": "owner",
+              "http": {
+                "url": "${{env.petstore_base_url}}/owners",
+                "method": "GET",
+                "params": { "pet": "${{captures.find_id}}" }
+              }
+            }
+          ]
+        }
+      }
+    })));
+    expect!(conversion.warnings).to(be_equal_to(vec![
+      "Step 'find' of workflow 'adopt': success and failure actions are not exported".to_string(),
+      "Step 'find' of workflow 'adopt': criterion '$response.body#/name == 'Rex' || $response.body#/name == 'Fido'' is not exported".to_string(),
+      "Step 'other' of workflow 'adopt': executes workflow 'other', which Step CI does not support".to_string()
+    ]));
+  }
+
+  #[test]
+  fn imports_step_ci_tests_as_workflows() {
+    let stepci = json!({
+      "version": "1.1",
+      "name": "Pet Store",
+      "env": { "host": "https://petstore.example.com", "petId": "1" },
+      "tests": {
+        "adopt": {
+          "steps": [
+            {
+              "name": "Find pet",
+              "http": {
+                "url": "${{env.host}}/pets/${{env.petId}}",
+                "method": "GET",
+                "captures": { "id": { "jsonpath": "$.data.id" }, "title": { "selector": "title" } },
+                "check": { "status": 200, "jsonpath": { "$.name": "Rex", "$.age": [{ "gte": 1 }, { "isNumber": true }] } }
+              }
+            },
+            {
+              "name": "Adopt",
+              "http": {
+                "url": "${{env.host}}/adoptions",
+                "method": "POST",
+                "json": { "pet": "${{captures.id}}" },
+                "check": { "headers": { "Location": [{ "match": "^/adoptions/" }] } }
+              }
+            },
+            { "name": "Query", "graphql": {} }
+          ]
+        }
+      }
+    });
+    let conversion = import_stepci(&stepci).unwrap();
+    let description = conversion.result;
+    expect!(description.info.title.clone()).to(be_equal_to("Pet Store"));
+    expect!(description.source_descriptions[0].name.clone()).to(be_equal_to("host"));
+    expect!(description.source_descriptions[0].url.clone()).to(be_equal_to("https://petstore.example.com"));
+
+    let workflow = &description.workflows[0];
+    expect!(workflow.inputs.clone()).to(be_equal_to(json!({
+      "type": "object",
+      "properties": { "petId": { "type": "string", "default": "1" } }
+    })));
+    let find = &workflow.steps[0];
+    expect!(find.operation_path.clone()).to(be_some().value("{$sourceDescriptions.host.url}#/paths/~1pets~1{petId}/get"));
+    expect!(find.outputs.get("id").cloned()).to(be_some().value("$response.body#/data/id"));
+    let conditions = find.success_criteria.iter().map(|criterion| criterion.condition.clone()).collect::<Vec<_>>();
+    expect!(conditions).to(be_equal_to(vec![
+      "$response.body#/age >= 1".to_string(),
+      "$response.body#/name == 'Rex'".to_string(),
+      "$statusCode == 200".to_string()
+    ]));
+
+    let adopt = &workflow.steps[1];
+    let body = adopt.request_body.as_ref().unwrap().payload.as_ref().unwrap().as_json();
+    expect!(body).to(be_some().value(json!({ "pet": "{$steps.Find-pet.outputs.id}" })));
+    expect!(adopt.success_criteria[0].context.clone()).to(be_some().value("$response.header.Location"));
+
+    expect!(conversion.warnings).to(be_equal_to(vec![
+      "Step 'Find pet' of test 'adopt': capture 'title' is not imported".to_string(),
+      "Step 'Find pet' of test 'adopt': 'isNumber' matcher of $response.body#/age is not imported".to_string(),
+      "Step 'Query' of test 'adopt': only HTTP steps are imported".to_string()
+    ]));
+  }
+
+  #[test]
+  fn converts_json_paths_to_json_pointers() {
+    expect!(json_pointer("$.items[0]['the/id']")).to(be_some().value("/items/0/the~1id"));
+    expect!(json_pointer("$")).to(be_some().value(""));
+    expect!(json_pointer("$.items[*].id")).to(be_none());
+    expect!(json_pointer("$..id")).to(be_none());
+  }
+}
@@ -0,0 +1,356 @@
+//! Support for rendering payloads that contain embedded runtime expressions (templates like
+//! `{$inputs.pet_id}`), substituting the values of the expressions.
+//!
+//! For string values in structured payloads (JSON, YAML and form payloads), a value that is only a
+//! template is replaced with the value of the expression (keeping its type). Otherwise, the text of
+//! the values is inserted into the string. XML payloads have the templates in any text and attribute
+//! values replaced (with the values being escaped when the XML is written).
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use serde_json::Value;
+#[cfg(feature = "yaml")] use yaml_rust2::Yaml;
+
+use crate::expressions::ExpressionResolver;
+use crate::extensions::AnyValue;
+use crate::payloads::{FormPayload, GraphQlPayload, JsonPayload, Payload, StringPayload};
+#[cfg(feature = "xml")] use crate::payloads::XmlPayload;
+#[cfg(feature = "yaml")] use crate::payloads::YamlPayload;
+#[cfg(feature = "xml")] use crate::xml::{XmlElement, XmlNode};
+
+/// How values that are not strings are injected into string payloads
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ValueInjection {
+  /// Inserted as JSON (i.e. `10`, `true` or `{"a":1}`) (default)
+  #[default]
+  Raw,
+  /// Inserted as a JSON string (i.e. `"10"`, `"true"` or `"{\"a\":1}"`). For structured payloads,
+  /// templates that are the whole value are replaced with the text of the value.
+  Stringified
+}
+
+/// Options for rendering templates
+#[derive(Debug, Clone, Default)]
+pub struct TemplateOptions {
+  /// How non-string values are injected
+  pub injection: ValueInjection
+}
+
+/// Template found in some text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template<'a> {
+  /// Byte offset of the start of the template (the `{`)
+  pub start: usize,
+  /// Byte offset after the end of the template (after the `}`)
+  pub end: usize,
+  /// Runtime expression in the template
+  pub expression: &'a str
+}
+
+/// Finds all the templates (`{$...}`) in the text
+pub fn find_templates(text: &str) -> Vec<Template<'_>> {
+  let mut templates = vec![];
+  let mut position = 0;
+  while let Some(index) = text[position..].find("{$") {
+    let start = position + index;
+    match text[start..].find('}') {
+      Some(end) => {
+        templates.push(Template {
+          start,
+          end: start + end + 1,
+          expression: text[start + 1..start + end].trim()
+        });
+        position = start + end + 1;
+      }
+      None => break
+    }
+  }
+  templates
+}
+
+fn resolve(resolver: &dyn ExpressionResolver, expression: &str) -> anyhow::Result<AnyValue> {
+  resolver.resolve(expression)
+    .map_err(|err| anyhow!("Failed to render template '{{{}}}': {}", expression, err))
+}
+
+/// Text of the value to insert into a string (strings are inserted as is, other values as JSON)
+fn value_text(value: &AnyValue) -> String {
+  match value {
+    AnyValue::String(s) => s.clone(),
+    _ => Value::from(value).to_string()
+  }
+}
+
+/// Renders all the templates in the text, inserting the values of the expressions
+pub fn render_template(
+  text: &str,
+  resolver: &dyn ExpressionResolver,
+  options: &TemplateOptions
+) -> anyhow::Result<String> {
+  let mut result = String::with_capacity(text.len());
+  let mut position = 0;
+  for template in find_templates(text) {
+    let value = resolve(resolver, template.expression)?;
+    result.push_str(&text[position..template.start]);
+    match (&value, options.injection) {
+      (AnyValue::String(s), _) => result.push_str(s),
+      (_, ValueInjection::Raw) => result.push_str(&value_text(&value)),
+      (_, ValueInjection::Stringified) => result.push_str(&Value::String(value_text(&value)).to_string())
+    }
+    position = template.end;
+  }
+  result.push_str(&text[position..]);
+  Ok(result)
+}
+
+/// Renders a string value of a structured payload. If the string is only a template, the value is
+/// returned, otherwise the values are inserted into the string.
+fn render_string_value(
+  text: &str,
+  resolver: &dyn ExpressionResolver,
+  options: &TemplateOptions
+) -> anyhow::Result<AnyValue> {
+  let templates = find_templates(text);
+  if let [template] = templates.as_slice() && template.start == 0 && template.end == text.len() {
+    let value = resolve(resolver, template.expression)?;
+    match options.injection {
+      ValueInjection::Raw => Ok(value),
+      ValueInjection::Stringified => Ok(AnyValue::String(value_text(&value)))
+    }
+  } else if templates.is_empty() {
+    Ok(AnyValue::String(text.to_string()))
+  } else {
+    let mut result = String::with_capacity(text.len());
+    let mut position = 0;
+    for template in templates {
+      result.push_str(&text[position..template.start]);
+      result.push_str(&value_text(&resolve(resolver, template.expression)?));
+      position = template.end;
+    }
+    result.push_str(&text[position..]);
+    Ok(AnyValue::String(result))
+  }
+}
+
+/// Renders the templates in all the string values of the JSON document
+pub fn render_json_templates(
+  json: &Value,
+  resolver: &dyn ExpressionResolver,
+  options: &TemplateOptions
+) -> anyhow::Result<Value> {
+  match json {
+    Value::String(s) => render_string_value(s, resolver, options).map(|value| Value::from(&value)),
+    Value::Array(array) => array.iter()
+      .map(|item| render_json_templates(item, resolver, options))
+      .collect::<anyhow::Result<Vec<_>>>()
+      .map(Value::Array),
+    Value::Object(map) => map.iter()
+      .map(|(key, value)| render_json_templates(value, resolver, options).map(|value| (key.clone(), value)))
+      .collect::<anyhow::Result<serde_json::Map<_, _>>>()
+      .map(Value::Object),
+    _ => Ok(json.clone())
+  }
+}
+
+/// Renders the templates in all the string values of the YAML node
+#[cfg(feature = "yaml")]
+pub fn render_yaml_templates(
+  yaml: &Yaml,
+  resolver: &dyn ExpressionResolver,
+  options: &TemplateOptions
+) -> anyhow::Result<Yaml> {
+  match yaml {
+    Yaml::String(s) => render_string_value(s, resolver, options).map(|value| value.to_yaml()),
+    Yaml::Array(array) => array.iter()
+      .map(|item| render_yaml_templates(item, resolver, options))
+      .collect::<anyhow::Result<Vec<_>>>()
+      .map(Yaml::Array),
+    Yaml::Hash(hash) => hash.iter()
+      .map(|(key, value)| render_yaml_templates(value, resolver, options).map(|value| (key.clone(), value)))
+      .collect::<anyhow::Result<yaml_rust2::yaml::Hash>>()
+      .map(Yaml::Hash),
+    _ => Ok(yaml.clone())
+  }
+}
+
+/// Renders the templates in the text and attribute values of the element. Values are always
+/// inserted as text, as they will be escaped when the XML is written.
+#[cfg(feature = "xml")]
+fn render_xml_element(element: &mut XmlElement, resolver: &dyn ExpressionResolver) -> anyhow::Result<()> {
+  let options = TemplateOptions::default();
+  for (_, value) in element.attributes.iter_mut() {
+    *value = render_template(value, resolver, &options)?;
+  }
+  for child in element.children.iter_mut() {
+    match child {
+      XmlNode::Element(child) => render_xml_element(child, resolver)?,
+      XmlNode::Text(text) | XmlNode::CData(text) => *text = render_template(text, resolver, &options)?,
+      _ => {}
+    }
+  }
+  Ok(())
+}
+
+/// Renders the templates in the payload, returning a new payload. String, JSON, YAML, form, GraphQL
+/// (only the variables) and XML payloads are supported, any other payloads are returned as is.
+pub fn render_payload_templates(
+  payload: &Arc<dyn Payload + Send + Sync>,
+  resolver: &dyn ExpressionResolver,
+  options: &TemplateOptions
+) -> anyhow::Result<Arc<dyn Payload + Send + Sync>> {
+  if let Some(string_payload) = payload.downcast_ref::<StringPayload>() {
+    return Ok(Arc::new(StringPayload(render_template(&string_payload.0, resolver, options)?)));
+  }
+  if let Some(json_payload) = payload.downcast_ref::<JsonPayload>() {
+    return Ok(Arc::new(JsonPayload(render_json_templates(&json_payload.0, resolver, options)?)));
+  }
+  if let Some(graphql_payload) = payload.downcast_ref::<GraphQlPayload>() {
+    let mut graphql = graphql_payload.clone();
+    for value in graphql.variables.values_mut() {
+      *value = render_json_templates(value, resolver, options)?;
+    }
+    return Ok(Arc::new(graphql));
+  }
+  if let Some(form_payload) = payload.downcast_ref::<FormPayload>() {
+    let mut form = form_payload.clone();
+    for value in form.0.values_mut() {
+      if let AnyValue::String(s) = value {
+        *value = render_string_value(s, resolver, options)?;
+      }
+    }
+    return Ok(Arc::new(form));
+  }
+  #[cfg(feature = "yaml")]
+  if let Some(yaml_payload) = payload.downcast_ref::<YamlPayload>() {
+    return Ok(Arc::new(YamlPayload(render_yaml_templates(&yaml_payload.0, resolver, options)?)));
+  }
+  #[cfg(feature = "xml")]
+  if let Some(xml_payload) = payload.downcast_ref::<XmlPayload>() {
+    let mut xml = xml_payload.clone();
+    xml.update(|document| render_xml_element(&mut document.root, resolver))?;
+    return Ok(Arc::new(xml));
+  }
+  Ok(payload.clone())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+
+  use expectest::prelude::*;
+  use indexmap::indexmap;
+  use maplit::hashmap;
+  use serde_json::json;
+
+  use crate::extensions::AnyValue;
+  use crate::payloads::{BytesPayload, FormPayload, GraphQlPayload, JsonPayload, Payload, StringPayload};
+  use crate::templates::*;
+
+  fn values() -> std::collections::HashMap<String, AnyValue> {
+    hashmap!{
+      "$inputs.pet_id".to_string() => AnyValue::Integer(100),
+      "$inputs.name".to_string() => AnyValue::String("Fido \"the dog\"".to_string()),
+      "$inputs.tags".to_string() => AnyValue::Array(vec![AnyValue::String("dog".to_string())])
+    }
+  }
+
+  #[test]
+  fn finds_templates() {
+    expect!(find_templates("{\"id\": {$inputs.pet_id}, \"a\": \"{$inputs.name }\", \"b\": {}}")).to(be_equal_to(vec![
+      Template { start: 7, end: 23, expression: "$inputs.pet_id" },
+      Template { start: 31, end: 46, expression: "$inputs.name" }
+    ]));
+    expect!(find_templates("no templates {a} {$unterminated").is_empty()).to(be_true());
+  }
+
+  #[test]
+  fn render_string_templates() {
+    let values = values();
+    let template = "{\"id\": {$inputs.pet_id}, \"name\": \"{$inputs.name}\", \"tags\": {$inputs.tags}}";
+    expect!(render_template(template, &values, &TemplateOptions::default()).unwrap())
+      .to(be_equal_to("{\"id\": 100, \"name\": \"Fido \"the dog\"\", \"tags\": [\"dog\"]}"));
+    let options = TemplateOptions { injection: ValueInjection::Stringified };
+    expect!(render_template(template, &values, &options).unwrap())
+      .to(be_equal_to("{\"id\": \"100\", \"name\": \"Fido \"the dog\"\", \"tags\": \"[\\\"dog\\\"]\"}"));
+    expect!(render_template("{$inputs.other}", &values, &options).unwrap_err().to_string())
+      .to(be_equal_to("Failed to render template '{$inputs.other}': Runtime expression '$inputs.other' could not be resolved"));
+  }
+
+  #[test]
+  fn render_json_payload_templates() {
+    let values = values();
+    let json = json!({
+      "id": "{$inputs.pet_id}",
+      "label": "Pet {$inputs.pet_id}: {$inputs.name}",
+      "tags": ["{$inputs.tags}", 1]
+    });
+    expect!(render_json_templates(&json, &values, &TemplateOptions::default()).unwrap()).to(be_equal_to(json!({
+      "id": 100,
+      "label": "Pet 100: Fido \"the dog\"",
+      "tags": [["dog"], 1]
+    })));
+    let options = TemplateOptions { injection: ValueInjection::Stringified };
+    expect!(render_json_templates(&json, &values, &options).unwrap()).to(be_equal_to(json!({
+      "id": "100",
+      "label": "Pet 100: Fido \"the dog\"",
+      "tags": ["[\"dog\"]", 1]
+    })));
+  }
+
+  #[test]
+  fn render_payloads() {
+    let values = values();
+    let options = TemplateOptions::default();
+
+    let payload: Arc<dyn Payload + Send + Sync> = Arc::new(StringPayload("id={$inputs.pet_id}".to_string()));
+    expect!(render_payload_templates(&payload, &values, &options).unwrap().as_string()).to(be_equal_to("id=100"));
+
+    let payload: Arc<dyn Payload + Send + Sync> = Arc::new(JsonPayload(json!({ "id": "{$inputs.pet_id}" })));
+    expect!(render_payload_templates(&payload, &values, &options).unwrap().as_json()).to(be_some().value(json!({ "id": 100 })));
+
+    let payload: Arc<dyn Payload + Send + Sync> = Arc::new(FormPayload(indexmap!{
+      "id".to_string() => AnyValue::String("{$inputs.pet_id}".to_string())
+    }));
+    let rendered = render_payload_templates(&payload, &values, &options).unwrap();
+    expect!(rendered.downcast_ref::<FormPayload>().map(|p| p.0["id"].clone())).to(be_some().value(AnyValue::Integer(100)));
+
+    let payload: Arc<dyn Payload + Send + Sync> = Arc::new(BytesPayload(bytes::Bytes::from_static(b"{$inputs.pet_id}")));
+    expect!(render_payload_templates(&payload, &values, &options).unwrap().as_string()).to(be_equal_to(payload.as_string()));
+  }
+
+  #[test]
+  fn render_graphql_payloads() {
+    let mut graphql = GraphQlPayload::new("query Pet($id: ID!) { pet(id: $id) { name } }");
+    graphql.variables.insert("id".to_string(), json!("{$inputs.pet_id}"));
+    let payload: Arc<dyn Payload + Send + Sync> = Arc::new(graphql);
+    let rendered = render_payload_templates(&payload, &values(), &TemplateOptions::default()).unwrap();
+    expect!(rendered.as_json()).to(be_some().value(json!({
+      "query": "query Pet($id: ID!) { pet(id: $id) { name } }",
+      "variables": { "id": 100 }
+    })));
+  }
+
+  #[test]
+  #[cfg(feature = "yaml")]
+  fn render_yaml_payloads() {
+    use crate::payloads::YamlPayload;
+
+    let yaml = yaml_rust2::YamlLoader::load_from_str("id: '{$inputs.pet_id}'\nname: Pet {$inputs.name}").unwrap();
+    let payload: Arc<dyn Payload + Send + Sync> = Arc::new(YamlPayload(yaml[0].clone()));
+    let rendered = render_payload_templates(&payload, &values(), &TemplateOptions::default()).unwrap();
+    expect!(rendered.as_string()).to(be_equal_to("id: 100\nname: \"Pet Fido \\\"the dog\\\"\""));
+  }
+
+  #[test]
+  #[cfg(feature = "xml")]
+  fn render_xml_payloads() {
+    use crate::payloads::XmlPayload;
+
+    let payload: Arc<dyn Payload + Send + Sync> = Arc::new(XmlPayload::parse(
+      "<pet id=\"{$inputs.pet_id}\"><name>{$inputs.name} &amp; co</name></pet>").unwrap());
+    let rendered = render_payload_templates(&payload, &values(), &TemplateOptions::default()).unwrap();
+    expect!(rendered.as_string())
+      .to(be_equal_to("<pet id=\"100\"><name>Fido \"the dog\" &amp; co</name></pet>"));
+  }
+}
@@ -0,0 +1,407 @@
+//! Renders an Arazzo description as a tree for inspecting documents in a terminal, as
+//! `workflow → steps → parameters/criteria/actions/outputs`, which is much easier to read than the
+//! `Debug` output of the models. With the `color` feature, the tree can be rendered with ANSI
+//! colours (see [`TreeView::colored`]).
+//!
+//! ```
+//! use arazzo_models::v1_0::ArazzoDescription;
+//!
+//! let description = ArazzoDescription::default();
+//! println!("{}", description.tree_view());
+//! ```
+
+use std::fmt::{Display, Formatter};
+
+use serde_json::Value;
+
+use crate::either::Either;
+use crate::extensions::AnyValue;
+use crate::v1_0::{ArazzoDescription, Criterion, ParameterObject, ReusableObject, Step, Workflow};
+
+/// Node of a rendered tree
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct TreeNode {
+  pub(crate) label: String,
+  pub(crate) children: Vec<TreeNode>
+}
+
+impl TreeNode {
+  pub(crate) fn new<S: Into<String>>(label: S) -> Self {
+    TreeNode { label: label.into(), children: vec![] }
+  }
+
+  pub(crate) fn with_children(mut self, children: Vec<TreeNode>) -> Self {
+    self.children = children;
+    self
+  }
+
+  /// Adds a child node with the children, if there are any
+  fn add_group<S: Into<String>>(&mut self, label: S, children: Vec<TreeNode>) {
+    if !children.is_empty() {
+      self.children.push(TreeNode::new(label).with_children(children));
+    }
+  }
+}
+
+/// Writes the nodes as branches of a tree, with each line starting with the prefix
+pub(crate) fn write_tree(output: &mut String, nodes: &[TreeNode], prefix: &str, ascii: bool) {
+  let (branch, last_branch, line) = if ascii {
+    ("|-- ", "`-- ", "|   ")
+  } else {
+    ("├── ", "└── ", "│   ")
+  };
+  for (index, node) in nodes.iter().enumerate() {
+    let last = index + 1 == nodes.len();
+    output.push_str(prefix);
+    output.push_str(if last { last_branch } else { branch });
+    output.push_str(&node.label);
+    output.push('\n');
+    let child_prefix = format!("{}{}", prefix, if last { "    " } else { line });
+    write_tree(output, &node.children, &child_prefix, ascii);
+  }
+}
+
+/// Tree view of an Arazzo description, which renders the tree with [`Display`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TreeView<'a> {
+  description: &'a ArazzoDescription,
+  color: bool,
+  ascii: bool
+}
+
+impl ArazzoDescription {
+  /// Returns a tree view of the description, for printing in a terminal
+  pub fn tree_view(&self) -> TreeView<'_> {
+    TreeView { description: self, color: false, ascii: false }
+  }
+}
+
+impl <'a> TreeView<'a> {
+  /// Renders the tree with ANSI colours
+  #[cfg(feature = "color")]
+  pub fn colored(mut self) -> Self {
+    self.color = true;
+    self
+  }
+
+  /// Draws the tree with ASCII characters instead of box drawing characters
+  pub fn ascii(mut self) -> Self {
+    self.ascii = true;
+    self
+  }
+
+  fn workflow_node(&self, workflow: &Workflow) -> TreeNode {
+    let mut label = self.paint(&workflow.workflow_id, "1");
+    if let Some(summary) = &workflow.summary {
+      label = format!("{}: {}", label, summary);
+    }
+    let mut node = TreeNode::new(label);
+
+    let inputs = input_names(&workflow.inputs);
+    if !inputs.is_empty() {
+      node.children.push(TreeNode::new(format!("inputs: {}", inputs.join(", "))));
+    } else if let Some(reference) = workflow.inputs.get("$ref").and_then(|reference| reference.as_str()) {
+      node.children.push(TreeNode::new(format!("inputs: {}", reference)));
+    }
+    if !workflow.depends_on.is_empty() {
+      node.children.push(TreeNode::new(format!("depends on: {}", workflow.depends_on.join(", "))));
+    }
+    node.add_group("parameters", self.parameter_nodes(&workflow.parameters));
+    node.add_group("steps", workflow.steps.iter().map(|step| self.step_node(step)).collect());
+    node.add_group("success actions", self.success_nodes(&workflow.success_actions));
+    node.add_group("failure actions", self.failure_nodes(&workflow.failure_actions));
+    node.add_group("outputs", self.output_nodes(workflow.outputs.iter()));
+    node
+  }
+
+  fn step_node(&self, step: &Step) -> TreeNode {
+    let target = match (&step.operation_id, &step.operation_path, &step.workflow_id) {
+      (Some(operation_id), _, _) => format!(" → {}", operation_id),
+      (None, Some(operation_path), _) => format!(" → {}", operation_path),
+      (None, None, Some(workflow_id)) => format!(" → workflow {}", workflow_id),
+      _ => String::new()
+    };
+    let target = if self.ascii { target.replace('→', "->") } else { target };
+    let mut node = TreeNode::new(format!("{}{}", self.paint(&step.step_id, "36"), target));
+    node.add_group("parameters", self.parameter_nodes(&step.parameters));
+    if let Some(request_body) = &step.request_body {
+      let mut label = "request body".to_string();
+      if let Some(content_type) = request_body.effective_content_type() {
+        label = format!("{} ({})", label, content_type);
+      }
+      let replacements = request_body.replacements.iter()
+        .map(|replacement| TreeNode::new(format!("{} = {}", replacement.target, value_text(&replacement.value))))
+        .collect();
+      node.children.push(TreeNode::new(label).with_children(replacements));
+    }
+    node.add_group("success criteria", step.success_criteria.iter()
+      .map(|criterion| TreeNode::new(criterion_text(criterion)))
+      .collect());
+    node.add_group("on success", self.success_nodes(&step.on_success));
+    node.add_group("on failure", self.failure_nodes(&step.on_failure));
+    node.add_group("outputs", self.output_nodes(step.outputs.iter()));
+    node
+  }
+
+  fn parameter_nodes(&self, parameters: &[Either<ParameterObject, ReusableObject>]) -> Vec<TreeNode> {
+    parameters.iter()
+      .map(|parameter| match parameter {
+        Either::First(parameter) => {
+          let name = match &parameter.r#in {
+            Some(location) => format!("{} ({})", parameter.name, location),
+            None => parameter.name.clone()
+          };
+          TreeNode::new(format!("{} = {}", name, self.paint(&value_text(&parameter.value), "33")))
+        }
+        Either::Second(reusable) => TreeNode::new(reusable_text(reusable))
+      })
+      .collect()
+  }
+
+  fn success_nodes<A>(&self, actions: &[Either<A, ReusableObject>]) -> Vec<TreeNode>
+    where A: ActionText + std::fmt::Debug + Clone + PartialEq {
+    self.action_nodes(actions, "32")
+  }
+
+  fn failure_nodes<A>(&self, actions: &[Either<A, ReusableObject>]) -> Vec<TreeNode>
+    where A: ActionText + std::fmt::Debug + Clone + PartialEq {
+    self.action_nodes(actions, "31")
+  }
+
+  fn action_nodes<A>(&self, actions: &[Either<A, ReusableObject>], color: &str) -> Vec<TreeNode>
+    where A: ActionText + std::fmt::Debug + Clone + PartialEq {
+    actions.iter()
+      .map(|action| match action {
+        Either::First(action) => {
+          let (name, text, criteria) = action.action_text();
+          TreeNode::new(format!("{}: {}", self.paint(&name, color), text))
+            .with_children(criteria.iter().map(|criterion| TreeNode::new(criterion_text(criterion))).collect())
+        }
+        Either::Second(reusable) => TreeNode::new(reusable_text(reusable))
+      })
+      .collect()
+  }
+
+  fn output_nodes<'b>(&self, outputs: impl Iterator<Item = (&'b String, &'b String)>) -> Vec<TreeNode> {
+    outputs
+      .map(|(name, expression)| TreeNode::new(format!("{} = {}", name, self.paint(expression, "33"))))
+      .collect()
+  }
+
+  /// Wraps the text in the ANSI code, if colours are enabled
+  fn paint(&self, text: &str, code: &str) -> String {
+    if self.color {
+      format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+      text.to_string()
+    }
+  }
+}
+
+impl Display for TreeView<'_> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let description = self.description;
+    writeln!(f, "{} ({}, arazzo {})", self.paint(&description.info.title, "1"), description.info.version,
+      description.arazzo)?;
+
+    let mut root = TreeNode::default();
+    root.add_group("sources", description.source_descriptions.iter()
+      .map(|source| match &source.r#type {
+        Some(source_type) => TreeNode::new(format!("{} ({}): {}", source.name, source_type, source.url)),
+        None => TreeNode::new(format!("{}: {}", source.name, source.url))
+      })
+      .collect());
+    root.add_group("workflows", description.workflows.iter().map(|workflow| self.workflow_node(workflow)).collect());
+
+    let components = &description.components;
+    let counts = [
+      (components.inputs.len(), "inputs"),
+      (components.parameters.len(), "parameters"),
+      (components.success_actions.len(), "success actions"),
+      (components.failure_actions.len(), "failure actions")
+    ];
+    let counts = counts.iter()
+      .filter(|(count, _)| *count > 0)
+      .map(|(count, name)| format!("{} {}", count, name))
+      .collect::<Vec<_>>();
+    if !counts.is_empty() {
+      root.children.push(TreeNode::new(format!("components: {}", counts.join(", "))));
+    }
+
+    let mut output = String::new();
+    write_tree(&mut output, &root.children, "", self.ascii);
+    write!(f, "{}", output)
+  }
+}
+
+/// Name, description and criteria of a success or failure action
+trait ActionText {
+  fn action_text(&self) -> (String, String, Vec<Criterion>);
+}
+
+impl ActionText for crate::v1_0::SuccessObject {
+  fn action_text(&self) -> (String, String, Vec<Criterion>) {
+    (self.name.clone(), action_target(&self.r#type, &self.step_id, &self.workflow_id), self.criteria.clone())
+  }
+}
+
+impl ActionText for crate::v1_0::FailureObject {
+  fn action_text(&self) -> (String, String, Vec<Criterion>) {
+    let mut text = action_target(&self.r#type, &self.step_id, &self.workflow_id);
+    let mut retry = vec![];
+    if let Some(retry_after) = self.retry_after {
+      retry.push(format!("after {}s", retry_after));
+    }
+    if let Some(retry_limit) = self.retry_limit {
+      retry.push(format!("up to {} times", retry_limit));
+    }
+    if !retry.is_empty() {
+      text = format!("{} ({})", text, retry.join(", "));
+    }
+    (self.name.clone(), text, self.criteria.clone())
+  }
+}
+
+fn action_target(action_type: &str, step_id: &Option<String>, workflow_id: &Option<String>) -> String {
+  match (step_id, workflow_id) {
+    (Some(step_id), _) => format!("{} step {}", action_type, step_id),
+    (None, Some(workflow_id)) => format!("{} workflow {}", action_type, workflow_id),
+    (None, None) => action_type.to_string()
+  }
+}
+
+/// Names of the properties of the inputs schema, with ` (required)` after the required ones
+fn input_names(schema: &Value) -> Vec<String> {
+  let required = schema.get("required")
+    .and_then(|required| required.as_array())
+    .map(|required| required.iter().filter_map(|name| name.as_str()).collect::<Vec<_>>())
+    .unwrap_or_default();
+  schema.get("properties")
+    .and_then(|properties| properties.as_object())
+    .map(|properties| properties.keys()
+      .map(|name| if required.contains(&name.as_str()) { format!("{} (required)", name) } else { name.clone() })
+      .collect())
+    .unwrap_or_default()
+}
+
+fn value_text(value: &Either<AnyValue, String>) -> String {
+  match value {
+    Either::First(value) => any_value_text(value),
+    Either::Second(expression) => expression.clone()
+  }
+}
+
+/// Compact text of a value (strings are quoted)
+fn any_value_text(value: &AnyValue) -> String {
+  match value {
+    AnyValue::Null => "null".to_string(),
+    AnyValue::Boolean(b) => b.to_string(),
+    AnyValue::Integer(i) => i.to_string(),
+    AnyValue::UInteger(u) => u.to_string(),
+    AnyValue::Float(f) => f.to_string(),
+    AnyValue::BigNumber(n) => n.clone(),
+    AnyValue::String(s) => format!("{:?}", s),
+    AnyValue::Array(values) => format!("[{}]", values.iter().map(any_value_text).collect::<Vec<_>>().join(", ")),
+    AnyValue::Binary(bytes) => format!("<{} bytes>", bytes.len()),
+    AnyValue::Object(map) => format!("{{{}}}", map.iter()
+      .map(|(key, value)| format!("{:?}: {}", key, any_value_text(value)))
+      .collect::<Vec<_>>()
+      .join(", "))
+  }
+}
+
+fn reusable_text(reusable: &ReusableObject) -> String {
+  match &reusable.value {
+    Some(value) => format!("{} = {}", reusable.reference, value),
+    None => reusable.reference.clone()
+  }
+}
+
+fn criterion_text(criterion: &Criterion) -> String {
+  let mut text = criterion.condition.clone();
+  if let Some(context) = &criterion.context {
+    text = format!("{} on {}", text, context);
+  }
+  match &criterion.r#type {
+    Some(Either::First(criterion_type)) if criterion_type != "simple" => format!("{} ({})", text, criterion_type),
+    Some(Either::Second(expression_type)) => format!("{} ({} {})", text, expression_type.r#type, expression_type.version),
+    _ => text
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::arazzo;
+  use crate::v1_0::{ArazzoDescription, Criterion, FailureObject};
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      info("Pets", "1.0.0");
+      source("petstore", "petstore.yaml");
+      workflow("adopt") {
+        summary: "Adopt a pet";
+        inputs: json!({ "type": "object", "required": ["owner"], "properties": { "owner": {}, "status": {} } });
+        step("find") {
+          operation_id: "findPets";
+          parameter("status", "query", "$inputs.status");
+          parameter("limit", "query", "10");
+          success_criterion: "$statusCode == 200";
+          on_failure: FailureObject {
+            name: "again".to_string(),
+            r#type: "retry".to_string(),
+            workflow_id: None,
+            step_id: None,
+            retry_after: Some(1.0),
+            retry_limit: Some(3),
+            criteria: vec![Criterion { condition: "$statusCode == 503".to_string(), .. Criterion::default() }],
+            extensions: Default::default()
+          };
+          output("id", "$response.body#/0/id");
+        }
+        output("pet", "$steps.find.outputs.id");
+      }
+    }
+  }
+
+  #[test]
+  fn renders_the_description_as_a_tree() {
+    expect!(description().tree_view().to_string()).to(be_equal_to(
+"Pets (1.0.0, arazzo 1.0.1)
+├── sources
+│   └── petstore (openapi): petstore.yaml
+└── workflows
+    └── adopt: Adopt a pet
+        ├── inputs: owner (required), status
+        ├── steps
+        │   └── find → findPets
+        │       ├── parameters
+        │       │   ├── status (query) = $inputs.status
+        │       │   └── limit (query) = \"10\"
+        │       ├── success criteria
+        │       │   └── $statusCode == 200
+        │       ├── on failure
+        │       │   └── again: retry (after 1s, up to 3 times)
+        │       │       └── $statusCode == 503
+        │       └── outputs
+        │           └── id = $response.body#/0/id
+        └── outputs
+            └── pet = $steps.find.outputs.id
+".to_string()));
+
+    let ascii = description().tree_view().ascii().to_string();
+    expect!(ascii.contains("|   `-- petstore (openapi): petstore.yaml\n")).to(be_true());
+    expect!(ascii.contains("`-- find -> findPets\n")).to(be_true());
+  }
+
+  #[test]
+  #[cfg(feature = "color")]
+  fn renders_the_tree_with_colours() {
+    let tree = description().tree_view().colored().to_string();
+    expect!(tree.starts_with("\x1b[1mPets\x1b[0m (1.0.0, arazzo 1.0.1)\n")).to(be_true());
+    expect!(tree.contains("\x1b[36mfind\x1b[0m → findPets")).to(be_true());
+    expect!(tree.contains("\x1b[31magain\x1b[0m: retry")).to(be_true());
+  }
+}
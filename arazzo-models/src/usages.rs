@@ -0,0 +1,270 @@
+//! Index of where the named objects in an Arazzo description (source descriptions, workflows and
+//! components) are used.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+
+use serde_json::Value;
+
+use crate::expressions::{expression_references, for_each_component_expression, for_each_expression, ReferenceKind};
+use crate::v1_0::ArazzoDescription;
+
+/// Named object in an Arazzo description
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ObjectRef {
+  /// Type of object
+  pub kind: ReferenceKind,
+  /// Name or ID of the object
+  pub name: String
+}
+
+impl ObjectRef {
+  /// Creates a new reference to a named object
+  pub fn new<S: Into<String>>(kind: ReferenceKind, name: S) -> Self {
+    ObjectRef { kind, name: name.into() }
+  }
+}
+
+impl Display for ObjectRef {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} '{}'", self.kind, self.name)
+  }
+}
+
+/// Location where a named object is used
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Usage {
+  /// Workflow or component that contains the reference
+  pub owner: ObjectRef,
+  /// JSON Pointer to the value that contains the reference, relative to the owner
+  pub path: String
+}
+
+impl Display for Usage {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} ({})", self.owner, self.path)
+  }
+}
+
+/// Index of all the places the named objects of a description are used. Step references are
+/// only meaningful within the workflow that owns them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageIndex {
+  usages: BTreeMap<ObjectRef, BTreeSet<Usage>>
+}
+
+impl UsageIndex {
+  /// Builds the usage index for the description
+  pub fn build(description: &ArazzoDescription) -> Self {
+    let mut index = UsageIndex::default();
+
+    for workflow in &description.workflows {
+      let owner = ObjectRef::new(ReferenceKind::Workflow, workflow.workflow_id.as_str());
+      for_each_expression(workflow, &mut |path, value| index.add_value(&owner, path, value));
+      index.add_input_refs(&owner, "/inputs", &workflow.inputs);
+      index.add_step_refs(&owner, "/successActions", workflow.success_actions.iter()
+        .map(|action| action.first().and_then(|action| action.step_id.as_ref())));
+      index.add_step_refs(&owner, "/failureActions", workflow.failure_actions.iter()
+        .map(|action| action.first().and_then(|action| action.step_id.as_ref())));
+      for (step_index, step) in workflow.steps.iter().enumerate() {
+        index.add_step_refs(&owner, &format!("/steps/{}/onSuccess", step_index), step.on_success.iter()
+          .map(|action| action.first().and_then(|action| action.step_id.as_ref())));
+        index.add_step_refs(&owner, &format!("/steps/{}/onFailure", step_index), step.on_failure.iter()
+          .map(|action| action.first().and_then(|action| action.step_id.as_ref())));
+      }
+    }
+
+    for_each_component_expression(&description.components, &mut |path, value| {
+      if let Some(owner) = component_owner(path) {
+        let path = &path[owner.1..];
+        index.add_value(&owner.0, path, value);
+      }
+    });
+    for (name, input) in &description.components.inputs {
+      let owner = ObjectRef::new(ReferenceKind::ComponentInput, name.as_str());
+      index.add_input_refs(&owner, "", input);
+    }
+
+    index
+  }
+
+  fn add(&mut self, target: ObjectRef, owner: &ObjectRef, path: &str) {
+    self.usages.entry(target).or_default().insert(Usage {
+      owner: owner.clone(),
+      path: path.to_string()
+    });
+  }
+
+  fn add_value(&mut self, owner: &ObjectRef, path: &str, value: &str) {
+    for reference in expression_references(value) {
+      self.add(ObjectRef::new(reference.kind, reference.name), owner, path);
+    }
+
+    // dependsOn and workflowId fields can refer to a workflow in this description by plain ID
+    let plain_workflow_id = path.ends_with("/workflowId") ||
+      path.rsplit_once('/').map(|(parent, _)| parent.ends_with("/dependsOn")).unwrap_or_default();
+    if plain_workflow_id && !value.starts_with('$') {
+      self.add(ObjectRef::new(ReferenceKind::Workflow, value), owner, path);
+    }
+  }
+
+  fn add_step_refs<'a>(&mut self, owner: &ObjectRef, path: &str, step_ids: impl Iterator<Item = Option<&'a String>>) {
+    for (index, step_id) in step_ids.enumerate() {
+      if let Some(step_id) = step_id {
+        self.add(ObjectRef::new(ReferenceKind::Step, step_id.as_str()), owner, &format!("{}/{}/stepId", path, index));
+      }
+    }
+  }
+
+  fn add_input_refs(&mut self, owner: &ObjectRef, path: &str, json: &Value) {
+    match json {
+      Value::Object(map) => for (key, value) in map {
+        let path = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+        if key == "$ref" && let Value::String(reference) = value {
+          if let Some(name) = reference.strip_prefix("#/components/inputs/") {
+            self.add(ObjectRef::new(ReferenceKind::ComponentInput, name), owner, &path);
+          }
+        } else {
+          self.add_input_refs(owner, &path, value);
+        }
+      },
+      Value::Array(array) => for (index, item) in array.iter().enumerate() {
+        self.add_input_refs(owner, &format!("{}/{}", path, index), item);
+      },
+      _ => {}
+    }
+  }
+
+  /// Iterates over all the referenced objects, along with the places they are used
+  pub fn iter(&self) -> impl Iterator<Item = (&ObjectRef, &BTreeSet<Usage>)> {
+    self.usages.iter()
+  }
+
+  /// Returns all the places where the named object is used
+  pub fn usages_of(&self, kind: ReferenceKind, name: &str) -> Vec<&Usage> {
+    self.usages.get(&ObjectRef::new(kind, name))
+      .map(|usages| usages.iter().collect())
+      .unwrap_or_default()
+  }
+
+  /// If the named object is used anywhere in the description
+  pub fn is_used(&self, kind: ReferenceKind, name: &str) -> bool {
+    self.usages.get(&ObjectRef::new(kind, name))
+      .map(|usages| !usages.is_empty())
+      .unwrap_or_default()
+  }
+
+  /// Returns all the named objects that are referenced from the given workflow or component
+  pub fn references_from(&self, owner: &ObjectRef) -> BTreeSet<&ObjectRef> {
+    self.usages.iter()
+      .filter(|(_, usages)| usages.iter().any(|usage| &usage.owner == owner))
+      .map(|(target, _)| target)
+      .collect()
+  }
+
+  /// Returns all the named objects that are referenced from the given workflow or component,
+  /// including the ones referenced indirectly via other workflows and components. Step references
+  /// are not included.
+  pub fn transitive_references_from(&self, owner: &ObjectRef) -> BTreeSet<ObjectRef> {
+    let mut result = BTreeSet::new();
+    let mut pending = vec![owner.clone()];
+
+    while let Some(next) = pending.pop() {
+      for target in self.references_from(&next) {
+        if target.kind != ReferenceKind::Step && target != owner && result.insert(target.clone()) {
+          pending.push(target.clone());
+        }
+      }
+    }
+
+    result
+  }
+}
+
+/// Works out the component that owns the value from its path relative to the components, returning
+/// it and the length of the path prefix that identifies it.
+fn component_owner(path: &str) -> Option<(ObjectRef, usize)> {
+  let mut parts = path.splitn(4, '/').skip(1);
+  let kind_name = parts.next()?;
+  let kind = match kind_name {
+    "inputs" => ReferenceKind::ComponentInput,
+    "parameters" => ReferenceKind::ComponentParameter,
+    "successActions" => ReferenceKind::ComponentSuccessAction,
+    "failureActions" => ReferenceKind::ComponentFailureAction,
+    _ => return None
+  };
+  let name = parts.next()?;
+  let len = 1 + kind_name.len() + 1 + name.len();
+  Some((ObjectRef::new(kind, name), len))
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use serde_json::json;
+
+  use crate::arazzo;
+  use crate::expressions::ReferenceKind;
+  use crate::usages::{ObjectRef, Usage, UsageIndex};
+  use crate::v1_0::*;
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      source("api", "http://api");
+      source("other", "http://other");
+      workflow("login") {
+        inputs: json!({ "$ref": "#/components/inputs/credentials" });
+        step("one") { operation_id: "$sourceDescriptions.api.login"; }
+      }
+      workflow("buy") {
+        depends_on: "login";
+        step("one") {
+          operation_id: "$sourceDescriptions.other.buy";
+          reusable_on_failure: "retry";
+        }
+      }
+      component_input("credentials", json!({ "type": "object" }));
+      component_failure_action("retry", FailureObject {
+        name: "retry".to_string(),
+        r#type: "retry".to_string(),
+        workflow_id: None,
+        step_id: None,
+        retry_after: None,
+        retry_limit: Some(3),
+        criteria: vec![Criterion { condition: "$statusCode == 503".to_string(), .. Criterion::default() }],
+        extensions: Default::default()
+      });
+    }
+  }
+
+  #[test]
+  fn finds_the_usages_of_named_objects() {
+    let index = UsageIndex::build(&description());
+
+    expect!(index.usages_of(ReferenceKind::Workflow, "login")).to(be_equal_to(vec![
+      &Usage { owner: ObjectRef::new(ReferenceKind::Workflow, "buy"), path: "/dependsOn/0".to_string() }
+    ]));
+    expect!(index.usages_of(ReferenceKind::ComponentInput, "credentials")).to(be_equal_to(vec![
+      &Usage { owner: ObjectRef::new(ReferenceKind::Workflow, "login"), path: "/inputs/$ref".to_string() }
+    ]));
+    expect!(index.usages_of(ReferenceKind::ComponentFailureAction, "retry")).to(be_equal_to(vec![
+      &Usage { owner: ObjectRef::new(ReferenceKind::Workflow, "buy"), path: "/steps/0/onFailure/0/reference".to_string() }
+    ]));
+    expect!(index.is_used(ReferenceKind::SourceDescription, "api")).to(be_true());
+    expect!(index.is_used(ReferenceKind::Workflow, "buy")).to(be_false());
+  }
+
+  #[test]
+  fn finds_transitive_references() {
+    let index = UsageIndex::build(&description());
+
+    let references = index.transitive_references_from(&ObjectRef::new(ReferenceKind::Workflow, "buy"));
+    expect!(references.into_iter().collect::<Vec<_>>()).to(be_equal_to(vec![
+      ObjectRef::new(ReferenceKind::SourceDescription, "api"),
+      ObjectRef::new(ReferenceKind::SourceDescription, "other"),
+      ObjectRef::new(ReferenceKind::Workflow, "login"),
+      ObjectRef::new(ReferenceKind::ComponentInput, "credentials"),
+      ObjectRef::new(ReferenceKind::ComponentFailureAction, "retry")
+    ]));
+  }
+}
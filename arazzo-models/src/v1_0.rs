@@ -1,7 +1,7 @@
 //! Version 1.0.x specification models (<https://spec.openapis.org/arazzo/v1.0.1.html>)
 
 use std::collections::{HashMap, BTreeMap};
-use std::rc::Rc;
+use std::sync::Arc;
 
 use serde_json::Value;
 
@@ -25,7 +25,7 @@ pub struct ArazzoDescription {
   pub workflows: Vec<Workflow>,
   /// An element to hold shared schemas.
   pub components: Components,
-  /// Extension values
+  /// Extension values, keyed without the `x-` prefix
   pub extensions: HashMap<String, AnyValue>
 }
 
@@ -54,7 +54,7 @@ pub struct Info {
   pub description: Option<String>,
   /// Document version
   pub version: String,
-  /// Extension values
+  /// Extension values, keyed without the `x-` prefix
   pub extensions: HashMap<String, AnyValue>
 }
 
@@ -68,7 +68,7 @@ pub struct SourceDescription {
   pub url: String,
   /// The type of source description.
   pub r#type: Option<String>,
-  /// Extension values
+  /// Extension values, keyed without the `x-` prefix
   pub extensions: HashMap<String, AnyValue>
 }
 
@@ -96,7 +96,7 @@ pub struct Workflow {
   pub outputs: BTreeMap<String, String>,
   /// List of parameters that are applicable for all steps described under the workflow.
   pub parameters: Vec<Either<ParameterObject, ReusableObject>>,
-  /// Extension values
+  /// Extension values, keyed without the `x-` prefix
   pub extensions: HashMap<String, AnyValue>
 }
 
@@ -128,7 +128,7 @@ pub struct Step {
   pub on_failure: Vec<Either<FailureObject, ReusableObject>>,
   /// Defined outputs of the step.
   pub outputs: BTreeMap<String, String>,
-  /// Extension values
+  /// Extension values, keyed without the `x-` prefix
   pub extensions: HashMap<String, AnyValue>
 }
 
@@ -142,7 +142,7 @@ pub struct ParameterObject {
   pub r#in: Option<String>,
   /// Value to pass in the parameter.
   pub value: Either<AnyValue, String>,
-  /// Extension values
+  /// Extension values, keyed without the `x-` prefix
   pub extensions: HashMap<String, AnyValue>
 }
 
@@ -172,7 +172,7 @@ pub struct SuccessObject {
   pub step_id: Option<String>,
   /// List of assertions to determine if this action shall be executed.
   pub criteria: Vec<Criterion>,
-  /// Extension values
+  /// Extension values, keyed without the `x-` prefix
   pub extensions: HashMap<String, AnyValue>
 }
 
@@ -197,7 +197,7 @@ pub struct FailureObject {
   pub retry_limit: Option<i64>,
   /// List of assertions to determine if this action shall be executed.
   pub criteria: Vec<Criterion>,
-  /// Extension values
+  /// Extension values, keyed without the `x-` prefix
   pub extensions: HashMap<String, AnyValue>
 }
 
@@ -213,7 +213,7 @@ pub struct Components {
   pub success_actions: HashMap<String, SuccessObject>,
   /// Object to hold reusable Failure Actions Objects.
   pub failure_actions: HashMap<String, FailureObject>,
-  /// Extension values
+  /// Extension values, keyed without the `x-` prefix
   pub extensions: HashMap<String, AnyValue>
 }
 
@@ -248,7 +248,7 @@ pub struct Criterion {
   pub condition: String,
   /// The type of condition to be applied.
   pub r#type: Option<Either<String, CriterionExpressionType>>,
-  /// Extension values
+  /// Extension values, keyed without the `x-` prefix
   pub extensions: HashMap<String, AnyValue>
 }
 
@@ -260,7 +260,7 @@ pub struct CriterionExpressionType {
   pub r#type: String,
   /// A shorthand string representing the version of the expression type being used.
   pub version: String,
-  /// Extension values
+  /// Extension values, keyed without the `x-` prefix
   pub extensions: HashMap<String, AnyValue>
 }
 
@@ -271,13 +271,31 @@ pub struct RequestBody {
   /// Content-Type for the request content.
   pub content_type: Option<String>,
   /// Value representing the request body payload.
-  pub payload: Option<Rc<dyn Payload + Send + Sync>>,
+  pub payload: Option<Arc<dyn Payload + Send + Sync>>,
   /// List of locations and values to set within a payload
   pub replacements: Vec<PayloadReplacement>,
-  /// Extension values
+  /// Extension values, keyed without the `x-` prefix
   pub extensions: HashMap<String, AnyValue>
 }
 
+impl RequestBody {
+  /// Returns the payload as the given payload type, if there is a payload of that type
+  pub fn payload_as<T: Payload>(&self) -> Option<&T> {
+    self.payload.as_ref().and_then(|payload| payload.downcast_ref::<T>())
+  }
+
+  /// Returns the payload as a String, if there is a payload
+  pub fn payload_string(&self) -> Option<String> {
+    self.payload.as_ref().map(|payload| payload.as_string())
+  }
+
+  /// Returns the payload as a JSON document, if there is a payload that is easily convertable
+  /// to JSON
+  pub fn payload_json(&self) -> Option<Value> {
+    self.payload.as_ref().and_then(|payload| payload.as_json())
+  }
+}
+
 impl PartialEq for RequestBody {
   fn eq(&self, other: &Self) -> bool {
     if self.content_type == other.content_type &&
@@ -286,7 +304,7 @@ impl PartialEq for RequestBody {
       if self.payload.is_none() && other.payload.is_none() {
         true
       } else if let Some(payload) = &self.payload && let Some(other_payload) = &other.payload {
-        payload.as_bytes() == other_payload.as_bytes()
+        payload.eq_payload(other_payload.as_ref())
       } else {
         false
       }
@@ -304,22 +322,71 @@ pub struct PayloadReplacement {
   pub target: String,
   /// The value set within the target location.
   pub  value: Either<AnyValue, String>,
-  /// Extension values
+  /// Extension values, keyed without the `x-` prefix
   pub extensions: HashMap<String, AnyValue>
 }
 
+impl From<ParameterObject> for Either<ParameterObject, ReusableObject> {
+  fn from(value: ParameterObject) -> Self {
+    Either::First(value)
+  }
+}
+
+impl From<ReusableObject> for Either<ParameterObject, ReusableObject> {
+  fn from(value: ReusableObject) -> Self {
+    Either::Second(value)
+  }
+}
+
+impl From<SuccessObject> for Either<SuccessObject, ReusableObject> {
+  fn from(value: SuccessObject) -> Self {
+    Either::First(value)
+  }
+}
+
+impl From<ReusableObject> for Either<SuccessObject, ReusableObject> {
+  fn from(value: ReusableObject) -> Self {
+    Either::Second(value)
+  }
+}
+
+impl From<FailureObject> for Either<FailureObject, ReusableObject> {
+  fn from(value: FailureObject) -> Self {
+    Either::First(value)
+  }
+}
+
+impl From<ReusableObject> for Either<FailureObject, ReusableObject> {
+  fn from(value: ReusableObject) -> Self {
+    Either::Second(value)
+  }
+}
+
+impl From<AnyValue> for Either<AnyValue, String> {
+  fn from(value: AnyValue) -> Self {
+    Either::First(value)
+  }
+}
+
+impl From<CriterionExpressionType> for Either<String, CriterionExpressionType> {
+  fn from(value: CriterionExpressionType) -> Self {
+    Either::Second(value)
+  }
+}
+
 #[cfg(test)]
 mod tests {
-  use std::any::Any;
-  use std::rc::Rc;
+  use std::sync::Arc;
 
   use expectest::expect;
-  use expectest::matchers::be_equal_to;
+  use expectest::matchers::{be_equal_to, be_none, be_some, be_true};
   use maplit::hashmap;
+  use serde_json::json;
 
+  use crate::either::Either;
   use crate::extensions::AnyValue;
-  use crate::payloads::StringPayload;
-  use crate::v1_0::RequestBody;
+  use crate::payloads::{JsonPayload, StringPayload};
+  use crate::v1_0::{ParameterObject, RequestBody, ReusableObject};
 
   #[test]
   fn request_body_partial_equals() {
@@ -345,7 +412,7 @@ mod tests {
     };
     let body4 = RequestBody {
       content_type: None,
-      payload: Some(Rc::new(StringPayload("some text".to_string()))),
+      payload: Some(Arc::new(StringPayload("some text".to_string()))),
       replacements: vec![],
       extensions: hashmap!{
         "a".to_string() => AnyValue::Integer(100)
@@ -369,8 +436,32 @@ mod tests {
     expect!(&body4).to_not(be_equal_to(&body2));
     expect!(&body4).to_not(be_equal_to(&body3));
 
-    let payload: &dyn Any = body4.payload.as_ref().unwrap().as_ref();
-    let p = payload.downcast_ref::<StringPayload>().unwrap();
+    let p = body4.payload_as::<StringPayload>().unwrap();
     expect!(&p.0).to(be_equal_to("some text"));
+    expect!(body4.payload_as::<JsonPayload>().is_none()).to(be_true());
+    expect!(body4.payload_string()).to(be_some().value("some text"));
+    expect!(body4.payload_json()).to(be_none());
+    expect!(body3.payload_string()).to(be_none());
+
+    let body5 = RequestBody {
+      payload: Some(Arc::new(JsonPayload(json!({ "a": 1, "b": 2 })))),
+      .. body1.clone()
+    };
+    let body6 = RequestBody {
+      payload: Some(Arc::new(JsonPayload(json!({ "b": 2, "a": 1 })))),
+      .. body1.clone()
+    };
+    expect!(&body5).to(be_equal_to(&body6));
+  }
+
+  #[test]
+  fn either_from_conversions() {
+    let parameter = ParameterObject { name: "a".to_string(), .. ParameterObject::default() };
+    let reference = ReusableObject { reference: "$components.parameters.a".to_string(), value: None };
+
+    let either: Either<ParameterObject, ReusableObject> = parameter.clone().into();
+    expect!(either).to(be_equal_to(Either::First(parameter)));
+    let either: Either<ParameterObject, ReusableObject> = reference.clone().into();
+    expect!(either).to(be_equal_to(Either::Second(reference)));
   }
 }
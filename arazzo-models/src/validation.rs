@@ -0,0 +1,605 @@
+//! Validation of Arazzo descriptions against the rules from the specification that can not be
+//! enforced when loading a document (like unique IDs and references to other objects).
+
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+use anyhow::anyhow;
+
+use crate::either::Either;
+use crate::extension_registry::ExtensionRegistry;
+use crate::expressions::ReferenceKind;
+use crate::usages::{Usage, UsageIndex};
+use crate::v1_0::{
+  ArazzoDescription,
+  Components,
+  FailureObject,
+  ParameterObject,
+  ReusableObject,
+  Step,
+  SuccessObject,
+  Workflow
+};
+
+/// Problem found when validating a description
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+  /// JSON Pointer to the location of the problem in the document
+  pub path: String,
+  /// Description of the problem
+  pub message: String
+}
+
+impl ValidationIssue {
+  /// Creates a new validation issue
+  pub fn new<P: Into<String>, M: Into<String>>(path: P, message: M) -> Self {
+    ValidationIssue { path: path.into(), message: message.into() }
+  }
+}
+
+impl Display for ValidationIssue {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}: {}", self.path, self.message)
+  }
+}
+
+/// Validates the description, returning all the problems found
+pub fn validate(description: &ArazzoDescription) -> Vec<ValidationIssue> {
+  let mut issues = vec![];
+
+  validate_document(description, &mut issues);
+  validate_source_descriptions(description, &mut issues);
+  validate_workflows(description, &mut issues);
+  validate_components(description, &mut issues);
+  validate_references(description, &mut issues);
+
+  issues
+}
+
+/// Validates the description, including any extension values that have a handler registered
+/// with the registry, returning all the problems found
+pub fn validate_with_extensions(
+  description: &ArazzoDescription,
+  registry: &ExtensionRegistry
+) -> Vec<ValidationIssue> {
+  let mut issues = validate(description);
+  issues.extend(registry.validate(description));
+  issues
+}
+
+fn issues_to_result(issues: Vec<ValidationIssue>) -> anyhow::Result<()> {
+  if issues.is_empty() {
+    Ok(())
+  } else {
+    Err(anyhow!("Arazzo description is not valid: {}",
+      issues.iter().map(|issue| issue.to_string()).collect::<Vec<_>>().join(", ")))
+  }
+}
+
+impl ArazzoDescription {
+  /// Validates this description, returning an error listing all the problems if it is not valid
+  pub fn validate(&self) -> anyhow::Result<()> {
+    issues_to_result(validate(self))
+  }
+
+  /// Validates this description and any extension values that have a handler registered with the
+  /// registry, returning an error listing all the problems if it is not valid
+  pub fn validate_with_extensions(&self, registry: &ExtensionRegistry) -> anyhow::Result<()> {
+    issues_to_result(validate_with_extensions(self, registry))
+  }
+}
+
+fn is_valid_name(name: &str, extra: &[char]) -> bool {
+  !name.is_empty() && name.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-' || extra.contains(&ch))
+}
+
+fn check_unique<'a>(
+  names: impl Iterator<Item = (usize, &'a str)>,
+  path: &str,
+  label: &str,
+  section: &str,
+  issues: &mut Vec<ValidationIssue>
+) {
+  let mut seen = HashSet::new();
+  for (index, name) in names {
+    if !seen.insert(name) {
+      issues.push(ValidationIssue::new(format!("{}/{}", path, index),
+        format!("Duplicate {} '{}' [{} Fixed Fields]", label, name, section)));
+    }
+  }
+}
+
+fn validate_document(description: &ArazzoDescription, issues: &mut Vec<ValidationIssue>) {
+  if !description.arazzo.starts_with("1.0.") {
+    issues.push(ValidationIssue::new("/arazzo",
+      format!("Arazzo version '{}' is not supported, expected 1.0.x [4.6.1.1 Fixed Fields]", description.arazzo)));
+  }
+  if description.info.title.is_empty() {
+    issues.push(ValidationIssue::new("/info/title", "Title is required [4.6.2.1 Fixed Fields]"));
+  }
+  if description.info.version.is_empty() {
+    issues.push(ValidationIssue::new("/info/version", "Version is required [4.6.2.1 Fixed Fields]"));
+  }
+  if description.source_descriptions.is_empty() {
+    issues.push(ValidationIssue::new("/sourceDescriptions",
+      "Source Description list must have at least one entry [4.6.1.1 Fixed Fields]"));
+  }
+  if description.workflows.is_empty() {
+    issues.push(ValidationIssue::new("/workflows", "Workflows list must have at least one entry [4.6.1.1 Fixed Fields]"));
+  }
+}
+
+fn validate_source_descriptions(description: &ArazzoDescription, issues: &mut Vec<ValidationIssue>) {
+  for (index, source) in description.source_descriptions.iter().enumerate() {
+    let path = format!("/sourceDescriptions/{}", index);
+    if !is_valid_name(&source.name, &[]) {
+      issues.push(ValidationIssue::new(format!("{}/name", path),
+        format!("Source Description name '{}' must only contain [A-Za-z0-9_\\-] [4.6.3.1 Fixed Fields]", source.name)));
+    }
+    if source.url.is_empty() {
+      issues.push(ValidationIssue::new(format!("{}/url", path), "URL is required [4.6.3.1 Fixed Fields]"));
+    }
+    if let Some(source_type) = &source.r#type && source_type != "openapi" && source_type != "arazzo" {
+      issues.push(ValidationIssue::new(format!("{}/type", path),
+        format!("Source Description type '{}' must be either 'openapi' or 'arazzo' [4.6.3.1 Fixed Fields]", source_type)));
+    }
+  }
+  check_unique(description.source_descriptions.iter().map(|sd| sd.name.as_str()).enumerate(),
+    "/sourceDescriptions", "Source Description name", "4.6.3.1", issues);
+}
+
+fn validate_workflows(description: &ArazzoDescription, issues: &mut Vec<ValidationIssue>) {
+  for (index, workflow) in description.workflows.iter().enumerate() {
+    validate_workflow(workflow, &description.components, &format!("/workflows/{}", index), issues);
+  }
+  check_unique(description.workflows.iter().map(|wf| wf.workflow_id.as_str()).enumerate(),
+    "/workflows", "Workflow ID", "4.6.4.1", issues);
+}
+
+fn validate_workflow(workflow: &Workflow, components: &Components, path: &str, issues: &mut Vec<ValidationIssue>) {
+  if workflow.workflow_id.is_empty() {
+    issues.push(ValidationIssue::new(format!("{}/workflowId", path), "Workflow ID is required [4.6.4.1 Fixed Fields]"));
+  }
+  if workflow.steps.is_empty() {
+    issues.push(ValidationIssue::new(format!("{}/steps", path), "At lest one Step is required [4.6.4.1 Fixed Fields]"));
+  }
+  for (index, step) in workflow.steps.iter().enumerate() {
+    validate_step(step, components, &format!("{}/steps/{}", path, index), issues);
+  }
+  check_unique(workflow.steps.iter().map(|step| step.step_id.as_str()).enumerate(),
+    &format!("{}/steps", path), "Step ID", "4.6.5.1", issues);
+  validate_parameters(&workflow.parameters, components, &format!("{}/parameters", path), ParameterTarget::Any, issues);
+  validate_success_actions(&workflow.success_actions, &format!("{}/successActions", path), issues);
+  validate_failure_actions(&workflow.failure_actions, &format!("{}/failureActions", path), issues);
+  validate_output_names(workflow.outputs.keys(), &format!("{}/outputs", path), issues);
+}
+
+fn validate_step(step: &Step, components: &Components, path: &str, issues: &mut Vec<ValidationIssue>) {
+  if step.step_id.is_empty() {
+    issues.push(ValidationIssue::new(format!("{}/stepId", path), "Step ID is required [4.6.5.1 Fixed Fields]"));
+  }
+
+  let targets = [&step.operation_id, &step.operation_path, &step.workflow_id].iter()
+    .filter(|target| target.is_some())
+    .count();
+  if targets != 1 {
+    issues.push(ValidationIssue::new(path,
+      "Step must have exactly one of operationId, operationPath or workflowId [4.6.5.1 Fixed Fields]"));
+  }
+
+  let target = if step.workflow_id.is_some() { ParameterTarget::Workflow } else { ParameterTarget::Operation };
+  validate_parameters(&step.parameters, components, &format!("{}/parameters", path), target, issues);
+  if step.workflow_id.is_some() && step.request_body.is_some() {
+    issues.push(ValidationIssue::new(format!("{}/requestBody", path),
+      "Request body can not be used when the step targets a workflow with workflowId, only with operationId or operationPath [4.6.5.1 Fixed Fields]"));
+  }
+  validate_success_actions(&step.on_success, &format!("{}/onSuccess", path), issues);
+  validate_failure_actions(&step.on_failure, &format!("{}/onFailure", path), issues);
+  validate_output_names(step.outputs.keys(), &format!("{}/outputs", path), issues);
+  #[cfg(feature = "json")]
+  if let Some(body) = &step.request_body {
+    validate_request_body(body, &format!("{}/requestBody", path), issues);
+  }
+}
+
+#[cfg(feature = "json")]
+fn validate_request_body(body: &crate::v1_0::RequestBody, path: &str, issues: &mut Vec<ValidationIssue>) {
+  for (index, err) in crate::replacements::check_replacement_targets(body) {
+    issues.push(ValidationIssue::new(format!("{}/replacements/{}/target", path, index),
+      format!("{} [4.6.14.1 Fixed Fields]", err)));
+  }
+}
+
+/// What the parameters being validated are passed to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ParameterTarget {
+  /// Parameters of a step that targets an operation, which require a location
+  Operation,
+  /// Parameters of a step that targets a workflow, which map to the workflow inputs and so can
+  /// not have a location
+  Workflow,
+  /// Parameters of a workflow or in the components, which can be used with either
+  Any
+}
+
+fn validate_parameters(
+  parameters: &[Either<ParameterObject, ReusableObject>],
+  components: &Components,
+  path: &str,
+  target: ParameterTarget,
+  issues: &mut Vec<ValidationIssue>
+) {
+  for (index, parameter) in parameters.iter().enumerate() {
+    let path = format!("{}/{}", path, index);
+    match parameter {
+      Either::First(parameter) => validate_parameter(parameter, &path, target, issues),
+      Either::Second(reusable) => {
+        validate_reusable(reusable, ReferenceKind::ComponentParameter, &path, issues);
+        if target == ParameterTarget::Workflow {
+          validate_workflow_parameter_reference(reusable, components, &path, issues);
+        }
+      }
+    }
+  }
+}
+
+fn validate_parameter(parameter: &ParameterObject, path: &str, target: ParameterTarget, issues: &mut Vec<ValidationIssue>) {
+  if parameter.name.is_empty() {
+    issues.push(ValidationIssue::new(format!("{}/name", path), "Parameter name is required [4.6.6.1 Fixed Fields]"));
+  }
+  match &parameter.r#in {
+    Some(location) if target == ParameterTarget::Workflow => {
+      issues.push(ValidationIssue::new(format!("{}/in", path),
+        format!("Parameter location '{}' can not be used when the step targets a workflow, as the parameters map to the workflow inputs [4.6.6.1 Fixed Fields]", location)));
+    }
+    Some(location) if !["path", "query", "header", "cookie"].contains(&location.as_str()) => {
+      issues.push(ValidationIssue::new(format!("{}/in", path),
+        format!("Parameter location '{}' must be one of path, query, header or cookie [4.6.6.1 Fixed Fields]", location)));
+    }
+    None if target == ParameterTarget::Operation => {
+      issues.push(ValidationIssue::new(format!("{}/in", path),
+        "Parameter location is required when the step targets an operation [4.6.6.1 Fixed Fields]"));
+    }
+    _ => {}
+  }
+}
+
+/// Checks that a component parameter used by a step that targets a workflow does not have a
+/// location. References to missing components are reported by `validate_references`.
+fn validate_workflow_parameter_reference(
+  reusable: &ReusableObject,
+  components: &Components,
+  path: &str,
+  issues: &mut Vec<ValidationIssue>
+) {
+  let name = reusable.reference.strip_prefix(ReferenceKind::ComponentParameter.prefix())
+    .unwrap_or_default();
+  if let Some(location) = components.parameters.get(name).and_then(|parameter| parameter.r#in.as_ref()) {
+    issues.push(ValidationIssue::new(format!("{}/reference", path),
+      format!("Component Parameter '{}' has location '{}', which can not be used when the step targets a workflow, as the parameters map to the workflow inputs [4.6.6.1 Fixed Fields]", name, location)));
+  }
+}
+
+fn validate_reusable(reusable: &ReusableObject, kind: ReferenceKind, path: &str, issues: &mut Vec<ValidationIssue>) {
+  if !reusable.reference.starts_with(kind.prefix()) {
+    issues.push(ValidationIssue::new(format!("{}/reference", path),
+      format!("Reference '{}' must refer to a {} [4.6.10.1 Fixed Fields]", reusable.reference, kind)));
+  }
+}
+
+fn validate_success_actions(
+  actions: &[Either<SuccessObject, ReusableObject>],
+  path: &str,
+  issues: &mut Vec<ValidationIssue>
+) {
+  for (index, action) in actions.iter().enumerate() {
+    let path = format!("{}/{}", path, index);
+    match action {
+      Either::First(action) => validate_success_action(action, &path, issues),
+      Either::Second(reusable) => validate_reusable(reusable, ReferenceKind::ComponentSuccessAction, &path, issues)
+    }
+  }
+}
+
+fn validate_success_action(action: &SuccessObject, path: &str, issues: &mut Vec<ValidationIssue>) {
+  if action.name.is_empty() {
+    issues.push(ValidationIssue::new(format!("{}/name", path), "Success Action name is required [4.6.7.1 Fixed Fields]"));
+  }
+  match action.r#type.as_str() {
+    "end" => validate_action_no_target(&action.workflow_id, &action.step_id, path, "4.6.7.1", issues),
+    "goto" => validate_action_target(&action.workflow_id, &action.step_id, path, "4.6.7.1", issues),
+    _ => issues.push(ValidationIssue::new(format!("{}/type", path),
+      format!("Success Action type '{}' must be either 'end' or 'goto' [4.6.7.1 Fixed Fields]", action.r#type)))
+  }
+}
+
+fn validate_failure_actions(
+  actions: &[Either<FailureObject, ReusableObject>],
+  path: &str,
+  issues: &mut Vec<ValidationIssue>
+) {
+  for (index, action) in actions.iter().enumerate() {
+    let path = format!("{}/{}", path, index);
+    match action {
+      Either::First(action) => validate_failure_action(action, &path, issues),
+      Either::Second(reusable) => validate_reusable(reusable, ReferenceKind::ComponentFailureAction, &path, issues)
+    }
+  }
+}
+
+fn validate_failure_action(action: &FailureObject, path: &str, issues: &mut Vec<ValidationIssue>) {
+  if action.name.is_empty() {
+    issues.push(ValidationIssue::new(format!("{}/name", path), "Failure Action name is required [4.6.8.1 Fixed Fields]"));
+  }
+  match action.r#type.as_str() {
+    "end" => validate_action_no_target(&action.workflow_id, &action.step_id, path, "4.6.8.1", issues),
+    "goto" => validate_action_target(&action.workflow_id, &action.step_id, path, "4.6.8.1", issues),
+    "retry" => if action.workflow_id.is_some() && action.step_id.is_some() {
+      issues.push(ValidationIssue::new(path,
+        "Failure Action can not have both a workflowId and a stepId [4.6.8.1 Fixed Fields]"));
+    },
+    _ => issues.push(ValidationIssue::new(format!("{}/type", path),
+      format!("Failure Action type '{}' must be one of 'end', 'retry' or 'goto' [4.6.8.1 Fixed Fields]", action.r#type)))
+  }
+  if let Some(retry_after) = action.retry_after && retry_after < 0.0 {
+    issues.push(ValidationIssue::new(format!("{}/retryAfter", path),
+      "Retry after must be a non-negative number [4.6.8.1 Fixed Fields]"));
+  }
+  if let Some(retry_limit) = action.retry_limit && retry_limit < 0 {
+    issues.push(ValidationIssue::new(format!("{}/retryLimit", path),
+      "Retry limit must be a non-negative integer [4.6.8.1 Fixed Fields]"));
+  }
+}
+
+fn validate_action_target(
+  workflow_id: &Option<String>,
+  step_id: &Option<String>,
+  path: &str,
+  section: &str,
+  issues: &mut Vec<ValidationIssue>
+) {
+  if workflow_id.is_some() == step_id.is_some() {
+    issues.push(ValidationIssue::new(path,
+      format!("A goto action must have exactly one of workflowId or stepId [{} Fixed Fields]", section)));
+  }
+}
+
+fn validate_action_no_target(
+  workflow_id: &Option<String>,
+  step_id: &Option<String>,
+  path: &str,
+  section: &str,
+  issues: &mut Vec<ValidationIssue>
+) {
+  if workflow_id.is_some() || step_id.is_some() {
+    issues.push(ValidationIssue::new(path,
+      format!("An end action can not have a workflowId or stepId [{} Fixed Fields]", section)));
+  }
+}
+
+fn validate_output_names<'a>(names: impl Iterator<Item = &'a String>, path: &str, issues: &mut Vec<ValidationIssue>) {
+  for name in names {
+    if !is_valid_name(name, &['.']) {
+      issues.push(ValidationIssue::new(format!("{}/{}", path, name),
+        format!("Output name '{}' must only contain [a-zA-Z0-9.\\-_]", name)));
+    }
+  }
+}
+
+fn validate_components(description: &ArazzoDescription, issues: &mut Vec<ValidationIssue>) {
+  let components = &description.components;
+  let keys = components.inputs.keys().map(|key| ("inputs", key))
+    .chain(components.parameters.keys().map(|key| ("parameters", key)))
+    .chain(components.success_actions.keys().map(|key| ("successActions", key)))
+    .chain(components.failure_actions.keys().map(|key| ("failureActions", key)));
+  for (field, key) in keys {
+    if !is_valid_name(key, &['.']) {
+      issues.push(ValidationIssue::new(format!("/components/{}/{}", field, key),
+        format!("Component key '{}' must only contain [a-zA-Z0-9.\\-_] [4.6.9.1 Fixed Fields]", key)));
+    }
+  }
+
+  for (key, parameter) in &components.parameters {
+    validate_parameter(parameter, &format!("/components/parameters/{}", key), ParameterTarget::Any, issues);
+  }
+  for (key, action) in &components.success_actions {
+    validate_success_action(action, &format!("/components/successActions/{}", key), issues);
+  }
+  for (key, action) in &components.failure_actions {
+    validate_failure_action(action, &format!("/components/failureActions/{}", key), issues);
+  }
+}
+
+fn usage_path(description: &ArazzoDescription, usage: &Usage) -> String {
+  let owner = &usage.owner;
+  let prefix = match owner.kind {
+    ReferenceKind::Workflow => description.workflows.iter()
+      .position(|wf| wf.workflow_id == owner.name)
+      .map(|index| format!("/workflows/{}", index))
+      .unwrap_or_default(),
+    ReferenceKind::ComponentInput => format!("/components/inputs/{}", owner.name),
+    ReferenceKind::ComponentParameter => format!("/components/parameters/{}", owner.name),
+    ReferenceKind::ComponentSuccessAction => format!("/components/successActions/{}", owner.name),
+    ReferenceKind::ComponentFailureAction => format!("/components/failureActions/{}", owner.name),
+    _ => String::default()
+  };
+  format!("{}{}", prefix, usage.path)
+}
+
+fn validate_references(description: &ArazzoDescription, issues: &mut Vec<ValidationIssue>) {
+  let index = UsageIndex::build(description);
+  let components = &description.components;
+
+  for (target, usages) in index.iter() {
+    for usage in usages {
+      let exists = match target.kind {
+        ReferenceKind::SourceDescription => description.source_descriptions.iter().any(|sd| sd.name == target.name),
+        ReferenceKind::Workflow => description.workflows.iter().any(|wf| wf.workflow_id == target.name),
+        ReferenceKind::Step => if usage.owner.kind == ReferenceKind::Workflow {
+          description.workflows.iter()
+            .find(|wf| wf.workflow_id == usage.owner.name)
+            .map(|wf| wf.steps.iter().any(|step| step.step_id == target.name))
+            .unwrap_or_default()
+        } else {
+          // Steps referenced from components are resolved against the workflow that uses them
+          true
+        },
+        ReferenceKind::ComponentInput => components.inputs.contains_key(&target.name),
+        ReferenceKind::ComponentParameter => components.parameters.contains_key(&target.name),
+        ReferenceKind::ComponentSuccessAction => components.success_actions.contains_key(&target.name),
+        ReferenceKind::ComponentFailureAction => components.failure_actions.contains_key(&target.name)
+      };
+      if !exists {
+        issues.push(ValidationIssue::new(usage_path(description, usage), format!("{} does not exist", target)));
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use crate::arazzo;
+  use crate::either::Either;
+  use crate::validation::{validate, ValidationIssue};
+  use crate::v1_0::*;
+
+  fn description() -> ArazzoDescription {
+    arazzo! {
+      info("Test", "1.0.0");
+      source("api", "http://api");
+      workflow("test") {
+        step("one") {
+          operation_id: "$sourceDescriptions.api.getOne";
+          reusable_parameter: "page";
+        }
+      }
+      component_parameter("page", ParameterObject {
+        name: "page".to_string(),
+        r#in: Some("query".to_string()),
+        .. ParameterObject::default()
+      });
+    }
+  }
+
+  #[test]
+  fn valid_description() {
+    expect!(validate(&description())).to(be_equal_to(vec![]));
+    expect!(description().validate()).to(be_ok());
+  }
+
+  #[test]
+  fn validates_the_objects() {
+    let mut description = description();
+    description.info.version = "".to_string();
+    description.source_descriptions[0].r#type = Some("wsdl".to_string());
+    description.workflows[0].steps.push(Step {
+      step_id: "one".to_string(),
+      operation_id: Some("getTwo".to_string()),
+      workflow_id: Some("other".to_string()),
+      parameters: vec![
+        Either::First(ParameterObject { name: "id".to_string(), .. ParameterObject::default() })
+      ],
+      on_success: vec![
+        Either::First(SuccessObject {
+          name: "next".to_string(),
+          r#type: "goto".to_string(),
+          workflow_id: None,
+          step_id: None,
+          criteria: vec![],
+          extensions: Default::default()
+        })
+      ],
+      .. Step::default()
+    });
+
+    expect!(validate(&description)).to(be_equal_to(vec![
+      ValidationIssue::new("/info/version", "Version is required [4.6.2.1 Fixed Fields]"),
+      ValidationIssue::new("/sourceDescriptions/0/type",
+        "Source Description type 'wsdl' must be either 'openapi' or 'arazzo' [4.6.3.1 Fixed Fields]"),
+      ValidationIssue::new("/workflows/0/steps/1",
+        "Step must have exactly one of operationId, operationPath or workflowId [4.6.5.1 Fixed Fields]"),
+      ValidationIssue::new("/workflows/0/steps/1/onSuccess/0",
+        "A goto action must have exactly one of workflowId or stepId [4.6.7.1 Fixed Fields]"),
+      ValidationIssue::new("/workflows/0/steps/1", "Duplicate Step ID 'one' [4.6.5.1 Fixed Fields]"),
+      ValidationIssue::new("/workflows/0/steps/1/workflowId", "Workflow 'other' does not exist")
+    ]));
+  }
+
+  #[test]
+  fn validates_references() {
+    let mut description = description();
+    description.workflows[0].outputs.insert("id".to_string(), "$steps.two.outputs.id".to_string());
+    description.components.parameters.clear();
+
+    let result = description.validate();
+    expect!(result.unwrap_err().to_string()).to(be_equal_to(
+      "Arazzo description is not valid: /workflows/0/outputs/id: Step 'two' does not exist, \
+      /workflows/0/steps/0/parameters/0/reference: Component Parameter 'page' does not exist"));
+  }
+
+  #[test]
+  fn validates_steps_that_target_workflows() {
+    let mut description = description();
+    description.workflows.push(Workflow {
+      workflow_id: "other".to_string(),
+      steps: vec![
+        Step {
+          step_id: "run".to_string(),
+          workflow_id: Some("test".to_string()),
+          parameters: vec![
+            Either::First(ParameterObject { name: "id".to_string(), .. ParameterObject::default() }),
+            Either::First(ParameterObject {
+              name: "token".to_string(),
+              r#in: Some("header".to_string()),
+              .. ParameterObject::default()
+            }),
+            Either::Second(ReusableObject { reference: "$components.parameters.page".to_string(), value: None })
+          ],
+          request_body: Some(RequestBody {
+            content_type: None,
+            payload: None,
+            replacements: vec![],
+            extensions: Default::default()
+          }),
+          .. Step::default()
+        }
+      ],
+      .. Workflow::default()
+    });
+
+    expect!(validate(&description)).to(be_equal_to(vec![
+      ValidationIssue::new("/workflows/1/steps/0/parameters/1/in",
+        "Parameter location 'header' can not be used when the step targets a workflow, as the parameters map to the workflow inputs [4.6.6.1 Fixed Fields]"),
+      ValidationIssue::new("/workflows/1/steps/0/parameters/2/reference",
+        "Component Parameter 'page' has location 'query', which can not be used when the step targets a workflow, as the parameters map to the workflow inputs [4.6.6.1 Fixed Fields]"),
+      ValidationIssue::new("/workflows/1/steps/0/requestBody",
+        "Request body can not be used when the step targets a workflow with workflowId, only with operationId or operationPath [4.6.5.1 Fixed Fields]")
+    ]));
+  }
+
+  #[cfg(feature = "json")]
+  #[test]
+  fn validates_request_body_replacement_targets() {
+    use std::sync::Arc;
+    use serde_json::json;
+    use crate::payloads::JsonPayload;
+
+    let mut description = description();
+    description.workflows[0].steps[0].request_body = Some(RequestBody {
+      content_type: Some("application/json".to_string()),
+      payload: Some(Arc::new(JsonPayload(json!({ "pet": { "id": 1 } })))),
+      replacements: vec![
+        PayloadReplacement {
+          target: "/pet/id".to_string(), value: Either::Second("$inputs.id".to_string()), extensions: Default::default() },
+        PayloadReplacement { target: "/owner/id".to_string(), value: Either::Second("$inputs.owner".to_string()), extensions: Default::default() }
+      ],
+      extensions: Default::default()
+    });
+    expect!(validate(&description)).to(be_equal_to(vec![
+      ValidationIssue::new("/workflows/0/steps/0/requestBody/replacements/1/target",
+        "Replacement target '/owner/id' does not exist in the payload [4.6.14.1 Fixed Fields]")
+    ]));
+  }
+}
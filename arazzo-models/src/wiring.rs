@@ -0,0 +1,164 @@
+//! Helpers for passing the outputs of one step into another step of the same workflow
+
+use anyhow::anyhow;
+
+use crate::either::Either;
+use crate::v1_0::{ParameterObject, PayloadReplacement, Step, Workflow};
+
+/// Where to set an output value on the consuming step
+#[derive(Debug, Clone, PartialEq)]
+pub enum WiringTarget {
+  /// Parameter of the step. The location is required for operations, and must be empty if the
+  /// step invokes a workflow.
+  Parameter {
+    /// Name of the parameter
+    name: String,
+    /// Location of the parameter (path, query, header or cookie)
+    location: Option<String>
+  },
+  /// Location within the request body payload (JSON Pointer or XPath expression)
+  PayloadReplacement(String)
+}
+
+impl Step {
+  /// Returns the runtime expression (`$steps.<stepId>.outputs.<name>`) that refers to the given
+  /// output of this step. Returns an error if the step does not define the output.
+  pub fn output_expression(&self, output: &str) -> anyhow::Result<String> {
+    if self.outputs.contains_key(output) {
+      Ok(format!("$steps.{}.outputs.{}", self.step_id, output))
+    } else {
+      Err(anyhow!("Step '{}' does not have an output named '{}'", self.step_id, output))
+    }
+  }
+}
+
+impl Workflow {
+  /// Passes the output of the producer step to the consumer step, either as a parameter or as a
+  /// payload replacement. Any existing parameter with the same name and location, or replacement
+  /// with the same target, is replaced. Returns an error if the steps or the output do not exist.
+  pub fn wire_step_output(
+    &mut self,
+    producer_step_id: &str,
+    output: &str,
+    consumer_step_id: &str,
+    target: WiringTarget
+  ) -> anyhow::Result<()> {
+    let producer = self.steps.iter()
+      .find(|step| step.step_id == producer_step_id)
+      .ok_or_else(|| anyhow!("Workflow '{}' does not have a step '{}'", self.workflow_id, producer_step_id))?;
+    let expression = producer.output_expression(output)?;
+    if producer_step_id == consumer_step_id {
+      return Err(anyhow!("Step '{}' can not consume its own outputs", consumer_step_id));
+    }
+
+    let workflow_id = self.workflow_id.clone();
+    let consumer = self.steps.iter_mut()
+      .find(|step| step.step_id == consumer_step_id)
+      .ok_or_else(|| anyhow!("Workflow '{}' does not have a step '{}'", workflow_id, consumer_step_id))?;
+
+    match target {
+      WiringTarget::Parameter { name, location } => {
+        let parameter = ParameterObject {
+          name,
+          r#in: location,
+          value: Either::Second(expression),
+          extensions: Default::default()
+        };
+        let existing = consumer.parameters.iter_mut().find(|p| {
+          p.first().map(|p| p.name == parameter.name && p.r#in == parameter.r#in).unwrap_or_default()
+        });
+        if let Some(existing) = existing {
+          *existing = Either::First(parameter);
+        } else {
+          consumer.parameters.push(Either::First(parameter));
+        }
+      }
+      WiringTarget::PayloadReplacement(target) => {
+        let body = consumer.request_body.as_mut()
+          .ok_or_else(|| anyhow!("Step '{}' does not have a request body", consumer_step_id))?;
+        let replacement = PayloadReplacement {
+          target,
+          value: Either::Second(expression),
+          extensions: Default::default()
+        };
+        if let Some(existing) = body.replacements.iter_mut().find(|r| r.target == replacement.target) {
+          *existing = replacement;
+        } else {
+          body.replacements.push(replacement);
+        }
+      }
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+  use maplit::btreemap;
+
+  use crate::either::Either;
+  use crate::v1_0::*;
+  use crate::wiring::WiringTarget;
+
+  fn workflow() -> Workflow {
+    Workflow {
+      workflow_id: "buy".to_string(),
+      steps: vec![
+        Step {
+          step_id: "find".to_string(),
+          outputs: btreemap!{ "id".to_string() => "$response.body#/id".to_string() },
+          .. Step::default()
+        },
+        Step {
+          step_id: "buy".to_string(),
+          request_body: Some(RequestBody {
+            content_type: None,
+            payload: None,
+            replacements: vec![],
+            extensions: Default::default()
+          }),
+          .. Step::default()
+        }
+      ],
+      .. Workflow::default()
+    }
+  }
+
+  #[test]
+  fn wire_step_output_as_a_parameter() {
+    let mut workflow = workflow();
+    let target = WiringTarget::Parameter { name: "id".to_string(), location: Some("path".to_string()) };
+    workflow.wire_step_output("find", "id", "buy", target.clone()).unwrap();
+    workflow.wire_step_output("find", "id", "buy", target).unwrap();
+
+    let parameters = &workflow.steps[1].parameters;
+    expect!(parameters.len()).to(be_equal_to(1));
+    expect!(parameters[0].first().unwrap().value.clone())
+      .to(be_equal_to(Either::Second("$steps.find.outputs.id".to_string())));
+  }
+
+  #[test]
+  fn wire_step_output_as_a_payload_replacement() {
+    let mut workflow = workflow();
+    workflow.wire_step_output("find", "id", "buy", WiringTarget::PayloadReplacement("/petId".to_string())).unwrap();
+
+    let replacements = &workflow.steps[1].request_body.as_ref().unwrap().replacements;
+    expect!(replacements[0].target.as_str()).to(be_equal_to("/petId"));
+    expect!(replacements[0].value.clone()).to(be_equal_to(Either::Second("$steps.find.outputs.id".to_string())));
+
+    let result = workflow.wire_step_output("buy", "id", "find", WiringTarget::PayloadReplacement("/id".to_string()));
+    expect!(result.unwrap_err().to_string()).to(be_equal_to("Step 'buy' does not have an output named 'id'"));
+  }
+
+  #[test]
+  fn wire_step_output_checks_the_steps_exist() {
+    let mut workflow = workflow();
+    let target = WiringTarget::PayloadReplacement("/id".to_string());
+    expect!(workflow.wire_step_output("other", "id", "buy", target.clone()).unwrap_err().to_string())
+      .to(be_equal_to("Workflow 'buy' does not have a step 'other'"));
+    expect!(workflow.wire_step_output("find", "id", "other", target).unwrap_err().to_string())
+      .to(be_equal_to("Workflow 'buy' does not have a step 'other'"));
+  }
+}
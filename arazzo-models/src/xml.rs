@@ -0,0 +1,696 @@
+//! XML document model with XPath 1.0 support, used for XML payloads.
+//!
+//! Documents are parsed with [quick-xml](https://docs.rs/quick-xml) into a document model that can
+//! be updated and written out again. Elements, attributes, text, CDATA sections, comments and
+//! processing instructions are kept. Anything before or after the root element (like the XML
+//! declaration and any DOCTYPE) is kept as is. Namespace prefixes are kept as part of the names.
+//!
+//! XPath expressions are evaluated with [sxd-xpath](https://docs.rs/sxd-xpath). The namespace
+//! prefixes declared in the document can be used in the expressions, and names without a prefix
+//! only match elements that are not in a namespace (so elements in a default namespace must be
+//! matched with `*[local-name()='name']`).
+//!
+//! Elements can be nested at most [`MAX_DEPTH`] levels deep, as the functions that walk the
+//! document are recursive.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Write};
+
+use anyhow::anyhow;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use sxd_document::dom::{ChildOfElement, ParentOfChild};
+use sxd_document::{Package, QName};
+use sxd_xpath::nodeset::Node;
+use sxd_xpath::{Context, Factory, Value};
+
+/// Node in an XML document
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmlNode {
+  /// Element
+  Element(XmlElement),
+  /// Text (with any entities decoded)
+  Text(String),
+  /// CDATA section
+  CData(String),
+  /// Comment
+  Comment(String),
+  /// Processing instruction
+  ProcessingInstruction(String)
+}
+
+/// XML element
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct XmlElement {
+  /// Name of the element, including any namespace prefix
+  pub name: String,
+  /// Attributes of the element, in the order they were defined
+  pub attributes: Vec<(String, String)>,
+  /// Child nodes
+  pub children: Vec<XmlNode>
+}
+
+impl XmlElement {
+  /// Returns the name of the element without any namespace prefix
+  pub fn local_name(&self) -> &str {
+    local_name(&self.name)
+  }
+
+  /// Returns the value of the attribute
+  pub fn attribute(&self, name: &str) -> Option<&str> {
+    self.attributes.iter()
+      .find(|(key, _)| key == name)
+      .map(|(_, value)| value.as_str())
+  }
+
+  /// Sets the value of the attribute, adding it if it does not exist
+  pub fn set_attribute(&mut self, name: &str, value: &str) {
+    if let Some((_, existing)) = self.attributes.iter_mut().find(|(key, _)| key == name) {
+      *existing = value.to_string();
+    } else {
+      self.attributes.push((name.to_string(), value.to_string()));
+    }
+  }
+
+  /// Returns the text content of the element (the text of all the child text and CDATA nodes)
+  pub fn text(&self) -> String {
+    self.children.iter()
+      .filter_map(|child| match child {
+        XmlNode::Text(text) | XmlNode::CData(text) => Some(text.as_str()),
+        _ => None
+      })
+      .collect()
+  }
+
+  /// Child elements
+  pub fn child_elements(&self) -> impl Iterator<Item = &XmlElement> {
+    self.children.iter().filter_map(|child| match child {
+      XmlNode::Element(element) => Some(element),
+      _ => None
+    })
+  }
+
+  fn child_element_mut(&mut self, index: usize) -> Option<&mut XmlElement> {
+    self.children.iter_mut()
+      .filter_map(|child| match child {
+        XmlNode::Element(element) => Some(element),
+        _ => None
+      })
+      .nth(index)
+  }
+
+  fn write(&self, buffer: &mut String) {
+    let _ = write!(buffer, "<{}", self.name);
+    for (key, value) in &self.attributes {
+      let _ = write!(buffer, " {}=\"{}\"", key, escape(value, true));
+    }
+    if self.children.is_empty() {
+      buffer.push_str("/>");
+    } else {
+      buffer.push('>');
+      for child in &self.children {
+        match child {
+          XmlNode::Element(element) => element.write(buffer),
+          XmlNode::Text(text) => buffer.push_str(&escape(text, false)),
+          XmlNode::CData(text) => { let _ = write!(buffer, "<![CDATA[{}]]>", text); }
+          XmlNode::Comment(text) => { let _ = write!(buffer, "<!--{}-->", text); }
+          XmlNode::ProcessingInstruction(text) => { let _ = write!(buffer, "<?{}?>", text); }
+        }
+      }
+      let _ = write!(buffer, "</{}>", self.name);
+    }
+  }
+}
+
+/// Parsed XML document
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct XmlDocument {
+  /// Everything before the root element (XML declaration, DOCTYPE, comments and whitespace)
+  pub prolog: String,
+  /// Root element
+  pub root: XmlElement,
+  /// Everything after the root element
+  pub epilog: String
+}
+
+/// Node selected by an XPath expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum XPathMatch<'a> {
+  /// Element
+  Element(&'a XmlElement),
+  /// Attribute value
+  Attribute {
+    /// Attribute name
+    name: &'a str,
+    /// Attribute value
+    value: &'a str
+  },
+  /// Text content of an element
+  Text(String)
+}
+
+impl XPathMatch<'_> {
+  /// Returns the string value of the match (the text content for elements)
+  pub fn string_value(&self) -> String {
+    match self {
+      XPathMatch::Element(element) => element.text(),
+      XPathMatch::Attribute { value, .. } => value.to_string(),
+      XPathMatch::Text(text) => text.clone()
+    }
+  }
+}
+
+/// Location of a selected node, as the element indices from the root element
+#[derive(Debug, Clone, PartialEq)]
+enum Target {
+  Element(Vec<usize>),
+  Attribute(Vec<usize>, String),
+  Text(Vec<usize>)
+}
+
+/// Result of evaluating an XPath expression
+enum Evaluated {
+  /// Selected nodes, in document order
+  Nodes(Vec<Target>),
+  /// String and boolean value of an expression that does not select nodes (i.e. `count(//pet)`)
+  Value(String, bool)
+}
+
+impl XmlDocument {
+  /// Writes the document out as an XML string
+  pub fn to_xml_string(&self) -> String {
+    let mut buffer = self.prolog.clone();
+    self.root.write(&mut buffer);
+    buffer.push_str(&self.epilog);
+    buffer
+  }
+
+  fn element(&self, path: &[usize]) -> Option<&XmlElement> {
+    path.iter().try_fold(&self.root, |element, index| element.child_elements().nth(*index))
+  }
+
+  fn element_mut(&mut self, path: &[usize]) -> Option<&mut XmlElement> {
+    path.iter().try_fold(&mut self.root, |element, index| element.child_element_mut(*index))
+  }
+
+  /// Returns all the nodes selected by the XPath expression. Returns an error if the expression
+  /// is not valid, or does not select nodes.
+  pub fn select(&self, xpath: &str) -> anyhow::Result<Vec<XPathMatch<'_>>> {
+    let targets = self.nodes(xpath)?;
+    Ok(targets.iter()
+      .filter_map(|target| match target {
+        Target::Element(path) => self.element(path).map(XPathMatch::Element),
+        Target::Attribute(path, name) => self.element(path)
+          .and_then(|element| element.attributes.iter().find(|(key, _)| key == name))
+          .map(|(name, value)| XPathMatch::Attribute { name: name.as_str(), value: value.as_str() }),
+        Target::Text(path) => self.element(path).map(|element| XPathMatch::Text(element.text()))
+      })
+      .collect())
+  }
+
+  /// Returns the string value of the first node selected by the XPath expression, or the string
+  /// value of an expression that does not select nodes (i.e. `count(//pet)`)
+  pub fn select_string(&self, xpath: &str) -> anyhow::Result<Option<String>> {
+    match self.evaluate(xpath)? {
+      Evaluated::Nodes(_) => Ok(self.select(xpath)?.first().map(|node| node.string_value())),
+      Evaluated::Value(value, _) => Ok(Some(value))
+    }
+  }
+
+  /// Returns the boolean value of the XPath expression. Expressions that select nodes are true if
+  /// they select any nodes.
+  pub fn matches(&self, xpath: &str) -> anyhow::Result<bool> {
+    match self.evaluate(xpath)? {
+      Evaluated::Nodes(targets) => Ok(!targets.is_empty()),
+      Evaluated::Value(_, value) => Ok(value)
+    }
+  }
+
+  /// Sets the value of all the nodes selected by the XPath expression. For elements, the child
+  /// nodes are replaced with the text value, and for attributes the attribute value is set.
+  /// Returns the number of nodes that were updated.
+  pub fn set_value(&mut self, xpath: &str, value: &str) -> anyhow::Result<usize> {
+    self.update(xpath, |node| match node {
+      NodeMut::Element(element) => element.children = vec![XmlNode::Text(value.to_string())],
+      NodeMut::Attribute(element, name) => element.set_attribute(name, value)
+    })
+  }
+
+  /// Replaces all the elements selected by the XPath expression with the given element. Selected
+  /// attributes and text are set to the text content of the element. Returns the number of nodes
+  /// that were updated.
+  pub fn replace_element(&mut self, xpath: &str, replacement: &XmlElement) -> anyhow::Result<usize> {
+    self.update(xpath, |node| match node {
+      NodeMut::Element(element) => *element = replacement.clone(),
+      NodeMut::Attribute(element, name) => element.set_attribute(name, &replacement.text())
+    })
+  }
+
+  fn update<F>(&mut self, xpath: &str, mut callback: F) -> anyhow::Result<usize>
+    where F: FnMut(NodeMut<'_>) {
+    let targets = self.nodes(xpath)?;
+    let mut count = 0;
+    for target in &targets {
+      match target {
+        Target::Element(path) | Target::Text(path) => if let Some(element) = self.element_mut(path) {
+          callback(NodeMut::Element(element));
+          count += 1;
+        }
+        Target::Attribute(path, name) => if let Some(element) = self.element_mut(path) {
+          callback(NodeMut::Attribute(element, name));
+          count += 1;
+        }
+      }
+    }
+    Ok(count)
+  }
+
+  fn nodes(&self, xpath: &str) -> anyhow::Result<Vec<Target>> {
+    match self.evaluate(xpath)? {
+      Evaluated::Nodes(targets) => Ok(targets),
+      Evaluated::Value(..) => Err(anyhow!("XPath '{}' does not select any nodes", xpath))
+    }
+  }
+
+  /// Evaluates the XPath expression with sxd-xpath, against a copy of the document. The prefixes
+  /// declared in the document can be used in the expression.
+  fn evaluate(&self, xpath: &str) -> anyhow::Result<Evaluated> {
+    let expression = Factory::new().build(xpath)
+      .map_err(|err| anyhow!("XPath '{}' is not valid: {}", xpath, err))?
+      .ok_or_else(|| anyhow!("XPath '{}' is empty", xpath))?;
+
+    let package = Package::new();
+    let document = package.as_document();
+    let mut prefixes = BTreeMap::new();
+    let root = copy_element(document, &self.root, &HashMap::new(), &mut prefixes);
+    document.root().append_child(root);
+    if let Some(prefix) = xpath_prefixes(xpath).find(|prefix| !prefixes.contains_key(*prefix)) {
+      return Err(anyhow!("XPath '{}' uses the prefix '{}', which is not declared in the document", xpath, prefix));
+    }
+    let mut context = Context::new();
+    for (prefix, uri) in &prefixes {
+      context.set_namespace(prefix, uri);
+    }
+
+    let value = expression.evaluate(&context, document.root())
+      .map_err(|err| anyhow!("XPath '{}' could not be evaluated: {}", xpath, err))?;
+    let Value::Nodeset(nodes) = value else {
+      return Ok(Evaluated::Value(value.string(), value.boolean()));
+    };
+    let mut targets = vec![];
+    for node in nodes.document_order() {
+      let target = match node {
+        Node::Root(_) => Some(Target::Element(vec![])),
+        Node::Element(element) => Some(Target::Element(element_path(element))),
+        Node::Attribute(attribute) => attribute.parent().map(|element| {
+          let name = attribute.name().local_part();
+          let name = match attribute.preferred_prefix() {
+            Some(prefix) => format!("{}:{}", prefix, name),
+            None => name.to_string()
+          };
+          Target::Attribute(element_path(element), name)
+        }),
+        Node::Text(text) => text.parent().map(|element| Target::Text(element_path(element))),
+        _ => None
+      };
+      if let Some(target) = target && !targets.contains(&target) {
+        targets.push(target);
+      }
+    }
+    // The paths of the elements sort in document order, but sxd does not keep the order of the
+    // attributes
+    targets.sort_by_cached_key(|target| match target {
+      Target::Element(path) => (path.clone(), 0),
+      Target::Attribute(path, name) => (path.clone(), 1 + self.element(path)
+        .and_then(|element| element.attributes.iter().position(|(key, _)| key == name))
+        .unwrap_or_default()),
+      Target::Text(path) => (path.clone(), usize::MAX)
+    });
+    Ok(Evaluated::Nodes(targets))
+  }
+}
+
+/// Namespace URI used for prefixes that are not declared in the document, so they can still be
+/// used in XPath expressions
+const UNDECLARED_NAMESPACE: &str = "urn:arazzo:undeclared:";
+
+/// Copies the element into the sxd document, resolving the namespace prefixes. The prefixes that
+/// are declared are collected (the first declaration of a prefix is used for XPath expressions).
+fn copy_element<'d>(
+  document: sxd_document::dom::Document<'d>,
+  element: &XmlElement,
+  scope: &HashMap<String, String>,
+  prefixes: &mut BTreeMap<String, String>
+) -> sxd_document::dom::Element<'d> {
+  let mut scope = scope.clone();
+  for (key, value) in &element.attributes {
+    if key == "xmlns" {
+      scope.insert(String::new(), value.clone());
+    } else if let Some(prefix) = key.strip_prefix("xmlns:") {
+      scope.insert(prefix.to_string(), value.clone());
+      prefixes.entry(prefix.to_string()).or_insert_with(|| value.clone());
+    }
+  }
+  let mut namespace = |prefix: &str| -> String {
+    scope.get(prefix).cloned().unwrap_or_else(|| {
+      let uri = format!("{}{}", UNDECLARED_NAMESPACE, prefix);
+      prefixes.entry(prefix.to_string()).or_insert_with(|| uri.clone());
+      uri
+    })
+  };
+
+  let copy = match element.name.split_once(':') {
+    Some((prefix, name)) => {
+      let copy = document.create_element(QName::with_namespace_uri(Some(&namespace(prefix)), name));
+      copy.set_preferred_prefix(Some(prefix));
+      copy
+    }
+    None => match scope.get("") {
+      Some(uri) if !uri.is_empty() => document.create_element(QName::with_namespace_uri(Some(uri), &element.name)),
+      _ => document.create_element(element.name.as_str())
+    }
+  };
+  for (key, value) in &element.attributes {
+    if key == "xmlns" || key.starts_with("xmlns:") {
+      continue;
+    }
+    match key.split_once(':') {
+      Some((prefix, name)) => {
+        let attribute = copy.set_attribute_value(QName::with_namespace_uri(Some(&namespace(prefix)), name), value);
+        attribute.set_preferred_prefix(Some(prefix));
+      }
+      None => {
+        copy.set_attribute_value(key.as_str(), value);
+      }
+    }
+  }
+  for child in &element.children {
+    match child {
+      XmlNode::Element(child) => copy.append_child(copy_element(document, child, &scope, prefixes)),
+      XmlNode::Text(text) | XmlNode::CData(text) => copy.append_child(document.create_text(text)),
+      XmlNode::Comment(text) => copy.append_child(document.create_comment(text)),
+      XmlNode::ProcessingInstruction(text) => {
+        let (target, value) = match text.split_once(char::is_whitespace) {
+          Some((target, value)) => (target, Some(value.trim_start())),
+          None => (text.as_str(), None)
+        };
+        copy.append_child(document.create_processing_instruction(target, value));
+      }
+    }
+  }
+  copy
+}
+
+/// Namespace prefixes used in the XPath expression (names followed by a single `:`, outside of
+/// string literals). sxd-xpath panics on prefixes that are not in the context, so they are checked
+/// before the expression is evaluated.
+fn xpath_prefixes(xpath: &str) -> impl Iterator<Item = &str> {
+  let mut prefixes = vec![];
+  let mut quote = None;
+  let mut name_start = None;
+  let mut previous = None;
+  for (index, ch) in xpath.char_indices() {
+    if let Some(open) = quote {
+      if ch == open {
+        quote = None;
+      }
+    } else if ch == '\'' || ch == '"' {
+      quote = Some(ch);
+      name_start = None;
+    } else if ch.is_alphanumeric() || ch == '_' || ch == '-' || ch == '.' {
+      if name_start.is_none() && previous != Some(':') {
+        name_start = Some(index);
+      }
+    } else {
+      if ch == ':' && let Some(start) = name_start {
+        let next = xpath[index + 1..].chars().next();
+        if next != Some(':') && previous != Some(':') {
+          prefixes.push(&xpath[start..index]);
+        }
+      }
+      name_start = None;
+    }
+    previous = Some(ch);
+  }
+  prefixes.into_iter()
+}
+
+/// Path to the element, as the element indices from the root element
+fn element_path(element: sxd_document::dom::Element<'_>) -> Vec<usize> {
+  let mut path = vec![];
+  let mut current = element;
+  while let Some(ParentOfChild::Element(parent)) = current.parent() {
+    let index = parent.children().iter()
+      .filter_map(|child| match child {
+        ChildOfElement::Element(child) => Some(*child),
+        _ => None
+      })
+      .position(|child| child == current)
+      .unwrap_or_default();
+    path.push(index);
+    current = parent;
+  }
+  path.reverse();
+  path
+}
+
+/// Mutable reference to a selected node
+enum NodeMut<'a> {
+  Element(&'a mut XmlElement),
+  Attribute(&'a mut XmlElement, &'a str)
+}
+
+fn local_name(name: &str) -> &str {
+  name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Escapes the special characters in text or attribute values
+pub fn escape(text: &str, attribute: bool) -> String {
+  if attribute {
+    quick_xml::escape::escape(text).to_string()
+  } else {
+    quick_xml::escape::partial_escape(text).to_string()
+  }
+}
+
+/// Maximum nesting depth of elements. Documents with elements nested deeper than this fail to
+/// parse (instead of overflowing the stack in the functions that walk the document).
+pub const MAX_DEPTH: usize = 256;
+
+fn parse_error(source: &str, position: u64, message: impl Display) -> anyhow::Error {
+  let position = (position as usize).min(source.len());
+  let line = source.as_bytes()[..position].iter().filter(|ch| **ch == b'\n').count() + 1;
+  anyhow!("Invalid XML: {} (line {})", message, line)
+}
+
+fn parse_element(source: &str, position: u64, start: &BytesStart<'_>) -> anyhow::Result<XmlElement> {
+  let name = String::from_utf8_lossy(start.name().as_ref()).to_string();
+  let mut element = XmlElement { name, .. XmlElement::default() };
+  for attribute in start.attributes() {
+    let attribute = attribute.map_err(|err| parse_error(source, position, err))?;
+    let value = attribute.unescape_value().map_err(|err| parse_error(source, position, err))?;
+    element.attributes.push((String::from_utf8_lossy(attribute.key.as_ref()).to_string(), value.to_string()));
+  }
+  Ok(element)
+}
+
+/// Adds the text to the element, merging it with the previous text node (as entity references are
+/// read separately from the text around them)
+fn push_text(element: &mut XmlElement, text: &str) {
+  if let Some(XmlNode::Text(previous)) = element.children.last_mut() {
+    previous.push_str(text);
+  } else {
+    element.children.push(XmlNode::Text(text.to_string()));
+  }
+}
+
+/// Adds the element to its parent at the top of the stack, or returns it if it is the root element
+fn close_element(stack: &mut [XmlElement], element: XmlElement) -> Option<XmlElement> {
+  match stack.last_mut() {
+    Some(parent) => {
+      parent.children.push(XmlNode::Element(element));
+      None
+    }
+    None => Some(element)
+  }
+}
+
+/// Parses the XML document with quick-xml
+pub fn parse_xml(source: &str) -> anyhow::Result<XmlDocument> {
+  let mut reader = Reader::from_str(source);
+  let mut stack: Vec<XmlElement> = vec![];
+  let mut root = None;
+  let mut prolog_end = 0;
+  let mut root_end = 0;
+
+  loop {
+    let position = reader.buffer_position();
+    let event = reader.read_event()
+      .map_err(|err| parse_error(source, reader.error_position(), err))?;
+    let decode_error = |err| parse_error(source, position, err);
+    match event {
+      Event::Start(_) | Event::Empty(_) if root.is_some() =>
+        return Err(parse_error(source, position, "unexpected content after the root element")),
+      Event::Start(start) => {
+        if stack.len() >= MAX_DEPTH {
+          return Err(parse_error(source, position, format!("elements are nested more than {} levels deep", MAX_DEPTH)));
+        }
+        if stack.is_empty() {
+          prolog_end = position as usize;
+        }
+        stack.push(parse_element(source, position, &start)?);
+      }
+      Event::Empty(start) => {
+        if stack.is_empty() {
+          prolog_end = position as usize;
+        }
+        let element = parse_element(source, position, &start)?;
+        if let Some(element) = close_element(&mut stack, element) {
+          root = Some(element);
+          root_end = reader.buffer_position() as usize;
+        }
+      }
+      Event::End(_) => {
+        let element = stack.pop().ok_or_else(|| parse_error(source, position, "unexpected end tag"))?;
+        if let Some(element) = close_element(&mut stack, element) {
+          root = Some(element);
+          root_end = reader.buffer_position() as usize;
+        }
+      }
+      Event::Text(text) => match stack.last_mut() {
+        Some(element) => push_text(element, &text.decode().map_err(decode_error)?),
+        None if text.iter().all(u8::is_ascii_whitespace) => {}
+        None => return Err(parse_error(source, position, "text outside of the root element"))
+      },
+      Event::GeneralRef(reference) => {
+        let Some(element) = stack.last_mut() else {
+          return Err(parse_error(source, position, "entity reference outside of the root element"));
+        };
+        match reference.resolve_char_ref().map_err(|err| parse_error(source, position, err))? {
+          Some(ch) => push_text(element, ch.encode_utf8(&mut [0; 4])),
+          None => {
+            let name = reference.decode().map_err(decode_error)?;
+            let text = resolve_predefined_entity(&name)
+              .ok_or_else(|| parse_error(source, position, format!("unknown entity reference '&{};'", name)))?;
+            push_text(element, text);
+          }
+        }
+      }
+      Event::CData(text) => match stack.last_mut() {
+        Some(element) => element.children.push(XmlNode::CData(text.decode().map_err(decode_error)?.to_string())),
+        None => return Err(parse_error(source, position, "CDATA section outside of the root element"))
+      },
+      Event::Comment(text) => if let Some(element) = stack.last_mut() {
+        element.children.push(XmlNode::Comment(text.decode().map_err(decode_error)?.to_string()));
+      },
+      Event::PI(text) => if let Some(element) = stack.last_mut() {
+        element.children.push(XmlNode::ProcessingInstruction(String::from_utf8_lossy(&text).to_string()));
+      },
+      Event::Decl(_) | Event::DocType(_) => if !stack.is_empty() {
+        return Err(parse_error(source, position, "declaration inside an element"));
+      },
+      Event::Eof => break
+    }
+  }
+
+  if let Some(element) = stack.last() {
+    return Err(parse_error(source, source.len() as u64, format!("unterminated element '{}'", element.name)));
+  }
+  let root = root.ok_or_else(|| parse_error(source, source.len() as u64, "expected an element"))?;
+  Ok(XmlDocument { prolog: source[..prolog_end].to_string(), root, epilog: source[root_end..].to_string() })
+}
+
+#[cfg(test)]
+mod tests {
+  use expectest::prelude::*;
+
+  use crate::xml::{parse_xml, XPathMatch, XmlElement, XmlNode, MAX_DEPTH};
+
+  const PET: &str = r#"<?xml version="1.0"?>
+<!-- pet store -->
+<pets xmlns:p="urn:pets">
+  <pet id="1" status="available"><name>Fido &amp; Co</name><tag>dog</tag></pet>
+  <pet id="2"><name><![CDATA[Tom <cat>]]></name><tag>cat</tag></pet>
+  <p:owner p:id="3">Jo</p:owner>
+</pets>
+"#;
+
+  #[test]
+  fn parse_and_write_xml() {
+    let document = parse_xml(PET).unwrap();
+    expect!(document.prolog.as_str()).to(be_equal_to("<?xml version=\"1.0\"?>\n<!-- pet store -->\n"));
+    expect!(document.root.name.as_str()).to(be_equal_to("pets"));
+    expect!(document.root.child_elements().count()).to(be_equal_to(3));
+    expect!(document.to_xml_string()).to(be_equal_to(PET));
+
+    let element = XmlElement {
+      name: "a".to_string(),
+      attributes: vec![("b".to_string(), "\"<>".to_string())],
+      children: vec![XmlNode::Text("1 < 2".to_string()), XmlNode::Element(XmlElement { name: "c".to_string(), .. XmlElement::default() })]
+    };
+    let document = crate::xml::XmlDocument { root: element, .. Default::default() };
+    expect!(document.to_xml_string()).to(be_equal_to("<a b=\"&quot;&lt;&gt;\">1 &lt; 2<c/></a>"));
+
+    expect!(parse_xml("<a><b></a>").unwrap_err().to_string())
+      .to(be_equal_to("Invalid XML: ill-formed document: expected `</b>`, but `</a>` was found (line 1)"));
+    expect!(parse_xml("<a/><b/>")).to(be_err());
+    expect!(parse_xml("<a x=1/>")).to(be_err());
+  }
+
+  #[test]
+  fn select_with_xpath() {
+    let document = parse_xml(PET).unwrap();
+    expect!(document.select_string("/pets/pet[1]/name").unwrap()).to(be_some().value("Fido & Co"));
+    expect!(document.select_string("/pets/pet[2]/name/text()").unwrap()).to(be_some().value("Tom <cat>"));
+    expect!(document.select_string("/pets/pet[@id='2']/tag").unwrap()).to(be_some().value("cat"));
+    expect!(document.select_string("/pets/pet[tag='dog']/@status").unwrap()).to(be_some().value("available"));
+    expect!(document.select_string("/pets/pet[last()]/@id").unwrap()).to(be_some().value("2"));
+    expect!(document.select_string("//p:owner/@p:id").unwrap()).to(be_some().value("3"));
+    expect!(document.select_string("count(//pet)").unwrap()).to(be_some().value("2"));
+    expect!(document.select_string("/pets/pet[name='Tom <cat>']/tag").unwrap()).to(be_some().value("cat"));
+    expect!(document.select("//name").unwrap().len()).to(be_equal_to(2));
+    expect!(document.select("/pets/*").unwrap().len()).to(be_equal_to(3));
+    expect!(document.select("/pets/pet[@status]").unwrap().len()).to(be_equal_to(1));
+    expect!(document.select("/pets/pet/@*").unwrap()).to(be_equal_to(vec![
+      XPathMatch::Attribute { name: "id", value: "1" },
+      XPathMatch::Attribute { name: "status", value: "available" },
+      XPathMatch::Attribute { name: "id", value: "2" }
+    ]));
+    expect!(document.select("/other").unwrap().is_empty()).to(be_true());
+    expect!(document.select("pets").unwrap().len()).to(be_equal_to(1));
+    expect!(document.select("//owner").unwrap().is_empty()).to(be_true());
+    expect!(document.select("/pets/pet[")).to(be_err());
+    expect!(document.select("count(//pet)")).to(be_err());
+    expect!(document.matches("count(//pet) = 2").unwrap()).to(be_true());
+    expect!(document.matches("//pet[@id='3']").unwrap()).to(be_false());
+    expect!(document.select("/pets/x:pet").unwrap_err().to_string())
+      .to(be_equal_to("XPath '/pets/x:pet' uses the prefix 'x', which is not declared in the document"));
+    expect!(document.select("/child::pets/pet[@id='a:b']").unwrap().len()).to(be_equal_to(0));
+
+    let document = parse_xml("<pets xmlns='urn:pets'><pet>Tom</pet></pets>").unwrap();
+    expect!(document.select("/pets/pet").unwrap().is_empty()).to(be_true());
+    expect!(document.select_string("/*/*[local-name()='pet']").unwrap()).to(be_some().value("Tom"));
+  }
+
+  #[test]
+  fn set_values_with_xpath() {
+    let mut document = parse_xml("<order><pet id='1'>Fido</pet><qty>1</qty></order>").unwrap();
+    expect!(document.set_value("/order/pet/@id", "10").unwrap()).to(be_equal_to(1));
+    expect!(document.set_value("/order/qty", "5 & more").unwrap()).to(be_equal_to(1));
+    expect!(document.set_value("/order/missing", "x").unwrap()).to(be_equal_to(0));
+    let replacement = XmlElement { name: "pet".to_string(), attributes: vec![], children: vec![XmlNode::Text("Tom".to_string())] };
+    expect!(document.replace_element("//pet", &replacement).unwrap()).to(be_equal_to(1));
+    expect!(document.to_xml_string()).to(be_equal_to("<order><pet>Tom</pet><qty>5 &amp; more</qty></order>"));
+  }
+
+  #[test]
+  fn fails_on_deeply_nested_elements() {
+    let xml = format!("{}{}", "<a>".repeat(50_000), "</a>".repeat(50_000));
+    expect!(parse_xml(&xml).unwrap_err().to_string())
+      .to(be_equal_to("Invalid XML: elements are nested more than 256 levels deep (line 1)"));
+
+    let xml = format!("{}{}", "<a>".repeat(MAX_DEPTH), "</a>".repeat(MAX_DEPTH));
+    expect!(parse_xml(&xml)).to(be_ok());
+  }
+}
@@ -1,16 +1,27 @@
 //! Functions and Traits for loading Arazzo objects from a YAML document
 use std::collections::BTreeMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 use anyhow::anyhow;
 use serde_json::{json, Map, Value};
+#[cfg(feature = "arbitrary_precision")] use serde_json::Number;
 use maplit::hashmap;
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser, Tag};
+use yaml_rust2::scanner::{Marker, TScalarStyle};
 use yaml_rust2::yaml::Hash;
-use yaml_rust2::Yaml;
+use yaml_rust2::{Yaml, YamlLoader};
 
 use crate::either::Either;
-use crate::extensions::{yaml_extract_extensions, AnyValue};
-use crate::payloads::{EmptyPayload, JsonPayload, Payload, StringPayload};
+use crate::extensions::{yaml_extract_extensions, AnyValue, YAML_BINARY_KEY};
+use crate::fields::ObjectFields;
+use crate::payload_registry::{create_payload, PayloadValue};
+use crate::payloads::{
+  string_payload,
+  with_schema_hint,
+  BytesPayload,
+  EmptyPayload,
+  Payload
+};
 use crate::v1_0::{
   ArazzoDescription,
   Components,
@@ -33,39 +44,47 @@ impl TryFrom<&Yaml> for ArazzoDescription {
 
   fn try_from(value: &Yaml) -> Result<Self, Self::Error> {
     if let Some(hash) = value.as_hash() {
-      if let Ok(version) = yaml_hash_require_string(hash, "arazzo") {
-        let info = Info::try_from(hash)?;
-        let source_descriptions = yaml_load_source_descriptions(hash)?;
-        let workflows = yaml_load_workflows(hash)?;
-        let components = Components::try_from(hash)?;
-
-        Ok(ArazzoDescription {
-          arazzo: version,
-          info,
-          source_descriptions,
-          workflows,
-          components,
-          extensions: yaml_extract_extensions(&hash)?
-        })
-      } else {
-        Err(anyhow!("Arazzo version number is required [4.6.1.1 Fixed Fields]"))
-      }
+      yaml_load_description(hash, yaml_load_workflows)
     } else {
       Err(anyhow!("YAML value must be a Hash, got {}", yaml_type_name(value)))
     }
   }
 }
 
+/// Loads the description from the top-level Hash, using the function to load the workflows. This
+/// allows the streaming loader to load the workflows as they are parsed, while still reporting
+/// the same errors in the same order.
+pub(crate) fn yaml_load_description<F>(hash: &Hash, load_workflows: F) -> anyhow::Result<ArazzoDescription>
+  where F: FnOnce(&Hash) -> anyhow::Result<Vec<Workflow>> {
+  if let Ok(version) = yaml_hash_require_string(hash, "arazzo") {
+    let info = Info::try_from(hash)?;
+    let source_descriptions = yaml_load_source_descriptions(hash)?;
+    let workflows = load_workflows(hash)?;
+    let components = Components::try_from(hash)?;
+
+    Ok(ArazzoDescription {
+      arazzo: version,
+      info,
+      source_descriptions,
+      workflows,
+      components,
+      extensions: yaml_extract_extensions(hash)?
+    })
+  } else {
+    Err(anyhow!("Arazzo version number is required [4.6.1.1 Fixed Fields]"))
+  }
+}
+
 impl TryFrom<&Yaml> for SourceDescription {
   type Error = anyhow::Error;
 
   fn try_from(value: &Yaml) -> Result<Self, Self::Error> {
     if let Some(hash) = value.as_hash() {
       Ok(SourceDescription {
-        name: yaml_hash_require_string(&hash, "name")?,
-        url: yaml_hash_require_string(&hash, "url")?,
-        r#type: yaml_hash_lookup_string(&hash, "type"),
-        extensions: yaml_extract_extensions(&hash)?
+        name: yaml_hash_require_string(hash, "name")?,
+        url: yaml_hash_require_string(hash, "url")?,
+        r#type: yaml_hash_lookup_string(hash, "type"),
+        extensions: yaml_extract_extensions(hash)?
       })
     } else {
       Err(anyhow!("YAML value must be a Hash, got {}", yaml_type_name(value)))
@@ -74,13 +93,13 @@ impl TryFrom<&Yaml> for SourceDescription {
 }
 
 fn yaml_load_source_descriptions(hash: &Hash) -> anyhow::Result<Vec<SourceDescription>> {
-  if let Some(array) = yaml_hash_lookup(hash, "sourceDescriptions", |v | v.as_vec().cloned()) {
+  if let Some(array) = yaml_hash_lookup(hash, "sourceDescriptions", |v | v.as_vec()) {
     if array.is_empty() {
       Err(anyhow!("Source Description list must have at least one entry [4.6.1.1 Fixed Fields]"))
     } else {
-      let mut list = vec![];
+      let mut list = Vec::with_capacity(array.len());
 
-      for item in &array {
+      for item in array {
         list.push(SourceDescription::try_from(item)?);
       }
 
@@ -95,13 +114,13 @@ impl TryFrom<&Hash> for Info {
   type Error = anyhow::Error;
 
   fn try_from(value: &Hash) -> Result<Self, Self::Error> {
-    if let Some(hash) = yaml_hash_lookup(value, "info", |v | v.as_hash().cloned()) {
+    if let Some(hash) = yaml_hash_lookup(value, "info", |v | v.as_hash()) {
       Ok(Info {
-        title: yaml_hash_require_string(&hash, "title")?,
-        summary: yaml_hash_lookup_string(&hash, "summary"),
-        description: yaml_hash_lookup_string(&hash, "description"),
-        version: yaml_hash_require_string(&hash, "version")?,
-        extensions: yaml_extract_extensions(&hash)?
+        title: yaml_hash_require_string(hash, "title")?,
+        summary: yaml_hash_lookup_string(hash, "summary"),
+        description: yaml_hash_lookup_string(hash, "description"),
+        version: yaml_hash_require_string(hash, "version")?,
+        extensions: yaml_extract_extensions(hash)?
       })
     } else {
       Err(anyhow!("Info Object is required [4.6.1.1 Fixed Fields]"))
@@ -109,41 +128,56 @@ impl TryFrom<&Hash> for Info {
   }
 }
 
-fn yaml_load_workflows(hash: &Hash) -> anyhow::Result<Vec<Workflow>> {
-  if let Some(array) = yaml_hash_lookup(hash, "workflows", |v | v.as_vec().cloned()) {
+pub(crate) fn yaml_load_workflows(hash: &Hash) -> anyhow::Result<Vec<Workflow>> {
+  if let Some(array) = yaml_hash_lookup(hash, "workflows", |v | v.as_vec()) {
     if array.is_empty() {
-      Err(anyhow!("Workflows list must have at least one entry [4.6.1.1 Fixed Fields]"))
+      Err(yaml_empty_workflows_error())
     } else {
-      let mut list = vec![];
+      let mut list = Vec::with_capacity(array.len());
 
-      for item in &array {
+      for item in array {
         list.push(Workflow::try_from(item)?);
       }
 
       Ok(list)
     }
   } else {
-    Err(anyhow!("Workflow Object is required [4.6.1.1 Fixed Fields]"))
+    match yaml_hash_lookup(hash, "workflows", Some) {
+      Some(value) => Err(anyhow!("Workflows must be an Array, got {} [4.6.1.1 Fixed Fields]", yaml_type_name(value))),
+      None => Err(anyhow!("Workflow Object is required [4.6.1.1 Fixed Fields]"))
+    }
   }
 }
 
+/// Error returned when the workflows list has no entries
+pub(crate) fn yaml_empty_workflows_error() -> anyhow::Error {
+  anyhow!("Workflows list must have at least one entry [4.6.1.1 Fixed Fields]")
+}
+
+/// Fixed fields of a Workflow Object
+const WORKFLOW_FIELDS: [&str; 10] = [
+  "workflowId", "summary", "description", "inputs", "dependsOn", "steps", "successActions",
+  "failureActions", "outputs", "parameters"
+];
+
 impl TryFrom<&Yaml> for Workflow {
   type Error = anyhow::Error;
 
   fn try_from(value: &Yaml) -> Result<Self, Self::Error> {
     if let Some(hash) = value.as_hash() {
+      let fields = yaml_hash_fields(hash, &WORKFLOW_FIELDS);
       Ok(Workflow {
-        workflow_id: yaml_hash_require_string(hash, "workflowId")?,
-        summary: yaml_hash_lookup_string(hash, "summary"),
-        description: yaml_hash_lookup_string(hash, "description"),
-        inputs: yaml_hash_entry_to_json(hash, "inputs")?,
-        depends_on: yaml_hash_lookup_string_list(hash, "dependsOn").unwrap_or_default(),
-        steps: yaml_load_steps(hash)?,
-        success_actions: yaml_load_success_actions(hash)?,
-        failure_actions: yaml_load_failure_actions(hash)?,
-        outputs: yaml_load_outputs(hash),
-        parameters: yaml_load_parameters(hash)?,
-        extensions: yaml_extract_extensions(&hash)?
+        workflow_id: yaml_require_string(fields.get("workflowId"), "workflowId")?,
+        summary: fields.get("summary").and_then(yaml_value_string),
+        description: fields.get("description").and_then(yaml_value_string),
+        inputs: fields.get("inputs").map(yaml_to_json).transpose()?.unwrap_or_default(),
+        depends_on: fields.get("dependsOn").and_then(yaml_value_string_list).unwrap_or_default(),
+        steps: yaml_load_steps(fields.get("steps"))?,
+        success_actions: yaml_load_success_actions(fields.get("successActions"))?,
+        failure_actions: yaml_load_failure_actions(fields.get("failureActions"))?,
+        outputs: yaml_load_outputs(fields.get("outputs")),
+        parameters: yaml_load_parameters(fields.get("parameters"))?,
+        extensions: fields.extensions()?
       })
     } else {
       Err(anyhow!("YAML value must be a Hash, got {}", yaml_type_name(value)))
@@ -151,14 +185,14 @@ impl TryFrom<&Yaml> for Workflow {
   }
 }
 
-fn yaml_load_steps(hash: &Hash) -> anyhow::Result<Vec<Step>> {
-  if let Some(array) = yaml_hash_lookup(hash, "steps", |v | v.as_vec().cloned()) {
+fn yaml_load_steps(steps: Option<&Yaml>) -> anyhow::Result<Vec<Step>> {
+  if let Some(array) = steps.and_then(|steps| steps.as_vec()) {
     if array.is_empty() {
       Err(anyhow!("At lest one Step is required [4.6.4.1 Fixed Fields]"))
     } else {
-      let mut list = vec![];
+      let mut list = Vec::with_capacity(array.len());
 
-      for item in &array {
+      for item in array {
         list.push(Step::try_from(item)?);
       }
 
@@ -169,13 +203,13 @@ fn yaml_load_steps(hash: &Hash) -> anyhow::Result<Vec<Step>> {
   }
 }
 
-fn yaml_load_parameters(hash: &Hash) -> anyhow::Result<Vec<Either<ParameterObject, ReusableObject>>> {
-  if let Some(array) = yaml_hash_lookup(hash, "parameters", |v | v.as_vec().cloned()) {
-    let mut list = vec![];
+fn yaml_load_parameters(parameters: Option<&Yaml>) -> anyhow::Result<Vec<Either<ParameterObject, ReusableObject>>> {
+  if let Some(array) = parameters.and_then(|parameters| parameters.as_vec()) {
+    let mut list = Vec::with_capacity(array.len());
 
-    for item in &array {
+    for item in array {
       if let Some(hash) = item.as_hash() {
-        if hash.contains_key(&Yaml::String("reference".to_string())) {
+        if yaml_hash_has_reference(hash) {
           list.push(Either::Second(ReusableObject::try_from(hash)?));
         } else {
           list.push(Either::First(ParameterObject::try_from(hash)?));
@@ -189,13 +223,13 @@ fn yaml_load_parameters(hash: &Hash) -> anyhow::Result<Vec<Either<ParameterObjec
   }
 }
 
-fn yaml_load_success_actions(hash: &Hash) -> anyhow::Result<Vec<Either<SuccessObject, ReusableObject>>> {
-  if let Some(array) = yaml_hash_lookup(hash, "successActions", |v | v.as_vec().cloned()) {
-    let mut list = vec![];
+fn yaml_load_success_actions(actions: Option<&Yaml>) -> anyhow::Result<Vec<Either<SuccessObject, ReusableObject>>> {
+  if let Some(array) = actions.and_then(|actions| actions.as_vec()) {
+    let mut list = Vec::with_capacity(array.len());
 
-    for item in &array {
+    for item in array {
       if let Some(hash) = item.as_hash() {
-        if hash.contains_key(&Yaml::String("reference".to_string())) {
+        if yaml_hash_has_reference(hash) {
           list.push(Either::Second(ReusableObject::try_from(hash)?));
         } else {
           list.push(Either::First(SuccessObject::try_from(hash)?));
@@ -209,13 +243,13 @@ fn yaml_load_success_actions(hash: &Hash) -> anyhow::Result<Vec<Either<SuccessOb
   }
 }
 
-fn yaml_load_failure_actions(hash: &Hash) -> anyhow::Result<Vec<Either<FailureObject, ReusableObject>>> {
-  if let Some(array) = yaml_hash_lookup(hash, "failureActions", |v | v.as_vec().cloned()) {
-    let mut list = vec![];
+fn yaml_load_failure_actions(actions: Option<&Yaml>) -> anyhow::Result<Vec<Either<FailureObject, ReusableObject>>> {
+  if let Some(array) = actions.and_then(|actions| actions.as_vec()) {
+    let mut list = Vec::with_capacity(array.len());
 
-    for item in &array {
+    for item in array {
       if let Some(hash) = item.as_hash() {
-        if hash.contains_key(&Yaml::String("reference".to_string())) {
+        if yaml_hash_has_reference(hash) {
           list.push(Either::Second(ReusableObject::try_from(hash)?));
         } else {
           list.push(Either::First(FailureObject::try_from(hash)?));
@@ -229,42 +263,51 @@ fn yaml_load_failure_actions(hash: &Hash) -> anyhow::Result<Vec<Either<FailureOb
   }
 }
 
-fn yaml_load_outputs(hash: &Hash) -> BTreeMap<String, String> {
-  yaml_hash_lookup(hash, "outputs", |v | {
-    if let Some(outputs_hash) = v.as_hash() {
-      Some(outputs_hash.iter()
-        .filter_map(|(k, v)| {
-          if let Some(key) = k.as_str() {
-            v.as_str().map(|value| (key.to_string(), value.to_string()))
-          } else {
-            None
-          }
-        }).collect())
-    } else {
-      None
-    }
+/// If the hash has a `reference` key (i.e. is a Reusable Object)
+fn yaml_hash_has_reference(hash: &Hash) -> bool {
+  hash.keys().any(|key| key.as_str() == Some("reference"))
+}
+
+fn yaml_load_outputs(outputs: Option<&Yaml>) -> BTreeMap<String, String> {
+  outputs.and_then(|v | {
+    v.as_hash().map(|outputs_hash| outputs_hash.iter()
+      .filter_map(|(k, v)| {
+        if let Some(key) = k.as_str() {
+          v.as_str().map(|value| (key.to_string(), value.to_string()))
+        } else {
+          None
+        }
+      }).collect())
   }).unwrap_or_default()
 }
 
+/// Fixed fields of a Step Object
+const STEP_FIELDS: [&str; 11] = [
+  "stepId", "operationId", "operationPath", "workflowId", "description", "parameters",
+  "requestBody", "onSuccess", "successCriteria", "onFailure", "outputs"
+];
+
 impl TryFrom<&Yaml> for Step {
   type Error = anyhow::Error;
 
   fn try_from(value: &Yaml) -> Result<Self, Self::Error> {
     if let Some(hash) = value.as_hash() {
+      let fields = yaml_hash_fields(hash, &STEP_FIELDS);
       Ok(Step {
-        step_id: yaml_hash_require_string(&hash, "stepId")?,
-        operation_id: yaml_hash_lookup_string(&hash, "operationId"),
-        operation_path: yaml_hash_lookup_string(&hash, "operationPath"),
-        workflow_id: yaml_hash_lookup_string(&hash, "workflowId"),
-        description: yaml_hash_lookup_string(&hash, "description"),
-        parameters: yaml_load_parameters(hash)?,
-        request_body: yaml_hash_lookup(hash, "requestBody", |v| Some(RequestBody::try_from(v)))
+        step_id: yaml_require_string(fields.get("stepId"), "stepId")?,
+        operation_id: fields.get("operationId").and_then(yaml_value_string),
+        operation_path: fields.get("operationPath").and_then(yaml_value_string),
+        workflow_id: fields.get("workflowId").and_then(yaml_value_string),
+        description: fields.get("description").and_then(yaml_value_string),
+        parameters: yaml_load_parameters(fields.get("parameters"))?,
+        request_body: fields.get("requestBody")
+          .map(RequestBody::try_from)
           .transpose()?,
-        on_success: yaml_load_success_actions(hash)?,
-        success_criteria: yaml_load_success_criteria(hash)?,
-        on_failure: yaml_load_failure_actions(hash)?,
-        outputs: yaml_load_outputs(hash),
-        extensions: yaml_extract_extensions(&hash)?
+        on_success: yaml_load_success_actions(fields.get("onSuccess"))?,
+        success_criteria: yaml_load_criteria(fields.get("successCriteria"))?,
+        on_failure: yaml_load_failure_actions(fields.get("onFailure"))?,
+        outputs: yaml_load_outputs(fields.get("outputs")),
+        extensions: fields.extensions()?
       })
     } else {
       Err(anyhow!("YAML value must be a Hash, got {}", yaml_type_name(value)))
@@ -272,35 +315,25 @@ impl TryFrom<&Yaml> for Step {
   }
 }
 
-fn yaml_load_success_criteria(hash: &Hash) -> anyhow::Result<Vec<Criterion>> {
-  if let Some(criteria) = yaml_hash_lookup(hash, "successCriteria", |v | v.as_vec().cloned()) {
-    let mut result = vec![];
-
-    for value in &criteria {
-      result.push(Criterion::try_from(value)?);
-    }
-
-    Ok(result)
-  } else {
-    Ok(vec![])
-  }
-}
+/// Fixed fields of a Parameter Object
+const PARAMETER_FIELDS: [&str; 3] = ["name", "in", "value"];
 
 impl TryFrom<&Hash> for ParameterObject {
   type Error = anyhow::Error;
 
   fn try_from(value: &Hash) -> Result<Self, Self::Error> {
+    let fields = yaml_hash_fields(value, &PARAMETER_FIELDS);
     Ok(ParameterObject {
-      name: yaml_hash_require_string(value, "name")?,
-      r#in: yaml_hash_lookup_string(value, "in"),
-      value: yaml_load_any_or_expression(value, "value")?,
-      extensions: yaml_extract_extensions(value)?
+      name: yaml_require_string(fields.get("name"), "name")?,
+      r#in: fields.get("in").and_then(yaml_value_string),
+      value: yaml_load_any_or_expression(fields.get("value"))?,
+      extensions: fields.extensions()?
     })
   }
 }
 
-fn yaml_load_any_or_expression(hash: &Hash, key: &str) -> anyhow::Result<Either<AnyValue, String>> {
-  yaml_hash_lookup(hash, key, |v | {
+fn yaml_load_any_or_expression(value: Option<&Yaml>) -> anyhow::Result<Either<AnyValue, String>> {
+  value.and_then(|v | {
     if let Some(s) = v.as_str() {
       if s.starts_with('$') {
         Some(Either::Second(s.to_string()))
@@ -315,18 +348,21 @@ fn yaml_load_any_or_expression(hash: &Hash, key: &str) -> anyhow::Result<Either<
   }).ok_or_else(|| anyhow!("Parameter value is required [4.6.6.1 Fixed Fields]"))
 }
 
+/// Fixed fields of Success and Failure Action Objects
+const ACTION_FIELDS: [&str; 7] = ["name", "type", "workflowId", "stepId", "retryAfter", "retryLimit", "criteria"];
 
 impl TryFrom<&Hash> for SuccessObject {
   type Error = anyhow::Error;
 
   fn try_from(value: &Hash) -> Result<Self, Self::Error> {
+    let fields = yaml_hash_fields(value, &ACTION_FIELDS);
     Ok(SuccessObject {
-      name: yaml_hash_require_string(value, "name")?,
-      r#type: yaml_hash_require_string(value, "type")?,
-      workflow_id: yaml_hash_lookup_string(value, "workflowId"),
-      step_id: yaml_hash_lookup_string(value, "stepId"),
-      criteria: yaml_load_criteria(value, "criteria")?,
-      extensions: yaml_extract_extensions(value)?
+      name: yaml_require_string(fields.get("name"), "name")?,
+      r#type: yaml_require_string(fields.get("type"), "type")?,
+      workflow_id: fields.get("workflowId").and_then(yaml_value_string),
+      step_id: fields.get("stepId").and_then(yaml_value_string),
+      criteria: yaml_load_criteria(fields.get("criteria"))?,
+      extensions: fields.extensions()?
     })
   }
 }
@@ -335,15 +371,16 @@ impl TryFrom<&Hash> for FailureObject {
   type Error = anyhow::Error;
 
   fn try_from(value: &Hash) -> Result<Self, Self::Error> {
+    let fields = yaml_hash_fields(value, &ACTION_FIELDS);
     Ok(FailureObject {
-      name: yaml_hash_require_string(value, "name")?,
-      r#type: yaml_hash_require_string(value, "type")?,
-      workflow_id: yaml_hash_lookup_string(value, "workflowId"),
-      step_id: yaml_hash_lookup_string(value, "stepId"),
-      retry_after: yaml_hash_lookup_number(value, "retryAfter"),
-      retry_limit: yaml_hash_lookup_integer(value, "retryLimit"),
-      criteria: yaml_load_criteria(value, "criteria")?,
-      extensions: yaml_extract_extensions(value)?
+      name: yaml_require_string(fields.get("name"), "name")?,
+      r#type: yaml_require_string(fields.get("type"), "type")?,
+      workflow_id: fields.get("workflowId").and_then(yaml_value_string),
+      step_id: fields.get("stepId").and_then(yaml_value_string),
+      retry_after: fields.get("retryAfter").and_then(yaml_value_number),
+      retry_limit: fields.get("retryLimit").and_then(yaml_value_integer),
+      criteria: yaml_load_criteria(fields.get("criteria"))?,
+      extensions: fields.extensions()?
     })
   }
 }
@@ -352,10 +389,10 @@ impl TryFrom<&Hash> for Components {
   type Error = anyhow::Error;
 
   fn try_from(value: &Hash) -> Result<Self, Self::Error> {
-    if let Some(hash) = yaml_hash_lookup(value, "components", |v | v.as_hash().cloned()) {
+    if let Some(hash) = yaml_hash_lookup(value, "components", |v | v.as_hash()) {
       let mut inputs = hashmap!{};
-      if let Some(inputs_hash) = yaml_hash_lookup(&hash, "inputs", |v | v.as_hash().cloned()) {
-        for (key, value) in &inputs_hash {
+      if let Some(inputs_hash) = yaml_hash_lookup(hash, "inputs", |v | v.as_hash()) {
+        for (key, value) in inputs_hash {
           if let Some(key) = key.as_str() {
             inputs.insert(key.to_string(), yaml_to_json(value)?);
           } else {
@@ -365,8 +402,8 @@ impl TryFrom<&Hash> for Components {
       }
 
       let mut parameters = hashmap!{};
-      if let Some(parameters_hash) = yaml_hash_lookup(&hash, "parameters", |v | v.as_hash().cloned()) {
-        for (key, value) in &parameters_hash {
+      if let Some(parameters_hash) = yaml_hash_lookup(hash, "parameters", |v | v.as_hash()) {
+        for (key, value) in parameters_hash {
           if let Some(key) = key.as_str() {
             if let Some(parameter_hash) = value.as_hash() {
               parameters.insert(key.to_string(), ParameterObject::try_from(parameter_hash)?);
@@ -378,8 +415,8 @@ impl TryFrom<&Hash> for Components {
       }
 
       let mut success_actions = hashmap!{};
-      if let Some(success_hash) = yaml_hash_lookup(&hash, "successActions", |v | v.as_hash().cloned()) {
-        for (key, value) in &success_hash {
+      if let Some(success_hash) = yaml_hash_lookup(hash, "successActions", |v | v.as_hash()) {
+        for (key, value) in success_hash {
           if let Some(key) = key.as_str() {
             if let Some(hash) = value.as_hash() {
               success_actions.insert(key.to_string(), SuccessObject::try_from(hash)?);
@@ -391,8 +428,8 @@ impl TryFrom<&Hash> for Components {
       }
 
       let mut failure_actions = hashmap!{};
-      if let Some(failure_hash) = yaml_hash_lookup(&hash, "failureActions", |v | v.as_hash().cloned()) {
-        for (key, value) in &failure_hash {
+      if let Some(failure_hash) = yaml_hash_lookup(hash, "failureActions", |v | v.as_hash()) {
+        for (key, value) in failure_hash {
           if let Some(key) = key.as_str() {
             if let Some(hash) = value.as_hash() {
               failure_actions.insert(key.to_string(), FailureObject::try_from(hash)?);
@@ -408,7 +445,7 @@ impl TryFrom<&Hash> for Components {
         parameters,
         success_actions,
         failure_actions,
-        extensions: yaml_extract_extensions(&hash)?
+        extensions: yaml_extract_extensions(hash)?
       })
     } else {
       Ok(Components::default())
@@ -431,16 +468,20 @@ impl TryFrom<&Hash> for ReusableObject {
   }
 }
 
+/// Fixed fields of a Criterion Object
+const CRITERION_FIELDS: [&str; 3] = ["context", "condition", "type"];
+
 impl TryFrom<&Yaml> for Criterion {
   type Error = anyhow::Error;
 
   fn try_from(value: &Yaml) -> Result<Self, Self::Error> {
     if let Some(hash) = value.as_hash() {
+      let fields = yaml_hash_fields(hash, &CRITERION_FIELDS);
       Ok(Criterion {
-        context: yaml_hash_lookup_string(hash, "context"),
-        condition: yaml_hash_require_string(hash, "condition")?,
-        r#type: yaml_load_criterion_expression_type(hash)?,
-        extensions: yaml_extract_extensions(hash)?
+        context: fields.get("context").and_then(yaml_value_string),
+        condition: yaml_require_string(fields.get("condition"), "condition")?,
+        r#type: yaml_load_criterion_expression_type(fields.get("type"))?,
+        extensions: fields.extensions()?
       })
     } else {
       Err(anyhow!("YAML value must be a Hash, got {}", yaml_type_name(value)))
@@ -448,16 +489,18 @@ impl TryFrom<&Yaml> for Criterion {
   }
 }
 
-fn yaml_load_criteria(hash: &Hash, key: &str) -> anyhow::Result<Vec<Criterion>> {
-  let array = yaml_hash_lookup(hash, key, |value | value.as_vec().cloned())
-    .unwrap_or_default();
-  let mut criterion = vec![];
+fn yaml_load_criteria(criteria: Option<&Yaml>) -> anyhow::Result<Vec<Criterion>> {
+  if let Some(array) = criteria.and_then(|criteria| criteria.as_vec()) {
+    let mut criterion = Vec::with_capacity(array.len());
 
-  for item in &array {
-    criterion.push(Criterion::try_from(item)?);
-  }
+    for item in array {
+      criterion.push(Criterion::try_from(item)?);
+    }
 
-  Ok(criterion)
+    Ok(criterion)
+  } else {
+    Ok(vec![])
+  }
 }
 
 impl TryFrom<&Yaml> for CriterionExpressionType {
@@ -476,12 +519,12 @@ impl TryFrom<&Yaml> for CriterionExpressionType {
   }
 }
 
-fn yaml_load_criterion_expression_type(hash: &Hash) -> anyhow::Result<Option<Either<String, CriterionExpressionType>>> {
-  yaml_hash_lookup(hash, "type", |value | {
+fn yaml_load_criterion_expression_type(value: Option<&Yaml>) -> anyhow::Result<Option<Either<String, CriterionExpressionType>>> {
+  value.map(|value | {
     if let Some(s) = value.as_str() {
-      Some(Ok(Either::First(s.to_string())))
+      Ok(Either::First(s.to_string()))
     } else {
-      Some(CriterionExpressionType::try_from(value).map(Either::Second))
+      CriterionExpressionType::try_from(value).map(Either::Second)
     }
   }).transpose()
 }
@@ -494,11 +537,12 @@ impl TryFrom<&Yaml> for RequestBody {
       let content_type = yaml_hash_lookup_string(hash, "contentType");
       let payload = yaml_load_payload(hash, "payload", content_type.as_ref())?;
       let replacements = yaml_load_replacements(hash, "replacements")?;
+      let extensions = yaml_extract_extensions(hash)?;
       Ok(RequestBody {
+        payload: payload.map(|payload| with_schema_hint(payload, content_type.as_deref(), &extensions)),
         content_type,
-        payload,
         replacements,
-        extensions: yaml_extract_extensions(&hash)?
+        extensions
       })
     } else {
       Err(anyhow!("YAML value must be a Hash, got {}", yaml_type_name(value)))
@@ -509,20 +553,19 @@ impl TryFrom<&Yaml> for RequestBody {
 fn yaml_load_payload(
   hash: &Hash,
   key: &str,
-  _content_type: Option<&String>
-) -> anyhow::Result<Option<Rc<dyn Payload + Send + Sync>>> {
+  content_type: Option<&String>
+) -> anyhow::Result<Option<Arc<dyn Payload + Send + Sync>>> {
   yaml_hash_lookup(hash, key, |value| {
     match value {
-      Yaml::String(s) => {
-        let payload: Rc<dyn Payload + Send + Sync> = Rc::new(StringPayload(s.clone()));
-        Some(Ok(payload))
-      },
-      Yaml::Null => Some(Ok(Rc::new(EmptyPayload))),
-      _ => Some(yaml_to_json(value)
-        .map(|json| {
-          let payload: Rc<dyn Payload + Send + Sync> = Rc::new(JsonPayload(json));
+      Yaml::String(s) => Some(Ok(string_payload(s, content_type.map(|ct| ct.as_str())))),
+      Yaml::Null => Some(Ok(Arc::new(EmptyPayload))),
+      Yaml::Hash(h) if h.len() == 1 && h.contains_key(&Yaml::String(YAML_BINARY_KEY.to_string())) => {
+        Some(AnyValue::try_from(value).map(|value| {
+          let payload: Arc<dyn Payload + Send + Sync> = Arc::new(BytesPayload(value.as_bytes().cloned().unwrap_or_default()));
           payload
         }))
+      }
+      _ => Some(Ok(create_payload(&PayloadValue::Yaml(value), content_type.map(|ct| ct.as_str()))))
     }
   }).transpose()
 }
@@ -531,15 +574,17 @@ fn yaml_load_replacements(
   hash: &Hash,
   key: &str
 ) -> anyhow::Result<Vec<PayloadReplacement>> {
-  let array = yaml_hash_lookup(hash, key, |value | value.as_vec().cloned())
-    .unwrap_or_default();
-  let mut replacements = vec![];
+  if let Some(array) = yaml_hash_lookup(hash, key, |value | value.as_vec()) {
+    let mut replacements = Vec::with_capacity(array.len());
 
-  for item in &array {
-    replacements.push(PayloadReplacement::try_from(item)?);
-  }
+    for item in array {
+      replacements.push(PayloadReplacement::try_from(item)?);
+    }
 
-  Ok(replacements)
+    Ok(replacements)
+  } else {
+    Ok(vec![])
+  }
 }
 
 impl TryFrom<&Yaml> for PayloadReplacement {
@@ -549,7 +594,7 @@ impl TryFrom<&Yaml> for PayloadReplacement {
     if let Some(hash) = value.as_hash() {
       Ok(PayloadReplacement {
         target: yaml_hash_require_string(hash, "target")?,
-        value: yaml_load_any_or_expression(hash, "value")?,
+        value: yaml_load_any_or_expression(yaml_hash_lookup(hash, "value", Some))?,
         extensions: yaml_extract_extensions(hash)?
       })
     } else {
@@ -558,6 +603,53 @@ impl TryFrom<&Yaml> for PayloadReplacement {
   }
 }
 
+/// If the tag of a scalar value is the `!!binary` tag
+pub(crate) fn yaml_is_binary_tag(tag: &Option<Tag>) -> bool {
+  tag.as_ref()
+    .map(|tag| (tag.handle == "tag:yaml.org,2002:" || tag.handle == "!!") && tag.suffix == "binary")
+    .unwrap_or_default()
+}
+
+/// Event receiver that passes events through to the YAML loader, but converts scalar values
+/// tagged with `!!binary` into a Hash with a [`YAML_BINARY_KEY`] key.
+#[derive(Default)]
+struct BinaryTagReceiver {
+  loader: YamlLoader,
+  documents: usize
+}
+
+impl MarkedEventReceiver for BinaryTagReceiver {
+  fn on_event(&mut self, event: Event, mark: Marker) {
+    match event {
+      Event::Scalar(value, _, anchor, tag) if yaml_is_binary_tag(&tag) => {
+        self.loader.on_event(Event::MappingStart(anchor, None), mark);
+        self.loader.on_event(Event::Scalar(YAML_BINARY_KEY.to_string(), TScalarStyle::DoubleQuoted, 0, None), mark);
+        self.loader.on_event(Event::Scalar(value, TScalarStyle::DoubleQuoted, 0, None), mark);
+        self.loader.on_event(Event::MappingEnd, mark);
+      }
+      Event::DocumentEnd => {
+        self.documents += 1;
+        self.loader.on_event(event, mark);
+      }
+      _ => self.loader.on_event(event, mark)
+    }
+  }
+}
+
+/// Loads all the YAML documents from the source. This is the same as `YamlLoader::load_from_str`,
+/// except that any `!!binary` values are loaded as a Hash with a single `!!binary` key (which
+/// [`AnyValue`] will decode as Binary data), instead of as a plain String.
+pub fn yaml_load_documents(source: &str) -> anyhow::Result<Vec<Yaml>> {
+  let mut receiver = BinaryTagReceiver::default();
+  Parser::new(source.chars()).load(&mut receiver, true)?;
+  let documents = receiver.loader.documents();
+  if documents.len() == receiver.documents {
+    Ok(documents.to_vec())
+  } else {
+    Err(anyhow!("Failed to load the YAML documents"))
+  }
+}
+
 /// Returns the type name of the YAML value
 pub fn yaml_type_name(yaml: &Yaml) -> String {
   match yaml {
@@ -573,54 +665,72 @@ pub fn yaml_type_name(yaml: &Yaml) -> String {
   }.to_string()
 }
 
+/// Classifies the entries of the YAML Hash in a single pass (see [`ObjectFields`]). Entries
+/// that do not have a String key are ignored.
+fn yaml_hash_fields<'a, const N: usize>(
+  hash: &'a Hash,
+  keys: &'static [&'static str; N]
+) -> ObjectFields<'a, Yaml, N> {
+  ObjectFields::classify(keys, hash.iter().filter_map(|(key, value)| key.as_str().map(|key| (key, value))))
+}
+
 /// Looks up a String value with the given String key in a YAML Hash. If the value is easily
 /// convertable to a String (is a Number or Boolean), `to_string()` will be called on it.
 pub fn yaml_hash_lookup_string(hash: &Hash, key: &str) -> Option<String> {
-  if let Some(value) = hash.get(&Yaml::String(key.to_string())) {
-    match value {
-      Yaml::Real(s) => Some(s.clone()),
-      Yaml::Integer(i) => Some(i.to_string()),
-      Yaml::String(s) => Some(s.clone()),
-      Yaml::Boolean(b) => Some(b.to_string()),
-      _ => None
-    }
-  } else {
-    None
+  hash.get(&Yaml::String(key.to_string())).and_then(yaml_value_string)
+}
+
+/// Converts the YAML value to a String, if it is a String, Number or Boolean
+fn yaml_value_string(value: &Yaml) -> Option<String> {
+  match value {
+    Yaml::Real(s) => Some(s.clone()),
+    Yaml::Integer(i) => Some(i.to_string()),
+    Yaml::String(s) => Some(s.clone()),
+    Yaml::Boolean(b) => Some(b.to_string()),
+    _ => None
   }
 }
 
 /// Looks up a numeric value with the given String key in a YAML Hash. If the value is an integer
 /// it will be converted to a double.
 pub fn yaml_hash_lookup_number(hash: &Hash, key: &str) -> Option<f64> {
-  if let Some(value) = hash.get(&Yaml::String(key.to_string())) {
-    match value {
-      Yaml::Real(f) => f.parse::<f64>().ok(),
-      Yaml::Integer(i) => Some(*i as f64),
-      _ => None
-    }
-  } else {
-    None
+  hash.get(&Yaml::String(key.to_string())).and_then(yaml_value_number)
+}
+
+/// Converts the YAML value to a double, if it is a Real or Integer
+fn yaml_value_number(value: &Yaml) -> Option<f64> {
+  match value {
+    Yaml::Real(f) => f.parse::<f64>().ok(),
+    Yaml::Integer(i) => Some(*i as f64),
+    _ => None
   }
 }
 
 /// Looks up an integer value with the given String key in a YAML Hash. If the value is a float
 /// it will be converted to an integer.
 pub fn yaml_hash_lookup_integer(hash: &Hash, key: &str) -> Option<i64> {
-  if let Some(value) = hash.get(&Yaml::String(key.to_string())) {
-    match value {
-      Yaml::Real(f) => f.parse::<f64>().ok().map(|f| f as i64),
-      Yaml::Integer(i) => Some(*i),
-      _ => None
-    }
-  } else {
-    None
+  hash.get(&Yaml::String(key.to_string())).and_then(yaml_value_integer)
+}
+
+/// Converts the YAML value to an integer, if it is a Real or Integer
+fn yaml_value_integer(value: &Yaml) -> Option<i64> {
+  match value {
+    Yaml::Real(f) => f.parse::<f64>().ok().map(|f| f as i64),
+    Yaml::Integer(i) => Some(*i),
+    _ => None
   }
 }
 
 /// Looks up a required String value with the given String key in a YAML Hash. If the key does
 /// not exist, or the resulting value is not a String, an Error is returned.
 pub fn yaml_hash_require_string(hash: &Hash, key: &str) -> anyhow::Result<String> {
-  if let Some(value) = hash.get(&Yaml::String(key.to_string())) {
+  yaml_require_string(hash.get(&Yaml::String(key.to_string())), key)
+}
+
+/// Returns the value of a required field with the given key as a String. If there is no value,
+/// or the value is not a String, an Error is returned.
+fn yaml_require_string(value: Option<&Yaml>, key: &str) -> anyhow::Result<String> {
+  if let Some(value) = value {
     if let Some(value) = value.as_str() {
       Ok(value.to_string())
     } else {
@@ -632,11 +742,11 @@ pub fn yaml_hash_require_string(hash: &Hash, key: &str) -> anyhow::Result<String
 }
 
 /// Looks up a String key in the given hash, calling the provided callback if it is found.
-pub fn yaml_hash_lookup<F, U>(
-  hash: &Hash,
+pub fn yaml_hash_lookup<'a, F, U>(
+  hash: &'a Hash,
   key: &str,
   callback: F
-) -> Option<U> where F: FnOnce(&Yaml) -> Option<U> {
+) -> Option<U> where F: FnOnce(&'a Yaml) -> Option<U> {
   if let Some(value) = hash.get(&Yaml::String(key.to_string())) {
     callback(value)
   } else {
@@ -648,23 +758,13 @@ pub fn yaml_hash_lookup<F, U>(
 /// is easily convertable to a String (is a Number or Boolean), `to_string()` will be called on it.
 /// All other values are ignored.
 pub fn yaml_hash_lookup_string_list(hash: &Hash, key: &str) -> Option<Vec<String>> {
-  if let Some(value) = hash.get(&Yaml::String(key.to_string())) {
-    if let Some(array) = value.as_vec() {
-      Some(array.iter().flat_map(|value| {
-        match value {
-          Yaml::Real(s) => Some(s.clone()),
-          Yaml::Integer(i) => Some(i.to_string()),
-          Yaml::String(s) => Some(s.clone()),
-          Yaml::Boolean(b) => Some(b.to_string()),
-          _ => None
-        }
-      }).collect())
-    } else {
-      None
-    }
-  } else {
-    None
-  }
+  hash.get(&Yaml::String(key.to_string())).and_then(yaml_value_string_list)
+}
+
+/// Converts a YAML Array to a list of Strings, ignoring any values that can not be converted
+fn yaml_value_string_list(value: &Yaml) -> Option<Vec<String>> {
+  value.as_vec()
+    .map(|array| array.iter().filter_map(yaml_value_string).collect())
 }
 
 /// Looks up the entry in the hash and converts it to JSON. If there is no entry with that key,
@@ -683,6 +783,8 @@ pub fn yaml_to_json(yaml: &Yaml) -> anyhow::Result<Value> {
     Yaml::Null => Ok(Value::Null),
     Yaml::Boolean(b) => Ok(Value::Bool(*b)),
     Yaml::Integer(i) => Ok(json!(*i)),
+    #[cfg(feature = "arbitrary_precision")]
+    Yaml::Real(f) if f.parse::<Number>().is_ok() => Ok(Value::Number(f.parse::<Number>()?)),
     Yaml::Real(f) => f.parse::<f64>()
       .map(|f| json!(f))
       .map_err(|err| anyhow!(err)),
@@ -719,16 +821,15 @@ mod tests {
   use maplit::{btreemap, hashmap};
   use pretty_assertions::assert_eq;
   use serde_json::{json, Value};
-  use std::any::Any;
   use trim_margin::MarginTrimmable;
   use yaml_rust2::yaml::Hash;
   use yaml_rust2::{Yaml, YamlLoader};
 
   use crate::either::Either;
   use crate::extensions::AnyValue;
-  use crate::payloads::{JsonPayload, StringPayload};
+  use crate::payloads::{BytesPayload, FormPayload, Payload, StringPayload, YamlPayload};
   use crate::v1_0::*;
-  use crate::yaml::yaml_to_json;
+  use crate::yaml::{yaml_load_documents, yaml_to_json};
 
   #[test]
   fn yaml_to_json_test() {
@@ -748,6 +849,15 @@ mod tests {
     expect!(yaml_to_json(&array)).to(be_ok().value(json!([ null, false, 100 ])));
   }
 
+  #[test]
+  #[cfg(feature = "arbitrary_precision")]
+  fn yaml_to_json_keeps_the_precision_of_numbers() {
+    let json = yaml_to_json(&Yaml::Real("3.14159265358979323846".to_string())).unwrap();
+    expect!(json.to_string()).to(be_equal_to("3.14159265358979323846"));
+    let json = yaml_to_json(&Yaml::Real("123456789012345678901234567890".to_string())).unwrap();
+    expect!(json.to_string()).to(be_equal_to("123456789012345678901234567890"));
+  }
+
   #[test]
   fn fails_to_load_if_the_main_value_is_not_a_yaml_hash() {
     expect!(ArazzoDescription::try_from(&Yaml::String("test".to_string()))).to(be_err());
@@ -1078,8 +1188,8 @@ mod tests {
     expect!(&failure.r#type).to(be_equal_to("end"));
     expect!(failure.workflow_id.clone()).to(be_some().value("workflowId"));
     expect!(failure.step_id.clone()).to(be_some().value("stepId"));
-    expect!(failure.retry_after.clone()).to(be_some().value(10.5));
-    expect!(failure.retry_limit.clone()).to(be_some().value(10));
+    expect!(failure.retry_after).to(be_some().value(10.5));
+    expect!(failure.retry_limit).to(be_some().value(10));
 
     let mut hash = Hash::new();
     hash.insert(Yaml::String("name".to_string()), Yaml::String("test".to_string()));
@@ -1090,8 +1200,8 @@ mod tests {
     expect!(&failure.r#type).to(be_equal_to("end"));
     expect!(failure.workflow_id.clone()).to(be_none());
     expect!(failure.step_id.clone()).to(be_none());
-    expect!(failure.retry_after.clone()).to(be_none());
-    expect!(failure.retry_limit.clone()).to(be_none());
+    expect!(failure.retry_after).to(be_none());
+    expect!(failure.retry_limit).to(be_none());
   }
 
   #[test]
@@ -1243,6 +1353,17 @@ mod tests {
     }));
   }
 
+  #[test]
+  fn load_binary_payload() {
+    let yaml = YamlLoader::load_from_str("contentType: application/x-protobuf\npayload: CgNQZXQ=").unwrap();
+    let body = RequestBody::try_from(&yaml[0]).unwrap();
+    expect!(body.payload_as::<BytesPayload>().unwrap().0.to_vec()).to(be_equal_to(b"\n\x03Pet".to_vec()));
+
+    let yaml = yaml_load_documents("payload: !!binary CgNQZXQ=").unwrap();
+    let body = RequestBody::try_from(&yaml[0]).unwrap();
+    expect!(body.payload_as::<BytesPayload>().unwrap().0.to_vec()).to(be_equal_to(b"\n\x03Pet".to_vec()));
+  }
+
   #[test]
   fn load_payload() {
     let body = r#"
@@ -1261,9 +1382,8 @@ mod tests {
     let yaml = YamlLoader::load_from_str(body).unwrap();
 
     let body = RequestBody::try_from(&yaml[0]).unwrap();
-    expect!(body.content_type).to(be_some().value("application/json"));
-    let payload: &dyn Any = body.payload.as_ref().unwrap().as_ref();
-    let p = payload.downcast_ref::<StringPayload>().unwrap();
+    expect!(body.content_type.clone()).to(be_some().value("application/json"));
+    let p = body.payload_as::<StringPayload>().unwrap();
     assert_eq!(
       r#" |{
           |  "petOrder": {
@@ -1289,9 +1409,9 @@ mod tests {
     let yaml = YamlLoader::load_from_str(body).unwrap();
 
     let body = RequestBody::try_from(&yaml[0]).unwrap();
-    expect!(body.content_type).to(be_some().value("application/json"));
-    let payload: &dyn Any = body.payload.as_ref().unwrap().as_ref();
-    let p = payload.downcast_ref::<JsonPayload>().unwrap();
+    expect!(body.content_type.clone()).to(be_some().value("application/json"));
+    let p = body.payload_as::<YamlPayload>().unwrap();
+    expect!(p.as_string()).to(be_equal_to("petOrder:\n  petId: $inputs.pet_id\n  couponCode: $inputs.coupon_code\n  quantity: $inputs.quantity\n  status: placed\n  complete: false"));
     assert_eq!(
       &json!({
        "petOrder": {
@@ -1302,7 +1422,7 @@ mod tests {
           "complete": false
         }
       }),
-      &p.0
+      &p.as_json().unwrap()
     );
 
     let body = r#"
@@ -1318,9 +1438,11 @@ mod tests {
     let yaml = YamlLoader::load_from_str(body).unwrap();
 
     let body = RequestBody::try_from(&yaml[0]).unwrap();
-    expect!(body.content_type).to(be_some().value("application/x-www-form-urlencoded"));
-    let payload: &dyn Any = body.payload.as_ref().unwrap().as_ref();
-    let p = payload.downcast_ref::<JsonPayload>().unwrap();
+    expect!(body.content_type.clone()).to(be_some().value("application/x-www-form-urlencoded"));
+    let p = body.payload_as::<FormPayload>().unwrap();
+    expect!(p.as_string()).to(be_equal_to("client_id=%24inputs.clientId&grant_type=%24inputs.grantType&\
+      redirect_uri=%24inputs.redirectUri&client_secret=%24inputs.clientSecret&\
+      code=%24steps.browser-authorize.outputs.code&scope=%24inputs.scope"));
     assert_eq!(
       &json!({
         "client_id": "$inputs.clientId",
@@ -1330,7 +1452,7 @@ mod tests {
         "code": "$steps.browser-authorize.outputs.code",
         "scope": "$inputs.scope"
       }),
-      &p.0
+      &p.as_json().unwrap()
     );
   }
 
@@ -0,0 +1,549 @@
+//! Streaming loader for YAML documents. The `yaml` module loads the whole document into a `Yaml`
+//! tree before converting it, so the tree for every workflow is in memory at the same time as the
+//! models. The streaming loader reads the parser events directly, and converts each workflow as
+//! soon as it has been parsed, so only the tree of one workflow is held in memory at a time.
+//!
+//! As the events carry their location in the document, the loader can also record the span of
+//! every object and list in the document, keyed by its JSON Pointer (with
+//! [`yaml_stream_load_with_spans`] or [`yaml_stream_load_reader_with_spans`]). These can be used to
+//! point to the source of a problem (i.e. the path of a `ValidationIssue`). Recording the spans is
+//! opt-in, as the spans grow with the size of the document.
+//!
+//! The values of anchors are kept until the end of the document, as an alias can refer to any
+//! anchor before it. Documents with a large number of anchored values will use more memory.
+//!
+//! ```rust
+//! use arazzo_models::yaml_stream::yaml_stream_load_with_spans;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let loaded = yaml_stream_load_with_spans("arazzo: 1.0.1
+//! info:
+//!   title: Pets
+//!   version: 1.0.0
+//! sourceDescriptions:
+//!   - name: petstore
+//!     url: petstore.json
+//! workflows:
+//!   - workflowId: listPets
+//!     steps:
+//!       - stepId: list
+//!         operationId: listPets
+//! ")?;
+//! assert_eq!(loaded.description.workflows[0].workflow_id, "listPets");
+//!
+//! let span = loaded.spans.get("/workflows/0/steps/0").unwrap();
+//! assert_eq!(span.start.line, 11);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+
+use anyhow::{anyhow, Context};
+use yaml_rust2::parser::{Event, Parser, Tag};
+use yaml_rust2::scanner::{Marker, ScanError, TScalarStyle};
+use yaml_rust2::yaml::Hash;
+use yaml_rust2::Yaml;
+
+use crate::extensions::YAML_BINARY_KEY;
+use crate::v1_0::{ArazzoDescription, Workflow};
+use crate::yaml::{
+  yaml_empty_workflows_error,
+  yaml_is_binary_tag,
+  yaml_load_description,
+  yaml_load_workflows,
+  yaml_type_name
+};
+
+/// Position in a YAML document
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourcePosition {
+  /// Offset from the start of the document, in characters
+  pub index: usize,
+  /// Line number (starting from 1)
+  pub line: usize,
+  /// Column number (starting from 0)
+  pub column: usize
+}
+
+impl From<Marker> for SourcePosition {
+  fn from(marker: Marker) -> Self {
+    SourcePosition { index: marker.index(), line: marker.line(), column: marker.col() }
+  }
+}
+
+/// Span of a value in a YAML document. The end is the position of the next token after the
+/// value, which for block values is the start of the following line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SourceSpan {
+  /// Start of the value
+  pub start: SourcePosition,
+  /// End of the value
+  pub end: SourcePosition
+}
+
+/// Spans of the objects and lists in a YAML document, keyed by their JSON Pointer (i.e.
+/// `/workflows/0/steps/1`). The top-level object has an empty pointer.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceSpans {
+  spans: HashMap<String, SourceSpan>
+}
+
+impl SourceSpans {
+  /// Returns the span of the object or list at the JSON Pointer
+  pub fn get(&self, pointer: &str) -> Option<&SourceSpan> {
+    self.spans.get(pointer)
+  }
+
+  /// Returns the span of the object or list at the JSON Pointer, or the closest object or list
+  /// that contains it. This can be used for pointers to scalar values (i.e. the path of a
+  /// `ValidationIssue` like `/workflows/0/workflowId`), which do not have spans.
+  pub fn find(&self, pointer: &str) -> Option<(&str, &SourceSpan)> {
+    let mut pointer = pointer;
+    loop {
+      if let Some((key, span)) = self.spans.get_key_value(pointer) {
+        return Some((key.as_str(), span));
+      }
+      let (parent, _) = pointer.rsplit_once('/')?;
+      pointer = parent;
+    }
+  }
+
+  /// The number of spans
+  pub fn len(&self) -> usize {
+    self.spans.len()
+  }
+
+  /// If there are no spans
+  pub fn is_empty(&self) -> bool {
+    self.spans.is_empty()
+  }
+
+  /// Iterator over the JSON Pointers and spans, in no particular order
+  pub fn iter(&self) -> impl Iterator<Item = (&str, &SourceSpan)> {
+    self.spans.iter().map(|(pointer, span)| (pointer.as_str(), span))
+  }
+}
+
+/// Description loaded by the streaming loader, with the spans of the objects and lists in the
+/// document
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpannedDescription {
+  /// Loaded description
+  pub description: ArazzoDescription,
+  /// Spans of the objects and lists in the document
+  pub spans: SourceSpans
+}
+
+/// Loads the description from the first document in the YAML source, converting each workflow as
+/// it is parsed. The loaded description is the same as loading the document with
+/// `yaml_load_documents` and converting it with `ArazzoDescription::try_from`, and any errors are
+/// the same as well.
+pub fn yaml_stream_load(source: &str) -> anyhow::Result<ArazzoDescription> {
+  StreamLoader::new(Parser::new(source.chars()), false).load()
+    .map(|loaded| loaded.description)
+}
+
+/// Loads the description from the first document in the YAML source (see [`yaml_stream_load`]),
+/// recording the spans of the objects and lists in the document
+pub fn yaml_stream_load_with_spans(source: &str) -> anyhow::Result<SpannedDescription> {
+  StreamLoader::new(Parser::new(source.chars()), true).load()
+}
+
+/// Loads the description from the first document read from the reader, converting each workflow
+/// as it is parsed. The document is read a line at a time, so the source text is never held in
+/// memory.
+pub fn yaml_stream_load_reader<R: BufRead>(reader: R) -> anyhow::Result<ArazzoDescription> {
+  load_from_reader(reader, false).map(|loaded| loaded.description)
+}
+
+/// Loads the description from the first document read from the reader (see
+/// [`yaml_stream_load_reader`]), recording the spans of the objects and lists in the document
+pub fn yaml_stream_load_reader_with_spans<R: BufRead>(reader: R) -> anyhow::Result<SpannedDescription> {
+  load_from_reader(reader, true)
+}
+
+fn load_from_reader<R: BufRead>(reader: R, record_spans: bool) -> anyhow::Result<SpannedDescription> {
+  let mut error = None;
+  let result = {
+    let chars = ReaderChars { reader, line: String::new(), position: 0, error: &mut error };
+    StreamLoader::new(Parser::new(chars), record_spans).load()
+  };
+
+  match error {
+    Some(err) => Err(err).context("Failed to read the YAML document"),
+    None => result
+  }
+}
+
+/// Characters read from a reader a line at a time. As an iterator can not return errors, any error
+/// ends the characters and is stored to be returned after parsing.
+struct ReaderChars<'a, R> {
+  reader: R,
+  line: String,
+  position: usize,
+  error: &'a mut Option<io::Error>
+}
+
+impl<R: BufRead> Iterator for ReaderChars<'_, R> {
+  type Item = char;
+
+  fn next(&mut self) -> Option<char> {
+    loop {
+      if let Some(ch) = self.line[self.position..].chars().next() {
+        self.position += ch.len_utf8();
+        return Some(ch);
+      }
+
+      self.line.clear();
+      self.position = 0;
+      match self.reader.read_line(&mut self.line) {
+        Ok(0) => return None,
+        Ok(_) => {}
+        Err(err) => {
+          *self.error = Some(err);
+          return None;
+        }
+      }
+    }
+  }
+}
+
+/// Object or list that is being built from the events
+struct Frame {
+  node: Yaml,
+  anchor: usize,
+  /// Key of the next value, for a Hash
+  key: Option<Yaml>,
+  /// JSON Pointer to the node, if the span of the node is to be recorded
+  pointer: Option<String>,
+  start: Marker
+}
+
+impl Frame {
+  /// If no values have been added to this node
+  fn is_empty(&self) -> bool {
+    self.key.is_none() && match &self.node {
+      Yaml::Array(array) => array.is_empty(),
+      Yaml::Hash(hash) => hash.is_empty(),
+      _ => true
+    }
+  }
+
+  /// JSON Pointer of the next value added to this node. Keys of a Hash do not have a pointer.
+  fn child_pointer(&self) -> Option<String> {
+    let pointer = self.pointer.as_ref()?;
+    match (&self.node, &self.key) {
+      (Yaml::Array(array), _) => Some(format!("{}/{}", pointer, array.len())),
+      (Yaml::Hash(_), Some(key)) => key.as_str()
+        .map(|key| format!("{}/{}", pointer, escape_pointer(key))),
+      _ => None
+    }
+  }
+
+  /// Adds the value to this node. Values of a Hash are preceded by their key.
+  fn insert(&mut self, value: Yaml, mark: Marker) -> anyhow::Result<()> {
+    match &mut self.node {
+      Yaml::Array(array) => array.push(value),
+      Yaml::Hash(hash) => match self.key.take() {
+        Some(key) => insert_entry(hash, key, value, mark)?,
+        None => self.key = Some(value)
+      },
+      _ => unreachable!("Only Arrays and Hashes are built from frames")
+    }
+    Ok(())
+  }
+}
+
+/// Inserts the entry, returning the same error as the yaml-rust2 loader if the key is duplicated
+fn insert_entry(hash: &mut Hash, key: Yaml, value: Yaml, mark: Marker) -> anyhow::Result<()> {
+  if hash.contains_key(&key) {
+    Err(ScanError::new_string(mark, format!("{:?}: duplicated key in mapping", key)).into())
+  } else {
+    hash.insert(key, value);
+    Ok(())
+  }
+}
+
+/// Returns the earlier of the two positions. The parser marks the start of a block mapping at the
+/// first `:`, so the start of a mapping is moved back to its first key.
+fn earliest(a: Marker, b: Marker) -> Marker {
+  if b.index() < a.index() { b } else { a }
+}
+
+/// Escapes a key for use in a JSON Pointer
+fn escape_pointer(key: &str) -> String {
+  key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Loads a description from the parser events
+struct StreamLoader<T> {
+  parser: Parser<T>,
+  anchors: HashMap<usize, Yaml>,
+  /// Spans of the objects and lists, if they are being recorded
+  spans: Option<HashMap<String, SourceSpan>>
+}
+
+impl<T: Iterator<Item = char>> StreamLoader<T> {
+  fn new(parser: Parser<T>, record_spans: bool) -> Self {
+    StreamLoader { parser, anchors: HashMap::new(), spans: record_spans.then(HashMap::new) }
+  }
+
+  /// JSON Pointer to pass to `read_node`, if spans are being recorded
+  fn pointer<F: FnOnce() -> Option<String>>(&self, pointer: F) -> Option<String> {
+    self.spans.as_ref().and_then(|_| pointer())
+  }
+
+  fn load(mut self) -> anyhow::Result<SpannedDescription> {
+    loop {
+      match self.parser.peek()?.0 {
+        Event::StreamStart | Event::DocumentStart | Event::Nothing => { self.parser.next_token()?; }
+        Event::StreamEnd => return Err(anyhow!("The YAML source does not contain a document")),
+        _ => break
+      }
+    }
+
+    if !matches!(self.parser.peek()?.0, Event::MappingStart(..)) {
+      let (value, _) = self.read_node(None)?;
+      return Err(anyhow!("YAML value must be a Hash, got {}", yaml_type_name(&value)));
+    }
+
+    let (_, mut start) = self.parser.next_token()?;
+    start = earliest(start, self.parser.peek()?.1);
+    let mut hash = Hash::new();
+    let mut workflows = None;
+    let end = loop {
+      if let (Event::MappingEnd, _) = self.parser.peek()? {
+        break self.parser.next_token()?.1;
+      }
+
+      let (key, _) = self.read_node(None)?;
+      let (value, mark) = if key.as_str() == Some("workflows") && matches!(self.parser.peek()?.0, Event::SequenceStart(..)) {
+        let (loaded, mark) = self.read_workflows()?;
+        workflows = Some(loaded);
+        // Only used to detect duplicated keys, as the workflows have already been loaded
+        (Yaml::Null, mark)
+      } else {
+        let pointer = self.pointer(|| key.as_str().map(|key| format!("/{}", escape_pointer(key))));
+        self.read_node(pointer)?
+      };
+      insert_entry(&mut hash, key, value, mark)?;
+    };
+    self.record_span(String::new(), start, end);
+
+    let description = yaml_load_description(&hash, |hash| match workflows {
+      Some(workflows) => workflows,
+      None => yaml_load_workflows(hash)
+    })?;
+    Ok(SpannedDescription { description, spans: SourceSpans { spans: self.spans.unwrap_or_default() } })
+  }
+
+  /// Reads the workflows list, converting each workflow as soon as it has been read. Errors
+  /// converting the workflows are returned in the result, as the errors for the other top-level
+  /// fields need to be reported first. Errors parsing the document are returned straight away.
+  fn read_workflows(&mut self) -> anyhow::Result<(anyhow::Result<Vec<Workflow>>, Marker)> {
+    let (_, start) = self.parser.next_token()?;
+    let mut workflows = Ok(Vec::new());
+    let mut index = 0;
+    let end = loop {
+      if let (Event::SequenceEnd, _) = self.parser.peek()? {
+        break self.parser.next_token()?.1;
+      }
+
+      let (value, _) = self.read_node(self.pointer(|| Some(format!("/workflows/{}", index))))?;
+      if let Ok(list) = &mut workflows {
+        match Workflow::try_from(&value) {
+          Ok(workflow) => list.push(workflow),
+          Err(err) => workflows = Err(err)
+        }
+      }
+      index += 1;
+    };
+    self.record_span("/workflows".to_string(), start, end);
+
+    let workflows = workflows.and_then(|list| if list.is_empty() { Err(yaml_empty_workflows_error()) } else { Ok(list) });
+    Ok((workflows, end))
+  }
+
+  /// Reads the next node from the events, recording the spans of it (and any objects and lists in
+  /// it) if it has a JSON Pointer. Returns the node with the position of its last event.
+  fn read_node(&mut self, pointer: Option<String>) -> anyhow::Result<(Yaml, Marker)> {
+    let mut stack: Vec<Frame> = vec![];
+    loop {
+      let (event, mark) = self.parser.next_token()?;
+      if let Some(frame) = stack.last_mut() && frame.is_empty() {
+        frame.start = earliest(frame.start, mark);
+      }
+      let (node, anchor) = match event {
+        Event::SequenceStart(anchor, _) | Event::MappingStart(anchor, _) => {
+          let node = if let Event::SequenceStart(..) = event { Yaml::Array(vec![]) } else { Yaml::Hash(Hash::new()) };
+          let pointer = match stack.last() {
+            Some(parent) => parent.child_pointer(),
+            None => pointer.clone()
+          };
+          stack.push(Frame { node, anchor, key: None, pointer, start: mark });
+          continue;
+        }
+        Event::SequenceEnd | Event::MappingEnd => {
+          let frame = stack.pop().ok_or_else(|| anyhow!("Unexpected end of a YAML collection"))?;
+          if let Some(pointer) = frame.pointer {
+            self.record_span(pointer, frame.start, mark);
+          }
+          (frame.node, frame.anchor)
+        }
+        Event::Scalar(value, style, anchor, tag) => (scalar_value(value, style, tag), anchor),
+        Event::Alias(id) => (self.anchors.get(&id).cloned().unwrap_or(Yaml::BadValue), 0),
+        event => return Err(anyhow!("Unexpected YAML event {:?}", event))
+      };
+
+      // Valid anchor IDs start from 1
+      if anchor > 0 {
+        self.anchors.insert(anchor, node.clone());
+      }
+      match stack.last_mut() {
+        Some(parent) => parent.insert(node, mark)?,
+        None => return Ok((node, mark))
+      }
+    }
+  }
+
+  fn record_span(&mut self, pointer: String, start: Marker, end: Marker) {
+    if let Some(spans) = &mut self.spans {
+      spans.insert(pointer, SourceSpan { start: start.into(), end: end.into() });
+    }
+  }
+}
+
+/// Resolves the value of a scalar, in the same way as the yaml-rust2 loader. Values tagged with
+/// `!!binary` are loaded as a Hash with a [`YAML_BINARY_KEY`] key (see `yaml_load_documents`).
+fn scalar_value(value: String, style: TScalarStyle, tag: Option<Tag>) -> Yaml {
+  if yaml_is_binary_tag(&tag) {
+    let mut hash = Hash::new();
+    hash.insert(Yaml::String(YAML_BINARY_KEY.to_string()), Yaml::String(value));
+    Yaml::Hash(hash)
+  } else if style != TScalarStyle::Plain {
+    Yaml::String(value)
+  } else if let Some(Tag { handle, suffix }) = tag {
+    if handle == "tag:yaml.org,2002:" {
+      match suffix.as_str() {
+        "bool" => match value.as_str() {
+          "true" | "True" | "TRUE" => Yaml::Boolean(true),
+          "false" | "False" | "FALSE" => Yaml::Boolean(false),
+          _ => Yaml::BadValue
+        },
+        "int" => value.parse::<i64>().map(Yaml::Integer).unwrap_or(Yaml::BadValue),
+        "float" => match Yaml::from_str(&value) {
+          Yaml::Real(_) | Yaml::Integer(_) => Yaml::Real(value),
+          _ => Yaml::BadValue
+        },
+        "null" => match value.as_str() {
+          "~" | "null" => Yaml::Null,
+          _ => Yaml::BadValue
+        },
+        _ => Yaml::String(value)
+      }
+    } else {
+      Yaml::String(value)
+    }
+  } else {
+    Yaml::from_str(&value)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::BufReader;
+
+  use expectest::prelude::*;
+  use yaml_rust2::YamlLoader;
+
+  use crate::v1_0::ArazzoDescription;
+  use crate::yaml::yaml_load_documents;
+  use crate::yaml_stream::{
+    yaml_stream_load,
+    yaml_stream_load_reader,
+    yaml_stream_load_reader_with_spans,
+    yaml_stream_load_with_spans,
+    SourcePosition
+  };
+
+  const YAML: &str = "arazzo: 1.0.1
+info:
+  title: Pets
+  version: 1.0.0
+sourceDescriptions:
+  - name: petstore
+    url: petstore.json
+workflows:
+  - workflowId: listPets
+    parameters: &paging
+      - name: page
+        in: query
+        value: 1
+    steps:
+      - stepId: list
+        operationId: listPets
+        parameters: *paging
+      - stepId: image
+        operationId: getImage
+        x-checksum: !!binary aGVsbG8=
+  - workflowId: findPet
+    steps:
+      - stepId: find
+        operationId: findPet
+x-owner: pets
+";
+
+  #[test]
+  fn loads_the_same_description_as_the_yaml_loader() {
+    let expected = ArazzoDescription::try_from(&yaml_load_documents(YAML).unwrap()[0]).unwrap();
+    let loaded = yaml_stream_load(YAML).unwrap();
+    expect!(loaded.clone()).to(be_equal_to(expected));
+
+    let from_reader = yaml_stream_load_reader(BufReader::new(YAML.as_bytes())).unwrap();
+    expect!(from_reader).to(be_equal_to(loaded.clone()));
+
+    let with_spans = yaml_stream_load_with_spans(YAML).unwrap();
+    expect!(with_spans.description.clone()).to(be_equal_to(loaded));
+    let from_reader = yaml_stream_load_reader_with_spans(BufReader::new(YAML.as_bytes())).unwrap();
+    expect!(from_reader).to(be_equal_to(with_spans));
+  }
+
+  #[test]
+  fn records_the_spans_of_objects_and_lists() {
+    let spans = yaml_stream_load_with_spans(YAML).unwrap().spans;
+
+    let step = spans.get("/workflows/0/steps/1").unwrap();
+    expect!(step.start).to(be_equal_to(SourcePosition { index: 325, line: 18, column: 8 }));
+    expect!(step.end.line).to(be_equal_to(21));
+    expect!(spans.get("/workflows/1").unwrap().start.line).to(be_equal_to(21));
+    expect!(spans.get("/info").unwrap().start.line).to(be_equal_to(3));
+    expect!(spans.get("/workflows/0/steps/0/stepId")).to(be_none());
+
+    let (pointer, span) = spans.find("/workflows/0/steps/0/stepId").unwrap();
+    expect!(pointer).to(be_equal_to("/workflows/0/steps/0"));
+    expect!(span.start.line).to(be_equal_to(15));
+    expect!(spans.find("/arazzo").unwrap().0).to(be_equal_to(""));
+  }
+
+  #[test]
+  fn returns_the_same_errors_as_the_yaml_loader() {
+    for source in [
+      "- arazzo: 1.0.1\n",
+      "info:\n  title: Pets\n",
+      "arazzo: 1.0.1\nworkflows:\n  - workflowId: listPets\n",
+      "arazzo: 1.0.1\ninfo:\n  title: Pets\n  version: 1.0.0\nsourceDescriptions:\n  - name: pets\n    url: pets.json\nworkflows: []\n",
+      "arazzo: 1.0.1\ninfo:\n  title: Pets\n  version: 1.0.0\nsourceDescriptions:\n  - name: pets\n    url: pets.json\nworkflows:\n  - summary: No ID\n",
+      "arazzo: 1.0.1\ninfo:\n  title: Pets\n  version: 1.0.0\nsourceDescriptions:\n  - name: pets\n    url: pets.json\nworkflows: {}\n"
+    ] {
+      let expected = ArazzoDescription::try_from(&yaml_load_documents(source).unwrap()[0]).unwrap_err();
+      expect!(yaml_stream_load(source).unwrap_err().to_string()).to(be_equal_to(expected.to_string()));
+    }
+
+    expect!(yaml_stream_load("")).to(be_err());
+    let duplicated = "arazzo: 1.0.1\ninfo:\n  title: Pets\n  title: Dogs\narazzo: 1.0.2\n";
+    expect!(yaml_stream_load(duplicated).unwrap_err().to_string())
+      .to(be_equal_to(YamlLoader::load_from_str(duplicated).unwrap_err().to_string()));
+  }
+}
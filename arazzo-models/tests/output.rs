@@ -1,7 +1,9 @@
+use expectest::prelude::*;
 use maplit::btreemap;
 use trim_margin::MarginTrimmable;
 use pretty_assertions::assert_eq;
 use serde_json::json;
+use yaml_rust2::YamlLoader;
 use arazzo_models::either::Either;
 use arazzo_models::v1_0::{ArazzoDescription, Criterion, Info, ParameterObject, SourceDescription, Step, Workflow};
 
@@ -270,3 +272,32 @@ fn model_to_json_test() {
        |  ]
        |}"#.trim_margin().as_ref().unwrap());
 }
+
+#[test]
+fn extension_keys_round_trip() {
+  let json = json!({
+    "arazzo": "1.0.1",
+    "info": {
+      "title": "Extensions",
+      "version": "1.0.0",
+      "x-one": 1
+    },
+    "sourceDescriptions": [
+      { "name": "api", "type": "openapi", "url": "openapi.yaml", "x-x-two": "two" }
+    ],
+    "workflows": [
+      { "workflowId": "w", "steps": [ { "stepId": "s", "operationId": "op", "x-step": [1, 2] } ] }
+    ],
+    "x-three": { "x-nested": true },
+    "x-": "empty"
+  });
+  let description = ArazzoDescription::try_from(&json).unwrap();
+  expect!(description.info.extensions.contains_key("one")).to(be_true());
+  expect!(description.source_descriptions[0].extensions.contains_key("x-two")).to(be_true());
+
+  expect!(serde_json::to_value(&description).unwrap()).to(be_equal_to(json.clone()));
+
+  let yaml = YamlLoader::load_from_str(serde_yaml::to_string(&description).unwrap().as_str()).unwrap();
+  let description2 = ArazzoDescription::try_from(&yaml[0]).unwrap();
+  expect!(serde_json::to_value(&description2).unwrap()).to(be_equal_to(json));
+}